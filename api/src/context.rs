@@ -20,8 +20,9 @@ use aptos_logger::error;
 use aptos_mempool::{MempoolClientRequest, MempoolClientSender, SubmissionStatus};
 use aptos_state_view::TStateView;
 use aptos_storage_interface::{
+    cached_state_view::CachedDbStateView,
     state_view::{DbStateView, DbStateViewAtVersion, LatestDbStateCheckpointView},
-    DbReader, Order, MAX_REQUEST_LIMIT,
+    ArchivedTransaction, DbReader, Order, MAX_REQUEST_LIMIT,
 };
 use aptos_types::{
     access_path::{AccessPath, Path},
@@ -45,7 +46,7 @@ use futures::{channel::oneshot, SinkExt};
 use itertools::Itertools;
 use move_core_types::language_storage::{ModuleId, StructTag};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     sync::{Arc, RwLock},
 };
 
@@ -165,6 +166,20 @@ impl Context {
     }
 
     pub fn get_latest_ledger_info<E: ServiceUnavailableError>(&self) -> Result<LedgerInfo, E> {
+        let ledger_info = self
+            .get_latest_ledger_info_with_signatures()
+            .context("Failed to retrieve latest ledger info")
+            .map_err(|e| {
+                E::service_unavailable_with_code_no_info(e, AptosErrorCode::InternalError)
+            })?;
+        self.get_ledger_info(&ledger_info)
+    }
+
+    /// Builds the [`LedgerInfo`] for the given signed ledger info
+    pub fn get_ledger_info<E: ServiceUnavailableError>(
+        &self,
+        ledger_info: &LedgerInfoWithSignatures,
+    ) -> Result<LedgerInfo, E> {
         let maybe_oldest_version = self
             .db
             .get_first_viable_txn_version()
@@ -172,12 +187,6 @@ impl Context {
             .map_err(|e| {
                 E::service_unavailable_with_code_no_info(e, AptosErrorCode::InternalError)
             })?;
-        let ledger_info = self
-            .get_latest_ledger_info_with_signatures()
-            .context("Failed to retrieve latest ledger info")
-            .map_err(|e| {
-                E::service_unavailable_with_code_no_info(e, AptosErrorCode::InternalError)
-            })?;
         let (oldest_version, oldest_block_event) = self
             .db
             .get_next_block_event(maybe_oldest_version)
@@ -195,7 +204,7 @@ impl Context {
 
         Ok(LedgerInfo::new(
             &self.chain_id(),
-            &ledger_info,
+            ledger_info,
             oldest_version,
             oldest_block_event.height(),
             newest_block_event.height(),
@@ -315,7 +324,19 @@ impl Context {
             .collect::<Result<Vec<(StructTag, Vec<u8>)>>>()?;
 
         // We should be able to do an unwrap here, otherwise the above db read would fail.
-        let resolver = self.state_view_at_version(version)?.into_move_resolver();
+        let state_view = CachedDbStateView::from(self.state_view_at_version(version)?);
+        // Telling resource groups apart needs the modules defining the resources, read them in
+        // one batch.
+        state_view.prime_cache(
+            &kvs.iter()
+                .map(|(struct_tag, _)| {
+                    StateKey::AccessPath(AccessPath::code_access_path(struct_tag.module_id()))
+                })
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>(),
+        )?;
+        let resolver = state_view.into_move_resolver();
 
         // Extract resources from resource groups and flatten into all resources
         let kvs = kvs
@@ -737,6 +758,30 @@ impl Context {
         self.db.get_accumulator_root_hash(version)
     }
 
+    /// Reads a transaction that has been pruned from the DB from the ledger archive, if any.
+    pub fn get_archived_transaction(&self, version: u64) -> Result<Option<ArchivedTransaction>> {
+        self.db.get_archived_transaction(version)
+    }
+
+    /// Finds a transaction that has been pruned from the DB in the ledger archive, if any.
+    pub fn get_archived_transaction_by_hash(
+        &self,
+        hash: HashValue,
+    ) -> Result<Option<ArchivedTransaction>> {
+        self.db.get_archived_transaction_by_hash(hash)
+    }
+
+    /// Reads events on `event_key` that have been pruned from the DB from the ledger archive,
+    /// starting at sequence number `start`, if any.
+    pub fn get_archived_events(
+        &self,
+        event_key: &EventKey,
+        start: u64,
+        limit: u16,
+    ) -> Result<Vec<EventWithVersion>> {
+        self.db.get_archived_events(event_key, start, limit as u64)
+    }
+
     fn convert_into_transaction_on_chain_data(
         &self,
         txn: TransactionWithProof,
@@ -8,8 +8,8 @@ use crate::{
     failpoint::fail_point_poem,
     page::Page,
     response::{
-        BadRequestError, BasicErrorWith404, BasicResponse, BasicResponseStatus, BasicResultWith404,
-        InternalError,
+        version_pruned, BadRequestError, BasicErrorWith404, BasicResponse, BasicResponseStatus,
+        BasicResultWith404, InternalError,
     },
     ApiTags,
 };
@@ -18,7 +18,8 @@ use aptos_api_types::{
     verify_field_identifier, Address, AptosErrorCode, AsConverter, IdentifierWrapper, LedgerInfo,
     MoveStructTag, VerifyInputWithRecursion, VersionedEvent, U64,
 };
-use aptos_types::event::EventKey;
+use aptos_storage_interface::Error as StorageError;
+use aptos_types::{contract_event::EventWithVersion, event::EventKey};
 use poem_openapi::{
     param::{Path, Query},
     OpenApi,
@@ -151,22 +152,39 @@ impl EventsApi {
         event_key: EventKey,
     ) -> BasicResultWith404<Vec<VersionedEvent>> {
         let ledger_version = latest_ledger_info.version();
-        let events = self
-            .context
-            .get_events(
-                &event_key,
-                page.start_option(),
-                page.limit(&latest_ledger_info)?,
-                ledger_version,
-            )
-            .context(format!("Failed to find events by key {}", event_key))
-            .map_err(|err| {
-                BasicErrorWith404::internal_with_code(
-                    err,
-                    AptosErrorCode::InternalError,
-                    &latest_ledger_info,
-                )
-            })?;
+        let limit = page.limit(&latest_ledger_info)?;
+        let events =
+            match self
+                .context
+                .get_events(&event_key, page.start_option(), limit, ledger_version)
+            {
+                // The DB doesn't know about the events on a key if all of them are pruned.
+                Ok(events)
+                    if events.is_empty()
+                        && page.start_option().is_some()
+                        && latest_ledger_info.oldest_ledger_version.0 > 0 =>
+                {
+                    self.get_archived_events(
+                        &event_key,
+                        page.start_option(),
+                        limit,
+                        &latest_ledger_info,
+                    )?
+                    .unwrap_or_default()
+                },
+                Ok(events) => events,
+                Err(err) => match err.downcast_ref::<StorageError>() {
+                    Some(StorageError::Pruned { version, .. }) => self
+                        .get_archived_events(
+                            &event_key,
+                            page.start_option(),
+                            limit,
+                            &latest_ledger_info,
+                        )?
+                        .ok_or_else(|| version_pruned(*version, &latest_ledger_info))?,
+                    _ => return Err(Self::events_error(err, &event_key, &latest_ledger_info)),
+                },
+            };
 
         match accept_type {
             AcceptType::Json => {
@@ -190,4 +208,78 @@ impl EventsApi {
             },
         }
     }
+
+    /// Reads the events from `start` that are pruned from the DB from the ledger archive, and the
+    /// following ones from the DB if the archive doesn't have enough. Without `start`, it's the
+    /// latest `limit` events. Returns `None` if the archive doesn't have the first one either.
+    fn get_archived_events(
+        &self,
+        event_key: &EventKey,
+        start: Option<u64>,
+        limit: u16,
+        latest_ledger_info: &LedgerInfo,
+    ) -> Result<Option<Vec<EventWithVersion>>, BasicErrorWith404> {
+        let ledger_version = latest_ledger_info.version();
+        let start = match start {
+            Some(start) => start,
+            // The DB only reports events pruned if later ones on the key are left.
+            None => match self
+                .context
+                .get_events(event_key, None, 1, ledger_version)
+                .map_err(|err| Self::events_error(err, event_key, latest_ledger_info))?
+                .first()
+            {
+                Some(latest) => (latest.event.sequence_number() + 1).saturating_sub(limit as u64),
+                None => return Ok(None),
+            },
+        };
+
+        let mut events = self
+            .context
+            .get_archived_events(event_key, start, limit)
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err.context(format!(
+                        "Failed to get archived events by key {}",
+                        event_key
+                    )),
+                    AptosErrorCode::InternalError,
+                    latest_ledger_info,
+                )
+            })?;
+        if events.is_empty() {
+            return Ok(None);
+        }
+        let num_archived = events.len() as u16;
+        if num_archived < limit {
+            match self.context.get_events(
+                event_key,
+                Some(start + num_archived as u64),
+                limit - num_archived,
+                ledger_version,
+            ) {
+                Ok(rest) => events.extend(rest),
+                // The archive wasn't enabled when the following events were pruned.
+                Err(err)
+                    if matches!(
+                        err.downcast_ref::<StorageError>(),
+                        Some(StorageError::Pruned { .. })
+                    ) => {},
+                Err(err) => return Err(Self::events_error(err, event_key, latest_ledger_info)),
+            }
+        }
+        Ok(Some(events))
+    }
+
+    fn events_error(
+        err: anyhow::Error,
+        event_key: &EventKey,
+        latest_ledger_info: &LedgerInfo,
+    ) -> BasicErrorWith404 {
+        BasicErrorWith404::internal_with_code(
+            err.context(format!("Failed to find events by key {}", event_key)),
+            AptosErrorCode::InternalError,
+            latest_ledger_info,
+        )
+    }
 }
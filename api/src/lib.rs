@@ -17,6 +17,7 @@ mod index;
 mod log;
 pub mod metrics;
 mod page;
+mod proofs;
 mod response;
 mod runtime;
 mod set_failpoints;
@@ -0,0 +1,469 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    accept_type::AcceptType,
+    context::Context,
+    failpoint::fail_point_poem,
+    response::{
+        api_disabled, transaction_not_found_by_hash, version_not_found, version_pruned,
+        BadRequestError, BasicErrorWith404, BasicResponse, BasicResponseStatus, BasicResultWith404,
+        InternalError, ServiceUnavailableError,
+    },
+    ApiTags,
+};
+use anyhow::Context as AnyhowContext;
+use aptos_api_types::{
+    verify_module_identifier, Address, AptosErrorCode, BcsStateValueWithProof,
+    BcsTransactionWithProof, HashValue, HexEncodedBytes, IdentifierWrapper, LedgerInfo,
+    MoveStructTag, VerifyInputWithRecursion, U64,
+};
+use aptos_types::{
+    access_path::AccessPath,
+    ledger_info::LedgerInfoWithSignatures,
+    state_proof::StateProof,
+    state_store::state_key::StateKey,
+    transaction::{TransactionWithProof, Version},
+};
+use move_core_types::language_storage::{ModuleId, StructTag};
+use poem_openapi::{
+    param::{Path, Query},
+    OpenApi,
+};
+use std::{convert::TryInto, sync::Arc};
+
+/// API for retrieving state and transactions along with their proofs
+///
+/// All values are proven against the latest signed ledger info of the node, and every
+/// response carries a [`StateProof`] of that ledger info relative to the version already
+/// trusted by the client. Proofs are only available in BCS.
+pub struct ProofsApi {
+    pub context: Arc<Context>,
+}
+
+#[OpenApi]
+impl ProofsApi {
+    /// Get state proof
+    ///
+    /// Retrieves the latest signed ledger info along with the epoch change proofs since
+    /// `known_version`. Light clients use this to ratchet their trusted state forward.
+    ///
+    /// Only available in BCS.
+    #[oai(
+        path = "/state_proof",
+        method = "get",
+        operation_id = "get_state_proof",
+        tag = "ApiTags::General"
+    )]
+    async fn get_state_proof(
+        &self,
+        accept_type: AcceptType,
+        /// Ledger version already trusted by the client
+        ///
+        /// If not provided, no epoch change proofs are returned
+        known_version: Query<Option<U64>>,
+    ) -> BasicResultWith404<HexEncodedBytes> {
+        fail_point_poem("endpoint_get_state_proof")?;
+        self.context
+            .check_api_output_enabled("Get state proof", &accept_type)?;
+        let (ledger_info, ledger_info_with_sigs) = self.latest_ledger_info()?;
+        let state_proof = self.state_proof(
+            known_version.0.map(|inner| inner.0),
+            ledger_info_with_sigs,
+            &ledger_info,
+        )?;
+
+        match accept_type {
+            AcceptType::Json => Err(api_disabled("Get state proof by json")),
+            AcceptType::Bcs => {
+                BasicResponse::try_from_bcs((state_proof, &ledger_info, BasicResponseStatus::Ok))
+            },
+        }
+    }
+
+    /// Get account resource with proof
+    ///
+    /// Retrieves an individual resource from a given account at a specific ledger version,
+    /// along with the proofs to verify it against the latest signed ledger info. If the
+    /// resource doesn't exist, a proof of its absence is returned.
+    ///
+    /// The ledger version must be a state checkpoint (the last version of a block). If not
+    /// provided, the version of the latest signed ledger info is used.
+    ///
+    /// Only available in BCS.
+    #[oai(
+        path = "/accounts/:address/resource/:resource_type/proof",
+        method = "get",
+        operation_id = "get_account_resource_with_proof",
+        tag = "ApiTags::Accounts"
+    )]
+    async fn get_account_resource_with_proof(
+        &self,
+        accept_type: AcceptType,
+        /// Address of account with or without a `0x` prefix
+        address: Path<Address>,
+        /// Name of struct to retrieve e.g. `0x1::account::Account`
+        resource_type: Path<MoveStructTag>,
+        /// Ledger version to get state of account
+        ///
+        /// If not provided, it will be the version of the latest signed ledger info
+        ledger_version: Query<Option<U64>>,
+        /// Ledger version already trusted by the client
+        ///
+        /// If not provided, no epoch change proofs are returned
+        known_version: Query<Option<U64>>,
+    ) -> BasicResultWith404<HexEncodedBytes> {
+        resource_type
+            .0
+            .verify(0)
+            .context("'resource_type' invalid")
+            .map_err(|err| {
+                BasicErrorWith404::bad_request_with_code_no_info(err, AptosErrorCode::InvalidInput)
+            })?;
+        fail_point_poem("endpoint_get_account_resource_with_proof")?;
+        self.context
+            .check_api_output_enabled("Get account resource with proof", &accept_type)?;
+        let resource_type: StructTag = resource_type
+            .0
+            .try_into()
+            .context("Failed to parse given resource type")
+            .map_err(|err| {
+                BasicErrorWith404::bad_request_with_code_no_info(err, AptosErrorCode::InvalidInput)
+            })?;
+        let state_key = StateKey::AccessPath(AccessPath::resource_access_path(
+            address.0.into(),
+            resource_type,
+        ));
+        self.state_value_with_proof(
+            &accept_type,
+            state_key,
+            ledger_version.0.map(|inner| inner.0),
+            known_version.0.map(|inner| inner.0),
+        )
+    }
+
+    /// Get account module with proof
+    ///
+    /// Retrieves an individual module from a given account at a specific ledger version,
+    /// along with the proofs to verify it against the latest signed ledger info. If the
+    /// module doesn't exist, a proof of its absence is returned.
+    ///
+    /// The ledger version must be a state checkpoint (the last version of a block). If not
+    /// provided, the version of the latest signed ledger info is used.
+    ///
+    /// Only available in BCS.
+    #[oai(
+        path = "/accounts/:address/module/:module_name/proof",
+        method = "get",
+        operation_id = "get_account_module_with_proof",
+        tag = "ApiTags::Accounts"
+    )]
+    async fn get_account_module_with_proof(
+        &self,
+        accept_type: AcceptType,
+        /// Address of account with or without a `0x` prefix
+        address: Path<Address>,
+        /// Name of module to retrieve e.g. `coin`
+        module_name: Path<IdentifierWrapper>,
+        /// Ledger version to get state of account
+        ///
+        /// If not provided, it will be the version of the latest signed ledger info
+        ledger_version: Query<Option<U64>>,
+        /// Ledger version already trusted by the client
+        ///
+        /// If not provided, no epoch change proofs are returned
+        known_version: Query<Option<U64>>,
+    ) -> BasicResultWith404<HexEncodedBytes> {
+        verify_module_identifier(module_name.0.as_str())
+            .context("'module_name' invalid")
+            .map_err(|err| {
+                BasicErrorWith404::bad_request_with_code_no_info(err, AptosErrorCode::InvalidInput)
+            })?;
+        fail_point_poem("endpoint_get_account_module_with_proof")?;
+        self.context
+            .check_api_output_enabled("Get account module with proof", &accept_type)?;
+        let module_id = ModuleId::new(address.0.into(), module_name.0.into());
+        let state_key = StateKey::AccessPath(AccessPath::code_access_path(module_id));
+        self.state_value_with_proof(
+            &accept_type,
+            state_key,
+            ledger_version.0.map(|inner| inner.0),
+            known_version.0.map(|inner| inner.0),
+        )
+    }
+
+    /// Get transaction by version with proof
+    ///
+    /// Retrieves a committed transaction and its events by version, along with the proofs
+    /// to verify them against the latest signed ledger info. If the version has been pruned,
+    /// a 410 will be returned.
+    ///
+    /// Only available in BCS.
+    #[oai(
+        path = "/transactions/by_version/:txn_version/proof",
+        method = "get",
+        operation_id = "get_transaction_by_version_with_proof",
+        tag = "ApiTags::Transactions"
+    )]
+    async fn get_transaction_by_version_with_proof(
+        &self,
+        accept_type: AcceptType,
+        /// Version of transaction to retrieve
+        txn_version: Path<U64>,
+        /// Ledger version already trusted by the client
+        ///
+        /// If not provided, no epoch change proofs are returned
+        known_version: Query<Option<U64>>,
+    ) -> BasicResultWith404<HexEncodedBytes> {
+        fail_point_poem("endpoint_transaction_by_version_with_proof")?;
+        self.context
+            .check_api_output_enabled("Get transaction by version with proof", &accept_type)?;
+        let (ledger_info, ledger_info_with_sigs) = self.latest_ledger_info()?;
+        let version = txn_version.0 .0;
+        self.check_version(version, &ledger_info)?;
+        let transaction_with_proof = self
+            .context
+            .db
+            .get_transaction_by_version(version, ledger_info.version(), true)
+            .context(format!("Failed to get transaction by version {}", version))
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?;
+
+        self.transaction_with_proof(
+            &accept_type,
+            transaction_with_proof,
+            known_version.0.map(|inner| inner.0),
+            ledger_info_with_sigs,
+            &ledger_info,
+        )
+    }
+
+    /// Get transaction by hash with proof
+    ///
+    /// Retrieves a committed transaction and its events by hash, along with the proofs
+    /// to verify them against the latest signed ledger info. Pending transactions are
+    /// not returned, as they can't be proven.
+    ///
+    /// Only available in BCS.
+    #[oai(
+        path = "/transactions/by_hash/:txn_hash/proof",
+        method = "get",
+        operation_id = "get_transaction_by_hash_with_proof",
+        tag = "ApiTags::Transactions"
+    )]
+    async fn get_transaction_by_hash_with_proof(
+        &self,
+        accept_type: AcceptType,
+        /// Hash of transaction to retrieve
+        txn_hash: Path<HashValue>,
+        /// Ledger version already trusted by the client
+        ///
+        /// If not provided, no epoch change proofs are returned
+        known_version: Query<Option<U64>>,
+    ) -> BasicResultWith404<HexEncodedBytes> {
+        fail_point_poem("endpoint_transaction_by_hash_with_proof")?;
+        self.context
+            .check_api_output_enabled("Get transaction by hash with proof", &accept_type)?;
+        let (ledger_info, ledger_info_with_sigs) = self.latest_ledger_info()?;
+        let hash = txn_hash.0;
+        let transaction_with_proof = self
+            .context
+            .db
+            .get_transaction_by_hash(hash.into(), ledger_info.version(), true)
+            .context(format!("Failed to get transaction by hash {}", hash))
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?
+            .ok_or_else(|| transaction_not_found_by_hash(hash, &ledger_info))?;
+
+        self.transaction_with_proof(
+            &accept_type,
+            transaction_with_proof,
+            known_version.0.map(|inner| inner.0),
+            ledger_info_with_sigs,
+            &ledger_info,
+        )
+    }
+}
+
+impl ProofsApi {
+    /// Retrieves the latest signed ledger info, which all proofs are relative to
+    ///
+    /// The returned [`LedgerInfo`] is built from the same signed ledger info, so the version
+    /// in the response headers matches the proofs.
+    fn latest_ledger_info(
+        &self,
+    ) -> Result<(LedgerInfo, LedgerInfoWithSignatures), BasicErrorWith404> {
+        let ledger_info_with_sigs = self
+            .context
+            .get_latest_ledger_info_with_signatures()
+            .context("Failed to retrieve latest ledger info")
+            .map_err(|err| {
+                BasicErrorWith404::service_unavailable_with_code_no_info(
+                    err,
+                    AptosErrorCode::InternalError,
+                )
+            })?;
+        let ledger_info = self.context.get_ledger_info(&ledger_info_with_sigs)?;
+        Ok((ledger_info, ledger_info_with_sigs))
+    }
+
+    /// Ensures the version is neither in the future nor pruned
+    fn check_version(
+        &self,
+        version: Version,
+        ledger_info: &LedgerInfo,
+    ) -> Result<(), BasicErrorWith404> {
+        if version > ledger_info.version() {
+            Err(version_not_found(version, ledger_info))
+        } else if version < ledger_info.oldest_ledger_version.0 {
+            Err(version_pruned(version, ledger_info))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Builds the proof of the signed ledger info relative to the client's known version
+    fn state_proof(
+        &self,
+        known_version: Option<Version>,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+        ledger_info: &LedgerInfo,
+    ) -> Result<StateProof, BasicErrorWith404> {
+        let known_version = known_version.unwrap_or_else(|| ledger_info.version());
+        if known_version > ledger_info.version() {
+            return Err(BasicErrorWith404::bad_request_with_code(
+                format!(
+                    "Known version {} is newer than the latest ledger version {}",
+                    known_version,
+                    ledger_info.version()
+                ),
+                AptosErrorCode::InvalidInput,
+                ledger_info,
+            ));
+        }
+        self.context
+            .db
+            .get_state_proof_with_ledger_info(known_version, ledger_info_with_sigs)
+            .context("Failed to build state proof")
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    ledger_info,
+                )
+            })
+    }
+
+    /// Reads a state value and proves it against the latest signed ledger info
+    fn state_value_with_proof(
+        &self,
+        accept_type: &AcceptType,
+        state_key: StateKey,
+        ledger_version: Option<Version>,
+        known_version: Option<Version>,
+    ) -> BasicResultWith404<HexEncodedBytes> {
+        let (ledger_info, ledger_info_with_sigs) = self.latest_ledger_info()?;
+        let version = ledger_version.unwrap_or_else(|| ledger_info.version());
+        self.check_version(version, &ledger_info)?;
+
+        let transaction_info_with_proof = self
+            .context
+            .db
+            .get_transaction_by_version(version, ledger_info.version(), false)
+            .context(format!(
+                "Failed to get transaction info at version {}",
+                version
+            ))
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?
+            .proof;
+        // State is only authenticated at the state checkpoints
+        if transaction_info_with_proof
+            .transaction_info()
+            .state_checkpoint_hash()
+            .is_none()
+        {
+            return Err(BasicErrorWith404::bad_request_with_code(
+                format!(
+                    "Ledger version {} is not a state checkpoint, use the last version of a block",
+                    version
+                ),
+                AptosErrorCode::InvalidInput,
+                &ledger_info,
+            ));
+        }
+
+        let (state_value, state_value_proof) = self
+            .context
+            .db
+            .get_state_value_with_proof_by_version(&state_key, version)
+            .context(format!(
+                "Failed to query DB for {:?} at version {}",
+                state_key, version
+            ))
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?;
+        let state_proof = self.state_proof(known_version, ledger_info_with_sigs, &ledger_info)?;
+
+        let value_with_proof = BcsStateValueWithProof {
+            state_proof,
+            version,
+            state_value,
+            transaction_info_with_proof,
+            state_value_proof,
+        };
+        match accept_type {
+            AcceptType::Json => Err(api_disabled("Get state value with proof by json")),
+            AcceptType::Bcs => BasicResponse::try_from_bcs((
+                value_with_proof,
+                &ledger_info,
+                BasicResponseStatus::Ok,
+            )),
+        }
+    }
+
+    /// Renders a committed transaction along with its proofs
+    fn transaction_with_proof(
+        &self,
+        accept_type: &AcceptType,
+        transaction_with_proof: TransactionWithProof,
+        known_version: Option<Version>,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+        ledger_info: &LedgerInfo,
+    ) -> BasicResultWith404<HexEncodedBytes> {
+        let state_proof = self.state_proof(known_version, ledger_info_with_sigs, ledger_info)?;
+
+        let transaction_with_proof = BcsTransactionWithProof {
+            state_proof,
+            transaction_with_proof,
+        };
+        match accept_type {
+            AcceptType::Json => Err(api_disabled("Get transaction with proof by json")),
+            AcceptType::Bcs => BasicResponse::try_from_bcs((
+                transaction_with_proof,
+                ledger_info,
+                BasicResponseStatus::Ok,
+            )),
+        }
+    }
+}
@@ -4,8 +4,8 @@
 use crate::{
     accounts::AccountsApi, basic::BasicApi, blocks::BlocksApi, check_size::PostSizeLimit,
     context::Context, error_converter::convert_error, events::EventsApi, index::IndexApi,
-    log::middleware_log, set_failpoints, state::StateApi, transactions::TransactionsApi,
-    view_function::ViewFunctionApi,
+    log::middleware_log, proofs::ProofsApi, set_failpoints, state::StateApi,
+    transactions::TransactionsApi, view_function::ViewFunctionApi,
 };
 use anyhow::Context as AnyhowContext;
 use aptos_config::config::NodeConfig;
@@ -58,6 +58,7 @@ pub fn get_api_service(
         BlocksApi,
         EventsApi,
         IndexApi,
+        ProofsApi,
         StateApi,
         TransactionsApi,
         ViewFunctionApi,
@@ -81,6 +82,9 @@ pub fn get_api_service(
         IndexApi {
             context: context.clone(),
         },
+        ProofsApi {
+            context: context.clone(),
+        },
         StateApi {
             context: context.clone(),
         },
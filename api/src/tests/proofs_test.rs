@@ -0,0 +1,186 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::new_test_context;
+use aptos_api_test_context::{current_function_name, TestContext};
+use aptos_api_types::{mime_types::BCS, BcsStateValueWithProof, BcsTransactionWithProof};
+use aptos_types::{
+    access_path::AccessPath, account_config::AccountResource, state_proof::StateProof,
+    state_store::state_key::StateKey,
+};
+use move_core_types::{account_address::AccountAddress, move_resource::MoveStructType};
+use serde::de::DeserializeOwned;
+
+async fn get_bcs<T: DeserializeOwned>(context: &TestContext, path: &str) -> T {
+    let resp = context
+        .reply(
+            warp::test::request()
+                .method("GET")
+                .header("Accept", BCS)
+                .path(&context.prepend_path(path)),
+        )
+        .await;
+    assert_eq!(200, resp.status(), "{:?}", resp.body());
+    bcs::from_bytes(resp.body()).unwrap()
+}
+
+fn account_resource_key(address: AccountAddress) -> StateKey {
+    StateKey::AccessPath(AccessPath::resource_access_path(
+        address,
+        AccountResource::struct_tag(),
+    ))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_account_resource_with_proof() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account);
+    context.commit_block(&vec![txn]).await;
+
+    let address = context.root_account().address();
+    let value: BcsStateValueWithProof = get_bcs(
+        &context,
+        &format!("/accounts/{}/resource/0x1::account::Account/proof", address),
+    )
+    .await;
+
+    let ledger_info = value.state_proof.latest_ledger_info();
+    assert_eq!(
+        context.get_latest_ledger_info().version(),
+        ledger_info.version()
+    );
+    assert_eq!(value.version, ledger_info.version());
+    assert!(value
+        .state_proof
+        .epoch_changes()
+        .ledger_info_with_sigs
+        .is_empty());
+    value
+        .verify(ledger_info, &account_resource_key(address))
+        .unwrap();
+    let resource: AccountResource =
+        bcs::from_bytes(value.state_value.as_ref().unwrap().bytes()).unwrap();
+    assert_eq!(resource.sequence_number(), 1);
+
+    // The proof must not verify for another state key
+    assert!(value
+        .verify(ledger_info, &account_resource_key(account.address()))
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_account_resource_with_proof_of_absence() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account);
+    context.commit_block(&vec![txn]).await;
+
+    let address = AccountAddress::from_hex_literal("0xA550C19").unwrap();
+    let value: BcsStateValueWithProof = get_bcs(
+        &context,
+        &format!("/accounts/{}/resource/0x1::account::Account/proof", address),
+    )
+    .await;
+
+    assert!(value.state_value.is_none());
+    value
+        .verify(
+            value.state_proof.latest_ledger_info(),
+            &account_resource_key(address),
+        )
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_account_resource_with_proof_at_non_checkpoint_version() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account);
+    context.commit_block(&vec![txn]).await;
+
+    // The block metadata transaction isn't a state checkpoint
+    let version = context.get_latest_ledger_info().version() - 2;
+    context
+        .expect_status_code(400)
+        .get(&format!(
+            "/accounts/{}/resource/0x1::account::Account/proof?ledger_version={}",
+            context.root_account().address(),
+            version
+        ))
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_transaction_by_version_with_proof() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account);
+    context.commit_block(&vec![txn.clone()]).await;
+
+    let version = context.get_latest_ledger_info().version() - 1;
+    let txn_with_proof: BcsTransactionWithProof = get_bcs(
+        &context,
+        &format!("/transactions/by_version/{}/proof?known_version=0", version),
+    )
+    .await;
+
+    assert_eq!(txn_with_proof.transaction_with_proof.version, version);
+    assert_eq!(
+        txn_with_proof
+            .transaction_with_proof
+            .transaction
+            .as_signed_user_txn()
+            .unwrap(),
+        &txn
+    );
+    txn_with_proof
+        .verify(txn_with_proof.state_proof.latest_ledger_info())
+        .unwrap();
+
+    let by_hash: BcsTransactionWithProof = get_bcs(
+        &context,
+        &format!(
+            "/transactions/by_hash/{}/proof",
+            txn.clone().committed_hash().to_hex_literal()
+        ),
+    )
+    .await;
+    assert_eq!(
+        by_hash.transaction_with_proof,
+        txn_with_proof.transaction_with_proof
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_state_proof() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account);
+    context.commit_block(&vec![txn]).await;
+
+    let state_proof: StateProof = get_bcs(&context, "/state_proof?known_version=0").await;
+    assert_eq!(
+        context.get_latest_ledger_info().version(),
+        state_proof.latest_ledger_info().version()
+    );
+
+    // Known version in the future is rejected
+    context
+        .expect_status_code(400)
+        .get(&format!(
+            "/state_proof?known_version={}",
+            state_proof.latest_ledger_info().version() + 1
+        ))
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_proofs_by_json_is_disabled() {
+    let context = new_test_context(current_function_name!());
+    context.expect_status_code(403).get("/state_proof").await;
+    context
+        .expect_status_code(403)
+        .get("/transactions/by_version/0/proof")
+        .await;
+}
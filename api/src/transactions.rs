@@ -14,8 +14,9 @@ use crate::{
     page::Page,
     response::{
         api_disabled, transaction_not_found_by_hash, transaction_not_found_by_version,
-        BadRequestError, BasicError, BasicErrorWith404, BasicResponse, BasicResponseStatus,
-        BasicResult, BasicResultWith404, InsufficientStorageError, InternalError,
+        version_pruned, BadRequestError, BasicError, BasicErrorWith404, BasicResponse,
+        BasicResponseStatus, BasicResult, BasicResultWith404, InsufficientStorageError,
+        InternalError,
     },
     ApiTags,
 };
@@ -29,6 +30,8 @@ use aptos_api_types::{
     MAX_RECURSIVE_TYPES_ALLOWED, U64,
 };
 use aptos_crypto::{hash::CryptoHash, signing_message};
+use aptos_logger::{span_export, txn_span};
+use aptos_storage_interface::ArchivedTransaction;
 use aptos_types::{
     account_config::CoinStoreResource,
     account_view::AccountView,
@@ -614,30 +617,40 @@ impl TransactionsApi {
 }
 
 impl TransactionsApi {
-    /// List all transactions paging by ledger version
+    /// List all transactions paging by ledger version, reading the ones pruned from the DB from
+    /// the ledger archive
     fn list(&self, accept_type: &AcceptType, page: Page) -> BasicResultWith404<Vec<Transaction>> {
         let latest_ledger_info = self.context.get_latest_ledger_info()?;
         let ledger_version = latest_ledger_info.version();
 
         let limit = page.limit(&latest_ledger_info)?;
         let start_version = page.compute_start(limit, ledger_version, &latest_ledger_info)?;
-        let data = self
-            .context
-            .get_transactions(start_version, limit, ledger_version)
-            .context("Failed to read raw transactions from storage")
-            .map_err(|err| {
-                BasicErrorWith404::internal_with_code(
-                    err,
-                    AptosErrorCode::InternalError,
-                    &latest_ledger_info,
-                )
-            })?;
+        let (mut data, archived_timestamp) =
+            self.get_archived_transactions(start_version, limit, &latest_ledger_info)?;
+        let remaining = limit - data.len() as u16;
+        if remaining > 0 {
+            data.extend(
+                self.context
+                    .get_transactions(start_version + data.len() as u64, remaining, ledger_version)
+                    .context("Failed to read raw transactions from storage")
+                    .map_err(|err| {
+                        BasicErrorWith404::internal_with_code(
+                            err,
+                            AptosErrorCode::InternalError,
+                            &latest_ledger_info,
+                        )
+                    })?,
+            );
+        }
 
         match accept_type {
             AcceptType::Json => {
-                let timestamp = self
-                    .context
-                    .get_block_timestamp(&latest_ledger_info, start_version)?;
+                let timestamp = match archived_timestamp {
+                    Some(timestamp) => timestamp,
+                    None => self
+                        .context
+                        .get_block_timestamp(&latest_ledger_info, start_version)?,
+                };
                 BasicResponse::try_from_json((
                     self.context.render_transactions_sequential(
                         &latest_ledger_info,
@@ -654,6 +667,28 @@ impl TransactionsApi {
         }
     }
 
+    /// Reads the part of a page that's pruned from the DB from the ledger archive, along with the
+    /// block timestamp of its first transaction. Returns 410 if it's not archived either
+    fn get_archived_transactions(
+        &self,
+        start_version: u64,
+        limit: u16,
+        ledger_info: &LedgerInfo,
+    ) -> Result<(Vec<TransactionOnChainData>, Option<u64>), BasicErrorWith404> {
+        let end_version = ledger_info
+            .oldest_ledger_version
+            .0
+            .min(start_version + limit as u64);
+        let mut data = vec![];
+        let mut timestamp = None;
+        for version in start_version..end_version {
+            let (txn, txn_timestamp) = self.get_archived_transaction(version, ledger_info)?;
+            timestamp.get_or_insert(txn_timestamp);
+            data.push(txn);
+        }
+        Ok((data, timestamp))
+    }
+
     async fn get_transaction_by_hash_inner(
         &self,
         accept_type: &AcceptType,
@@ -670,9 +705,30 @@ impl TransactionsApi {
                     AptosErrorCode::InternalError,
                     &ledger_info,
                 )
-            })?
-            .context(format!("Failed to find transaction with hash: {}", hash))
-            .map_err(|_| transaction_not_found_by_hash(hash, &ledger_info))?;
+            })?;
+        let txn_data = match txn_data {
+            Some(txn_data) => txn_data,
+            // Not in the DB nor in mempool, but it might have been pruned.
+            None => {
+                let archived = self
+                    .context
+                    .get_archived_transaction_by_hash(hash.into())
+                    .context(format!(
+                        "Failed to get archived transaction by hash {}",
+                        hash
+                    ))
+                    .map_err(|err| {
+                        BasicErrorWith404::internal_with_code(
+                            err,
+                            AptosErrorCode::InternalError,
+                            &ledger_info,
+                        )
+                    })?
+                    .ok_or_else(|| transaction_not_found_by_hash(hash, &ledger_info))?;
+                let (txn, timestamp) = Self::from_archived_transaction(archived);
+                return self.render_archived_transaction(accept_type, txn, timestamp, &ledger_info);
+            },
+        };
 
         self.get_transaction_inner(accept_type, txn_data, &ledger_info)
             .await
@@ -684,6 +740,10 @@ impl TransactionsApi {
         version: U64,
     ) -> BasicResultWith404<Transaction> {
         let ledger_info = self.context.get_latest_ledger_info()?;
+        if version.0 < ledger_info.oldest_ledger_version.0 {
+            let (txn, timestamp) = self.get_archived_transaction(version.0, &ledger_info)?;
+            return self.render_archived_transaction(accept_type, txn, timestamp, &ledger_info);
+        }
         let txn_data = self
             .get_by_version(version.0, &ledger_info)
             .context(format!("Failed to get transaction by version {}", version))
@@ -753,6 +813,78 @@ impl TransactionsApi {
         }
     }
 
+    /// Retrieves a transaction pruned from the DB from the ledger archive, along with its block
+    /// timestamp, returns 410 if it's not archived either
+    fn get_archived_transaction(
+        &self,
+        version: u64,
+        ledger_info: &LedgerInfo,
+    ) -> Result<(TransactionOnChainData, u64), BasicErrorWith404> {
+        let archived = self
+            .context
+            .get_archived_transaction(version)
+            .context(format!(
+                "Failed to get archived transaction by version {}",
+                version
+            ))
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    ledger_info,
+                )
+            })?
+            .ok_or_else(|| version_pruned(version, ledger_info))?;
+        Ok(Self::from_archived_transaction(archived))
+    }
+
+    /// The block metadata is pruned as well, so the timestamp comes from the archive.
+    fn from_archived_transaction(archived: ArchivedTransaction) -> (TransactionOnChainData, u64) {
+        let timestamp = archived.block_timestamp_usecs;
+        let txn = TransactionOnChainData::from((
+            archived.version,
+            archived.transaction,
+            archived.info,
+            archived.events,
+            archived.accumulator_root_hash,
+            archived.write_set,
+        ));
+        (txn, timestamp)
+    }
+
+    /// Converts an archived transaction into the outgoing type
+    fn render_archived_transaction(
+        &self,
+        accept_type: &AcceptType,
+        txn: TransactionOnChainData,
+        timestamp: u64,
+        ledger_info: &LedgerInfo,
+    ) -> BasicResultWith404<Transaction> {
+        match accept_type {
+            AcceptType::Json => {
+                let transaction = self
+                    .context
+                    .move_resolver_poem(ledger_info)?
+                    .as_converter(self.context.db.clone())
+                    .try_into_onchain_transaction(timestamp, txn)
+                    .context("Failed to convert archived transaction to Transaction")
+                    .map_err(|err| {
+                        BasicErrorWith404::internal_with_code(
+                            err,
+                            AptosErrorCode::InternalError,
+                            ledger_info,
+                        )
+                    })?;
+                BasicResponse::try_from_json((transaction, ledger_info, BasicResponseStatus::Ok))
+            },
+            AcceptType::Bcs => BasicResponse::try_from_bcs((
+                TransactionData::OnChain(txn),
+                ledger_info,
+                BasicResponseStatus::Ok,
+            )),
+        }
+    }
+
     /// Retrieves a transaction by ledger version
     fn get_by_version(
         &self,
@@ -964,6 +1096,15 @@ impl TransactionsApi {
 
     /// Submits a single transaction, and converts mempool codes to errors
     async fn create_internal(&self, txn: SignedTransaction) -> Result<(), AptosError> {
+        let span = if span_export::is_enabled() {
+            txn_span!(
+                "api.submit_transaction",
+                txn_hash = %txn.clone().committed_hash().to_hex(),
+                status = span_export::Empty
+            )
+        } else {
+            span_export::Span::none()
+        };
         let (mempool_status, vm_status_opt) = self
             .context
             .submit_transaction(txn)
@@ -972,6 +1113,7 @@ impl TransactionsApi {
             .map_err(|err| {
                 aptos_api_types::AptosError::new_with_error_code(err, AptosErrorCode::InternalError)
             })?;
+        span.record("status", &format!("{:?}", mempool_status.code).as_str());
         match mempool_status.code {
             MempoolStatusCode::Accepted => Ok(()),
             MempoolStatusCode::MempoolIsFull | MempoolStatusCode::TooManyTransactions => {
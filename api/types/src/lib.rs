@@ -14,6 +14,7 @@ mod index;
 mod ledger_info;
 pub mod mime_types;
 mod move_types;
+mod proof;
 mod table;
 mod transaction;
 mod view;
@@ -36,6 +37,7 @@ pub use move_types::{
     MoveScriptBytecode, MoveStruct, MoveStructField, MoveStructTag, MoveType, MoveValue,
     MAX_RECURSIVE_TYPES_ALLOWED, U128, U256, U64,
 };
+pub use proof::{BcsStateValueWithProof, BcsTransactionWithProof};
 use serde::{Deserialize, Deserializer};
 use std::str::FromStr;
 pub use table::{RawTableItemRequest, TableItemRequest};
@@ -0,0 +1,67 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{ensure, format_err};
+use aptos_crypto::hash::CryptoHash;
+use aptos_types::{
+    ledger_info::LedgerInfo,
+    proof::{SparseMerkleProof, TransactionInfoWithProof},
+    state_proof::StateProof,
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::{TransactionWithProof, Version},
+};
+use serde::{Deserialize, Serialize};
+
+/// A state value along with the proofs needed to verify it, for encoding in BCS
+///
+/// The `state_proof` carries the signed ledger info the value is proven against,
+/// along with the epoch changes since the version known to the client.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BcsStateValueWithProof {
+    /// Proof of the latest signed ledger info, relative to the version known to the client
+    pub state_proof: StateProof,
+    /// The ledger version the state value was read at
+    pub version: Version,
+    /// The state value, `None` if it doesn't exist at `version`
+    pub state_value: Option<StateValue>,
+    /// Proof of the transaction info at `version` against the signed ledger info
+    pub transaction_info_with_proof: TransactionInfoWithProof,
+    /// Proof of the state value (or its absence) against the state root at `version`
+    pub state_value_proof: SparseMerkleProof,
+}
+
+impl BcsStateValueWithProof {
+    /// Verifies the state value of `state_key` against a verified `ledger_info`
+    pub fn verify(&self, ledger_info: &LedgerInfo, state_key: &StateKey) -> anyhow::Result<()> {
+        self.transaction_info_with_proof
+            .verify(ledger_info, self.version)?;
+        let state_root_hash = self
+            .transaction_info_with_proof
+            .transaction_info()
+            .state_checkpoint_hash()
+            .ok_or_else(|| format_err!("Version {} is not a state checkpoint", self.version))?;
+        self.state_value_proof
+            .verify(state_root_hash, state_key.hash(), self.state_value.as_ref())
+    }
+}
+
+/// A committed transaction along with the proofs needed to verify it, for encoding in BCS
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BcsTransactionWithProof {
+    /// Proof of the latest signed ledger info, relative to the version known to the client
+    pub state_proof: StateProof,
+    /// The transaction and its events, with the proof against the signed ledger info
+    pub transaction_with_proof: TransactionWithProof,
+}
+
+impl BcsTransactionWithProof {
+    /// Verifies the transaction and its events against a verified `ledger_info`
+    pub fn verify(&self, ledger_info: &LedgerInfo) -> anyhow::Result<()> {
+        ensure!(
+            self.transaction_with_proof.events.is_some(),
+            "Transaction at version {} is missing its events",
+            self.transaction_with_proof.version
+        );
+        self.transaction_with_proof.verify(ledger_info)
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{format_err, Result};
+use aptos_block_executor::telemetry::BlockExecutionReport;
 use aptos_gas::{
     AbstractValueSizeGasParameters, ChangeSetConfigs, NativeGasParameters,
     LATEST_GAS_FEATURE_VERSION,
@@ -12,12 +13,14 @@ use aptos_types::{
     account_address::AccountAddress,
     chain_id::ChainId,
     on_chain_config::{Features, OnChainConfig},
+    state_store::state_key::StateKey,
     transaction::{ChangeSet, Transaction, TransactionInfo, TransactionOutput, Version},
 };
 use aptos_validator_interface::{
     AptosValidatorInterface, DBDebuggerInterface, DebuggerStateView, RestDebuggerInterface,
 };
 use aptos_vm::{
+    block_executor::{state_key_conflict_group, BlockAptosVM},
     data_cache::StorageAdapter,
     move_vm_ext::{MoveVmExt, SessionExt, SessionId},
     AptosVM, VMExecutor,
@@ -25,13 +28,27 @@ use aptos_vm::{
 use move_binary_format::errors::VMResult;
 use std::{path::Path, sync::Arc};
 
+/// Number of entries printed in each section of a conflict report.
+const NUM_CONFLICT_REPORT_ENTRIES: usize = 10;
+
 pub struct AptosDebugger {
     debugger: Arc<dyn AptosValidatorInterface + Send>,
+    report_conflicts: bool,
 }
 
 impl AptosDebugger {
     pub fn new(debugger: Arc<dyn AptosValidatorInterface + Send>) -> Self {
-        Self { debugger }
+        Self {
+            debugger,
+            report_conflicts: false,
+        }
+    }
+
+    /// Prints a report of the conflicts between the transactions of each replayed block, which
+    /// requires parallel execution (i.e. a concurrency level above 1).
+    pub fn with_conflict_reports(mut self) -> Self {
+        self.report_conflicts = true;
+        self
     }
 
     pub fn rest_client(rest_client: Client) -> Result<Self> {
@@ -50,8 +67,68 @@ impl AptosDebugger {
         txns: Vec<Transaction>,
     ) -> Result<Vec<TransactionOutput>> {
         let state_view = DebuggerStateView::new(self.debugger.clone(), version);
-        AptosVM::execute_block(txns, &state_view)
-            .map_err(|err| format_err!("Unexpected VM Error: {:?}", err))
+        if !self.report_conflicts {
+            return AptosVM::execute_block(txns, &state_view)
+                .map_err(|err| format_err!("Unexpected VM Error: {:?}", err));
+        }
+
+        let (ret, report) = BlockAptosVM::execute_block_with_report(
+            txns,
+            &state_view,
+            AptosVM::get_concurrency_level(),
+        );
+        match report {
+            Some(report) => Self::print_conflict_report(version, &report),
+            None => println!("No conflict report, the block was executed sequentially."),
+        }
+        ret.map_err(|err| format_err!("Unexpected VM Error: {:?}", err))
+    }
+
+    fn print_conflict_report(first_version: Version, report: &BlockExecutionReport<StateKey>) {
+        println!(
+            "Block at version {}: {} transactions, {} re-executions, {} aborts, {} validation \
+             waves, {} dependencies.",
+            first_version,
+            report.num_txns(),
+            report.num_reexecutions(),
+            report.num_aborts(),
+            report.num_validation_waves,
+            report.num_dependencies,
+        );
+
+        let mut incarnations: Vec<_> = report
+            .incarnations_per_txn
+            .iter()
+            .enumerate()
+            .filter(|(_, incarnations)| **incarnations > 1)
+            .collect();
+        incarnations.sort_by(|(_, a), (_, b)| b.cmp(a));
+        println!("Most re-executed transactions:");
+        for (idx, incarnations) in incarnations.into_iter().take(NUM_CONFLICT_REPORT_ENTRIES) {
+            println!(
+                "    version {}: {} incarnations",
+                first_version + idx as Version,
+                incarnations
+            );
+        }
+
+        println!("Most conflicting resource types, modules and tables:");
+        for (group, count) in report
+            .conflicts_by(state_key_conflict_group)
+            .into_iter()
+            .take(NUM_CONFLICT_REPORT_ENTRIES)
+        {
+            println!("    {}: {}", group, count);
+        }
+
+        println!("Most conflicting state keys:");
+        for (state_key, count) in report
+            .conflicting_keys
+            .iter()
+            .take(NUM_CONFLICT_REPORT_ENTRIES)
+        {
+            println!("    {:?}: {}", state_key, count);
+        }
     }
 
     pub async fn execute_past_transactions(
@@ -29,6 +29,11 @@ pub struct Argument {
 
     #[clap(long, default_value = "1")]
     concurrency_level: usize,
+
+    /// Print a report of the conflicts between the transactions of each replayed block, which
+    /// requires a concurrency level above 1.
+    #[clap(long)]
+    report_conflicts: bool,
 }
 
 #[tokio::main]
@@ -37,12 +42,15 @@ async fn main() -> Result<()> {
     let args = Argument::parse();
     AptosVM::set_concurrency_level_once(args.concurrency_level);
 
-    let debugger = match args.target {
+    let mut debugger = match args.target {
         Target::Rest { endpoint } => {
             AptosDebugger::rest_client(Client::new(Url::parse(&endpoint)?))?
         },
         Target::DB { path } => AptosDebugger::db(path)?,
     };
+    if args.report_conflicts {
+        debugger = debugger.with_conflict_reports();
+    }
 
     println!(
         "{:#?}",
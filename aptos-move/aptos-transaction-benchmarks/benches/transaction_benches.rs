@@ -5,6 +5,7 @@ use aptos_language_e2e_tests::account_universe::P2PTransferGen;
 use aptos_transaction_benchmarks::{
     measurement::wall_time_measurement, transactions::TransactionBencher,
 };
+use aptos_vm::block_executor::BlockPreordering;
 use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion};
 use proptest::prelude::*;
 
@@ -12,6 +13,8 @@ use proptest::prelude::*;
 // Transaction benchmarks
 //
 
+const NUM_CONTENDED_ACCOUNTS: usize = 10;
+
 fn peer_to_peer<M: Measurement + 'static>(c: &mut Criterion<M>) {
     c.bench_function("peer_to_peer", |b| {
         let bencher = TransactionBencher::new(any_with::<P2PTransferGen>((1_000, 1_000_000)));
@@ -24,10 +27,26 @@ fn peer_to_peer<M: Measurement + 'static>(c: &mut Criterion<M>) {
     });
 }
 
+/// Transfers between few accounts, so most transactions conflict with the ones close to them in
+/// the block, with and without preordering the block.
+fn peer_to_peer_contention<M: Measurement + 'static>(c: &mut Criterion<M>) {
+    c.bench_function("peer_to_peer_contention_parallel", |b| {
+        let mut bencher = TransactionBencher::new(any_with::<P2PTransferGen>((1_000, 1_000_000)));
+        bencher.num_accounts(NUM_CONTENDED_ACCOUNTS);
+        bencher.bench_parallel(b)
+    });
+
+    c.bench_function("peer_to_peer_contention_parallel_preordered", |b| {
+        let mut bencher = TransactionBencher::new(any_with::<P2PTransferGen>((1_000, 1_000_000)));
+        bencher.num_accounts(NUM_CONTENDED_ACCOUNTS);
+        bencher.bench_parallel_preordered(b, &BlockPreordering::Speculative)
+    });
+}
+
 criterion_group!(
     name = txn_benches;
     config = wall_time_measurement().sample_size(10);
-    targets = peer_to_peer
+    targets = peer_to_peer, peer_to_peer_contention
 );
 
 criterion_main!(txn_benches);
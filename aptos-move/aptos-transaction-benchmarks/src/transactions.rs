@@ -13,7 +13,10 @@ use aptos_types::{
     on_chain_config::{OnChainConfig, ValidatorSet},
     transaction::Transaction,
 };
-use aptos_vm::{block_executor::BlockAptosVM, data_cache::AsMoveResolver};
+use aptos_vm::{
+    block_executor::{BlockAptosVM, BlockPreordering},
+    data_cache::AsMoveResolver,
+};
 use criterion::{measurement::Measurement, BatchSize, Bencher};
 use proptest::{
     collection::vec,
@@ -91,6 +94,26 @@ where
             BatchSize::LargeInput,
         )
     }
+
+    /// Runs the bencher, preordering the block with `preordering` before parallel execution.
+    pub fn bench_parallel_preordered<M: Measurement>(
+        &self,
+        b: &mut Bencher<M>,
+        preordering: &BlockPreordering,
+    ) {
+        b.iter_batched(
+            || {
+                TransactionBenchState::with_size(
+                    &self.strategy,
+                    self.num_accounts,
+                    self.num_transactions,
+                )
+            },
+            |state| state.execute_parallel_preordered(preordering),
+            // The input here is the entire list of signed transactions, so it's pretty large.
+            BatchSize::LargeInput,
+        )
+    }
 }
 
 struct TransactionBenchState {
@@ -200,6 +223,19 @@ impl TransactionBenchState {
         )
         .expect("VM should not fail to start");
     }
+
+    /// Executes this state in a single block via parallel execution, after preordering it.
+    fn execute_parallel_preordered(self, preordering: &BlockPreordering) {
+        // The output is ignored here since we're just testing transaction performance, not trying
+        // to assert correctness.
+        BlockAptosVM::execute_block_preordered(
+            self.transactions,
+            self.executor.get_state_view(),
+            num_cpus::get(),
+            preordering,
+        )
+        .expect("VM should not fail to start");
+    }
 }
 
 /// Returns a strategy for the account universe customized for benchmarks.
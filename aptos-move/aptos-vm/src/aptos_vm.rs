@@ -6,7 +6,7 @@ use crate::{
         discard_error_output, discard_error_vm_status, PreprocessedTransaction, VMAdapter,
     },
     aptos_vm_impl::{get_transaction_output, AptosVMImpl, AptosVMInternals},
-    block_executor::BlockAptosVM,
+    block_executor::{BlockAptosVM, BlockPreordering},
     counters::*,
     data_cache::{AsMoveResolver, IntoMoveResolver},
     delta_state_view::DeltaStateView,
@@ -73,6 +73,8 @@ static EXECUTION_CONCURRENCY_LEVEL: OnceCell<usize> = OnceCell::new();
 static NUM_PROOF_READING_THREADS: OnceCell<usize> = OnceCell::new();
 static PARANOID_TYPE_CHECKS: OnceCell<bool> = OnceCell::new();
 static PROCESSED_TRANSACTIONS_DETAILED_COUNTERS: OnceCell<bool> = OnceCell::new();
+static BLOCK_EXECUTION_REPORTS: OnceCell<bool> = OnceCell::new();
+static BLOCK_PREORDERING: OnceCell<BlockPreordering> = OnceCell::new();
 
 /// Remove this once the bundle is removed from the code.
 static MODULE_BUNDLE_DISALLOWED: AtomicBool = AtomicBool::new(true);
@@ -159,6 +161,33 @@ impl AptosVM {
         }
     }
 
+    /// Enables reporting the conflicts between transactions in parallel execution to the
+    /// metrics when invoked the first time.
+    pub fn set_block_execution_reports() {
+        // Only the first call succeeds, due to OnceCell semantics.
+        BLOCK_EXECUTION_REPORTS.set(true).ok();
+    }
+
+    /// Get whether we should report the conflicts between transactions in parallel execution
+    pub fn get_block_execution_reports() -> bool {
+        match BLOCK_EXECUTION_REPORTS.get() {
+            Some(value) => *value,
+            None => false,
+        }
+    }
+
+    /// Sets how blocks are preordered before parallel execution, only if it was not set
+    /// already.
+    pub fn set_block_preordering(preordering: BlockPreordering) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        BLOCK_PREORDERING.set(preordering).ok();
+    }
+
+    /// Get how blocks are preordered before parallel execution, None if they are not.
+    pub fn get_block_preordering() -> Option<&'static BlockPreordering> {
+        BLOCK_PREORDERING.get()
+    }
+
     pub fn internals(&self) -> AptosVMInternals {
         AptosVMInternals::new(&self.0)
     }
@@ -7,28 +7,35 @@ use crate::{
     adapter_common::{preprocess_transaction, PreprocessedTransaction},
     block_executor::vm_wrapper::AptosExecutorTask,
     counters::{
-        BLOCK_EXECUTOR_CONCURRENCY, BLOCK_EXECUTOR_EXECUTE_BLOCK_SECONDS,
-        BLOCK_EXECUTOR_SIGNATURE_VERIFICATION_SECONDS,
+        BLOCK_EXECUTOR_CONCURRENCY, BLOCK_EXECUTOR_CONFLICTS, BLOCK_EXECUTOR_EXECUTE_BLOCK_SECONDS,
+        BLOCK_EXECUTOR_PREORDERING_SECONDS, BLOCK_EXECUTOR_SIGNATURE_VERIFICATION_SECONDS,
     },
+    data_cache::AsMoveResolver,
+    read_write_set_analysis::ReadWriteSetAnalysis,
     AptosVM,
 };
 use aptos_aggregator::{delta_change_set::DeltaOp, transaction::TransactionOutputExt};
 use aptos_block_executor::{
     errors::Error,
     executor::{BlockExecutor, RAYON_EXEC_POOL},
+    preordering::{preorder, Footprint},
     task::{
         Transaction as BlockExecutorTransaction,
         TransactionOutput as BlockExecutorTransactionOutput,
     },
+    telemetry::BlockExecutionReport,
 };
+use aptos_logger::debug;
 use aptos_state_view::StateView;
 use aptos_types::{
+    access_path::{AccessPath, Path},
     state_store::state_key::StateKey,
     transaction::{Transaction, TransactionOutput, TransactionStatus},
     write_set::{WriteOp, WriteSet, WriteSetMut},
 };
-use move_core_types::vm_status::VMStatus;
+use move_core_types::{language_storage::ResourceKey, vm_status::VMStatus};
 use rayon::prelude::*;
+use read_write_set_dynamic::NormalizedReadWriteSetAnalysis;
 
 impl BlockExecutorTransaction for PreprocessedTransaction {
     type Key = StateKey;
@@ -79,6 +86,93 @@ impl BlockExecutorTransactionOutput for AptosTransactionOutput {
     }
 }
 
+/// Number of the most conflicting state key groups of a block that are logged.
+const NUM_CONFLICT_GROUPS_TO_LOG: usize = 10;
+
+/// Number of transactions analyzed by each thread with the same module cache.
+const STATIC_FOOTPRINTS_CHUNK_SIZE: usize = 100;
+
+/// Groups the conflicting `StateKey`s of a `BlockExecutionReport` by resource type (without type
+/// arguments), module or table, e.g. `0x1::coin::CoinStore`.
+pub fn state_key_conflict_group(state_key: &StateKey) -> String {
+    match state_key {
+        StateKey::AccessPath(access_path) => match access_path.get_path() {
+            Path::Code(module_id) => format!("code {}", module_id.short_str_lossless()),
+            Path::Resource(tag) | Path::ResourceGroup(tag) => format!(
+                "{}::{}::{}",
+                tag.address.short_str_lossless(),
+                tag.module,
+                tag.name
+            ),
+        },
+        StateKey::TableItem { handle, .. } => format!("table {}", handle.0.short_str_lossless()),
+        StateKey::Raw(_) => "raw".to_string(),
+    }
+}
+
+/// Buckets the conflicting `StateKey`s of a `BlockExecutionReport` by kind, i.e. `resource`,
+/// `table`, `module` or `other`, which unlike their groups are few enough to label metrics with.
+pub fn state_key_conflict_kind(state_key: &StateKey) -> &'static str {
+    match state_key {
+        StateKey::AccessPath(access_path) => match access_path.get_path() {
+            Path::Code(_) => "module",
+            Path::Resource(_) | Path::ResourceGroup(_) => "resource",
+        },
+        StateKey::TableItem { .. } => "table",
+        StateKey::Raw(_) => "other",
+    }
+}
+
+/// How to estimate the footprints of the transactions of a block to preorder it before parallel
+/// execution (see `aptos_block_executor::preordering`). The estimates only affect performance:
+/// the results are the same as executing the block in its own order.
+pub enum BlockPreordering {
+    /// Executes each transaction alone on top of the state before the block.
+    Speculative,
+    /// Uses the read/write set analysis of the Move modules, which doesn't account for
+    /// aggregators and tables. Transactions it can't analyze are not reordered.
+    StaticAnalysis(NormalizedReadWriteSetAnalysis),
+}
+
+/// Estimates the footprints of the transactions of a block with the read/write set analysis.
+fn static_footprints<S: StateView + Sync>(
+    analysis: &NormalizedReadWriteSetAnalysis,
+    signature_verified_block: &[PreprocessedTransaction],
+    state_view: &S,
+) -> Vec<Option<Footprint<StateKey>>> {
+    let to_state_keys = |keys: Vec<ResourceKey>| {
+        keys.into_iter()
+            .map(|key| {
+                StateKey::AccessPath(AccessPath::resource_access_path(
+                    key.address(),
+                    key.type_().clone(),
+                ))
+            })
+            .collect()
+    };
+
+    RAYON_EXEC_POOL.install(|| {
+        signature_verified_block
+            .par_chunks(STATIC_FOOTPRINTS_CHUNK_SIZE)
+            .flat_map_iter(|chunk| {
+                let resolver = state_view.as_move_resolver();
+                let analysis = ReadWriteSetAnalysis::new(analysis, &resolver);
+                chunk
+                    .iter()
+                    .map(|txn| {
+                        let (reads, writes) = analysis.get_keys_transaction(txn, true).ok()?;
+                        Some(Footprint {
+                            reads: to_state_keys(reads),
+                            writes: to_state_keys(writes),
+                            deltas: vec![],
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    })
+}
+
 pub struct BlockAptosVM();
 
 impl BlockAptosVM {
@@ -87,6 +181,84 @@ impl BlockAptosVM {
         state_view: &S,
         concurrency_level: usize,
     ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        let (ret, report) = Self::execute_block_impl(
+            transactions,
+            state_view,
+            concurrency_level,
+            AptosVM::get_block_execution_reports(),
+            AptosVM::get_block_preordering(),
+        );
+        if let Some(report) = report {
+            report.observe();
+            for (kind, count) in report.conflicts_by(state_key_conflict_kind) {
+                BLOCK_EXECUTOR_CONFLICTS
+                    .with_label_values(&[kind])
+                    .inc_by(count as u64);
+            }
+            debug!(
+                "[Execution]: {} txns, {} re-executions, {} aborts, {} validation waves, {} dependencies, most conflicting: {:?}",
+                report.num_txns(),
+                report.num_reexecutions(),
+                report.num_aborts(),
+                report.num_validation_waves,
+                report.num_dependencies,
+                report
+                    .conflicts_by(state_key_conflict_group)
+                    .into_iter()
+                    .take(NUM_CONFLICT_GROUPS_TO_LOG)
+                    .collect::<Vec<_>>(),
+            );
+        }
+        ret
+    }
+
+    /// Same as `execute_block`, but also returns the report of the conflicts between the
+    /// transactions of the block if it was executed in parallel, e.g. for replays.
+    pub fn execute_block_with_report<S: StateView + Sync>(
+        transactions: Vec<Transaction>,
+        state_view: &S,
+        concurrency_level: usize,
+    ) -> (
+        Result<Vec<TransactionOutput>, VMStatus>,
+        Option<BlockExecutionReport<StateKey>>,
+    ) {
+        Self::execute_block_impl(
+            transactions,
+            state_view,
+            concurrency_level,
+            true,
+            AptosVM::get_block_preordering(),
+        )
+    }
+
+    /// Same as `execute_block`, but preorders the block with `preordering` regardless of the
+    /// configured one, e.g. for benchmarks.
+    pub fn execute_block_preordered<S: StateView + Sync>(
+        transactions: Vec<Transaction>,
+        state_view: &S,
+        concurrency_level: usize,
+        preordering: &BlockPreordering,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        Self::execute_block_impl(
+            transactions,
+            state_view,
+            concurrency_level,
+            false,
+            Some(preordering),
+        )
+        .0
+    }
+
+    fn execute_block_impl<S: StateView + Sync>(
+        transactions: Vec<Transaction>,
+        state_view: &S,
+        concurrency_level: usize,
+        with_report: bool,
+        preordering: Option<&BlockPreordering>,
+    ) -> (
+        Result<Vec<TransactionOutput>, VMStatus>,
+        Option<BlockExecutionReport<StateKey>>,
+    ) {
         let _timer = BLOCK_EXECUTOR_EXECUTE_BLOCK_SECONDS.start_timer();
         // Verify the signatures of all the transactions in parallel.
         // This is time consuming so don't wait and do the checking
@@ -108,28 +280,62 @@ impl BlockAptosVM {
             concurrency_level,
         );
 
-        let ret = executor
-            .execute_block(state_view, signature_verified_block, state_view)
-            .map(|results| {
-                // Process the outputs in parallel, combining delta writes with other writes.
-                RAYON_EXEC_POOL.install(|| {
-                    results
-                        .into_par_iter()
-                        .map(|(output, delta_writes)| {
-                            output      // AptosTransactionOutput
+        let order = match preordering {
+            Some(preordering) if concurrency_level > 1 => {
+                let _timer = BLOCK_EXECUTOR_PREORDERING_SECONDS.start_timer();
+                let footprints = match preordering {
+                    BlockPreordering::Speculative => executor.speculative_footprints(
+                        state_view,
+                        &signature_verified_block,
+                        state_view,
+                    ),
+                    BlockPreordering::StaticAnalysis(analysis) => {
+                        static_footprints(analysis, &signature_verified_block, state_view)
+                    },
+                };
+                Some(preorder(&footprints))
+                    .filter(|order| order.iter().enumerate().any(|(pos, idx)| pos != *idx))
+            },
+            _ => None,
+        };
+
+        let (ret, report) = if let Some(order) = order {
+            executor.execute_block_in_order(
+                state_view,
+                signature_verified_block,
+                state_view,
+                order,
+                with_report,
+            )
+        } else if with_report {
+            executor.execute_block_with_report(state_view, signature_verified_block, state_view)
+        } else {
+            (
+                executor.execute_block(state_view, signature_verified_block, state_view),
+                None,
+            )
+        };
+        let ret = ret.map(|results| {
+            // Process the outputs in parallel, combining delta writes with other writes.
+            RAYON_EXEC_POOL.install(|| {
+                results
+                    .into_par_iter()
+                    .map(|(output, delta_writes)| {
+                        output      // AptosTransactionOutput
                             .into()     // TransactionOutputExt
                             .output_with_delta_writes(WriteSetMut::new(delta_writes))
-                        })
-                        .collect()
-                })
-            });
+                    })
+                    .collect()
+            })
+        });
 
-        match ret {
+        let ret = match ret {
             Ok(outputs) => Ok(outputs),
             Err(Error::ModulePathReadWrite) => {
                 unreachable!("[Execution]: Must be handled by sequential fallback")
             },
             Err(Error::UserError(err)) => Err(err),
-        }
+        };
+        (ret, report)
     }
 }
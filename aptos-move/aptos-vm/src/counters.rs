@@ -26,6 +26,17 @@ pub static BLOCK_EXECUTOR_CONCURRENCY: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static BLOCK_EXECUTOR_PREORDERING_SECONDS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        // metric name
+        "block_executor_preordering_seconds",
+        // metric description
+        "The time spent in seconds estimating the footprints of the transactions of a block and preordering it in executor",
+        exponential_buckets(/*start=*/ 1e-3, /*factor=*/ 2.0, /*count=*/ 20).unwrap(),
+    )
+    .unwrap()
+});
+
 pub static BLOCK_EXECUTOR_SIGNATURE_VERIFICATION_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         // metric name
@@ -37,6 +48,18 @@ pub static BLOCK_EXECUTOR_SIGNATURE_VERIFICATION_SECONDS: Lazy<Histogram> = Lazy
     .unwrap()
 });
 
+/// Number of conflicts between the transactions of a block in parallel execution, by the kind of
+/// the conflicting keys (resource, table, module or other), when block execution reports are
+/// enabled. The resource types and tables they belong to are logged instead.
+pub static BLOCK_EXECUTOR_CONFLICTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "block_executor_conflicts",
+        "Number of conflicts between transactions in parallel execution",
+        &["state_key_kind"]
+    )
+    .unwrap()
+});
+
 /// Count the number of transactions that brake invariants of VM.
 pub static TRANSACTIONS_INVARIANT_VIOLATION: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -160,7 +160,6 @@ impl<'a, R: MoveResolverExt> ReadWriteSetAnalysis<'a, R> {
         }
     }
 
-    #[allow(dead_code)]
     /// Internal API to get the read/write set of `PreprocessedTransaction`.
     pub(crate) fn get_keys_transaction(
         &self,
@@ -15,6 +15,15 @@ pub static MODULE_PUBLISHING_FALLBACK_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of times a preordered block had to be re-executed in the order of the block.
+pub static PREORDERING_FALLBACK_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_preordering_fallback_count",
+        "Count times the transactions of a preordered block conflicted in a way the order didn't preserve (re-execution in block order)"
+    )
+    .unwrap()
+});
+
 /// Count of speculative transaction re-executions due to a failed validation.
 pub static SPECULATIVE_ABORT_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -24,6 +33,33 @@ pub static SPECULATIVE_ABORT_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of reads that waited on an earlier transaction to finish re-executing.
+pub static SPECULATIVE_DEPENDENCY_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_speculative_dependency_count",
+        "Number of reads in parallel execution that waited on a dependency to be re-executed"
+    )
+    .unwrap()
+});
+
+pub static TXN_EXECUTIONS_PER_BLOCK_TXN: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_execution_txn_incarnations",
+        "Number of incarnations of each transaction in parallel execution, when reported",
+        exponential_buckets(/*start=*/ 1.0, /*factor=*/ 2.0, /*count=*/ 12).unwrap(),
+    )
+    .unwrap()
+});
+
+pub static VALIDATION_WAVES_PER_BLOCK: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_execution_validation_waves",
+        "Number of validation waves of each block in parallel execution, when reported",
+        exponential_buckets(/*start=*/ 1.0, /*factor=*/ 2.0, /*count=*/ 16).unwrap(),
+    )
+    .unwrap()
+});
+
 pub static VM_INIT_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         // metric name
@@ -6,8 +6,10 @@ use crate::{
     counters::{TASK_EXECUTE_SECONDS, TASK_VALIDATE_SECONDS, VM_INIT_SECONDS},
     errors::*,
     output_delta_resolver::OutputDeltaResolver,
-    scheduler::{Scheduler, SchedulerTask, Version, Wave},
+    preordering::{permute, reordering_commutes, unpermute, Footprint},
+    scheduler::{Scheduler, SchedulerTask, TxnIndex, Version, Wave},
     task::{ExecutionStatus, ExecutorTask, Transaction, TransactionOutput},
+    telemetry::{BlockExecutionReport, ConflictTracker},
     txn_last_input_output::TxnLastInputOutput,
     view::{LatestView, MVHashMapView},
 };
@@ -17,12 +19,15 @@ use aptos_state_view::TStateView;
 use aptos_types::write_set::WriteOp;
 use num_cpus;
 use once_cell::sync::Lazy;
+use rayon::prelude::*;
 use std::{
-    collections::btree_map::BTreeMap,
+    collections::{btree_map::BTreeMap, HashSet},
     marker::PhantomData,
     sync::atomic::{AtomicBool, Ordering},
 };
 
+const BLOCK_ORDER_RESULT: &str = "Executing in the order of the block always produces a result";
+
 pub static RAYON_EXEC_POOL: Lazy<rayon::ThreadPool> = Lazy::new(|| {
     rayon::ThreadPoolBuilder::new()
         .num_threads(num_cpus::get())
@@ -58,6 +63,50 @@ where
         }
     }
 
+    /// Estimates the footprint of each transaction of the block for `preordering::preorder`, by
+    /// executing all of them in parallel on top of `base_view`, each as if it was alone in the
+    /// block. Transactions that abort or skip the rest of the block have no footprint.
+    pub fn speculative_footprints(
+        &self,
+        executor_arguments: E::Argument,
+        signature_verified_block: &[T],
+        base_view: &S,
+    ) -> Vec<Option<Footprint<T::Key>>> {
+        let versioned_data_cache = MVHashMap::new();
+        let scheduler = Scheduler::new(signature_verified_block.len());
+
+        RAYON_EXEC_POOL.install(|| {
+            signature_verified_block
+                .par_iter()
+                .enumerate()
+                .map_init(
+                    || E::init(executor_arguments),
+                    |executor, (idx, txn)| {
+                        let speculative_view =
+                            MVHashMapView::new(&versioned_data_cache, &scheduler, None);
+                        match executor.execute_transaction(
+                            &LatestView::<T, S>::new_mv_view(base_view, &speculative_view, idx),
+                            txn,
+                            idx,
+                            false,
+                        ) {
+                            ExecutionStatus::Success(output) => Some(Footprint {
+                                reads: speculative_view
+                                    .take_reads()
+                                    .iter()
+                                    .map(|desc| desc.path().clone())
+                                    .collect(),
+                                writes: output.get_writes().into_iter().map(|(k, _)| k).collect(),
+                                deltas: output.get_deltas().into_iter().map(|(k, _)| k).collect(),
+                            }),
+                            ExecutionStatus::SkipRest(_) | ExecutionStatus::Abort(_) => None,
+                        }
+                    },
+                )
+                .collect()
+        })
+    }
+
     fn execute(
         &self,
         version: Version,
@@ -67,12 +116,17 @@ where
         scheduler: &Scheduler,
         executor: &E,
         base_view: &S,
+        conflict_tracker: Option<&ConflictTracker<T::Key>>,
     ) -> SchedulerTask {
         let _timer = TASK_EXECUTE_SECONDS.start_timer();
         let (idx_to_execute, incarnation) = version;
         let txn = &signature_verified_block[idx_to_execute];
+        if let Some(conflict_tracker) = conflict_tracker {
+            conflict_tracker.record_execution(idx_to_execute);
+        }
 
-        let speculative_view = MVHashMapView::new(versioned_data_cache, scheduler);
+        let speculative_view =
+            MVHashMapView::new(versioned_data_cache, scheduler, conflict_tracker);
 
         // VM execution.
         let execute_result = executor.execute_transaction(
@@ -141,6 +195,7 @@ where
         last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
         versioned_data_cache: &MVHashMap<T::Key, T::Value>,
         scheduler: &Scheduler,
+        conflict_tracker: Option<&ConflictTracker<T::Key>>,
     ) -> SchedulerTask {
         use MVHashMapError::*;
         use MVHashMapOutput::*;
@@ -151,8 +206,8 @@ where
             .read_set(idx_to_validate)
             .expect("Prior read-set must be recorded");
 
-        let valid = read_set.iter().all(|r| {
-            match versioned_data_cache.read(r.path(), idx_to_validate) {
+        let invalid_read = read_set.iter().find(|r| {
+            !match versioned_data_cache.read(r.path(), idx_to_validate) {
                 Ok(Version(version, _)) => r.validate_version(version),
                 Ok(Resolved(value)) => r.validate_resolved(value),
                 Err(Dependency(_)) => false, // Dependency implies a validation failure.
@@ -167,11 +222,15 @@ where
                 Err(DeltaApplicationFailure) => r.validate_delta_application_failure(),
             }
         });
+        let valid = invalid_read.is_none();
 
         let aborted = !valid && scheduler.try_abort(idx_to_validate, incarnation);
 
         if aborted {
             counters::SPECULATIVE_ABORT_COUNT.inc();
+            if let (Some(conflict_tracker), Some(read)) = (conflict_tracker, invalid_read) {
+                conflict_tracker.record_abort(idx_to_validate, read.path());
+            }
 
             // Not valid and successfully aborted, mark the latest write/delta sets as estimates.
             for k in last_input_output.modified_keys(idx_to_validate) {
@@ -194,6 +253,7 @@ where
         scheduler: &Scheduler,
         base_view: &S,
         committing: bool,
+        conflict_tracker: Option<&ConflictTracker<T::Key>>,
     ) {
         // Make executor for each task. TODO: fast concurrent executor.
         let init_timer = VM_INIT_SECONDS.start_timer();
@@ -218,6 +278,7 @@ where
                     last_input_output,
                     versioned_data_cache,
                     scheduler,
+                    conflict_tracker,
                 ),
                 SchedulerTask::ExecutionTask(version_to_execute, None) => self.execute(
                     version_to_execute,
@@ -227,6 +288,7 @@ where
                     scheduler,
                     &executor,
                     base_view,
+                    conflict_tracker,
                 ),
                 SchedulerTask::ExecutionTask(_, Some(condvar)) => {
                     let (lock, cvar) = &*condvar;
@@ -245,18 +307,72 @@ where
         }
     }
 
+    #[cfg(any(test, feature = "fuzzing"))]
     pub(crate) fn execute_transactions_parallel(
         &self,
         executor_initial_arguments: E::Argument,
         signature_verified_block: &Vec<T>,
         base_view: &S,
     ) -> Result<Vec<(E::Output, Vec<(T::Key, WriteOp)>)>, E::Error> {
+        self.execute_transactions_parallel_impl(
+            executor_initial_arguments,
+            signature_verified_block,
+            base_view,
+            None,
+            None,
+        )
+        .0
+        .expect(BLOCK_ORDER_RESULT)
+    }
+
+    /// Same as `execute_transactions_parallel`, but also reports the conflicts between the
+    /// transactions of the block.
+    #[cfg(test)]
+    pub(crate) fn execute_transactions_parallel_with_report(
+        &self,
+        executor_initial_arguments: E::Argument,
+        signature_verified_block: &Vec<T>,
+        base_view: &S,
+    ) -> (
+        Result<Vec<(E::Output, Vec<(T::Key, WriteOp)>)>, E::Error>,
+        BlockExecutionReport<T::Key>,
+    ) {
+        let conflict_tracker = ConflictTracker::new(signature_verified_block.len());
+        let (ret, num_validation_waves) = self.execute_transactions_parallel_impl(
+            executor_initial_arguments,
+            signature_verified_block,
+            base_view,
+            Some(&conflict_tracker),
+            None,
+        );
+        (
+            ret.expect(BLOCK_ORDER_RESULT),
+            conflict_tracker.into_report(num_validation_waves),
+        )
+    }
+
+    /// Returns the result of the execution along with the number of validation waves.
+    ///
+    /// If an `order` is provided, `signature_verified_block` must have been permuted by it (see
+    /// `preordering::permute`), and the result is in the order of the original block, or None
+    /// if it must be re-executed in that order.
+    fn execute_transactions_parallel_impl(
+        &self,
+        executor_initial_arguments: E::Argument,
+        signature_verified_block: &Vec<T>,
+        base_view: &S,
+        conflict_tracker: Option<&ConflictTracker<T::Key>>,
+        order: Option<&[TxnIndex]>,
+    ) -> (
+        Option<Result<Vec<(E::Output, Vec<(T::Key, WriteOp)>)>, E::Error>>,
+        Wave,
+    ) {
         assert!(self.concurrency_level > 1, "Must use sequential execution");
 
         let versioned_data_cache = MVHashMap::new();
 
         if signature_verified_block.is_empty() {
-            return Ok(vec![]);
+            return (Some(Ok(vec![])), 0);
         }
 
         let num_txns = signature_verified_block.len();
@@ -275,11 +391,24 @@ where
                         &scheduler,
                         base_view,
                         committing.swap(false, Ordering::SeqCst),
+                        conflict_tracker,
                     );
                 });
             }
         });
 
+        if let Some(order) = order {
+            let ret = self.collect_preordered_outputs(order, &last_input_output, base_view);
+            let num_validation_waves = scheduler.validation_wave();
+            RAYON_EXEC_POOL.spawn(move || {
+                // Explicit async drops.
+                drop(last_input_output);
+                drop(scheduler);
+                drop(versioned_data_cache);
+            });
+            return (ret, num_validation_waves);
+        }
+
         // TODO: for large block sizes and many cores, extract outputs in parallel.
         let mut final_results = Vec::with_capacity(num_txns);
 
@@ -304,13 +433,14 @@ where
             ret
         };
 
+        let num_validation_waves = scheduler.validation_wave();
         RAYON_EXEC_POOL.spawn(move || {
             // Explicit async drops.
             drop(last_input_output);
             drop(scheduler);
         });
 
-        match maybe_err {
+        let ret = match maybe_err {
             Some(err) => Err(err),
             None => {
                 final_results.resize_with(num_txns, E::Output::skip_output);
@@ -322,7 +452,70 @@ where
                     .zip(delta_resolver.resolve(base_view, num_txns).into_iter())
                     .collect())
             },
+        };
+        (Some(ret), num_validation_waves)
+    }
+
+    /// Extracts the outputs of a block executed in `order`, in the order of the block, if that
+    /// is equivalent to having executed the block in its own order. Returns None otherwise, or
+    /// if a transaction aborted or skipped the rest of the block (whose outcome may depend on
+    /// the order), in which case the block must be re-executed in its own order.
+    fn collect_preordered_outputs(
+        &self,
+        order: &[TxnIndex],
+        last_input_output: &TxnLastInputOutput<T::Key, E::Output, E::Error>,
+        base_view: &S,
+    ) -> Option<Result<Vec<(E::Output, Vec<(T::Key, WriteOp)>)>, E::Error>> {
+        if last_input_output.module_publishing_may_race() {
+            // Detected again (and handled) when executing in the order of the block.
+            return None;
+        }
+
+        let mut outputs = Vec::with_capacity(order.len());
+        let mut footprints = Vec::with_capacity(order.len());
+        for position in 0..order.len() {
+            let output = match last_input_output.take_output(position) {
+                ExecutionStatus::Success(t) => t,
+                ExecutionStatus::SkipRest(_) | ExecutionStatus::Abort(_) => return None,
+            };
+            footprints.push(Footprint {
+                reads: last_input_output
+                    .read_set(position)
+                    .expect("Read set must be recorded after execution")
+                    .iter()
+                    .map(|desc| desc.path().clone())
+                    .collect(),
+                writes: output.get_writes().into_iter().map(|(k, _)| k).collect(),
+                deltas: output.get_deltas().into_iter().map(|(k, _)| k).collect(),
+            });
+            outputs.push(output);
+        }
+        if !reordering_commutes(order, &footprints) {
+            return None;
+        }
+
+        // Deltas were materialized in the execution order, re-materialize them in the order of
+        // the block from the writes and deltas to the aggregators.
+        let outputs = unpermute(outputs, order);
+        let aggregator_keys: HashSet<_> = footprints
+            .into_iter()
+            .flat_map(|footprint| footprint.deltas)
+            .collect();
+        let versioned_outputs = MVHashMap::new();
+        for (idx, output) in outputs.iter().enumerate() {
+            for (k, v) in output.get_writes() {
+                if aggregator_keys.contains(&k) {
+                    versioned_outputs.add_write(&k, (idx, 0), v);
+                }
+            }
+            for (k, delta) in output.get_deltas() {
+                versioned_outputs.add_delta(&k, idx, delta);
+            }
         }
+        let delta_resolver: OutputDeltaResolver<T> = OutputDeltaResolver::new(versioned_outputs);
+        let materialized_deltas = delta_resolver.try_resolve(base_view, outputs.len())?;
+
+        Some(Ok(outputs.into_iter().zip(materialized_deltas).collect()))
     }
 
     pub(crate) fn execute_transactions_sequential(
@@ -380,18 +573,124 @@ where
         signature_verified_block: Vec<T>,
         base_view: &S,
     ) -> Result<Vec<(E::Output, Vec<(T::Key, WriteOp)>)>, E::Error> {
-        let mut ret = if self.concurrency_level > 1 {
-            self.execute_transactions_parallel(
-                executor_arguments,
-                &signature_verified_block,
-                base_view,
+        self.execute_block_impl(
+            executor_arguments,
+            signature_verified_block,
+            base_view,
+            false,
+            None,
+        )
+        .0
+    }
+
+    /// Same as `execute_block`, but also reports the conflicts between the transactions of the
+    /// block if it was executed in parallel (the report is None for sequential execution).
+    pub fn execute_block_with_report(
+        &self,
+        executor_arguments: E::Argument,
+        signature_verified_block: Vec<T>,
+        base_view: &S,
+    ) -> (
+        Result<Vec<(E::Output, Vec<(T::Key, WriteOp)>)>, E::Error>,
+        Option<BlockExecutionReport<T::Key>>,
+    ) {
+        self.execute_block_impl(
+            executor_arguments,
+            signature_verified_block,
+            base_view,
+            true,
+            None,
+        )
+    }
+
+    /// Same as `execute_block_with_report`, but executes the transactions in parallel in the
+    /// given `order` (a permutation of the indices of the block, e.g. from
+    /// `preordering::preorder`). The result is the same as executing in the order of the block:
+    /// if the transactions turn out to conflict in a way the order doesn't preserve, the block is
+    /// re-executed in its own order. For a reordered block, the report is indexed by the
+    /// position of the transactions in `order`.
+    pub fn execute_block_in_order(
+        &self,
+        executor_arguments: E::Argument,
+        signature_verified_block: Vec<T>,
+        base_view: &S,
+        order: Vec<TxnIndex>,
+        with_report: bool,
+    ) -> (
+        Result<Vec<(E::Output, Vec<(T::Key, WriteOp)>)>, E::Error>,
+        Option<BlockExecutionReport<T::Key>>,
+    ) {
+        assert_eq!(
+            order.len(),
+            signature_verified_block.len(),
+            "Order must be a permutation of the block"
+        );
+        self.execute_block_impl(
+            executor_arguments,
+            signature_verified_block,
+            base_view,
+            with_report,
+            Some(order),
+        )
+    }
+
+    fn execute_block_impl(
+        &self,
+        executor_arguments: E::Argument,
+        mut signature_verified_block: Vec<T>,
+        base_view: &S,
+        with_report: bool,
+        order: Option<Vec<TxnIndex>>,
+    ) -> (
+        Result<Vec<(E::Output, Vec<(T::Key, WriteOp)>)>, E::Error>,
+        Option<BlockExecutionReport<T::Key>>,
+    ) {
+        let (mut ret, report) = if self.concurrency_level > 1 {
+            let conflict_tracker =
+                with_report.then(|| ConflictTracker::new(signature_verified_block.len()));
+            let mut num_validation_waves = 0;
+
+            let mut ret = None;
+            if let Some(order) = order {
+                let preordered_block = permute(signature_verified_block, &order);
+                let (preordered_ret, waves) = self.execute_transactions_parallel_impl(
+                    executor_arguments,
+                    &preordered_block,
+                    base_view,
+                    conflict_tracker.as_ref(),
+                    Some(&order),
+                );
+                signature_verified_block = unpermute(preordered_block, &order);
+                num_validation_waves += waves;
+                if preordered_ret.is_none() {
+                    debug!("[Execution]: Preordering conflicts, fallback to block order");
+                    counters::PREORDERING_FALLBACK_COUNT.inc();
+                }
+                ret = preordered_ret;
+            }
+            let ret = ret.unwrap_or_else(|| {
+                let (ret, waves) = self.execute_transactions_parallel_impl(
+                    executor_arguments,
+                    &signature_verified_block,
+                    base_view,
+                    conflict_tracker.as_ref(),
+                    None,
+                );
+                num_validation_waves += waves;
+                ret.expect(BLOCK_ORDER_RESULT)
+            });
+
+            (
+                ret,
+                conflict_tracker.map(|tracker| tracker.into_report(num_validation_waves)),
             )
         } else {
-            self.execute_transactions_sequential(
+            let ret = self.execute_transactions_sequential(
                 executor_arguments,
                 &signature_verified_block,
                 base_view,
-            )
+            );
+            (ret, None)
         };
 
         if matches!(ret, Err(Error::ModulePathReadWrite)) {
@@ -409,6 +708,6 @@ where
             drop(signature_verified_block);
         });
 
-        ret
+        (ret, report)
     }
 }
@@ -139,10 +139,12 @@ pub mod counters;
 pub mod errors;
 pub mod executor;
 pub mod output_delta_resolver;
+pub mod preordering;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod proptest_types;
 mod scheduler;
 pub mod task;
+pub mod telemetry;
 mod txn_last_input_output;
 #[cfg(test)]
 mod unit_tests;
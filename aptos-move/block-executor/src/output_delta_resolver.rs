@@ -24,6 +24,16 @@ impl<T: Transaction> OutputDeltaResolver<T> {
         base_view: &impl TStateView<Key = T::Key>,
         block_size: usize,
     ) -> Vec<Vec<(T::Key, WriteOp)>> {
+        self.try_resolve(base_view, block_size)
+            .expect("Failed to apply aggregator delta output")
+    }
+
+    /// Same as `resolve`, but returns None if a delta can't be applied instead of panicking.
+    pub(crate) fn try_resolve(
+        self,
+        base_view: &impl TStateView<Key = T::Key>,
+        block_size: usize,
+    ) -> Option<Vec<Vec<(T::Key, WriteOp)>>> {
         let mut ret: Vec<Vec<(T::Key, WriteOp)>> = vec![vec![]; block_size];
 
         // TODO: with more deltas, re-use executor threads and process in parallel.
@@ -44,12 +54,7 @@ impl<T: Transaction> OutputDeltaResolver<T> {
                     },
                     EntryCell::Delta(delta) => {
                         // Apply to the latest value and store in outputs.
-                        let aggregator_value = delta
-                            .apply_to(
-                                latest_value
-                                    .expect("Failed to apply delta to (non-existent) aggregator"),
-                            )
-                            .expect("Failed to apply aggregator delta output");
+                        let aggregator_value = delta.apply_to(latest_value?).ok()?;
 
                         ret[*idx].push((
                             key.clone(),
@@ -63,6 +68,6 @@ impl<T: Transaction> OutputDeltaResolver<T> {
 
         RAYON_EXEC_POOL.spawn(move || drop(self));
 
-        ret
+        Some(ret)
     }
 }
@@ -0,0 +1,180 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Preordering of the transactions of a block before parallel execution.
+//!
+//! Block-STM commits transactions in the order of the block, so a chain of transactions that
+//! conflict with each other (e.g. transfers from the same hot account) gets executed and
+//! re-executed one after another while the threads have nothing else to do. Given an estimate
+//! of the keys each transaction reads and writes, `preorder` computes an order in which the
+//! conflicting transactions are spread out, so that the ones in between can be executed in
+//! parallel with them.
+//!
+//! The estimates may be wrong, so the executor checks the keys actually accessed during the
+//! execution in the new order and only keeps the result if it is identical to executing in the
+//! order of the block (see `reordering_commutes`), re-executing the block otherwise.
+
+use crate::scheduler::TxnIndex;
+use std::{cmp::max, collections::HashMap, hash::Hash};
+
+/// Estimated set of keys a transaction accesses.
+#[derive(Clone, Debug)]
+pub struct Footprint<K> {
+    pub reads: Vec<K>,
+    pub writes: Vec<K>,
+    /// Keys updated with aggregator deltas, which don't conflict with each other.
+    pub deltas: Vec<K>,
+}
+
+#[derive(Default)]
+struct KeyLevels {
+    read: Option<usize>,
+    write: Option<usize>,
+    delta: Option<usize>,
+}
+
+fn after(level: Option<usize>) -> usize {
+    level.map_or(0, |level| level + 1)
+}
+
+/// Returns a deterministic order (a permutation of the transaction indices) for the transactions
+/// with the given footprints.
+///
+/// Each transaction is assigned a level: one more than the highest level of the earlier
+/// transactions it conflicts with. Transactions are then ordered by level, and by index within
+/// a level, so the relative order of any two conflicting transactions is preserved. A
+/// transaction without a footprint is a barrier: it is ordered after all the transactions
+/// before it, and before all the transactions after it.
+pub fn preorder<K: Hash + Eq>(footprints: &[Option<Footprint<K>>]) -> Vec<TxnIndex> {
+    let mut levels_by_key: HashMap<&K, KeyLevels> = HashMap::new();
+    let mut levels = Vec::with_capacity(footprints.len());
+    let mut floor = 0;
+    let mut max_level = 0;
+
+    for footprint in footprints {
+        let level = match footprint {
+            None => {
+                let level = if levels.is_empty() {
+                    floor
+                } else {
+                    max(floor, max_level + 1)
+                };
+                floor = level + 1;
+                level
+            },
+            Some(footprint) => {
+                let mut level = floor;
+                for key in &footprint.reads {
+                    if let Some(key_levels) = levels_by_key.get(key) {
+                        level = max(level, max(after(key_levels.write), after(key_levels.delta)));
+                    }
+                }
+                for key in &footprint.writes {
+                    if let Some(key_levels) = levels_by_key.get(key) {
+                        level = max(
+                            level,
+                            max(
+                                after(key_levels.read),
+                                max(after(key_levels.write), after(key_levels.delta)),
+                            ),
+                        );
+                    }
+                }
+                for key in &footprint.deltas {
+                    if let Some(key_levels) = levels_by_key.get(key) {
+                        level = max(level, max(after(key_levels.read), after(key_levels.write)));
+                    }
+                }
+
+                for key in &footprint.reads {
+                    let key_levels = levels_by_key.entry(key).or_default();
+                    key_levels.read = max(key_levels.read, Some(level));
+                }
+                for key in &footprint.writes {
+                    let key_levels = levels_by_key.entry(key).or_default();
+                    key_levels.write = max(key_levels.write, Some(level));
+                }
+                for key in &footprint.deltas {
+                    let key_levels = levels_by_key.entry(key).or_default();
+                    key_levels.delta = max(key_levels.delta, Some(level));
+                }
+                level
+            },
+        };
+        max_level = max(max_level, level);
+        levels.push(level);
+    }
+
+    let mut order: Vec<TxnIndex> = (0..footprints.len()).collect();
+    order.sort_by_key(|idx| (levels[*idx], *idx));
+    order
+}
+
+/// Moves the element at index `order[i]` of `items` to index `i`.
+pub(crate) fn permute<T>(items: Vec<T>, order: &[TxnIndex]) -> Vec<T> {
+    let mut items: Vec<Option<T>> = items.into_iter().map(Some).collect();
+    order
+        .iter()
+        .map(|idx| items[*idx].take().expect("Order must be a permutation"))
+        .collect()
+}
+
+/// Moves the element at index `i` of `items` back to index `order[i]`.
+pub(crate) fn unpermute<T>(items: Vec<T>, order: &[TxnIndex]) -> Vec<T> {
+    let mut ret: Vec<Option<T>> = (0..items.len()).map(|_| None).collect();
+    for (item, idx) in items.into_iter().zip(order) {
+        ret[*idx] = Some(item);
+    }
+    ret.into_iter()
+        .map(|item| item.expect("Order must be a permutation"))
+        .collect()
+}
+
+/// Returns whether executing the transactions in `order` is equivalent to executing them in
+/// the order of the block, given the keys they actually accessed (`footprints[i]` being the
+/// footprint of the transaction at position `i`, i.e. of `order[i]`).
+///
+/// That is the case if every pair of conflicting transactions was executed in the order of the
+/// block: all the transactions then read the same values as they would have, and so behave
+/// the same.
+pub(crate) fn reordering_commutes<K: Hash + Eq>(
+    order: &[TxnIndex],
+    footprints: &[Footprint<K>],
+) -> bool {
+    // For each key, the highest index of an earlier executed transaction that accessed it.
+    let mut last_reader: HashMap<&K, TxnIndex> = HashMap::new();
+    let mut last_writer: HashMap<&K, TxnIndex> = HashMap::new();
+    let mut last_delta: HashMap<&K, TxnIndex> = HashMap::new();
+
+    for (idx, footprint) in order.iter().zip(footprints) {
+        let later = |accesses: &HashMap<&K, TxnIndex>, key: &K| {
+            accesses.get(key).map_or(false, |other| other > idx)
+        };
+        if footprint
+            .reads
+            .iter()
+            .any(|key| later(&last_writer, key) || later(&last_delta, key))
+            || footprint.writes.iter().any(|key| {
+                later(&last_reader, key) || later(&last_writer, key) || later(&last_delta, key)
+            })
+            || footprint
+                .deltas
+                .iter()
+                .any(|key| later(&last_reader, key) || later(&last_writer, key))
+        {
+            return false;
+        }
+
+        for (accesses, keys) in [
+            (&mut last_reader, &footprint.reads),
+            (&mut last_writer, &footprint.writes),
+            (&mut last_delta, &footprint.deltas),
+        ] {
+            for key in keys {
+                let last = accesses.entry(key).or_insert(*idx);
+                *last = max(*last, *idx);
+            }
+        }
+    }
+    true
+}
@@ -220,6 +220,12 @@ impl Scheduler {
         }
     }
 
+    /// Returns the current validation wave, i.e. how many times the validation index was
+    /// decreased so far.
+    pub fn validation_wave(&self) -> Wave {
+        Self::unpack_validation_idx(self.validation_idx.load(Ordering::SeqCst)).1
+    }
+
     /// If successful, returns Some(TxnIndex), the index of committed transaction.
     /// The current implementation has one dedicated thread to try_commit.
     pub fn try_commit(&self) -> Option<TxnIndex> {
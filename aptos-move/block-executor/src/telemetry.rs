@@ -0,0 +1,135 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters::{
+        SPECULATIVE_DEPENDENCY_COUNT, TXN_EXECUTIONS_PER_BLOCK_TXN, VALIDATION_WAVES_PER_BLOCK,
+    },
+    scheduler::{TxnIndex, Wave},
+};
+use dashmap::DashMap;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Summary of the parallel execution of a block: how much work was redone because of conflicts
+/// between transactions, and which keys caused them.
+#[derive(Clone, Debug)]
+pub struct BlockExecutionReport<K> {
+    /// Number of incarnations of each transaction, i.e. 1 + the number of its re-executions.
+    pub incarnations_per_txn: Vec<usize>,
+    /// Number of times each transaction failed validation and was aborted.
+    pub aborts_per_txn: Vec<usize>,
+    /// Number of times the validation index was decreased, which starts a new wave of
+    /// validations of all the transactions after it.
+    pub num_validation_waves: Wave,
+    /// Number of reads that had to wait on an earlier transaction to finish re-executing.
+    pub num_dependencies: usize,
+    /// Keys that failed a validation or produced a dependency, with the number of times they
+    /// did, most conflicting first.
+    pub conflicting_keys: Vec<(K, usize)>,
+}
+
+impl<K> BlockExecutionReport<K> {
+    pub fn num_txns(&self) -> usize {
+        self.incarnations_per_txn.len()
+    }
+
+    pub fn num_reexecutions(&self) -> usize {
+        self.incarnations_per_txn
+            .iter()
+            .map(|incarnations| incarnations.saturating_sub(1))
+            .sum()
+    }
+
+    pub fn num_aborts(&self) -> usize {
+        self.aborts_per_txn.iter().sum()
+    }
+
+    /// Aggregates the conflicting keys by `group` (e.g. by resource type), most conflicting
+    /// group first.
+    pub fn conflicts_by<G, F>(&self, group: F) -> Vec<(G, usize)>
+    where
+        G: Hash + Eq,
+        F: Fn(&K) -> G,
+    {
+        let mut conflicts = HashMap::new();
+        for (key, count) in &self.conflicting_keys {
+            *conflicts.entry(group(key)).or_insert(0) += count;
+        }
+        let mut conflicts: Vec<_> = conflicts.into_iter().collect();
+        conflicts.sort_by(|(_, a), (_, b)| b.cmp(a));
+        conflicts
+    }
+
+    /// Exports the report to the block executor metrics.
+    pub fn observe(&self) {
+        for incarnations in &self.incarnations_per_txn {
+            TXN_EXECUTIONS_PER_BLOCK_TXN.observe(*incarnations as f64);
+        }
+        VALIDATION_WAVES_PER_BLOCK.observe(self.num_validation_waves as f64);
+        SPECULATIVE_DEPENDENCY_COUNT.inc_by(self.num_dependencies as u64);
+    }
+}
+
+/// Collects the data for a `BlockExecutionReport` from all the worker threads executing a block.
+pub(crate) struct ConflictTracker<K> {
+    incarnations: Vec<AtomicUsize>,
+    aborts: Vec<AtomicUsize>,
+    num_dependencies: AtomicUsize,
+    conflicting_keys: DashMap<K, usize>,
+}
+
+impl<K: Hash + Eq + Clone> ConflictTracker<K> {
+    pub(crate) fn new(num_txns: usize) -> Self {
+        Self {
+            incarnations: (0..num_txns).map(|_| AtomicUsize::new(0)).collect(),
+            aborts: (0..num_txns).map(|_| AtomicUsize::new(0)).collect(),
+            num_dependencies: AtomicUsize::new(0),
+            conflicting_keys: DashMap::new(),
+        }
+    }
+
+    pub(crate) fn record_execution(&self, txn_idx: TxnIndex) {
+        self.incarnations[txn_idx].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that `txn_idx` was aborted because its read of `key` was invalidated.
+    pub(crate) fn record_abort(&self, txn_idx: TxnIndex, key: &K) {
+        self.aborts[txn_idx].fetch_add(1, Ordering::Relaxed);
+        self.record_conflict(key);
+    }
+
+    /// Records that a read of `key` hit an estimate written by an aborted transaction.
+    pub(crate) fn record_dependency(&self, key: &K) {
+        self.num_dependencies.fetch_add(1, Ordering::Relaxed);
+        self.record_conflict(key);
+    }
+
+    fn record_conflict(&self, key: &K) {
+        *self.conflicting_keys.entry(key.clone()).or_insert(0) += 1;
+    }
+
+    pub(crate) fn into_report(self, num_validation_waves: Wave) -> BlockExecutionReport<K> {
+        let mut conflicting_keys: Vec<_> = self.conflicting_keys.into_iter().collect();
+        conflicting_keys.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        BlockExecutionReport {
+            incarnations_per_txn: self
+                .incarnations
+                .into_iter()
+                .map(AtomicUsize::into_inner)
+                .collect(),
+            aborts_per_txn: self
+                .aborts
+                .into_iter()
+                .map(AtomicUsize::into_inner)
+                .collect(),
+            num_validation_waves,
+            num_dependencies: self.num_dependencies.into_inner(),
+            conflicting_keys,
+        }
+    }
+}
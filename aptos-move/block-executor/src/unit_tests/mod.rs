@@ -3,6 +3,7 @@
 
 use crate::{
     executor::BlockExecutor,
+    preordering::{preorder, Footprint},
     proptest_types::types::{DeltaDataView, ExpectedOutput, KeyType, Task, Transaction, ValueType},
     scheduler::{Scheduler, SchedulerTask},
     task::ModulePath,
@@ -691,3 +692,143 @@ fn test_rolling_commit_wave_update() {
     assert!(s.try_commit().is_none());
     assert!(matches!(s.next_task(false), SchedulerTask::Done));
 }
+
+#[test]
+fn conflict_report() {
+    let key = KeyType(random::<[u8; 32]>(), false);
+    let other_key = KeyType(random::<[u8; 32]>(), false);
+    // Every transaction reads and writes the same key, and writes a key nobody reads.
+    let transactions: Vec<_> = (0..100)
+        .map(|_| Transaction::Write {
+            incarnation: Arc::new(AtomicUsize::new(0)),
+            reads: vec![vec![key]],
+            writes_and_deltas: vec![(
+                vec![(key, random_value(false)), (other_key, random_value(false))],
+                vec![],
+            )],
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<[u8; 32]>, ValueType<Vec<u8>>> {
+        phantom: PhantomData,
+    };
+
+    let (output, report) = BlockExecutor::<
+        Transaction<KeyType<[u8; 32]>, ValueType<Vec<u8>>>,
+        Task<KeyType<[u8; 32]>, ValueType<Vec<u8>>>,
+        DeltaDataView<KeyType<[u8; 32]>, ValueType<Vec<u8>>>,
+    >::new(num_cpus::get())
+    .execute_transactions_parallel_with_report((), &transactions, &data_view);
+    let output = output.map(|zipped| zipped.into_iter().map(|(res, _)| res).collect());
+    ExpectedOutput::generate_baseline(&transactions, None).assert_output(&output);
+
+    assert_eq!(report.num_txns(), transactions.len());
+    for (incarnations, aborts) in report
+        .incarnations_per_txn
+        .iter()
+        .zip(report.aborts_per_txn.iter())
+    {
+        assert!(*incarnations >= 1);
+        assert!(*aborts < *incarnations);
+    }
+    assert!(report.num_aborts() <= report.num_reexecutions());
+    // Only the key that is read can cause conflicts.
+    assert!(report.conflicting_keys.iter().all(|(k, _)| *k == key));
+}
+
+#[test]
+fn preorder_levels() {
+    let footprint = |reads: Vec<u8>, writes: Vec<u8>, deltas: Vec<u8>| {
+        Some(Footprint {
+            reads,
+            writes,
+            deltas,
+        })
+    };
+    let footprints = vec![
+        footprint(vec![0], vec![0], vec![9]),
+        footprint(vec![0], vec![0], vec![9]),
+        footprint(vec![1], vec![1], vec![9]),
+        footprint(vec![1], vec![1], vec![9]),
+        footprint(vec![2], vec![], vec![]),
+        footprint(vec![9], vec![], vec![]),
+        // Barrier.
+        None,
+        footprint(vec![3], vec![3], vec![]),
+    ];
+
+    // Deltas to the same key don't conflict, but a read of it does.
+    assert_eq!(preorder(&footprints), vec![0, 2, 4, 1, 3, 5, 6, 7]);
+}
+
+fn run_preordered(
+    transactions: Vec<Transaction<KeyType<[u8; 32]>, ValueType<Vec<u8>>>>,
+    order: Vec<usize>,
+) {
+    let data_view = DeltaDataView::<KeyType<[u8; 32]>, ValueType<Vec<u8>>> {
+        phantom: PhantomData,
+    };
+
+    let (output, _) = BlockExecutor::<
+        Transaction<KeyType<[u8; 32]>, ValueType<Vec<u8>>>,
+        Task<KeyType<[u8; 32]>, ValueType<Vec<u8>>>,
+        DeltaDataView<KeyType<[u8; 32]>, ValueType<Vec<u8>>>,
+    >::new(num_cpus::get())
+    .execute_block_in_order((), transactions.clone(), &data_view, order, false);
+    let (output, resolved) = output.unwrap().into_iter().unzip();
+
+    ExpectedOutput::generate_baseline(&transactions, Some(resolved)).assert_output(&Ok(output));
+}
+
+// Two chains of transactions, each reading and writing its own key, and all updating the
+// same aggregator.
+fn two_chains() -> Vec<Transaction<KeyType<[u8; 32]>, ValueType<Vec<u8>>>> {
+    let keys = [
+        KeyType(random::<[u8; 32]>(), false),
+        KeyType(random::<[u8; 32]>(), false),
+    ];
+    let aggregator_key = KeyType(random::<[u8; 32]>(), false);
+    keys.iter()
+        .flat_map(|key| {
+            (0..50).map(move |_| Transaction::Write {
+                incarnation: Arc::new(AtomicUsize::new(0)),
+                reads: vec![vec![*key]],
+                writes_and_deltas: vec![(vec![(*key, random_value(false))], vec![(
+                    aggregator_key,
+                    delta_add(5, u128::MAX),
+                )])],
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn preordered_execution() {
+    let transactions = two_chains();
+    let data_view = DeltaDataView::<KeyType<[u8; 32]>, ValueType<Vec<u8>>> {
+        phantom: PhantomData,
+    };
+    let executor = BlockExecutor::<
+        Transaction<KeyType<[u8; 32]>, ValueType<Vec<u8>>>,
+        Task<KeyType<[u8; 32]>, ValueType<Vec<u8>>>,
+        DeltaDataView<KeyType<[u8; 32]>, ValueType<Vec<u8>>>,
+    >::new(num_cpus::get());
+
+    let footprints = executor.speculative_footprints((), &transactions, &data_view);
+    let order = preorder(&footprints);
+    // The two chains are interleaved.
+    assert_eq!(
+        order,
+        (0..50).flat_map(|idx| [idx, idx + 50]).collect::<Vec<_>>()
+    );
+
+    run_preordered(transactions, order);
+}
+
+#[test]
+fn preordered_execution_fallback() {
+    // An order that doesn't preserve the chains must produce the same result as the block order.
+    let transactions = two_chains();
+    let order = (0..transactions.len()).rev().collect();
+
+    run_preordered(transactions, order);
+}
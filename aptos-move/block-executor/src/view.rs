@@ -5,6 +5,7 @@ use crate::{
     counters,
     scheduler::{Scheduler, TxnIndex},
     task::{ModulePath, Transaction},
+    telemetry::ConflictTracker,
     txn_last_input_output::ReadDescriptor,
 };
 use anyhow::Result;
@@ -32,6 +33,7 @@ pub type ResolvedData = Option<Vec<u8>>;
 pub(crate) struct MVHashMapView<'a, K, V> {
     versioned_map: &'a MVHashMap<K, V>,
     scheduler: &'a Scheduler,
+    conflict_tracker: Option<&'a ConflictTracker<K>>,
     captured_reads: RefCell<Vec<ReadDescriptor<K>>>,
 }
 
@@ -55,10 +57,15 @@ impl<
         V: TransactionWrite + Send + Sync,
     > MVHashMapView<'a, K, V>
 {
-    pub(crate) fn new(versioned_map: &'a MVHashMap<K, V>, scheduler: &'a Scheduler) -> Self {
+    pub(crate) fn new(
+        versioned_map: &'a MVHashMap<K, V>,
+        scheduler: &'a Scheduler,
+        conflict_tracker: Option<&'a ConflictTracker<K>>,
+    ) -> Self {
         Self {
             versioned_map,
             scheduler,
+            conflict_tracker,
             captured_reads: RefCell::new(Vec::new()),
         }
     }
@@ -101,6 +108,9 @@ impl<
                     return ReadResult::Unresolved(delta);
                 },
                 Err(Dependency(dep_idx)) => {
+                    if let Some(conflict_tracker) = self.conflict_tracker {
+                        conflict_tracker.record_dependency(key);
+                    }
                     // `self.txn_idx` estimated to depend on a write from `dep_idx`.
                     match self.scheduler.wait_for_dependency(txn_idx, dep_idx) {
                         Some(dep_condition) => {
@@ -349,7 +349,10 @@ pub fn setup_environment_and_start_node(
     logger_filter_update_job: Option<LoggerFilterUpdater>,
 ) -> anyhow::Result<AptosHandle> {
     // Start the node inspection service
-    services::start_node_inspection_service(&node_config);
+    let logger = logger_filter_update_job
+        .as_ref()
+        .map(LoggerFilterUpdater::logger);
+    services::start_node_inspection_service(&node_config, logger);
 
     // Set up the storage database and any RocksDB checkpoints
     let (aptos_db, db_rw, backup_service, genesis_waypoint) =
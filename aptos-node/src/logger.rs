@@ -40,12 +40,16 @@ pub fn create_logger(
         .level(node_config.logger.level)
         .telemetry_level(node_config.logger.telemetry_level)
         .enable_telemetry_flush(node_config.logger.enable_telemetry_flush)
-        .console_port(node_config.logger.console_port);
+        .console_port(node_config.logger.console_port)
+        .span_export(node_config.logger.span_export.clone());
     if node_config.logger.enable_backtrace {
         logger_builder.enable_backtrace();
     }
-    if let Some(log_file) = log_file {
-        logger_builder.printer(Box::new(FileWriter::new(log_file)));
+    if let Some(log_file) = log_file.or_else(|| node_config.logger.log_file.clone()) {
+        logger_builder.printer(Box::new(FileWriter::with_rotation(
+            log_file,
+            node_config.logger.log_rotation.clone(),
+        )));
     }
     if node_config.logger.enable_telemetry_remote_log {
         let (tx, rx) = mpsc::channel(TELEMETRY_LOG_INGEST_BUFFER_SIZE);
@@ -7,7 +7,7 @@ use aptos_config::config::NodeConfig;
 use aptos_consensus::network_interface::ConsensusMsg;
 use aptos_consensus_notifications::ConsensusNotifier;
 use aptos_event_notifications::ReconfigNotificationListener;
-use aptos_logger::{debug, telemetry_log_writer::TelemetryLog, LoggerFilterUpdater};
+use aptos_logger::{debug, telemetry_log_writer::TelemetryLog, Logger, LoggerFilterUpdater};
 use aptos_mempool::{network::MempoolSyncMsg, MempoolClientRequest, QuorumStoreRequest};
 use aptos_mempool_notifications::MempoolNotificationListener;
 use aptos_storage_interface::{DbReader, DbReaderWriter};
@@ -108,10 +108,10 @@ pub fn start_mempool_runtime_and_get_consensus_sender(
 }
 
 /// Spawns a new thread for the node inspection service
-pub fn start_node_inspection_service(node_config: &NodeConfig) {
+pub fn start_node_inspection_service(node_config: &NodeConfig, logger: Option<Arc<Logger>>) {
     let node_config = node_config.clone();
     thread::spawn(move || {
-        aptos_inspection_service::inspection_service::start_inspection_service(node_config)
+        aptos_inspection_service::inspection_service::start_inspection_service(node_config, logger)
     });
 }
 
@@ -1,8 +1,8 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::network;
-use aptos_config::config::{NodeConfig, WaypointConfig};
+use crate::{network, utils};
+use aptos_config::config::{ExecutionConfig, NodeConfig, PersistableConfig, WaypointConfig};
 use aptos_event_notifications::EventSubscriptionService;
 use aptos_infallible::RwLock;
 use aptos_storage_interface::{DbReader, DbReaderWriter, DbWriter};
@@ -10,6 +10,7 @@ use aptos_temppath::TempPath;
 use aptos_types::{
     chain_id::ChainId, on_chain_config::ON_CHAIN_CONFIG_REGISTRY, waypoint::Waypoint,
 };
+use aptos_vm::{block_executor::BlockPreordering, AptosVM};
 use std::sync::Arc;
 
 /// A mock database implementing DbReader and DbWriter
@@ -44,6 +45,20 @@ fn test_mutual_authentication_validators() {
     );
 }
 
+#[test]
+fn test_block_preordering_from_config() {
+    // Enable speculative preordering in the execution config
+    let mut node_config = NodeConfig::default();
+    node_config.execution = ExecutionConfig::parse("block_preordering: speculative").unwrap();
+
+    // Apply the config to the VM and verify blocks are preordered speculatively
+    utils::set_aptos_vm_configurations(&node_config);
+    assert!(matches!(
+        AptosVM::get_block_preordering(),
+        Some(BlockPreordering::Speculative)
+    ));
+}
+
 #[cfg(feature = "check-vm-features")]
 #[test]
 fn test_aptos_vm_does_not_have_test_natives() {
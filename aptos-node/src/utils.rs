@@ -2,13 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::anyhow;
-use aptos_config::config::NodeConfig;
+use aptos_config::config::{BlockPreorderingConfig, NodeConfig};
 use aptos_state_view::account_with_state_view::AsAccountWithStateView;
 use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReaderWriter};
 use aptos_types::{
     account_config::CORE_CODE_ADDRESS, account_view::AccountView, chain_id::ChainId,
 };
-use aptos_vm::AptosVM;
+use aptos_vm::{block_executor::BlockPreordering, AptosVM};
 
 /// Error message to display when non-production features are enabled
 pub const ERROR_MSG_BAD_FEATURE_FLAGS: &str = r#"
@@ -60,4 +60,13 @@ pub fn set_aptos_vm_configurations(node_config: &NodeConfig) {
     {
         AptosVM::set_processed_transactions_detailed_counters();
     }
+    if node_config.execution.block_execution_reports {
+        AptosVM::set_block_execution_reports();
+    }
+    match node_config.execution.block_preordering {
+        BlockPreorderingConfig::Disabled => {},
+        BlockPreorderingConfig::Speculative => {
+            AptosVM::set_block_preordering(BlockPreordering::Speculative)
+        },
+    }
 }
@@ -23,6 +23,27 @@ pub struct ExecutionConfig {
     pub paranoid_type_verification: bool,
     pub paranoid_hot_potato_verification: bool,
     pub processed_transactions_detailed_counters: bool,
+    /// Whether to report the conflicts between transactions in parallel execution to metrics.
+    pub block_execution_reports: bool,
+    /// How to preorder the transactions of a block before parallel execution.
+    pub block_preordering: BlockPreorderingConfig,
+}
+
+/// The preorderings that can be configured. The static read/write set analysis needs the
+/// normalized analysis of the framework, so it can only be set programmatically.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockPreorderingConfig {
+    /// Execute the block in its own order.
+    Disabled,
+    /// Estimate the footprints by executing each transaction alone on the pre-block state.
+    Speculative,
+}
+
+impl Default for BlockPreorderingConfig {
+    fn default() -> Self {
+        BlockPreorderingConfig::Disabled
+    }
 }
 
 impl std::fmt::Debug for ExecutionConfig {
@@ -52,6 +73,8 @@ impl Default for ExecutionConfig {
             paranoid_type_verification: true,
             paranoid_hot_potato_verification: true,
             processed_transactions_detailed_counters: false,
+            block_execution_reports: false,
+            block_preordering: BlockPreorderingConfig::Disabled,
         }
     }
 }
@@ -2,11 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 pub const DEFAULT_BATCH_SIZE: u16 = 500;
 pub const DEFAULT_FETCH_TASKS: u8 = 5;
 pub const DEFAULT_PROCESSOR_TASKS: u8 = 5;
 pub const DEFAULT_EMIT_EVERY: u64 = 1000;
+pub const DEFAULT_BACKFILL_SHARDS: u16 = 8;
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -69,6 +71,60 @@ pub struct IndexerConfig {
     /// Which address does the ans contract live at. Only available for token_processor. If null, disable ANS indexing
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub ans_contract_address: Option<String>,
+
+    /// Path to the YAML file defining the tables to index. Only available for custom_processor.
+    /// Alternatively can set the `CUSTOM_PROCESSOR_CONFIG` env var
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_processor_config: Option<PathBuf>,
+
+    /// If set, the versions before the starting version are backfilled in the background from
+    /// the tables of the default_processor. Only available for account_transactions_processor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backfill_account_transactions: Option<bool>,
+
+    /// Outputs to write the processed rows to in addition to Postgres, which is still required
+    /// as it tracks the progress of the processors. Configs with sinks are rejected for the
+    /// custom_processor, with backfill_account_transactions and with backfill.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sinks: Vec<IndexerSinkConfig>,
+
+    /// If set, backfills a range of versions in parallel shards and exits, instead of indexing
+    /// the chain as it grows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backfill: Option<IndexerBackfillConfig>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndexerBackfillConfig {
+    /// First version to backfill
+    pub start_version: u64,
+
+    /// Version to stop before
+    pub end_version: u64,
+
+    /// How many shards to split the range into, each with its own fetcher and processor
+    pub num_shards: u16,
+
+    /// Database to backfill, if not `postgres_uri`, e.g. to verify the backfill before switching
+    /// to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub postgres_uri: Option<String>,
+
+    /// How many versions at the end of the range to compare with the rows the live processor
+    /// wrote to `postgres_uri`. Requires a separate backfill database. Set to 0 to disable.
+    pub verify_versions: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IndexerSinkConfig {
+    /// Newline-delimited JSON files in the given directory
+    JsonLines { path: PathBuf },
+    /// Parquet files in the given directory
+    Parquet { path: PathBuf },
+    /// A SQLite database at the given path
+    Sqlite { path: PathBuf },
 }
 
 pub fn env_or_default<T: std::str::FromStr>(
@@ -1,16 +1,20 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::utils;
+use crate::{config::Token, utils};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[derive(Clone, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct InspectionServiceConfig {
     pub address: String,
     pub port: u16,
     pub expose_configuration: bool,
+    pub expose_consensus_state: bool,
     pub expose_system_information: bool,
+    // Bearer token required by the admin endpoints, which are disabled when not set
+    pub admin_auth_token: Option<Token>,
 }
 
 impl Default for InspectionServiceConfig {
@@ -19,11 +23,31 @@ impl Default for InspectionServiceConfig {
             address: "0.0.0.0".to_string(),
             port: 9101,
             expose_configuration: false,
+            expose_consensus_state: false,
             expose_system_information: true,
+            admin_auth_token: None,
         }
     }
 }
 
+// The node config is exposed by the unauthenticated /configuration endpoint using debug
+// formatting, so the admin auth token is kept out of it.
+impl fmt::Debug for InspectionServiceConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InspectionServiceConfig")
+            .field("address", &self.address)
+            .field("port", &self.port)
+            .field("expose_configuration", &self.expose_configuration)
+            .field("expose_consensus_state", &self.expose_consensus_state)
+            .field("expose_system_information", &self.expose_system_information)
+            .field(
+                "admin_auth_token",
+                &self.admin_auth_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
 impl InspectionServiceConfig {
     pub fn randomize_ports(&mut self) {
         self.port = utils::get_available_port();
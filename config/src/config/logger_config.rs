@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::utils;
-use aptos_logger::{Level, CHANNEL_SIZE};
+use aptos_logger::{
+    log_rotation::LogRotationConfig, span_export::SpanExportConfig, Level, CHANNEL_SIZE,
+};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -21,6 +24,12 @@ pub struct LoggerConfig {
     pub enable_telemetry_remote_log: bool,
     pub enable_telemetry_flush: bool,
     pub telemetry_level: Level,
+    // Exports transaction lifecycle spans to an OpenTelemetry collector or file
+    pub span_export: Option<SpanExportConfig>,
+    // Writes the logs to this file instead of stdout
+    pub log_file: Option<PathBuf>,
+    // Rotates the log file, instead of appending to it forever
+    pub log_rotation: Option<LogRotationConfig>,
 }
 
 impl Default for LoggerConfig {
@@ -37,6 +46,9 @@ impl Default for LoggerConfig {
             enable_telemetry_remote_log: true,
             enable_telemetry_flush: true,
             telemetry_level: Level::Error,
+            span_export: None,
+            log_file: None,
+            log_rotation: None,
         }
     }
 }
@@ -343,6 +343,16 @@ impl NodeConfig {
             None,
         );
 
+        self.indexer.custom_processor_config = std::env::var("CUSTOM_PROCESSOR_CONFIG")
+            .ok()
+            .map(PathBuf::from)
+            .or(self.indexer.custom_processor_config);
+        invariant(
+            self.indexer.processor.as_deref() != Some("custom_processor")
+                || self.indexer.custom_processor_config.is_some(),
+            "Either 'config.indexer.custom_processor_config' or 'CUSTOM_PROCESSOR_CONFIG' must be set for the custom_processor!".into(),
+        )?;
+
         self.indexer.starting_version = match std::env::var("STARTING_VERSION").ok() {
             None => self.indexer.starting_version,
             Some(s) => match s.parse::<u64>() {
@@ -374,6 +384,43 @@ impl NodeConfig {
         self.indexer.processor_tasks =
             default_if_zero_u8(self.indexer.processor_tasks, DEFAULT_PROCESSOR_TASKS);
         self.indexer.emit_every = self.indexer.emit_every.or(Some(0));
+        if let Some(backfill) = &mut self.indexer.backfill {
+            if backfill.num_shards == 0 {
+                backfill.num_shards = DEFAULT_BACKFILL_SHARDS;
+            }
+            invariant(
+                backfill.start_version < backfill.end_version,
+                "The indexer backfill start_version must be less than its end_version".into(),
+            )?;
+            invariant(
+                backfill.verify_versions <= backfill.end_version - backfill.start_version,
+                "The indexer backfill verify_versions must be within the backfilled range".into(),
+            )?;
+            invariant(
+                backfill.verify_versions == 0
+                    || backfill
+                        .postgres_uri
+                        .as_ref()
+                        .map_or(false, |uri| Some(uri) != self.indexer.postgres_uri.as_ref()),
+                "Verifying the indexer backfill requires a separate backfill postgres_uri".into(),
+            )?;
+        }
+        // Postgres still tracks the progress of the processors, and the rows these modes produce
+        // never reach the sinks
+        if !self.indexer.sinks.is_empty() {
+            invariant(
+                self.indexer.processor.as_deref() != Some("custom_processor"),
+                "The indexer sinks aren't supported by the custom_processor".into(),
+            )?;
+            invariant(
+                !self.indexer.backfill_account_transactions.unwrap_or(false),
+                "The indexer sinks can't be used with backfill_account_transactions".into(),
+            )?;
+            invariant(
+                self.indexer.backfill.is_none(),
+                "The indexer sinks can't be used with the indexer backfill".into(),
+            )?;
+        }
         self.indexer.gap_lookback_versions = env_or_default(
             "GAP_LOOKBACK_VERSIONS",
             self.indexer.gap_lookback_versions.or(Some(1_500_000)),
@@ -607,6 +654,35 @@ mod test {
             .unwrap_or_else(|e| panic!("Error in safety_rules.yaml: {}", e));
     }
 
+    #[test]
+    fn validate_indexer_sinks() {
+        let mut config = NodeConfig::default_for_public_full_node();
+        config.indexer.enabled = true;
+        config.indexer.postgres_uri = Some("postgresql://localhost/indexer".to_string());
+        config.indexer.processor = Some("coin_processor".to_string());
+        config.indexer.sinks = vec![IndexerSinkConfig::Parquet {
+            path: PathBuf::from("/opt/aptos/indexer-parquet"),
+        }];
+        assert!(config.clone().validate_indexer_configs().is_ok());
+
+        let mut custom_processor = config.clone();
+        custom_processor.indexer.processor = Some("custom_processor".to_string());
+        custom_processor.indexer.custom_processor_config = Some(PathBuf::from("custom.yaml"));
+        assert!(matches!(
+            custom_processor.validate_indexer_configs(),
+            Err(Error::InvariantViolation(_))
+        ));
+
+        let mut backfill_account_transactions = config;
+        backfill_account_transactions
+            .indexer
+            .backfill_account_transactions = Some(true);
+        assert!(matches!(
+            backfill_account_transactions.validate_indexer_configs(),
+            Err(Error::InvariantViolation(_))
+        ));
+    }
+
     #[test]
     fn validate_invalid_network_id() {
         let mut config = NodeConfig::default_for_public_full_node();
@@ -1,6 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+use aptos_compression::CompressionCodec;
 use serde::{Deserialize, Serialize};
 
 // The maximum message size per state sync message
@@ -105,14 +106,35 @@ impl Default for StateSyncDriverConfig {
 pub struct StorageServiceConfig {
     pub max_concurrent_requests: u64, // Max num of concurrent storage server tasks
     pub max_epoch_chunk_size: u64,    // Max num of epoch ending ledger infos per chunk
-    pub max_lru_cache_size: u64,      // Max num of items in the lru cache before eviction
-    pub max_network_channel_size: u64, // Max num of pending network messages
-    pub max_network_chunk_bytes: u64, // Max num of bytes to send per network message
-    pub max_state_chunk_size: u64,    // Max num of state keys and values per chunk
-    pub max_subscription_period_ms: u64, // Max period (ms) of pending subscription requests
-    pub max_transaction_chunk_size: u64, // Max num of transactions per chunk
+    pub max_lz4_high_compression_level: i32, // Max LZ4 high compression level supported (0 to disable)
+    pub max_lru_cache_size: u64,             // Max num of items in the lru cache before eviction
+    pub max_network_channel_size: u64,       // Max num of pending network messages
+    pub max_network_chunk_bytes: u64,        // Max num of bytes to send per network message
+    pub max_state_chunk_size: u64,           // Max num of state keys and values per chunk
+    pub max_state_value_dictionary_bytes: u64, // Max size of the Zstd dictionary trained on state values (0 to disable)
+    pub max_subscription_period_ms: u64,       // Max period (ms) of pending subscription requests
+    pub max_transaction_chunk_size: u64,       // Max num of transactions per chunk
     pub max_transaction_output_chunk_size: u64, // Max num of transaction outputs per chunk
+    pub max_zstd_compression_level: i32, // Max Zstd compression level supported (0 to disable)
     pub storage_summary_refresh_interval_ms: u64, // The interval (ms) to refresh the storage summary
+    pub target_network_chunk_bytes: u64, // Target num of bytes per network message (chunk sizes adapt to this)
+}
+
+impl StorageServiceConfig {
+    /// Returns the compression codecs supported by the storage service.
+    /// The levels of the codecs are the max levels supported.
+    pub fn supported_compression_codecs(&self) -> Vec<CompressionCodec> {
+        let mut compression_codecs = vec![CompressionCodec::Lz4];
+        if self.max_lz4_high_compression_level > 0 {
+            compression_codecs.push(CompressionCodec::Lz4HighCompression(
+                self.max_lz4_high_compression_level,
+            ));
+        }
+        if self.max_zstd_compression_level > 0 {
+            compression_codecs.push(CompressionCodec::Zstd(self.max_zstd_compression_level));
+        }
+        compression_codecs
+    }
 }
 
 impl Default for StorageServiceConfig {
@@ -120,14 +142,18 @@ impl Default for StorageServiceConfig {
         Self {
             max_concurrent_requests: 4000,
             max_epoch_chunk_size: 200,
+            max_lz4_high_compression_level: 9,
             max_lru_cache_size: 500, // At ~0.6MiB per chunk, this should take no more than 0.5GiB
             max_network_channel_size: 4000,
             max_network_chunk_bytes: MAX_MESSAGE_SIZE as u64,
             max_state_chunk_size: 4000,
+            max_state_value_dictionary_bytes: 64 * 1024, // 64 KiB
             max_subscription_period_ms: 5000,
             max_transaction_chunk_size: 2000,
             max_transaction_output_chunk_size: 1000,
+            max_zstd_compression_level: 9,
             storage_summary_refresh_interval_ms: 50,
+            target_network_chunk_bytes: 10 * 1024 * 1024, // 10 MiB
         }
     }
 }
@@ -178,19 +204,22 @@ impl Default for DataStreamingServiceConfig {
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct AptosDataClientConfig {
+    pub compression_codec: CompressionCodec, // The codec to request for compressed data (if supported by the peer)
     pub max_num_in_flight_priority_polls: u64, // Max num of in-flight polls for priority peers
-    pub max_num_in_flight_regular_polls: u64,  // Max num of in-flight polls for regular peers
+    pub max_num_in_flight_regular_polls: u64, // Max num of in-flight polls for regular peers
     pub max_num_output_reductions: u64, // The max num of output reductions before transactions are returned
     pub max_response_timeout_ms: u64, // Max timeout (in ms) when waiting for a response (after exponential increases)
     pub response_timeout_ms: u64,     // First timeout (in ms) when waiting for a response
     pub subscription_timeout_ms: u64, // Timeout (in ms) when waiting for a subscription response
     pub summary_poll_interval_ms: u64, // Interval (in ms) between data summary polls
     pub use_compression: bool,        // Whether or not to request compression for incoming data
+    pub use_compression_dictionaries: bool, // Whether or not to use Zstd dictionaries (if offered by the peer) for state values
 }
 
 impl Default for AptosDataClientConfig {
     fn default() -> Self {
         Self {
+            compression_codec: CompressionCodec::Lz4,
             max_num_in_flight_priority_polls: 10,
             max_num_in_flight_regular_polls: 10,
             max_num_output_reductions: 0,
@@ -199,6 +228,7 @@ impl Default for AptosDataClientConfig {
             subscription_timeout_ms: 5000,  // 5 seconds
             summary_poll_interval_ms: 200,
             use_compression: true,
+            use_compression_dictionaries: false,
         }
     }
 }
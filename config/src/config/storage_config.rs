@@ -104,6 +104,8 @@ pub const NO_OP_STORAGE_PRUNER_CONFIG: PrunerConfig = PrunerConfig {
         prune_window: 0,
         batch_size: 0,
         user_pruning_window_offset: 0,
+        archive_dir: None,
+        archive_chunk_bytes: 0,
     },
     state_merkle_pruner_config: StateMerklePrunerConfig {
         enable: false,
@@ -117,7 +119,7 @@ pub const NO_OP_STORAGE_PRUNER_CONFIG: PrunerConfig = PrunerConfig {
     },
 };
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct LedgerPrunerConfig {
     /// Boolean to enable/disable the ledger pruner. The ledger pruner is responsible for pruning
@@ -133,6 +135,13 @@ pub struct LedgerPrunerConfig {
     pub batch_size: usize,
     /// The offset for user pruning window to adjust
     pub user_pruning_window_offset: u64,
+    /// If set, every batch of transactions is exported to this directory, in the same format as
+    /// transaction backups in a `LocalFs` backup storage, before being pruned. The API then serves
+    /// historical transactions from the archive.
+    pub archive_dir: Option<PathBuf>,
+    /// Batches are appended to the same archive chunk until it reaches this size, like the max
+    /// chunk size of transaction backups.
+    pub archive_chunk_bytes: u64,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -172,7 +181,7 @@ impl From<EpochSnapshotPrunerConfig> for StateMerklePrunerConfig {
     }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, Default)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, Default)]
 #[serde(default, deny_unknown_fields)]
 pub struct PrunerConfig {
     pub ledger_pruner_config: LedgerPrunerConfig,
@@ -189,6 +198,8 @@ impl Default for LedgerPrunerConfig {
             prune_window: 150_000_000,
             batch_size: 500,
             user_pruning_window_offset: 200_000,
+            archive_dir: None,
+            archive_chunk_bytes: 128 * 1024 * 1024,
         }
     }
 }
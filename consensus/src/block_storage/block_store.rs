@@ -4,10 +4,11 @@
 use crate::{
     block_storage::{
         block_tree::BlockTree,
-        tracing::{observe_block, BlockStage},
+        tracing::{observe_block, trace_block, BlockStage},
         BlockReader,
     },
     counters,
+    introspection::BlockTreeSnapshot,
     payload_manager::PayloadManager,
     persistent_liveness_storage::{
         PersistentLivenessStorage, RecoveryData, RootInfo, RootMetadata,
@@ -42,12 +43,14 @@ pub mod sync_manager;
 fn update_counters_for_ordered_blocks(ordered_blocks: &[Arc<ExecutedBlock>]) {
     for block in ordered_blocks {
         observe_block(block.block().timestamp_usecs(), BlockStage::ORDERED);
+        trace_block(block.block(), BlockStage::ORDERED);
     }
 }
 
 pub fn update_counters_for_committed_blocks(blocks_to_commit: &[Arc<ExecutedBlock>]) {
     for block in blocks_to_commit {
         observe_block(block.block().timestamp_usecs(), BlockStage::COMMITTED);
+        trace_block(block.block(), BlockStage::COMMITTED);
         let txn_status = block.compute_result().compute_status();
         counters::NUM_TXNS_PER_BLOCK.observe(txn_status.len() as f64);
         counters::COMMITTED_BLOCKS_COUNT.inc();
@@ -504,6 +507,11 @@ impl BlockStore {
             .set((ordered_round - commit_round) as i64);
         ordered_round > self.back_pressure_limit + commit_round
     }
+
+    /// Returns a snapshot of the block tree for introspection
+    pub fn introspect(&self) -> BlockTreeSnapshot {
+        self.inner.read().introspect()
+    }
 }
 
 impl BlockReader for BlockStore {
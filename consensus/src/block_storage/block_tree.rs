@@ -4,6 +4,7 @@
 use crate::{
     block_storage::block_store::update_counters_for_committed_blocks,
     counters,
+    introspection::{BlockSnapshot, BlockTreeSnapshot},
     logging::{LogEvent, LogSchema},
     persistent_liveness_storage::PersistentLivenessStorage,
 };
@@ -12,7 +13,7 @@ use aptos_consensus_types::{
     executed_block::ExecutedBlock, quorum_cert::QuorumCert,
     timeout_2chain::TwoChainTimeoutCertificate,
 };
-use aptos_crypto::HashValue;
+use aptos_crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
 use aptos_logger::prelude::*;
 use aptos_types::{block_info::BlockInfo, ledger_info::LedgerInfoWithSignatures};
 use mirai_annotations::{checked_verify_eq, precondition};
@@ -444,6 +445,58 @@ impl BlockTree {
         self.process_pruned_blocks(id_to_remove);
         self.update_highest_commit_cert(commit_proof);
     }
+
+    /// Returns a snapshot of the active tree (i.e., the commit root and all its descendants)
+    pub(super) fn introspect(&self) -> BlockTreeSnapshot {
+        let ordered_block_ids: HashSet<HashValue> = self
+            .path_from_commit_root(self.ordered_root_id)
+            .unwrap_or_default()
+            .iter()
+            .map(|block| block.id())
+            .chain(std::iter::once(self.commit_root_id))
+            .collect();
+
+        let mut blocks = vec![];
+        let mut to_visit = vec![self.linkable_root()];
+        while let Some(linkable_block) = to_visit.pop() {
+            let block = linkable_block.executed_block();
+            let mut children: Vec<HashValue> = linkable_block.children().iter().cloned().collect();
+            children.sort();
+            for child_id in &children {
+                to_visit.push(
+                    self.get_linkable_block(child_id)
+                        .expect("Child must exist in the tree"),
+                );
+            }
+            blocks.push(BlockSnapshot {
+                id: block.id(),
+                parent_id: block.parent_id(),
+                epoch: block.epoch(),
+                round: block.round(),
+                author: block.block().author(),
+                children,
+                certified: self.id_to_quorum_cert.contains_key(&block.id()),
+                executed: block.compute_result().root_hash() != *ACCUMULATOR_PLACEHOLDER_HASH,
+                ordered: ordered_block_ids.contains(&block.id()),
+                committed: block.id() == self.commit_root_id,
+            });
+        }
+        blocks.sort_by_key(|block| (block.round, block.id));
+
+        BlockTreeSnapshot {
+            ordered_root_id: self.ordered_root_id,
+            commit_root_id: self.commit_root_id,
+            highest_certified_block_id: self.highest_certified_block_id,
+            highest_quorum_cert_round: self.highest_quorum_cert.certified_block().round(),
+            highest_timeout_cert_round: self
+                .highest_2chain_timeout_cert
+                .as_ref()
+                .map(|tc| tc.round()),
+            highest_ordered_round: self.highest_ordered_cert.commit_info().round(),
+            highest_commit_round: self.highest_commit_cert.commit_info().round(),
+            blocks,
+        }
+    }
 }
 
 #[cfg(any(test, feature = "fuzzing"))]
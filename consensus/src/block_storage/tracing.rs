@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::counters;
+use aptos_consensus_types::block::Block;
+use aptos_crypto::HashValue;
 use aptos_infallible::duration_since_epoch;
-use std::time::Duration;
+use aptos_logger::{span_export, txn_span};
+use aptos_types::transaction::SignedTransaction;
+use std::time::{Duration, SystemTime};
 
 pub struct BlockStage;
 
@@ -31,3 +35,45 @@ pub fn observe_block(timestamp: u64, stage: &'static str) {
             .observe(t.as_secs_f64());
     }
 }
+
+/// Export a span of the block from its proposal timestamp until it reached the stage.
+pub fn trace_block(block: &Block, stage: &'static str) {
+    if !span_export::is_enabled() {
+        return;
+    }
+    txn_span!(
+        "consensus",
+        block_id = %block.id().to_hex(),
+        otel.name = %format!("consensus.{}", stage),
+        otel.start_time_unix_nano = block.timestamp_usecs().saturating_mul(1000),
+        epoch = block.epoch(),
+        round = block.round(),
+        num_txns = block.payload().map_or(0, |payload| payload.len() as u64)
+    )
+    .in_scope(|| {});
+}
+
+/// Export a span for every transaction of the block, from the start of the stage until now.
+/// The spans are keyed by the transaction hash, so they show up next to the mempool stages of
+/// the transaction.
+pub fn trace_block_txns(
+    block_id: HashValue,
+    txns: &[SignedTransaction],
+    stage: &'static str,
+    start: SystemTime,
+) {
+    if !span_export::is_enabled() {
+        return;
+    }
+    let block_id = block_id.to_hex();
+    for txn in txns {
+        txn_span!(
+            "consensus",
+            txn_hash = %txn.clone().committed_hash().to_hex(),
+            otel.name = %format!("consensus.{}", stage),
+            otel.start_time_unix_nano = span_export::unix_nanos(start),
+            block_id = block_id.as_str()
+        )
+        .in_scope(|| {});
+    }
+}
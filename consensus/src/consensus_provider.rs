@@ -4,6 +4,7 @@
 use crate::{
     counters,
     epoch_manager::EpochManager,
+    introspection,
     network::NetworkTask,
     network_interface::{ConsensusMsg, ConsensusNetworkClient},
     persistent_liveness_storage::StorageWriteProxy,
@@ -36,6 +37,7 @@ pub fn start_consensus(
     reconfig_events: ReconfigNotificationListener,
 ) -> Runtime {
     let runtime = aptos_runtimes::spawn_named_runtime("consensus".into(), None);
+    introspection::set_enabled(node_config.inspection_service.expose_consensus_state);
     let storage = Arc::new(StorageWriteProxy::new(node_config, aptos_db.reader.clone()));
     let txn_notifier = Arc::new(MempoolNotifier::new(
         consensus_to_mempool_sender.clone(),
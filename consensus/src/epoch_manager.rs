@@ -13,6 +13,7 @@ use crate::{
         decoupled_execution_utils::prepare_phases_and_buffer_manager,
         ordering_state_computer::OrderingStateComputer,
     },
+    introspection,
     liveness::{
         cached_proposer_election::CachedProposerElection,
         leader_reputation::{
@@ -639,6 +640,9 @@ impl EpochManager {
             onchain_config.back_pressure_limit(),
             payload_manager,
         ));
+        if introspection::is_enabled() {
+            introspection::register_block_store(epoch, &block_store);
+        }
 
         info!(epoch = epoch, "Start DirectMempoolQuorumStore");
         let (consensus_to_quorum_store_tx, consensus_to_quorum_store_rx) =
@@ -12,6 +12,7 @@ use crate::{
         pipeline_phase::CountedRequest,
         signing_phase::{SigningRequest, SigningResponse},
     },
+    introspection::{self, BufferItemSnapshot, BufferManagerSnapshot},
     network::NetworkSender,
     round_manager::VerifiedEvent,
     state_replication::StateComputerCommitCallBackType,
@@ -532,25 +533,49 @@ impl BufferManager {
         let mut pending_executed = 0;
         let mut pending_signed = 0;
         let mut pending_aggregated = 0;
+        let introspect = introspection::is_enabled();
+        let mut items = vec![];
 
         while cursor.is_some() {
-            match self.buffer.get(&cursor) {
+            let item = self.buffer.get(&cursor);
+            let phase = match item {
                 BufferItem::Ordered(_) => {
                     pending_ordered += 1;
+                    "ordered"
                 },
                 BufferItem::Executed(_) => {
                     pending_executed += 1;
+                    "executed"
                 },
                 BufferItem::Signed(_) => {
                     pending_signed += 1;
+                    "signed"
                 },
                 BufferItem::Aggregated(_) => {
                     pending_aggregated += 1;
+                    "aggregated"
                 },
+            };
+            if introspect {
+                let blocks = item.get_blocks();
+                items.push(BufferItemSnapshot {
+                    block_id: item.block_id(),
+                    round: blocks.last().map_or(0, |block| block.round()),
+                    num_blocks: blocks.len(),
+                    phase: phase.to_string(),
+                });
             }
             cursor = self.buffer.get_next(&cursor);
         }
 
+        if introspect {
+            introspection::publish_buffer_manager(BufferManagerSnapshot {
+                items,
+                execution_root: self.execution_root,
+                signing_root: self.signing_root,
+            });
+        }
+
         counters::NUM_BLOCKS_IN_PIPELINE
             .with_label_values(&["ordered"])
             .set(pending_ordered as i64);
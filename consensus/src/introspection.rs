@@ -0,0 +1,472 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Read-only snapshots of the live consensus state.
+//!
+//! The different consensus components (the block store, the round manager, the proposer
+//! election and the buffer manager) publish their state into a process wide registry.
+//! The node inspection service assembles these into a single [`ConsensusStateSnapshot`],
+//! which can be encoded as JSON or rendered as a DOT graph of the block tree. Nothing is
+//! published unless the registry is enabled, as building the snapshots isn't free.
+
+use crate::block_storage::BlockStore;
+use aptos_consensus_types::common::{Author, Round};
+use aptos_crypto::HashValue;
+use aptos_infallible::RwLock;
+use aptos_short_hex_str::AsShortHexStr;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+    },
+};
+
+/// The number of recent rounds for which pending votes are retained
+const MAX_PENDING_VOTE_ROUNDS: usize = 10;
+
+/// The global registry that the consensus components publish into
+static CONSENSUS_INTROSPECTION: Lazy<ConsensusIntrospection> =
+    Lazy::new(ConsensusIntrospection::default);
+
+/// Returns a snapshot of the live consensus state (if consensus is running and the registry is
+/// enabled)
+pub fn get_consensus_state_snapshot() -> Option<ConsensusStateSnapshot> {
+    CONSENSUS_INTROSPECTION.snapshot()
+}
+
+/// Enables (or disables) publishing the consensus state into the registry
+pub fn set_enabled(enabled: bool) {
+    CONSENSUS_INTROSPECTION
+        .enabled
+        .store(enabled, Ordering::Relaxed);
+    if !enabled {
+        CONSENSUS_INTROSPECTION.clear();
+    }
+}
+
+/// Returns true iff the consensus components should publish their state, so that they don't
+/// build snapshots on the hot path otherwise
+pub(crate) fn is_enabled() -> bool {
+    CONSENSUS_INTROSPECTION.enabled.load(Ordering::Relaxed)
+}
+
+/// Registers the block store of the current epoch
+pub(crate) fn register_block_store(epoch: u64, block_store: &Arc<BlockStore>) {
+    CONSENSUS_INTROSPECTION.register_block_store(epoch, block_store)
+}
+
+/// Publishes the latest round state (including the pending votes)
+pub(crate) fn publish_round_state(round_state: RoundStateSnapshot) {
+    CONSENSUS_INTROSPECTION.publish_round_state(round_state)
+}
+
+/// Publishes the latest proposer election state
+pub(crate) fn publish_proposer_election(proposer_election: ProposerElectionSnapshot) {
+    *CONSENSUS_INTROSPECTION.proposer_election.write() = Some(proposer_election);
+}
+
+/// Publishes the latest buffer manager pipeline state
+pub(crate) fn publish_buffer_manager(buffer_manager: BufferManagerSnapshot) {
+    *CONSENSUS_INTROSPECTION.buffer_manager.write() = Some(buffer_manager);
+}
+
+#[derive(Default)]
+struct ConsensusIntrospection {
+    enabled: AtomicBool,
+    epoch: RwLock<Option<u64>>,
+    block_store: RwLock<Weak<BlockStore>>,
+    round_state: RwLock<Option<RoundStateSnapshot>>,
+    pending_votes: RwLock<BTreeMap<Round, PendingVotesSnapshot>>,
+    proposer_election: RwLock<Option<ProposerElectionSnapshot>>,
+    buffer_manager: RwLock<Option<BufferManagerSnapshot>>,
+}
+
+impl ConsensusIntrospection {
+    fn register_block_store(&self, epoch: u64, block_store: &Arc<BlockStore>) {
+        // Everything published for the previous epoch is now stale
+        self.clear();
+        *self.epoch.write() = Some(epoch);
+        *self.block_store.write() = Arc::downgrade(block_store);
+    }
+
+    fn clear(&self) {
+        *self.epoch.write() = None;
+        *self.block_store.write() = Weak::new();
+        *self.round_state.write() = None;
+        self.pending_votes.write().clear();
+        *self.proposer_election.write() = None;
+        *self.buffer_manager.write() = None;
+    }
+
+    fn publish_round_state(&self, round_state: RoundStateSnapshot) {
+        let mut pending_votes = self.pending_votes.write();
+        pending_votes.insert(round_state.current_round, round_state.pending_votes.clone());
+        while pending_votes.len() > MAX_PENDING_VOTE_ROUNDS {
+            let oldest_round = *pending_votes.keys().next().expect("Map cannot be empty!");
+            pending_votes.remove(&oldest_round);
+        }
+        *self.round_state.write() = Some(round_state);
+    }
+
+    fn snapshot(&self) -> Option<ConsensusStateSnapshot> {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return None;
+        }
+        let epoch = (*self.epoch.read())?;
+        let block_tree = self
+            .block_store
+            .read()
+            .upgrade()
+            .map(|block_store| block_store.introspect());
+
+        Some(ConsensusStateSnapshot {
+            epoch,
+            block_tree,
+            round_state: self.round_state.read().clone(),
+            pending_votes: self.pending_votes.read().values().cloned().collect(),
+            proposer_election: self.proposer_election.read().clone(),
+            buffer_manager: self.buffer_manager.read().clone(),
+        })
+    }
+}
+
+/// A point-in-time view of the consensus state of this node
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ConsensusStateSnapshot {
+    /// The current epoch
+    pub epoch: u64,
+    /// The block tree held by the block store
+    pub block_tree: Option<BlockTreeSnapshot>,
+    /// The state of the current round
+    pub round_state: Option<RoundStateSnapshot>,
+    /// The pending votes of the most recent rounds (in ascending round order)
+    pub pending_votes: Vec<PendingVotesSnapshot>,
+    /// The current proposer election state
+    pub proposer_election: Option<ProposerElectionSnapshot>,
+    /// The blocks currently in the execution pipeline
+    pub buffer_manager: Option<BufferManagerSnapshot>,
+}
+
+impl ConsensusStateSnapshot {
+    /// Renders the snapshot as a DOT graph. Each block points to its parent,
+    /// and blocks are colored by their status.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph consensus_epoch_{} {{", self.epoch).unwrap();
+        writeln!(dot, "  rankdir=LR;").unwrap();
+        writeln!(dot, "  node [shape=box, style=filled];").unwrap();
+
+        if let Some(block_tree) = &self.block_tree {
+            let pipeline_phases: BTreeMap<HashValue, &str> = self
+                .buffer_manager
+                .iter()
+                .flat_map(|buffer_manager| buffer_manager.items.iter())
+                .map(|item| (item.block_id, item.phase.as_str()))
+                .collect();
+
+            for block in &block_tree.blocks {
+                let color = if block.committed {
+                    "palegreen"
+                } else if block.ordered {
+                    "lightblue"
+                } else if block.certified {
+                    "khaki"
+                } else {
+                    "white"
+                };
+                let mut label = format!("round {}\\n{}", block.round, block.id);
+                if let Some(author) = block.author {
+                    write!(label, "\\nauthor {}", author.short_str()).unwrap();
+                }
+                if let Some(phase) = pipeline_phases.get(&block.id) {
+                    write!(label, "\\npipeline: {}", phase).unwrap();
+                }
+                writeln!(
+                    dot,
+                    "  \"{:x}\" [label=\"{}\", fillcolor={}];",
+                    block.id, label, color
+                )
+                .unwrap();
+            }
+            // Only link blocks whose parent is still in the tree
+            let block_ids: HashSet<HashValue> =
+                block_tree.blocks.iter().map(|block| block.id).collect();
+            for block in &block_tree.blocks {
+                if block_ids.contains(&block.parent_id) {
+                    writeln!(dot, "  \"{:x}\" -> \"{:x}\";", block.id, block.parent_id).unwrap();
+                }
+            }
+
+            let mut summary = format!(
+                "highest QC round: {}\\nhighest ordered round: {}\\nhighest commit round: {}",
+                block_tree.highest_quorum_cert_round,
+                block_tree.highest_ordered_round,
+                block_tree.highest_commit_round
+            );
+            if let Some(tc_round) = block_tree.highest_timeout_cert_round {
+                write!(summary, "\\nhighest TC round: {}", tc_round).unwrap();
+            }
+            if let Some(round_state) = &self.round_state {
+                write!(
+                    summary,
+                    "\\ncurrent round: {}\\nround deadline (ms): {}",
+                    round_state.current_round, round_state.current_round_deadline_ms
+                )
+                .unwrap();
+            }
+            writeln!(
+                dot,
+                "  summary [shape=note, fillcolor=lightyellow, label=\"{}\"];",
+                summary
+            )
+            .unwrap();
+        }
+
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+/// A view of the block tree in the block store
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct BlockTreeSnapshot {
+    /// The root of the ordering phase
+    pub ordered_root_id: HashValue,
+    /// The root of the commit phase
+    pub commit_root_id: HashValue,
+    /// The certified block with the highest round
+    pub highest_certified_block_id: HashValue,
+    /// The round of the highest quorum certificate
+    pub highest_quorum_cert_round: Round,
+    /// The round of the highest 2-chain timeout certificate (if any)
+    pub highest_timeout_cert_round: Option<Round>,
+    /// The highest ordered round
+    pub highest_ordered_round: Round,
+    /// The highest committed round
+    pub highest_commit_round: Round,
+    /// All blocks in the tree (sorted by round)
+    pub blocks: Vec<BlockSnapshot>,
+}
+
+/// A single block in the block tree
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct BlockSnapshot {
+    /// The block id
+    pub id: HashValue,
+    /// The id of the parent block
+    pub parent_id: HashValue,
+    /// The epoch of the block
+    pub epoch: u64,
+    /// The round of the block
+    pub round: Round,
+    /// The block author (None for NIL and genesis blocks)
+    pub author: Option<Author>,
+    /// The ids of the children of the block
+    pub children: Vec<HashValue>,
+    /// True iff the block has a quorum certificate
+    pub certified: bool,
+    /// True iff the block has been executed
+    pub executed: bool,
+    /// True iff the block has been ordered
+    pub ordered: bool,
+    /// True iff the block has been committed
+    pub committed: bool,
+}
+
+/// A view of the round state
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct RoundStateSnapshot {
+    /// The current round
+    pub current_round: Round,
+    /// The highest committed round known to the round state
+    pub highest_committed_round: Round,
+    /// The deadline of the current round (as a unix timestamp in milliseconds)
+    pub current_round_deadline_ms: u64,
+    /// The round of the vote sent by this node (if any)
+    pub vote_sent_round: Option<Round>,
+    /// True iff the vote sent by this node is a timeout vote
+    pub vote_sent_is_timeout: bool,
+    /// The votes collected in the current round
+    pub pending_votes: PendingVotesSnapshot,
+}
+
+/// A view of the votes collected for a single round
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct PendingVotesSnapshot {
+    /// The round the votes were collected for
+    pub round: Round,
+    /// The voters for each ledger info digest
+    pub votes: Vec<LedgerInfoVotes>,
+    /// The authors that sent a timeout vote
+    pub timeout_voters: Vec<Author>,
+}
+
+/// The voters for a single ledger info
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct LedgerInfoVotes {
+    /// The digest of the ledger info
+    pub ledger_info_digest: HashValue,
+    /// The authors that voted for the ledger info
+    pub voters: Vec<Author>,
+}
+
+/// A view of the proposer election
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ProposerElectionSnapshot {
+    /// The round the proposer was elected for
+    pub round: Round,
+    /// The elected proposer
+    pub proposer: Author,
+    /// The ratio of voting power participating in consensus
+    pub voting_power_participation_ratio: f64,
+    /// The reputation weights of all candidates (only for reputation based elections)
+    pub candidate_weights: Option<Vec<CandidateWeight>>,
+}
+
+/// The reputation of a single proposer candidate
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct CandidateWeight {
+    /// The candidate
+    pub author: Author,
+    /// The reputation weight assigned by the heuristic
+    pub reputation_weight: u64,
+    /// The voting power of the candidate
+    pub voting_power: u64,
+}
+
+/// A view of the buffer manager pipeline
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct BufferManagerSnapshot {
+    /// The buffer items (from head to tail)
+    pub items: Vec<BufferItemSnapshot>,
+    /// The block id of the next item to execute (if any)
+    pub execution_root: Option<HashValue>,
+    /// The block id of the next item to sign (if any)
+    pub signing_root: Option<HashValue>,
+}
+
+/// A single item in the buffer manager
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct BufferItemSnapshot {
+    /// The id of the last block in the item
+    pub block_id: HashValue,
+    /// The round of the last block in the item
+    pub round: Round,
+    /// The number of blocks in the item
+    pub num_blocks: usize,
+    /// The pipeline phase of the item (ordered, executed, signed or aggregated)
+    pub phase: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_block(round: Round, parent_id: HashValue, committed: bool) -> BlockSnapshot {
+        BlockSnapshot {
+            id: HashValue::random(),
+            parent_id,
+            epoch: 1,
+            round,
+            author: Some(Author::random()),
+            children: vec![],
+            certified: true,
+            executed: committed,
+            ordered: committed,
+            committed,
+        }
+    }
+
+    #[test]
+    fn test_dot_rendering() {
+        let root = create_block(1, HashValue::zero(), true);
+        let child = create_block(2, root.id, false);
+        let snapshot = ConsensusStateSnapshot {
+            epoch: 1,
+            block_tree: Some(BlockTreeSnapshot {
+                ordered_root_id: root.id,
+                commit_root_id: root.id,
+                highest_certified_block_id: child.id,
+                highest_quorum_cert_round: 2,
+                highest_timeout_cert_round: None,
+                highest_ordered_round: 1,
+                highest_commit_round: 1,
+                blocks: vec![root.clone(), child.clone()],
+            }),
+            round_state: None,
+            pending_votes: vec![],
+            proposer_election: None,
+            buffer_manager: Some(BufferManagerSnapshot {
+                items: vec![BufferItemSnapshot {
+                    block_id: child.id,
+                    round: 2,
+                    num_blocks: 1,
+                    phase: "executed".into(),
+                }],
+                execution_root: None,
+                signing_root: Some(child.id),
+            }),
+        };
+
+        let dot = snapshot.to_dot();
+        assert!(dot.starts_with("digraph consensus_epoch_1 {"));
+        assert!(dot.contains(&format!("\"{:x}\" -> \"{:x}\";", child.id, root.id)));
+        assert!(!dot.contains(&format!("\"{:x}\" -> ", root.id)));
+        assert!(dot.contains("pipeline: executed"));
+        assert!(dot.contains("highest QC round: 2"));
+
+        // The JSON encoding must round trip
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let decoded: ConsensusStateSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, snapshot);
+    }
+
+    #[test]
+    fn test_pending_votes_retention() {
+        let introspection = ConsensusIntrospection::default();
+        introspection.enabled.store(true, Ordering::Relaxed);
+        for round in 0..(MAX_PENDING_VOTE_ROUNDS as u64 * 2) {
+            introspection.publish_round_state(RoundStateSnapshot {
+                current_round: round,
+                highest_committed_round: 0,
+                current_round_deadline_ms: 0,
+                vote_sent_round: None,
+                vote_sent_is_timeout: false,
+                pending_votes: PendingVotesSnapshot {
+                    round,
+                    ..PendingVotesSnapshot::default()
+                },
+            });
+        }
+
+        // No snapshot is available before a block store is registered
+        assert!(introspection.snapshot().is_none());
+
+        let pending_votes = introspection.pending_votes.read();
+        assert_eq!(pending_votes.len(), MAX_PENDING_VOTE_ROUNDS);
+        assert_eq!(
+            *pending_votes.keys().next().unwrap(),
+            MAX_PENDING_VOTE_ROUNDS as u64
+        );
+    }
+    #[test]
+    fn test_clear_stale_state() {
+        let introspection = ConsensusIntrospection::default();
+        *introspection.epoch.write() = Some(1);
+        *introspection.buffer_manager.write() = Some(BufferManagerSnapshot::default());
+
+        // Nothing is exposed unless enabled
+        assert!(introspection.snapshot().is_none());
+        introspection.enabled.store(true, Ordering::Relaxed);
+        assert!(introspection.snapshot().unwrap().buffer_manager.is_some());
+
+        // The state of the previous epoch is dropped when a new block store is registered
+        introspection.clear();
+        assert!(introspection.snapshot().is_none());
+        assert!(introspection.buffer_manager.read().is_none());
+    }
+}
@@ -44,6 +44,8 @@ mod util;
 pub mod consensus_provider;
 /// Required by the telemetry service
 pub mod counters;
+/// Required by the inspection service
+pub mod introspection;
 /// AptosNet interface.
 pub mod network_interface;
 mod payload_manager;
@@ -51,6 +53,8 @@ mod payload_manager;
 pub use consensusdb::create_checkpoint;
 /// Required by the smoke tests
 pub use consensusdb::CONSENSUS_DB_NAME;
+/// Required by the leader reputation simulator in the CLI
+pub use liveness::leader_reputation_simulator;
 #[cfg(feature = "fuzzing")]
 pub use round_manager::round_manager_fuzzing;
 
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::proposer_election::ProposerElection;
-use crate::counters::PROPOSER_ELECTION_DURATION;
+use crate::{counters::PROPOSER_ELECTION_DURATION, introspection::CandidateWeight};
 use aptos_consensus_types::common::{Author, Round};
 use aptos_infallible::Mutex;
 use aptos_logger::prelude::info;
@@ -66,4 +66,8 @@ impl ProposerElection for CachedProposerElection {
     fn get_voting_power_participation_ratio(&self, round: Round) -> f64 {
         self.get_or_compute_entry(round).1
     }
+
+    fn get_latest_candidate_weights(&self) -> Option<Vec<CandidateWeight>> {
+        self.proposer_election.get_latest_candidate_weights()
+    }
 }
@@ -10,6 +10,7 @@ use crate::{
         CONSENSUS_PARTICIPATION_STATUS, FAILED_PROPOSALS_IN_WINDOW,
         LEADER_REPUTATION_ROUND_HISTORY_SIZE,
     },
+    introspection::{self, CandidateWeight},
     liveness::proposer_election::{choose_index, ProposerElection},
 };
 use anyhow::{ensure, Result};
@@ -560,6 +561,9 @@ pub struct LeaderReputation {
     exclude_round: u64,
     use_root_hash: bool,
     window_for_chain_health: usize,
+    // The candidate weights computed by the latest election, only kept when introspection is
+    // enabled
+    latest_candidate_weights: Mutex<Option<Vec<CandidateWeight>>>,
 }
 
 impl LeaderReputation {
@@ -585,6 +589,7 @@ impl LeaderReputation {
             exclude_round,
             use_root_hash,
             window_for_chain_health,
+            latest_candidate_weights: Mutex::new(None),
         }
     }
 
@@ -697,6 +702,21 @@ impl ProposerElection for LeaderReputation {
         let proposers = &self.epoch_to_proposers[&self.epoch];
         assert_eq!(weights.len(), proposers.len());
 
+        if introspection::is_enabled() {
+            *self.latest_candidate_weights.lock() = Some(
+                proposers
+                    .iter()
+                    .zip(weights.iter())
+                    .zip(self.voting_powers.iter())
+                    .map(|((author, weight), voting_power)| CandidateWeight {
+                        author: *author,
+                        reputation_weight: *weight,
+                        voting_power: *voting_power,
+                    })
+                    .collect(),
+            );
+        }
+
         // Multiply weights by voting power:
         let stake_weights: Vec<u128> = weights
             .iter_mut()
@@ -732,6 +752,10 @@ impl ProposerElection for LeaderReputation {
         self.get_valid_proposer_and_voting_power_participation_ratio(round)
             .1
     }
+
+    fn get_latest_candidate_weights(&self) -> Option<Vec<CandidateWeight>> {
+        self.latest_candidate_weights.lock().clone()
+    }
 }
 
 pub(crate) fn extract_epoch_to_proposers_impl(
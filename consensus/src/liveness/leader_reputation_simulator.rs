@@ -0,0 +1,468 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline replay of historical blocks through the leader reputation proposer election.
+//!
+//! The simulator takes the committed `NewBlockEvent` history and re-runs the proposer
+//! election for every round with a configurable `LeaderReputationType`. As the simulation
+//! cannot know how a different proposer would have behaved, the reputation of each validator
+//! is always computed from the real history, and failed round impact is estimated from the
+//! historical failure rate of each elected proposer.
+//!
+//! Note: `ProposerAndVoterV2` seeds the election with the accumulator root hash, which is not
+//! available in the block history. A zero root hash is used instead, so the simulated proposer
+//! of an individual round differs from the real one, while the proposer distribution does not.
+
+use crate::{
+    counters::CHAIN_HEALTH_WINDOW_SIZES,
+    liveness::{
+        leader_reputation::{
+            LeaderReputation, MetadataBackend, ProposerAndVoterHeuristic, ReputationHeuristic,
+        },
+        proposer_election::ProposerElection,
+    },
+};
+use anyhow::{ensure, Result};
+use aptos_consensus_types::common::{Author, Round};
+use aptos_crypto::HashValue;
+use aptos_types::{
+    account_address::AccountAddress, account_config::NewBlockEvent,
+    on_chain_config::LeaderReputationType,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+/// The committed history of a single epoch
+#[derive(Clone, Debug)]
+pub struct SimulatedEpoch {
+    /// The epoch
+    pub epoch: u64,
+    /// The validators of the epoch (ordered by validator index)
+    pub validators: Vec<Author>,
+    /// The voting powers of the validators (ordered by validator index)
+    pub voting_powers: Vec<u64>,
+    /// The committed blocks of the epoch (ordered by round)
+    pub blocks: Vec<NewBlockEvent>,
+}
+
+/// The parameters of a single leader reputation configuration to simulate
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct SimulationConfig {
+    /// The proposer election heuristic and its parameters
+    pub leader_reputation_type: LeaderReputationType,
+    /// The number of recent rounds that don't count into reputations
+    pub exclude_round: u64,
+    /// The window (in blocks) used to compute chain health
+    pub window_for_chain_health: usize,
+}
+
+/// The simulated proposer statistics of a single validator
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ValidatorSimulationStats {
+    /// The validator
+    pub validator: Author,
+    /// The number of rounds the validator was elected as proposer in the history
+    pub historical_proposer_rounds: u64,
+    /// The number of rounds the validator failed to propose in the history
+    pub historical_failed_rounds: u64,
+    /// The number of rounds the validator was elected as proposer in the simulation
+    pub simulated_proposer_rounds: u64,
+    /// The historical share of rounds the validator was elected as proposer
+    pub historical_proposer_share: f64,
+    /// The simulated share of rounds the validator was elected as proposer
+    pub simulated_proposer_share: f64,
+    /// The number of failed rounds expected from the validator in the simulation
+    pub expected_failed_rounds: f64,
+}
+
+impl ValidatorSimulationStats {
+    fn new(validator: Author) -> Self {
+        Self {
+            validator,
+            historical_proposer_rounds: 0,
+            historical_failed_rounds: 0,
+            simulated_proposer_rounds: 0,
+            historical_proposer_share: 0.0,
+            simulated_proposer_share: 0.0,
+            expected_failed_rounds: 0.0,
+        }
+    }
+}
+
+/// The result of simulating a single configuration
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SimulationResult {
+    /// The total number of simulated rounds
+    pub total_rounds: u64,
+    /// The total number of failed rounds in the history
+    pub historical_failed_rounds: u64,
+    /// The total number of failed rounds expected in the simulation
+    pub expected_failed_rounds: f64,
+    /// The statistics of each validator (ordered by validator address)
+    pub validators: Vec<ValidatorSimulationStats>,
+}
+
+/// Replays historical blocks through the leader reputation proposer election
+pub struct LeaderReputationSimulator {
+    epochs: Vec<SimulatedEpoch>,
+    /// The history of all epochs (most recent block first), as expected by the heuristics.
+    /// Shared by the proposer elections of all epochs.
+    history: Arc<Vec<NewBlockEvent>>,
+}
+
+impl LeaderReputationSimulator {
+    /// Creates a simulator over the given epochs of committed history
+    pub fn new(mut epochs: Vec<SimulatedEpoch>) -> Result<Self> {
+        epochs.sort_by_key(|epoch| epoch.epoch);
+        for epoch in &epochs {
+            ensure!(
+                epoch.validators.len() == epoch.voting_powers.len(),
+                "Epoch {} has {} validators but {} voting powers",
+                epoch.epoch,
+                epoch.validators.len(),
+                epoch.voting_powers.len()
+            );
+            ensure!(
+                epoch.blocks.windows(2).all(|w| w[0].round() < w[1].round()),
+                "Blocks of epoch {} need to be sorted by round",
+                epoch.epoch
+            );
+        }
+
+        let mut history: Vec<NewBlockEvent> = epochs
+            .iter()
+            .flat_map(|epoch| epoch.blocks.iter().cloned())
+            .collect();
+        history.reverse();
+
+        Ok(Self {
+            epochs,
+            history: Arc::new(history),
+        })
+    }
+
+    /// Replays every round of the history with the given configuration
+    pub fn simulate(&self, config: &SimulationConfig) -> Result<SimulationResult> {
+        ensure!(
+            CHAIN_HEALTH_WINDOW_SIZES.contains(&config.window_for_chain_health),
+            "Window for chain health {} must be one of {:?}",
+            config.window_for_chain_health,
+            CHAIN_HEALTH_WINDOW_SIZES
+        );
+
+        let mut stats: BTreeMap<Author, ValidatorSimulationStats> = BTreeMap::new();
+        let mut total_rounds = 0;
+
+        for epoch in &self.epochs {
+            let outcomes = historical_round_outcomes(epoch);
+            if outcomes.is_empty() {
+                continue;
+            }
+            let proposer_election = self.create_proposer_election(epoch, config);
+
+            for (round, (proposer, succeeded)) in outcomes {
+                total_rounds += 1;
+                let historical = stats
+                    .entry(proposer)
+                    .or_insert_with(|| ValidatorSimulationStats::new(proposer));
+                historical.historical_proposer_rounds += 1;
+                if !succeeded {
+                    historical.historical_failed_rounds += 1;
+                }
+
+                let simulated_proposer = proposer_election.get_valid_proposer(round);
+                stats
+                    .entry(simulated_proposer)
+                    .or_insert_with(|| ValidatorSimulationStats::new(simulated_proposer))
+                    .simulated_proposer_rounds += 1;
+            }
+        }
+
+        // Estimate the failed rounds of each validator from its historical failure rate.
+        // Validators that were never elected in the history get the average failure rate.
+        let historical_failed_rounds: u64 =
+            stats.values().map(|s| s.historical_failed_rounds).sum();
+        let average_failure_rate = if total_rounds > 0 {
+            historical_failed_rounds as f64 / total_rounds as f64
+        } else {
+            0.0
+        };
+        for validator_stats in stats.values_mut() {
+            let failure_rate = if validator_stats.historical_proposer_rounds > 0 {
+                validator_stats.historical_failed_rounds as f64
+                    / validator_stats.historical_proposer_rounds as f64
+            } else {
+                average_failure_rate
+            };
+            validator_stats.expected_failed_rounds =
+                failure_rate * validator_stats.simulated_proposer_rounds as f64;
+            if total_rounds > 0 {
+                validator_stats.historical_proposer_share =
+                    validator_stats.historical_proposer_rounds as f64 / total_rounds as f64;
+                validator_stats.simulated_proposer_share =
+                    validator_stats.simulated_proposer_rounds as f64 / total_rounds as f64;
+            }
+        }
+
+        Ok(SimulationResult {
+            total_rounds,
+            historical_failed_rounds,
+            expected_failed_rounds: stats.values().map(|s| s.expected_failed_rounds).sum(),
+            validators: stats.into_values().collect(),
+        })
+    }
+
+    /// Creates the proposer election for the given epoch, mirroring how the epoch manager
+    /// creates it for a live validator.
+    fn create_proposer_election(
+        &self,
+        epoch: &SimulatedEpoch,
+        config: &SimulationConfig,
+    ) -> LeaderReputation {
+        let leader_reputation_type = &config.leader_reputation_type;
+        let proposer_and_voter_config = match leader_reputation_type {
+            LeaderReputationType::ProposerAndVoter(proposer_and_voter_config)
+            | LeaderReputationType::ProposerAndVoterV2(proposer_and_voter_config) => {
+                proposer_and_voter_config
+            },
+        };
+        let num_validators = epoch.validators.len();
+        let proposer_window_size =
+            num_validators * proposer_and_voter_config.proposer_window_num_validators_multiplier;
+        let voter_window_size =
+            num_validators * proposer_and_voter_config.voter_window_num_validators_multiplier;
+        let heuristic: Box<dyn ReputationHeuristic> = Box::new(ProposerAndVoterHeuristic::new(
+            AccountAddress::ZERO,
+            proposer_and_voter_config.active_weight,
+            proposer_and_voter_config.inactive_weight,
+            proposer_and_voter_config.failed_weight,
+            proposer_and_voter_config.failure_threshold_percent,
+            voter_window_size,
+            proposer_window_size,
+            leader_reputation_type.use_reputation_window_from_stale_end(),
+        ));
+        let window_size = std::cmp::max(proposer_window_size, voter_window_size);
+
+        let voting_powers = if proposer_and_voter_config.weight_by_voting_power {
+            epoch.voting_powers.clone()
+        } else {
+            vec![1; num_validators]
+        };
+
+        // Consider the same previous epochs as the epoch manager (if they are in the history)
+        let first_epoch_to_consider = std::cmp::max(
+            if epoch.epoch == 1 { 1 } else { 2 },
+            epoch.epoch.saturating_sub(
+                proposer_and_voter_config.use_history_from_previous_epoch_max_count as u64,
+            ),
+        );
+        let epoch_to_proposers = self
+            .epochs
+            .iter()
+            .filter(|e| e.epoch >= first_epoch_to_consider && e.epoch <= epoch.epoch)
+            .map(|e| (e.epoch, e.validators.clone()))
+            .chain(std::iter::once((epoch.epoch, epoch.validators.clone())))
+            .collect::<HashMap<_, _>>();
+
+        LeaderReputation::new(
+            epoch.epoch,
+            epoch_to_proposers,
+            voting_powers,
+            Box::new(HistoryBackend {
+                history: Arc::clone(&self.history),
+                window_size,
+            }),
+            heuristic,
+            config.exclude_round,
+            leader_reputation_type.use_root_hash_for_seed(),
+            config.window_for_chain_health,
+        )
+    }
+}
+
+/// Returns the elected proposer of every round in the epoch, and whether it succeeded
+fn historical_round_outcomes(epoch: &SimulatedEpoch) -> BTreeMap<Round, (Author, bool)> {
+    let mut outcomes = BTreeMap::new();
+    for block in &epoch.blocks {
+        let is_nil = block.proposer() == AccountAddress::ZERO;
+        // The failed proposers are for the rounds immediately preceding the block
+        // (including the block round itself, for NIL blocks).
+        let num_failed = block.failed_proposer_indices().len() as u64;
+        let first_failed_round = (block.round() + u64::from(is_nil)).saturating_sub(num_failed);
+        for (offset, index) in block.failed_proposer_indices().iter().enumerate() {
+            if let Some(failed_proposer) = epoch.validators.get(*index as usize) {
+                outcomes.insert(
+                    first_failed_round + offset as u64,
+                    (*failed_proposer, false),
+                );
+            }
+        }
+        if !is_nil {
+            outcomes.insert(block.round(), (block.proposer(), true));
+        }
+    }
+    outcomes
+}
+
+/// A metadata backend that serves the NewBlockEvent windows from an in-memory history
+struct HistoryBackend {
+    /// All events (most recent first, i.e. in descending (epoch, round) order)
+    history: Arc<Vec<NewBlockEvent>>,
+    window_size: usize,
+}
+
+impl MetadataBackend for HistoryBackend {
+    fn get_block_metadata(
+        &self,
+        target_epoch: u64,
+        target_round: Round,
+    ) -> (Vec<NewBlockEvent>, HashValue) {
+        // The history is sorted, so the window starts at the first event at or before the target
+        let start = self
+            .history
+            .partition_point(|event| (event.epoch(), event.round()) > (target_epoch, target_round));
+        let end = std::cmp::min(start + self.window_size, self.history.len());
+        (self.history[start..end].to_vec(), HashValue::zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_types::on_chain_config::ProposerAndVoterConfig;
+
+    fn create_config(failed_weight: u64) -> SimulationConfig {
+        SimulationConfig {
+            leader_reputation_type: LeaderReputationType::ProposerAndVoterV2(
+                ProposerAndVoterConfig {
+                    active_weight: 1000,
+                    inactive_weight: 10,
+                    failed_weight,
+                    failure_threshold_percent: 10,
+                    proposer_window_num_validators_multiplier: 10,
+                    voter_window_num_validators_multiplier: 1,
+                    weight_by_voting_power: true,
+                    use_history_from_previous_epoch_max_count: 5,
+                },
+            ),
+            exclude_round: 2,
+            window_for_chain_health: 100,
+        }
+    }
+
+    /// Creates an epoch in which the last validator fails every other proposal
+    fn create_epoch(num_validators: usize, num_blocks: u64) -> SimulatedEpoch {
+        let validators: Vec<Author> = (0..num_validators).map(|_| Author::random()).collect();
+        let mut blocks = vec![];
+        let mut round = 0;
+        for i in 0..num_blocks {
+            let proposer_index = (i as usize) % (num_validators - 1);
+            let failed_proposer_indices = if i % 2 == 0 {
+                round += 1;
+                vec![(num_validators - 1) as u64]
+            } else {
+                vec![]
+            };
+            round += 1;
+            blocks.push(NewBlockEvent::new(
+                AccountAddress::ZERO,
+                2,
+                round,
+                round,
+                vec![0xFF; (num_validators + 7) / 8],
+                validators[proposer_index],
+                failed_proposer_indices,
+                i,
+            ));
+        }
+        SimulatedEpoch {
+            epoch: 2,
+            voting_powers: vec![1; num_validators],
+            validators,
+            blocks,
+        }
+    }
+
+    #[test]
+    fn test_historical_round_outcomes() {
+        let epoch = create_epoch(4, 4);
+        let outcomes = historical_round_outcomes(&epoch);
+
+        // Every round is accounted for: 4 successful rounds and 2 failed rounds
+        assert_eq!(outcomes.len(), 6);
+        assert_eq!(outcomes.keys().cloned().collect::<Vec<_>>(), vec![
+            1, 2, 3, 4, 5, 6
+        ]);
+        assert_eq!(outcomes[&1], (epoch.validators[3], false));
+        assert_eq!(outcomes[&2], (epoch.validators[0], true));
+        assert_eq!(outcomes[&4], (epoch.validators[3], false));
+    }
+
+    #[test]
+    fn test_history_backend_window() {
+        let epoch = create_epoch(4, 10);
+        let simulator = LeaderReputationSimulator::new(vec![epoch]).unwrap();
+        let backend = HistoryBackend {
+            history: Arc::clone(&simulator.history),
+            window_size: 3,
+        };
+
+        // The window holds the most recent events at or before the target round
+        let rounds = |target_round| {
+            backend
+                .get_block_metadata(2, target_round)
+                .0
+                .iter()
+                .map(|event| event.round())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rounds(10), vec![9, 8, 6]);
+        assert_eq!(rounds(9), vec![9, 8, 6]);
+        assert_eq!(rounds(5), vec![5, 3, 2]);
+        assert_eq!(rounds(2), vec![2]);
+        assert!(rounds(1).is_empty());
+        assert_eq!(rounds(100), vec![15, 14, 12]);
+    }
+
+    #[test]
+    fn test_simulation() {
+        let epoch = create_epoch(4, 200);
+        let failing_validator = epoch.validators[3];
+        let simulator = LeaderReputationSimulator::new(vec![epoch]).unwrap();
+
+        let result = simulator.simulate(&create_config(1)).unwrap();
+        assert_eq!(result.total_rounds, 300);
+        assert_eq!(result.historical_failed_rounds, 100);
+        let total_simulated: u64 = result
+            .validators
+            .iter()
+            .map(|s| s.simulated_proposer_rounds)
+            .sum();
+        assert_eq!(total_simulated, result.total_rounds);
+
+        // The failing validator should be elected much less often than in the history
+        let failing_stats = result
+            .validators
+            .iter()
+            .find(|s| s.validator == failing_validator)
+            .unwrap();
+        assert_eq!(failing_stats.historical_proposer_rounds, 100);
+        assert!(failing_stats.simulated_proposer_rounds < 50);
+        assert!(result.expected_failed_rounds < result.historical_failed_rounds as f64);
+
+        // Not penalizing failures should elect the failing validator more often
+        let lenient_result = simulator.simulate(&create_config(1000)).unwrap();
+        let lenient_failing_stats = lenient_result
+            .validators
+            .iter()
+            .find(|s| s.validator == failing_validator)
+            .unwrap();
+        assert!(
+            lenient_failing_stats.simulated_proposer_rounds
+                > failing_stats.simulated_proposer_rounds
+        );
+    }
+}
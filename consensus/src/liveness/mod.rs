@@ -3,6 +3,7 @@
 
 pub(crate) mod cached_proposer_election;
 pub(crate) mod leader_reputation;
+pub mod leader_reputation_simulator;
 pub(crate) mod proposal_generator;
 pub(crate) mod proposer_election;
 pub(crate) mod rotating_proposer_election;
@@ -1,6 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::introspection::CandidateWeight;
 use aptos_consensus_types::common::{Author, Round};
 use aptos_fallible::copy_from_slice::copy_slice_to_vec;
 use num_traits::CheckedAdd;
@@ -33,6 +34,12 @@ pub trait ProposerElection {
             self.get_voting_power_participation_ratio(round),
         )
     }
+
+    /// Return the candidate weights used by the latest election (only available for
+    /// reputation based proposer elections).
+    fn get_latest_candidate_weights(&self) -> Option<Vec<CandidateWeight>> {
+        None
+    }
 }
 
 // next consumes seed and returns random deterministic u64 value in [0, max) range
@@ -3,6 +3,7 @@
 
 use crate::{
     counters,
+    introspection::RoundStateSnapshot,
     pending_votes::{PendingVotes, VoteReceptionResult},
     util::time_service::{SendTask, TimeService},
 };
@@ -295,6 +296,21 @@ impl RoundState {
         self.vote_sent.clone()
     }
 
+    /// Returns a snapshot of the round state (including the pending votes)
+    pub fn introspect(&self) -> RoundStateSnapshot {
+        RoundStateSnapshot {
+            current_round: self.current_round,
+            highest_committed_round: self.highest_committed_round,
+            current_round_deadline_ms: self.current_round_deadline.as_millis() as u64,
+            vote_sent_round: self
+                .vote_sent
+                .as_ref()
+                .map(|vote| vote.vote_data().proposed().round()),
+            vote_sent_is_timeout: self.is_vote_timeout(),
+            pending_votes: self.pending_votes.introspect(self.current_round),
+        }
+    }
+
     /// Setup a longer timeout task for leader because it enters the round earlier.
     pub fn setup_leader_timeout(&mut self) {
         self.setup_timeout(2);
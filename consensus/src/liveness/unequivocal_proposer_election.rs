@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::proposer_election::ProposerElection;
+use crate::introspection::CandidateWeight;
 use aptos_consensus_types::{
     block::Block,
     common::{Author, Round},
@@ -29,6 +30,10 @@ impl ProposerElection for UnequivocalProposerElection {
         self.proposer_election
             .get_voting_power_participation_ratio(round)
     }
+
+    fn get_latest_candidate_weights(&self) -> Option<Vec<CandidateWeight>> {
+        self.proposer_election.get_latest_candidate_weights()
+    }
 }
 
 impl UnequivocalProposerElection {
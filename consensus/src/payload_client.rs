@@ -7,7 +7,7 @@ use aptos_consensus_types::{
     common::{Payload, PayloadFilter, Round},
     request_response::{ConsensusResponse, PayloadRequest},
 };
-use aptos_logger::prelude::*;
+use aptos_logger::{prelude::*, span_export, txn_span};
 use fail::fail_point;
 use futures::{
     channel::{mpsc, oneshot},
@@ -93,6 +93,13 @@ impl PayloadClient for QuorumStoreClient {
         fail_point!("consensus::pull_payload", |_| {
             Err(anyhow::anyhow!("Injected error in pull_payload").into())
         });
+        let span = txn_span!(
+            "consensus.pull_payload",
+            round = round,
+            max_items = max_items,
+            max_bytes = max_bytes,
+            num_txns = span_export::Empty
+        );
         let mut callback_wrapper = Some(wait_callback);
         // keep polling QuorumStore until there's payloads available or there's still pending payloads
         let mut count = self.poll_count;
@@ -110,6 +117,7 @@ impl PayloadClient for QuorumStoreClient {
             }
             break payload;
         };
+        span.record("num_txns", &(payload.len() as u64));
         debug!(
             poll_count = self.poll_count - count,
             "Pull payloads from QuorumStore"
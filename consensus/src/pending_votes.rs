@@ -7,8 +7,9 @@
 //! when enough votes (or timeout votes) have been observed.
 //! Votes are automatically dropped when the structure goes out of scope.
 
+use crate::introspection::{LedgerInfoVotes, PendingVotesSnapshot};
 use aptos_consensus_types::{
-    common::Author,
+    common::{Author, Round},
     quorum_cert::QuorumCert,
     timeout_2chain::{TwoChainTimeoutCertificate, TwoChainTimeoutWithPartialSignatures},
     vote::Vote,
@@ -217,6 +218,31 @@ impl PendingVotes {
         VoteReceptionResult::VoteAdded(voting_power)
     }
 
+    /// Returns a snapshot of the votes collected so far for the given round
+    pub fn introspect(&self, round: Round) -> PendingVotesSnapshot {
+        let mut votes: Vec<_> = self
+            .li_digest_to_votes
+            .iter()
+            .map(|(li_digest, li)| LedgerInfoVotes {
+                ledger_info_digest: *li_digest,
+                voters: li.signatures().keys().cloned().collect(),
+            })
+            .collect();
+        votes.sort_by_key(|li_votes| li_votes.ledger_info_digest);
+
+        let timeout_voters = self
+            .maybe_partial_2chain_tc
+            .as_ref()
+            .map(|partial_tc| partial_tc.signers().cloned().collect())
+            .unwrap_or_default();
+
+        PendingVotesSnapshot {
+            round,
+            votes,
+            timeout_voters,
+        }
+    }
+
     pub fn drain_votes(
         &mut self,
     ) -> (
@@ -3,11 +3,12 @@
 
 use crate::{
     block_storage::{
-        tracing::{observe_block, BlockStage},
+        tracing::{observe_block, trace_block, BlockStage},
         BlockReader, BlockRetriever, BlockStore,
     },
     counters,
     error::{error_kind, VerifyError},
+    introspection::{self, ProposerElectionSnapshot},
     liveness::{
         proposal_generator::ProposalGenerator,
         proposer_election::ProposerElection,
@@ -289,6 +290,7 @@ impl RoundManager {
             self.new_log(LogEvent::NewRound),
             reason = new_round_event.reason
         );
+        self.publish_proposer_election(new_round_event.round);
 
         if self
             .proposer_election
@@ -400,6 +402,7 @@ impl RoundManager {
         let signed_proposal =
             Block::new_proposal_from_block_data_and_signature(proposal, signature);
         observe_block(signed_proposal.timestamp_usecs(), BlockStage::SIGNED);
+        trace_block(&signed_proposal, BlockStage::SIGNED);
         info!(self.new_log(LogEvent::Propose), "{}", signed_proposal);
         Ok(ProposalMsg::new(
             signed_proposal,
@@ -919,6 +922,7 @@ impl RoundManager {
         if let Err(e) = self.process_new_round_event(new_round_event).await {
             error!(error = ?e, "[RoundManager] Error during start");
         }
+        self.publish_round_state();
     }
 
     /// Inspect the current consensus state.
@@ -940,6 +944,29 @@ impl RoundManager {
         &self.round_state
     }
 
+    /// Publishes the round state (and the pending votes) for introspection
+    fn publish_round_state(&self) {
+        if introspection::is_enabled() {
+            introspection::publish_round_state(self.round_state.introspect());
+        }
+    }
+
+    /// Publishes the proposer election for the given round for introspection
+    fn publish_proposer_election(&self, round: Round) {
+        if !introspection::is_enabled() {
+            return;
+        }
+        let (proposer, voting_power_participation_ratio) = self
+            .proposer_election
+            .get_valid_proposer_and_voting_power_participation_ratio(round);
+        introspection::publish_proposer_election(ProposerElectionSnapshot {
+            round,
+            proposer,
+            voting_power_participation_ratio,
+            candidate_weights: self.proposer_election.get_latest_candidate_weights(),
+        });
+    }
+
     fn new_log(&self, event: LogEvent) -> LogSchema {
         LogSchema::new(event)
             .round(self.round_state.current_round())
@@ -989,6 +1016,7 @@ impl RoundManager {
                         unexpected_event => unreachable!("Unexpected event: {:?}", unexpected_event),
                     }
                     .with_context(|| format!("from peer {}", peer_id));
+                    self.publish_round_state();
 
                     let round_state = self.round_state();
                     match result {
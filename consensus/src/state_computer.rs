@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    block_storage::tracing::{observe_block, BlockStage},
+    block_storage::tracing::{observe_block, trace_block, trace_block_txns, BlockStage},
     counters,
     error::StateSyncError,
     monitor,
@@ -21,14 +21,14 @@ use aptos_consensus_types::{
 use aptos_crypto::HashValue;
 use aptos_executor_types::{BlockExecutorTrait, Error as ExecutionError, StateComputeResult};
 use aptos_infallible::Mutex;
-use aptos_logger::prelude::*;
+use aptos_logger::{prelude::*, span_export};
 use aptos_types::{
     account_address::AccountAddress, contract_event::ContractEvent, epoch_state::EpochState,
     ledger_info::LedgerInfoWithSignatures, transaction::Transaction,
 };
 use fail::fail_point;
 use futures::{SinkExt, StreamExt};
-use std::{boxed::Box, cmp::max, sync::Arc};
+use std::{boxed::Box, cmp::max, sync::Arc, time::SystemTime};
 use tokio::sync::Mutex as AsyncMutex;
 
 type NotificationType = (
@@ -116,6 +116,7 @@ impl StateComputer for ExecutionProxy {
         let transactions_to_execute =
             block.transactions_to_execute(&self.validators.lock(), txns.clone());
 
+        let execute_start = SystemTime::now();
         let compute_result = monitor!(
             "execute_block",
             tokio::task::spawn_blocking(move || {
@@ -125,6 +126,8 @@ impl StateComputer for ExecutionProxy {
         )
         .expect("spawn_blocking failed")?;
         observe_block(block.timestamp_usecs(), BlockStage::EXECUTED);
+        trace_block(block, BlockStage::EXECUTED);
+        trace_block_txns(block_id, &txns, BlockStage::EXECUTED, execute_start);
 
         // notify mempool about failed transaction
         if let Err(e) = self
@@ -156,6 +159,9 @@ impl StateComputer for ExecutionProxy {
         let mut latest_round: u64 = 0;
         let mut payloads = Vec::new();
 
+        let commit_start = SystemTime::now();
+        let mut traced_txns = Vec::new();
+
         let payload_manager = self.payload_manager.lock().as_ref().unwrap().clone();
         for block in blocks {
             block_ids.push(block.id());
@@ -165,6 +171,9 @@ impl StateComputer for ExecutionProxy {
             }
 
             let signed_txns = payload_manager.get_transactions(block.block()).await?;
+            if span_export::is_enabled() {
+                traced_txns.push((block.id(), signed_txns.clone()));
+            }
 
             txns.extend(block.transactions_to_commit(&self.validators.lock(), signed_txns));
             reconfig_events.extend(block.reconfig_event());
@@ -185,6 +194,9 @@ impl StateComputer for ExecutionProxy {
             .await
         )
         .expect("spawn_blocking failed");
+        for (block_id, signed_txns) in traced_txns {
+            trace_block_txns(block_id, &signed_txns, BlockStage::COMMITTED, commit_start);
+        }
 
         let blocks = blocks.to_vec();
         let wrapped_callback = move || {
@@ -0,0 +1,138 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::CompressionError;
+use aptos_infallible::Mutex;
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, sync::Arc};
+
+/// Zstd dictionaries are identified by a hash of their contents, so that
+/// the same dictionary has the same identifier on every node.
+pub type DictionaryId = u64;
+
+/// The max size of a dictionary. Dictionaries only help with small items,
+/// so anything larger is rejected (e.g., when it is sent by a peer).
+pub const MAX_DICTIONARY_BYTES: usize = 1024 * 1024; // 1 MiB
+
+/// The max number of dictionaries fetched from peers that are kept
+/// registered. The least recently used dictionaries are evicted first.
+pub const MAX_NUM_FETCHED_DICTIONARIES: usize = 32;
+
+/// The max number of dictionaries that can be requested (but not yet
+/// registered) at once. The oldest requests are dropped first.
+const MAX_NUM_REQUESTED_DICTIONARIES: usize = 32;
+
+/// The Zstd dictionaries known to this process
+struct DictionaryRegistry {
+    local: HashMap<DictionaryId, Arc<Vec<u8>>>, // Dictionaries trained by this process (never evicted)
+    fetched: LruCache<DictionaryId, Arc<Vec<u8>>>, // Dictionaries fetched from peers
+    requested: LruCache<DictionaryId, ()>, // Dictionaries requested from peers (not yet received)
+}
+
+static DICTIONARIES: Lazy<Mutex<DictionaryRegistry>> = Lazy::new(|| {
+    Mutex::new(DictionaryRegistry {
+        local: HashMap::new(),
+        fetched: LruCache::new(MAX_NUM_FETCHED_DICTIONARIES),
+        requested: LruCache::new(MAX_NUM_REQUESTED_DICTIONARIES),
+    })
+});
+
+/// Returns the identifier of the given dictionary
+pub fn get_dictionary_id(dictionary: &[u8]) -> DictionaryId {
+    let digest = Sha256::digest(dictionary);
+    let mut id_bytes = [0u8; 8];
+    id_bytes.copy_from_slice(&digest[..8]);
+    DictionaryId::from_le_bytes(id_bytes)
+}
+
+/// Registers a dictionary trained by this process (so that it can be used
+/// by the Zstd dictionary codec) and returns the dictionary identifier.
+/// Unlike fetched dictionaries, these are never evicted.
+pub fn register_dictionary(dictionary: Vec<u8>) -> Result<DictionaryId, CompressionError> {
+    ensure_dictionary_size(&dictionary)?;
+    let dictionary_id = get_dictionary_id(&dictionary);
+    DICTIONARIES
+        .lock()
+        .local
+        .entry(dictionary_id)
+        .or_insert_with(|| Arc::new(dictionary));
+    Ok(dictionary_id)
+}
+
+/// Records that the dictionary with the given identifier is being requested
+/// from a peer. Only requested dictionaries can be registered once fetched.
+pub fn request_dictionary(dictionary_id: DictionaryId) {
+    DICTIONARIES.lock().requested.put(dictionary_id, ());
+}
+
+/// Registers a dictionary fetched from a peer. The dictionary must have been
+/// requested (see [`request_dictionary`]) and must match the identifier.
+pub fn register_requested_dictionary(
+    dictionary_id: DictionaryId,
+    dictionary: Vec<u8>,
+) -> Result<(), CompressionError> {
+    let mut dictionaries = DICTIONARIES.lock();
+    if dictionaries.requested.pop(&dictionary_id).is_none() {
+        return Err(CompressionError(format!(
+            "The dictionary was not requested: {}",
+            dictionary_id
+        )));
+    }
+    ensure_dictionary_size(&dictionary)?;
+    if get_dictionary_id(&dictionary) != dictionary_id {
+        return Err(CompressionError(format!(
+            "The dictionary doesn't match the identifier: {}",
+            dictionary_id
+        )));
+    }
+    dictionaries
+        .fetched
+        .put(dictionary_id, Arc::new(dictionary));
+    Ok(())
+}
+
+/// Returns the registered dictionary with the given identifier (if any)
+pub fn get_dictionary(dictionary_id: DictionaryId) -> Option<Arc<Vec<u8>>> {
+    let mut dictionaries = DICTIONARIES.lock();
+    if let Some(dictionary) = dictionaries.local.get(&dictionary_id) {
+        return Some(dictionary.clone());
+    }
+    dictionaries.fetched.get(&dictionary_id).cloned()
+}
+
+/// Returns true iff a dictionary with the given identifier is registered
+pub fn is_dictionary_registered(dictionary_id: DictionaryId) -> bool {
+    let dictionaries = DICTIONARIES.lock();
+    dictionaries.local.contains_key(&dictionary_id) || dictionaries.fetched.contains(&dictionary_id)
+}
+
+/// Trains a Zstd dictionary (of at most `max_dictionary_bytes`) on the given samples
+pub fn train_dictionary(
+    samples: &[Vec<u8>],
+    max_dictionary_bytes: usize,
+) -> Result<Vec<u8>, CompressionError> {
+    zstd::dict::from_samples(samples, max_dictionary_bytes.min(MAX_DICTIONARY_BYTES))
+        .map_err(|error| CompressionError(format!("Failed to train the dictionary: {}", error)))
+}
+
+/// Returns the registered dictionary with the given identifier, or an error
+pub(crate) fn get_registered_dictionary(
+    dictionary_id: DictionaryId,
+) -> Result<Arc<Vec<u8>>, String> {
+    get_dictionary(dictionary_id)
+        .ok_or_else(|| format!("The dictionary is not registered: {}", dictionary_id))
+}
+
+/// Returns an error iff the dictionary is larger than the max dictionary size
+fn ensure_dictionary_size(dictionary: &[u8]) -> Result<(), CompressionError> {
+    if dictionary.len() > MAX_DICTIONARY_BYTES {
+        return Err(CompressionError(format!(
+            "The dictionary is too large! Size: {}, max: {}",
+            dictionary.len(),
+            MAX_DICTIONARY_BYTES
+        )));
+    }
+    Ok(())
+}
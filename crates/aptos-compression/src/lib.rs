@@ -1,25 +1,34 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::metrics::{
-    increment_compression_byte_count, increment_compression_error,
-    start_compression_operation_timer, CompressionClient, COMPRESS, COMPRESSED_BYTES, DECOMPRESS,
-    RAW_BYTES,
+use crate::{
+    dictionary::{get_registered_dictionary, DictionaryId},
+    metrics::{
+        increment_compression_byte_count, increment_compression_error,
+        start_compression_operation_timer, CompressionClient, COMPRESS, COMPRESSED_BYTES,
+        DECOMPRESS, RAW_BYTES,
+    },
 };
 use aptos_logger::prelude::*;
 use lz4::block::CompressionMode;
+use serde::{Deserialize, Serialize};
 use std::io::{Error, ErrorKind};
 use thiserror::Error;
 
 /// This crate provides a simple library interface for data compression.
 /// It is useful for compressing large data chunks that are
 /// sent across the network (e.g., by state sync and consensus).
-/// Internally, it uses LZ4 in fast mode to compress the data.
-/// See https://github.com/10xGenomics/lz4-rs for more information.
+/// By default, it uses LZ4 in fast mode to compress the data, but callers
+/// can also select LZ4 in high compression mode or Zstd (see [`CompressionCodec`]).
+/// Zstd can also use a dictionary (see [`dictionary`]), which improves the
+/// compression of small and similar items (e.g., state values).
+/// See https://github.com/10xGenomics/lz4-rs and https://github.com/gyscos/zstd-rs
+/// for more information.
 ///
 /// Note: the crate also exposes some basic compression metrics
 /// that can be used to track the cumulative compression ratio
 /// and compression/decompression durations during the runtime.
+pub mod dictionary;
 pub mod metrics;
 #[cfg(test)]
 mod tests;
@@ -28,6 +37,10 @@ mod tests;
 /// This was determined anecdotally.
 const ACCELERATION_PARAMETER: i32 = 1;
 
+/// The valid compression levels for each codec
+const LZ4_HIGH_COMPRESSION_LEVELS: std::ops::RangeInclusive<i32> = 1..=12;
+const ZSTD_COMPRESSION_LEVELS: std::ops::RangeInclusive<i32> = 1..=22;
+
 /// A useful wrapper for representing compressed data
 pub type CompressedData = Vec<u8>;
 
@@ -36,12 +49,90 @@ pub type CompressedData = Vec<u8>;
 #[error("Encountered a compression error! Error: {0}")]
 pub struct CompressionError(String);
 
-/// Compresses the raw data stream
+/// The compression codecs (and levels) that can be used to compress data.
+/// Higher levels trade compression (and decompression) speed for smaller
+/// compressed sizes, which is useful on bandwidth constrained links.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum CompressionCodec {
+    Lz4,                                   // LZ4 in fast mode
+    Lz4HighCompression(i32),               // LZ4 in high compression mode, with the given level
+    Zstd(i32),                             // Zstd with the given compression level
+    ZstdWithDictionary(i32, DictionaryId), // Zstd with the given compression level and dictionary
+}
+
+impl CompressionCodec {
+    /// Returns a summary label for the codec
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Self::Lz4 => "lz4",
+            Self::Lz4HighCompression(_) => "lz4_high_compression",
+            Self::Zstd(_) => "zstd",
+            Self::ZstdWithDictionary(_, _) => "zstd_with_dictionary",
+        }
+    }
+
+    /// Returns an error iff the compression level of the codec is invalid
+    pub fn validate(&self) -> Result<(), CompressionError> {
+        let (level, valid_levels) = match self {
+            Self::Lz4 => return Ok(()),
+            Self::Lz4HighCompression(level) => (level, LZ4_HIGH_COMPRESSION_LEVELS),
+            Self::Zstd(level) | Self::ZstdWithDictionary(level, _) => {
+                (level, ZSTD_COMPRESSION_LEVELS)
+            },
+        };
+        if valid_levels.contains(level) {
+            Ok(())
+        } else {
+            Err(CompressionError(format!(
+                "Invalid compression level for {}: {}, expected a level in {:?}",
+                self.get_label(),
+                level,
+                valid_levels
+            )))
+        }
+    }
+
+    /// Returns true iff the codec is offered by one of the given codecs. The
+    /// levels of the offered codecs are treated as the maximum supported levels.
+    pub fn is_supported_by(&self, offered_codecs: &[CompressionCodec]) -> bool {
+        offered_codecs
+            .iter()
+            .any(|offered_codec| match (self, offered_codec) {
+                (Self::Lz4, Self::Lz4) => true,
+                (Self::Lz4HighCompression(level), Self::Lz4HighCompression(max_level))
+                | (Self::Zstd(level), Self::Zstd(max_level)) => level <= max_level,
+                (
+                    Self::ZstdWithDictionary(level, dictionary_id),
+                    Self::ZstdWithDictionary(max_level, offered_dictionary_id),
+                ) => level <= max_level && dictionary_id == offered_dictionary_id,
+                _ => false,
+            })
+    }
+}
+
+impl Default for CompressionCodec {
+    fn default() -> Self {
+        Self::Lz4
+    }
+}
+
+/// Compresses the raw data stream (using LZ4 in fast mode)
 pub fn compress(
     raw_data: Vec<u8>,
     client: CompressionClient,
     max_bytes: usize,
 ) -> Result<CompressedData, CompressionError> {
+    compress_with_codec(raw_data, CompressionCodec::Lz4, client, max_bytes)
+}
+
+/// Compresses the raw data stream using the given codec
+pub fn compress_with_codec(
+    raw_data: Vec<u8>,
+    codec: CompressionCodec,
+    client: CompressionClient,
+    max_bytes: usize,
+) -> Result<CompressedData, CompressionError> {
+    codec.validate()?;
     if raw_data.len() > max_bytes {
         return Err(CompressionError(format!(
             "Uncompressed size greater than max. size: {}, max: {}",
@@ -53,15 +144,28 @@ pub fn compress(
     let timer = start_compression_operation_timer(COMPRESS, client.clone());
 
     // Compress the data
-    let compression_mode = CompressionMode::FAST(ACCELERATION_PARAMETER);
-    let compressed_data = match lz4::block::compress(&raw_data, Some(compression_mode), true) {
+    let compression_result = match codec {
+        CompressionCodec::Lz4 => {
+            let compression_mode = CompressionMode::FAST(ACCELERATION_PARAMETER);
+            lz4::block::compress(&raw_data, Some(compression_mode), true)
+                .map_err(|error| format!("Failed to compress the data: {}", error))
+        },
+        CompressionCodec::Lz4HighCompression(level) => {
+            let compression_mode = CompressionMode::HIGHCOMPRESSION(level);
+            lz4::block::compress(&raw_data, Some(compression_mode), true)
+                .map_err(|error| format!("Failed to compress the data: {}", error))
+        },
+        CompressionCodec::Zstd(level) => zstd::bulk::compress(&raw_data, level)
+            .map_err(|error| format!("Failed to compress the data: {}", error)),
+        CompressionCodec::ZstdWithDictionary(level, dictionary_id) => {
+            compress_zstd_with_dictionary(&raw_data, level, dictionary_id)
+        },
+    };
+    let compressed_data = match compression_result {
         Ok(compressed_data) => compressed_data,
         Err(error) => {
             increment_compression_error(COMPRESS, client);
-            return Err(CompressionError(format!(
-                "Failed to compress the data: {}",
-                error
-            )));
+            return Err(CompressionError(error));
         },
     };
 
@@ -84,61 +188,107 @@ pub fn compress(
     // Log the relative data compression statistics
     let relative_data_size = calculate_relative_size(&raw_data, &compressed_data);
     trace!(
-        "Compressed {} bytes to {} bytes ({} %) in {} seconds.",
+        "Compressed {} bytes to {} bytes ({} %) using {} in {} seconds.",
         raw_data.len(),
         compressed_data.len(),
         relative_data_size,
+        codec.get_label(),
         compression_duration
     );
 
     Ok(compressed_data)
 }
 
-/// Decompresses the compressed data stream
+/// Decompresses the compressed data stream (compressed using LZ4)
 pub fn decompress(
     compressed_data: &CompressedData,
     client: CompressionClient,
     max_size: usize,
+) -> Result<Vec<u8>, CompressionError> {
+    decompress_with_codec(compressed_data, CompressionCodec::Lz4, client, max_size)
+}
+
+/// Decompresses the data stream compressed using the given codec
+pub fn decompress_with_codec(
+    compressed_data: &CompressedData,
+    codec: CompressionCodec,
+    client: CompressionClient,
+    max_size: usize,
 ) -> Result<Vec<u8>, CompressionError> {
     // Start the decompression timer
     let timer = start_compression_operation_timer(DECOMPRESS, client.clone());
 
-    // Check size of the data and initialize raw_data
-    let size = match get_decompressed_size(compressed_data, max_size) {
-        Ok(size) => size,
+    // Decompress the data
+    let raw_data = match codec {
+        CompressionCodec::Lz4 | CompressionCodec::Lz4HighCompression(_) => {
+            decompress_lz4(compressed_data, max_size)
+        },
+        CompressionCodec::Zstd(_) => zstd::bulk::decompress(compressed_data, max_size)
+            .map_err(|error| format!("Failed to decompress the data: {}", error)),
+        CompressionCodec::ZstdWithDictionary(_, dictionary_id) => {
+            decompress_zstd_with_dictionary(compressed_data, dictionary_id, max_size)
+        },
+    };
+    let raw_data = match raw_data {
+        Ok(raw_data) => raw_data,
         Err(error) => {
             increment_compression_error(DECOMPRESS, client);
-            return Err(CompressionError(format!(
-                "Failed to get decompressed size: {}",
-                error
-            )));
+            return Err(CompressionError(error));
         },
     };
-    let mut raw_data = vec![0u8; size];
-
-    // Decompress the data
-    if let Err(error) = lz4::block::decompress_to_buffer(compressed_data, None, &mut raw_data) {
-        increment_compression_error(DECOMPRESS, client);
-        return Err(CompressionError(format!(
-            "Failed to decompress the data: {}",
-            error
-        )));
-    };
 
     // Stop the timer and log the relative data compression statistics
     let decompression_duration = timer.stop_and_record();
     let relative_data_size = calculate_relative_size(compressed_data, &raw_data);
     trace!(
-        "Decompressed {} bytes to {} bytes ({} %) in {} seconds.",
+        "Decompressed {} bytes to {} bytes ({} %) using {} in {} seconds.",
         compressed_data.len(),
         raw_data.len(),
         relative_data_size,
+        codec.get_label(),
         decompression_duration
     );
 
     Ok(raw_data)
 }
 
+/// Decompresses the LZ4 compressed data stream (regardless of the compression mode)
+fn decompress_lz4(compressed_data: &CompressedData, max_size: usize) -> Result<Vec<u8>, String> {
+    // Check size of the data and initialize raw_data
+    let size = get_decompressed_size(compressed_data, max_size)
+        .map_err(|error| format!("Failed to get decompressed size: {}", error))?;
+    let mut raw_data = vec![0u8; size];
+
+    // Decompress the data
+    lz4::block::decompress_to_buffer(compressed_data, None, &mut raw_data)
+        .map_err(|error| format!("Failed to decompress the data: {}", error))?;
+    Ok(raw_data)
+}
+
+/// Compresses the data using Zstd and the registered dictionary with the given identifier
+fn compress_zstd_with_dictionary(
+    raw_data: &[u8],
+    level: i32,
+    dictionary_id: DictionaryId,
+) -> Result<Vec<u8>, String> {
+    let dictionary = get_registered_dictionary(dictionary_id)?;
+    zstd::bulk::Compressor::with_dictionary(level, &dictionary)
+        .and_then(|mut compressor| compressor.compress(raw_data))
+        .map_err(|error| format!("Failed to compress the data: {}", error))
+}
+
+/// Decompresses the data using Zstd and the registered dictionary with the given identifier
+fn decompress_zstd_with_dictionary(
+    compressed_data: &CompressedData,
+    dictionary_id: DictionaryId,
+    max_size: usize,
+) -> Result<Vec<u8>, String> {
+    let dictionary = get_registered_dictionary(dictionary_id)?;
+    zstd::bulk::Decompressor::with_dictionary(&dictionary)
+        .and_then(|mut decompressor| decompressor.decompress(compressed_data, max_size))
+        .map_err(|error| format!("Failed to decompress the data: {}", error))
+}
+
 /// Derived from lz4-rs crate, which starts the compressed payload with the original data size as i32
 /// see: https://github.com/10XGenomics/lz4-rs/blob/0abc0a52af1f6010f9a57640b1dc8eb8d2d697aa/src/block/mod.rs#L162
 fn get_decompressed_size(src: &CompressedData, max_size: usize) -> std::io::Result<usize> {
@@ -1,7 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::CompressionClient;
+use crate::{dictionary, CompressionClient, CompressionCodec};
 use aptos_crypto::{ed25519::Ed25519PrivateKey, hash::HashValue, PrivateKey, SigningKey, Uniform};
 use aptos_types::{
     account_address::AccountAddress,
@@ -66,6 +66,184 @@ fn test_compression_limits() {
     assert!(maybe_decompressed_bytes.is_err());
 }
 
+#[test]
+fn test_compression_codecs() {
+    let codecs = [
+        CompressionCodec::Lz4,
+        CompressionCodec::Lz4HighCompression(9),
+        CompressionCodec::Zstd(1),
+        CompressionCodec::Zstd(19),
+    ];
+    for codec in codecs {
+        // Compress and decompress transactions with proof
+        let transactions_with_proof = create_transaction_list_with_proof(1000, 1999, 1999, true);
+        let bcs_encoded_bytes = bcs::to_bytes(&transactions_with_proof).unwrap();
+        let compressed_bytes = crate::compress_with_codec(
+            bcs_encoded_bytes.clone(),
+            codec,
+            CompressionClient::StateSync,
+            MAX_COMPRESSION_SIZE,
+        )
+        .unwrap();
+        assert!(compressed_bytes.len() < bcs_encoded_bytes.len());
+        let decompressed_bytes = crate::decompress_with_codec(
+            &compressed_bytes,
+            codec,
+            CompressionClient::StateSync,
+            MAX_COMPRESSION_SIZE,
+        )
+        .unwrap();
+        assert_eq!(bcs_encoded_bytes, decompressed_bytes);
+
+        // Verify the decompression limit is respected
+        let maybe_decompressed_bytes =
+            crate::decompress_with_codec(&compressed_bytes, codec, CompressionClient::StateSync, 1);
+        assert!(maybe_decompressed_bytes.is_err());
+    }
+}
+
+#[test]
+fn test_compression_codec_levels() {
+    // Verify invalid compression levels are rejected
+    for codec in [
+        CompressionCodec::Lz4HighCompression(0),
+        CompressionCodec::Lz4HighCompression(13),
+        CompressionCodec::Zstd(0),
+        CompressionCodec::Zstd(23),
+    ] {
+        assert!(codec.validate().is_err());
+        let maybe_compressed_bytes = crate::compress_with_codec(
+            vec![0; 100],
+            codec,
+            CompressionClient::StateSync,
+            MAX_COMPRESSION_SIZE,
+        );
+        assert!(maybe_compressed_bytes.is_err());
+    }
+
+    // Verify offered levels are treated as maximums
+    let offered_codecs = [CompressionCodec::Lz4, CompressionCodec::Zstd(3)];
+    assert!(CompressionCodec::Lz4.is_supported_by(&offered_codecs));
+    assert!(CompressionCodec::Zstd(1).is_supported_by(&offered_codecs));
+    assert!(CompressionCodec::Zstd(3).is_supported_by(&offered_codecs));
+    assert!(!CompressionCodec::Zstd(4).is_supported_by(&offered_codecs));
+    assert!(!CompressionCodec::Lz4HighCompression(1).is_supported_by(&offered_codecs));
+}
+
+#[test]
+fn test_zstd_dictionary() {
+    // Train a dictionary on (small) transactions
+    let samples: Vec<_> = (0..2000)
+        .map(|sequence_number| bcs::to_bytes(&create_test_transaction(sequence_number)).unwrap())
+        .collect();
+    let trained_dictionary = dictionary::train_dictionary(&samples, 16 * 1024).unwrap();
+    let dictionary_id = dictionary::get_dictionary_id(&trained_dictionary);
+
+    // Verify the dictionary can't be used before it is registered
+    let codec = CompressionCodec::ZstdWithDictionary(3, dictionary_id);
+    let raw_data = bcs::to_bytes(&create_test_transaction(5000)).unwrap();
+    assert!(!dictionary::is_dictionary_registered(dictionary_id));
+    assert!(crate::compress_with_codec(
+        raw_data.clone(),
+        codec,
+        CompressionClient::StateSync,
+        MAX_COMPRESSION_SIZE
+    )
+    .is_err());
+
+    // Register the dictionary and verify the identifier
+    assert_eq!(
+        dictionary::register_dictionary(trained_dictionary).unwrap(),
+        dictionary_id
+    );
+    assert!(dictionary::is_dictionary_registered(dictionary_id));
+
+    // Compress and decompress using the dictionary
+    let compressed_bytes = crate::compress_with_codec(
+        raw_data.clone(),
+        codec,
+        CompressionClient::StateSync,
+        MAX_COMPRESSION_SIZE,
+    )
+    .unwrap();
+    let decompressed_bytes = crate::decompress_with_codec(
+        &compressed_bytes,
+        codec,
+        CompressionClient::StateSync,
+        MAX_COMPRESSION_SIZE,
+    )
+    .unwrap();
+    assert_eq!(raw_data, decompressed_bytes);
+
+    // Verify the dictionary improves the compression of small items
+    let compressed_bytes_without_dictionary = crate::compress_with_codec(
+        raw_data,
+        CompressionCodec::Zstd(3),
+        CompressionClient::StateSync,
+        MAX_COMPRESSION_SIZE,
+    )
+    .unwrap();
+    assert!(compressed_bytes.len() < compressed_bytes_without_dictionary.len());
+
+    // Verify dictionaries are only supported if the identifiers match
+    let offered_codecs = [CompressionCodec::ZstdWithDictionary(9, dictionary_id)];
+    assert!(codec.is_supported_by(&offered_codecs));
+    assert!(!CompressionCodec::ZstdWithDictionary(3, dictionary_id + 1)
+        .is_supported_by(&offered_codecs));
+    assert!(!CompressionCodec::Zstd(3).is_supported_by(&offered_codecs));
+}
+
+#[test]
+fn test_fetched_dictionaries() {
+    // Create a dictionary (the contents don't matter for registration)
+    let create_dictionary = |index: u64| format!("test_fetched_dictionary_{}", index).into_bytes();
+    let fetched_dictionary = create_dictionary(0);
+    let dictionary_id = dictionary::get_dictionary_id(&fetched_dictionary);
+
+    // Verify dictionaries that weren't requested are rejected
+    assert!(
+        dictionary::register_requested_dictionary(dictionary_id, fetched_dictionary.clone())
+            .is_err()
+    );
+    assert!(!dictionary::is_dictionary_registered(dictionary_id));
+
+    // Verify dictionaries that don't match the requested identifier are rejected
+    dictionary::request_dictionary(dictionary_id);
+    assert!(
+        dictionary::register_requested_dictionary(dictionary_id, create_dictionary(1)).is_err()
+    );
+    assert!(!dictionary::is_dictionary_registered(dictionary_id));
+
+    // Verify dictionaries that are too large are rejected
+    let large_dictionary = vec![0; dictionary::MAX_DICTIONARY_BYTES + 1];
+    let large_dictionary_id = dictionary::get_dictionary_id(&large_dictionary);
+    dictionary::request_dictionary(large_dictionary_id);
+    assert!(
+        dictionary::register_requested_dictionary(large_dictionary_id, large_dictionary).is_err()
+    );
+    assert!(!dictionary::is_dictionary_registered(large_dictionary_id));
+
+    // Verify requested dictionaries are registered (but only once)
+    dictionary::request_dictionary(dictionary_id);
+    dictionary::register_requested_dictionary(dictionary_id, fetched_dictionary.clone()).unwrap();
+    assert!(dictionary::is_dictionary_registered(dictionary_id));
+    assert!(dictionary::register_requested_dictionary(dictionary_id, fetched_dictionary).is_err());
+
+    // Register more dictionaries than the registry holds, while using the first one
+    for index in 2..dictionary::MAX_NUM_FETCHED_DICTIONARIES as u64 + 2 {
+        let dictionary = create_dictionary(index);
+        let id = dictionary::get_dictionary_id(&dictionary);
+        dictionary::request_dictionary(id);
+        dictionary::register_requested_dictionary(id, dictionary).unwrap();
+        assert!(dictionary::get_dictionary(dictionary_id).is_some());
+    }
+
+    // Verify the least recently used dictionary was evicted
+    let evicted_dictionary_id = dictionary::get_dictionary_id(&create_dictionary(2));
+    assert!(dictionary::is_dictionary_registered(dictionary_id));
+    assert!(!dictionary::is_dictionary_registered(evicted_dictionary_id));
+}
+
 /// Ensures that the given object can be compressed and decompressed successfully
 /// when BCS encoded.
 fn test_compress_and_decompress<T: Debug + DeserializeOwned + PartialEq + Serialize>(object: T) {
@@ -97,6 +97,8 @@ impl FaucetCliArgs {
                 address: Some(account.to_hex_literal()),
                 pub_key: None,
                 return_txns: None,
+                challenge: None,
+                nonce: None,
             })
             .await;
             match response {
@@ -0,0 +1,887 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Protection of the faucet against being drained by scripts
+//!
+//! Mint requests are checked in order against:
+//! 1. A deny list of IPs and addresses, which are always rejected
+//! 2. An allow list of IPs and addresses, which skip the other checks
+//! 3. An optional proof of work, solving a challenge from `/challenge`
+//! 4. Quotas of requests and amounts per IP and per address over a time window
+//!
+//! Accepted requests count towards the quotas while they are being minted, and are kept in a
+//! history once minted, which can be persisted to a local file of JSON lines so that the quotas
+//! survive restarts.
+
+use anyhow::{ensure, Context, Result};
+use aptos_crypto::HashValue;
+use aptos_logger::warn;
+use aptos_sdk::types::account_address::AccountAddress;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AbuseProtectionConfig {
+    /// Quota of each requesting IP
+    pub ip_quota: Option<QuotaConfig>,
+    /// Quota of each receiving address
+    pub address_quota: Option<QuotaConfig>,
+    /// IPs and addresses that skip the proof of work and quotas
+    pub allow_list: AccessList,
+    /// IPs and addresses that are always rejected
+    pub deny_list: AccessList,
+    /// Requires solving a challenge before each mint if set
+    pub proof_of_work: Option<ProofOfWorkConfig>,
+    /// File to persist the request history to, it's only kept in memory if not set
+    pub history_file: Option<PathBuf>,
+    /// Number of trusted proxies in front of the faucet, which each append the address they
+    /// received the request from to the `X-Forwarded-For` header. The requester IP is the entry
+    /// that many from the right, the entries to the left of it can be set by anyone. The header
+    /// is ignored if 0.
+    pub trusted_proxy_hops: usize,
+}
+
+impl AbuseProtectionConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read abuse protection config {:?}", path))?;
+        let config: AbuseProtectionConfig = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse abuse protection config {:?}", path))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (name, quota) in [
+            ("ip_quota", &self.ip_quota),
+            ("address_quota", &self.address_quota),
+        ] {
+            if let Some(quota) = quota {
+                ensure!(
+                    quota.window_secs > 0,
+                    "{} window_secs must be positive",
+                    name
+                );
+                ensure!(
+                    quota.max_requests.is_some() || quota.max_amount.is_some(),
+                    "{} must set max_requests or max_amount",
+                    name
+                );
+            }
+        }
+        if let Some(proof_of_work) = &self.proof_of_work {
+            ensure!(
+                proof_of_work.difficulty <= HashValue::LENGTH_IN_BITS,
+                "proof_of_work difficulty must be at most {} bits",
+                HashValue::LENGTH_IN_BITS
+            );
+            ensure!(
+                proof_of_work.challenge_ttl_secs > 0,
+                "proof_of_work challenge_ttl_secs must be positive"
+            );
+        }
+        Ok(())
+    }
+
+    /// The longest window of the quotas, as older requests don't need to be kept
+    fn max_window_secs(&self) -> u64 {
+        [&self.ip_quota, &self.address_quota]
+            .into_iter()
+            .flatten()
+            .map(|quota| quota.window_secs)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// Limits of the requests within a sliding time window
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuotaConfig {
+    pub window_secs: u64,
+    /// Maximum number of requests in the window
+    pub max_requests: Option<u64>,
+    /// Maximum total amount minted in the window
+    pub max_amount: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AccessList {
+    pub ips: Vec<IpAddr>,
+    pub addresses: Vec<AccountAddress>,
+}
+
+impl AccessList {
+    fn contains(&self, ip: Option<IpAddr>, address: AccountAddress) -> bool {
+        ip.map_or(false, |ip| self.ips.contains(&ip)) || self.addresses.contains(&address)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProofOfWorkConfig {
+    /// Number of leading zero bits required of the solution's hash
+    pub difficulty: usize,
+    /// How long a challenge can be used for
+    pub challenge_ttl_secs: u64,
+}
+
+/// A proof of work challenge, solved by finding a `nonce` where the SHA3-256 of the challenge
+/// bytes followed by the little endian bytes of the `nonce` has `difficulty` leading zero bits
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Challenge {
+    pub challenge: HashValue,
+    pub difficulty: usize,
+    pub expiration_timestamp_secs: u64,
+}
+
+/// A request accepted by the faucet
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MintRecord {
+    pub timestamp_secs: u64,
+    pub ip: Option<IpAddr>,
+    pub address: AccountAddress,
+    pub amount: u64,
+}
+
+/// Why a mint request was rejected
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MintRejection {
+    Denied,
+    InvalidProofOfWork(&'static str),
+    QuotaExceeded(String),
+}
+
+impl MintRejection {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            MintRejection::Denied => StatusCode::FORBIDDEN,
+            MintRejection::InvalidProofOfWork(_) => StatusCode::BAD_REQUEST,
+            MintRejection::QuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+}
+
+impl fmt::Display for MintRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MintRejection::Denied => write!(f, "Requester is not allowed to use the faucet"),
+            MintRejection::InvalidProofOfWork(reason) => {
+                write!(f, "Invalid proof of work: {}", reason)
+            },
+            MintRejection::QuotaExceeded(quota) => write!(f, "Quota exceeded: {}", quota),
+        }
+    }
+}
+
+/// The history file is compacted once it has this many times more lines than records in the
+/// quota windows
+const HISTORY_COMPACTION_RATIO: usize = 2;
+/// Small histories are not worth compacting
+const MIN_HISTORY_LINES_TO_COMPACT: usize = 1000;
+/// Unused challenges are kept until they expire, so `/challenge` only issues this many at once
+const MAX_OUTSTANDING_CHALLENGES: usize = 100_000;
+/// Limit of unused challenges per requesting IP, so that one requester can't use them all up
+const MAX_OUTSTANDING_CHALLENGES_PER_IP: usize = 10;
+
+struct RequestHistory {
+    /// Minted requests
+    records: VecDeque<MintRecord>,
+    /// Requests being minted, which count towards the quotas as well
+    pending: HashMap<u64, MintRecord>,
+    next_pending_id: u64,
+    file: Option<HistoryFile>,
+}
+
+struct HistoryFile {
+    path: PathBuf,
+    file: File,
+    num_lines: usize,
+}
+
+impl HistoryFile {
+    fn append(&mut self, record: &MintRecord) -> Result<()> {
+        append_record(&mut self.file, record)?;
+        self.num_lines += 1;
+        Ok(())
+    }
+
+    /// Rewrites the file with only the records still in the quota windows, as expired ones are
+    /// never read again
+    fn compact<'a>(
+        &mut self,
+        records: impl ExactSizeIterator<Item = &'a MintRecord>,
+    ) -> Result<()> {
+        let num_lines = records.len();
+        self.file = rewrite_history(&self.path, records)?;
+        self.num_lines = num_lines;
+        Ok(())
+    }
+}
+
+/// A mint request that passed the checks. It counts towards the quotas until it's either
+/// recorded with `minted()` or dropped because the mint failed.
+pub struct MintReservation<'a> {
+    protection: &'a AbuseProtection,
+    id: u64,
+}
+
+impl MintReservation<'_> {
+    /// Moves the request to the history once its coins are minted
+    pub fn minted(self) {
+        let mut history = self.protection.history.lock().unwrap();
+        if let Some(record) = history.pending.remove(&self.id) {
+            self.protection.record(&mut history, record);
+        }
+    }
+}
+
+impl Drop for MintReservation<'_> {
+    fn drop(&mut self) {
+        self.protection
+            .history
+            .lock()
+            .unwrap()
+            .pending
+            .remove(&self.id);
+    }
+}
+
+pub struct AbuseProtection {
+    config: AbuseProtectionConfig,
+    history: Mutex<RequestHistory>,
+    /// Unused challenges, with their expiration time and the IP they were issued to
+    challenges: Mutex<HashMap<HashValue, (u64, Option<IpAddr>)>>,
+}
+
+impl AbuseProtection {
+    pub fn new(config: AbuseProtectionConfig) -> Result<Self> {
+        config.validate()?;
+        let (records, file) = if let Some(path) = &config.history_file {
+            let (records, file) =
+                load_history(path, now_secs().saturating_sub(config.max_window_secs()))?;
+            let file = HistoryFile {
+                path: path.clone(),
+                file,
+                num_lines: records.len(),
+            };
+            (records, Some(file))
+        } else {
+            (VecDeque::new(), None)
+        };
+
+        Ok(AbuseProtection {
+            config,
+            history: Mutex::new(RequestHistory {
+                records,
+                pending: HashMap::new(),
+                next_pending_id: 0,
+                file,
+            }),
+            challenges: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The IP of the requester. Behind trusted proxies, it's the entry of the `X-Forwarded-For`
+    /// header added by the outermost one. Falls back to the address of the connection if the
+    /// header is missing or doesn't have a valid IP there, so the request is still subject to the
+    /// IP quota and deny list, albeit as the proxy.
+    pub fn requester_ip(
+        &self,
+        remote: Option<SocketAddr>,
+        forwarded_for: Option<&str>,
+    ) -> Option<IpAddr> {
+        let hops = self.config.trusted_proxy_hops;
+        if hops > 0 {
+            let forwarded_ip = forwarded_for.and_then(|forwarded_for| {
+                forwarded_for
+                    .rsplit(',')
+                    .nth(hops - 1)
+                    .and_then(|ip| ip.trim().parse().ok())
+            });
+            match forwarded_ip {
+                Some(ip) => return Some(ip),
+                None => warn!(
+                    "Invalid X-Forwarded-For {:?} from {:?} with {} trusted proxies",
+                    forwarded_for, remote, hops
+                ),
+            }
+        }
+        remote.map(|remote| remote.ip())
+    }
+
+    /// Issues a new proof of work challenge to `ip`, if proof of work is required. Rejected if
+    /// `ip`, or all the requesters together, already have too many unused challenges.
+    pub fn new_challenge(&self, ip: Option<IpAddr>) -> Result<Option<Challenge>, MintRejection> {
+        self.new_challenge_at(now_secs(), ip)
+    }
+
+    fn new_challenge_at(
+        &self,
+        now: u64,
+        ip: Option<IpAddr>,
+    ) -> Result<Option<Challenge>, MintRejection> {
+        let proof_of_work = match &self.config.proof_of_work {
+            Some(proof_of_work) => proof_of_work,
+            None => return Ok(None),
+        };
+        let challenge = HashValue::random();
+        let expiration_timestamp_secs = now + proof_of_work.challenge_ttl_secs;
+
+        let mut challenges = self.challenges.lock().unwrap();
+        challenges.retain(|_, (expiration, _)| *expiration > now);
+        if challenges
+            .values()
+            .filter(|(_, challenge_ip)| *challenge_ip == ip)
+            .count()
+            >= MAX_OUTSTANDING_CHALLENGES_PER_IP
+        {
+            return Err(MintRejection::QuotaExceeded(format!(
+                "at most {} unused challenges per IP",
+                MAX_OUTSTANDING_CHALLENGES_PER_IP
+            )));
+        }
+        if challenges.len() >= MAX_OUTSTANDING_CHALLENGES {
+            return Err(MintRejection::QuotaExceeded(
+                "too many unused challenges, try again later".to_string(),
+            ));
+        }
+        challenges.insert(challenge, (expiration_timestamp_secs, ip));
+        Ok(Some(Challenge {
+            challenge,
+            difficulty: proof_of_work.difficulty,
+            expiration_timestamp_secs,
+        }))
+    }
+
+    /// Checks a mint request, and reserves its share of the quotas if it's accepted
+    pub fn check(
+        &self,
+        ip: Option<IpAddr>,
+        address: AccountAddress,
+        amount: u64,
+        challenge: Option<&str>,
+        nonce: Option<u64>,
+    ) -> Result<MintReservation<'_>, MintRejection> {
+        self.check_at(now_secs(), ip, address, amount, challenge, nonce)
+    }
+
+    fn check_at(
+        &self,
+        now: u64,
+        ip: Option<IpAddr>,
+        address: AccountAddress,
+        amount: u64,
+        challenge: Option<&str>,
+        nonce: Option<u64>,
+    ) -> Result<MintReservation<'_>, MintRejection> {
+        if self.config.deny_list.contains(ip, address) {
+            return Err(MintRejection::Denied);
+        }
+
+        let record = MintRecord {
+            timestamp_secs: now,
+            ip,
+            address,
+            amount,
+        };
+        let mut history = self.history.lock().unwrap();
+        let oldest = now.saturating_sub(self.config.max_window_secs());
+        while matches!(history.records.front(), Some(record) if record.timestamp_secs < oldest) {
+            history.records.pop_front();
+        }
+
+        if !self.config.allow_list.contains(ip, address) {
+            if let Some(proof_of_work) = &self.config.proof_of_work {
+                self.verify_proof_of_work(now, proof_of_work.difficulty, challenge, nonce)?;
+            }
+            let records = || history.records.iter().chain(history.pending.values());
+            if let (Some(quota), Some(ip)) = (&self.config.ip_quota, ip) {
+                check_quota(records(), now, quota, amount, "IP", |record| {
+                    record.ip == Some(ip)
+                })?;
+            }
+            if let Some(quota) = &self.config.address_quota {
+                check_quota(records(), now, quota, amount, "address", |record| {
+                    record.address == address
+                })?;
+            }
+        }
+
+        let id = history.next_pending_id;
+        history.next_pending_id += 1;
+        history.pending.insert(id, record);
+        Ok(MintReservation {
+            protection: self,
+            id,
+        })
+    }
+
+    /// Adds a minted request to the history
+    fn record(&self, history: &mut RequestHistory, record: MintRecord) {
+        // A failure to persist shouldn't stop the faucet
+        if let Some(file) = history.file.as_mut() {
+            if let Err(err) = file.append(&record) {
+                warn!("Failed to persist mint request {:?}: {:#}", record, err);
+            }
+        }
+        history.records.push_back(record);
+
+        let RequestHistory { records, file, .. } = history;
+        if let Some(file) = file {
+            if file.num_lines >= MIN_HISTORY_LINES_TO_COMPACT
+                && file.num_lines > records.len() * HISTORY_COMPACTION_RATIO
+            {
+                if let Err(err) = file.compact(records.iter()) {
+                    warn!("Failed to compact mint request history: {:#}", err);
+                }
+            }
+        }
+    }
+
+    /// Checks the proof of work, and uses up its challenge
+    fn verify_proof_of_work(
+        &self,
+        now: u64,
+        difficulty: usize,
+        challenge: Option<&str>,
+        nonce: Option<u64>,
+    ) -> Result<(), MintRejection> {
+        let (challenge, nonce) = match (challenge, nonce) {
+            (Some(challenge), Some(nonce)) => (challenge, nonce),
+            _ => {
+                return Err(MintRejection::InvalidProofOfWork(
+                    "'challenge' and 'nonce' are required, get a challenge from /challenge",
+                ))
+            },
+        };
+        let challenge = HashValue::from_hex(challenge.trim_start_matches("0x"))
+            .map_err(|_| MintRejection::InvalidProofOfWork("challenge is not a valid hash"))?;
+
+        let mut challenges = self.challenges.lock().unwrap();
+        match challenges.get(&challenge) {
+            Some((expiration, _)) if *expiration > now => {},
+            _ => {
+                return Err(MintRejection::InvalidProofOfWork(
+                    "challenge is unknown, expired or already used",
+                ))
+            },
+        }
+        if !is_solution(challenge, nonce, difficulty) {
+            return Err(MintRejection::InvalidProofOfWork(
+                "nonce doesn't solve the challenge",
+            ));
+        }
+        challenges.remove(&challenge);
+        Ok(())
+    }
+}
+
+/// Whether the nonce solves the challenge at the given difficulty
+pub fn is_solution(challenge: HashValue, nonce: u64, difficulty: usize) -> bool {
+    let mut bytes = challenge.to_vec();
+    bytes.extend_from_slice(&nonce.to_le_bytes());
+    HashValue::sha3_256_of(&bytes).common_prefix_bits_len(HashValue::zero()) >= difficulty
+}
+
+fn check_quota<'a, F: Fn(&MintRecord) -> bool>(
+    records: impl Iterator<Item = &'a MintRecord>,
+    now: u64,
+    quota: &QuotaConfig,
+    amount: u64,
+    name: &str,
+    matches: F,
+) -> Result<(), MintRejection> {
+    let window_start = now.saturating_sub(quota.window_secs);
+    let (requests, total_amount) = records
+        .filter(|record| record.timestamp_secs >= window_start && matches(record))
+        .fold((0u64, 0u64), |(requests, total_amount), record| {
+            (requests + 1, total_amount.saturating_add(record.amount))
+        });
+
+    if let Some(max_requests) = quota.max_requests {
+        if requests >= max_requests {
+            return Err(MintRejection::QuotaExceeded(format!(
+                "at most {} requests per {} every {} seconds",
+                max_requests, name, quota.window_secs
+            )));
+        }
+    }
+    if let Some(max_amount) = quota.max_amount {
+        if total_amount.saturating_add(amount) > max_amount {
+            return Err(MintRejection::QuotaExceeded(format!(
+                "at most {} coins per {} every {} seconds",
+                max_amount, name, quota.window_secs
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Loads the records that are still within the quota windows, and rewrites the file with only
+/// those so that it doesn't grow forever
+fn load_history(path: &Path, oldest: u64) -> Result<(VecDeque<MintRecord>, File)> {
+    let mut records = VecDeque::new();
+    if path.exists() {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open mint request history {:?}", path))?;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            match serde_json::from_str::<MintRecord>(&line) {
+                Ok(record) if record.timestamp_secs >= oldest => records.push_back(record),
+                Ok(_) => {},
+                Err(err) => warn!("Skipping invalid mint request record {:?}: {}", line, err),
+            }
+        }
+    }
+
+    let file = rewrite_history(path, records.iter())?;
+    Ok((records, file))
+}
+
+/// Replaces the history file with `records` through a temporary file, so that a crash while
+/// rewriting it doesn't lose the history, and reopens it for appending
+fn rewrite_history<'a>(path: &Path, records: impl Iterator<Item = &'a MintRecord>) -> Result<File> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = File::create(&tmp_path)
+        .with_context(|| format!("Failed to create mint request history {:?}", tmp_path))?;
+    for record in records {
+        append_record(&mut file, record)?;
+    }
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)?;
+
+    Ok(OpenOptions::new().append(true).open(path)?)
+}
+
+fn append_record(file: &mut File, record: &MintRecord) -> Result<()> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+    Ok(())
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_000_000;
+
+    fn ip(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::from([10, 0, 0, last]))
+    }
+
+    fn quota(max_requests: Option<u64>, max_amount: Option<u64>) -> Option<QuotaConfig> {
+        Some(QuotaConfig {
+            window_secs: 100,
+            max_requests,
+            max_amount,
+        })
+    }
+
+    fn solve(challenge: &Challenge) -> u64 {
+        (0..)
+            .find(|nonce| is_solution(challenge.challenge, *nonce, challenge.difficulty))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_quotas() {
+        let protection = AbuseProtection::new(AbuseProtectionConfig {
+            ip_quota: quota(Some(2), None),
+            address_quota: quota(None, Some(100)),
+            ..Default::default()
+        })
+        .unwrap();
+        let address = AccountAddress::random();
+        let check =
+            |now, ip, address, amount| protection.check_at(now, ip, address, amount, None, None);
+
+        check(NOW, ip(1), address, 60).unwrap().minted();
+        // Over the amount of the address, but not the requests of the IP
+        assert!(matches!(
+            check(NOW + 1, ip(1), address, 50),
+            Err(MintRejection::QuotaExceeded(_))
+        ));
+        check(NOW + 1, ip(1), AccountAddress::random(), 50)
+            .unwrap()
+            .minted();
+        // Over the requests of the IP
+        assert!(matches!(
+            check(NOW + 2, ip(1), AccountAddress::random(), 1),
+            Err(MintRejection::QuotaExceeded(_))
+        ));
+        check(NOW + 2, ip(2), AccountAddress::random(), 1)
+            .unwrap()
+            .minted();
+        // Unknown IPs only have the address quota
+        check(NOW + 2, None, AccountAddress::random(), 1)
+            .unwrap()
+            .minted();
+
+        // Once the window passes, requests are allowed again
+        check(NOW + 101, ip(1), address, 100).unwrap().minted();
+    }
+
+    #[test]
+    fn test_access_lists() {
+        let allowed = AccountAddress::random();
+        let denied = AccountAddress::random();
+        let protection = AbuseProtection::new(AbuseProtectionConfig {
+            ip_quota: quota(Some(1), None),
+            allow_list: AccessList {
+                ips: vec![ip(1).unwrap()],
+                addresses: vec![allowed],
+            },
+            deny_list: AccessList {
+                ips: vec![ip(2).unwrap()],
+                addresses: vec![denied],
+            },
+            ..Default::default()
+        })
+        .unwrap();
+
+        for _ in 0..3 {
+            protection
+                .check_at(NOW, ip(1), AccountAddress::random(), 1, None, None)
+                .unwrap()
+                .minted();
+            protection
+                .check_at(NOW, ip(3), allowed, 1, None, None)
+                .unwrap()
+                .minted();
+        }
+        assert!(matches!(
+            protection.check_at(NOW, ip(2), allowed, 1, None, None),
+            Err(MintRejection::Denied)
+        ));
+        assert!(matches!(
+            protection.check_at(NOW, ip(1), denied, 1, None, None),
+            Err(MintRejection::Denied)
+        ));
+    }
+
+    #[test]
+    fn test_proof_of_work() {
+        let protection = AbuseProtection::new(AbuseProtectionConfig {
+            proof_of_work: Some(ProofOfWorkConfig {
+                difficulty: 8,
+                challenge_ttl_secs: 10,
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+        let address = AccountAddress::random();
+        let check = |now, challenge: &Challenge, nonce| {
+            protection.check_at(
+                now,
+                ip(1),
+                address,
+                1,
+                Some(&challenge.challenge.to_hex()),
+                Some(nonce),
+            )
+        };
+
+        assert!(matches!(
+            protection.check_at(NOW, ip(1), address, 1, None, None),
+            Err(MintRejection::InvalidProofOfWork(_))
+        ));
+
+        let challenge = protection.new_challenge_at(NOW, ip(1)).unwrap().unwrap();
+        let nonce = solve(&challenge);
+        let wrong_nonce = (0..)
+            .find(|nonce| !is_solution(challenge.challenge, *nonce, challenge.difficulty))
+            .unwrap();
+        assert!(check(NOW, &challenge, wrong_nonce).is_err());
+        check(NOW, &challenge, nonce).unwrap();
+        // Challenges can only be used once
+        assert!(check(NOW, &challenge, nonce).is_err());
+
+        // Challenges expire
+        let challenge = protection.new_challenge_at(NOW, ip(1)).unwrap().unwrap();
+        let nonce = solve(&challenge);
+        assert!(check(NOW + 10, &challenge, nonce).is_err());
+    }
+
+    #[test]
+    fn test_challenge_limits() {
+        let protection = AbuseProtection::new(AbuseProtectionConfig {
+            proof_of_work: Some(ProofOfWorkConfig {
+                difficulty: 8,
+                challenge_ttl_secs: 10,
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let first = protection.new_challenge_at(NOW, ip(1)).unwrap().unwrap();
+        for _ in 1..MAX_OUTSTANDING_CHALLENGES_PER_IP {
+            protection.new_challenge_at(NOW, ip(1)).unwrap().unwrap();
+        }
+        assert!(matches!(
+            protection.new_challenge_at(NOW, ip(1)),
+            Err(MintRejection::QuotaExceeded(_))
+        ));
+        // Other IPs still get challenges
+        protection.new_challenge_at(NOW, ip(2)).unwrap().unwrap();
+
+        // Using a challenge frees up its slot
+        protection
+            .check_at(
+                NOW,
+                ip(1),
+                AccountAddress::random(),
+                1,
+                Some(&first.challenge.to_hex()),
+                Some(solve(&first)),
+            )
+            .unwrap();
+        protection.new_challenge_at(NOW, ip(1)).unwrap().unwrap();
+
+        // So does expiring
+        assert!(protection.new_challenge_at(NOW, ip(1)).is_err());
+        protection
+            .new_challenge_at(NOW + 10, ip(1))
+            .unwrap()
+            .unwrap();
+
+        // The total is limited as well
+        let mut challenges = protection.challenges.lock().unwrap();
+        for _ in challenges.len()..MAX_OUTSTANDING_CHALLENGES {
+            challenges.insert(HashValue::random(), (NOW + 20, None));
+        }
+        drop(challenges);
+        assert!(matches!(
+            protection.new_challenge_at(NOW + 10, ip(3)),
+            Err(MintRejection::QuotaExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_persisted_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AbuseProtectionConfig {
+            address_quota: quota(Some(1), None),
+            history_file: Some(dir.path().join("history.jsonl")),
+            ..Default::default()
+        };
+        let address = AccountAddress::random();
+
+        let now = now_secs();
+        let protection = AbuseProtection::new(config.clone()).unwrap();
+        protection
+            .check_at(now - 1000, None, address, 1, None, None)
+            .unwrap()
+            .minted();
+        protection
+            .check_at(now, None, address, 1, None, None)
+            .unwrap()
+            .minted();
+        drop(protection);
+
+        // The quota still applies after a restart, and only the recent request is kept
+        let protection = AbuseProtection::new(config).unwrap();
+        assert!(matches!(
+            protection.check_at(now, None, address, 1, None, None),
+            Err(MintRejection::QuotaExceeded(_))
+        ));
+        assert_eq!(protection.history.lock().unwrap().records.len(), 1);
+    }
+
+    #[test]
+    fn test_reservations() {
+        let protection = AbuseProtection::new(AbuseProtectionConfig {
+            address_quota: quota(Some(1), None),
+            ..Default::default()
+        })
+        .unwrap();
+        let address = AccountAddress::random();
+        let check = || protection.check_at(NOW, ip(1), address, 1, None, None);
+
+        // A request being minted counts towards the quota
+        let reservation = check().unwrap();
+        assert!(matches!(check(), Err(MintRejection::QuotaExceeded(_))));
+        // But not if the mint fails
+        drop(reservation);
+        assert!(protection.history.lock().unwrap().records.is_empty());
+        check().unwrap().minted();
+        assert!(matches!(check(), Err(MintRejection::QuotaExceeded(_))));
+        assert_eq!(protection.history.lock().unwrap().records.len(), 1);
+    }
+
+    #[test]
+    fn test_requester_ip() {
+        let remote = Some(SocketAddr::from(([192, 168, 0, 1], 8000)));
+        let requester_ip = |trusted_proxy_hops, forwarded_for| {
+            AbuseProtection::new(AbuseProtectionConfig {
+                trusted_proxy_hops,
+                ..Default::default()
+            })
+            .unwrap()
+            .requester_ip(remote, forwarded_for)
+        };
+        let remote_ip = remote.map(|remote| remote.ip());
+
+        assert_eq!(requester_ip(0, Some("10.0.0.1")), remote_ip);
+        // The left-most entries are set by the client
+        assert_eq!(requester_ip(1, Some("1.1.1.1, 10.0.0.1")), ip(1));
+        assert_eq!(requester_ip(2, Some("1.1.1.1, 10.0.0.1, 10.0.0.2")), ip(1));
+        // Anything unexpected falls back to the proxy
+        assert_eq!(requester_ip(1, None), remote_ip);
+        assert_eq!(requester_ip(1, Some("1.1.1.1, garbage")), remote_ip);
+        assert_eq!(requester_ip(3, Some("10.0.0.1, 10.0.0.2")), remote_ip);
+    }
+
+    #[test]
+    fn test_history_compaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let protection = AbuseProtection::new(AbuseProtectionConfig {
+            address_quota: quota(Some(1), None),
+            history_file: Some(path.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+        let num_lines = || std::fs::read_to_string(&path).unwrap().lines().count();
+
+        // Requests in a single window accumulate
+        let now = now_secs();
+        for _ in 0..MIN_HISTORY_LINES_TO_COMPACT {
+            protection
+                .check_at(now, None, AccountAddress::random(), 1, None, None)
+                .unwrap()
+                .minted();
+        }
+        assert_eq!(num_lines(), MIN_HISTORY_LINES_TO_COMPACT);
+
+        // Expired ones are dropped from the file as well
+        for _ in 0..10 {
+            protection
+                .check_at(now + 1000, None, AccountAddress::random(), 1, None, None)
+                .unwrap()
+                .minted();
+        }
+        assert!(num_lines() <= 10);
+        assert_eq!(protection.history.lock().unwrap().records.len(), 10);
+    }
+}
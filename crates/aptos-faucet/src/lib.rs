@@ -22,10 +22,14 @@
 //! cargo run -p aptos-faucet -- -h
 //! ```
 
+use crate::{
+    abuse::{AbuseProtection, AbuseProtectionConfig},
+    pool::MinterPool,
+};
 use anyhow::Result;
 use aptos_config::keys::ConfigKey;
 use aptos_crypto::ed25519::Ed25519PrivateKey;
-use aptos_logger::info;
+use aptos_logger::{info, warn};
 use aptos_rest_client::Client;
 use aptos_sdk::{
     transaction_builder::{aptos_stdlib, TransactionFactory},
@@ -35,13 +39,14 @@ use aptos_sdk::{
     },
 };
 use clap::Parser;
-use futures::lock::Mutex;
 use reqwest::StatusCode;
 use std::{convert::Infallible, path::PathBuf, sync::Arc};
 use url::Url;
 use warp::{http, Filter, Rejection, Reply};
 
+pub mod abuse;
 pub mod mint;
+pub mod pool;
 
 /// Aptos Testnet utility service for creating test accounts and minting test coins
 #[derive(Clone, Debug, Parser)]
@@ -86,6 +91,14 @@ pub struct FaucetArgs {
     pub maximum_amount: Option<u64>,
     #[clap(long)]
     pub do_not_delegate: bool,
+    /// Number of delegated minter accounts to spread requests across, each with its own
+    /// sequence number.  Ignored with `--do-not-delegate`
+    #[clap(long, default_value = "1")]
+    pub num_minters: usize,
+    /// Path to a YAML config of quotas, allow and deny lists, and proof of work, to protect the
+    /// faucet from being drained
+    #[clap(long, parse(from_os_str))]
+    pub abuse_protection_config: Option<PathBuf>,
 }
 
 impl FaucetArgs {
@@ -124,38 +137,56 @@ impl FaucetArgs {
             None
         };
 
-        let service = Arc::new(Service::new(
+        let service = Service::new(
             self.server_url.clone(),
             self.chain_id,
             faucet_account,
             maximum_amount,
-        ));
+        );
 
-        let actual_service = if self.do_not_delegate {
+        let mut actual_service = if self.do_not_delegate {
+            if self.num_minters > 1 {
+                warn!("[faucet]: minting from a single account, as minting is not delegated");
+            }
             service
         } else {
-            delegate_mint_account(service, self.server_url, self.chain_id, self.maximum_amount)
-                .await
+            delegate_mint_accounts(
+                Arc::new(service),
+                self.server_url,
+                self.chain_id,
+                self.maximum_amount,
+                self.num_minters,
+            )
+            .await
         };
 
+        if let Some(ref path) = self.abuse_protection_config {
+            let config =
+                AbuseProtectionConfig::load(path).expect("Failed to load abuse protection config");
+            actual_service = actual_service.with_abuse_protection(
+                AbuseProtection::new(config).expect("Failed to set up abuse protection"),
+            );
+        }
+        let actual_service = Arc::new(actual_service);
+
         println!("Faucet is running. Faucet endpoint: {}", address);
 
         info!(
-            "[faucet]: running on: {}. Minting from {}",
+            "[faucet]: running on: {}. Minting from {:?}",
             address,
-            actual_service.faucet_account.lock().await.address()
+            actual_service.minters.addresses().await
         );
         warp::serve(routes(actual_service)).run(address).await;
     }
 }
 
 pub struct Service {
-    pub faucet_account: Mutex<LocalAccount>,
+    pub minters: MinterPool,
     pub transaction_factory: TransactionFactory,
-    pub outstanding_requests: std::sync::RwLock<Vec<crate::mint::MintParams>>,
     client: Client,
     endpoint: Url,
     maximum_amount: Option<u64>,
+    abuse_protection: Option<AbuseProtection>,
 }
 
 impl Service {
@@ -164,20 +195,49 @@ impl Service {
         chain_id: ChainId,
         faucet_account: LocalAccount,
         maximum_amount: Option<u64>,
+    ) -> Self {
+        Self::with_minters(endpoint, chain_id, vec![faucet_account], maximum_amount)
+    }
+
+    /// Creates a service that spreads requests across the given minter accounts
+    pub fn with_minters(
+        endpoint: Url,
+        chain_id: ChainId,
+        minter_accounts: Vec<LocalAccount>,
+        maximum_amount: Option<u64>,
     ) -> Self {
         let client = Client::new(endpoint.clone());
         Service {
-            faucet_account: Mutex::new(faucet_account),
+            minters: MinterPool::new(minter_accounts),
             transaction_factory: TransactionFactory::new(chain_id)
                 .with_gas_unit_price(std::cmp::max(1, aptos_global_constants::GAS_UNIT_PRICE))
                 .with_transaction_expiration_time(30),
-            outstanding_requests: std::sync::RwLock::new(vec![]),
             client,
             endpoint,
             maximum_amount,
+            abuse_protection: None,
         }
     }
 
+    /// Checks mint requests from the mint routes against quotas, access lists and proof of
+    /// work.  Requests through [`mint::process`] directly aren't checked.
+    pub fn with_abuse_protection(mut self, abuse_protection: AbuseProtection) -> Self {
+        self.abuse_protection = Some(abuse_protection);
+        self
+    }
+
+    pub fn abuse_protection(&self) -> Option<&AbuseProtection> {
+        self.abuse_protection.as_ref()
+    }
+
+    /// The amount that's actually minted for a requested amount
+    pub fn mint_amount(&self, requested_amount: u64) -> u64 {
+        std::cmp::min(
+            requested_amount,
+            self.maximum_amount.unwrap_or(requested_amount),
+        )
+    }
+
     // By default the path is prefixed with the version, e.g. `v1/`. The fake
     // API used in the faucet tests doesn't have a versioned API however, so
     // we just set it to `/`.
@@ -195,9 +255,11 @@ pub fn routes(
     service: Arc<Service>,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     let mint = mint::mint_routes(service.clone());
+    let challenge = mint::challenge_route(service.clone());
     let health = health_route(service);
 
     health
+        .or(challenge)
         .or(mint)
         .with(warp::log::custom(|info| {
             let forwarded_for = info
@@ -223,7 +285,7 @@ pub fn routes(
             warp::cors()
                 .allow_any_origin()
                 .allow_headers(vec![http::header::CONTENT_TYPE])
-                .allow_methods(vec!["POST"]),
+                .allow_methods(vec!["GET", "POST"]),
         )
 }
 
@@ -237,7 +299,7 @@ fn health_route(
 }
 
 async fn handle_health(service: Arc<Service>) -> Result<Box<dyn warp::Reply>, Infallible> {
-    let faucet_address = service.faucet_account.lock().await.address();
+    let faucet_address = service.minters.first().address().await;
     let faucet_account = service.client.get_account(faucet_address).await;
 
     match faucet_account {
@@ -252,17 +314,27 @@ async fn handle_health(service: Arc<Service>) -> Result<Box<dyn warp::Reply>, In
 /// The idea is that this may be happening concurrently. If we end up in such a race, the faucets
 /// might attempt to send transactions with the same sequence number, in such an event, one will
 /// succeed and the other will hit an unwrap. Eventually all faucets should get online.
-pub async fn delegate_mint_account(
+pub async fn delegate_mint_accounts(
     service: Arc<Service>,
     server_url: Url,
     chain_id: ChainId,
     maximum_amount: Option<u64>,
-) -> Arc<Service> {
-    // Create a new random account, then delegate to it
+    num_minters: usize,
+) -> Service {
+    let mut delegated_accounts = Vec::with_capacity(num_minters);
+    for _ in 0..std::cmp::max(1, num_minters) {
+        delegated_accounts.push(delegate_mint_account(&service).await);
+    }
+
+    Service::with_minters(server_url, chain_id, delegated_accounts, maximum_amount)
+}
+
+/// Creates a new random account, then delegates minting to it
+async fn delegate_mint_account(service: &Service) -> LocalAccount {
     let mut delegated_account = LocalAccount::generate(&mut rand::rngs::OsRng);
 
     // Create the account
-    let response = mint::process(&service, mint::MintParams {
+    let response = mint::process(service, mint::MintParams {
         amount: 100_000_000_000,
         auth_key: None,
         address: Some(
@@ -274,6 +346,8 @@ pub async fn delegate_mint_account(
         ),
         pub_key: None,
         return_txns: Some(true),
+        challenge: None,
+        nonce: None,
     })
     .await
     .expect("Failed to create new account");
@@ -293,7 +367,7 @@ pub async fn delegate_mint_account(
 
     // Delegate minting to the account
     {
-        let mut faucet_account = service.faucet_account.lock().await;
+        let mut faucet_account = service.minters.first().account.lock().await;
         service
             .client
             .submit_and_wait(&faucet_account.sign_with_transaction_builder(
@@ -318,10 +392,5 @@ pub async fn delegate_mint_account(
         .await
         .unwrap();
 
-    Arc::new(Service::new(
-        server_url,
-        chain_id,
-        delegated_account,
-        maximum_amount,
-    ))
+    delegated_account
 }
@@ -17,7 +17,10 @@ async fn main() {
 #[cfg(test)]
 mod tests {
     use aptos_crypto::{ed25519::Ed25519PublicKey, hash::HashValue};
-    use aptos_faucet::{routes, Service};
+    use aptos_faucet::{
+        abuse::{AbuseProtection, AbuseProtectionConfig, QuotaConfig},
+        routes, Service,
+    };
     use aptos_infallible::RwLock;
     use aptos_keygen::KeyGen;
     use aptos_rest_client::{
@@ -72,6 +75,11 @@ mod tests {
     }
 
     fn setup(maximum_amount: Option<u64>) -> (AccountStates, Arc<Service>) {
+        let (accounts, service) = setup_service(maximum_amount);
+        (accounts, Arc::new(service))
+    }
+
+    fn setup_service(maximum_amount: Option<u64>) -> (AccountStates, Service) {
         let mut keygen = KeyGen::from_seed([0; 32]);
         let (private_key, public_key) = keygen.generate_ed25519_keypair();
         let account_address = AuthenticationKey::ed25519(&public_key).derived_address();
@@ -119,7 +127,7 @@ mod tests {
             maximum_amount,
         )
         .configure_for_testing();
-        (accounts, Arc::new(service))
+        (accounts, service)
     }
 
     async fn handle_get_account(
@@ -409,6 +417,91 @@ mod tests {
         assert_eq!(account.balance, amount);
     }
 
+    #[tokio::test]
+    async fn test_mint_address_quota() {
+        let (accounts, service) = setup_service(None);
+        let service = service.with_abuse_protection(
+            AbuseProtection::new(AbuseProtectionConfig {
+                address_quota: Some(QuotaConfig {
+                    window_secs: 3600,
+                    max_requests: Some(1),
+                    max_amount: None,
+                }),
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let filter = routes(Arc::new(service));
+
+        let address = "459c77a38803bd53f3adee52703810e3a74fd7c46952c497e75afb0a7932586d";
+        let amount = 13345;
+        for expected_status in [StatusCode::OK, StatusCode::TOO_MANY_REQUESTS] {
+            let resp = warp::test::request()
+                .method("POST")
+                .path(format!("/mint?address={}&amount={}", address, amount).as_str())
+                .reply(&filter)
+                .await;
+            assert_eq!(resp.status(), expected_status);
+        }
+
+        // Only the first request was minted
+        let reader = accounts.read();
+        let addr = AccountAddress::try_from(address.to_owned()).unwrap();
+        let account = reader.get(&addr).expect("account should be created");
+        assert_eq!(account.balance, amount);
+    }
+
+    #[tokio::test]
+    async fn test_mint_failure_releases_quota() {
+        let (accounts, service) = setup_service(None);
+        let service = service.with_abuse_protection(
+            AbuseProtection::new(AbuseProtectionConfig {
+                address_quota: Some(QuotaConfig {
+                    window_secs: 3600,
+                    max_requests: Some(1),
+                    max_amount: None,
+                }),
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let faucet_address = service.minters.first().address().await;
+        let filter = routes(Arc::new(service));
+        let path = "/mint?address=459c77a38803bd53f3adee52703810e3a74fd7c46952c497e75afb0a7932586d&amount=13345";
+
+        // The mint fails without the faucet account, which doesn't use up the quota
+        let faucet_account = accounts.write().remove(&faucet_address).unwrap();
+        let resp = warp::test::request()
+            .method("POST")
+            .path(path)
+            .reply(&filter)
+            .await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        accounts.write().insert(faucet_address, faucet_account);
+        for expected_status in [StatusCode::OK, StatusCode::TOO_MANY_REQUESTS] {
+            let resp = warp::test::request()
+                .method("POST")
+                .path(path)
+                .reply(&filter)
+                .await;
+            assert_eq!(resp.status(), expected_status);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_challenge_not_required() {
+        let (_accounts, service) = setup(None);
+
+        let resp = warp::test::request()
+            .method("GET")
+            .path("/challenge")
+            .reply(&routes(service))
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn test_health() {
         let (_accounts, service) = setup(None);
@@ -443,7 +536,7 @@ mod tests {
     #[tokio::test]
     async fn test_mint_fullnode_error() {
         let (accounts, service) = setup(None);
-        let address = service.faucet_account.lock().await.address();
+        let address = service.minters.first().address().await;
         accounts.write().remove(&address);
         let filter = routes(service);
 
@@ -4,7 +4,7 @@
 // README: The aptos-faucet is deprecated in favor of the tap. Do not add new code
 // to this until you've spoken with the Ecosystem Platform team + dport.
 
-use crate::Service;
+use crate::{pool::Minter, Service};
 use anyhow::Result;
 use aptos_crypto::{ed25519::Ed25519PublicKey, hash::HashValue};
 use aptos_logger::{info, warn};
@@ -16,7 +16,7 @@ use aptos_sdk::types::{
 };
 use reqwest::StatusCode;
 use serde::Deserialize;
-use std::{convert::Infallible, fmt, sync::Arc};
+use std::{convert::Infallible, fmt, net::SocketAddr, sync::Arc};
 use warp::{Filter, Rejection, Reply};
 
 static MINTER_SCRIPT: &[u8] = include_bytes!("minter.mv");
@@ -31,15 +31,91 @@ pub fn mint_routes(
         .and(warp::post())
         .and(warp::any().map(move || service.clone()))
         .and(warp::query().map(move |params: MintParams| params))
-        .and_then(|_, service, params| handle(service, params))
+        .and(warp::addr::remote())
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .and_then(|_, service, params, remote, forwarded_for| {
+            handle(service, params, remote, forwarded_for)
+        })
+}
+
+pub fn challenge_route(
+    service: Arc<Service>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    // GET /challenge
+    warp::path!("challenge")
+        .and(warp::get())
+        .and(warp::any().map(move || service.clone()))
+        .and(warp::addr::remote())
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .and_then(handle_challenge)
+}
+
+async fn handle_challenge(
+    service: Arc<Service>,
+    remote: Option<SocketAddr>,
+    forwarded_for: Option<String>,
+) -> Result<Box<dyn warp::Reply>, Infallible> {
+    let challenge = match service.abuse_protection() {
+        Some(abuse_protection) => abuse_protection
+            .new_challenge(abuse_protection.requester_ip(remote, forwarded_for.as_deref())),
+        None => Ok(None),
+    };
+    match challenge {
+        Ok(Some(challenge)) => Ok(Box::new(warp::reply::json(&challenge))),
+        Ok(None) => Ok(Box::new(warp::reply::with_status(
+            "Proof of work is not required".to_string(),
+            StatusCode::NOT_FOUND,
+        ))),
+        Err(rejection) => Ok(Box::new(warp::reply::with_status(
+            rejection.to_string(),
+            rejection.status_code(),
+        ))),
+    }
 }
 
 async fn handle(
     service: Arc<Service>,
     params: MintParams,
+    remote: Option<SocketAddr>,
+    forwarded_for: Option<String>,
 ) -> Result<Box<dyn warp::Reply>, Infallible> {
+    // Requests without a receiver fail in processing anyways
+    let mut reservation = None;
+    if let (Some(abuse_protection), Some(receiver)) =
+        (service.abuse_protection(), params.receiver())
+    {
+        let ip = abuse_protection.requester_ip(remote, forwarded_for.as_deref());
+        match abuse_protection.check(
+            ip,
+            receiver,
+            service.mint_amount(params.amount),
+            params.challenge.as_deref(),
+            params.nonce,
+        ) {
+            Ok(accepted) => reservation = Some(accepted),
+            Err(rejection) => {
+                info!(
+                    ip = ip,
+                    receiver = receiver,
+                    rejection = rejection.to_string(),
+                    "mint request rejected"
+                );
+                return Ok(Box::new(warp::reply::with_status(
+                    rejection.to_string(),
+                    rejection.status_code(),
+                )));
+            },
+        }
+    }
+
+    // Only minted requests count towards the quotas, a failed one releases its reservation
     match process(&service, params).await {
-        Ok(body) => Ok(Box::new(body.to_string())),
+        Ok(body) => {
+            if let Some(reservation) = reservation {
+                reservation.minted();
+            }
+            Ok(Box::new(body.to_string()))
+        },
         Err(err) => Ok(Box::new(warp::reply::with_status(
             err.to_string(),
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -73,6 +149,10 @@ pub struct MintParams {
     pub address: Option<String>,
     pub pub_key: Option<Ed25519PublicKey>,
     pub return_txns: Option<bool>,
+    /// Proof of work challenge from `/challenge`, if required
+    pub challenge: Option<String>,
+    /// Solution of the proof of work challenge
+    pub nonce: Option<u64>,
 }
 
 impl std::fmt::Display for MintParams {
@@ -109,20 +189,22 @@ impl MintParams {
 }
 
 pub async fn process(service: &Service, params: MintParams) -> Result<Response> {
-    let maybe_maximum_amount = service.maximum_amount.unwrap_or(params.amount);
-    let amount = std::cmp::min(params.amount, maybe_maximum_amount);
+    let amount = service.mint_amount(params.amount);
 
     let receiver_address = params.receiver().ok_or_else(|| {
         anyhow::format_err!("You must provide 'address' (preferred), 'pub_key', or 'auth_key'")
     })?;
 
-    let (mut faucet_seq, mut receiver_seq) = sequences(service, receiver_address).await?;
+    // Each minter has its own sequence number, so requests on different minters don't wait on
+    // each other
+    let minter = service.minters.next();
+    let (mut faucet_seq, mut receiver_seq) = sequences(service, minter, receiver_address).await?;
     if receiver_seq.is_some() && amount == 0 {
         anyhow::bail!("Account is already created and amount asked for is 0");
     }
 
     let our_faucet_seq = {
-        let mut faucet_account = service.faucet_account.lock().await;
+        let mut faucet_account = minter.account.lock().await;
 
         // If the onchain sequence_number is greater than what we have, update our
         // sequence_numbers
@@ -139,16 +221,16 @@ pub async fn process(service: &Service, params: MintParams) -> Result<Response>
             // Enforce a stronger ordering of priorities based upon the MintParams that arrived
             // first. Then put the other folks to sleep to try again until the queue fills up.
             if !set_outstanding {
-                let mut requests = service.outstanding_requests.write().unwrap();
+                let mut requests = minter.outstanding_requests.write().unwrap();
                 requests.push(params.clone());
                 set_outstanding = true;
             }
 
-            if service.outstanding_requests.read().unwrap().first() == Some(&params) {
+            if minter.outstanding_requests.read().unwrap().first() == Some(&params) {
                 // There might have been two requests with the same parameters, so we ensure that
                 // we only pop off one of them. We do a read lock first since that is cheap,
                 // followed by a write lock.
-                let mut requests = service.outstanding_requests.write().unwrap();
+                let mut requests = minter.outstanding_requests.write().unwrap();
                 if requests.first() == Some(&params) {
                     requests.remove(0);
                     break;
@@ -163,7 +245,7 @@ pub async fn process(service: &Service, params: MintParams) -> Result<Response>
         );
 
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        let (lhs, rhs) = sequences(service, receiver_address).await?;
+        let (lhs, rhs) = sequences(service, minter, receiver_address).await?;
         faucet_seq = lhs;
         receiver_seq = rhs;
 
@@ -175,7 +257,7 @@ pub async fn process(service: &Service, params: MintParams) -> Result<Response>
     // After 30 seconds, we still have not caught up, we are likely unhealthy
     if our_faucet_seq >= faucet_seq + 50 {
         warn!("We are unhealthy, transactions have likely expired.");
-        let mut faucet_account = service.faucet_account.lock().await;
+        let mut faucet_account = minter.account.lock().await;
         if faucet_account.sequence_number() >= faucet_seq + 50 {
             info!("Resetting the sequence number counter.");
             *faucet_account.sequence_number_mut() = faucet_seq;
@@ -185,7 +267,7 @@ pub async fn process(service: &Service, params: MintParams) -> Result<Response>
     }
 
     let txn = {
-        let mut faucet_account = service.faucet_account.lock().await;
+        let mut faucet_account = minter.account.lock().await;
         faucet_account.sign_with_transaction_builder(service.transaction_factory.script(
             Script::new(MINTER_SCRIPT.to_vec(), vec![], vec![
                 TransactionArgument::Address(receiver_address),
@@ -199,7 +281,7 @@ pub async fn process(service: &Service, params: MintParams) -> Result<Response>
     // If there was an issue submitting a transaction we should just reset our sequence_numbers
     // to what was on chain
     if response.is_err() {
-        *service.faucet_account.lock().await.sequence_number_mut() = faucet_seq;
+        *minter.account.lock().await.sequence_number_mut() = faucet_seq;
         response?;
     }
 
@@ -210,8 +292,12 @@ pub async fn process(service: &Service, params: MintParams) -> Result<Response>
     }
 }
 
-async fn sequences(service: &Service, receiver: AccountAddress) -> Result<(u64, Option<u64>)> {
-    let faucet_address = service.faucet_account.lock().await.address();
+async fn sequences(
+    service: &Service,
+    minter: &Minter,
+    receiver: AccountAddress,
+) -> Result<(u64, Option<u64>)> {
+    let faucet_address = minter.address().await;
     let f_request = service.client.get_account(faucet_address);
     let r_request = service.client.get_account(receiver);
     let mut responses = futures::future::join_all([f_request, r_request]).await;
@@ -0,0 +1,96 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::mint::MintParams;
+use aptos_sdk::types::{account_address::AccountAddress, LocalAccount};
+use futures::lock::Mutex;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    RwLock,
+};
+
+/// An account that mints coins, with its own sequence number and queue of requests
+pub struct Minter {
+    pub account: Mutex<LocalAccount>,
+    pub outstanding_requests: RwLock<Vec<MintParams>>,
+}
+
+impl Minter {
+    pub fn new(account: LocalAccount) -> Self {
+        Minter {
+            account: Mutex::new(account),
+            outstanding_requests: RwLock::new(vec![]),
+        }
+    }
+
+    pub async fn address(&self) -> AccountAddress {
+        self.account.lock().await.address()
+    }
+}
+
+/// Minter accounts that requests are spread across, so that they don't all wait on the
+/// sequence number of a single account
+pub struct MinterPool {
+    minters: Vec<Minter>,
+    next: AtomicUsize,
+}
+
+impl MinterPool {
+    pub fn new(accounts: Vec<LocalAccount>) -> Self {
+        assert!(!accounts.is_empty(), "Minter pool must have an account");
+        MinterPool {
+            minters: accounts.into_iter().map(Minter::new).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// The minter to use for the next request, in round robin order
+    pub fn next(&self) -> &Minter {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.minters.len();
+        &self.minters[index]
+    }
+
+    /// The first minter, which is the only one when the mint account isn't delegated
+    pub fn first(&self) -> &Minter {
+        &self.minters[0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.minters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.minters.is_empty()
+    }
+
+    pub async fn addresses(&self) -> Vec<AccountAddress> {
+        let mut addresses = Vec::with_capacity(self.minters.len());
+        for minter in &self.minters {
+            addresses.push(minter.address().await);
+        }
+        addresses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_round_robin() {
+        let accounts: Vec<_> = (0..3)
+            .map(|_| LocalAccount::generate(&mut rand::rngs::OsRng))
+            .collect();
+        let expected: Vec<_> = accounts.iter().map(|account| account.address()).collect();
+        let pool = MinterPool::new(accounts);
+
+        assert_eq!(pool.addresses().await, expected);
+        let mut used = vec![];
+        for _ in 0..6 {
+            used.push(pool.next().address().await);
+        }
+        assert_eq!(used[..3], expected[..]);
+        assert_eq!(used[3..], expected[..]);
+        assert_eq!(pool.first().address().await, expected[0]);
+    }
+}
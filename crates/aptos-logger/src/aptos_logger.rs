@@ -8,13 +8,15 @@ use crate::{
     counters::{
         PROCESSED_STRUCT_LOG_COUNT, STRUCT_LOG_PARSE_ERROR_COUNT, STRUCT_LOG_QUEUE_ERROR_COUNT,
     },
+    log_rotation::{LogFile, LogRotationConfig},
     logger::Logger,
     sample,
     sample::SampleRate,
+    span_export::{SpanExportConfig, SpanExportLayer},
     telemetry_log_writer::{TelemetryLog, TelemetryLogWriter},
     Event, Filter, Key, Level, LevelFilter, Metadata,
 };
-use aptos_infallible::RwLock;
+use aptos_infallible::{Mutex, RwLock};
 use backtrace::Backtrace;
 use chrono::{SecondsFormat, Utc};
 use futures::channel;
@@ -230,6 +232,7 @@ pub struct AptosDataBuilder {
     is_async: bool,
     enable_telemetry_flush: bool,
     custom_format: Option<fn(&LogEntry) -> Result<String, fmt::Error>>,
+    span_export: Option<SpanExportConfig>,
 }
 
 impl AptosDataBuilder {
@@ -247,6 +250,7 @@ impl AptosDataBuilder {
             is_async: false,
             enable_telemetry_flush: true,
             custom_format: None,
+            span_export: None,
         }
     }
 
@@ -311,6 +315,12 @@ impl AptosDataBuilder {
         self
     }
 
+    /// Exports the spans created with `txn_span!` to an OpenTelemetry destination
+    pub fn span_export(&mut self, span_export: Option<SpanExportConfig>) -> &mut Self {
+        self.span_export = span_export;
+        self
+    }
+
     pub fn init(&mut self) {
         self.build();
     }
@@ -372,6 +382,7 @@ impl AptosDataBuilder {
                 sender: Some(sender),
                 printer: None,
                 filter: RwLock::new(filter),
+                filter_overrides: Mutex::new(FilterOverrides::default()),
                 enable_telemetry_flush: self.enable_telemetry_flush,
                 formatter: self.custom_format.take().unwrap_or(text_format),
             });
@@ -390,6 +401,7 @@ impl AptosDataBuilder {
                 sender: None,
                 printer: self.printer.take(),
                 filter: RwLock::new(filter),
+                filter_overrides: Mutex::new(FilterOverrides::default()),
                 enable_telemetry_flush: self.enable_telemetry_flush,
                 formatter: self.custom_format.take().unwrap_or(text_format),
             })
@@ -405,7 +417,13 @@ impl AptosDataBuilder {
             None
         };
 
-        crate::logger::set_global_logger(logger.clone(), console_port);
+        let span_export = self.span_export.as_ref().and_then(|config| {
+            SpanExportLayer::new(config)
+                .map_err(|error| eprintln!("Failed to start the span exporter: {}", error))
+                .ok()
+        });
+
+        crate::logger::set_global_logger(logger.clone(), console_port, span_export);
         logger
     }
 }
@@ -422,6 +440,51 @@ impl FilterTuple {
     fn enabled(&self, metadata: &Metadata) -> bool {
         self.local_filter.enabled(metadata) || self.telemetry_filter.enabled(metadata)
     }
+
+    fn get(&self, kind: FilterKind) -> &Filter {
+        match kind {
+            FilterKind::Local => &self.local_filter,
+            FilterKind::Telemetry => &self.telemetry_filter,
+        }
+    }
+
+    fn get_mut(&mut self, kind: FilterKind) -> &mut Filter {
+        match kind {
+            FilterKind::Local => &mut self.local_filter,
+            FilterKind::Telemetry => &mut self.telemetry_filter,
+        }
+    }
+}
+
+/// Selects one of the `Filter`s of the logger
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterKind {
+    /// The filter of what is logged in text output
+    Local,
+    /// The filter of what is sent to the telemetry service
+    Telemetry,
+}
+
+/// A filter replaced at runtime, kept so that it can be restored
+struct FilterOverride {
+    id: u64,
+    replaced: Filter,
+}
+
+#[derive(Default)]
+struct FilterOverrides {
+    next_id: u64,
+    local: Option<FilterOverride>,
+    telemetry: Option<FilterOverride>,
+}
+
+impl FilterOverrides {
+    fn get_mut(&mut self, kind: FilterKind) -> &mut Option<FilterOverride> {
+        match kind {
+            FilterKind::Local => &mut self.local,
+            FilterKind::Telemetry => &mut self.telemetry,
+        }
+    }
 }
 
 pub struct AptosData {
@@ -429,6 +492,8 @@ pub struct AptosData {
     sender: Option<sync::mpsc::SyncSender<LoggerServiceEvent>>,
     printer: Option<Box<dyn Writer>>,
     filter: RwLock<FilterTuple>,
+    /// Filters overridden at runtime, which `LoggerFilterUpdater` leaves alone
+    filter_overrides: Mutex<FilterOverrides>,
     enable_telemetry_flush: bool,
     pub(crate) formatter: fn(&LogEntry) -> Result<String, fmt::Error>,
 }
@@ -471,6 +536,70 @@ impl AptosData {
         self.filter.write().telemetry_filter = filter;
     }
 
+    /// Returns the directives of a filter, e.g. `info,aptos_mempool=debug`
+    pub fn filter_directives(&self, kind: FilterKind) -> String {
+        self.filter.read().get(kind).to_string()
+    }
+
+    pub fn is_filter_overridden(&self, kind: FilterKind) -> bool {
+        self.filter_overrides.lock().get_mut(kind).is_some()
+    }
+
+    /// Overrides a filter until `reset_filter` restores the one it replaced. Returns the id of
+    /// the override.
+    pub fn override_filter(&self, kind: FilterKind, filter: Filter) -> u64 {
+        let mut overrides = self.filter_overrides.lock();
+        overrides.next_id += 1;
+        let id = overrides.next_id;
+
+        let replaced = std::mem::replace(self.filter.write().get_mut(kind), filter);
+        match overrides.get_mut(kind) {
+            // Keep the filter from before the first override
+            Some(filter_override) => filter_override.id = id,
+            entry => *entry = Some(FilterOverride { id, replaced }),
+        }
+        id
+    }
+
+    /// Restores the filter replaced by an override. If `id` is given, the filter is only
+    /// restored if it wasn't overridden again since. Returns true if the filter was restored.
+    pub fn reset_filter(&self, kind: FilterKind, id: Option<u64>) -> bool {
+        let mut overrides = self.filter_overrides.lock();
+        let entry = overrides.get_mut(kind);
+        let is_current = match entry.as_ref() {
+            Some(filter_override) => id.map_or(true, |id| id == filter_override.id),
+            None => false,
+        };
+        if !is_current {
+            return false;
+        }
+
+        if let Some(filter_override) = entry.take() {
+            *self.filter.write().get_mut(kind) = filter_override.replaced;
+        }
+        true
+    }
+
+    /// Replaces the filters that aren't overridden. For overridden filters, this replaces the
+    /// filter restored once the override is reset.
+    fn update_filter(&self, filter_tuple: FilterTuple) {
+        let mut overrides = self.filter_overrides.lock();
+        let mut filters = self.filter.write();
+        let FilterTuple {
+            local_filter,
+            telemetry_filter,
+        } = filter_tuple;
+        for (kind, filter) in [
+            (FilterKind::Local, local_filter),
+            (FilterKind::Telemetry, telemetry_filter),
+        ] {
+            match overrides.get_mut(kind) {
+                Some(filter_override) => filter_override.replaced = filter,
+                None => *filters.get_mut(kind) = filter,
+            }
+        }
+    }
+
     fn send_entry(&self, entry: LogEntry) {
         if let Some(printer) = &self.printer {
             let s = (self.formatter)(&entry).expect("Unable to format");
@@ -634,16 +763,20 @@ impl Writer for StdoutWriter {
 
 /// A struct for writing logs to a file
 pub struct FileWriter {
-    log_file: RwLock<std::fs::File>,
+    log_file: RwLock<LogFile>,
 }
 
 impl FileWriter {
     pub fn new(log_file: std::path::PathBuf) -> Self {
-        let file = std::fs::OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(log_file)
-            .expect("Unable to open log file");
+        Self::with_rotation(log_file, None)
+    }
+
+    /// Creates a writer rotating the file according to the config, if one is given
+    pub fn with_rotation(
+        log_file: std::path::PathBuf,
+        rotation: Option<LogRotationConfig>,
+    ) -> Self {
+        let file = LogFile::open(log_file, rotation).expect("Unable to open log file");
         Self {
             log_file: RwLock::new(file),
         }
@@ -653,7 +786,7 @@ impl FileWriter {
 impl Writer for FileWriter {
     /// Write to file
     fn write(&self, log: String) {
-        if let Err(err) = writeln!(self.log_file.write(), "{}", log) {
+        if let Err(err) = self.log_file.write().write_line(&log) {
             eprintln!("Unable to write to log file: {}", err);
         }
     }
@@ -723,6 +856,10 @@ impl LoggerFilterUpdater {
         }
     }
 
+    pub fn logger(&self) -> Arc<AptosData> {
+        self.logger.clone()
+    }
+
     pub async fn run(self) {
         let mut interval = time::interval(FILTER_REFRESH_INTERVAL);
         loop {
@@ -735,7 +872,7 @@ impl LoggerFilterUpdater {
     fn update_filter(&self) {
         // TODO: check for change to env var before rebuilding filter.
         let filter = self.logger_builder.build_filter();
-        self.logger.set_filter(filter);
+        self.logger.update_filter(filter);
     }
 }
 
@@ -746,8 +883,8 @@ mod tests {
         aptos_logger::{json_format, RUST_LOG_TELEMETRY},
         debug, error, info,
         logger::Logger,
-        trace, warn, AptosDataBuilder, Event, Key, KeyValue, Level, LoggerFilterUpdater, Metadata,
-        Schema, Value, Visitor,
+        trace, warn, AptosDataBuilder, Event, FilterKind, Key, KeyValue, Level,
+        LoggerFilterUpdater, Metadata, Schema, Value, Visitor,
     };
     use chrono::{DateTime, Utc};
     #[cfg(test)]
@@ -815,7 +952,7 @@ mod tests {
     fn set_test_logger() -> Receiver<LogEntry> {
         let (logger, receiver) = LogStream::new(true);
         let logger = Arc::new(logger);
-        crate::logger::set_global_logger(logger, None);
+        crate::logger::set_global_logger(logger, None, None);
         receiver
     }
 
@@ -1024,4 +1161,27 @@ mod tests {
                 "source_path"
             )));
     }
+
+    #[test]
+    fn test_filter_override() {
+        let (logger_builder, logger) = new_async_logger();
+        let configured = logger.filter_directives(FilterKind::Local);
+        let updater = LoggerFilterUpdater::new(logger.clone(), logger_builder);
+
+        let first = logger.override_filter(FilterKind::Local, "debug".parse().unwrap());
+        let second = logger.override_filter(FilterKind::Local, "aptos=trace".parse().unwrap());
+        assert!(logger.is_filter_overridden(FilterKind::Local));
+        assert!(!logger.is_filter_overridden(FilterKind::Telemetry));
+
+        // Refreshing the filters keeps the override
+        updater.update_filter();
+        assert_eq!(logger.filter_directives(FilterKind::Local), "aptos=trace");
+
+        // A stale override doesn't reset the filter, the latest one restores the configured filter
+        assert!(!logger.reset_filter(FilterKind::Local, Some(first)));
+        assert!(logger.reset_filter(FilterKind::Local, Some(second)));
+        assert_eq!(logger.filter_directives(FilterKind::Local), configured);
+        assert!(!logger.is_filter_overridden(FilterKind::Local));
+        assert!(!logger.reset_filter(FilterKind::Local, None));
+    }
 }
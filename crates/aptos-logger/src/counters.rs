@@ -53,3 +53,12 @@ pub static APTOS_LOG_INGEST_WRITER_DISCONNECTED: Lazy<IntCounter> = Lazy::new(||
     )
     .unwrap()
 });
+
+/// Counter for transaction spans that were dropped instead of exported
+pub static SPAN_EXPORT_DROPPED_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_span_export_dropped_count",
+        "Number of spans dropped because the exporter fell behind or failed"
+    )
+    .unwrap()
+});
@@ -4,8 +4,9 @@
 //! Filtering definitions for controlling what modules and levels are logged
 
 use crate::{Level, Metadata};
-use std::{env, str::FromStr};
+use std::{env, fmt, str::FromStr};
 
+#[derive(Debug)]
 pub struct FilterParseError;
 
 /// A definition of the most verbose `Level` allowed, or completely off.
@@ -40,6 +41,20 @@ impl FromStr for LevelFilter {
     }
 }
 
+impl fmt::Display for LevelFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LevelFilter::Off => "off",
+            LevelFilter::Error => "error",
+            LevelFilter::Warn => "warn",
+            LevelFilter::Info => "info",
+            LevelFilter::Debug => "debug",
+            LevelFilter::Trace => "trace",
+        };
+        f.pad(name)
+    }
+}
+
 impl From<Level> for LevelFilter {
     fn from(level: Level) -> Self {
         match level {
@@ -145,6 +160,35 @@ impl Filter {
     }
 }
 
+/// Parses the directives strictly, unlike `Builder::parse` which skips invalid ones
+impl FromStr for Filter {
+    type Err = FilterParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut builder = Builder::new();
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            builder.directives.push(directive.parse()?);
+        }
+        Ok(builder.build())
+    }
+}
+
+/// Formats the directives in the same syntax they are parsed from, e.g. `info,crate1=debug`
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, directive) in self.directives.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ",")?;
+            }
+            match &directive.name {
+                Some(name) => write!(f, "{}={}", name, directive.level)?,
+                None => write!(f, "{}", directive.level)?,
+            }
+        }
+        Ok(())
+    }
+}
+
 /// A `Filter` directive for which logs to keep based on a module `name` based filter
 #[derive(Debug)]
 struct Directive {
@@ -185,7 +229,7 @@ impl FromStr for Directive {
 
 #[cfg(test)]
 mod tests {
-    use super::{Builder, Level, LevelFilter, Metadata};
+    use super::{Builder, Filter, Level, LevelFilter, Metadata};
 
     fn make_metadata(level: Level, target: &'static str) -> Metadata {
         Metadata::new(level, target, target, "")
@@ -351,4 +395,15 @@ mod tests {
         assert_eq!(dirs[1].name.as_deref(), Some("crate2"));
         assert_eq!(dirs[1].level, LevelFilter::Debug);
     }
+
+    #[test]
+    fn parse_strict_round_trip() {
+        let filter: Filter = "crate1::mod1=warn, info".parse().unwrap();
+        assert_eq!(filter.to_string(), "info,crate1::mod1=warn");
+        assert!(filter.enabled(&make_metadata(Level::Info, "crate2")));
+        assert!(!filter.enabled(&make_metadata(Level::Info, "crate1::mod1")));
+
+        assert!("info,crate1=loud".parse::<Filter>().is_err());
+        assert_eq!("".parse::<Filter>().unwrap().to_string(), "error");
+    }
 }
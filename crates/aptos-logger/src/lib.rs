@@ -147,17 +147,19 @@ pub mod aptos_logger;
 mod event;
 mod filter;
 mod kv;
+pub mod log_rotation;
 mod logger;
 mod macros;
 mod metadata;
 pub mod sample;
+pub mod span_export;
 pub mod telemetry_log_writer;
 pub mod tracing_adapter;
 
 mod security;
 
 pub use crate::aptos_logger::{
-    AptosData as Logger, AptosDataBuilder, LoggerFilterUpdater, Writer, CHANNEL_SIZE,
+    AptosData as Logger, AptosDataBuilder, FilterKind, LoggerFilterUpdater, Writer, CHANNEL_SIZE,
 };
 pub use aptos_log_derive::Schema;
 pub use event::Event;
@@ -166,5 +168,8 @@ pub use kv::{Key, KeyValue, Schema, Value, Visitor};
 pub use logger::flush;
 pub use metadata::{Level, Metadata};
 pub use security::SecurityEvent;
+// Used by the `txn_span!` macro
+#[doc(hidden)]
+pub use tracing;
 
 mod counters;
@@ -0,0 +1,440 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rotation of the log file written by the [`FileWriter`](crate::aptos_logger::FileWriter).
+//!
+//! The file is rotated once it grows past `max_file_size_bytes` or has been open for
+//! `rotation_interval_secs`. Rotated files are renamed to `<file name>.<UTC timestamp>`, gzipped
+//! in the background if `compress` is set, and only the newest `max_files` are kept. The
+//! background work runs on one thread per log file, which handles the rotated files in order.
+//!
+//! Rotation happens on the thread writing the log, which is the logger service thread when
+//! logging is async, so no line is lost or split across files. On SIGHUP the file is reopened
+//! at its configured path, for external tools that move the file instead of truncating it.
+
+use chrono::Utc;
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Format of the timestamp appended to rotated files. It sorts in rotation order.
+const ROTATED_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S%.6f";
+const GZIP_EXTENSION: &str = "gz";
+const PARTIAL_EXTENSION: &str = "part";
+
+/// Incremented on every reopen request, each log file reopens once it sees a new value
+static REOPEN_GENERATION: AtomicU64 = AtomicU64::new(0);
+#[cfg(unix)]
+static SIGHUP_LISTENER: std::sync::Once = std::sync::Once::new();
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogRotationConfig {
+    /// Rotates the file once it is larger than this
+    pub max_file_size_bytes: Option<u64>,
+    /// Rotates the file once it has been written to for this long
+    pub rotation_interval_secs: Option<u64>,
+    /// Number of rotated files kept, older ones are deleted
+    pub max_files: usize,
+    /// Gzips the rotated files
+    pub compress: bool,
+    /// Reopens the file on SIGHUP
+    pub reopen_on_sighup: bool,
+}
+
+impl Default for LogRotationConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size_bytes: Some(512 * 1024 * 1024),
+            rotation_interval_secs: None,
+            max_files: 10,
+            compress: true,
+            reopen_on_sighup: true,
+        }
+    }
+}
+
+/// Makes every rotated log file reopen its path before its next write
+pub fn request_reopen() {
+    REOPEN_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Starts a thread calling [`request_reopen`] on every SIGHUP. Only the first call has an effect.
+fn listen_for_sighup() {
+    #[cfg(unix)]
+    SIGHUP_LISTENER.call_once(|| {
+        let result = thread::Builder::new().name("log-sighup".into()).spawn(|| {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(err) => {
+                    eprintln!("[Logging] Unable to start the SIGHUP listener: {}", err);
+                    return;
+                },
+            };
+            runtime.block_on(async {
+                use tokio::signal::unix::{signal, SignalKind};
+
+                let mut hangups = match signal(SignalKind::hangup()) {
+                    Ok(hangups) => hangups,
+                    Err(err) => {
+                        eprintln!("[Logging] Unable to listen for SIGHUP: {}", err);
+                        return;
+                    },
+                };
+                while hangups.recv().await.is_some() {
+                    request_reopen();
+                }
+            })
+        });
+        if let Err(err) = result {
+            eprintln!("[Logging] Unable to start the SIGHUP listener: {}", err);
+        }
+    });
+}
+
+/// A log file, rotated according to its [`LogRotationConfig`] if it has one
+pub(crate) struct LogFile {
+    path: PathBuf,
+    file: File,
+    rotation: Option<LogRotationConfig>,
+    /// Bytes in the current file
+    size: u64,
+    opened_at: Instant,
+    reopen_generation: u64,
+    /// Compresses the rotated files and deletes old ones, started on the first rotation
+    cleanup: Option<Cleanup>,
+}
+
+/// The thread compressing the rotated files sent to it and deleting old ones, one at a time so
+/// that the cleanups don't delete files from under each other
+struct Cleanup {
+    sender: Sender<PathBuf>,
+    worker: JoinHandle<()>,
+}
+
+impl Cleanup {
+    fn start(path: PathBuf, rotation: LogRotationConfig) -> io::Result<Self> {
+        let (sender, receiver) = mpsc::channel::<PathBuf>();
+        let worker = thread::Builder::new()
+            .name("log-rotation".into())
+            .spawn(move || {
+                for rotated_path in receiver {
+                    if rotation.compress {
+                        // The file is gone if more rotations than max_files were queued
+                        match compress(&rotated_path) {
+                            Err(err) if err.kind() != io::ErrorKind::NotFound => eprintln!(
+                                "[Logging] Unable to compress {}: {}",
+                                rotated_path.display(),
+                                err
+                            ),
+                            _ => {},
+                        }
+                    }
+                    if let Err(err) = remove_old_files(&path, rotation.max_files) {
+                        eprintln!("[Logging] Unable to remove old log files: {}", err);
+                    }
+                }
+            })?;
+        Ok(Self { sender, worker })
+    }
+}
+
+impl LogFile {
+    pub(crate) fn open(path: PathBuf, rotation: Option<LogRotationConfig>) -> io::Result<Self> {
+        if rotation
+            .as_ref()
+            .map_or(false, |rotation| rotation.reopen_on_sighup)
+        {
+            listen_for_sighup();
+        }
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            rotation,
+            size,
+            opened_at: Instant::now(),
+            reopen_generation: REOPEN_GENERATION.load(Ordering::Relaxed),
+            cleanup: None,
+        })
+    }
+
+    /// Writes the line, rotating or reopening the file first if needed
+    pub(crate) fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.rotation.is_some() {
+            // Failing to rotate shouldn't lose the line, it goes to the current file instead
+            if let Err(err) = self.reopen_if_requested() {
+                eprintln!("[Logging] Unable to reopen the log file: {}", err);
+            }
+            if self.should_rotate(line.len() as u64 + 1) {
+                if let Err(err) = self.rotate() {
+                    eprintln!("[Logging] Unable to rotate the log file: {}", err);
+                }
+            }
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn reopen_if_requested(&mut self) -> io::Result<()> {
+        let generation = REOPEN_GENERATION.load(Ordering::Relaxed);
+        if generation == self.reopen_generation {
+            return Ok(());
+        }
+        self.reopen_generation = generation;
+        self.reopen()
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        self.file = open_append(&self.path)?;
+        self.size = self.file.metadata()?.len();
+        self.opened_at = Instant::now();
+        Ok(())
+    }
+
+    fn should_rotate(&self, next_write: u64) -> bool {
+        let rotation = match &self.rotation {
+            Some(rotation) => rotation,
+            None => return false,
+        };
+        // An empty file is never rotated, even if a single line is over the size limit
+        if self.size == 0 {
+            return false;
+        }
+        let too_large = rotation
+            .max_file_size_bytes
+            .map_or(false, |max_size| self.size + next_write > max_size);
+        let too_old = rotation.rotation_interval_secs.map_or(false, |interval| {
+            self.opened_at.elapsed() >= Duration::from_secs(interval)
+        });
+        too_large || too_old
+    }
+
+    /// Moves the current file aside and starts a new one. The rotated file is compressed and old
+    /// files are deleted in the background.
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotation = match &self.rotation {
+            Some(rotation) => rotation.clone(),
+            None => return Ok(()),
+        };
+        self.file.flush()?;
+        let rotated_path = rotated_path(&self.path);
+        fs::rename(&self.path, &rotated_path)?;
+        self.reopen()?;
+
+        // The writes go on while the cleanup runs, which is restarted if it panicked
+        let rotated_path = match &self.cleanup {
+            Some(cleanup) if !cleanup.worker.is_finished() => {
+                match cleanup.sender.send(rotated_path) {
+                    Ok(()) => return Ok(()),
+                    Err(mpsc::SendError(rotated_path)) => rotated_path,
+                }
+            },
+            _ => rotated_path,
+        };
+        let cleanup = Cleanup::start(self.path.clone(), rotation)?;
+        // The receiver is alive until the sender is dropped
+        let _ = cleanup.sender.send(rotated_path);
+        self.cleanup = Some(cleanup);
+        Ok(())
+    }
+
+    /// Waits until the rotated files so far are compressed and the old ones deleted
+    #[cfg(test)]
+    fn wait_for_cleanup(&mut self) {
+        if let Some(Cleanup { sender, worker }) = self.cleanup.take() {
+            drop(sender);
+            let _ = worker.join();
+        }
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().append(true).create(true).open(path)
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(Utc::now().format(ROTATED_TIMESTAMP_FORMAT).to_string());
+    path.with_file_name(file_name)
+}
+
+/// Gzips the file next to it, and removes it once the compressed file is complete
+fn compress(path: &Path) -> io::Result<()> {
+    let compressed_path = with_extension(path, GZIP_EXTENSION);
+    let partial_path = with_extension(&compressed_path, PARTIAL_EXTENSION);
+
+    let mut encoder = GzEncoder::new(File::create(&partial_path)?, Compression::default());
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::rename(&partial_path, &compressed_path)?;
+    fs::remove_file(path)
+}
+
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_os_string();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// Deletes the oldest rotated files of the log file at `path`, so that `max_files` remain
+fn remove_old_files(path: &Path, max_files: usize) -> io::Result<()> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let prefix = format!(
+        "{}.",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    );
+
+    // Rotated files by timestamp, a file and its compressed copy count as one
+    let mut rotated = BTreeSet::new();
+    for entry in fs::read_dir(directory)? {
+        let file_name = entry?.file_name().to_string_lossy().into_owned();
+        let timestamp = match file_name.strip_prefix(&prefix) {
+            Some(suffix) => suffix
+                .strip_suffix(&format!(".{}", GZIP_EXTENSION))
+                .unwrap_or(suffix),
+            None => continue,
+        };
+        if is_rotated_timestamp(timestamp) {
+            rotated.insert(timestamp.to_string());
+        }
+    }
+
+    let excess = rotated.len().saturating_sub(max_files);
+    for timestamp in rotated.into_iter().take(excess) {
+        let rotated_path = directory.join(format!("{}{}", prefix, timestamp));
+        for path in [with_extension(&rotated_path, GZIP_EXTENSION), rotated_path] {
+            match fs::remove_file(&path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {},
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_rotated_timestamp(timestamp: &str) -> bool {
+    chrono::NaiveDateTime::parse_from_str(timestamp, ROTATED_TIMESTAMP_FORMAT).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn rotated_files(directory: &Path) -> Vec<String> {
+        let mut files: Vec<_> = fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|file_name| file_name.starts_with("node.log."))
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn rotate_by_size() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("node.log");
+        let rotation = LogRotationConfig {
+            max_file_size_bytes: Some(20),
+            rotation_interval_secs: None,
+            max_files: 2,
+            compress: false,
+            reopen_on_sighup: false,
+        };
+        let mut log_file = LogFile::open(path.clone(), Some(rotation)).unwrap();
+
+        // Each line is 10 bytes with its newline, so two lines fit in a file
+        for i in 0..9 {
+            log_file.write_line(&format!("line {:04}", i)).unwrap();
+            // Rotated file names only differ by their timestamp
+            thread::sleep(Duration::from_millis(1));
+        }
+        log_file.wait_for_cleanup();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line 0008\n");
+        let files = rotated_files(directory.path());
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            fs::read_to_string(directory.path().join(&files[0])).unwrap(),
+            "line 0004\nline 0005\n"
+        );
+        assert_eq!(
+            fs::read_to_string(directory.path().join(&files[1])).unwrap(),
+            "line 0006\nline 0007\n"
+        );
+    }
+
+    #[test]
+    fn compress_rotated_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("node.log");
+        let rotation = LogRotationConfig {
+            max_file_size_bytes: None,
+            rotation_interval_secs: Some(0),
+            max_files: 1,
+            compress: true,
+            reopen_on_sighup: false,
+        };
+        let mut log_file = LogFile::open(path.clone(), Some(rotation)).unwrap();
+
+        log_file.write_line("first").unwrap();
+        log_file.write_line("second").unwrap();
+        log_file.wait_for_cleanup();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+        let files = rotated_files(directory.path());
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with(".gz"));
+        let mut decompressed = String::new();
+        GzDecoder::new(File::open(directory.path().join(&files[0])).unwrap())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, "first\n");
+    }
+
+    #[test]
+    fn reopen_moved_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("node.log");
+        let rotation = LogRotationConfig {
+            max_file_size_bytes: None,
+            reopen_on_sighup: false,
+            ..LogRotationConfig::default()
+        };
+        let mut log_file = LogFile::open(path.clone(), Some(rotation)).unwrap();
+
+        log_file.write_line("before").unwrap();
+        let moved_path = directory.path().join("moved.log");
+        fs::rename(&path, &moved_path).unwrap();
+        request_reopen();
+        log_file.write_line("after").unwrap();
+
+        assert_eq!(fs::read_to_string(&moved_path).unwrap(), "before\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "after\n");
+    }
+}
@@ -3,7 +3,7 @@
 
 //! Global logger definition and functions
 
-use crate::{counters::STRUCT_LOG_COUNT, error, Event, Metadata};
+use crate::{counters::STRUCT_LOG_COUNT, error, span_export::SpanExportLayer, Event, Metadata};
 use once_cell::sync::OnceCell;
 use std::sync::Arc;
 use tracing_subscriber::prelude::*;
@@ -40,7 +40,11 @@ pub(crate) fn enabled(metadata: &Metadata) -> bool {
 }
 
 /// Sets the global `Logger` exactly once
-pub fn set_global_logger(logger: Arc<dyn Logger>, console_port: Option<u16>) {
+pub fn set_global_logger(
+    logger: Arc<dyn Logger>,
+    console_port: Option<u16>,
+    span_export: Option<SpanExportLayer>,
+) {
     if LOGGER.set(logger).is_err() {
         eprintln!("Global logger has already been set");
         error!("Global logger has already been set");
@@ -59,14 +63,18 @@ pub fn set_global_logger(logger: Arc<dyn Logger>, console_port: Option<u16>) {
                 .server_addr(([0, 0, 0, 0], p))
                 .spawn();
 
-            tracing_subscriber::registry().with(console_layer).init();
+            tracing_subscriber::registry()
+                .with(console_layer)
+                .with(span_export)
+                .init();
             return;
         }
     }
     if console_port.is_none() {
         let _ = tracing::subscriber::set_global_default(
-            crate::tracing_adapter::TracingToAptosDataLayer
-                .with_subscriber(tracing_subscriber::Registry::default()),
+            tracing_subscriber::Registry::default()
+                .with(crate::tracing_adapter::TracingToAptosDataLayer)
+                .with(span_export),
         );
     } else {
         error!("console_port was set but has no effect, build with --cfg aptos-console");
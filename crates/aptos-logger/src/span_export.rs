@@ -0,0 +1,605 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exports transaction lifecycle spans in the OpenTelemetry (OTLP/JSON) format.
+//!
+//! Spans are created with the [`txn_span!`](crate::txn_span) macro, which tags them with
+//! [`TXN_TRACE_TARGET`]. Only those spans are exported, everything else keeps going through
+//! the regular [`TracingToAptosDataLayer`](crate::tracing_adapter::TracingToAptosDataLayer).
+//!
+//! A span carrying one of the key fields `txn_hash`, `batch_id` or `block_id` derives its trace
+//! id from that value, so every stage a transaction goes through on this node ends up in the
+//! same trace, even when the stages run on unrelated threads. Spans without a key join the
+//! trace of their parent span.
+//!
+//! Two extra fields are understood by the exporter and are not exported as attributes:
+//! * `otel.name` overrides the span name, which lets a single callsite emit differently
+//!   named stages.
+//! * `otel.start_time_unix_nano` overrides the start time of the span, for stages that started
+//!   before the span could be created (e.g. the time a transaction was inserted into mempool).
+//!
+//! ```
+//! use aptos_logger::txn_span;
+//!
+//! let txn_hash = "2a5ef9c0b5f5c1f2a3c2d3b4e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3f2";
+//! let _entered = txn_span!("mempool.add_txn", txn_hash = txn_hash).entered();
+//! // => exported once the span is closed
+//! ```
+
+use crate::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{
+        hash_map::{DefaultHasher, RandomState},
+        BTreeMap,
+    },
+    fmt,
+    fs::{File, OpenOptions},
+    hash::{BuildHasher, Hash, Hasher},
+    io::{self, BufRead, BufReader, Write},
+    net::TcpStream,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+// Lets instrumented crates create disabled spans and record fields later without depending on
+// tracing themselves
+pub use tracing::{field::Empty, Span};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// The tracing target of spans that are exported
+pub const TXN_TRACE_TARGET: &str = "aptos_txn_trace";
+
+/// Fields that key a trace, in order of precedence
+const KEY_FIELDS: [&str; 3] = ["txn_hash", "batch_id", "block_id"];
+const NAME_FIELD: &str = "otel.name";
+const START_TIME_FIELD: &str = "otel.start_time_unix_nano";
+
+/// OTLP span kind for spans internal to the node
+const SPAN_KIND_INTERNAL: u8 = 1;
+const COLLECTOR_TRACES_PATH: &str = "/v1/traces";
+const COLLECTOR_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Set once an exporter is running, so callers can skip computing span fields otherwise
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Creates a span that is exported by the [`SpanExportLayer`], see the module docs for the
+/// fields it understands.
+#[macro_export]
+macro_rules! txn_span {
+    ($name:expr) => {
+        $crate::tracing::info_span!(target: $crate::span_export::TXN_TRACE_TARGET, $name)
+    };
+    ($name:expr, $($fields:tt)*) => {
+        $crate::tracing::info_span!(
+            target: $crate::span_export::TXN_TRACE_TARGET,
+            $name,
+            $($fields)*
+        )
+    };
+}
+
+/// Returns true if spans are being exported. Use this to avoid computing transaction hashes
+/// and other span fields when nobody is going to look at them.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Converts a time to the nanoseconds since the unix epoch, as expected by
+/// `otel.start_time_unix_nano`
+pub fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Where the exported spans are sent
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanExportDestination {
+    /// Appends one OTLP/JSON `ExportTraceServiceRequest` per line to the file
+    File(PathBuf),
+    /// Posts OTLP/JSON requests to the HTTP receiver of a local collector, e.g. `127.0.0.1:4318`
+    Collector(String),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpanExportConfig {
+    pub destination: SpanExportDestination,
+    /// Reported as the `service.name` resource attribute
+    pub service_name: String,
+    /// Number of finished spans buffered before new ones are dropped
+    pub channel_size: usize,
+    /// Maximum number of spans sent in one request
+    pub batch_size: usize,
+    /// Maximum time a finished span waits before being sent
+    pub flush_interval_ms: u64,
+}
+
+impl Default for SpanExportConfig {
+    fn default() -> Self {
+        Self {
+            destination: SpanExportDestination::Collector("127.0.0.1:4318".into()),
+            service_name: "aptos-node".into(),
+            channel_size: 10_000,
+            batch_size: 512,
+            flush_interval_ms: 1_000,
+        }
+    }
+}
+
+/// An OTLP attribute value
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum AnyValue {
+    StringValue(String),
+    BoolValue(bool),
+    // OTLP/JSON encodes 64 bit integers as strings
+    IntValue(String),
+    DoubleValue(f64),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct KeyValue {
+    key: String,
+    value: AnyValue,
+}
+
+/// A finished span, serialized as an OTLP/JSON span
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedSpan {
+    trace_id: String,
+    span_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_span_id: Option<String>,
+    name: String,
+    kind: u8,
+    start_time_unix_nano: String,
+    end_time_unix_nano: String,
+    attributes: Vec<KeyValue>,
+}
+
+/// The state of an open span, kept in the span's extensions
+struct SpanState {
+    trace_id: String,
+    span_id: u64,
+    parent_span_id: Option<u64>,
+    fields: SpanFields,
+}
+
+#[derive(Default)]
+struct SpanFields {
+    name: Option<String>,
+    start_time_unix_nano: Option<u64>,
+    attributes: BTreeMap<&'static str, AnyValue>,
+}
+
+impl SpanFields {
+    fn insert(&mut self, field: &Field, value: AnyValue) {
+        match (field.name(), value) {
+            (NAME_FIELD, AnyValue::StringValue(name)) => self.name = Some(name),
+            (START_TIME_FIELD, AnyValue::IntValue(start)) => {
+                self.start_time_unix_nano = start.parse().ok()
+            },
+            (name, value) => {
+                self.attributes.insert(name, value);
+            },
+        }
+    }
+
+    fn key(&self) -> Option<&str> {
+        KEY_FIELDS
+            .iter()
+            .find_map(|key| match self.attributes.get(key) {
+                Some(AnyValue::StringValue(value)) => Some(value.as_str()),
+                _ => None,
+            })
+    }
+}
+
+impl Visit for SpanFields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, AnyValue::DoubleValue(value))
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, AnyValue::IntValue(value.to_string()))
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, AnyValue::IntValue(value.to_string()))
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, AnyValue::BoolValue(value))
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, AnyValue::StringValue(value.to_string()))
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, AnyValue::StringValue(format!("{:?}", value)))
+    }
+}
+
+/// Derives the trace id of a key. Keys that are hashes keep their leading bytes, so a trace can
+/// be found from the transaction hash alone.
+fn trace_id_for_key(key: &str) -> String {
+    let hex = key.trim_start_matches("0x");
+    if hex.len() >= 32 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return hex[..32].to_ascii_lowercase();
+    }
+
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    let high = hasher.finish();
+    high.hash(&mut hasher);
+    format!("{:016x}{:016x}", high, hasher.finish())
+}
+
+/// The splitmix64 finalizer, used to turn a counter into well distributed ids
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+/// A tracing layer that exports the spans created with [`txn_span!`](crate::txn_span)
+pub struct SpanExportLayer {
+    sender: SyncSender<ExportedSpan>,
+    seed: u64,
+    next_id: AtomicU64,
+}
+
+impl SpanExportLayer {
+    /// Creates the layer and starts the thread sending finished spans to the destination
+    pub fn new(config: &SpanExportConfig) -> io::Result<Self> {
+        let exporter = match &config.destination {
+            SpanExportDestination::File(path) => {
+                Exporter::File(OpenOptions::new().create(true).append(true).open(path)?)
+            },
+            SpanExportDestination::Collector(address) => Exporter::Collector(address.clone()),
+        };
+        let (sender, receiver) = mpsc::sync_channel(config.channel_size);
+        let service = SpanExportService {
+            receiver,
+            exporter,
+            service_name: config.service_name.clone(),
+            batch_size: config.batch_size.max(1),
+            flush_interval: Duration::from_millis(config.flush_interval_ms),
+        };
+        thread::Builder::new()
+            .name("span-export".into())
+            .spawn(move || service.run())?;
+
+        ENABLED.store(true, Ordering::Relaxed);
+        Ok(Self::with_sender(sender))
+    }
+
+    fn with_sender(sender: SyncSender<ExportedSpan>) -> Self {
+        Self {
+            sender,
+            seed: RandomState::new().build_hasher().finish(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn new_id(&self) -> u64 {
+        loop {
+            let id = mix(self.seed ^ self.next_id.fetch_add(1, Ordering::Relaxed));
+            if id != 0 {
+                return id;
+            }
+        }
+    }
+
+    fn new_trace_id(&self) -> String {
+        format!("{:016x}{:016x}", self.new_id(), self.new_id())
+    }
+}
+
+impl<S> Layer<S> for SpanExportLayer
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().target() != TXN_TRACE_TARGET {
+            return;
+        }
+        let span = ctx.span(id).expect("Unable to load span; this is a bug");
+
+        let mut fields = SpanFields::default();
+        attrs.record(&mut fields);
+
+        let parent = span.parent().and_then(|parent| {
+            parent
+                .extensions()
+                .get::<SpanState>()
+                .map(|state| (state.trace_id.clone(), state.span_id))
+        });
+        let (trace_id, parent_span_id) = match (fields.key().map(trace_id_for_key), parent) {
+            (Some(trace_id), Some((parent_trace_id, parent_span_id)))
+                if trace_id == parent_trace_id =>
+            {
+                (trace_id, Some(parent_span_id))
+            },
+            (Some(trace_id), _) => (trace_id, None),
+            (None, Some((parent_trace_id, parent_span_id))) => {
+                (parent_trace_id, Some(parent_span_id))
+            },
+            (None, None) => (self.new_trace_id(), None),
+        };
+
+        if fields.start_time_unix_nano.is_none() {
+            fields.start_time_unix_nano = Some(unix_nanos(SystemTime::now()));
+        }
+        span.extensions_mut().insert(SpanState {
+            trace_id,
+            span_id: self.new_id(),
+            parent_span_id,
+            fields,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(state) = span.extensions_mut().get_mut::<SpanState>() {
+                values.record(&mut state.fields);
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(&id) {
+            Some(span) => span,
+            None => return,
+        };
+        let state = match span.extensions_mut().remove::<SpanState>() {
+            Some(state) => state,
+            None => return,
+        };
+
+        let SpanState {
+            trace_id,
+            span_id,
+            parent_span_id,
+            fields,
+        } = state;
+        let exported = ExportedSpan {
+            trace_id,
+            span_id: format!("{:016x}", span_id),
+            parent_span_id: parent_span_id.map(|id| format!("{:016x}", id)),
+            name: fields
+                .name
+                .unwrap_or_else(|| span.metadata().name().to_string()),
+            kind: SPAN_KIND_INTERNAL,
+            start_time_unix_nano: fields.start_time_unix_nano.unwrap_or_default().to_string(),
+            end_time_unix_nano: unix_nanos(SystemTime::now()).to_string(),
+            attributes: fields
+                .attributes
+                .into_iter()
+                .map(|(key, value)| KeyValue {
+                    key: key.to_string(),
+                    value,
+                })
+                .collect(),
+        };
+
+        // Never block the traced code on the exporter, drop the span instead
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(exported) {
+            crate::counters::SPAN_EXPORT_DROPPED_COUNT.inc();
+        }
+    }
+}
+
+enum Exporter {
+    File(File),
+    Collector(String),
+}
+
+impl Exporter {
+    fn export(&mut self, body: &[u8]) -> io::Result<()> {
+        match self {
+            Exporter::File(file) => {
+                file.write_all(body)?;
+                file.write_all(b"\n")?;
+                file.flush()
+            },
+            Exporter::Collector(address) => post_to_collector(address, body),
+        }
+    }
+}
+
+/// Sends a request to the OTLP/HTTP receiver of a collector. The collector is expected to be
+/// local, so a plain HTTP/1.1 request is enough.
+fn post_to_collector(address: &str, body: &[u8]) -> io::Result<()> {
+    let mut stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(COLLECTOR_TIMEOUT))?;
+    stream.set_write_timeout(Some(COLLECTOR_TIMEOUT))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        COLLECTOR_TRACES_PATH,
+        address,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("collector responded with '{}'", status_line.trim()),
+        )),
+    }
+}
+
+struct SpanExportService {
+    receiver: Receiver<ExportedSpan>,
+    exporter: Exporter,
+    service_name: String,
+    batch_size: usize,
+    flush_interval: Duration,
+}
+
+impl SpanExportService {
+    fn run(mut self) {
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut deadline = Instant::now() + self.flush_interval;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let disconnected = match self.receiver.recv_timeout(timeout) {
+                Ok(span) => {
+                    batch.push(span);
+                    false
+                },
+                Err(RecvTimeoutError::Timeout) => false,
+                Err(RecvTimeoutError::Disconnected) => true,
+            };
+
+            if batch.len() >= self.batch_size || Instant::now() >= deadline || disconnected {
+                if !batch.is_empty() {
+                    self.export(std::mem::take(&mut batch));
+                }
+                deadline = Instant::now() + self.flush_interval;
+            }
+            if disconnected {
+                return;
+            }
+        }
+    }
+
+    fn export(&mut self, spans: Vec<ExportedSpan>) {
+        let num_spans = spans.len();
+        let request = export_request(&self.service_name, spans);
+        let result = serde_json::to_vec(&request)
+            .map_err(io::Error::from)
+            .and_then(|body| self.exporter.export(&body));
+        if let Err(error) = result {
+            crate::counters::SPAN_EXPORT_DROPPED_COUNT.inc_by(num_spans as u64);
+            warn!(
+                error = %error,
+                "[span-export] Failed to export {} spans", num_spans
+            );
+        }
+    }
+}
+
+/// Builds an OTLP/JSON `ExportTraceServiceRequest`
+fn export_request(service_name: &str, spans: Vec<ExportedSpan>) -> serde_json::Value {
+    let service_name = KeyValue {
+        key: "service.name".into(),
+        value: AnyValue::StringValue(service_name.into()),
+    };
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [service_name],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "aptos-logger" },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::prelude::*;
+
+    fn attribute<'a>(span: &'a ExportedSpan, key: &str) -> Option<&'a AnyValue> {
+        span.attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| &attribute.value)
+    }
+
+    #[test]
+    fn trace_id_derivation() {
+        let hash = "0xAB0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        assert_eq!(trace_id_for_key(hash), "ab0102030405060708090a0b0c0d0e0f");
+        assert_eq!(trace_id_for_key("epoch 3 round 7").len(), 32);
+        assert_eq!(
+            trace_id_for_key("epoch 3 round 7"),
+            trace_id_for_key("epoch 3 round 7")
+        );
+        assert_ne!(
+            trace_id_for_key("epoch 3 round 7"),
+            trace_id_for_key("epoch 3 round 8")
+        );
+    }
+
+    #[test]
+    fn export_keyed_spans() {
+        let (sender, receiver) = mpsc::sync_channel(16);
+        let subscriber = tracing_subscriber::registry().with(SpanExportLayer::with_sender(sender));
+        let hash = "1f1e1d1c1b1a191817161514131211100f0e0d0c0b0a09080706050403020100";
+
+        tracing::subscriber::with_default(subscriber, || {
+            let stage = "mempool.commit";
+            let parent = txn_span!(
+                "mempool",
+                txn_hash = hash,
+                otel.name = stage,
+                otel.start_time_unix_nano = 42u64,
+                num_txns = tracing::field::Empty
+            );
+            parent.in_scope(|| {
+                txn_span!("validate", is_valid = true).in_scope(|| {});
+                tracing::info_span!("not_exported").in_scope(|| {});
+            });
+            parent.record("num_txns", &3u64);
+            drop(parent);
+            txn_span!("other").in_scope(|| {});
+        });
+
+        let spans: Vec<_> = receiver.try_iter().collect();
+        assert_eq!(spans.len(), 3);
+        let (child, parent, other) = (&spans[0], &spans[1], &spans[2]);
+
+        assert_eq!(parent.name, "mempool.commit");
+        assert_eq!(parent.trace_id, &hash[..32]);
+        assert_eq!(parent.parent_span_id, None);
+        assert_eq!(parent.start_time_unix_nano, "42");
+        assert_eq!(
+            attribute(parent, "num_txns"),
+            Some(&AnyValue::IntValue("3".into()))
+        );
+        assert_eq!(attribute(parent, NAME_FIELD), None);
+        assert_eq!(attribute(parent, START_TIME_FIELD), None);
+
+        assert_eq!(child.name, "validate");
+        assert_eq!(child.trace_id, parent.trace_id);
+        assert_eq!(child.parent_span_id.as_ref(), Some(&parent.span_id));
+        assert_eq!(
+            attribute(child, "is_valid"),
+            Some(&AnyValue::BoolValue(true))
+        );
+
+        assert_ne!(other.trace_id, parent.trace_id);
+        assert_eq!(other.parent_span_id, None);
+
+        let request = export_request("test", spans.clone());
+        assert_eq!(
+            request["resourceSpans"][0]["scopeSpans"][0]["spans"][1]["traceId"],
+            serde_json::json!(&hash[..32])
+        );
+    }
+}
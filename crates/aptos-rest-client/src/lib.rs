@@ -7,6 +7,8 @@ pub mod aptos;
 pub mod error;
 pub mod faucet;
 pub use faucet::FaucetClient;
+pub mod light_client;
+pub use light_client::LightClient;
 pub mod response;
 pub use response::Response;
 pub mod state;
@@ -23,9 +25,9 @@ pub use aptos_api_types::{
 use aptos_api_types::{
     deserialize_from_string,
     mime_types::{BCS, BCS_SIGNED_TRANSACTION as BCS_CONTENT_TYPE},
-    AptosError, BcsBlock, Block, GasEstimation, HexEncodedBytes, IndexResponse, MoveModuleId,
-    TransactionData, TransactionOnChainData, TransactionsBatchSubmissionResult, UserTransaction,
-    VersionedEvent,
+    AptosError, BcsBlock, BcsStateValueWithProof, BcsTransactionWithProof, Block, GasEstimation,
+    HexEncodedBytes, IndexResponse, MoveModuleId, TransactionData, TransactionOnChainData,
+    TransactionsBatchSubmissionResult, UserTransaction, VersionedEvent,
 };
 use aptos_crypto::HashValue;
 use aptos_logger::{debug, info, sample, sample::SampleRate};
@@ -33,6 +35,7 @@ use aptos_types::{
     account_address::AccountAddress,
     account_config::{AccountResource, CoinStoreResource, NewBlockEvent, CORE_CODE_ADDRESS},
     contract_event::EventWithVersion,
+    state_proof::StateProof,
     transaction::SignedTransaction,
 };
 use move_core_types::language_storage::StructTag;
@@ -1112,6 +1115,98 @@ impl Client {
         self.get_bcs(url).await
     }
 
+    /// Retrieves the latest signed ledger info, along with the epoch change proofs since
+    /// `known_version`
+    pub async fn get_state_proof_bcs(
+        &self,
+        known_version: Option<u64>,
+    ) -> AptosResult<Response<StateProof>> {
+        let url = self.build_path("state_proof")?;
+        self.get_with_proof_bcs(url, None, known_version).await
+    }
+
+    /// Retrieves a resource along with the proofs to verify it.
+    ///
+    /// Note: the proofs are not verified, see [`LightClient`] for that.
+    pub async fn get_account_resource_with_proof_bcs(
+        &self,
+        address: AccountAddress,
+        resource_type: &str,
+        ledger_version: Option<u64>,
+        known_version: Option<u64>,
+    ) -> AptosResult<Response<BcsStateValueWithProof>> {
+        let url = self.build_path(&format!(
+            "accounts/{}/resource/{}/proof",
+            address, resource_type
+        ))?;
+        self.get_with_proof_bcs(url, ledger_version, known_version)
+            .await
+    }
+
+    /// Retrieves a module along with the proofs to verify it.
+    ///
+    /// Note: the proofs are not verified, see [`LightClient`] for that.
+    pub async fn get_account_module_with_proof_bcs(
+        &self,
+        address: AccountAddress,
+        module_name: &str,
+        ledger_version: Option<u64>,
+        known_version: Option<u64>,
+    ) -> AptosResult<Response<BcsStateValueWithProof>> {
+        let url = self.build_path(&format!(
+            "accounts/{}/module/{}/proof",
+            address, module_name
+        ))?;
+        self.get_with_proof_bcs(url, ledger_version, known_version)
+            .await
+    }
+
+    /// Retrieves a committed transaction along with the proofs to verify it.
+    ///
+    /// Note: the proofs are not verified, see [`LightClient`] for that.
+    pub async fn get_transaction_by_version_with_proof_bcs(
+        &self,
+        version: u64,
+        known_version: Option<u64>,
+    ) -> AptosResult<Response<BcsTransactionWithProof>> {
+        let url = self.build_path(&format!("transactions/by_version/{}/proof", version))?;
+        self.get_with_proof_bcs(url, None, known_version).await
+    }
+
+    /// Retrieves a committed transaction along with the proofs to verify it.
+    ///
+    /// Note: the proofs are not verified, see [`LightClient`] for that.
+    pub async fn get_transaction_by_hash_with_proof_bcs(
+        &self,
+        hash: HashValue,
+        known_version: Option<u64>,
+    ) -> AptosResult<Response<BcsTransactionWithProof>> {
+        let url = self.build_path(&format!(
+            "transactions/by_hash/{}/proof",
+            hash.to_hex_literal()
+        ))?;
+        self.get_with_proof_bcs(url, None, known_version).await
+    }
+
+    async fn get_with_proof_bcs<T: DeserializeOwned>(
+        &self,
+        url: Url,
+        ledger_version: Option<u64>,
+        known_version: Option<u64>,
+    ) -> AptosResult<Response<T>> {
+        let mut request = self.inner.get(url).header(ACCEPT, BCS);
+        if let Some(ledger_version) = ledger_version {
+            request = request.query(&[("ledger_version", ledger_version)])
+        }
+        if let Some(known_version) = known_version {
+            request = request.query(&[("known_version", known_version)])
+        }
+
+        let response = request.send().await?;
+        let response = self.check_and_parse_bcs_response(response).await?;
+        Ok(response.and_then(|inner| bcs::from_bytes(&inner))?)
+    }
+
     pub async fn get_account_events(
         &self,
         address: AccountAddress,
@@ -0,0 +1,242 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! A client that verifies every response against a signed `LedgerInfo`.
+//!
+//! The light client starts from a trusted waypoint, and ratchets its `TrustedState`
+//! forward through the epoch change proofs carried by every response. Values are only
+//! returned once they are proven against a ledger info signed by the validators of the
+//! trusted epoch, so a single dishonest fullnode can't forge state or transactions.
+
+use crate::{error::RestError, Client, Response};
+use anyhow::anyhow;
+use aptos_api_types::BcsStateValueWithProof;
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_infallible::Mutex;
+use aptos_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    ledger_info::LedgerInfo,
+    state_proof::StateProof,
+    state_store::state_key::StateKey,
+    transaction::{TransactionWithProof, Version},
+    trusted_state::{TrustedState, TrustedStateChange},
+    waypoint::Waypoint,
+};
+use move_core_types::{
+    identifier::Identifier, language_storage::ModuleId, parser::parse_struct_tag,
+};
+use serde::de::DeserializeOwned;
+use std::future::Future;
+
+type AptosResult<T> = Result<T, RestError>;
+
+/// A REST client that only returns values proven against a trusted ledger
+pub struct LightClient {
+    client: Client,
+    trusted_state: Mutex<TrustedState>,
+}
+
+impl LightClient {
+    /// Creates a light client trusting the given epoch waypoint, e.g. the genesis waypoint
+    pub fn new(client: Client, waypoint: Waypoint) -> Self {
+        Self::from_trusted_state(client, TrustedState::from_epoch_waypoint(waypoint))
+    }
+
+    /// Creates a light client from a previously persisted trusted state
+    pub fn from_trusted_state(client: Client, trusted_state: TrustedState) -> Self {
+        Self {
+            client,
+            trusted_state: Mutex::new(trusted_state),
+        }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// The current trusted state, which can be persisted to resume from later
+    pub fn trusted_state(&self) -> TrustedState {
+        self.trusted_state.lock().clone()
+    }
+
+    /// Ratchets the trusted state to the latest ledger info of the fullnode
+    pub async fn sync(&self) -> AptosResult<Response<LedgerInfo>> {
+        let (response, ledger_info) = self
+            .fetch_verified(
+                move |known_version| self.client.get_state_proof_bcs(Some(known_version)),
+                |state_proof| state_proof,
+            )
+            .await?;
+        Ok(response.map(|_| ledger_info))
+    }
+
+    /// Retrieves a resource at the latest ledger version, `None` if it is proven to not exist
+    pub async fn get_account_resource_bcs<T: DeserializeOwned>(
+        &self,
+        address: AccountAddress,
+        resource_type: &str,
+    ) -> AptosResult<Response<Option<T>>> {
+        let struct_tag = parse_struct_tag(resource_type)?;
+        let state_key = StateKey::AccessPath(AccessPath::resource_access_path(address, struct_tag));
+        let response = self
+            .get_verified_state_value(&state_key, move |known_version| {
+                self.client.get_account_resource_with_proof_bcs(
+                    address,
+                    resource_type,
+                    None,
+                    Some(known_version),
+                )
+            })
+            .await?;
+        Ok(response
+            .and_then(|state_value| state_value.map(|bytes| bcs::from_bytes(&bytes)).transpose())?)
+    }
+
+    /// Retrieves a module's bytecode at the latest ledger version, `None` if it is proven to
+    /// not exist
+    pub async fn get_account_module_bcs(
+        &self,
+        address: AccountAddress,
+        module_name: &str,
+    ) -> AptosResult<Response<Option<Vec<u8>>>> {
+        let module_id = ModuleId::new(address, Identifier::new(module_name)?);
+        let state_key = StateKey::AccessPath(AccessPath::code_access_path(module_id));
+        self.get_verified_state_value(&state_key, move |known_version| {
+            self.client.get_account_module_with_proof_bcs(
+                address,
+                module_name,
+                None,
+                Some(known_version),
+            )
+        })
+        .await
+    }
+
+    /// Retrieves a committed transaction and its events by version
+    pub async fn get_transaction_by_version_bcs(
+        &self,
+        version: Version,
+    ) -> AptosResult<Response<TransactionWithProof>> {
+        let (response, ledger_info) = self
+            .fetch_verified(
+                move |known_version| {
+                    self.client
+                        .get_transaction_by_version_with_proof_bcs(version, Some(known_version))
+                },
+                |txn| &txn.state_proof,
+            )
+            .await?;
+        let txn = response.inner();
+        if txn.transaction_with_proof.version != version {
+            return Err(anyhow!(
+                "Transaction version ({}) not expected ({})",
+                txn.transaction_with_proof.version,
+                version
+            )
+            .into());
+        }
+        txn.verify(&ledger_info)?;
+        Ok(response.map(|txn| txn.transaction_with_proof))
+    }
+
+    /// Retrieves a committed transaction and its events by hash
+    pub async fn get_transaction_by_hash_bcs(
+        &self,
+        hash: HashValue,
+    ) -> AptosResult<Response<TransactionWithProof>> {
+        let (response, ledger_info) = self
+            .fetch_verified(
+                move |known_version| {
+                    self.client
+                        .get_transaction_by_hash_with_proof_bcs(hash, Some(known_version))
+                },
+                |txn| &txn.state_proof,
+            )
+            .await?;
+        let txn = response.inner();
+        let txn_hash = txn.transaction_with_proof.transaction.hash();
+        if txn_hash != hash {
+            return Err(anyhow!("Transaction hash ({}) not expected ({})", txn_hash, hash).into());
+        }
+        txn.verify(&ledger_info)?;
+        Ok(response.map(|txn| txn.transaction_with_proof))
+    }
+
+    async fn get_verified_state_value<F, Fut>(
+        &self,
+        state_key: &StateKey,
+        fetch: F,
+    ) -> AptosResult<Response<Option<Vec<u8>>>>
+    where
+        F: Fn(Version) -> Fut,
+        Fut: Future<Output = AptosResult<Response<BcsStateValueWithProof>>>,
+    {
+        let (response, ledger_info) = self
+            .fetch_verified(fetch, |value| &value.state_proof)
+            .await?;
+        response.inner().verify(&ledger_info, state_key)?;
+        Ok(response.map(|value| value.state_value.map(|v| v.into_bytes())))
+    }
+
+    /// Fetches a response with the client's trusted version, until the ledger info it carries
+    /// can be verified. Each round trip ratchets at least one epoch forward, so this stops once
+    /// the client is in the latest epoch.
+    ///
+    /// Each call verifies the responses against its own copy of the trusted state, which was the
+    /// one the request was made with, since concurrent calls may ratchet the shared one meanwhile.
+    async fn fetch_verified<T, F, Fut, P>(
+        &self,
+        fetch: F,
+        state_proof: P,
+    ) -> AptosResult<(Response<T>, LedgerInfo)>
+    where
+        F: Fn(Version) -> Fut,
+        Fut: Future<Output = AptosResult<Response<T>>>,
+        P: Fn(&T) -> &StateProof,
+    {
+        let mut trusted_state = self.trusted_state();
+        loop {
+            let response = fetch(trusted_state.version()).await?;
+            let (new_state, ledger_info) =
+                self.ratchet(trusted_state, state_proof(response.inner()))?;
+            if let Some(ledger_info) = ledger_info {
+                return Ok((response, ledger_info));
+            }
+            trusted_state = new_state;
+        }
+    }
+
+    /// Ratchets the trusted state with the state proof, and returns the new trusted state along
+    /// with its latest ledger info if it could be verified. The shared trusted state is only
+    /// replaced if the new one is more recent, so that it never goes backwards.
+    fn ratchet(
+        &self,
+        trusted_state: TrustedState,
+        state_proof: &StateProof,
+    ) -> anyhow::Result<(TrustedState, Option<LedgerInfo>)> {
+        let trusted_state = match trusted_state.verify_and_ratchet(state_proof)? {
+            TrustedStateChange::Epoch { new_state, .. }
+            | TrustedStateChange::Version { new_state } => new_state,
+            TrustedStateChange::NoChange => trusted_state,
+        };
+        {
+            let mut shared_trusted_state = self.trusted_state.lock();
+            if trusted_state.version() > shared_trusted_state.version() {
+                *shared_trusted_state = trusted_state.clone();
+            }
+        }
+
+        // If the epoch change proof was truncated, the trusted state is only ratcheted up to
+        // the last epoch change, and the latest ledger info isn't verified yet.
+        let latest_ledger_info = state_proof.latest_ledger_info();
+        let verified = trusted_state.waypoint().verify(latest_ledger_info).is_ok();
+        if !verified && !state_proof.epoch_changes().more {
+            return Err(anyhow!(
+                "Latest ledger info at version {} couldn't be verified",
+                latest_ledger_info.version()
+            ));
+        }
+        Ok((trusted_state, verified.then(|| latest_ledger_info.clone())))
+    }
+}
@@ -8,8 +8,7 @@
 
 use crate::{
     common::{
-        check_network, get_block_index_from_request, handle_request, native_coin, native_coin_tag,
-        with_context,
+        check_network, get_block_index_from_request, handle_request, native_coin, with_context,
     },
     error::{ApiError, ApiResult},
     types::{AccountBalanceRequest, AccountBalanceResponse, Amount, Currency, *},
@@ -53,7 +52,6 @@ async fn account_balance(
     let network_identifier = request.network_identifier;
 
     check_network(network_identifier, &server_context)?;
-    let rest_client = server_context.rest_client()?;
 
     // Retrieve the block index to read
     let block_height =
@@ -67,7 +65,7 @@ async fn account_balance(
     let balance_version = block_info.last_version;
 
     let (sequence_number, operators, balances) = get_balances(
-        &rest_client,
+        &server_context,
         request.account_identifier,
         balance_version,
         request.currencies,
@@ -87,11 +85,12 @@ async fn account_balance(
 /// Retrieve the balances for an account
 #[allow(clippy::manual_retain)]
 async fn get_balances(
-    rest_client: &aptos_rest_client::Client,
+    server_context: &RosettaContext,
     account: AccountIdentifier,
     version: u64,
     maybe_filter_currencies: Option<Vec<Currency>>,
 ) -> ApiResult<(u64, Option<Vec<AccountAddress>>, Vec<Amount>)> {
+    let rest_client = server_context.rest_client()?;
     let owner_address = account.account_address()?;
 
     // Retrieve all account resources
@@ -120,12 +119,14 @@ async fn get_balances(
                     if account.is_base_account() {
                         let coin_store: CoinStoreResource = bcs::from_bytes(&bytes)?;
                         if let Some(coin_type) = struct_tag.type_params.first() {
-                            // Only display supported coins
-                            if coin_type == &native_coin_tag() {
+                            // Only display coins that have a CoinInfo
+                            if let Some(currency) = server_context.get_currency(coin_type).await? {
                                 balances.push(Amount {
                                     value: coin_store.coin().to_string(),
-                                    currency: native_coin(),
+                                    currency,
                                 });
+                            } else {
+                                warn!("Skipping balance of unknown coin {}", coin_type);
                             }
                         }
                     }
@@ -144,7 +145,7 @@ async fn get_balances(
                             // Keep track of operators
                             maybe_operators.as_mut().unwrap().push(operator);
                             match get_total_stake(
-                                rest_client,
+                                &rest_client,
                                 &account,
                                 contract.pool_address,
                                 version,
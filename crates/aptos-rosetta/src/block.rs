@@ -3,16 +3,20 @@
 
 use crate::{
     common::{
-        check_network, get_block_index_from_request, get_timestamp, handle_request, with_context,
-        BlockHash, Y2K_MS,
+        check_network, get_block_index_from_request, get_timestamp, handle_request,
+        parse_transaction_hash, with_context, BlockHash, Y2K_MS,
+    },
+    error::{ApiError, ApiResult},
+    types::{
+        Block, BlockIdentifier, BlockRequest, BlockResponse, BlockTransactionRequest,
+        BlockTransactionResponse, Transaction,
     },
-    error::ApiResult,
-    types::{Block, BlockIdentifier, BlockRequest, BlockResponse, Transaction},
     RosettaContext,
 };
 use aptos_logger::{debug, trace};
+use aptos_rest_client::aptos_api_types::TransactionData;
 use aptos_types::chain_id::ChainId;
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc};
 use warp::Filter;
 
 pub fn block_route(
@@ -25,6 +29,16 @@ pub fn block_route(
         .and_then(handle_request(block))
 }
 
+pub fn block_transaction_route(
+    server_context: RosettaContext,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::path!("block" / "transaction")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_context(server_context))
+        .and_then(handle_request(block_transaction))
+}
+
 /// Retrieves a block (in this case a single transaction) given it's identifier.
 ///
 /// Our implementation allows for by `index`, which is the ledger `version` or by
@@ -69,6 +83,60 @@ async fn block(request: BlockRequest, server_context: RosettaContext) -> ApiResu
     Ok(BlockResponse { block })
 }
 
+/// Retrieves a transaction of a block by its hash
+///
+/// The block must be the one containing the transaction, its hash and index must match.
+///
+/// [API Spec](https://www.rosetta-api.org/docs/BlockApi.html#blocktransaction)
+async fn block_transaction(
+    request: BlockTransactionRequest,
+    server_context: RosettaContext,
+) -> ApiResult<BlockTransactionResponse> {
+    debug!("/block/transaction");
+    trace!(
+        request = ?request,
+        server_context = ?server_context,
+        "/block/transaction",
+    );
+
+    check_network(request.network_identifier, &server_context)?;
+
+    let block_identifier = request.block_identifier;
+    if BlockHash::from_str(&block_identifier.hash)?.block_height(server_context.chain_id)?
+        != block_identifier.index
+    {
+        return Err(ApiError::InvalidInput(Some(format!(
+            "Block hash {} doesn't match block index {}",
+            block_identifier.hash, block_identifier.index
+        ))));
+    }
+
+    let hash = parse_transaction_hash(&request.transaction_identifier)?;
+    let txn = match server_context
+        .rest_client()?
+        .get_transaction_by_hash_bcs(hash)
+        .await?
+        .into_inner()
+    {
+        TransactionData::OnChain(txn) => txn,
+        TransactionData::Pending(_) => return Err(ApiError::TransactionIsPending),
+    };
+
+    let block = server_context
+        .block_cache()?
+        .get_block_by_height(block_identifier.index, false)
+        .await?;
+    if txn.version < block.first_version || txn.version > block.last_version {
+        return Err(ApiError::TransactionNotFound(Some(format!(
+            "Transaction {} is not in block {}",
+            request.transaction_identifier.hash, block_identifier.index
+        ))));
+    }
+
+    let transaction = Transaction::from_transaction(&server_context, txn).await?;
+    Ok(BlockTransactionResponse { transaction })
+}
+
 /// Build up the transaction, which should contain the `operations` as the change set
 async fn build_block(
     server_context: &RosettaContext,
@@ -94,7 +162,7 @@ async fn build_block(
     }
 
     // Ensure the transactions are sorted in order
-    transactions.sort_by(|first, second| first.metadata.version.0.cmp(&second.metadata.version.0));
+    transactions.sort_by_key(|txn| txn.metadata.version.map(|version| version.0));
 
     Ok(Block {
         block_identifier,
@@ -5,21 +5,26 @@ use crate::{
     error::{ApiError, ApiResult},
     types::{
         Currency, CurrencyMetadata, MetadataRequest, NetworkIdentifier, PartialBlockIdentifier,
-        APTOS_COIN_MODULE, APTOS_COIN_RESOURCE,
+        TransactionIdentifier, APTOS_COIN_MODULE, APTOS_COIN_RESOURCE, COIN_INFO_RESOURCE,
+        COIN_MODULE,
     },
     RosettaContext,
 };
-use aptos_crypto::{ValidCryptoMaterial, ValidCryptoMaterialStringExt};
+use aptos_crypto::{HashValue, ValidCryptoMaterial, ValidCryptoMaterialStringExt};
+use aptos_infallible::RwLock;
 use aptos_logger::debug;
 use aptos_rest_client::{Account, Response};
 use aptos_sdk::move_types::{
     ident_str,
     language_storage::{StructTag, TypeTag},
+    parser::parse_type_tag,
+};
+use aptos_types::{
+    account_address::AccountAddress, account_config::CoinInfoResource, chain_id::ChainId,
 };
-use aptos_types::{account_address::AccountAddress, chain_id::ChainId};
 use futures::future::BoxFuture;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::{convert::Infallible, fmt::LowerHex, future::Future, str::FromStr};
+use std::{collections::HashMap, convert::Infallible, fmt::LowerHex, future::Future, str::FromStr};
 use warp::Filter;
 
 /// The year 2000 in milliseconds, as this is the lower limit for Rosetta API implementations
@@ -119,6 +124,14 @@ pub fn strip_hex_prefix(str: &str) -> &str {
     str.strip_prefix("0x").unwrap_or(str)
 }
 
+/// Parses the hash of a transaction, with or without the `0x` prefix
+pub fn parse_transaction_hash(
+    transaction_identifier: &TransactionIdentifier,
+) -> ApiResult<HashValue> {
+    HashValue::from_hex(strip_hex_prefix(&transaction_identifier.hash))
+        .map_err(|_| ApiError::deserialization_failed("TransactionIdentifier"))
+}
+
 pub fn encode_bcs<T: Serialize>(obj: &T) -> ApiResult<String> {
     let bytes = bcs::to_bytes(obj)?;
     Ok(hex::encode(bytes))
@@ -158,11 +171,97 @@ pub fn native_coin_tag() -> TypeTag {
     }))
 }
 
-pub fn is_native_coin(currency: &Currency) -> ApiResult<()> {
-    if currency == &native_coin() {
-        Ok(())
-    } else {
-        Err(ApiError::UnsupportedCurrency(Some(currency.symbol.clone())))
+/// Parses the coin type of a currency from its `move_type` metadata
+///
+/// Any `0x1::coin::Coin<T>` is supported, so `T` must be a struct
+pub fn parse_coin_type(currency: &Currency) -> ApiResult<TypeTag> {
+    if let Some(CurrencyMetadata { move_type }) = &currency.metadata {
+        if let Ok(coin_type @ TypeTag::Struct(_)) = parse_type_tag(move_type) {
+            return Ok(coin_type);
+        }
+    }
+
+    Err(ApiError::UnsupportedCurrency(Some(currency.symbol.clone())))
+}
+
+/// Cache of the [`Currency`] of each coin type, read from its `0x1::coin::CoinInfo`
+///
+/// The symbol and decimals of a coin never change, so they're kept forever once found
+#[derive(Debug, Default)]
+pub struct CoinCache {
+    currencies: RwLock<HashMap<TypeTag, Currency>>,
+}
+
+impl CoinCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Retrieves the currency of a coin type only if it was already looked up
+    pub fn get_cached_currency(&self, coin_type: &TypeTag) -> Option<Currency> {
+        if coin_type == &native_coin_tag() {
+            Some(native_coin())
+        } else {
+            self.currencies.read().get(coin_type).cloned()
+        }
+    }
+
+    /// Retrieves the currency of a coin type, or `None` if the coin doesn't exist
+    pub async fn get_currency(
+        &self,
+        rest_client: &aptos_rest_client::Client,
+        coin_type: &TypeTag,
+    ) -> ApiResult<Option<Currency>> {
+        if let Some(currency) = self.get_cached_currency(coin_type) {
+            return Ok(Some(currency));
+        }
+
+        // The CoinInfo is stored on the account that published the coin
+        let address = if let TypeTag::Struct(struct_tag) = coin_type {
+            struct_tag.address
+        } else {
+            return Ok(None);
+        };
+        let coin_info = match rest_client
+            .get_account_resource_bcs::<CoinInfoResource>(
+                address,
+                &format!(
+                    "0x1::{}::{}<{}>",
+                    COIN_MODULE, COIN_INFO_RESOURCE, coin_type
+                ),
+            )
+            .await
+            .map_err(ApiError::from)
+        {
+            Ok(response) => response.into_inner(),
+            Err(ApiError::AccountNotFound(_)) | Err(ApiError::ResourceNotFound(_)) => {
+                return Ok(None)
+            },
+            Err(err) => {
+                return Err(ApiError::CoinTypeFailedToBeFetched(Some(format!(
+                    "Failed to retrieve CoinInfo for {}: {}",
+                    coin_type, err
+                ))))
+            },
+        };
+
+        let symbol = coin_info.symbol().map_err(|_| {
+            ApiError::CoinTypeFailedToBeFetched(Some(format!(
+                "Symbol of {} isn't valid UTF-8",
+                coin_type
+            )))
+        })?;
+        let currency = Currency {
+            symbol,
+            decimals: coin_info.decimals(),
+            metadata: Some(CurrencyMetadata {
+                move_type: coin_type.to_string(),
+            }),
+        };
+        self.currencies
+            .write()
+            .insert(coin_type.clone(), currency.clone());
+        Ok(Some(currency))
     }
 }
 
@@ -280,21 +379,12 @@ pub fn to_hex_lower<T: LowerHex>(obj: &T) -> String {
     format!("{:x}", obj)
 }
 
-/// Retrieves the currency from the given parameters
-/// TODO: What do do about the type params?
-pub fn parse_currency(address: AccountAddress, module: &str, name: &str) -> ApiResult<Currency> {
-    match (address, module, name) {
-        (AccountAddress::ONE, APTOS_COIN_MODULE, APTOS_COIN_RESOURCE) => Ok(native_coin()),
-        _ => Err(ApiError::TransactionParseError(Some(format!(
-            "Invalid coin for transfer {}::{}::{}",
-            address, module, name
-        )))),
-    }
-}
-
 #[cfg(test)]
 mod test {
-    use crate::common::BlockHash;
+    use crate::{
+        common::{native_coin, native_coin_tag, parse_coin_type, BlockHash},
+        types::{Currency, CurrencyMetadata},
+    };
     use aptos_types::chain_id::{ChainId, NamedChain};
     use std::str::FromStr;
 
@@ -339,4 +429,27 @@ mod test {
             BlockHash::from_str(str).expect_err("Invalid block hash");
         }
     }
+
+    #[test]
+    pub fn coin_type_check() {
+        assert_eq!(
+            parse_coin_type(&native_coin()).expect("Native coin should parse"),
+            native_coin_tag()
+        );
+
+        let currency = |move_type: Option<&str>| Currency {
+            symbol: "USDC".to_string(),
+            decimals: 6,
+            metadata: move_type.map(|move_type| CurrencyMetadata {
+                move_type: move_type.to_string(),
+            }),
+        };
+        let coin_type = parse_coin_type(&currency(Some("0xcafe::usdc::USDC")))
+            .expect("Struct coin type should parse");
+        assert_eq!(coin_type.to_string(), "0xcafe::usdc::USDC");
+
+        for invalid in [None, Some("u64"), Some("0xcafe::usdc")] {
+            parse_coin_type(&currency(invalid)).expect_err("Invalid coin type");
+        }
+    }
 }
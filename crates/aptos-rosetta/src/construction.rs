@@ -27,7 +27,7 @@
 use crate::{
     common::{
         check_network, decode_bcs, decode_key, encode_bcs, get_account, handle_request,
-        native_coin, parse_currency, with_context,
+        native_coin, parse_coin_type, with_context,
     },
     error::{ApiError, ApiResult},
     types::{InternalOperation, *},
@@ -39,10 +39,7 @@ use aptos_crypto::{
 };
 use aptos_global_constants::adjust_gas_headroom;
 use aptos_logger::debug;
-use aptos_sdk::{
-    move_types::language_storage::{StructTag, TypeTag},
-    transaction_builder::TransactionFactory,
-};
+use aptos_sdk::{move_types::language_storage::TypeTag, transaction_builder::TransactionFactory};
 use aptos_types::{
     account_address::AccountAddress,
     chain_id::ChainId,
@@ -231,51 +228,28 @@ async fn fill_in_operator(
         InternalOperation::SetOperator(op) => {
             // If there was no old operator set, and there is only one, we should use that
             if op.old_operator.is_none() {
-                let store = rest_client
-                    .get_account_resource_bcs::<Store>(op.owner, "0x1::staking_contract::Store")
-                    .await?
-                    .into_inner();
-                if store.staking_contracts.len() != 1 {
-                    let operators: Vec<_> = store.staking_contracts.keys().collect();
-                    return Err(ApiError::InvalidInput(Some(format!(
-                        "Account has more than one operator, operator must be specified from: {:?}",
-                        operators
-                    ))));
-                } else {
-                    op.old_operator = Some(
-                        *store
-                            .staking_contracts
-                            .iter()
-                            .next()
-                            .map(|inner| inner.0)
-                            .unwrap(),
-                    );
-                }
+                op.old_operator = Some(get_only_operator(rest_client, op.owner).await?);
             }
         },
         InternalOperation::SetVoter(op) => {
             // If there was no operator set, and there is only one, we should use that
             if op.operator.is_none() {
-                let store = rest_client
-                    .get_account_resource_bcs::<Store>(op.owner, "0x1::staking_contract::Store")
-                    .await?
-                    .into_inner();
-                if store.staking_contracts.len() != 1 {
-                    let operators: Vec<_> = store.staking_contracts.keys().collect();
-                    return Err(ApiError::InvalidInput(Some(format!(
-                        "Account has more than one operator, operator must be specified from: {:?}",
-                        operators
-                    ))));
-                } else {
-                    op.operator = Some(
-                        *store
-                            .staking_contracts
-                            .iter()
-                            .next()
-                            .map(|inner| inner.0)
-                            .unwrap(),
-                    );
-                }
+                op.operator = Some(get_only_operator(rest_client, op.owner).await?);
+            }
+        },
+        InternalOperation::AddStake(op) => {
+            if op.operator.is_none() {
+                op.operator = Some(get_only_operator(rest_client, op.owner).await?);
+            }
+        },
+        InternalOperation::UnlockStake(op) => {
+            if op.operator.is_none() {
+                op.operator = Some(get_only_operator(rest_client, op.owner).await?);
+            }
+        },
+        InternalOperation::WithdrawStake(op) => {
+            if op.operator.is_none() {
+                op.operator = Some(get_only_operator(rest_client, op.owner).await?);
             }
         },
         _ => {},
@@ -284,6 +258,31 @@ async fn fill_in_operator(
     Ok(internal_operation)
 }
 
+/// Retrieves the operator of the owner's staking contract, if the owner has exactly one
+async fn get_only_operator(
+    rest_client: &aptos_rest_client::Client,
+    owner: AccountAddress,
+) -> ApiResult<AccountAddress> {
+    let store = rest_client
+        .get_account_resource_bcs::<Store>(owner, "0x1::staking_contract::Store")
+        .await?
+        .into_inner();
+    if store.staking_contracts.len() != 1 {
+        let operators: Vec<_> = store.staking_contracts.keys().collect();
+        Err(ApiError::InvalidInput(Some(format!(
+            "Account has more than one operator, operator must be specified from: {:?}",
+            operators
+        ))))
+    } else {
+        Ok(*store
+            .staking_contracts
+            .iter()
+            .next()
+            .map(|inner| inner.0)
+            .unwrap())
+    }
+}
+
 async fn simulate_transaction(
     rest_client: &aptos_rest_client::Client,
     chain_id: ChainId,
@@ -444,6 +443,16 @@ async fn construction_metadata(
         response.inner().sequence_number
     };
 
+    // Non-native coins must exist, and match their CoinInfo
+    if let InternalOperation::Transfer(transfer) = &request.options.internal_operation {
+        let coin_type = parse_coin_type(&transfer.currency)?;
+        if server_context.get_currency(&coin_type).await?.as_ref() != Some(&transfer.currency) {
+            return Err(ApiError::UnsupportedCurrency(Some(
+                transfer.currency.symbol.clone(),
+            )));
+        }
+    }
+
     // We have to cheat the set operator and set voter operations right here
     let internal_operation = fill_in_operator(
         rest_client.as_ref(),
@@ -522,8 +531,9 @@ async fn construction_parse(
                 module.name().as_str(),
                 function_name.as_str(),
             ) {
-                (AccountAddress::ONE, COIN_MODULE, TRANSFER_FUNCTION) => {
-                    parse_transfer_operation(sender, &type_args, &args)?
+                (AccountAddress::ONE, COIN_MODULE, TRANSFER_FUNCTION)
+                | (AccountAddress::ONE, APTOS_ACCOUNT_MODULE, TRANSFER_COINS_FUNCTION) => {
+                    parse_transfer_operation(&server_context, sender, &type_args, &args).await?
                 },
                 (AccountAddress::ONE, APTOS_ACCOUNT_MODULE, TRANSFER_FUNCTION) => {
                     parse_account_transfer_operation(sender, &type_args, &args)?
@@ -547,6 +557,15 @@ async fn construction_parse(
                 (AccountAddress::ONE, STAKING_CONTRACT_MODULE, RESET_LOCKUP_FUNCTION) => {
                     parse_reset_lockup_operation(sender, &type_args, &args)?
                 },
+                (AccountAddress::ONE, STAKING_CONTRACT_MODULE, ADD_STAKE_FUNCTION) => {
+                    parse_add_stake_operation(sender, &type_args, &args)?
+                },
+                (AccountAddress::ONE, STAKING_CONTRACT_MODULE, UNLOCK_STAKE_FUNCTION) => {
+                    parse_unlock_stake_operation(sender, &type_args, &args)?
+                },
+                (AccountAddress::ONE, STAKING_CONTRACT_MODULE, DISTRIBUTE_FUNCTION) => {
+                    parse_distribute_operation(sender, &type_args, &args)?
+                },
                 _ => {
                     return Err(ApiError::TransactionParseError(Some(format!(
                         "Unsupported entry function type {:x}::{}::{}",
@@ -602,25 +621,24 @@ fn parse_create_account_operation(
     }
 }
 
-fn parse_transfer_operation(
+async fn parse_transfer_operation(
+    server_context: &RosettaContext,
     sender: AccountAddress,
     type_args: &[TypeTag],
     args: &[Vec<u8>],
 ) -> ApiResult<Vec<Operation>> {
     let mut operations = Vec::new();
 
-    // Check coin is the native coin
-
+    // Non-native coins can only be looked up when online
     let currency = match type_args.first() {
-        Some(TypeTag::Struct(struct_tag)) => {
-            let StructTag {
-                address,
-                module,
-                name,
-                ..
-            } = &**struct_tag;
-
-            parse_currency(*address, module.as_str(), name.as_str())?
+        Some(coin_type @ TypeTag::Struct(_)) => {
+            match server_context.get_currency(coin_type).await {
+                Ok(Some(currency)) => currency,
+                Ok(None) | Err(ApiError::NodeIsOffline) => {
+                    return Err(ApiError::UnsupportedCurrency(Some(coin_type.to_string())))
+                },
+                Err(err) => return Err(err),
+            }
         },
         _ => {
             return Err(ApiError::TransactionParseError(Some(
@@ -823,6 +841,85 @@ pub fn parse_reset_lockup_operation(
     )])
 }
 
+pub fn parse_add_stake_operation(
+    sender: AccountAddress,
+    type_args: &[TypeTag],
+    args: &[Vec<u8>],
+) -> ApiResult<Vec<Operation>> {
+    if !type_args.is_empty() {
+        return Err(ApiError::TransactionParseError(Some(format!(
+            "Add stake should not have type arguments: {:?}",
+            type_args
+        ))));
+    }
+
+    let operator: AccountAddress = parse_function_arg("add_stake", args, 0)?;
+    let amount: u64 = parse_function_arg("add_stake", args, 1)?;
+    Ok(vec![Operation::add_stake(
+        0,
+        None,
+        sender,
+        Some(AccountIdentifier::base_account(operator)),
+        amount,
+    )])
+}
+
+pub fn parse_unlock_stake_operation(
+    sender: AccountAddress,
+    type_args: &[TypeTag],
+    args: &[Vec<u8>],
+) -> ApiResult<Vec<Operation>> {
+    if !type_args.is_empty() {
+        return Err(ApiError::TransactionParseError(Some(format!(
+            "Unlock stake should not have type arguments: {:?}",
+            type_args
+        ))));
+    }
+
+    let operator: AccountAddress = parse_function_arg("unlock_stake", args, 0)?;
+    let amount: u64 = parse_function_arg("unlock_stake", args, 1)?;
+    Ok(vec![Operation::unlock_stake(
+        0,
+        None,
+        sender,
+        Some(AccountIdentifier::base_account(operator)),
+        amount,
+    )])
+}
+
+/// Parses a distribute, which is a withdraw stake when the staker sends it themselves
+pub fn parse_distribute_operation(
+    sender: AccountAddress,
+    type_args: &[TypeTag],
+    args: &[Vec<u8>],
+) -> ApiResult<Vec<Operation>> {
+    if !type_args.is_empty() {
+        return Err(ApiError::TransactionParseError(Some(format!(
+            "Distribute should not have type arguments: {:?}",
+            type_args
+        ))));
+    }
+
+    let staker: AccountAddress = parse_function_arg("distribute", args, 0)?;
+    let operator: AccountAddress = parse_function_arg("distribute", args, 1)?;
+    if staker == sender {
+        Ok(vec![Operation::withdraw_stake(
+            0,
+            None,
+            sender,
+            Some(AccountIdentifier::base_account(operator)),
+        )])
+    } else {
+        Ok(vec![Operation::distribute(
+            0,
+            None,
+            staker,
+            AccountIdentifier::base_account(operator),
+            sender,
+        )])
+    }
+}
+
 /// Construction payloads command (OFFLINE)
 ///
 /// Constructs payloads for given known operations
@@ -924,6 +1021,71 @@ async fn construction_payloads(
                 ))));
             }
         },
+        InternalOperation::AddStake(inner) => {
+            if let InternalOperation::AddStake(ref metadata_op) = metadata.internal_operation {
+                if inner.owner == metadata_op.owner && inner.amount == metadata_op.amount {
+                    if inner.operator.is_none() {
+                        inner.operator = metadata_op.operator;
+                    }
+                } else {
+                    return Err(ApiError::InvalidInput(Some(format!(
+                        "Add stake operation doesn't match metadata {:?} vs {:?}",
+                        inner, metadata.internal_operation
+                    ))));
+                }
+            } else {
+                return Err(ApiError::InvalidInput(Some(format!(
+                    "Add stake operation doesn't match metadata {:?} vs {:?}",
+                    inner, metadata.internal_operation
+                ))));
+            }
+        },
+        InternalOperation::UnlockStake(inner) => {
+            if let InternalOperation::UnlockStake(ref metadata_op) = metadata.internal_operation {
+                if inner.owner == metadata_op.owner && inner.amount == metadata_op.amount {
+                    if inner.operator.is_none() {
+                        inner.operator = metadata_op.operator;
+                    }
+                } else {
+                    return Err(ApiError::InvalidInput(Some(format!(
+                        "Unlock stake operation doesn't match metadata {:?} vs {:?}",
+                        inner, metadata.internal_operation
+                    ))));
+                }
+            } else {
+                return Err(ApiError::InvalidInput(Some(format!(
+                    "Unlock stake operation doesn't match metadata {:?} vs {:?}",
+                    inner, metadata.internal_operation
+                ))));
+            }
+        },
+        InternalOperation::WithdrawStake(inner) => {
+            if let InternalOperation::WithdrawStake(ref metadata_op) = metadata.internal_operation {
+                if inner.owner == metadata_op.owner {
+                    if inner.operator.is_none() {
+                        inner.operator = metadata_op.operator;
+                    }
+                } else {
+                    return Err(ApiError::InvalidInput(Some(format!(
+                        "Withdraw stake operation doesn't match metadata {:?} vs {:?}",
+                        inner, metadata.internal_operation
+                    ))));
+                }
+            } else {
+                return Err(ApiError::InvalidInput(Some(format!(
+                    "Withdraw stake operation doesn't match metadata {:?} vs {:?}",
+                    inner, metadata.internal_operation
+                ))));
+            }
+        },
+        InternalOperation::Distribute(_) => {
+            if operation != metadata.internal_operation {
+                return Err(ApiError::InvalidInput(Some(format!(
+                    "Distribute operation doesn't match metadata {:?} vs {:?}",
+                    operation, metadata.internal_operation
+                ))));
+            }
+        },
     }
 
     // Encode operation
@@ -0,0 +1,125 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    common::{check_network, handle_request, with_context},
+    error::{ApiError, ApiResult},
+    types::{
+        BlockEvent, BlockEventType, BlockIdentifier, EventsBlocksRequest, EventsBlocksResponse,
+    },
+    RosettaContext,
+};
+use aptos_logger::{debug, trace};
+use aptos_types::chain_id::ChainId;
+use std::cmp::{max, min};
+use warp::Filter;
+
+/// Maximum number of block events returned at once
+pub const MAX_EVENTS_LIMIT: u64 = 1000;
+
+pub fn events_blocks_route(
+    server_context: RosettaContext,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::path!("events" / "blocks")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_context(server_context))
+        .and_then(handle_request(events_blocks))
+}
+
+/// Retrieves a range of the block event stream
+///
+/// Blocks are final in Aptos, so the stream only has a `block_added` event per block, with the
+/// block height as its sequence.  Pruned blocks are skipped.
+///
+/// [API Spec](https://www.rosetta-api.org/docs/EventsApi.html#eventsblocks)
+async fn events_blocks(
+    request: EventsBlocksRequest,
+    server_context: RosettaContext,
+) -> ApiResult<EventsBlocksResponse> {
+    debug!("/events/blocks");
+    trace!(
+        request = ?request,
+        server_context = ?server_context,
+        "/events/blocks",
+    );
+
+    check_network(request.network_identifier, &server_context)?;
+
+    let limit = request.limit.unwrap_or(MAX_EVENTS_LIMIT);
+    if limit == 0 || limit > MAX_EVENTS_LIMIT {
+        return Err(ApiError::InvalidInput(Some(format!(
+            "Limit must be between 1 and {}",
+            MAX_EVENTS_LIMIT
+        ))));
+    }
+
+    let response = server_context
+        .rest_client()?
+        .get_ledger_information()
+        .await?;
+    let state = response.state();
+
+    Ok(EventsBlocksResponse {
+        max_sequence: state.block_height,
+        events: block_added_events(
+            server_context.chain_id,
+            state.oldest_block_height,
+            state.block_height,
+            request.offset,
+            limit,
+        ),
+    })
+}
+
+/// The `block_added` events of at most `limit` blocks from `offset`, or of the last `limit`
+/// blocks if there's no offset
+fn block_added_events(
+    chain_id: ChainId,
+    oldest_block_height: u64,
+    latest_block_height: u64,
+    offset: Option<u64>,
+    limit: u64,
+) -> Vec<BlockEvent> {
+    let start = offset.unwrap_or_else(|| latest_block_height.saturating_sub(limit - 1));
+    let start = max(start, oldest_block_height);
+    let end = min(start.saturating_add(limit - 1), latest_block_height);
+    (start..=end)
+        .map(|block_height| BlockEvent {
+            sequence: block_height,
+            block_identifier: BlockIdentifier::from_height(block_height, chain_id),
+            block_event_type: BlockEventType::BlockAdded,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_block_added_events() {
+        let chain_id = ChainId::test();
+        let sequences = |offset, limit| -> Vec<u64> {
+            block_added_events(chain_id, 5, 20, offset, limit)
+                .into_iter()
+                .map(|event| event.sequence)
+                .collect()
+        };
+
+        assert_eq!(sequences(Some(10), 3), vec![10, 11, 12]);
+        // Without an offset, the latest events are returned
+        assert_eq!(sequences(None, 3), vec![18, 19, 20]);
+        // Pruned blocks are skipped
+        assert_eq!(sequences(Some(0), 3), vec![5, 6, 7]);
+        assert_eq!(sequences(Some(19), 3), vec![19, 20]);
+        assert!(sequences(Some(21), 3).is_empty());
+
+        let event = &block_added_events(chain_id, 0, 20, Some(7), 1)[0];
+        assert_eq!(
+            event.block_identifier,
+            BlockIdentifier::from_height(7, chain_id)
+        );
+        assert_eq!(event.block_event_type, BlockEventType::BlockAdded);
+    }
+}
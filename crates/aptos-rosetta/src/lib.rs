@@ -7,12 +7,13 @@
 
 use crate::{
     block::BlockRetriever,
-    common::{handle_request, with_context},
+    common::{handle_request, with_context, CoinCache},
     error::{ApiError, ApiResult},
-    types::Store,
+    types::{Currency, Store},
 };
 use aptos_config::config::ApiConfig;
 use aptos_logger::{debug, warn};
+use aptos_sdk::move_types::language_storage::TypeTag;
 use aptos_types::{account_address::AccountAddress, chain_id::ChainId};
 use aptos_warp_webserver::{logger, Error, WebServer};
 use std::{collections::BTreeMap, convert::Infallible, sync::Arc};
@@ -25,7 +26,10 @@ use warp::{
 mod account;
 mod block;
 mod construction;
+mod events;
+mod mempool;
 mod network;
+mod search;
 
 pub mod client;
 pub mod common;
@@ -44,6 +48,8 @@ pub struct RosettaContext {
     pub chain_id: ChainId,
     /// Block index cache
     pub block_cache: Option<Arc<BlockRetriever>>,
+    /// Currencies of the coins seen so far
+    pub coin_cache: Arc<CoinCache>,
     pub owner_addresses: Vec<AccountAddress>,
     pub pool_address_to_owner: BTreeMap<AccountAddress, AccountAddress>,
 }
@@ -85,6 +91,7 @@ impl RosettaContext {
             rest_client,
             chain_id,
             block_cache,
+            coin_cache: Arc::new(CoinCache::new()),
             owner_addresses,
             pool_address_to_owner,
         }
@@ -98,6 +105,18 @@ impl RosettaContext {
         }
     }
 
+    /// Retrieves the currency of a coin type, which requires being online for any coin other
+    /// than the native coin
+    async fn get_currency(&self, coin_type: &TypeTag) -> ApiResult<Option<Currency>> {
+        if let Some(currency) = self.coin_cache.get_cached_currency(coin_type) {
+            Ok(Some(currency))
+        } else {
+            self.coin_cache
+                .get_currency(self.rest_client()?.as_ref(), coin_type)
+                .await
+        }
+    }
+
     fn block_cache(&self) -> ApiResult<Arc<BlockRetriever>> {
         if let Some(ref block_cache) = self.block_cache {
             Ok(block_cache.clone())
@@ -173,6 +192,7 @@ pub fn routes(
 ) -> impl Filter<Extract = (impl Reply,), Error = Infallible> + Clone {
     account::routes(context.clone())
         .or(block::block_route(context.clone()))
+        .or(block::block_transaction_route(context.clone()))
         .or(construction::combine_route(context.clone()))
         .or(construction::derive_route(context.clone()))
         .or(construction::hash_route(context.clone()))
@@ -181,9 +201,13 @@ pub fn routes(
         .or(construction::payloads_route(context.clone()))
         .or(construction::preprocess_route(context.clone()))
         .or(construction::submit_route(context.clone()))
+        .or(events::events_blocks_route(context.clone()))
+        .or(mempool::mempool_route(context.clone()))
+        .or(mempool::mempool_transaction_route(context.clone()))
         .or(network::list_route(context.clone()))
         .or(network::options_route(context.clone()))
         .or(network::status_route(context.clone()))
+        .or(search::search_transactions_route(context.clone()))
         .or(health_check_route(context))
         .with(
             warp::cors()
@@ -0,0 +1,98 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    common::{check_network, handle_request, parse_transaction_hash, with_context},
+    error::{ApiError, ApiResult},
+    types::{
+        MempoolRequest, MempoolResponse, MempoolTransactionRequest, MempoolTransactionResponse,
+        Transaction,
+    },
+    RosettaContext,
+};
+use aptos_logger::{debug, trace};
+use aptos_rest_client::aptos_api_types::TransactionData;
+use warp::Filter;
+
+pub fn mempool_route(
+    server_context: RosettaContext,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::path!("mempool")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_context(server_context))
+        .and_then(handle_request(mempool))
+}
+
+pub fn mempool_transaction_route(
+    server_context: RosettaContext,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::path!("mempool" / "transaction")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_context(server_context))
+        .and_then(handle_request(mempool_transaction))
+}
+
+/// Lists the transactions in mempool
+///
+/// The REST API doesn't expose the contents of mempool, only lookups of a transaction by hash,
+/// so this is always empty.  Use `/mempool/transaction` to check a submitted transaction.
+///
+/// [API Spec](https://www.rosetta-api.org/docs/MempoolApi.html#mempool)
+async fn mempool(
+    request: MempoolRequest,
+    server_context: RosettaContext,
+) -> ApiResult<MempoolResponse> {
+    debug!("/mempool");
+    trace!(
+        request = ?request,
+        server_context = ?server_context,
+        "/mempool",
+    );
+
+    check_network(request.network_identifier, &server_context)?;
+    // Make sure we're online, so that an offline server doesn't look like an empty mempool
+    server_context.rest_client()?;
+
+    Ok(MempoolResponse {
+        transaction_identifiers: vec![],
+    })
+}
+
+/// Retrieves a transaction in mempool by its hash
+///
+/// The operations are estimated from the transaction payload, and the fee is the maximum fee
+/// of the transaction.  Committed transactions aren't in mempool anymore, and should be looked
+/// up with `/block/transaction` instead.
+///
+/// [API Spec](https://www.rosetta-api.org/docs/MempoolApi.html#mempooltransaction)
+async fn mempool_transaction(
+    request: MempoolTransactionRequest,
+    server_context: RosettaContext,
+) -> ApiResult<MempoolTransactionResponse> {
+    debug!("/mempool/transaction");
+    trace!(
+        request = ?request,
+        server_context = ?server_context,
+        "/mempool/transaction",
+    );
+
+    check_network(request.network_identifier, &server_context)?;
+
+    let hash = parse_transaction_hash(&request.transaction_identifier)?;
+    match server_context
+        .rest_client()?
+        .get_transaction_by_hash_bcs(hash)
+        .await?
+        .into_inner()
+    {
+        TransactionData::Pending(txn) => Ok(MempoolTransactionResponse {
+            transaction: Transaction::from_pending_transaction(&server_context.coin_cache, *txn),
+        }),
+        TransactionData::OnChain(_) => Err(ApiError::TransactionNotFound(Some(format!(
+            "Transaction {} is not in mempool, it was already committed",
+            request.transaction_identifier.hash
+        )))),
+    }
+}
@@ -0,0 +1,733 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    common::{
+        check_network, handle_request, native_coin_tag, parse_coin_type, parse_transaction_hash,
+        with_context,
+    },
+    error::{ApiError, ApiResult},
+    types::{
+        AccountIdentifier, BlockIdentifier, BlockTransaction, Currency, Operation, Operator,
+        SearchTransactionsMetadata, SearchTransactionsRequest, SearchTransactionsResponse,
+        SubAccountIdentifier, Transaction, COIN_MODULE, COIN_STORE_RESOURCE, DEPOSIT_EVENTS_FIELD,
+    },
+    RosettaContext,
+};
+use aptos_crypto::HashValue;
+use aptos_logger::{debug, trace};
+use aptos_rest_client::aptos_api_types::{BcsBlock, TransactionData, TransactionOnChainData};
+use aptos_sdk::move_types::language_storage::TypeTag;
+use aptos_types::account_address::AccountAddress;
+use std::{
+    cmp::{max, min},
+    collections::BTreeMap,
+    future::Future,
+};
+use warp::Filter;
+
+/// Maximum number of versions searched by a single request
+pub const MAX_SEARCH_VERSIONS: u64 = 10_000;
+/// Maximum number of transactions returned at once
+pub const MAX_SEARCH_LIMIT: u64 = 100;
+/// Number of transactions retrieved at once from the REST API
+const SEARCH_PAGE_SIZE: u64 = 100;
+
+pub fn search_transactions_route(
+    server_context: RosettaContext,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::path!("search" / "transactions")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_context(server_context))
+        .and_then(handle_request(search_transactions))
+}
+
+/// Searches for transactions matching all (or any) of the criteria of the request
+///
+/// Only a range of at most [`MAX_SEARCH_VERSIONS`] versions is searched, which is by default the
+/// latest versions up to `max_block`, and can be set with the request metadata.  The range is
+/// returned in the response metadata, and its `end_version` is required to get the next pages.
+/// Matching transactions are ordered from the latest.
+///
+/// Searches by account only look at the transactions the account sent, and the ones depositing
+/// the searched currency (APT by default) to it, instead of the whole range.  If versions of the
+/// range get pruned meanwhile, they return the matches of the versions still stored, with the
+/// `start_version` raised accordingly.
+///
+/// [API Spec](https://www.rosetta-api.org/docs/SearchApi.html#searchtransactions)
+async fn search_transactions(
+    request: SearchTransactionsRequest,
+    server_context: RosettaContext,
+) -> ApiResult<SearchTransactionsResponse> {
+    debug!("/search/transactions");
+    trace!(
+        request = ?request,
+        server_context = ?server_context,
+        "/search/transactions",
+    );
+
+    check_network(request.network_identifier.clone(), &server_context)?;
+
+    if request.coin_identifier.is_some() {
+        return Err(ApiError::InvalidInput(Some(
+            "Coin identifiers aren't supported, Aptos is account based".to_string(),
+        )));
+    }
+    let limit = request.limit.unwrap_or(MAX_SEARCH_LIMIT);
+    if limit == 0 || limit > MAX_SEARCH_LIMIT {
+        return Err(ApiError::InvalidInput(Some(format!(
+            "Limit must be between 1 and {}",
+            MAX_SEARCH_LIMIT
+        ))));
+    }
+    let offset = request.offset.unwrap_or_default();
+    let filter = SearchFilter::new(&request)?;
+    let (mut start_version, end_version) = get_search_range(&server_context, &request).await?;
+
+    // Transactions looked up by hash or account don't need a scan of the range
+    let candidates = match (
+        filter.operator,
+        filter.transaction_hash,
+        filter.searched_address(),
+    ) {
+        (Operator::And, Some(hash), _) => {
+            get_transaction_by_hash(&server_context, hash, start_version, end_version).await?
+        },
+        (Operator::And, None, Some(address)) => {
+            let coin_type = match &filter.currency {
+                Some(currency) => parse_coin_type(currency)?,
+                None => native_coin_tag(),
+            };
+            let (txns, searched_start_version) = scan_account_transactions(
+                &server_context,
+                address,
+                &coin_type,
+                start_version,
+                end_version,
+            )
+            .await?;
+            start_version = searched_start_version;
+            txns
+        },
+        _ => scan_transactions(&server_context, start_version, end_version).await?,
+    };
+    let mut matches = vec![];
+    for txn in candidates {
+        let version = txn.version;
+        let transaction = Transaction::from_transaction(&server_context, txn).await?;
+        if filter.matches(&transaction) {
+            matches.push((version, transaction));
+        }
+    }
+
+    // Latest first
+    matches.reverse();
+    let total_count = matches.len() as u64;
+    let page: Vec<_> = matches
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+    let page_end = offset.saturating_add(page.len() as u64);
+    let next_offset = if page_end < total_count {
+        Some(page_end)
+    } else {
+        None
+    };
+
+    // Consecutive transactions are often in the same block, so only lookup new blocks
+    let rest_client = server_context.rest_client()?;
+    let mut transactions = vec![];
+    let mut current_block: Option<BcsBlock> = None;
+    for (version, transaction) in page {
+        let block = match current_block.take() {
+            Some(block) if block.first_version <= version && version <= block.last_version => block,
+            _ => rest_client
+                .get_block_by_version_bcs(version, false)
+                .await?
+                .into_inner(),
+        };
+        transactions.push(BlockTransaction {
+            block_identifier: BlockIdentifier::from_block(&block, server_context.chain_id),
+            transaction,
+        });
+        current_block = Some(block);
+    }
+
+    Ok(SearchTransactionsResponse {
+        transactions,
+        total_count,
+        next_offset,
+        metadata: SearchTransactionsMetadata {
+            start_version: Some(start_version.into()),
+            end_version: Some(end_version.into()),
+        },
+    })
+}
+
+/// Determines the inclusive range of versions to search from the request
+///
+/// Pages after the first must be searched in the same range, since new transactions would
+/// otherwise shift the offsets, so they require the `end_version` returned with the first page.
+async fn get_search_range(
+    server_context: &RosettaContext,
+    request: &SearchTransactionsRequest,
+) -> ApiResult<(u64, u64)> {
+    let metadata = request.metadata.as_ref();
+    if request.offset.unwrap_or_default() > 0
+        && metadata.and_then(|inner| inner.end_version).is_none()
+    {
+        return Err(ApiError::InvalidInput(Some(
+            "An offset requires the end_version metadata returned with the first page".to_string(),
+        )));
+    }
+
+    let response = server_context
+        .rest_client()?
+        .get_ledger_information()
+        .await?;
+    let state = response.state();
+
+    let mut end_version = state.version;
+    if let Some(max_block) = request.max_block {
+        let block_info = server_context
+            .block_cache()?
+            .get_block_info_by_height(max_block, server_context.chain_id)
+            .await?;
+        end_version = min(end_version, block_info.last_version);
+    }
+    if let Some(version) = metadata.and_then(|inner| inner.end_version) {
+        end_version = min(end_version, version.0);
+    }
+    let start_version = metadata
+        .and_then(|inner| inner.start_version)
+        .map(|version| version.0)
+        .unwrap_or_else(|| end_version.saturating_sub(MAX_SEARCH_VERSIONS - 1));
+    let start_version = max(start_version, state.oldest_ledger_version);
+
+    if start_version > end_version {
+        return Err(ApiError::InvalidInput(Some(format!(
+            "Empty search range [{}, {}]",
+            start_version, end_version
+        ))));
+    }
+    if end_version - start_version >= MAX_SEARCH_VERSIONS {
+        return Err(ApiError::InvalidInput(Some(format!(
+            "Search range [{}, {}] is larger than {} versions",
+            start_version, end_version, MAX_SEARCH_VERSIONS
+        ))));
+    }
+    Ok((start_version, end_version))
+}
+
+/// Retrieves a committed transaction by hash, if it's in the range
+async fn get_transaction_by_hash(
+    server_context: &RosettaContext,
+    hash: HashValue,
+    start_version: u64,
+    end_version: u64,
+) -> ApiResult<Vec<TransactionOnChainData>> {
+    let response = server_context
+        .rest_client()?
+        .get_transaction_by_hash_bcs(hash)
+        .await;
+    Ok(match response.map(|inner| inner.into_inner()) {
+        Ok(TransactionData::OnChain(txn))
+            if start_version <= txn.version && txn.version <= end_version =>
+        {
+            vec![txn]
+        },
+        Ok(_) => vec![],
+        Err(err) => match ApiError::from(err) {
+            ApiError::TransactionNotFound(_) => vec![],
+            err => return Err(err),
+        },
+    })
+}
+
+/// Retrieves all the transactions of the range, in order
+async fn scan_transactions(
+    server_context: &RosettaContext,
+    start_version: u64,
+    end_version: u64,
+) -> ApiResult<Vec<TransactionOnChainData>> {
+    let rest_client = server_context.rest_client()?;
+    let mut txns = vec![];
+    let mut version = start_version;
+    while version <= end_version {
+        let page_size = min(SEARCH_PAGE_SIZE, end_version - version + 1) as u16;
+        let page = rest_client
+            .get_transactions_bcs(Some(version), Some(page_size))
+            .await?
+            .into_inner();
+        if page.is_empty() {
+            break;
+        }
+        for txn in page {
+            if txn.version > end_version {
+                break;
+            }
+            version = txn.version + 1;
+            txns.push(txn);
+        }
+    }
+    Ok(txns)
+}
+
+/// Retrieves the transactions of the range that `address` sent, or that deposited `coin_type` to
+/// it, in order, along with the start of the range actually searched
+///
+/// Both are paged from the latest, until the start of the range or the pruned versions, in which
+/// case the range is clamped to the oldest version still stored.
+async fn scan_account_transactions(
+    server_context: &RosettaContext,
+    address: AccountAddress,
+    coin_type: &TypeTag,
+    start_version: u64,
+    end_version: u64,
+) -> ApiResult<(Vec<TransactionOnChainData>, u64)> {
+    let rest_client = server_context.rest_client()?;
+    let rest_client = rest_client.as_ref();
+    let in_range = |version: u64| start_version <= version && version <= end_version;
+    let mut txns = BTreeMap::new();
+    let mut pruned = false;
+
+    let mut next_end = None;
+    while let Some((_, limit)) = previous_page(next_end) {
+        let page = get_stored_page(next_end, limit, |start, limit| async move {
+            match rest_client
+                .get_account_transactions_bcs(address, start, Some(limit))
+                .await
+            {
+                Ok(response) => Ok(response.into_inner()),
+                Err(err) => match ApiError::from(err) {
+                    ApiError::AccountNotFound(_) => Ok(vec![]),
+                    err => Err(err),
+                },
+            }
+        })
+        .await?;
+        let page = match page {
+            Some(page) => page,
+            None => {
+                pruned = true;
+                break;
+            },
+        };
+        let first = match page.first() {
+            Some(first) => first,
+            None => break,
+        };
+        let first_sequence_number = first
+            .transaction
+            .as_signed_user_txn()
+            .map_err(|err| ApiError::InternalError(Some(err.to_string())))?
+            .sequence_number();
+        let done = first.version <= start_version;
+        next_end = Some(first_sequence_number);
+        for txn in page {
+            if in_range(txn.version) {
+                txns.insert(txn.version, txn);
+            }
+        }
+        if done {
+            break;
+        }
+    }
+
+    let coin_store = format!(
+        "0x1::{}::{}<{}>",
+        COIN_MODULE, COIN_STORE_RESOURCE, coin_type
+    );
+    let coin_store = coin_store.as_str();
+    let mut deposit_versions = vec![];
+    let mut next_end = None;
+    while let Some((_, limit)) = previous_page(next_end) {
+        let page = get_stored_page(next_end, limit, |start, limit| async move {
+            match rest_client
+                .get_account_events_bcs(
+                    address,
+                    coin_store,
+                    DEPOSIT_EVENTS_FIELD,
+                    start,
+                    Some(limit),
+                )
+                .await
+            {
+                Ok(response) => Ok(response.into_inner()),
+                Err(err) => match ApiError::from(err) {
+                    ApiError::AccountNotFound(_) | ApiError::ResourceNotFound(_) => Ok(vec![]),
+                    err => Err(err),
+                },
+            }
+        })
+        .await?;
+        let page = match page {
+            Some(page) => page,
+            None => {
+                pruned = true;
+                break;
+            },
+        };
+        let first = match page.first() {
+            Some(first) => first,
+            None => break,
+        };
+        let done = first.transaction_version <= start_version;
+        next_end = Some(first.event.sequence_number());
+        deposit_versions.extend(
+            page.into_iter()
+                .map(|event| event.transaction_version)
+                .filter(|version| in_range(*version) && !txns.contains_key(version)),
+        );
+        if done {
+            break;
+        }
+    }
+
+    for version in deposit_versions {
+        if txns.contains_key(&version) {
+            continue;
+        }
+        match rest_client.get_transaction_by_version_bcs(version).await {
+            Ok(response) => {
+                if let TransactionData::OnChain(txn) = response.into_inner() {
+                    txns.insert(version, txn);
+                }
+            },
+            Err(err) => match ApiError::from(err) {
+                ApiError::VersionPruned(_) => pruned = true,
+                err => return Err(err),
+            },
+        }
+    }
+
+    // Only the versions still stored were fully searched
+    let mut start_version = start_version;
+    if pruned {
+        let response = rest_client.get_ledger_information().await?;
+        start_version = max(start_version, response.state().oldest_ledger_version);
+        txns = txns.split_off(&start_version);
+    }
+    Ok((txns.into_values().collect(), start_version))
+}
+
+/// Retrieves the page of `limit` items before `end` (or the latest page) with `get_page`,
+/// shrinking it to the items after the pruned ones if it reaches into the pruned versions.
+/// Returns `None` if the item right before `end` is pruned.
+async fn get_stored_page<T, F, Fut>(
+    end: Option<u64>,
+    mut limit: u16,
+    get_page: F,
+) -> ApiResult<Option<Vec<T>>>
+where
+    F: Fn(Option<u64>, u16) -> Fut,
+    Fut: Future<Output = ApiResult<Vec<T>>>,
+{
+    loop {
+        match get_page(end.map(|end| end - limit as u64), limit).await {
+            Ok(page) => return Ok(Some(page)),
+            Err(ApiError::VersionPruned(_)) if limit > 1 => limit /= 2,
+            Err(ApiError::VersionPruned(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Start and limit of the page of sequence numbers before `end`, or of the latest page if there's
+/// no `end` yet.  There are no pages before sequence number 0.
+fn previous_page(end: Option<u64>) -> Option<(Option<u64>, u16)> {
+    match end {
+        None => Some((None, SEARCH_PAGE_SIZE as u16)),
+        Some(0) => None,
+        Some(end) => {
+            let start = end.saturating_sub(SEARCH_PAGE_SIZE);
+            Some((Some(start), (end - start) as u16))
+        },
+    }
+}
+
+/// The criteria of a search.  Operation criteria match if any of the operations matches.
+#[derive(Debug)]
+struct SearchFilter {
+    operator: Operator,
+    transaction_hash: Option<HashValue>,
+    account: Option<(AccountAddress, Option<SubAccountIdentifier>)>,
+    address: Option<AccountAddress>,
+    currency: Option<Currency>,
+    status: Option<String>,
+    operation_type: Option<String>,
+    success: Option<bool>,
+}
+
+impl SearchFilter {
+    fn new(request: &SearchTransactionsRequest) -> ApiResult<SearchFilter> {
+        Ok(SearchFilter {
+            operator: request.operator.unwrap_or_default(),
+            transaction_hash: request
+                .transaction_identifier
+                .as_ref()
+                .map(parse_transaction_hash)
+                .transpose()?,
+            account: request
+                .account_identifier
+                .as_ref()
+                .map(|account| -> ApiResult<_> {
+                    Ok((account.account_address()?, account.sub_account.clone()))
+                })
+                .transpose()?,
+            address: request
+                .address
+                .as_ref()
+                .map(|address| {
+                    AccountIdentifier {
+                        address: address.clone(),
+                        sub_account: None,
+                    }
+                    .account_address()
+                })
+                .transpose()?,
+            currency: request.currency.clone(),
+            status: request.status.clone(),
+            operation_type: request.operation_type.clone(),
+            success: request.success,
+        })
+    }
+
+    /// Address all the matching transactions involve, if any
+    fn searched_address(&self) -> Option<AccountAddress> {
+        self.account
+            .as_ref()
+            .map(|(address, _)| *address)
+            .or(self.address)
+    }
+
+    fn matches(&self, txn: &Transaction) -> bool {
+        let any_operation = |predicate: &dyn Fn(&Operation) -> bool| {
+            txn.operations.iter().any(|operation| predicate(operation))
+        };
+        let operation_address = |operation: &Operation| {
+            operation
+                .account
+                .as_ref()
+                .and_then(|account| account.account_address().ok())
+        };
+
+        let criteria: Vec<bool> = [
+            self.transaction_hash
+                .map(|hash| parse_transaction_hash(&txn.transaction_identifier).ok() == Some(hash)),
+            self.account.as_ref().map(|(address, sub_account)| {
+                any_operation(&|operation| {
+                    operation_address(operation) == Some(*address)
+                        && operation
+                            .account
+                            .as_ref()
+                            .map_or(false, |account| &account.sub_account == sub_account)
+                })
+            }),
+            self.address.map(|address| {
+                any_operation(&|operation| operation_address(operation) == Some(address))
+            }),
+            self.currency.as_ref().map(|currency| {
+                any_operation(&|operation| {
+                    operation
+                        .amount
+                        .as_ref()
+                        .map_or(false, |amount| &amount.currency == currency)
+                })
+            }),
+            self.status.as_ref().map(|status| {
+                any_operation(&|operation| operation.status.as_ref() == Some(status))
+            }),
+            self.operation_type.as_ref().map(|operation_type| {
+                any_operation(&|operation| &operation.operation_type == operation_type)
+            }),
+            self.success.map(|success| success != txn.metadata.failed),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if criteria.is_empty() {
+            return true;
+        }
+        match self.operator {
+            Operator::And => criteria.into_iter().all(|matches| matches),
+            Operator::Or => criteria.into_iter().any(|matches| matches),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        common::native_coin,
+        types::{OperationStatusType, OperationType, TransactionMetadata, TransactionType},
+    };
+    use aptos_types::chain_id::ChainId;
+
+    fn transfer(sender: AccountAddress, receiver: AccountAddress, failed: bool) -> Transaction {
+        let status = if failed {
+            OperationStatusType::Failure
+        } else {
+            OperationStatusType::Success
+        };
+        Transaction {
+            transaction_identifier: HashValue::random().into(),
+            operations: vec![
+                Operation::withdraw(
+                    0,
+                    Some(status),
+                    AccountIdentifier::base_account(sender),
+                    native_coin(),
+                    100,
+                ),
+                Operation::deposit(
+                    1,
+                    Some(status),
+                    AccountIdentifier::base_account(receiver),
+                    native_coin(),
+                    100,
+                ),
+                Operation::gas_fee(2, sender, 10, 100),
+            ],
+            metadata: TransactionMetadata {
+                transaction_type: TransactionType::User,
+                version: Some(1.into()),
+                failed,
+                vm_status: "".to_string(),
+            },
+        }
+    }
+
+    fn request() -> SearchTransactionsRequest {
+        SearchTransactionsRequest {
+            network_identifier: ChainId::test().into(),
+            operator: None,
+            max_block: None,
+            offset: None,
+            limit: None,
+            transaction_identifier: None,
+            account_identifier: None,
+            coin_identifier: None,
+            currency: None,
+            status: None,
+            operation_type: None,
+            address: None,
+            success: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_previous_page() {
+        assert_eq!(previous_page(None), Some((None, SEARCH_PAGE_SIZE as u16)));
+        assert_eq!(previous_page(Some(250)), Some((Some(150), 100)));
+        assert_eq!(previous_page(Some(30)), Some((Some(0), 30)));
+        assert_eq!(previous_page(Some(0)), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_stored_page() {
+        // Sequence numbers before 180 are pruned
+        let get_page = |start: Option<u64>, limit: u16| async move {
+            let start = start.unwrap_or(250 - limit as u64);
+            if start < 180 {
+                Err(ApiError::VersionPruned(None))
+            } else {
+                Ok((start..start + limit as u64).collect::<Vec<_>>())
+            }
+        };
+        assert_eq!(
+            get_stored_page(None, 100, get_page).await.unwrap(),
+            Some((200..250).collect())
+        );
+        assert_eq!(
+            get_stored_page(Some(200), 100, get_page).await.unwrap(),
+            Some((188..200).collect())
+        );
+        assert_eq!(
+            get_stored_page(Some(188), 100, get_page).await.unwrap(),
+            Some((182..188).collect())
+        );
+        assert_eq!(
+            get_stored_page(Some(180), 100, get_page).await.unwrap(),
+            None
+        );
+
+        let failing_page = |_, _| async { Err::<Vec<u64>, _>(ApiError::NodeIsOffline) };
+        assert!(get_stored_page(None, 100, failing_page).await.is_err());
+    }
+
+    #[test]
+    fn test_search_filter() {
+        let sender = AccountAddress::random();
+        let receiver = AccountAddress::random();
+        let other = AccountAddress::random();
+        let txn = transfer(sender, receiver, false);
+        let failed_txn = transfer(sender, receiver, true);
+        let matches = |request: SearchTransactionsRequest, txn: &Transaction| {
+            SearchFilter::new(&request).unwrap().matches(txn)
+        };
+
+        // No criteria matches everything
+        assert!(matches(request(), &txn));
+
+        let by_hash = SearchTransactionsRequest {
+            transaction_identifier: Some(txn.transaction_identifier.clone()),
+            ..request()
+        };
+        assert!(matches(by_hash.clone(), &txn));
+        assert!(!matches(by_hash, &failed_txn));
+
+        let by_receiver = SearchTransactionsRequest {
+            account_identifier: Some(AccountIdentifier::base_account(receiver)),
+            ..request()
+        };
+        assert!(matches(by_receiver, &txn));
+        let by_other = SearchTransactionsRequest {
+            address: Some(other.to_hex_literal()),
+            ..request()
+        };
+        assert!(!matches(by_other.clone(), &txn));
+        // Staking sub accounts don't match base accounts
+        let by_stake = SearchTransactionsRequest {
+            account_identifier: Some(AccountIdentifier::total_stake_account(receiver)),
+            ..request()
+        };
+        assert!(!matches(by_stake, &txn));
+
+        let deposits = SearchTransactionsRequest {
+            operation_type: Some(OperationType::Deposit.to_string()),
+            currency: Some(native_coin()),
+            success: Some(true),
+            ..request()
+        };
+        assert!(matches(deposits.clone(), &txn));
+        assert!(!matches(deposits.clone(), &failed_txn));
+        assert!(matches(
+            SearchTransactionsRequest {
+                operator: Some(Operator::Or),
+                ..deposits
+            },
+            &failed_txn
+        ));
+        assert!(!matches(
+            SearchTransactionsRequest {
+                operator: Some(Operator::Or),
+                success: Some(true),
+                ..by_other
+            },
+            &failed_txn
+        ));
+
+        let failures = SearchTransactionsRequest {
+            status: Some(OperationStatusType::Failure.to_string()),
+            ..request()
+        };
+        assert!(!matches(failures.clone(), &txn));
+        assert!(matches(failures, &failed_txn));
+    }
+}
@@ -160,13 +160,26 @@ impl BlockIdentifier {
         block: &aptos_rest_client::aptos_api_types::BcsBlock,
         chain_id: ChainId,
     ) -> BlockIdentifier {
+        BlockIdentifier::from_height(block.block_height, chain_id)
+    }
+
+    /// Block hashes only depend on the height, so this doesn't need to lookup the block
+    pub fn from_height(block_height: u64, chain_id: ChainId) -> BlockIdentifier {
         BlockIdentifier {
-            index: block.block_height,
-            hash: BlockHash::new(chain_id, block.block_height).to_string(),
+            index: block_height,
+            hash: BlockHash::new(chain_id, block_height).to_string(),
         }
     }
 }
 
+/// Identifier for a UTXO coin.  Aptos is account based, so these are never used
+///
+/// [API Spec](https://www.rosetta-api.org/docs/models/CoinIdentifier.html)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CoinIdentifier {
+    pub identifier: String,
+}
+
 /// Identifier for this specific network deployment
 ///
 /// [API Spec](https://www.rosetta-api.org/docs/models/NetworkIdentifier.html)
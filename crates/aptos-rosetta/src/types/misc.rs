@@ -93,20 +93,28 @@ pub enum OperationType {
     SetVoter,
     InitializeStakePool,
     ResetLockup,
+    AddStake,
+    UnlockStake,
+    WithdrawStake,
+    Distribute,
     // Fee must always be last for ordering
     Fee,
 }
 
 impl OperationType {
+    const ADD_STAKE: &'static str = "add_stake";
     const CREATE_ACCOUNT: &'static str = "create_account";
     const DEPOSIT: &'static str = "deposit";
+    const DISTRIBUTE: &'static str = "distribute";
     const FEE: &'static str = "fee";
     const INITIALIZE_STAKE_POOL: &'static str = "initialize_stake_pool";
     const RESET_LOCKUP: &'static str = "reset_lockup";
     const SET_OPERATOR: &'static str = "set_operator";
     const SET_VOTER: &'static str = "set_voter";
     const STAKING_REWARD: &'static str = "staking_reward";
+    const UNLOCK_STAKE: &'static str = "unlock_stake";
     const WITHDRAW: &'static str = "withdraw";
+    const WITHDRAW_STAKE: &'static str = "withdraw_stake";
 
     pub fn all() -> Vec<OperationType> {
         use OperationType::*;
@@ -120,6 +128,10 @@ impl OperationType {
             StakingReward,
             InitializeStakePool,
             ResetLockup,
+            AddStake,
+            UnlockStake,
+            WithdrawStake,
+            Distribute,
         ]
     }
 }
@@ -138,6 +150,10 @@ impl FromStr for OperationType {
             Self::SET_VOTER => Ok(OperationType::SetVoter),
             Self::INITIALIZE_STAKE_POOL => Ok(OperationType::InitializeStakePool),
             Self::RESET_LOCKUP => Ok(OperationType::ResetLockup),
+            Self::ADD_STAKE => Ok(OperationType::AddStake),
+            Self::UNLOCK_STAKE => Ok(OperationType::UnlockStake),
+            Self::WITHDRAW_STAKE => Ok(OperationType::WithdrawStake),
+            Self::DISTRIBUTE => Ok(OperationType::Distribute),
             _ => Err(ApiError::DeserializationFailed(Some(format!(
                 "Invalid OperationType: {}",
                 s
@@ -158,6 +174,10 @@ impl Display for OperationType {
             SetVoter => Self::SET_VOTER,
             InitializeStakePool => Self::INITIALIZE_STAKE_POOL,
             ResetLockup => Self::RESET_LOCKUP,
+            AddStake => Self::ADD_STAKE,
+            UnlockStake => Self::UNLOCK_STAKE,
+            WithdrawStake => Self::WITHDRAW_STAKE,
+            Distribute => Self::DISTRIBUTE,
             Fee => Self::FEE,
         })
     }
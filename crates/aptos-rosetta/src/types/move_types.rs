@@ -28,11 +28,15 @@ pub const VESTING_RESOURCE: &str = "Vesting";
 
 pub const CREATE_ACCOUNT_FUNCTION: &str = "create_account";
 pub const TRANSFER_FUNCTION: &str = "transfer";
+pub const TRANSFER_COINS_FUNCTION: &str = "transfer_coins";
 pub const RESET_LOCKUP_FUNCTION: &str = "reset_lockup";
 pub const CREATE_STAKING_CONTRACT_FUNCTION: &str = "create_staking_contract";
 pub const SWITCH_OPERATOR_WITH_SAME_COMMISSION_FUNCTION: &str =
     "switch_operator_with_same_commission";
 pub const UPDATE_VOTER_FUNCTION: &str = "update_voter";
+pub const ADD_STAKE_FUNCTION: &str = "add_stake";
+pub const UNLOCK_STAKE_FUNCTION: &str = "unlock_stake";
+pub const DISTRIBUTE_FUNCTION: &str = "distribute";
 
 pub const DECIMALS_FIELD: &str = "decimal";
 pub const DEPOSIT_EVENTS_FIELD: &str = "deposit_events";
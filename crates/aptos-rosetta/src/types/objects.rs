@@ -6,7 +6,7 @@
 //! [Spec](https://www.rosetta-api.org/docs/api_objects.html)
 
 use crate::{
-    common::{is_native_coin, native_coin, native_coin_tag},
+    common::{native_coin, parse_coin_type, CoinCache},
     construction::{
         parse_create_stake_pool_operation, parse_reset_lockup_operation,
         parse_set_operator_operation, parse_set_voter_operation,
@@ -30,7 +30,7 @@ use aptos_types::{
     event::EventKey,
     stake_pool::{SetOperatorEvent, StakePool},
     state_store::state_key::StateKey,
-    transaction::{EntryFunction, TransactionPayload},
+    transaction::{EntryFunction, SignedTransaction, TransactionPayload},
     write_set::{WriteOp, WriteSet},
 };
 use itertools::Itertools;
@@ -111,6 +111,28 @@ pub struct Block {
     pub transactions: Vec<Transaction>,
 }
 
+/// An event of the block event stream.  Since Aptos blocks are final, there are only
+/// `block_added` events, and the sequence of an event is the height of its block.
+///
+/// [API Spec](https://www.rosetta-api.org/docs/models/BlockEvent.html)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockEvent {
+    /// Position of the event in the stream
+    pub sequence: u64,
+    /// Block added or removed
+    pub block_identifier: BlockIdentifier,
+    #[serde(rename = "type")]
+    pub block_event_type: BlockEventType,
+}
+
+/// [API Spec](https://www.rosetta-api.org/docs/models/BlockEventType.html)
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockEventType {
+    BlockAdded,
+    BlockRemoved,
+}
+
 /// A combination of a transaction and the block associated.  In Aptos, this is just the same
 /// as the version associated with the transaction
 ///
@@ -118,9 +140,9 @@ pub struct Block {
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct BlockTransaction {
     /// Block associated with transaction
-    block_identifier: BlockIdentifier,
+    pub block_identifier: BlockIdentifier,
     /// Transaction associated with block
-    transaction: Transaction,
+    pub transaction: Transaction,
 }
 
 /// Currency represented as atomic units including decimals
@@ -370,6 +392,73 @@ impl Operation {
             Some(OperationMetadata::reset_lockup(operator)),
         )
     }
+
+    pub fn add_stake(
+        operation_index: u64,
+        status: Option<OperationStatusType>,
+        owner: AccountAddress,
+        operator: Option<AccountIdentifier>,
+        amount: u64,
+    ) -> Operation {
+        Operation::new(
+            OperationType::AddStake,
+            operation_index,
+            status,
+            AccountIdentifier::base_account(owner),
+            None,
+            Some(OperationMetadata::stake_amount(operator, amount)),
+        )
+    }
+
+    pub fn unlock_stake(
+        operation_index: u64,
+        status: Option<OperationStatusType>,
+        owner: AccountAddress,
+        operator: Option<AccountIdentifier>,
+        amount: u64,
+    ) -> Operation {
+        Operation::new(
+            OperationType::UnlockStake,
+            operation_index,
+            status,
+            AccountIdentifier::base_account(owner),
+            None,
+            Some(OperationMetadata::stake_amount(operator, amount)),
+        )
+    }
+
+    pub fn withdraw_stake(
+        operation_index: u64,
+        status: Option<OperationStatusType>,
+        owner: AccountAddress,
+        operator: Option<AccountIdentifier>,
+    ) -> Operation {
+        Operation::new(
+            OperationType::WithdrawStake,
+            operation_index,
+            status,
+            AccountIdentifier::base_account(owner),
+            None,
+            Some(OperationMetadata::withdraw_stake(operator)),
+        )
+    }
+
+    pub fn distribute(
+        operation_index: u64,
+        status: Option<OperationStatusType>,
+        staker: AccountAddress,
+        operator: AccountIdentifier,
+        sender: AccountAddress,
+    ) -> Operation {
+        Operation::new(
+            OperationType::Distribute,
+            operation_index,
+            status,
+            AccountIdentifier::base_account(staker),
+            None,
+            Some(OperationMetadata::distribute(operator, sender)),
+        )
+    }
 }
 
 impl std::cmp::PartialOrd for Operation {
@@ -418,6 +507,8 @@ pub struct OperationMetadata {
     pub staked_balance: Option<U64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub commission_percentage: Option<U64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<U64>,
 }
 
 impl OperationMetadata {
@@ -470,6 +561,45 @@ impl OperationMetadata {
             ..Default::default()
         }
     }
+
+    pub fn stake_amount(operator: Option<AccountIdentifier>, amount: u64) -> Self {
+        OperationMetadata {
+            operator,
+            amount: Some(amount.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn withdraw_stake(operator: Option<AccountIdentifier>) -> Self {
+        OperationMetadata {
+            operator,
+            ..Default::default()
+        }
+    }
+
+    pub fn distribute(operator: AccountIdentifier, sender: AccountAddress) -> Self {
+        OperationMetadata {
+            operator: Some(operator),
+            sender: Some(AccountIdentifier::base_account(sender)),
+            ..Default::default()
+        }
+    }
+}
+
+/// How to combine the criteria of a search
+///
+/// [API Spec](https://www.rosetta-api.org/docs/models/Operator.html)
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operator {
+    Or,
+    And,
+}
+
+impl Default for Operator {
+    fn default() -> Self {
+        Operator::And
+    }
 }
 
 /// Public key used for the rosetta implementation.  All private keys will never be handled
@@ -559,10 +689,15 @@ pub struct Transaction {
     pub metadata: TransactionMetadata,
 }
 
+/// VM status of transactions in mempool
+pub const PENDING_VM_STATUS: &str = "Pending";
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct TransactionMetadata {
     pub transaction_type: TransactionType,
-    pub version: U64,
+    /// Not set for transactions in mempool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<U64>,
     pub failed: bool,
     pub vm_status: String,
 }
@@ -629,6 +764,7 @@ impl Transaction {
             // Parse all failed operations from the payload
             if let Some(user_txn) = maybe_user_txn {
                 let mut ops = parse_failed_operations_from_txn_payload(
+                    &server_context.coin_cache,
                     operation_index,
                     user_txn.sender(),
                     user_txn.payload(),
@@ -660,12 +796,41 @@ impl Transaction {
             operations,
             metadata: TransactionMetadata {
                 transaction_type: txn_type,
-                version: txn.version.into(),
+                version: Some(txn.version.into()),
                 failed: !successful,
                 vm_status: format!("{:?}", txn_info.status()),
             },
         })
     }
+
+    /// Estimates the operations of a transaction in mempool from its payload, like for failed
+    /// transactions, but without a status since it hasn't been executed yet
+    pub fn from_pending_transaction(coin_cache: &CoinCache, txn: SignedTransaction) -> Transaction {
+        let mut operations =
+            parse_failed_operations_from_txn_payload(coin_cache, 0, txn.sender(), txn.payload());
+
+        // The gas fee is at most the max gas amount
+        operations.push(Operation::gas_fee(
+            operations.len() as u64,
+            txn.sender(),
+            txn.max_gas_amount(),
+            txn.gas_unit_price(),
+        ));
+        for operation in operations.iter_mut() {
+            operation.status = None;
+        }
+
+        Transaction {
+            transaction_identifier: txn.committed_hash().into(),
+            operations,
+            metadata: TransactionMetadata {
+                transaction_type: TransactionType::User,
+                version: None,
+                failed: false,
+                vm_status: PENDING_VM_STATUS.to_string(),
+            },
+        }
+    }
 }
 
 /// Parses operations from the transaction payload
@@ -673,6 +838,7 @@ impl Transaction {
 /// This case only occurs if the transaction failed, and that's because it's less accurate
 /// than just following the state changes
 fn parse_failed_operations_from_txn_payload(
+    coin_cache: &CoinCache,
     operation_index: u64,
     sender: AccountAddress,
     payload: &TransactionPayload,
@@ -684,16 +850,16 @@ fn parse_failed_operations_from_txn_payload(
             inner.module().name().as_str(),
             inner.function().as_str(),
         ) {
-            (AccountAddress::ONE, COIN_MODULE, TRANSFER_FUNCTION) => {
+            (AccountAddress::ONE, COIN_MODULE, TRANSFER_FUNCTION)
+            | (AccountAddress::ONE, APTOS_ACCOUNT_MODULE, TRANSFER_COINS_FUNCTION) => {
                 // Only put the transfer in if we can understand the currency
                 if let Some(type_tag) = inner.ty_args().first() {
                     // We don't want to do lookups on failures for currencies that don't exist,
                     // so we only look up cached info not new info
-                    // TODO: If other coins are supported, this will need to be updated to handle more coins
-                    if type_tag == &native_coin_tag() {
+                    if let Some(currency) = coin_cache.get_cached_currency(type_tag) {
                         operations = parse_transfer_from_txn_payload(
                             inner,
-                            native_coin(),
+                            currency,
                             sender,
                             operation_index,
                         )
@@ -877,10 +1043,9 @@ async fn parse_operations_from_write_set(
         },
         (AccountAddress::ONE, COIN_MODULE, COIN_STORE_RESOURCE, 1) => {
             if let Some(type_tag) = struct_tag.type_params.first() {
-                // TODO: This will need to be updated to support more coins
-                if type_tag == &native_coin_tag() {
+                if let Some(currency) = server_context.get_currency(type_tag).await? {
                     parse_coinstore_changes(
-                        native_coin(),
+                        currency,
                         version,
                         address,
                         data,
@@ -889,6 +1054,10 @@ async fn parse_operations_from_write_set(
                     )
                     .await
                 } else {
+                    warn!(
+                        "Skipping coinstore {} of unknown coin at version {}",
+                        struct_tag, version
+                    );
                     Ok(vec![])
                 }
             } else {
@@ -1366,6 +1535,10 @@ pub enum InternalOperation {
     SetVoter(SetVoter),
     InitializeStakePool(InitializeStakePool),
     ResetLockup(ResetLockup),
+    AddStake(AddStake),
+    UnlockStake(UnlockStake),
+    WithdrawStake(WithdrawStake),
+    Distribute(Distribute),
 }
 
 impl InternalOperation {
@@ -1488,6 +1661,93 @@ impl InternalOperation {
                                 }));
                             }
                         },
+                        Ok(OperationType::AddStake) => {
+                            if let (
+                                Some(OperationMetadata {
+                                    operator,
+                                    amount: Some(amount),
+                                    ..
+                                }),
+                                Some(account),
+                            ) = (&operation.metadata, &operation.account)
+                            {
+                                let operator = if let Some(operator) = operator {
+                                    Some(operator.account_address()?)
+                                } else {
+                                    None
+                                };
+                                return Ok(Self::AddStake(AddStake {
+                                    owner: account.account_address()?,
+                                    operator,
+                                    amount: amount.0,
+                                }));
+                            }
+                        },
+                        Ok(OperationType::UnlockStake) => {
+                            if let (
+                                Some(OperationMetadata {
+                                    operator,
+                                    amount: Some(amount),
+                                    ..
+                                }),
+                                Some(account),
+                            ) = (&operation.metadata, &operation.account)
+                            {
+                                let operator = if let Some(operator) = operator {
+                                    Some(operator.account_address()?)
+                                } else {
+                                    None
+                                };
+                                return Ok(Self::UnlockStake(UnlockStake {
+                                    owner: account.account_address()?,
+                                    operator,
+                                    amount: amount.0,
+                                }));
+                            }
+                        },
+                        Ok(OperationType::WithdrawStake) => {
+                            if let Some(account) = &operation.account {
+                                let operator = if let Some(OperationMetadata {
+                                    operator: Some(operator),
+                                    ..
+                                }) = &operation.metadata
+                                {
+                                    Some(operator.account_address()?)
+                                } else {
+                                    None
+                                };
+                                return Ok(Self::WithdrawStake(WithdrawStake {
+                                    owner: account.account_address()?,
+                                    operator,
+                                }));
+                            }
+                        },
+                        Ok(OperationType::Distribute) => {
+                            if let (
+                                Some(OperationMetadata {
+                                    operator: Some(operator),
+                                    sender: Some(sender),
+                                    ..
+                                }),
+                                Some(account),
+                            ) = (&operation.metadata, &operation.account)
+                            {
+                                let staker = account.account_address()?;
+                                let sender = sender.account_address()?;
+                                // Distributing one's own staking contract is a withdraw stake
+                                if staker == sender {
+                                    return Err(ApiError::InvalidInput(Some(
+                                        "Distribute by the staker must be a withdraw stake"
+                                            .to_string(),
+                                    )));
+                                }
+                                return Ok(Self::Distribute(Distribute {
+                                    sender,
+                                    staker,
+                                    operator: operator.account_address()?,
+                                }));
+                            }
+                        },
                         _ => {},
                     }
                 }
@@ -1515,6 +1775,10 @@ impl InternalOperation {
             Self::SetVoter(inner) => inner.owner,
             Self::InitializeStakePool(inner) => inner.owner,
             Self::ResetLockup(inner) => inner.owner,
+            Self::AddStake(inner) => inner.owner,
+            Self::UnlockStake(inner) => inner.owner,
+            Self::WithdrawStake(inner) => inner.owner,
+            Self::Distribute(inner) => inner.sender,
         }
     }
 
@@ -1527,11 +1791,22 @@ impl InternalOperation {
                 create_account.sender,
             ),
             InternalOperation::Transfer(transfer) => {
-                is_native_coin(&transfer.currency)?;
-                (
-                    aptos_stdlib::aptos_account_transfer(transfer.receiver, transfer.amount.0),
-                    transfer.sender,
-                )
+                let coin_type = parse_coin_type(&transfer.currency)?;
+                if transfer.currency == native_coin() {
+                    (
+                        aptos_stdlib::aptos_account_transfer(transfer.receiver, transfer.amount.0),
+                        transfer.sender,
+                    )
+                } else {
+                    (
+                        aptos_stdlib::aptos_account_transfer_coins(
+                            coin_type,
+                            transfer.receiver,
+                            transfer.amount.0,
+                        ),
+                        transfer.sender,
+                    )
+                }
             },
             InternalOperation::SetOperator(set_operator) => {
                 if set_operator.old_operator.is_none() {
@@ -1575,6 +1850,53 @@ impl InternalOperation {
                 aptos_stdlib::staking_contract_reset_lockup(reset_lockup.operator),
                 reset_lockup.owner,
             ),
+            InternalOperation::AddStake(add_stake) => {
+                if add_stake.operator.is_none() {
+                    return Err(ApiError::InvalidInput(Some(
+                        "Add stake doesn't have an operator".to_string(),
+                    )));
+                }
+                (
+                    aptos_stdlib::staking_contract_add_stake(
+                        add_stake.operator.unwrap(),
+                        add_stake.amount,
+                    ),
+                    add_stake.owner,
+                )
+            },
+            InternalOperation::UnlockStake(unlock_stake) => {
+                if unlock_stake.operator.is_none() {
+                    return Err(ApiError::InvalidInput(Some(
+                        "Unlock stake doesn't have an operator".to_string(),
+                    )));
+                }
+                (
+                    aptos_stdlib::staking_contract_unlock_stake(
+                        unlock_stake.operator.unwrap(),
+                        unlock_stake.amount,
+                    ),
+                    unlock_stake.owner,
+                )
+            },
+            InternalOperation::WithdrawStake(withdraw_stake) => {
+                if withdraw_stake.operator.is_none() {
+                    return Err(ApiError::InvalidInput(Some(
+                        "Withdraw stake doesn't have an operator".to_string(),
+                    )));
+                }
+                // Distributing pays out the unlocked stake once the lockup has expired
+                (
+                    aptos_stdlib::staking_contract_distribute(
+                        withdraw_stake.owner,
+                        withdraw_stake.operator.unwrap(),
+                    ),
+                    withdraw_stake.owner,
+                )
+            },
+            InternalOperation::Distribute(distribute) => (
+                aptos_stdlib::staking_contract_distribute(distribute.staker, distribute.operator),
+                distribute.sender,
+            ),
         })
     }
 }
@@ -1668,9 +1990,8 @@ impl Transfer {
             )));
         }
 
-        // Check that the currency is supported
-        // TODO: in future use currency, since there's more than just 1
-        is_native_coin(&withdraw_amount.currency)?;
+        // Check that the currency is a coin, whether it exists is checked when online
+        parse_coin_type(&withdraw_amount.currency)?;
 
         let withdraw_value = i128::from_str(&withdraw_amount.value)
             .map_err(|_| ApiError::InvalidTransferOperations(Some("Withdraw amount is invalid")))?;
@@ -1733,3 +2054,34 @@ pub struct ResetLockup {
     pub owner: AccountAddress,
     pub operator: AccountAddress,
 }
+
+/// Adds stake to a staking contract
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AddStake {
+    pub owner: AccountAddress,
+    pub operator: Option<AccountAddress>,
+    pub amount: u64,
+}
+
+/// Unlocks stake of a staking contract, which can be withdrawn after the lockup expires
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct UnlockStake {
+    pub owner: AccountAddress,
+    pub operator: Option<AccountAddress>,
+    pub amount: u64,
+}
+
+/// Withdraws the unlocked stake of the owner's staking contract, through a distribute
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct WithdrawStake {
+    pub owner: AccountAddress,
+    pub operator: Option<AccountAddress>,
+}
+
+/// Distributes the unlocked stake of someone else's staking contract
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Distribute {
+    pub sender: AccountAddress,
+    pub staker: AccountAddress,
+    pub operator: AccountAddress,
+}
@@ -3,9 +3,10 @@
 
 use crate::{
     types::{
-        AccountIdentifier, Allow, Amount, Block, BlockIdentifier, Currency, InternalOperation,
-        NetworkIdentifier, Operation, PartialBlockIdentifier, Peer, PublicKey, Signature,
-        SigningPayload, SyncStatus, Transaction, TransactionIdentifier, Version,
+        AccountIdentifier, Allow, Amount, Block, BlockEvent, BlockIdentifier, BlockTransaction,
+        CoinIdentifier, Currency, InternalOperation, NetworkIdentifier, Operation, Operator,
+        PartialBlockIdentifier, Peer, PublicKey, Signature, SigningPayload, SyncStatus,
+        Transaction, TransactionIdentifier, Version,
     },
     AccountAddress, ApiError,
 };
@@ -117,6 +118,28 @@ pub struct BlockResponse {
     pub block: Block,
 }
 
+/// Request for a transaction in a block, for blocks that didn't return all their transactions
+///
+/// [API Spec](https://www.rosetta-api.org/docs/models/BlockTransactionRequest.html)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockTransactionRequest {
+    /// Network identifier describing the blockchain and the chain id
+    pub network_identifier: NetworkIdentifier,
+    /// Block containing the transaction
+    pub block_identifier: BlockIdentifier,
+    /// Hash of the transaction
+    pub transaction_identifier: TransactionIdentifier,
+}
+
+/// Response with the transaction requested
+///
+/// [API Spec](https://www.rosetta-api.org/docs/models/BlockTransactionResponse.html)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockTransactionResponse {
+    /// The transaction requested
+    pub transaction: Transaction,
+}
+
 /// Request to combine signatures and an unsigned transaction for submission as a
 /// [`aptos_types::transaction::SignedTransaction`]
 ///
@@ -471,6 +494,32 @@ pub struct ConstructionSubmitResponse {
     pub transaction_identifier: TransactionIdentifier,
 }
 
+/// Request for a range of the block events
+///
+/// [API Spec](https://www.rosetta-api.org/docs/models/EventsBlocksRequest.html)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EventsBlocksRequest {
+    /// Network identifier describing the blockchain and the chain id
+    pub network_identifier: NetworkIdentifier,
+    /// Sequence of the first event to return.  If not set, the last `limit` events are returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// Maximum number of events to return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+/// Response with the block events requested
+///
+/// [API Spec](https://www.rosetta-api.org/docs/models/EventsBlocksResponse.html)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EventsBlocksResponse {
+    /// Sequence of the latest event
+    pub max_sequence: u64,
+    /// Block events in order of sequence
+    pub events: Vec<BlockEvent>,
+}
+
 /// Request for all transactions in mempool
 ///
 /// [API Spec](https://www.rosetta-api.org/docs/models/MempoolRequest.html)
@@ -563,6 +612,88 @@ pub struct NetworkStatusResponse {
     pub peers: Vec<Peer>,
 }
 
+/// Request to search for transactions matching the given criteria
+///
+/// Only transactions in a bounded range of versions are searched, see
+/// [`SearchTransactionsMetadata`]
+///
+/// [API Spec](https://www.rosetta-api.org/docs/models/SearchTransactionsRequest.html)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SearchTransactionsRequest {
+    /// Network identifier describing the blockchain and the chain id
+    pub network_identifier: NetworkIdentifier,
+    /// Whether transactions must match all (the default) or any of the criteria
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator: Option<Operator>,
+    /// Latest block to search in, defaults to the latest block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_block: Option<u64>,
+    /// Offset into the matching transactions, which are ordered from the latest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// Maximum number of transactions to return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Hash of the transaction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_identifier: Option<TransactionIdentifier>,
+    /// Account of an operation, including its sub account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_identifier: Option<AccountIdentifier>,
+    /// Not supported, as Aptos is account based
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coin_identifier: Option<CoinIdentifier>,
+    /// Currency of an operation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+    /// Status of an operation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Type of an operation
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub operation_type: Option<String>,
+    /// Address of the account of an operation, regardless of its sub account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Whether the transaction succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<SearchTransactionsMetadata>,
+}
+
+/// Range of versions to search in, which must not be larger than 10,000 versions.  Defaults to
+/// the latest versions up to `max_block`.
+///
+/// The response returns the range that was searched, which starts later than requested if
+/// versions of the range are pruned.  Requests with an offset must pass its `end_version`, so
+/// that the pages of a search are taken from the same transactions.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SearchTransactionsMetadata {
+    /// First version to search, inclusive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_version: Option<U64>,
+    /// Last version to search, inclusive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_version: Option<U64>,
+}
+
+/// Response with the transactions matching a search
+///
+/// [API Spec](https://www.rosetta-api.org/docs/models/SearchTransactionsResponse.html)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SearchTransactionsResponse {
+    /// Matching transactions, from the latest
+    pub transactions: Vec<BlockTransaction>,
+    /// Number of matching transactions in the searched range
+    pub total_count: u64,
+    /// Offset of the next page of transactions, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<u64>,
+    /// Range of versions searched, to pass along with `next_offset`
+    pub metadata: SearchTransactionsMetadata,
+}
+
 /// Response with a transaction that was hashed or submitted
 ///
 /// [API Spec](https://www.rosetta-api.org/docs/models/TransactionIdentifierResponse.html)
@@ -3,3 +3,4 @@
 
 pub mod analyze_validators;
 pub mod fetch_metadata;
+pub mod simulate_leader_reputation;
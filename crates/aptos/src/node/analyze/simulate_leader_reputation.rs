@@ -0,0 +1,170 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{analyze_validators::AnalyzeValidators, fetch_metadata::EpochInfo};
+use anyhow::{anyhow, Result};
+use aptos_consensus::leader_reputation_simulator::{
+    SimulatedEpoch, SimulationConfig, SimulationResult,
+};
+use aptos_storage_interface::DbReader;
+use aptos_types::{
+    account_address::AccountAddress,
+    on_chain_config::{OnChainConsensusConfig, ProposerElectionType},
+};
+use std::collections::HashMap;
+
+/// Window used for chain health by default, matching the default node config
+const DEFAULT_WINDOW_FOR_CHAIN_HEALTH: usize = 100;
+
+pub struct SimulateLeaderReputationHelper {}
+
+impl SimulateLeaderReputationHelper {
+    /// Configuration matching the default on-chain consensus config
+    pub fn default_config() -> Result<SimulationConfig> {
+        let consensus_config = OnChainConsensusConfig::default();
+        match consensus_config.proposer_election_type() {
+            ProposerElectionType::LeaderReputation(leader_reputation_type) => {
+                Ok(SimulationConfig {
+                    leader_reputation_type: leader_reputation_type.clone(),
+                    exclude_round: consensus_config.leader_reputation_exclude_round(),
+                    window_for_chain_health: DEFAULT_WINDOW_FOR_CHAIN_HEALTH,
+                })
+            },
+            other => Err(anyhow!(
+                "Default proposer election type {:?} is not leader reputation",
+                other
+            )),
+        }
+    }
+
+    /// Convert epochs fetched through the REST API into simulator input.
+    pub fn from_epoch_infos(epochs: Vec<EpochInfo>) -> Vec<SimulatedEpoch> {
+        epochs
+            .into_iter()
+            .map(|epoch_info| {
+                let mut validators = epoch_info.validators;
+                validators.sort_by_key(|v| v.validator_index);
+                SimulatedEpoch {
+                    epoch: epoch_info.epoch,
+                    validators: validators.iter().map(|v| v.address).collect(),
+                    voting_powers: validators.iter().map(|v| v.voting_power).collect(),
+                    blocks: epoch_info.blocks.into_iter().map(|b| b.event).collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// Fetch epochs [start_epoch, end_epoch] from the DB as simulator input.
+    /// Negative epochs are relative to the latest epoch.
+    pub fn fetch_epochs_from_db(
+        aptos_db: &dyn DbReader,
+        start_epoch: i64,
+        end_epoch: Option<i64>,
+    ) -> Result<Vec<SimulatedEpoch>> {
+        let latest_epoch = aptos_db.get_latest_ledger_info()?.ledger_info().epoch();
+        let resolve = |epoch: i64| {
+            if epoch < 0 {
+                (latest_epoch as i64 + epoch + 1).max(0) as u64
+            } else {
+                epoch as u64
+            }
+        };
+        let start_epoch = std::cmp::max(resolve(start_epoch), 2);
+        let end_epoch = std::cmp::min(end_epoch.map_or(latest_epoch, resolve), latest_epoch);
+
+        let mut result = vec![];
+        for epoch in start_epoch..=end_epoch {
+            // Validator set of the epoch is in the ledger info ending the previous epoch
+            let epoch_change_proof = aptos_db.get_epoch_ending_ledger_infos(epoch - 1, epoch)?;
+            let verifier = epoch_change_proof
+                .ledger_info_with_sigs
+                .first()
+                .and_then(|li| li.ledger_info().next_epoch_state())
+                .map(|epoch_state| epoch_state.verifier.clone())
+                .ok_or_else(|| anyhow!("Missing validator set for epoch {}", epoch))?;
+            let validators: Vec<AccountAddress> =
+                verifier.get_ordered_account_addresses_iter().collect();
+            let voting_powers = validators
+                .iter()
+                .map(|v| verifier.get_voting_power(v).unwrap_or(0))
+                .collect();
+
+            let mut blocks: Vec<_> = AnalyzeValidators::fetch_epoch(epoch, aptos_db)?
+                .into_iter()
+                .map(|b| b.event)
+                .collect();
+            blocks.reverse();
+            println!("Fetched {} blocks for epoch {}", blocks.len(), epoch);
+
+            result.push(SimulatedEpoch {
+                epoch,
+                validators,
+                voting_powers,
+                blocks,
+            });
+        }
+        Ok(result)
+    }
+
+    /// Print per validator proposer shares, side by side with the compared configuration if given.
+    pub fn print_simulation_table(base: &SimulationResult, compared: Option<&SimulationResult>) {
+        let print_summary = |name: &str, result: &SimulationResult| {
+            println!(
+                "{}: {} rounds, {} historical failed rounds, {:.1} expected failed rounds ({:.3}% failure rate)",
+                name,
+                result.total_rounds,
+                result.historical_failed_rounds,
+                result.expected_failed_rounds,
+                100.0 * result.expected_failed_rounds / (result.total_rounds.max(1) as f64),
+            );
+        };
+        print_summary("base", base);
+        if let Some(compared) = compared {
+            print_summary("compared", compared);
+        }
+
+        let compared_stats: HashMap<_, _> = compared
+            .map(|c| c.validators.iter().map(|s| (s.validator, s)).collect())
+            .unwrap_or_default();
+
+        println!(
+            "{: <10} | {: <10} | {: <10} | {: <10} | {: <10} | {: <10} | {: <10} | {: <66}",
+            "% history",
+            "% failed",
+            "% base",
+            "base fail",
+            "% compare",
+            "comp fail",
+            "delta",
+            "author",
+        );
+        for stats in &base.validators {
+            let historical_failure_rate = if stats.historical_proposer_rounds > 0 {
+                stats.historical_failed_rounds as f64 / stats.historical_proposer_rounds as f64
+            } else {
+                0.0
+            };
+            let other = compared_stats.get(&stats.validator);
+            println!(
+                "{:7.3}%   | {:7.3}%   | {:7.3}%   | {: <10.1} | {: <10} | {: <10} | {: <10} | {}",
+                100.0 * stats.historical_proposer_share,
+                100.0 * historical_failure_rate,
+                100.0 * stats.simulated_proposer_share,
+                stats.expected_failed_rounds,
+                other
+                    .map(|o| format!("{:7.3}%", 100.0 * o.simulated_proposer_share))
+                    .unwrap_or_default(),
+                other
+                    .map(|o| format!("{:.1}", o.expected_failed_rounds))
+                    .unwrap_or_default(),
+                other
+                    .map(|o| format!(
+                        "{:+7.3}%",
+                        100.0 * (o.simulated_proposer_share - stats.simulated_proposer_share)
+                    ))
+                    .unwrap_or_default(),
+                stats.validator,
+            );
+        }
+    }
+}
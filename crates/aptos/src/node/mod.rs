@@ -17,6 +17,7 @@ use crate::{
     node::analyze::{
         analyze_validators::{AnalyzeValidators, ValidatorStats},
         fetch_metadata::FetchMetadata,
+        simulate_leader_reputation::SimulateLeaderReputationHelper,
     },
 };
 use aptos_backup_cli::{
@@ -26,8 +27,13 @@ use aptos_backup_cli::{
     utils::{ConcurrentDownloadsOpt, GlobalRestoreOpt, ReplayConcurrencyLevelOpt, RocksdbOpt},
 };
 use aptos_cached_packages::aptos_stdlib;
-use aptos_config::config::NodeConfig;
+use aptos_config::config::{
+    NodeConfig, RocksdbConfigs, BUFFERED_STATE_TARGET_ITEMS,
+    DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD, NO_OP_STORAGE_PRUNER_CONFIG,
+};
+use aptos_consensus::leader_reputation_simulator::{LeaderReputationSimulator, SimulationConfig};
 use aptos_crypto::{bls12381, bls12381::PublicKey, x25519, ValidCryptoMaterialStringExt};
+use aptos_db::AptosDB;
 use aptos_faucet::FaucetArgs;
 use aptos_genesis::config::{HostAndPort, OperatorConfiguration};
 use aptos_rest_client::{aptos_api_types::VersionedEvent, Client, State};
@@ -54,7 +60,7 @@ use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
-    path::PathBuf,
+    path::{Path, PathBuf},
     pin::Pin,
     sync::Arc,
     thread,
@@ -83,6 +89,7 @@ pub enum NodeTool {
     UpdateConsensusKey(UpdateConsensusKey),
     UpdateValidatorNetworkAddresses(UpdateValidatorNetworkAddresses),
     AnalyzeValidatorPerformance(AnalyzeValidatorPerformance),
+    SimulateLeaderReputation(SimulateLeaderReputation),
     BootstrapDbFromBackup(BootstrapDbFromBackup),
 }
 
@@ -103,6 +110,7 @@ impl NodeTool {
             UpdateConsensusKey(tool) => tool.execute_serialized().await,
             UpdateValidatorNetworkAddresses(tool) => tool.execute_serialized().await,
             AnalyzeValidatorPerformance(tool) => tool.execute_serialized().await,
+            SimulateLeaderReputation(tool) => tool.execute_serialized().await,
             BootstrapDbFromBackup(tool) => tool.execute_serialized().await,
         }
     }
@@ -1183,6 +1191,8 @@ impl CliCommand<()> for RunLocalTestnet {
                     chain_id: ChainId::test(),
                     maximum_amount: None,
                     do_not_delegate: self.do_not_delegate,
+                    num_minters: 1,
+                    abuse_protection_config: None,
                 }
                 .run(),
             )
@@ -1456,6 +1466,116 @@ impl CliCommand<()> for AnalyzeValidatorPerformance {
     }
 }
 
+/// Simulate leader reputation proposer election over historical blocks
+///
+/// Replays the committed `NewBlockEvent` history (from the REST API, or from a local DB)
+/// through the leader reputation proposer election with the given configuration,
+/// and reports the proposer share and expected failed rounds of each validator.
+/// A second configuration can be given to compare the two side by side.
+#[derive(Parser)]
+pub struct SimulateLeaderReputation {
+    /// First epoch to simulate
+    ///
+    /// Negative values are relative to the latest epoch. Defaults to the previous epoch
+    #[clap(long, default_value = "-2")]
+    pub start_epoch: i64,
+
+    /// Last epoch to simulate
+    ///
+    /// Defaults to the latest epoch
+    #[clap(long)]
+    pub end_epoch: Option<i64>,
+
+    /// Local DB directory to read the history from, instead of the REST API
+    #[clap(long, parse(from_os_str))]
+    pub db_dir: Option<PathBuf>,
+
+    /// YAML file with the configuration to simulate
+    ///
+    /// Contains `leader_reputation_type`, `exclude_round` and `window_for_chain_health`.
+    /// Defaults to the default on-chain consensus config.
+    #[clap(long, parse(from_os_str))]
+    pub config_file: Option<PathBuf>,
+
+    /// YAML file with a second configuration to compare against
+    #[clap(long, parse(from_os_str))]
+    pub compare_config_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) rest_options: RestOptions,
+    #[clap(flatten)]
+    pub(crate) profile_options: ProfileOptions,
+}
+
+impl SimulateLeaderReputation {
+    fn load_config(file: &Path) -> CliTypedResult<SimulationConfig> {
+        from_yaml(&String::from_utf8(read_from_file(file)?).map_err(CliError::from)?)
+    }
+}
+
+#[async_trait]
+impl CliCommand<()> for SimulateLeaderReputation {
+    fn command_name(&self) -> &'static str {
+        "SimulateLeaderReputation"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        let base_config = match &self.config_file {
+            Some(file) => Self::load_config(file)?,
+            None => SimulateLeaderReputationHelper::default_config()?,
+        };
+        let compare_config = self
+            .compare_config_file
+            .as_deref()
+            .map(Self::load_config)
+            .transpose()?;
+
+        let epochs = if let Some(db_dir) = &self.db_dir {
+            let aptos_db = AptosDB::open(
+                db_dir,
+                true,
+                NO_OP_STORAGE_PRUNER_CONFIG,
+                RocksdbConfigs::default(),
+                false,
+                BUFFERED_STATE_TARGET_ITEMS,
+                DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+            )?;
+            SimulateLeaderReputationHelper::fetch_epochs_from_db(
+                &aptos_db,
+                self.start_epoch,
+                self.end_epoch,
+            )?
+        } else {
+            let client = self.rest_options.client(&self.profile_options)?;
+            let epochs = FetchMetadata::fetch_new_block_events(
+                &client,
+                Some(self.start_epoch),
+                self.end_epoch,
+            )
+            .await?;
+            SimulateLeaderReputationHelper::from_epoch_infos(epochs)
+        };
+
+        if epochs.is_empty() {
+            println!("No data found for given input");
+            return Ok(());
+        }
+        println!(
+            "Simulating epochs [{}, {}]",
+            epochs.first().unwrap().epoch,
+            epochs.last().unwrap().epoch
+        );
+
+        let simulator = LeaderReputationSimulator::new(epochs)?;
+        let base = simulator.simulate(&base_config)?;
+        let compared = compare_config
+            .map(|config| simulator.simulate(&config))
+            .transpose()?;
+        SimulateLeaderReputationHelper::print_simulation_table(&base, compared.as_ref());
+        Ok(())
+    }
+}
+
 /// Bootstrap AptosDB from a backup
 ///
 /// Enables users to load from a backup to catch their node's DB up to a known state.
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS at_insat_index;
+DROP INDEX IF EXISTS at_version_index;
+DROP TABLE IF EXISTS account_transactions;
\ No newline at end of file
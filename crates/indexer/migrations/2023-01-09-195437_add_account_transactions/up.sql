@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- Every account a transaction touched: sender, secondary signers, event accounts, token offer
+-- recipients and owners of written resources or modules
+CREATE TABLE account_transactions (
+  transaction_version BIGINT NOT NULL,
+  account_address VARCHAR(66) NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  -- Constraints
+  PRIMARY KEY (account_address, transaction_version)
+);
+-- The primary key serves the lookups of the activity of an account, most recent first
+CREATE INDEX at_version_index ON account_transactions (transaction_version DESC);
+CREATE INDEX at_insat_index ON account_transactions (inserted_at);
\ No newline at end of file
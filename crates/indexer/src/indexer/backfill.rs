@@ -0,0 +1,411 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Backfills a range of versions by splitting it into shards, each processed by its own
+//! `Tailer` (and so its own fetcher and processor).
+//!
+//! The progress of each shard is tracked in `processor_statuses`: a shard resumes from its first
+//! version not marked successful. Shards of most processors run in parallel and write to the
+//! `current_*` tables in any order, which is safe because every upsert into them only overwrites
+//! rows of an earlier `last_transaction_version`, so the last write per key wins across shards.
+//! Processors that read rows they wrote for earlier transactions (the creator of a token
+//! collection, the APT coin info) run their shards one after the other, in version order.
+
+use crate::{
+    database::PgDbPool,
+    indexer::{
+        fetcher::TransactionFetcherOptions, tailer::Tailer,
+        transaction_processor::TransactionProcessor,
+    },
+    models::processor_status::ProcessorStatusV2Query,
+    processors::{
+        account_transactions_processor, coin_processor, default_processor, stake_processor,
+        token_processor,
+    },
+};
+use anyhow::{bail, Context as AnyhowContext, Result};
+use aptos_api::context::Context;
+use aptos_logger::{error, info};
+use diesel::{
+    sql_query,
+    sql_types::{BigInt, Nullable, Text},
+    RunQueryDsl,
+};
+use std::{sync::Arc, time::Duration};
+
+/// How long to wait for the fetcher when it has no batch ready.
+const EMPTY_BATCH_WAIT: Duration = Duration::from_millis(100);
+
+/// A range of versions `[start_version, end_version)` backfilled by a single tailer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BackfillShard {
+    pub start_version: u64,
+    pub end_version: u64,
+}
+
+/// Splits `[start_version, end_version)` into at most `num_shards` shards of the same size
+/// (give or take one version).
+pub fn split_into_shards(
+    start_version: u64,
+    end_version: u64,
+    num_shards: u64,
+) -> Vec<BackfillShard> {
+    let num_versions = end_version.saturating_sub(start_version);
+    let num_shards = std::cmp::max(std::cmp::min(num_shards, num_versions), 1);
+    (0..num_shards)
+        .map(|shard| BackfillShard {
+            start_version: start_version + num_versions * shard / num_shards,
+            end_version: start_version + num_versions * (shard + 1) / num_shards,
+        })
+        .filter(|shard| shard.start_version < shard.end_version)
+        .collect()
+}
+
+/// Returns the first version of `shard` that `processor_name` hasn't processed successfully,
+/// according to `processor_statuses`.
+pub fn get_shard_start_version(
+    conn_pool: &PgDbPool,
+    processor_name: &str,
+    shard: &BackfillShard,
+) -> Result<u64> {
+    let mut conn = conn_pool.get()?;
+    // The first version of the shard that is successful but not followed by a successful
+    // version, if the shard starts with a successful version.
+    let sql = "
+        WITH statuses AS
+        (
+            SELECT
+                version,
+                LEAD(version) OVER (ORDER BY version ASC) AS next_version
+            FROM
+                processor_statuses
+            WHERE
+                name = $1
+                AND success = TRUE
+                AND version >= $2
+                AND version < $3
+        )
+        SELECT
+            CASE
+                WHEN MIN(version) = $2 THEN
+                    MIN(version) FILTER (WHERE next_version IS NULL OR next_version <> version + 1) + 1
+                ELSE NULL
+            END AS version
+        FROM
+            statuses
+        ";
+    #[derive(Debug, QueryableByName)]
+    pub struct Gap {
+        #[diesel(sql_type = Nullable<BigInt>)]
+        pub version: Option<i64>,
+    }
+    let mut res: Vec<Gap> = sql_query(sql)
+        .bind::<Text, _>(processor_name)
+        .bind::<BigInt, _>(shard.start_version as i64)
+        .bind::<BigInt, _>(shard.end_version as i64)
+        .get_results(&mut conn)?;
+    Ok(res
+        .pop()
+        .and_then(|gap| gap.version)
+        .map_or(shard.start_version, |version| version as u64))
+}
+
+/// Whether the rows `processor_name` writes for a transaction depend on rows it wrote for
+/// earlier transactions, in which case a shard can only be processed after the ones before it.
+fn depends_on_earlier_versions(processor_name: &str) -> bool {
+    matches!(processor_name, coin_processor::NAME | token_processor::NAME)
+}
+
+/// Processes the versions of `shard` not processed yet.
+async fn run_shard(
+    context: Arc<Context>,
+    conn_pool: PgDbPool,
+    processor: Arc<dyn TransactionProcessor>,
+    options: TransactionFetcherOptions,
+    shard: BackfillShard,
+) -> Result<()> {
+    let processor_name = processor.name();
+    let start_version = get_shard_start_version(&conn_pool, processor_name, &shard)
+        .with_context(|| format!("Failed to get the start version of {:?}", shard))?;
+    if start_version >= shard.end_version {
+        info!(
+            processor_name = processor_name,
+            start_version = shard.start_version,
+            end_version = shard.end_version,
+            "Shard already backfilled"
+        );
+        return Ok(());
+    }
+
+    let tailer = Tailer::new(context, conn_pool, processor, options)
+        .context("Failed to instantiate tailer")?;
+    tailer.set_fetcher_version(start_version).await;
+    tailer.set_fetcher_ending_version(shard.end_version).await;
+    tailer.transaction_fetcher.lock().await.start().await;
+    info!(
+        processor_name = processor_name,
+        start_version = start_version,
+        end_version = shard.end_version,
+        "Backfilling shard"
+    );
+
+    let mut next_version = start_version;
+    while next_version < shard.end_version {
+        match tailer.process_next_batch().await {
+            (_, None) => tokio::time::sleep(EMPTY_BATCH_WAIT).await,
+            (_, Some(Ok(processing_result))) => {
+                next_version = processing_result.end_version + 1;
+            },
+            (_, Some(Err(tpe))) => {
+                let (err, start_version, end_version, _) = tpe.inner();
+                error!(
+                    processor_name = processor_name,
+                    start_version = start_version,
+                    end_version = end_version,
+                    error =? err,
+                    "Error backfilling batch!"
+                );
+                bail!(
+                    "Error in '{}' while backfilling versions [{}, {}]: {:?}",
+                    processor_name,
+                    start_version,
+                    end_version,
+                    err
+                );
+            },
+        }
+    }
+    info!(
+        processor_name = processor_name,
+        start_version = shard.start_version,
+        end_version = shard.end_version,
+        "Shard backfilled"
+    );
+    Ok(())
+}
+
+/// Backfills `[start_version, end_version)` in `num_shards` shards, with a new processor from
+/// `new_processor` for each. The shards run in parallel, unless the processor depends on the
+/// rows of earlier versions.
+pub async fn run_backfill(
+    context: Arc<Context>,
+    conn_pool: PgDbPool,
+    new_processor: impl Fn() -> Arc<dyn TransactionProcessor>,
+    options: TransactionFetcherOptions,
+    start_version: u64,
+    end_version: u64,
+    num_shards: u64,
+) -> Result<()> {
+    let shards = split_into_shards(start_version, end_version, num_shards);
+    let processor = new_processor();
+    if depends_on_earlier_versions(processor.name()) {
+        info!(
+            processor_name = processor.name(),
+            num_shards = shards.len(),
+            "Processor depends on earlier versions, backfilling shards in order"
+        );
+        for shard in shards {
+            run_shard(
+                context.clone(),
+                conn_pool.clone(),
+                new_processor(),
+                options.clone(),
+                shard,
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+
+    let tasks: Vec<_> = shards
+        .into_iter()
+        .map(|shard| {
+            tokio::spawn(run_shard(
+                context.clone(),
+                conn_pool.clone(),
+                new_processor(),
+                options.clone(),
+                shard,
+            ))
+        })
+        .collect();
+    for result in futures::future::try_join_all(tasks)
+        .await
+        .context("Backfill shard task failed")?
+    {
+        result?;
+    }
+    Ok(())
+}
+
+/// The append-only tables each processor writes, with their version column, which are compared
+/// by `verify_backfill`. The `current_*` tables aren't compared, since the live processor may
+/// have overwritten their rows with later versions.
+fn verified_tables(processor_name: &str) -> &'static [(&'static str, &'static str)] {
+    match processor_name {
+        default_processor::NAME => &[
+            ("transactions", "version"),
+            ("user_transactions", "version"),
+            ("block_metadata_transactions", "version"),
+            ("signatures", "transaction_version"),
+            ("events", "transaction_version"),
+            ("write_set_changes", "transaction_version"),
+            ("move_modules", "transaction_version"),
+            ("move_resources", "transaction_version"),
+            ("table_items", "transaction_version"),
+        ],
+        coin_processor::NAME => &[
+            ("coin_activities", "transaction_version"),
+            ("coin_balances", "transaction_version"),
+            ("coin_supply", "transaction_version"),
+        ],
+        token_processor::NAME => &[
+            ("tokens", "transaction_version"),
+            ("token_ownerships", "transaction_version"),
+            ("token_datas", "transaction_version"),
+            ("collection_datas", "transaction_version"),
+            ("token_activities", "transaction_version"),
+        ],
+        stake_processor::NAME => &[("proposal_votes", "transaction_version")],
+        account_transactions_processor::NAME => &[("account_transactions", "transaction_version")],
+        _ => &[],
+    }
+}
+
+/// Number of rows of `table` in `[start_version, end_version)`, and a digest of their contents
+/// (without `inserted_at`) that doesn't depend on their order.
+fn table_digest(
+    conn_pool: &PgDbPool,
+    table: &str,
+    version_column: &str,
+    start_version: u64,
+    end_version: u64,
+) -> Result<(i64, String)> {
+    let sql = format!(
+        "
+        SELECT
+            COUNT(*) AS num_rows,
+            COALESCE(MD5(STRING_AGG(row_hash, '' ORDER BY row_hash)), '') AS digest
+        FROM
+            (
+                SELECT MD5((TO_JSONB(t) - 'inserted_at')::TEXT) AS row_hash
+                FROM {} t
+                WHERE {} >= $1 AND {} < $2
+            ) rows
+        ",
+        table, version_column, version_column
+    );
+    #[derive(Debug, QueryableByName)]
+    pub struct Digest {
+        #[diesel(sql_type = BigInt)]
+        pub num_rows: i64,
+        #[diesel(sql_type = Text)]
+        pub digest: String,
+    }
+    let mut conn = conn_pool.get()?;
+    let digest: Digest = sql_query(sql)
+        .bind::<BigInt, _>(start_version as i64)
+        .bind::<BigInt, _>(end_version as i64)
+        .get_result(&mut conn)?;
+    Ok((digest.num_rows, digest.digest))
+}
+
+/// Compares the rows the backfill wrote for `[start_version, end_version)` with the ones the
+/// live processor wrote to its own database, which must have processed these versions.
+pub fn verify_backfill(
+    processor_name: &str,
+    backfill_conn_pool: &PgDbPool,
+    live_conn_pool: &PgDbPool,
+    start_version: u64,
+    end_version: u64,
+) -> Result<()> {
+    let live_version = ProcessorStatusV2Query::get_by_processor(
+        &processor_name.to_string(),
+        &mut live_conn_pool.get()?,
+    )?
+    .map(|status| status.last_success_version);
+    if live_version.map_or(true, |version| version < end_version as i64 - 1) {
+        bail!(
+            "The live '{}' is at version {:?}, it must have processed up to {} to verify",
+            processor_name,
+            live_version,
+            end_version - 1
+        );
+    }
+
+    let tables = verified_tables(processor_name);
+    if tables.is_empty() {
+        bail!("No tables to verify for '{}'", processor_name);
+    }
+    let mut mismatches = vec![];
+    for (table, version_column) in tables {
+        let backfilled = table_digest(
+            backfill_conn_pool,
+            table,
+            version_column,
+            start_version,
+            end_version,
+        )?;
+        let live = table_digest(
+            live_conn_pool,
+            table,
+            version_column,
+            start_version,
+            end_version,
+        )?;
+        info!(
+            processor_name = processor_name,
+            table = table,
+            backfilled_rows = backfilled.0,
+            live_rows = live.0,
+            matches = backfilled == live,
+            "Verified backfilled table"
+        );
+        if backfilled != live {
+            mismatches.push(*table);
+        }
+    }
+    if !mismatches.is_empty() {
+        bail!(
+            "Backfilled rows of {:?} differ from the live ones in versions [{}, {})",
+            mismatches,
+            start_version,
+            end_version
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_into_shards() {
+        assert_eq!(split_into_shards(10, 20, 3), vec![
+            BackfillShard {
+                start_version: 10,
+                end_version: 13,
+            },
+            BackfillShard {
+                start_version: 13,
+                end_version: 16,
+            },
+            BackfillShard {
+                start_version: 16,
+                end_version: 20,
+            },
+        ]);
+        // No more shards than versions
+        assert_eq!(split_into_shards(0, 2, 8).len(), 2);
+        assert!(split_into_shards(5, 5, 8).is_empty());
+    }
+
+    #[test]
+    fn test_depends_on_earlier_versions() {
+        assert!(depends_on_earlier_versions(token_processor::NAME));
+        assert!(depends_on_earlier_versions(coin_processor::NAME));
+        assert!(!depends_on_earlier_versions(default_processor::NAME));
+        assert!(!depends_on_earlier_versions(account_transactions_processor::NAME));
+    }
+}
@@ -23,6 +23,8 @@ pub struct Fetcher {
     options: TransactionFetcherOptions,
     chain_id: u8,
     current_version: u64,
+    /// If set, stops fetching before this version instead of following the chain
+    ending_version: Option<u64>,
     highest_known_version: u64,
     transactions_sender: mpsc::Sender<Vec<Transaction>>,
 }
@@ -31,6 +33,7 @@ impl Fetcher {
     pub fn new(
         context: Arc<Context>,
         starting_version: u64,
+        ending_version: Option<u64>,
         options: TransactionFetcherOptions,
         transactions_sender: mpsc::Sender<Vec<Transaction>>,
     ) -> Self {
@@ -39,6 +42,7 @@ impl Fetcher {
             options,
             chain_id: 0,
             current_version: starting_version,
+            ending_version,
             highest_known_version: 0,
             transactions_sender,
         }
@@ -46,7 +50,10 @@ impl Fetcher {
 
     pub fn set_highest_known_version(&mut self) -> anyhow::Result<()> {
         let info = self.context.get_latest_ledger_info_wrapped()?;
-        self.highest_known_version = info.ledger_version.0;
+        self.highest_known_version = match self.ending_version {
+            Some(ending_version) => std::cmp::min(info.ledger_version.0, ending_version - 1),
+            None => info.ledger_version.0,
+        };
         self.chain_id = info.chain_id;
         Ok(())
     }
@@ -88,6 +95,15 @@ impl Fetcher {
     pub async fn run(&mut self) {
         let transaction_fetch_batch_size = self.options.transaction_fetch_batch_size;
         loop {
+            if let Some(ending_version) = self.ending_version {
+                if self.current_version >= ending_version {
+                    info!(
+                        ending_version = ending_version,
+                        "Fetched all transactions, stopping"
+                    );
+                    return;
+                }
+            }
             self.ensure_highest_known_version().await;
 
             info!(
@@ -393,6 +409,7 @@ impl Default for TransactionFetcherOptions {
 
 pub struct TransactionFetcher {
     starting_version: u64,
+    ending_version: Option<u64>,
     options: TransactionFetcherOptions,
     pub context: Arc<Context>,
     pub resolver: Arc<StorageAdapterOwned<DbStateView>>,
@@ -413,6 +430,7 @@ impl TransactionFetcher {
 
         Self {
             starting_version,
+            ending_version: None,
             options,
             context,
             resolver,
@@ -431,8 +449,11 @@ impl TransactionFetcherTrait for TransactionFetcher {
         match self.transaction_receiver.try_next() {
             Ok(Some(transactions)) => transactions,
             Ok(None) => {
-                // We never close the channel, so this should never happen
-                panic!("Transaction fetcher channel closed");
+                // The channel is only closed once the fetcher reached the ending version
+                if self.ending_version.is_none() {
+                    panic!("Transaction fetcher channel closed");
+                }
+                vec![]
             },
             // The error here is when the channel is empty which we definitely expect.
             Err(_) => vec![],
@@ -452,6 +473,13 @@ impl TransactionFetcherTrait for TransactionFetcher {
         self.starting_version = version;
     }
 
+    async fn set_ending_version(&mut self, version: u64) {
+        if self.fetcher_handle.is_some() {
+            panic!("TransactionFetcher already started!");
+        }
+        self.ending_version = Some(version);
+    }
+
     async fn start(&mut self) {
         if self.fetcher_handle.is_some() {
             panic!("TransactionFetcher already started!");
@@ -459,11 +487,17 @@ impl TransactionFetcherTrait for TransactionFetcher {
         let context = self.context.clone();
         let transactions_sender = self.transactions_sender.take().unwrap();
         let starting_version = self.starting_version;
+        let ending_version = self.ending_version;
 
         let options2 = self.options.clone();
         let fetcher_handle = tokio::spawn(async move {
-            let mut fetcher =
-                Fetcher::new(context, starting_version, options2, transactions_sender);
+            let mut fetcher = Fetcher::new(
+                context,
+                starting_version,
+                ending_version,
+                options2,
+                transactions_sender,
+            );
             fetcher.run().await;
         });
         self.fetcher_handle = Some(fetcher_handle);
@@ -479,5 +513,7 @@ pub trait TransactionFetcherTrait: Send + Sync {
 
     async fn set_version(&mut self, version: u64);
 
+    async fn set_ending_version(&mut self, version: u64);
+
     async fn start(&mut self);
 }
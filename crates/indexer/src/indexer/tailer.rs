@@ -55,12 +55,15 @@ impl Tailer {
     }
 
     pub fn run_migrations(&self) {
-        let _ = &self
+        let mut conn = self
             .connection_pool
             .get()
-            .expect("Could not get connection for migrations")
-            .run_pending_migrations(MIGRATIONS)
+            .expect("Could not get connection for migrations");
+        conn.run_pending_migrations(MIGRATIONS)
             .expect("migrations failed!");
+        self.processor
+            .run_custom_migrations(&mut conn)
+            .expect("custom migrations failed!");
     }
 
     /// If chain id doesn't exist, save it. Otherwise, make sure that we're indexing the same chain
@@ -118,6 +121,16 @@ impl Tailer {
         info!(version = version, "Will start fetching from version");
     }
 
+    /// Makes the fetcher stop before `version` instead of following the chain
+    pub async fn set_fetcher_ending_version(&self, version: u64) {
+        self.transaction_fetcher
+            .lock()
+            .await
+            .set_ending_version(version)
+            .await;
+        info!(version = version, "Will stop fetching before version");
+    }
+
     pub async fn process_next_batch(
         &self,
     ) -> (
@@ -354,6 +367,10 @@ mod test {
             self.chain_id = version as u8;
         }
 
+        async fn set_ending_version(&mut self, _version: u64) {
+            // do nothing
+        }
+
         async fn start(&mut self) {
             // do nothing
         }
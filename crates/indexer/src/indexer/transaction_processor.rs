@@ -10,13 +10,14 @@ use crate::{
     indexer::{errors::TransactionProcessingError, processing_result::ProcessingResult},
     models::processor_statuses::ProcessorStatusModel,
     schema,
+    sinks::{write_to_sinks, Sink, SinkRows},
 };
 use aptos_api_types::Transaction;
 use async_trait::async_trait;
 use diesel::{pg::upsert::excluded, prelude::*};
 use field_count::FieldCount;
 use schema::processor_statuses::{self, dsl};
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
 /// The `TransactionProcessor` is used by an instance of a `Tailer` to process transactions
 #[async_trait]
@@ -38,8 +39,50 @@ pub trait TransactionProcessor: Send + Sync + Debug {
     /// This is used by the `get_conn()` helper below
     fn connection_pool(&self) -> &PgDbPool;
 
+    /// Creates or migrates the tables of the processor that aren't part of the embedded
+    /// migrations. Called by the `Tailer` after running those.
+    fn run_custom_migrations(&self, _conn: &mut PgConnection) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Outputs the processor writes its rows to in addition to Postgres
+    fn sinks(&self) -> &[Arc<dyn Sink>] {
+        &[]
+    }
+
     //* Below are helper methods that don't need to be implemented *//
 
+    /// Writes the rows `add_rows` adds to the sinks, if there are any. The batch is only
+    /// checkpointed in the sinks once it has been committed to Postgres, so it doesn't matter
+    /// whether this is called before or after writing to Postgres.
+    fn write_to_sinks<F>(
+        &self,
+        start_version: u64,
+        end_version: u64,
+        add_rows: F,
+    ) -> Result<(), TransactionProcessingError>
+    where
+        Self: Sized,
+        F: FnOnce(&mut SinkRows) -> anyhow::Result<()>,
+    {
+        if self.sinks().is_empty() {
+            return Ok(());
+        }
+        let mut rows = SinkRows::default();
+        add_rows(&mut rows)
+            .and_then(|_| {
+                write_to_sinks(self.sinks(), self.name(), start_version, end_version, &rows)
+            })
+            .map_err(|err| {
+                TransactionProcessingError::TransactionCommitError((
+                    err,
+                    start_version,
+                    end_version,
+                    self.name(),
+                ))
+            })
+    }
+
     /// Gets the connection.
     /// If it was unable to do so (default timeout: 30s), it will keep retrying until it can.
     fn get_conn(&self) -> PgPoolConnection {
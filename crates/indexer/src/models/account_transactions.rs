@@ -0,0 +1,178 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+
+use super::token_models::token_utils::TokenEvent;
+use crate::{schema::account_transactions, util::standardize_address};
+use aptos_api_types::{Transaction as APITransaction, TransactionSignature, WriteSetChange};
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(account_address, transaction_version))]
+#[diesel(table_name = account_transactions)]
+pub struct AccountTransaction {
+    pub transaction_version: i64,
+    pub account_address: String,
+}
+
+impl AccountTransaction {
+    /// Returns a row for each account the transaction touched: the sender and secondary signers,
+    /// the accounts of the events it emitted, the recipients of token offers, and the accounts
+    /// whose resources or modules it wrote or deleted.
+    pub fn from_transaction(transaction: &APITransaction) -> anyhow::Result<Vec<Self>> {
+        let (info, events) = match transaction {
+            APITransaction::UserTransaction(user_txn) => (&user_txn.info, &user_txn.events),
+            APITransaction::GenesisTransaction(genesis_txn) => {
+                (&genesis_txn.info, &genesis_txn.events)
+            },
+            APITransaction::BlockMetadataTransaction(block_metadata_txn) => {
+                (&block_metadata_txn.info, &block_metadata_txn.events)
+            },
+            _ => return Ok(vec![]),
+        };
+        let txn_version = info.version.0 as i64;
+        let mut accounts = BTreeSet::new();
+
+        if let APITransaction::UserTransaction(user_txn) = transaction {
+            accounts.insert(standardize_address(&user_txn.request.sender.to_string()));
+            if let Some(TransactionSignature::MultiAgentSignature(signature)) =
+                &user_txn.request.signature
+            {
+                for address in &signature.secondary_signer_addresses {
+                    accounts.insert(standardize_address(&address.to_string()));
+                }
+            }
+        }
+        for event in events {
+            accounts.insert(standardize_address(&event.guid.account_address.to_string()));
+            let event_type = event.typ.to_string();
+            match TokenEvent::from_event(event_type.as_str(), &event.data, txn_version)? {
+                Some(TokenEvent::OfferTokenEvent(inner)) => {
+                    accounts.insert(standardize_address(&inner.to_address));
+                },
+                Some(TokenEvent::CancelTokenOfferEvent(inner)) => {
+                    accounts.insert(standardize_address(&inner.to_address));
+                },
+                Some(TokenEvent::ClaimTokenEvent(inner)) => {
+                    accounts.insert(standardize_address(&inner.to_address));
+                },
+                _ => {},
+            }
+        }
+        for change in &info.changes {
+            let address = match change {
+                WriteSetChange::DeleteModule(inner) => &inner.address,
+                WriteSetChange::DeleteResource(inner) => &inner.address,
+                WriteSetChange::WriteModule(inner) => &inner.address,
+                WriteSetChange::WriteResource(inner) => &inner.address,
+                // Table items belong to a table handle rather than an account
+                WriteSetChange::DeleteTableItem(_) | WriteSetChange::WriteTableItem(_) => continue,
+            };
+            accounts.insert(standardize_address(&address.to_string()));
+        }
+
+        Ok(accounts
+            .into_iter()
+            .map(|account_address| Self {
+                transaction_version: txn_version,
+                account_address,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_transaction() {
+        let signature = json!({
+            "type": "ed25519_signature",
+            "public_key": "0x00",
+            "signature": "0x00",
+        });
+        let transaction: APITransaction = serde_json::from_value(json!({
+            "type": "user_transaction",
+            "version": "10",
+            "hash": format!("0x{}", "00".repeat(32)),
+            "state_change_hash": format!("0x{}", "00".repeat(32)),
+            "event_root_hash": format!("0x{}", "00".repeat(32)),
+            "state_checkpoint_hash": null,
+            "gas_used": "0",
+            "success": true,
+            "vm_status": "Executed successfully",
+            "accumulator_root_hash": format!("0x{}", "00".repeat(32)),
+            "changes": [
+                {
+                    "type": "write_resource",
+                    "address": "0xc",
+                    "state_key_hash": "0x00",
+                    "data": {"type": "0x1::account::Account", "data": {}},
+                },
+                {
+                    "type": "write_table_item",
+                    "state_key_hash": "0x00",
+                    "handle": "0x0e",
+                    "key": "0x01",
+                    "value": "0x02",
+                },
+            ],
+            "sender": "0xa",
+            "sequence_number": "0",
+            "max_gas_amount": "1000",
+            "gas_unit_price": "1",
+            "expiration_timestamp_secs": "100",
+            "payload": {
+                "type": "entry_function_payload",
+                "function": "0x3::token_transfers::offer_script",
+                "type_arguments": [],
+                "arguments": [],
+            },
+            "signature": {
+                "type": "multi_agent_signature",
+                "sender": signature,
+                "secondary_signer_addresses": ["0xb"],
+                "secondary_signers": [signature],
+            },
+            "events": [{
+                "guid": {"creation_number": "0", "account_address": "0xa"},
+                "sequence_number": "0",
+                "type": "0x3::token_transfers::TokenOfferEvent",
+                "data": {
+                    "amount": "1",
+                    "to_address": "0xd",
+                    "token_id": {
+                        "token_data_id": {"creator": "0xa", "collection": "c", "name": "n"},
+                        "property_version": "0",
+                    },
+                },
+            }],
+            "timestamp": "0",
+        }))
+        .unwrap();
+
+        // The sender, the secondary signer, the offer recipient and the owner of the resource,
+        // but not the table handle.
+        let accounts: Vec<_> = AccountTransaction::from_transaction(&transaction)
+            .unwrap()
+            .into_iter()
+            .map(|row| {
+                assert_eq!(row.transaction_version, 10);
+                row.account_address
+            })
+            .collect();
+        assert_eq!(
+            accounts,
+            ["0xa", "0xb", "0xc", "0xd"]
+                .iter()
+                .map(|address| standardize_address(address))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,367 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Configuration of the `custom_processor`, which indexes events, resources or table items of
+//! any contract into tables defined in a YAML file instead of Rust models and migrations, e.g.
+//!
+//! ```yaml
+//! # Stored in `processor_status` to track the progress of the processor.
+//! name: amm_processor
+//! tables:
+//!   - name: amm_swaps
+//!     event: "0xcafe::amm::SwapEvent"
+//!     columns:
+//!       - name: pool
+//!         path: pool
+//!         type: text
+//!       - name: amount_in
+//!         path: amount_in
+//!         type: numeric
+//!   - name: current_amm_pools
+//!     resource: "0xcafe::amm::Pool"
+//!     columns:
+//!       - name: reserve_x
+//!         path: reserve_x.value
+//!         type: numeric
+//!     upsert_key: [address, type_]
+//! ```
+//!
+//! A source type without generic type parameters matches all of its instantiations.
+
+use crate::util::standardize_address;
+use anyhow::{bail, ensure, Context, Result};
+use aptos_api_types::MoveStructTag;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs, path::Path, str::FromStr};
+
+/// Identifiers are interpolated in SQL statements, so only allow plain lowercase ones.
+static IDENTIFIER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[a-z_][a-z0-9_]{0,62}$").unwrap());
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomProcessorConfig {
+    pub name: String,
+    pub tables: Vec<CustomTableConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomTableConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub source: CustomSource,
+    #[serde(default)]
+    pub columns: Vec<CustomColumnConfig>,
+    /// Columns identifying a row, if the table keeps only the latest version of each row
+    /// instead of one row per event or write.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upsert_key: Option<Vec<String>>,
+}
+
+/// What a table indexes: one row per event, resource write or table item write of the given
+/// type or table.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomSource {
+    Event(String),
+    Resource(String),
+    TableHandle(String),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomColumnConfig {
+    pub name: String,
+    /// Dot separated path of the field in the event or resource data, e.g. `coin.value`, or in
+    /// the decoded key or value of a table item, e.g. `value.amount`. Array elements are
+    /// selected by index, e.g. `items.0`.
+    pub path: String,
+    #[serde(rename = "type")]
+    pub column_type: CustomColumnType,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomColumnType {
+    Text,
+    Bigint,
+    Numeric,
+    Boolean,
+    Jsonb,
+    Timestamp,
+}
+
+impl CustomColumnType {
+    pub fn sql_type(&self) -> &'static str {
+        match self {
+            Self::Text => "TEXT",
+            Self::Bigint => "BIGINT",
+            Self::Numeric => "NUMERIC",
+            Self::Boolean => "BOOLEAN",
+            Self::Jsonb => "JSONB",
+            Self::Timestamp => "TIMESTAMP",
+        }
+    }
+}
+
+/// A column every table has, filled from the transaction and the event or write itself.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImplicitColumn {
+    TransactionVersion,
+    TransactionTimestamp,
+    /// Index of the event or write set change in the transaction.
+    Index,
+    /// Account of the event stream, address of the resource, or handle of the table.
+    Address,
+    /// Type of the event or resource, or of the value of the table item.
+    Type,
+}
+
+impl ImplicitColumn {
+    pub const ALL: [Self; 5] = [
+        Self::TransactionVersion,
+        Self::TransactionTimestamp,
+        Self::Index,
+        Self::Address,
+        Self::Type,
+    ];
+
+    pub fn name(&self, source: &CustomSource) -> &'static str {
+        match (self, source) {
+            (Self::TransactionVersion, _) => "transaction_version",
+            (Self::TransactionTimestamp, _) => "transaction_timestamp",
+            (Self::Index, CustomSource::Event(_)) => "event_index",
+            (Self::Index, _) => "write_set_change_index",
+            (Self::Address, CustomSource::Event(_)) => "account_address",
+            (Self::Address, CustomSource::Resource(_)) => "address",
+            (Self::Address, CustomSource::TableHandle(_)) => "table_handle",
+            (Self::Type, _) => "type_",
+        }
+    }
+
+    pub fn column_type(&self) -> CustomColumnType {
+        match self {
+            Self::TransactionVersion | Self::Index => CustomColumnType::Bigint,
+            Self::TransactionTimestamp => CustomColumnType::Timestamp,
+            Self::Address | Self::Type => CustomColumnType::Text,
+        }
+    }
+}
+
+/// The source of a table with its type or handle parsed, to match the transactions against.
+#[derive(Clone, Debug)]
+pub enum ParsedSource {
+    Event(MoveStructTag),
+    Resource(MoveStructTag),
+    TableHandle(String),
+}
+
+impl ParsedSource {
+    /// Whether `tag` is an instantiation of `pattern`, or equal to it if it has type parameters.
+    pub fn matches_type(pattern: &MoveStructTag, tag: &MoveStructTag) -> bool {
+        if pattern.generic_type_params.is_empty() {
+            pattern.address == tag.address
+                && pattern.module == tag.module
+                && pattern.name == tag.name
+        } else {
+            pattern == tag
+        }
+    }
+}
+
+impl CustomProcessorConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read custom processor config {:?}", path))?;
+        let config: Self = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse custom processor config {:?}", path))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            !self.name.is_empty(),
+            "The processor name must not be empty"
+        );
+        let mut table_names = HashSet::new();
+        for table in &self.tables {
+            ensure!(
+                table_names.insert(&table.name),
+                "Table '{}' is defined more than once",
+                table.name
+            );
+            table.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl CustomTableConfig {
+    pub fn validate(&self) -> Result<()> {
+        validate_identifier(&self.name)?;
+        self.parsed_source()?;
+
+        let mut column_names: HashSet<_> = ImplicitColumn::ALL
+            .iter()
+            .map(|column| column.name(&self.source))
+            .collect();
+        column_names.insert("inserted_at");
+        for column in &self.columns {
+            validate_identifier(&column.name)?;
+            ensure!(
+                column_names.insert(&column.name),
+                "Column '{}' of table '{}' is defined more than once or is reserved",
+                column.name,
+                self.name
+            );
+            ensure!(
+                !matches!(self.source, CustomSource::TableHandle(_))
+                    || matches!(column.path.split('.').next(), Some("key" | "value")),
+                "Column '{}' of table '{}' must select the key or the value of the table item",
+                column.name,
+                self.name
+            );
+        }
+        if let Some(upsert_key) = &self.upsert_key {
+            ensure!(
+                !upsert_key.is_empty(),
+                "The upsert key of table '{}' must not be empty",
+                self.name
+            );
+            for column in upsert_key {
+                ensure!(
+                    column_names.contains(column.as_str()) && column != "inserted_at",
+                    "Upsert key column '{}' is not a column of table '{}'",
+                    column,
+                    self.name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn parsed_source(&self) -> Result<ParsedSource> {
+        Ok(match &self.source {
+            CustomSource::Event(typ) => ParsedSource::Event(parse_struct_tag(typ)?),
+            CustomSource::Resource(typ) => ParsedSource::Resource(parse_struct_tag(typ)?),
+            CustomSource::TableHandle(handle) => {
+                ensure!(
+                    handle.starts_with("0x") && handle.len() <= 66,
+                    "Invalid table handle '{}'",
+                    handle
+                );
+                ParsedSource::TableHandle(standardize_address(handle))
+            },
+        })
+    }
+
+    /// The columns identifying a row: the upsert key if any, otherwise the transaction version
+    /// and the index of the event or write set change.
+    pub fn primary_key(&self) -> Vec<String> {
+        match &self.upsert_key {
+            Some(upsert_key) => upsert_key.clone(),
+            None => [ImplicitColumn::TransactionVersion, ImplicitColumn::Index]
+                .iter()
+                .map(|column| column.name(&self.source).to_string())
+                .collect(),
+        }
+    }
+
+    /// Names and types of all the columns of the table, in the order of the values of a row.
+    pub fn all_columns(&self) -> Vec<(String, CustomColumnType)> {
+        ImplicitColumn::ALL
+            .iter()
+            .map(|column| (column.name(&self.source).to_string(), column.column_type()))
+            .chain(
+                self.columns
+                    .iter()
+                    .map(|column| (column.name.clone(), column.column_type)),
+            )
+            .collect()
+    }
+}
+
+fn validate_identifier(name: &str) -> Result<()> {
+    if !IDENTIFIER_REGEX.is_match(name) {
+        bail!(
+            "Invalid name '{}', must be lowercase letters, digits and underscores",
+            name
+        );
+    }
+    Ok(())
+}
+
+fn parse_struct_tag(typ: &str) -> Result<MoveStructTag> {
+    MoveStructTag::from_str(typ).with_context(|| format!("Invalid Move struct type '{}'", typ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+name: amm_processor
+tables:
+  - name: amm_swaps
+    event: "0xcafe::amm::SwapEvent"
+    columns:
+      - name: amount_in
+        path: amount_in
+        type: numeric
+  - name: current_amm_pools
+    resource: "0xcafe::amm::Pool<0x1::aptos_coin::AptosCoin>"
+    columns:
+      - name: reserve_x
+        path: reserve_x.value
+        type: numeric
+    upsert_key: [address, type_]
+"#;
+
+    #[test]
+    fn test_parse_config() {
+        let config: CustomProcessorConfig = serde_yaml::from_str(CONFIG).unwrap();
+        config.validate().unwrap();
+
+        let swaps = &config.tables[0];
+        assert!(matches!(swaps.source, CustomSource::Event(_)));
+        assert_eq!(swaps.primary_key(), vec![
+            "transaction_version",
+            "event_index"
+        ]);
+        assert_eq!(swaps.all_columns().len(), ImplicitColumn::ALL.len() + 1);
+
+        let pools = &config.tables[1];
+        assert_eq!(pools.primary_key(), vec!["address", "type_"]);
+        let pattern = match pools.parsed_source().unwrap() {
+            ParsedSource::Resource(tag) => tag,
+            _ => panic!("Expected a resource source"),
+        };
+        assert!(ParsedSource::matches_type(
+            &pattern,
+            &MoveStructTag::from_str("0xcafe::amm::Pool<0x1::aptos_coin::AptosCoin>").unwrap()
+        ));
+        assert!(!ParsedSource::matches_type(
+            &pattern,
+            &MoveStructTag::from_str("0xcafe::amm::Pool<0xcafe::usdc::USDC>").unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let mut config: CustomProcessorConfig = serde_yaml::from_str(CONFIG).unwrap();
+        config.tables[0].columns[0].name = "amount; DROP TABLE".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config: CustomProcessorConfig = serde_yaml::from_str(CONFIG).unwrap();
+        config.tables[1].upsert_key = Some(vec!["missing".to_string()]);
+        assert!(config.validate().is_err());
+
+        let mut config: CustomProcessorConfig = serde_yaml::from_str(CONFIG).unwrap();
+        config.tables[0].columns[0].name = "event_index".to_string();
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::custom_config::{CustomColumnType, CustomTableConfig, ImplicitColumn, ParsedSource};
+use crate::util::{parse_timestamp, parse_timestamp_secs, remove_null_bytes, standardize_address};
+use anyhow::{bail, Context, Result};
+use aptos_api_types::{
+    Event, MoveType, Transaction as APITransaction, TransactionInfo, WriteSetChange,
+};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// The value of a column of a row of a custom table.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CustomValue {
+    Text(Option<String>),
+    Bigint(Option<i64>),
+    Numeric(Option<BigDecimal>),
+    Boolean(Option<bool>),
+    Jsonb(Option<Value>),
+    Timestamp(Option<NaiveDateTime>),
+}
+
+impl CustomValue {
+    /// Converts the field of an event, resource or table item to a column value. Missing fields
+    /// are null. Integers and decimals may be JSON numbers or strings (as u64 and u128 are), and
+    /// timestamps are in seconds.
+    pub fn from_json(
+        value: Option<&Value>,
+        column_type: CustomColumnType,
+        txn_version: i64,
+    ) -> Result<Self> {
+        let value = value.filter(|value| !value.is_null());
+        Ok(match column_type {
+            CustomColumnType::Text => Self::Text(value.map(|value| match value {
+                Value::String(s) => s.replace('\u{0000}', ""),
+                value => value.to_string(),
+            })),
+            CustomColumnType::Bigint => Self::Bigint(
+                value
+                    .map(|value| match value {
+                        Value::Number(n) => n.as_i64().context("Integer out of range"),
+                        Value::String(s) => Ok(i64::from_str(s)?),
+                        value => bail!("Expected an integer, got {}", value),
+                    })
+                    .transpose()?,
+            ),
+            CustomColumnType::Numeric => Self::Numeric(
+                value
+                    .map(|value| match value {
+                        Value::Number(n) => Ok(BigDecimal::from_str(&n.to_string())?),
+                        Value::String(s) => Ok(BigDecimal::from_str(s)?),
+                        value => bail!("Expected a number, got {}", value),
+                    })
+                    .transpose()?,
+            ),
+            CustomColumnType::Boolean => Self::Boolean(
+                value
+                    .map(|value| value.as_bool().context("Expected a boolean"))
+                    .transpose()?,
+            ),
+            CustomColumnType::Jsonb => Self::Jsonb(value.map(remove_null_bytes)),
+            CustomColumnType::Timestamp => Self::Timestamp(
+                value
+                    .map(|value| {
+                        let secs = match value {
+                            Value::Number(n) => n.as_u64().context("Timestamp out of range")?,
+                            Value::String(s) => u64::from_str(s)?,
+                            value => bail!("Expected a timestamp, got {}", value),
+                        };
+                        Ok(parse_timestamp_secs(secs, txn_version))
+                    })
+                    .transpose()?,
+            ),
+        })
+    }
+
+    pub fn is_null(&self) -> bool {
+        match self {
+            Self::Text(v) => v.is_none(),
+            Self::Bigint(v) => v.is_none(),
+            Self::Numeric(v) => v.is_none(),
+            Self::Boolean(v) => v.is_none(),
+            Self::Jsonb(v) => v.is_none(),
+            Self::Timestamp(v) => v.is_none(),
+        }
+    }
+}
+
+/// A table of the custom processor, with the values of its rows in the order of
+/// `CustomTableConfig::all_columns`.
+#[derive(Clone, Debug)]
+pub struct CustomTable {
+    pub config: CustomTableConfig,
+    source: ParsedSource,
+}
+
+/// Selects the field at the dot separated `path` of `value`.
+fn select<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    if path.is_empty() {
+        return Some(value);
+    }
+    path.split('.')
+        .try_fold(value, |value, segment| match value {
+            Value::Object(fields) => fields.get(segment),
+            Value::Array(items) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get(index)),
+            _ => None,
+        })
+}
+
+impl CustomTable {
+    pub fn new(config: CustomTableConfig) -> Result<Self> {
+        let source = config.parsed_source()?;
+        Ok(Self { config, source })
+    }
+
+    /// Returns the rows for the events or writes of `transaction` the table indexes.
+    pub fn rows_from_transaction(
+        &self,
+        transaction: &APITransaction,
+    ) -> Result<Vec<Vec<CustomValue>>> {
+        let (info, events, timestamp): (&TransactionInfo, &[Event], u64) = match transaction {
+            APITransaction::UserTransaction(txn) => {
+                (&txn.info, txn.events.as_slice(), txn.timestamp.0)
+            },
+            APITransaction::GenesisTransaction(txn) => (&txn.info, txn.events.as_slice(), 0),
+            APITransaction::BlockMetadataTransaction(txn) => {
+                (&txn.info, txn.events.as_slice(), txn.timestamp.0)
+            },
+            APITransaction::StateCheckpointTransaction(txn) => {
+                (&txn.info, &[][..], txn.timestamp.0)
+            },
+            APITransaction::PendingTransaction(_) => return Ok(vec![]),
+        };
+        let txn_version = info.version.0 as i64;
+        let txn_timestamp = parse_timestamp(timestamp, txn_version);
+
+        // (index, address, type, data)
+        let mut items: Vec<(usize, String, Option<String>, Value)> = vec![];
+        match &self.source {
+            ParsedSource::Event(pattern) => {
+                for (index, event) in events.iter().enumerate() {
+                    if let MoveType::Struct(tag) = &event.typ {
+                        if ParsedSource::matches_type(pattern, tag) {
+                            items.push((
+                                index,
+                                standardize_address(&event.guid.account_address.to_string()),
+                                Some(event.typ.to_string()),
+                                event.data.clone(),
+                            ));
+                        }
+                    }
+                }
+            },
+            ParsedSource::Resource(pattern) => {
+                for (index, change) in info.changes.iter().enumerate() {
+                    if let WriteSetChange::WriteResource(write_resource) = change {
+                        if ParsedSource::matches_type(pattern, &write_resource.data.typ) {
+                            items.push((
+                                index,
+                                standardize_address(&write_resource.address.to_string()),
+                                Some(write_resource.data.typ.to_string()),
+                                serde_json::to_value(&write_resource.data.data)?,
+                            ));
+                        }
+                    }
+                }
+            },
+            ParsedSource::TableHandle(handle) => {
+                for (index, change) in info.changes.iter().enumerate() {
+                    if let WriteSetChange::WriteTableItem(write_table_item) = change {
+                        let table_handle =
+                            standardize_address(&write_table_item.handle.to_string());
+                        if &table_handle != handle {
+                            continue;
+                        }
+                        // The table item is only decoded if the node has the table info.
+                        let (data, value_type) = match &write_table_item.data {
+                            Some(data) => (
+                                json!({ "key": data.key, "value": data.value }),
+                                Some(data.value_type.clone()),
+                            ),
+                            None => (json!({ "key": write_table_item.key.to_string() }), None),
+                        };
+                        items.push((index, table_handle, value_type, data));
+                    }
+                }
+            },
+        }
+
+        items
+            .into_iter()
+            .map(|(index, address, typ, data)| {
+                let mut row: Vec<_> = ImplicitColumn::ALL
+                    .iter()
+                    .map(|column| match column {
+                        ImplicitColumn::TransactionVersion => {
+                            CustomValue::Bigint(Some(txn_version))
+                        },
+                        ImplicitColumn::TransactionTimestamp => {
+                            CustomValue::Timestamp(Some(txn_timestamp))
+                        },
+                        ImplicitColumn::Index => CustomValue::Bigint(Some(index as i64)),
+                        ImplicitColumn::Address => CustomValue::Text(Some(address.clone())),
+                        ImplicitColumn::Type => CustomValue::Text(typ.clone()),
+                    })
+                    .collect();
+                for column in &self.config.columns {
+                    row.push(
+                        CustomValue::from_json(
+                            select(&data, &column.path),
+                            column.column_type,
+                            txn_version,
+                        )
+                        .with_context(|| {
+                            format!(
+                                "Failed to convert '{}' to column '{}' of table '{}' at version {}",
+                                column.path, column.name, self.config.name, txn_version
+                            )
+                        })?,
+                    );
+                }
+                Ok(row)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_and_convert() {
+        let data = json!({
+            "pool": "0xcafe",
+            "amount_in": "340282366920938463463374607431768211455",
+            "reserve": { "value": 100 },
+            "fees": [{ "value": "7" }],
+            "paused": false,
+        });
+
+        assert_eq!(
+            CustomValue::from_json(select(&data, "pool"), CustomColumnType::Text, 0).unwrap(),
+            CustomValue::Text(Some("0xcafe".to_string()))
+        );
+        assert_eq!(
+            CustomValue::from_json(select(&data, "amount_in"), CustomColumnType::Numeric, 0)
+                .unwrap(),
+            CustomValue::Numeric(Some(
+                BigDecimal::from_str("340282366920938463463374607431768211455").unwrap()
+            ))
+        );
+        assert_eq!(
+            CustomValue::from_json(select(&data, "reserve.value"), CustomColumnType::Bigint, 0)
+                .unwrap(),
+            CustomValue::Bigint(Some(100))
+        );
+        assert_eq!(
+            CustomValue::from_json(select(&data, "fees.0.value"), CustomColumnType::Bigint, 0)
+                .unwrap(),
+            CustomValue::Bigint(Some(7))
+        );
+        assert_eq!(
+            CustomValue::from_json(select(&data, "paused"), CustomColumnType::Boolean, 0).unwrap(),
+            CustomValue::Boolean(Some(false))
+        );
+        assert!(
+            CustomValue::from_json(select(&data, "missing.field"), CustomColumnType::Text, 0)
+                .unwrap()
+                .is_null()
+        );
+        assert!(
+            CustomValue::from_json(select(&data, "pool"), CustomColumnType::Bigint, 0).is_err()
+        );
+    }
+}
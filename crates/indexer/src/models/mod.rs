@@ -1,8 +1,10 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod account_transactions;
 pub mod block_metadata_transactions;
 pub mod coin_models;
+pub mod custom_models;
 pub mod events;
 pub mod ledger_info;
 pub mod move_modules;
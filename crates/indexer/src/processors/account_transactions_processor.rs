@@ -0,0 +1,196 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    database::{execute_with_better_error, get_chunks, PgDbPool, PgPoolConnection},
+    indexer::{
+        errors::TransactionProcessingError, processing_result::ProcessingResult,
+        transaction_processor::TransactionProcessor,
+    },
+    models::account_transactions::AccountTransaction,
+    schema,
+    sinks::Sink,
+};
+use aptos_api_types::Transaction as APITransaction;
+use async_trait::async_trait;
+use diesel::{result::Error, sql_query, sql_types::BigInt, PgConnection, RunQueryDsl};
+use field_count::FieldCount;
+use std::{fmt::Debug, sync::Arc};
+
+pub const NAME: &str = "account_transactions_processor";
+/// Name under which the progress of the backfill from the default processor's tables is tracked.
+pub const BACKFILL_NAME: &str = "account_transactions_processor_backfill";
+/// How many versions to backfill per statement.
+pub const BACKFILL_BATCH_SIZE: u64 = 100_000;
+
+/// Derives the rows of `account_transactions` for the versions in `[$1, $2)` from the tables
+/// of the default_processor. Token offer recipients are only in the data of the events, and
+/// table items (whose address is empty) don't belong to an account.
+const BACKFILL_SQL: &str = "
+INSERT INTO account_transactions (transaction_version, account_address)
+SELECT version, sender FROM user_transactions
+WHERE version >= $1 AND version < $2
+UNION
+SELECT transaction_version, signer FROM signatures
+WHERE transaction_version >= $1 AND transaction_version < $2
+UNION
+SELECT transaction_version, account_address FROM events
+WHERE transaction_version >= $1 AND transaction_version < $2
+UNION
+SELECT transaction_version, '0x' || LPAD(SUBSTRING(data->>'to_address' FROM 3), 64, '0')
+FROM events
+WHERE transaction_version >= $1 AND transaction_version < $2
+AND type IN (
+  '0x3::token_transfers::TokenOfferEvent',
+  '0x3::token_transfers::TokenCancelOfferEvent',
+  '0x3::token_transfers::TokenClaimEvent'
+)
+UNION
+SELECT transaction_version, address FROM write_set_changes
+WHERE transaction_version >= $1 AND transaction_version < $2 AND address <> ''
+ON CONFLICT DO NOTHING
+";
+
+pub struct AccountTransactionsProcessor {
+    connection_pool: PgDbPool,
+    sinks: Vec<Arc<dyn Sink>>,
+}
+
+impl AccountTransactionsProcessor {
+    pub fn new(connection_pool: PgDbPool) -> Self {
+        Self {
+            connection_pool,
+            sinks: vec![],
+        }
+    }
+
+    pub fn with_sinks(mut self, sinks: Vec<Arc<dyn Sink>>) -> Self {
+        self.sinks = sinks;
+        self
+    }
+}
+
+impl Debug for AccountTransactionsProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = &self.connection_pool.state();
+        write!(
+            f,
+            "AccountTransactionsProcessor {{ connections: {:?}  idle_connections: {:?} }}",
+            state.connections, state.idle_connections
+        )
+    }
+}
+
+/// Backfills `account_transactions` for the versions in `[start_version, end_version)` from the
+/// tables of the default_processor, which must have processed them. This is much faster than
+/// processing the transactions again. Returns the number of rows inserted.
+pub fn backfill_from_default_processor(
+    conn: &mut PgConnection,
+    start_version: u64,
+    end_version: u64,
+) -> Result<usize, diesel::result::Error> {
+    sql_query(BACKFILL_SQL)
+        .bind::<BigInt, _>(start_version as i64)
+        .bind::<BigInt, _>(end_version as i64)
+        .execute(conn)
+}
+
+fn insert_to_db(
+    conn: &mut PgPoolConnection,
+    name: &'static str,
+    start_version: u64,
+    end_version: u64,
+    account_transactions: Vec<AccountTransaction>,
+) -> Result<(), diesel::result::Error> {
+    aptos_logger::trace!(
+        name = name,
+        start_version = start_version,
+        end_version = end_version,
+        "Inserting to db",
+    );
+    conn.build_transaction()
+        .read_write()
+        .run::<_, Error, _>(|pg_conn| insert_account_transactions(pg_conn, &account_transactions))
+}
+
+fn insert_account_transactions(
+    conn: &mut PgConnection,
+    item_to_insert: &[AccountTransaction],
+) -> Result<(), diesel::result::Error> {
+    use schema::account_transactions::dsl::*;
+
+    let chunks = get_chunks(item_to_insert.len(), AccountTransaction::field_count());
+    for (start_ind, end_ind) in chunks {
+        execute_with_better_error(
+            conn,
+            diesel::insert_into(schema::account_transactions::table)
+                .values(&item_to_insert[start_ind..end_ind])
+                .on_conflict((account_address, transaction_version))
+                .do_nothing(),
+            None,
+        )?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl TransactionProcessor for AccountTransactionsProcessor {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<APITransaction>,
+        start_version: u64,
+        end_version: u64,
+    ) -> Result<ProcessingResult, TransactionProcessingError> {
+        let mut all_account_transactions = vec![];
+        for txn in &transactions {
+            let mut account_transactions =
+                AccountTransaction::from_transaction(txn).map_err(|err| {
+                    TransactionProcessingError::TransactionCommitError((
+                        err,
+                        start_version,
+                        end_version,
+                        self.name(),
+                    ))
+                })?;
+            all_account_transactions.append(&mut account_transactions);
+        }
+
+        self.write_to_sinks(start_version, end_version, |rows| {
+            rows.add("account_transactions", &all_account_transactions)
+        })?;
+
+        let mut conn = self.get_conn();
+        let tx_result = insert_to_db(
+            &mut conn,
+            self.name(),
+            start_version,
+            end_version,
+            all_account_transactions,
+        );
+        match tx_result {
+            Ok(_) => Ok(ProcessingResult::new(
+                self.name(),
+                start_version,
+                end_version,
+            )),
+            Err(err) => Err(TransactionProcessingError::TransactionCommitError((
+                anyhow::Error::from(err),
+                start_version,
+                end_version,
+                self.name(),
+            ))),
+        }
+    }
+
+    fn connection_pool(&self) -> &PgDbPool {
+        &self.connection_pool
+    }
+
+    fn sinks(&self) -> &[Arc<dyn Sink>] {
+        &self.sinks
+    }
+}
@@ -16,22 +16,32 @@ use crate::{
         coin_supply::CoinSupply,
     },
     schema,
+    sinks::Sink,
 };
 use aptos_api_types::Transaction as APITransaction;
 use aptos_types::APTOS_COIN_TYPE;
 use async_trait::async_trait;
 use diesel::{pg::upsert::excluded, result::Error, ExpressionMethods, PgConnection};
 use field_count::FieldCount;
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
 
 pub const NAME: &str = "coin_processor";
 pub struct CoinTransactionProcessor {
     connection_pool: PgDbPool,
+    sinks: Vec<Arc<dyn Sink>>,
 }
 
 impl CoinTransactionProcessor {
     pub fn new(connection_pool: PgDbPool) -> Self {
-        Self { connection_pool }
+        Self {
+            connection_pool,
+            sinks: vec![],
+        }
+    }
+
+    pub fn with_sinks(mut self, sinks: Vec<Arc<dyn Sink>>) -> Self {
+        self.sinks = sinks;
+        self
     }
 }
 
@@ -293,6 +303,17 @@ impl TransactionProcessor for CoinTransactionProcessor {
             (&a.owner_address, &a.coin_type).cmp(&(&b.owner_address, &b.coin_type))
         });
 
+        self.write_to_sinks(start_version, end_version, |rows| {
+            rows.add("coin_activities", &all_coin_activities)?;
+            rows.add("coin_balances", &all_coin_balances)?;
+            rows.add("coin_supply", &all_coin_supply)?;
+            rows.add_current(
+                "current_coin_balances",
+                &["owner_address", "coin_type"],
+                &all_current_coin_balances,
+            )
+        })?;
+
         let tx_result = insert_to_db(
             &mut conn,
             self.name(),
@@ -322,4 +343,8 @@ impl TransactionProcessor for CoinTransactionProcessor {
     fn connection_pool(&self) -> &PgDbPool {
         &self.connection_pool
     }
+
+    fn sinks(&self) -> &[Arc<dyn Sink>] {
+        &self.sinks
+    }
 }
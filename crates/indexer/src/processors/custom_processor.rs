@@ -0,0 +1,369 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    database::{get_chunks, PgDbPool, PgPoolConnection},
+    indexer::{
+        errors::TransactionProcessingError, processing_result::ProcessingResult,
+        transaction_processor::TransactionProcessor,
+    },
+    models::custom_models::{
+        custom_config::{CustomProcessorConfig, ImplicitColumn},
+        custom_rows::{CustomTable, CustomValue},
+    },
+};
+use anyhow::{ensure, Result};
+use aptos_api_types::Transaction as APITransaction;
+use async_trait::async_trait;
+use diesel::{
+    pg::Pg,
+    result::Error,
+    sql_query,
+    sql_types::{BigInt, Bool, Jsonb, Nullable, Numeric, Text, Timestamp},
+    PgConnection, RunQueryDsl,
+};
+use std::{collections::HashMap, fmt::Debug};
+
+pub const NAME: &str = "custom_processor";
+
+/// Indexes the events, resources or table items defined in a `CustomProcessorConfig` into the
+/// tables it defines, which are created (or get their missing columns added) on startup.
+pub struct CustomTransactionProcessor {
+    connection_pool: PgDbPool,
+    /// The name from the config, so that each config tracks its own progress.
+    name: &'static str,
+    tables: Vec<CustomTable>,
+}
+
+impl CustomTransactionProcessor {
+    pub fn new(connection_pool: PgDbPool, config: CustomProcessorConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            connection_pool,
+            // The processor lives as long as the indexer, and its name must be static.
+            name: Box::leak(config.name.into_boxed_str()),
+            tables: config
+                .tables
+                .into_iter()
+                .map(CustomTable::new)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    fn rows_per_table(
+        &self,
+        transactions: &[APITransaction],
+    ) -> Result<Vec<Vec<Vec<CustomValue>>>> {
+        self.tables
+            .iter()
+            .map(|table| {
+                let mut rows = vec![];
+                for txn in transactions {
+                    rows.append(&mut table.rows_from_transaction(txn)?);
+                }
+                dedup_by_upsert_key(table, rows)
+            })
+            .collect()
+    }
+}
+
+impl Debug for CustomTransactionProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = &self.connection_pool.state();
+        write!(
+            f,
+            "CustomTransactionProcessor {{ name: {} connections: {:?}  idle_connections: {:?} }}",
+            self.name, state.connections, state.idle_connections
+        )
+    }
+}
+
+/// Statements creating `table`, or adding the columns missing from it if it already exists.
+/// Changing the type of a column or the primary key requires a manual migration.
+fn create_table_statements(table: &CustomTable) -> Vec<String> {
+    let config = &table.config;
+    let all_columns = config.all_columns();
+    let column_definitions: Vec<_> = all_columns
+        .iter()
+        .map(|(name, column_type)| format!("{} {}", name, column_type.sql_type()))
+        .chain(std::iter::once(
+            "inserted_at TIMESTAMP NOT NULL DEFAULT NOW()".to_string(),
+        ))
+        .collect();
+
+    let mut statements = vec![format!(
+        "CREATE TABLE IF NOT EXISTS {} ({}, PRIMARY KEY ({}))",
+        config.name,
+        column_definitions.join(", "),
+        config.primary_key().join(", "),
+    )];
+    statements.extend(all_columns.iter().map(|(name, column_type)| {
+        format!(
+            "ALTER TABLE {} ADD COLUMN IF NOT EXISTS {} {}",
+            config.name,
+            name,
+            column_type.sql_type()
+        )
+    }));
+    statements.push(format!(
+        "CREATE INDEX IF NOT EXISTS {0}_address_index ON {0} ({1})",
+        config.name,
+        ImplicitColumn::Address.name(&config.source),
+    ));
+    statements
+}
+
+/// Upserts `num_rows` rows, keeping the row of the latest transaction.
+fn insert_statement(table: &CustomTable, num_rows: usize) -> String {
+    let config = &table.config;
+    let columns: Vec<_> = config
+        .all_columns()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    let primary_key = config.primary_key();
+
+    let values: Vec<_> = (0..num_rows)
+        .map(|row| {
+            let params: Vec<_> = (0..columns.len())
+                .map(|column| format!("${}", row * columns.len() + column + 1))
+                .collect();
+            format!("({})", params.join(", "))
+        })
+        .collect();
+    let updates: Vec<_> = columns
+        .iter()
+        .filter(|column| !primary_key.contains(column))
+        .map(|column| format!("{0} = EXCLUDED.{0}", column))
+        .collect();
+
+    let on_conflict = if updates.is_empty() {
+        "DO NOTHING".to_string()
+    } else {
+        format!(
+            "DO UPDATE SET {}, inserted_at = NOW() WHERE {}.transaction_version <= EXCLUDED.transaction_version",
+            updates.join(", "),
+            config.name,
+        )
+    };
+    format!(
+        "INSERT INTO {} ({}) VALUES {} ON CONFLICT ({}) {}",
+        config.name,
+        columns.join(", "),
+        values.join(", "),
+        primary_key.join(", "),
+        on_conflict,
+    )
+}
+
+/// With an upsert key, a batch may have several rows for the same key, which a single upsert
+/// statement can't update twice: only keep the latest.
+fn dedup_by_upsert_key(
+    table: &CustomTable,
+    rows: Vec<Vec<CustomValue>>,
+) -> Result<Vec<Vec<CustomValue>>> {
+    let upsert_key = match &table.config.upsert_key {
+        Some(upsert_key) => upsert_key,
+        None => return Ok(rows),
+    };
+    let columns = table.config.all_columns();
+    let key_indices: Vec<_> = upsert_key
+        .iter()
+        .map(|key| columns.iter().position(|(name, _)| name == key).unwrap())
+        .collect();
+
+    let mut latest_by_key = HashMap::new();
+    for (position, row) in rows.iter().enumerate() {
+        for index in &key_indices {
+            ensure!(
+                !row[*index].is_null(),
+                "Upsert key column '{}' of table '{}' is null",
+                columns[*index].0,
+                table.config.name
+            );
+        }
+        let key = format!(
+            "{:?}",
+            key_indices.iter().map(|i| &row[*i]).collect::<Vec<_>>()
+        );
+        latest_by_key.insert(key, position);
+    }
+    let mut positions: Vec<_> = latest_by_key.into_values().collect();
+    positions.sort_unstable();
+
+    let mut rows: Vec<_> = rows.into_iter().map(Some).collect();
+    Ok(positions
+        .into_iter()
+        .map(|position| rows[position].take().unwrap())
+        .collect())
+}
+
+fn insert_rows(
+    conn: &mut PgConnection,
+    table: &CustomTable,
+    rows: &[Vec<CustomValue>],
+) -> Result<(), diesel::result::Error> {
+    let num_columns = table.config.all_columns().len();
+    for (start_ind, end_ind) in get_chunks(rows.len(), num_columns) {
+        if start_ind == end_ind {
+            continue;
+        }
+        let mut query = sql_query(insert_statement(table, end_ind - start_ind)).into_boxed::<Pg>();
+        for value in rows[start_ind..end_ind].iter().flatten() {
+            query = match value.clone() {
+                CustomValue::Text(v) => query.bind::<Nullable<Text>, _>(v),
+                CustomValue::Bigint(v) => query.bind::<Nullable<BigInt>, _>(v),
+                CustomValue::Numeric(v) => query.bind::<Nullable<Numeric>, _>(v),
+                CustomValue::Boolean(v) => query.bind::<Nullable<Bool>, _>(v),
+                CustomValue::Jsonb(v) => query.bind::<Nullable<Jsonb>, _>(v),
+                CustomValue::Timestamp(v) => query.bind::<Nullable<Timestamp>, _>(v),
+            };
+        }
+        query.execute(conn)?;
+    }
+    Ok(())
+}
+
+fn insert_to_db(
+    conn: &mut PgPoolConnection,
+    name: &'static str,
+    start_version: u64,
+    end_version: u64,
+    tables: &[CustomTable],
+    rows_per_table: &[Vec<Vec<CustomValue>>],
+) -> Result<(), diesel::result::Error> {
+    aptos_logger::trace!(
+        name = name,
+        start_version = start_version,
+        end_version = end_version,
+        "Inserting to db",
+    );
+    conn.build_transaction()
+        .read_write()
+        .run::<_, Error, _>(|pg_conn| {
+            for (table, rows) in tables.iter().zip(rows_per_table) {
+                insert_rows(pg_conn, table, rows)?;
+            }
+            Ok(())
+        })
+}
+
+#[async_trait]
+impl TransactionProcessor for CustomTransactionProcessor {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<APITransaction>,
+        start_version: u64,
+        end_version: u64,
+    ) -> Result<ProcessingResult, TransactionProcessingError> {
+        let rows_per_table = self.rows_per_table(&transactions).map_err(|err| {
+            TransactionProcessingError::TransactionCommitError((
+                err,
+                start_version,
+                end_version,
+                self.name(),
+            ))
+        })?;
+
+        let mut conn = self.get_conn();
+        let tx_result = insert_to_db(
+            &mut conn,
+            self.name(),
+            start_version,
+            end_version,
+            &self.tables,
+            &rows_per_table,
+        );
+        match tx_result {
+            Ok(_) => Ok(ProcessingResult::new(
+                self.name(),
+                start_version,
+                end_version,
+            )),
+            Err(err) => Err(TransactionProcessingError::TransactionCommitError((
+                anyhow::Error::from(err),
+                start_version,
+                end_version,
+                self.name(),
+            ))),
+        }
+    }
+
+    fn connection_pool(&self) -> &PgDbPool {
+        &self.connection_pool
+    }
+
+    fn run_custom_migrations(&self, conn: &mut PgConnection) -> Result<()> {
+        for table in &self.tables {
+            for statement in create_table_statements(table) {
+                sql_query(statement).execute(conn)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(upsert_key: Option<&str>) -> CustomTable {
+        let config: CustomProcessorConfig = serde_yaml::from_str(&format!(
+            r#"
+name: amm_processor
+tables:
+  - name: amm_pools
+    resource: "0xcafe::amm::Pool"
+    columns:
+      - name: reserve
+        path: reserve
+        type: numeric
+    {}
+"#,
+            upsert_key.unwrap_or_default()
+        ))
+        .unwrap();
+        CustomTable::new(config.tables[0].clone()).unwrap()
+    }
+
+    #[test]
+    fn test_insert_statement() {
+        assert_eq!(
+            insert_statement(&table(None), 2),
+            "INSERT INTO amm_pools (transaction_version, transaction_timestamp, \
+             write_set_change_index, address, type_, reserve) VALUES ($1, $2, $3, $4, $5, $6), \
+             ($7, $8, $9, $10, $11, $12) ON CONFLICT (transaction_version, write_set_change_index) \
+             DO UPDATE SET transaction_timestamp = EXCLUDED.transaction_timestamp, \
+             address = EXCLUDED.address, type_ = EXCLUDED.type_, reserve = EXCLUDED.reserve, \
+             inserted_at = NOW() WHERE amm_pools.transaction_version <= EXCLUDED.transaction_version"
+        );
+    }
+
+    #[test]
+    fn test_dedup_by_upsert_key() {
+        let row = |version: i64, address: &str| {
+            vec![
+                CustomValue::Bigint(Some(version)),
+                CustomValue::Timestamp(None),
+                CustomValue::Bigint(Some(0)),
+                CustomValue::Text(Some(address.to_string())),
+                CustomValue::Text(None),
+                CustomValue::Numeric(None),
+            ]
+        };
+        let rows = vec![row(1, "0xa"), row(2, "0xb"), row(3, "0xa")];
+
+        assert_eq!(
+            dedup_by_upsert_key(&table(Some("upsert_key: [address]")), rows.clone()).unwrap(),
+            vec![row(2, "0xb"), row(3, "0xa")]
+        );
+        assert_eq!(
+            dedup_by_upsert_key(&table(None), rows.clone()).unwrap(),
+            rows
+        );
+    }
+}
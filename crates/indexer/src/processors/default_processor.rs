@@ -21,21 +21,31 @@ use crate::{
         write_set_changes::{WriteSetChangeDetail, WriteSetChangeModel},
     },
     schema,
+    sinks::Sink,
 };
 use aptos_api_types::Transaction;
 use async_trait::async_trait;
 use diesel::{pg::upsert::excluded, result::Error, ExpressionMethods, PgConnection};
 use field_count::FieldCount;
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
 
 pub const NAME: &str = "default_processor";
 pub struct DefaultTransactionProcessor {
     connection_pool: PgDbPool,
+    sinks: Vec<Arc<dyn Sink>>,
 }
 
 impl DefaultTransactionProcessor {
     pub fn new(connection_pool: PgDbPool) -> Self {
-        Self { connection_pool }
+        Self {
+            connection_pool,
+            sinks: vec![],
+        }
+    }
+
+    pub fn with_sinks(mut self, sinks: Vec<Arc<dyn Sink>>) -> Self {
+        self.sinks = sinks;
+        self
     }
 }
 
@@ -470,6 +480,23 @@ impl TransactionProcessor for DefaultTransactionProcessor {
             .sort_by(|a, b| (&a.table_handle, &a.key_hash).cmp(&(&b.table_handle, &b.key_hash)));
         table_metadata.sort_by(|a, b| a.handle.cmp(&b.handle));
 
+        self.write_to_sinks(start_version, end_version, |rows| {
+            rows.add("transactions", &txns)?;
+            rows.add("user_transactions", &user_transactions)?;
+            rows.add("signatures", &signatures)?;
+            rows.add("block_metadata_transactions", &block_metadata_transactions)?;
+            rows.add("events", &events)?;
+            rows.add("write_set_changes", &write_set_changes)?;
+            rows.add("move_modules", &move_modules)?;
+            rows.add("move_resources", &move_resources)?;
+            rows.add("table_items", &table_items)?;
+            rows.add_current(
+                "current_table_items",
+                &["table_handle", "key_hash"],
+                &current_table_items,
+            )
+        })?;
+
         let mut conn = self.get_conn();
         let tx_result = insert_to_db(
             &mut conn,
@@ -506,4 +533,8 @@ impl TransactionProcessor for DefaultTransactionProcessor {
     fn connection_pool(&self) -> &PgDbPool {
         &self.connection_pool
     }
+
+    fn sinks(&self) -> &[Arc<dyn Sink>] {
+        &self.sinks
+    }
 }
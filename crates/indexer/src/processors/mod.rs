@@ -1,13 +1,17 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod account_transactions_processor;
 pub mod coin_processor;
+pub mod custom_processor;
 pub mod default_processor;
 pub mod stake_processor;
 pub mod token_processor;
 
 use self::{
-    coin_processor::NAME as COIN_PROCESSOR_NAME, default_processor::NAME as DEFAULT_PROCESSOR_NAME,
+    account_transactions_processor::NAME as ACCOUNT_TRANSACTIONS_PROCESSOR_NAME,
+    coin_processor::NAME as COIN_PROCESSOR_NAME, custom_processor::NAME as CUSTOM_PROCESSOR_NAME,
+    default_processor::NAME as DEFAULT_PROCESSOR_NAME,
     stake_processor::NAME as STAKE_PROCESSOR_NAME, token_processor::NAME as TOKEN_PROCESSOR_NAME,
 };
 
@@ -16,6 +20,8 @@ pub enum Processor {
     DefaultProcessor,
     TokenProcessor,
     StakeProcessor,
+    CustomProcessor,
+    AccountTransactionsProcessor,
 }
 
 impl Processor {
@@ -25,6 +31,8 @@ impl Processor {
             TOKEN_PROCESSOR_NAME => Self::TokenProcessor,
             COIN_PROCESSOR_NAME => Self::CoinProcessor,
             STAKE_PROCESSOR_NAME => Self::StakeProcessor,
+            CUSTOM_PROCESSOR_NAME => Self::CustomProcessor,
+            ACCOUNT_TRANSACTIONS_PROCESSOR_NAME => Self::AccountTransactionsProcessor,
             _ => panic!("Processor unsupported {}", input_str),
         }
     }
@@ -14,21 +14,31 @@ use crate::{
         staking_pool_voter::{CurrentStakingPoolVoter, StakingPoolVoterMap},
     },
     schema,
+    sinks::Sink,
 };
 use aptos_api_types::Transaction as APITransaction;
 use async_trait::async_trait;
 use diesel::{pg::upsert::excluded, result::Error, ExpressionMethods, PgConnection};
 use field_count::FieldCount;
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
 
 pub const NAME: &str = "stake_processor";
 pub struct StakeTransactionProcessor {
     connection_pool: PgDbPool,
+    sinks: Vec<Arc<dyn Sink>>,
 }
 
 impl StakeTransactionProcessor {
     pub fn new(connection_pool: PgDbPool) -> Self {
-        Self { connection_pool }
+        Self {
+            connection_pool,
+            sinks: vec![],
+        }
+    }
+
+    pub fn with_sinks(mut self, sinks: Vec<Arc<dyn Sink>>) -> Self {
+        self.sinks = sinks;
+        self
     }
 }
 
@@ -163,6 +173,15 @@ impl TransactionProcessor for StakeTransactionProcessor {
         all_current_stake_pool_voters
             .sort_by(|a, b| a.staking_pool_address.cmp(&b.staking_pool_address));
 
+        self.write_to_sinks(start_version, end_version, |rows| {
+            rows.add("proposal_votes", &all_proposal_votes)?;
+            rows.add_current(
+                "current_staking_pool_voter",
+                &["staking_pool_address"],
+                &all_current_stake_pool_voters,
+            )
+        })?;
+
         let mut conn = self.get_conn();
         let tx_result = insert_to_db(
             &mut conn,
@@ -190,4 +209,8 @@ impl TransactionProcessor for StakeTransactionProcessor {
     fn connection_pool(&self) -> &PgDbPool {
         &self.connection_pool
     }
+
+    fn sinks(&self) -> &[Arc<dyn Sink>] {
+        &self.sinks
+    }
 }
@@ -22,17 +22,19 @@ use crate::{
         },
     },
     schema,
+    sinks::Sink,
 };
 use aptos_api_types::Transaction;
 use async_trait::async_trait;
 use diesel::{pg::upsert::excluded, result::Error, ExpressionMethods, PgConnection};
 use field_count::FieldCount;
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
 
 pub const NAME: &str = "token_processor";
 pub struct TokenTransactionProcessor {
     connection_pool: PgDbPool,
     ans_contract_address: Option<String>,
+    sinks: Vec<Arc<dyn Sink>>,
 }
 
 impl TokenTransactionProcessor {
@@ -44,8 +46,14 @@ impl TokenTransactionProcessor {
         Self {
             connection_pool,
             ans_contract_address,
+            sinks: vec![],
         }
     }
+
+    pub fn with_sinks(mut self, sinks: Vec<Arc<dyn Sink>>) -> Self {
+        self.sinks = sinks;
+        self
+    }
 }
 
 impl Debug for TokenTransactionProcessor {
@@ -587,6 +595,44 @@ impl TransactionProcessor for TokenTransactionProcessor {
         all_current_ans_lookups
             .sort_by(|a, b| a.domain.cmp(&b.domain).then(a.subdomain.cmp(&b.subdomain)));
 
+        self.write_to_sinks(start_version, end_version, |rows| {
+            rows.add("tokens", &all_tokens)?;
+            rows.add("token_ownerships", &all_token_ownerships)?;
+            rows.add("token_datas", &all_token_datas)?;
+            rows.add("collection_datas", &all_collection_datas)?;
+            rows.add("token_activities", &all_token_activities)?;
+            rows.add_current(
+                "current_token_ownerships",
+                &["token_data_id_hash", "property_version", "owner_address"],
+                &all_current_token_ownerships,
+            )?;
+            rows.add_current(
+                "current_token_datas",
+                &["token_data_id_hash"],
+                &all_current_token_datas,
+            )?;
+            rows.add_current(
+                "current_collection_datas",
+                &["collection_data_id_hash"],
+                &all_current_collection_datas,
+            )?;
+            rows.add_current(
+                "current_token_pending_claims",
+                &[
+                    "token_data_id_hash",
+                    "property_version",
+                    "from_address",
+                    "to_address",
+                ],
+                &all_current_token_claims,
+            )?;
+            rows.add_current(
+                "current_ans_lookup",
+                &["domain", "subdomain"],
+                &all_current_ans_lookups,
+            )
+        })?;
+
         let tx_result = insert_to_db(
             &mut conn,
             self.name(),
@@ -625,4 +671,8 @@ impl TransactionProcessor for TokenTransactionProcessor {
     fn connection_pool(&self) -> &PgDbPool {
         &self.connection_pool
     }
+
+    fn sinks(&self) -> &[Arc<dyn Sink>] {
+        &self.sinks
+    }
 }
@@ -2,19 +2,32 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    database::new_db_pool,
+    database::{new_db_pool, PgDbPool},
     indexer::{
-        fetcher::TransactionFetcherOptions, processing_result::ProcessingResult, tailer::Tailer,
+        backfill::{run_backfill, verify_backfill},
+        fetcher::TransactionFetcherOptions,
+        processing_result::ProcessingResult,
+        tailer::Tailer,
         transaction_processor::TransactionProcessor,
     },
+    models::custom_models::custom_config::CustomProcessorConfig,
     processors::{
-        coin_processor::CoinTransactionProcessor, default_processor::DefaultTransactionProcessor,
-        stake_processor::StakeTransactionProcessor, token_processor::TokenTransactionProcessor,
+        account_transactions_processor::{
+            backfill_from_default_processor, AccountTransactionsProcessor, BACKFILL_BATCH_SIZE,
+            BACKFILL_NAME, NAME as ACCOUNT_TRANSACTIONS_PROCESSOR_NAME,
+        },
+        coin_processor::CoinTransactionProcessor,
+        custom_processor::CustomTransactionProcessor,
+        default_processor::{DefaultTransactionProcessor, NAME as DEFAULT_PROCESSOR_NAME},
+        stake_processor::StakeTransactionProcessor,
+        token_processor::TokenTransactionProcessor,
         Processor,
     },
+    sinks::{new_sinks, Sink},
 };
+use anyhow::Context as _;
 use aptos_api::context::Context;
-use aptos_config::config::{IndexerConfig, NodeConfig};
+use aptos_config::config::{IndexerBackfillConfig, IndexerConfig, NodeConfig};
 use aptos_logger::{error, info};
 use aptos_mempool::MempoolClientSender;
 use aptos_storage_interface::DbReader;
@@ -97,6 +110,169 @@ pub fn bootstrap(
     Some(Ok(runtime))
 }
 
+/// Backfills `account_transactions` up to `end_version` (exclusive) from the tables of the
+/// default_processor, as far as it has processed them, resuming from the recorded progress.
+fn backfill_account_transactions(
+    tailer: &Tailer,
+    conn_pool: &PgDbPool,
+    end_version: u64,
+) -> anyhow::Result<()> {
+    let default_processor_version = tailer
+        .get_start_version(&DEFAULT_PROCESSOR_NAME.to_string())
+        .context("Failed to get default processor version")?
+        .unwrap_or(0) as u64;
+    let end_version = std::cmp::min(end_version, default_processor_version);
+    let mut version = tailer
+        .get_start_version(&BACKFILL_NAME.to_string())
+        .context("Failed to get backfill version")?
+        .unwrap_or(0) as u64;
+    info!(
+        processor_name = BACKFILL_NAME,
+        start_version = version,
+        end_version = end_version,
+        "Backfilling from the default processor tables..."
+    );
+
+    while version < end_version {
+        let batch_end_version = std::cmp::min(version + BACKFILL_BATCH_SIZE, end_version);
+        let mut conn = conn_pool
+            .get()
+            .context("Could not get connection for backfill")?;
+        let num_rows = backfill_from_default_processor(&mut conn, version, batch_end_version)
+            .with_context(|| format!("Failed to backfill versions from {}", version))?;
+        tailer
+            .update_last_processed_version(BACKFILL_NAME, batch_end_version - 1)
+            .context("Failed to update backfill version")?;
+        info!(
+            processor_name = BACKFILL_NAME,
+            batch_start_version = version,
+            batch_end_version = batch_end_version,
+            num_rows = num_rows,
+            "Backfilled batch version"
+        );
+        version = batch_end_version;
+    }
+    Ok(())
+}
+
+/// Instantiates the processor named in the config, writing to `conn_pool`.
+fn new_processor(
+    config: &IndexerConfig,
+    conn_pool: PgDbPool,
+    sinks: Vec<Arc<dyn Sink>>,
+) -> Arc<dyn TransactionProcessor> {
+    match Processor::from_string(config.processor.as_ref().unwrap()) {
+        Processor::DefaultProcessor => {
+            Arc::new(DefaultTransactionProcessor::new(conn_pool.clone()).with_sinks(sinks))
+        },
+        Processor::TokenProcessor => Arc::new(
+            TokenTransactionProcessor::new(conn_pool.clone(), config.ans_contract_address.clone())
+                .with_sinks(sinks),
+        ),
+        Processor::CoinProcessor => {
+            Arc::new(CoinTransactionProcessor::new(conn_pool.clone()).with_sinks(sinks))
+        },
+        Processor::StakeProcessor => {
+            Arc::new(StakeTransactionProcessor::new(conn_pool.clone()).with_sinks(sinks))
+        },
+        Processor::AccountTransactionsProcessor => {
+            Arc::new(AccountTransactionsProcessor::new(conn_pool.clone()).with_sinks(sinks))
+        },
+        Processor::CustomProcessor => {
+            let custom_config = CustomProcessorConfig::load(
+                config
+                    .custom_processor_config
+                    .as_ref()
+                    .expect("custom_processor_config must be set"),
+            )
+            .expect("Failed to load custom processor config");
+            Arc::new(
+                CustomTransactionProcessor::new(conn_pool.clone(), custom_config)
+                    .expect("Invalid custom processor config"),
+            )
+        },
+    }
+}
+
+/// Backfills the range of versions of the backfill config in parallel shards, then verifies the
+/// end of the range against the live database if requested.
+async fn run_backfill_mode(
+    config: &IndexerConfig,
+    backfill_config: IndexerBackfillConfig,
+    context: Arc<Context>,
+    live_conn_pool: PgDbPool,
+    options: TransactionFetcherOptions,
+    skip_migrations: bool,
+) {
+    let conn_pool = match &backfill_config.postgres_uri {
+        Some(uri) => new_db_pool(uri).expect("Failed to create backfill connection pool"),
+        None => live_conn_pool.clone(),
+    };
+    let processor = new_processor(config, conn_pool.clone(), vec![]);
+    let processor_name = processor.name();
+    if !skip_migrations {
+        info!(processor_name = processor_name, "Running migrations...");
+        Tailer::new(
+            context.clone(),
+            conn_pool.clone(),
+            processor,
+            options.clone(),
+        )
+        .expect("Failed to instantiate tailer")
+        .run_migrations();
+    }
+
+    info!(
+        processor_name = processor_name,
+        start_version = backfill_config.start_version,
+        end_version = backfill_config.end_version,
+        num_shards = backfill_config.num_shards,
+        "Starting backfill..."
+    );
+    if let Err(err) = run_backfill(
+        context,
+        conn_pool.clone(),
+        || new_processor(config, conn_pool.clone(), vec![]),
+        options,
+        backfill_config.start_version,
+        backfill_config.end_version,
+        backfill_config.num_shards as u64,
+    )
+    .await
+    {
+        error!(
+            processor_name = processor_name,
+            error = format!("{:?}", err),
+            "Backfill failed!"
+        );
+        return;
+    }
+    info!(processor_name = processor_name, "Backfill done!");
+
+    if backfill_config.verify_versions > 0 {
+        let verify_start_version = backfill_config.end_version - backfill_config.verify_versions;
+        match verify_backfill(
+            processor_name,
+            &conn_pool,
+            &live_conn_pool,
+            verify_start_version,
+            backfill_config.end_version,
+        ) {
+            Ok(()) => info!(
+                processor_name = processor_name,
+                start_version = verify_start_version,
+                end_version = backfill_config.end_version,
+                "Backfill verified"
+            ),
+            Err(err) => error!(
+                processor_name = processor_name,
+                error = format!("{:?}", err),
+                "Backfill verification failed!"
+            ),
+        }
+    }
+}
+
 pub async fn run_forever(config: IndexerConfig, context: Arc<Context>) {
     // All of these options should be filled already with defaults
     let processor_name = config.processor.clone().unwrap();
@@ -110,7 +286,7 @@ pub async fn run_forever(config: IndexerConfig, context: Arc<Context>) {
 
     info!(processor_name = processor_name, "Starting indexer...");
 
-    let db_uri = &config.postgres_uri.unwrap();
+    let db_uri = config.postgres_uri.as_ref().unwrap();
     info!(
         processor_name = processor_name,
         "Creating connection pool..."
@@ -123,22 +299,35 @@ pub async fn run_forever(config: IndexerConfig, context: Arc<Context>) {
 
     info!(processor_name = processor_name, "Instantiating tailer... ");
 
-    let processor_enum = Processor::from_string(&processor_name);
-    let processor: Arc<dyn TransactionProcessor> = match processor_enum {
-        Processor::DefaultProcessor => {
-            Arc::new(DefaultTransactionProcessor::new(conn_pool.clone()))
-        },
-        Processor::TokenProcessor => Arc::new(TokenTransactionProcessor::new(
-            conn_pool.clone(),
-            config.ans_contract_address,
-        )),
-        Processor::CoinProcessor => Arc::new(CoinTransactionProcessor::new(conn_pool.clone())),
-        Processor::StakeProcessor => Arc::new(StakeTransactionProcessor::new(conn_pool.clone())),
-    };
-
     let options =
         TransactionFetcherOptions::new(None, None, Some(batch_size), None, fetch_tasks as usize);
 
+    if let Some(backfill_config) = config.backfill.clone() {
+        run_backfill_mode(
+            &config,
+            backfill_config,
+            context,
+            conn_pool,
+            options,
+            skip_migrations,
+        )
+        .await;
+        return;
+    }
+
+    let sinks = new_sinks(&config.sinks).expect("Failed to create sinks");
+    let processor = new_processor(&config, conn_pool.clone(), sinks);
+    // The custom processor tracks its progress under the name from its config.
+    let processor_name = processor.name().to_string();
+    let sinks = processor.sinks().to_vec();
+    if sinks.len() != config.sinks.len() {
+        panic!("Processor '{}' doesn't support sinks", processor_name);
+    }
+    // The backfill copies rows between Postgres tables, so the sinks would never get them
+    if !sinks.is_empty() && config.backfill_account_transactions.unwrap_or(false) {
+        panic!("backfill_account_transactions can't be used with sinks");
+    }
+
     let tailer = Tailer::new(context, conn_pool.clone(), processor, options)
         .expect("Failed to instantiate tailer");
 
@@ -163,10 +352,23 @@ pub async fn run_forever(config: IndexerConfig, context: Arc<Context>) {
             );
             0
         }) as u64;
-    let start_version = match config.starting_version {
+    let mut start_version = match config.starting_version {
         None => starting_version_from_db_short,
         Some(version) => version,
     };
+    for sink in &sinks {
+        let checkpoint = sink
+            .last_checkpoint(&processor_name)
+            .unwrap_or_else(|e| panic!("Failed to get sink checkpoint: {:?}", e));
+        // Reprocess what a sink missed, which is idempotent for Postgres
+        if let (None, Some(checkpoint)) = (config.starting_version, checkpoint) {
+            start_version = std::cmp::min(start_version, checkpoint + 1);
+        }
+    }
+    for sink in &sinks {
+        sink.rewind(&processor_name, start_version)
+            .unwrap_or_else(|e| panic!("Failed to rewind sink: {:?}", e));
+    }
 
     info!(
         processor_name = processor_name,
@@ -177,6 +379,24 @@ pub async fn run_forever(config: IndexerConfig, context: Arc<Context>) {
     );
     tailer.set_fetcher_version(start_version).await;
 
+    if processor_name == ACCOUNT_TRANSACTIONS_PROCESSOR_NAME
+        && config.backfill_account_transactions.unwrap_or(false)
+    {
+        let (tailer, conn_pool) = (tailer.clone(), conn_pool.clone());
+        // The backfill runs alongside the processor, so a failure is logged rather than
+        // stopping the indexer. It resumes from its progress on the next start.
+        tokio::task::spawn_blocking(move || {
+            match backfill_account_transactions(&tailer, &conn_pool, start_version) {
+                Ok(()) => info!(processor_name = BACKFILL_NAME, "Backfill done!"),
+                Err(e) => error!(
+                    processor_name = BACKFILL_NAME,
+                    error = format!("{:?}", e),
+                    "Backfill failed!"
+                ),
+            }
+        });
+    }
+
     info!(processor_name = processor_name, "Starting fetcher...");
     tailer.transaction_fetcher.lock().await.start().await;
 
@@ -252,6 +472,12 @@ pub async fn run_forever(config: IndexerConfig, context: Arc<Context>) {
                 );
                 panic!("Failed to update last processed version: {:?}", e);
             });
+        if num_res > 0 {
+            for sink in &sinks {
+                sink.checkpoint(&processor_name, batch_end_version)
+                    .unwrap_or_else(|e| panic!("Failed to checkpoint sink {:?}: {:?}", sink, e));
+            }
+        }
 
         ma.tick_now(num_res);
 
@@ -3,6 +3,14 @@
 
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    account_transactions (account_address, transaction_version) {
+        transaction_version -> Int8,
+        account_address -> Varchar,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     block_metadata_transactions (version) {
         version -> Int8,
@@ -496,6 +504,7 @@ diesel::table! {
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
+    account_transactions,
     block_metadata_transactions,
     coin_activities,
     coin_balances,
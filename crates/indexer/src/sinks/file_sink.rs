@@ -0,0 +1,267 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{parquet, Sink};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const CHECKPOINT_FILE: &str = "checkpoint";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileFormat {
+    /// Newline-delimited JSON, one row per line
+    JsonLines,
+    /// Parquet with a fixed schema per table, see the `parquet` module
+    Parquet,
+}
+
+impl FileFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            FileFormat::JsonLines => "jsonl",
+            FileFormat::Parquet => "parquet",
+        }
+    }
+
+    fn encode(self, table: &str, rows: &[Value]) -> Result<Vec<u8>> {
+        match self {
+            FileFormat::JsonLines => {
+                let mut contents = vec![];
+                for row in rows {
+                    serde_json::to_writer(&mut contents, row)?;
+                    contents.push(b'\n');
+                }
+                Ok(contents)
+            },
+            FileFormat::Parquet => parquet::encode_rows(table, rows),
+        }
+    }
+}
+
+/// Writes a file per batch, partitioned by processor and table:
+/// `<root>/<processor>/<table>/<start_version>-<end_version>.<jsonl|parquet>`, with the versions
+/// zero padded so that the files sort by version. The checkpoint of a processor is stored in
+/// `<root>/<processor>/checkpoint`.
+///
+/// The `current_*` tables can't be updated in place, so each batch file of a `current_*` table
+/// holds the rows that changed in the batch; the current row of a key is the one with the highest
+/// `last_transaction_version` across the files.
+#[derive(Debug)]
+pub struct FileSink {
+    root: PathBuf,
+    format: FileFormat,
+}
+
+impl FileSink {
+    pub fn new(root: &Path, format: FileFormat) -> Result<Self> {
+        fs::create_dir_all(root)
+            .with_context(|| format!("Failed to create sink directory {:?}", root))?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            format,
+        })
+    }
+
+    fn batch_file_name(&self, start_version: u64, end_version: u64) -> String {
+        format!(
+            "{:020}-{:020}.{}",
+            start_version,
+            end_version,
+            self.format.extension()
+        )
+    }
+
+    /// Parses the start version of a batch file name.
+    fn batch_start_version(&self, file_name: &str) -> Option<u64> {
+        file_name
+            .strip_suffix(self.format.extension())?
+            .strip_suffix('.')?
+            .split_once('-')?
+            .0
+            .parse()
+            .ok()
+    }
+}
+
+/// Writes `contents` to a temporary file first, so that readers never see a partial file.
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+impl Sink for FileSink {
+    fn write_rows(
+        &self,
+        processor: &str,
+        table: &str,
+        start_version: u64,
+        end_version: u64,
+        rows: &[Value],
+    ) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let dir = self.root.join(processor).join(table);
+        fs::create_dir_all(&dir)?;
+        write_atomically(
+            &dir.join(self.batch_file_name(start_version, end_version)),
+            &self.format.encode(table, rows)?,
+        )
+    }
+
+    fn upsert_current_rows(
+        &self,
+        processor: &str,
+        table: &str,
+        _key_columns: &[&str],
+        start_version: u64,
+        end_version: u64,
+        rows: &[Value],
+    ) -> Result<()> {
+        self.write_rows(processor, table, start_version, end_version, rows)
+    }
+
+    fn checkpoint(&self, processor: &str, version: u64) -> Result<()> {
+        if self
+            .last_checkpoint(processor)?
+            .map_or(false, |last| last >= version)
+        {
+            return Ok(());
+        }
+        let dir = self.root.join(processor);
+        fs::create_dir_all(&dir)?;
+        write_atomically(&dir.join(CHECKPOINT_FILE), version.to_string().as_bytes())
+    }
+
+    fn last_checkpoint(&self, processor: &str) -> Result<Option<u64>> {
+        let path = self.root.join(processor).join(CHECKPOINT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)?;
+        Ok(Some(contents.trim().parse().with_context(|| {
+            format!("Invalid checkpoint {:?} in {:?}", contents, path)
+        })?))
+    }
+
+    fn rewind(&self, processor: &str, version: u64) -> Result<()> {
+        let dir = self.root.join(processor);
+        if !dir.exists() {
+            return Ok(());
+        }
+        for table_dir in fs::read_dir(&dir)? {
+            let table_dir = table_dir?.path();
+            if !table_dir.is_dir() {
+                continue;
+            }
+            for file in fs::read_dir(&table_dir)? {
+                let path = file?.path();
+                let is_stale = match path.extension().and_then(|ext| ext.to_str()) {
+                    Some("tmp") => true,
+                    _ => path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .and_then(|name| self.batch_start_version(name))
+                        .map_or(false, |start_version| start_version >= version),
+                };
+                if is_stale {
+                    fs::remove_file(&path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::{Row, RowAccessor},
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_write_checkpoint_and_rewind() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = FileSink::new(dir.path(), FileFormat::JsonLines).unwrap();
+        let rows = vec![json!({"version": 1}), json!({"version": 2})];
+
+        sink.write_rows("default_processor", "transactions", 1, 2, &rows)
+            .unwrap();
+        sink.write_rows("default_processor", "transactions", 3, 4, &rows)
+            .unwrap();
+        assert_eq!(sink.last_checkpoint("default_processor").unwrap(), None);
+        sink.checkpoint("default_processor", 2).unwrap();
+        sink.checkpoint("default_processor", 1).unwrap();
+        assert_eq!(sink.last_checkpoint("default_processor").unwrap(), Some(2));
+
+        let table_dir = dir.path().join("default_processor").join("transactions");
+        let contents = fs::read_to_string(table_dir.join(sink.batch_file_name(1, 2))).unwrap();
+        assert_eq!(contents, "{\"version\":1}\n{\"version\":2}\n");
+
+        sink.rewind("default_processor", 3).unwrap();
+        assert!(table_dir.join(sink.batch_file_name(1, 2)).exists());
+        assert!(!table_dir.join(sink.batch_file_name(3, 4)).exists());
+    }
+
+    #[test]
+    fn test_parquet_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = FileSink::new(dir.path(), FileFormat::Parquet).unwrap();
+        let rows = vec![json!({
+            "owner_address": "0x1",
+            "coin_type_hash": "0x2",
+            "coin_type": "0x1::aptos_coin::AptosCoin",
+            "amount": "100",
+            "last_transaction_version": 1,
+            "last_transaction_timestamp": "2022-10-04T19:28:03",
+        })];
+
+        sink.upsert_current_rows(
+            "coin_processor",
+            "current_coin_balances",
+            &["owner_address"],
+            1,
+            1,
+            &rows,
+        )
+        .unwrap();
+        sink.upsert_current_rows(
+            "coin_processor",
+            "current_coin_balances",
+            &["owner_address"],
+            2,
+            3,
+            &rows,
+        )
+        .unwrap();
+
+        let table_dir = dir
+            .path()
+            .join("coin_processor")
+            .join("current_coin_balances");
+        let reader = SerializedFileReader::new(
+            fs::File::open(table_dir.join(sink.batch_file_name(1, 1))).unwrap(),
+        )
+        .unwrap();
+        let read_back: Vec<Row> = reader.get_row_iter(None).unwrap().collect();
+        assert_eq!(read_back.len(), 1);
+        assert_eq!(read_back[0].get_string(0).unwrap(), "0x1");
+        assert_eq!(read_back[0].get_long(4).unwrap(), 1);
+
+        sink.rewind("coin_processor", 2).unwrap();
+        assert!(table_dir.join(sink.batch_file_name(1, 1)).exists());
+        assert!(!table_dir.join(sink.batch_file_name(2, 3)).exists());
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Outputs the processors write their rows to in addition to Postgres, e.g. for analytics
+//! pipelines reading partitioned files, or tests querying a SQLite database.
+//!
+//! Sinks receive the tables of a batch as JSON rows: the append-only tables (e.g.
+//! `transactions`, `events` or `coin_activities`) are written per batch, while the rows of the
+//! `current_*` tables are upserted by their primary key. Each sink keeps its own checkpoint per
+//! processor, so a sink that is behind Postgres (e.g. because the indexer crashed after
+//! committing a batch to Postgres) gets the missing batches again on restart.
+//!
+//! Sinks don't replace Postgres: it still tracks the progress of the processors, and the
+//! backfills copy rows between Postgres tables. The config validation rejects sinks for the
+//! custom_processor, with `backfill_account_transactions` and with `backfill`.
+
+pub mod file_sink;
+pub mod parquet;
+pub mod sqlite_sink;
+
+use self::{
+    file_sink::{FileFormat, FileSink},
+    sqlite_sink::SqliteSink,
+};
+use anyhow::{Context, Result};
+use aptos_config::config::IndexerSinkConfig;
+use serde::Serialize;
+use serde_json::Value;
+use std::{fmt::Debug, sync::Arc};
+
+pub trait Sink: Send + Sync + Debug {
+    /// Writes the rows of `table` produced by `processor` for the versions
+    /// `[start_version, end_version]`. Writing the same batch again replaces it.
+    fn write_rows(
+        &self,
+        processor: &str,
+        table: &str,
+        start_version: u64,
+        end_version: u64,
+        rows: &[Value],
+    ) -> Result<()>;
+
+    /// Upserts the rows of the `current_*` table `table` produced by `processor` for the versions
+    /// `[start_version, end_version]`, keeping for each `key_columns` value the row with the
+    /// highest `last_transaction_version`.
+    fn upsert_current_rows(
+        &self,
+        processor: &str,
+        table: &str,
+        key_columns: &[&str],
+        start_version: u64,
+        end_version: u64,
+        rows: &[Value],
+    ) -> Result<()>;
+
+    /// Records that all the batches of `processor` up to `version` have been written.
+    fn checkpoint(&self, processor: &str, version: u64) -> Result<()>;
+
+    /// Last version checkpointed for `processor`, if any.
+    fn last_checkpoint(&self, processor: &str) -> Result<Option<u64>>;
+
+    /// Discards the batches of `processor` starting at or after `version`, which weren't
+    /// checkpointed and will be written again, possibly with different batch boundaries.
+    fn rewind(&self, processor: &str, version: u64) -> Result<()>;
+}
+
+#[derive(Debug)]
+struct SinkTable {
+    name: &'static str,
+    /// The primary key of a `current_*` table, `None` for append-only tables
+    key_columns: Option<&'static [&'static str]>,
+    rows: Vec<Value>,
+}
+
+/// The rows of a batch, by table.
+#[derive(Debug, Default)]
+pub struct SinkRows {
+    tables: Vec<SinkTable>,
+}
+
+impl SinkRows {
+    /// Adds the rows of an append-only table.
+    pub fn add<T: Serialize>(&mut self, table: &'static str, rows: &[T]) -> Result<()> {
+        self.push(table, None, rows)
+    }
+
+    /// Adds the rows of a `current_*` table, whose primary key is `key_columns`.
+    pub fn add_current<T: Serialize>(
+        &mut self,
+        table: &'static str,
+        key_columns: &'static [&'static str],
+        rows: &[T],
+    ) -> Result<()> {
+        self.push(table, Some(key_columns), rows)
+    }
+
+    fn push<T: Serialize>(
+        &mut self,
+        name: &'static str,
+        key_columns: Option<&'static [&'static str]>,
+        rows: &[T],
+    ) -> Result<()> {
+        let rows = rows
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()
+            .with_context(|| format!("Failed to serialize rows of {}", name))?;
+        self.tables.push(SinkTable {
+            name,
+            key_columns,
+            rows,
+        });
+        Ok(())
+    }
+}
+
+/// Writes a batch to all the sinks.
+pub fn write_to_sinks(
+    sinks: &[Arc<dyn Sink>],
+    processor: &str,
+    start_version: u64,
+    end_version: u64,
+    rows: &SinkRows,
+) -> Result<()> {
+    for sink in sinks {
+        for table in &rows.tables {
+            match table.key_columns {
+                Some(key_columns) => sink.upsert_current_rows(
+                    processor,
+                    table.name,
+                    key_columns,
+                    start_version,
+                    end_version,
+                    &table.rows,
+                ),
+                None => sink.write_rows(
+                    processor,
+                    table.name,
+                    start_version,
+                    end_version,
+                    &table.rows,
+                ),
+            }
+            .with_context(|| format!("Failed to write {} to sink {:?}", table.name, sink))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn new_sinks(configs: &[IndexerSinkConfig]) -> Result<Vec<Arc<dyn Sink>>> {
+    configs
+        .iter()
+        .map(|config| -> Result<Arc<dyn Sink>> {
+            Ok(match config {
+                IndexerSinkConfig::JsonLines { path } => {
+                    Arc::new(FileSink::new(path, FileFormat::JsonLines)?)
+                },
+                IndexerSinkConfig::Parquet { path } => {
+                    Arc::new(FileSink::new(path, FileFormat::Parquet)?)
+                },
+                IndexerSinkConfig::Sqlite { path } => Arc::new(SqliteSink::open(path)?),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,681 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encodes the rows of a batch as a Parquet file. Each table has a fixed schema, so that all the
+//! files of a table can be read as a single dataset. The schemas mirror the Postgres tables (see
+//! `schema.rs`) without `inserted_at`: `numeric` columns are written as UTF8 decimal strings,
+//! `jsonb` columns as JSON text and timestamps as microseconds since the epoch.
+
+use anyhow::{bail, format_err, Context, Result};
+use chrono::NaiveDateTime;
+use once_cell::sync::Lazy;
+use parquet::{
+    basic::{Compression, ConvertedType, Type as PhysicalType},
+    data_type::{BoolType, ByteArray, ByteArrayType, DataType, Int32Type, Int64Type},
+    file::{
+        properties::WriterProperties,
+        writer::{SerializedColumnWriter, SerializedFileWriter},
+    },
+    schema::{
+        parser::parse_message_type,
+        types::{ColumnDescriptor, SchemaDescriptor, Type},
+    },
+};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, sync::Arc};
+
+/// The Parquet schema of each table written to the sinks.
+const TABLE_SCHEMAS: &[(&str, &str)] = &[
+    (
+        "account_transactions",
+        "message account_transactions {
+            required int64 transaction_version;
+            required binary account_address (UTF8);
+        }",
+    ),
+    (
+        "block_metadata_transactions",
+        "message block_metadata_transactions {
+            required int64 version;
+            required int64 block_height;
+            required binary id (UTF8);
+            required int64 round;
+            required int64 epoch;
+            required binary previous_block_votes_bitvec (JSON);
+            required binary proposer (UTF8);
+            required binary failed_proposer_indices (JSON);
+            required int64 timestamp (TIMESTAMP_MICROS);
+        }",
+    ),
+    (
+        "coin_activities",
+        "message coin_activities {
+            required int64 transaction_version;
+            required binary event_account_address (UTF8);
+            required int64 event_creation_number;
+            required int64 event_sequence_number;
+            required binary owner_address (UTF8);
+            required binary coin_type (UTF8);
+            required binary amount (UTF8);
+            required binary activity_type (UTF8);
+            required boolean is_gas_fee;
+            required boolean is_transaction_success;
+            optional binary entry_function_id_str (UTF8);
+            required int64 block_height;
+            required int64 transaction_timestamp (TIMESTAMP_MICROS);
+            optional int64 event_index;
+        }",
+    ),
+    (
+        "coin_balances",
+        "message coin_balances {
+            required int64 transaction_version;
+            required binary owner_address (UTF8);
+            required binary coin_type_hash (UTF8);
+            required binary coin_type (UTF8);
+            required binary amount (UTF8);
+            required int64 transaction_timestamp (TIMESTAMP_MICROS);
+        }",
+    ),
+    (
+        "coin_supply",
+        "message coin_supply {
+            required int64 transaction_version;
+            required binary coin_type_hash (UTF8);
+            required binary coin_type (UTF8);
+            required binary supply (UTF8);
+            required int64 transaction_timestamp (TIMESTAMP_MICROS);
+            required int64 transaction_epoch;
+        }",
+    ),
+    (
+        "collection_datas",
+        "message collection_datas {
+            required binary collection_data_id_hash (UTF8);
+            required int64 transaction_version;
+            required binary creator_address (UTF8);
+            required binary collection_name (UTF8);
+            required binary description (UTF8);
+            required binary metadata_uri (UTF8);
+            required binary supply (UTF8);
+            required binary maximum (UTF8);
+            required boolean maximum_mutable;
+            required boolean uri_mutable;
+            required boolean description_mutable;
+            required binary table_handle (UTF8);
+            required int64 transaction_timestamp (TIMESTAMP_MICROS);
+        }",
+    ),
+    (
+        "current_ans_lookup",
+        "message current_ans_lookup {
+            required binary domain (UTF8);
+            required binary subdomain (UTF8);
+            optional binary registered_address (UTF8);
+            required int64 expiration_timestamp (TIMESTAMP_MICROS);
+            required int64 last_transaction_version;
+            required binary token_name (UTF8);
+        }",
+    ),
+    (
+        "current_coin_balances",
+        "message current_coin_balances {
+            required binary owner_address (UTF8);
+            required binary coin_type_hash (UTF8);
+            required binary coin_type (UTF8);
+            required binary amount (UTF8);
+            required int64 last_transaction_version;
+            required int64 last_transaction_timestamp (TIMESTAMP_MICROS);
+        }",
+    ),
+    (
+        "current_collection_datas",
+        "message current_collection_datas {
+            required binary collection_data_id_hash (UTF8);
+            required binary creator_address (UTF8);
+            required binary collection_name (UTF8);
+            required binary description (UTF8);
+            required binary metadata_uri (UTF8);
+            required binary supply (UTF8);
+            required binary maximum (UTF8);
+            required boolean maximum_mutable;
+            required boolean uri_mutable;
+            required boolean description_mutable;
+            required int64 last_transaction_version;
+            required binary table_handle (UTF8);
+            required int64 last_transaction_timestamp (TIMESTAMP_MICROS);
+        }",
+    ),
+    (
+        "current_staking_pool_voter",
+        "message current_staking_pool_voter {
+            required binary staking_pool_address (UTF8);
+            required binary voter_address (UTF8);
+            required int64 last_transaction_version;
+        }",
+    ),
+    (
+        "current_table_items",
+        "message current_table_items {
+            required binary table_handle (UTF8);
+            required binary key_hash (UTF8);
+            required binary key (UTF8);
+            required binary decoded_key (JSON);
+            optional binary decoded_value (JSON);
+            required boolean is_deleted;
+            required int64 last_transaction_version;
+        }",
+    ),
+    (
+        "current_token_datas",
+        "message current_token_datas {
+            required binary token_data_id_hash (UTF8);
+            required binary creator_address (UTF8);
+            required binary collection_name (UTF8);
+            required binary name (UTF8);
+            required binary maximum (UTF8);
+            required binary supply (UTF8);
+            required binary largest_property_version (UTF8);
+            required binary metadata_uri (UTF8);
+            required binary payee_address (UTF8);
+            required binary royalty_points_numerator (UTF8);
+            required binary royalty_points_denominator (UTF8);
+            required boolean maximum_mutable;
+            required boolean uri_mutable;
+            required boolean description_mutable;
+            required boolean properties_mutable;
+            required boolean royalty_mutable;
+            required binary default_properties (JSON);
+            required int64 last_transaction_version;
+            required binary collection_data_id_hash (UTF8);
+            required int64 last_transaction_timestamp (TIMESTAMP_MICROS);
+            required binary description (UTF8);
+        }",
+    ),
+    (
+        "current_token_ownerships",
+        "message current_token_ownerships {
+            required binary token_data_id_hash (UTF8);
+            required binary property_version (UTF8);
+            required binary owner_address (UTF8);
+            required binary creator_address (UTF8);
+            required binary collection_name (UTF8);
+            required binary name (UTF8);
+            required binary amount (UTF8);
+            required binary token_properties (JSON);
+            required int64 last_transaction_version;
+            required binary collection_data_id_hash (UTF8);
+            required binary table_type (UTF8);
+            required int64 last_transaction_timestamp (TIMESTAMP_MICROS);
+        }",
+    ),
+    (
+        "current_token_pending_claims",
+        "message current_token_pending_claims {
+            required binary token_data_id_hash (UTF8);
+            required binary property_version (UTF8);
+            required binary from_address (UTF8);
+            required binary to_address (UTF8);
+            required binary collection_data_id_hash (UTF8);
+            required binary creator_address (UTF8);
+            required binary collection_name (UTF8);
+            required binary name (UTF8);
+            required binary amount (UTF8);
+            required binary table_handle (UTF8);
+            required int64 last_transaction_version;
+            required int64 last_transaction_timestamp (TIMESTAMP_MICROS);
+        }",
+    ),
+    (
+        "events",
+        "message events {
+            required int64 sequence_number;
+            required int64 creation_number;
+            required binary account_address (UTF8);
+            required int64 transaction_version;
+            required int64 transaction_block_height;
+            required binary type_ (UTF8);
+            required binary data (JSON);
+            optional int64 event_index;
+        }",
+    ),
+    (
+        "move_modules",
+        "message move_modules {
+            required int64 transaction_version;
+            required int64 write_set_change_index;
+            required int64 transaction_block_height;
+            required binary name (UTF8);
+            required binary address (UTF8);
+            optional binary bytecode;
+            optional binary friends (JSON);
+            optional binary exposed_functions (JSON);
+            optional binary structs (JSON);
+            required boolean is_deleted;
+        }",
+    ),
+    (
+        "move_resources",
+        "message move_resources {
+            required int64 transaction_version;
+            required int64 write_set_change_index;
+            required int64 transaction_block_height;
+            required binary name (UTF8);
+            required binary address (UTF8);
+            required binary type_ (UTF8);
+            required binary module (UTF8);
+            optional binary generic_type_params (JSON);
+            optional binary data (JSON);
+            required boolean is_deleted;
+        }",
+    ),
+    (
+        "proposal_votes",
+        "message proposal_votes {
+            required int64 transaction_version;
+            required int64 proposal_id;
+            required binary voter_address (UTF8);
+            required binary staking_pool_address (UTF8);
+            required binary num_votes (UTF8);
+            required boolean should_pass;
+            required int64 transaction_timestamp (TIMESTAMP_MICROS);
+        }",
+    ),
+    (
+        "signatures",
+        "message signatures {
+            required int64 transaction_version;
+            required int64 multi_agent_index;
+            required int64 multi_sig_index;
+            required int64 transaction_block_height;
+            required binary signer (UTF8);
+            required boolean is_sender_primary;
+            required binary type_ (UTF8);
+            required binary public_key (UTF8);
+            required binary signature (UTF8);
+            required int64 threshold;
+            required binary public_key_indices (JSON);
+        }",
+    ),
+    (
+        "table_items",
+        "message table_items {
+            required binary key (UTF8);
+            required int64 transaction_version;
+            required int64 write_set_change_index;
+            required int64 transaction_block_height;
+            required binary table_handle (UTF8);
+            required binary decoded_key (JSON);
+            optional binary decoded_value (JSON);
+            required boolean is_deleted;
+        }",
+    ),
+    (
+        "token_activities",
+        "message token_activities {
+            required int64 transaction_version;
+            required binary event_account_address (UTF8);
+            required int64 event_creation_number;
+            required int64 event_sequence_number;
+            required binary collection_data_id_hash (UTF8);
+            required binary token_data_id_hash (UTF8);
+            required binary property_version (UTF8);
+            required binary creator_address (UTF8);
+            required binary collection_name (UTF8);
+            required binary name (UTF8);
+            required binary transfer_type (UTF8);
+            optional binary from_address (UTF8);
+            optional binary to_address (UTF8);
+            required binary token_amount (UTF8);
+            optional binary coin_type (UTF8);
+            optional binary coin_amount (UTF8);
+            required int64 transaction_timestamp (TIMESTAMP_MICROS);
+            optional int64 event_index;
+        }",
+    ),
+    (
+        "token_datas",
+        "message token_datas {
+            required binary token_data_id_hash (UTF8);
+            required int64 transaction_version;
+            required binary creator_address (UTF8);
+            required binary collection_name (UTF8);
+            required binary name (UTF8);
+            required binary maximum (UTF8);
+            required binary supply (UTF8);
+            required binary largest_property_version (UTF8);
+            required binary metadata_uri (UTF8);
+            required binary payee_address (UTF8);
+            required binary royalty_points_numerator (UTF8);
+            required binary royalty_points_denominator (UTF8);
+            required boolean maximum_mutable;
+            required boolean uri_mutable;
+            required boolean description_mutable;
+            required boolean properties_mutable;
+            required boolean royalty_mutable;
+            required binary default_properties (JSON);
+            required binary collection_data_id_hash (UTF8);
+            required int64 transaction_timestamp (TIMESTAMP_MICROS);
+            required binary description (UTF8);
+        }",
+    ),
+    (
+        "token_ownerships",
+        "message token_ownerships {
+            required binary token_data_id_hash (UTF8);
+            required binary property_version (UTF8);
+            required int64 transaction_version;
+            required binary table_handle (UTF8);
+            required binary creator_address (UTF8);
+            required binary collection_name (UTF8);
+            required binary name (UTF8);
+            optional binary owner_address (UTF8);
+            required binary amount (UTF8);
+            optional binary table_type (UTF8);
+            required binary collection_data_id_hash (UTF8);
+            required int64 transaction_timestamp (TIMESTAMP_MICROS);
+        }",
+    ),
+    (
+        "tokens",
+        "message tokens {
+            required binary token_data_id_hash (UTF8);
+            required binary property_version (UTF8);
+            required int64 transaction_version;
+            required binary creator_address (UTF8);
+            required binary collection_name (UTF8);
+            required binary name (UTF8);
+            required binary token_properties (JSON);
+            required binary collection_data_id_hash (UTF8);
+            required int64 transaction_timestamp (TIMESTAMP_MICROS);
+        }",
+    ),
+    (
+        "transactions",
+        "message transactions {
+            required int64 version;
+            required int64 block_height;
+            required binary hash (UTF8);
+            required binary type_ (UTF8);
+            optional binary payload (JSON);
+            required binary state_change_hash (UTF8);
+            required binary event_root_hash (UTF8);
+            optional binary state_checkpoint_hash (UTF8);
+            required binary gas_used (UTF8);
+            required boolean success;
+            required binary vm_status (UTF8);
+            required binary accumulator_root_hash (UTF8);
+            required int64 num_events;
+            required int64 num_write_set_changes;
+            required int64 epoch;
+        }",
+    ),
+    (
+        "user_transactions",
+        "message user_transactions {
+            required int64 version;
+            required int64 block_height;
+            required binary parent_signature_type (UTF8);
+            required binary sender (UTF8);
+            required int64 sequence_number;
+            required binary max_gas_amount (UTF8);
+            required int64 expiration_timestamp_secs (TIMESTAMP_MICROS);
+            required binary gas_unit_price (UTF8);
+            required int64 timestamp (TIMESTAMP_MICROS);
+            required binary entry_function_id_str (UTF8);
+            required int64 epoch;
+        }",
+    ),
+    (
+        "write_set_changes",
+        "message write_set_changes {
+            required int64 transaction_version;
+            required int64 index;
+            required binary hash (UTF8);
+            required binary type_ (UTF8);
+            required binary address (UTF8);
+        }",
+    ),
+];
+
+static SCHEMAS: Lazy<HashMap<&'static str, Arc<Type>>> = Lazy::new(|| {
+    TABLE_SCHEMAS
+        .iter()
+        .map(|(table, schema)| {
+            let schema = parse_message_type(schema)
+                .unwrap_or_else(|e| panic!("Invalid Parquet schema of {}: {}", table, e));
+            (*table, Arc::new(schema))
+        })
+        .collect()
+});
+
+/// Encodes `rows` of `table`, which must be JSON objects with the columns of the table, as a
+/// Parquet file.
+pub fn encode_rows(table: &str, rows: &[Value]) -> Result<Vec<u8>> {
+    let schema = SCHEMAS
+        .get(table)
+        .ok_or_else(|| format_err!("No Parquet schema for table {}", table))?;
+    let descriptor = SchemaDescriptor::new(schema.clone());
+    let mut objects = Vec::with_capacity(rows.len());
+    for row in rows {
+        let object = row
+            .as_object()
+            .ok_or_else(|| format_err!("Expected a JSON object, got {}", row))?;
+        if let Some(name) = object.keys().find(|name| {
+            !descriptor
+                .columns()
+                .iter()
+                .any(|column| column.name() == name.as_str())
+        }) {
+            bail!("Column {} isn't in the Parquet schema of {}", name, table);
+        }
+        objects.push(object);
+    }
+
+    let mut file = vec![];
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = SerializedFileWriter::new(&mut file, schema.clone(), Arc::new(properties))?;
+    let mut row_group = writer.next_row_group()?;
+    let mut columns = descriptor.columns().iter();
+    while let Some(mut column_writer) = row_group.next_column()? {
+        let column = columns
+            .next()
+            .ok_or_else(|| format_err!("More columns than in the schema of {}", table))?;
+        write_column(&mut column_writer, column, &objects)
+            .with_context(|| format!("Failed to write column {} of {}", column.name(), table))?;
+        column_writer.close()?;
+    }
+    row_group.close()?;
+    writer.close()?;
+    Ok(file)
+}
+
+fn write_column(
+    writer: &mut SerializedColumnWriter,
+    column: &ColumnDescriptor,
+    objects: &[&Map<String, Value>],
+) -> Result<()> {
+    let values: Vec<Option<&Value>> = objects
+        .iter()
+        .map(|object| object.get(column.name()).filter(|value| !value.is_null()))
+        .collect();
+    let def_levels: Option<Vec<i16>> = if column.max_def_level() > 0 {
+        Some(values.iter().map(|value| value.is_some() as i16).collect())
+    } else if values.iter().any(Option::is_none) {
+        bail!("Missing value of a required column");
+    } else {
+        None
+    };
+    let values: Vec<&Value> = values.into_iter().flatten().collect();
+
+    match (column.physical_type(), column.converted_type()) {
+        (PhysicalType::BOOLEAN, _) => {
+            write_values::<BoolType>(writer, &values, def_levels, Value::as_bool)
+        },
+        (PhysicalType::INT32, _) => {
+            write_values::<Int32Type>(writer, &values, def_levels, |value| {
+                value.as_i64().and_then(|value| i32::try_from(value).ok())
+            })
+        },
+        (PhysicalType::INT64, ConvertedType::TIMESTAMP_MICROS) => {
+            write_values::<Int64Type>(writer, &values, def_levels, |value| {
+                let timestamp = serde_json::from_value::<NaiveDateTime>(value.clone()).ok()?;
+                Some(
+                    timestamp.timestamp() * 1_000_000
+                        + i64::from(timestamp.timestamp_subsec_micros()),
+                )
+            })
+        },
+        (PhysicalType::INT64, _) => {
+            write_values::<Int64Type>(writer, &values, def_levels, Value::as_i64)
+        },
+        (PhysicalType::BYTE_ARRAY, ConvertedType::UTF8) => {
+            write_values::<ByteArrayType>(writer, &values, def_levels, |value| {
+                value.as_str().map(ByteArray::from)
+            })
+        },
+        (PhysicalType::BYTE_ARRAY, ConvertedType::JSON) => {
+            write_values::<ByteArrayType>(writer, &values, def_levels, |value| {
+                Some(ByteArray::from(value.to_string().into_bytes()))
+            })
+        },
+        (PhysicalType::BYTE_ARRAY, ConvertedType::NONE) => {
+            write_values::<ByteArrayType>(writer, &values, def_levels, |value| {
+                serde_json::from_value::<Vec<u8>>(value.clone())
+                    .ok()
+                    .map(ByteArray::from)
+            })
+        },
+        (physical_type, converted_type) => bail!(
+            "Unsupported column type {} ({})",
+            physical_type,
+            converted_type
+        ),
+    }
+}
+
+/// Writes the non-null `values` of a column, converted by `convert`.
+fn write_values<T: DataType>(
+    writer: &mut SerializedColumnWriter,
+    values: &[&Value],
+    def_levels: Option<Vec<i16>>,
+    convert: impl Fn(&Value) -> Option<T::T>,
+) -> Result<()> {
+    let values = values
+        .iter()
+        .map(|value| convert(value).ok_or_else(|| format_err!("Unexpected value {}", value)))
+        .collect::<Result<Vec<_>>>()?;
+    writer
+        .typed::<T>()
+        .write_batch(&values, def_levels.as_deref(), None)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::{Row, RowAccessor},
+    };
+    use serde_json::json;
+
+    fn read_rows(file: Vec<u8>) -> (Type, Vec<Row>) {
+        let reader = SerializedFileReader::new(Bytes::from(file)).unwrap();
+        let schema = reader.metadata().file_metadata().schema().clone();
+        let rows = reader.get_row_iter(None).unwrap().collect();
+        (schema, rows)
+    }
+
+    #[test]
+    fn test_table_schemas() {
+        for (table, _) in TABLE_SCHEMAS {
+            assert_eq!(SCHEMAS[table].name(), *table);
+        }
+        assert_eq!(SCHEMAS.len(), TABLE_SCHEMAS.len());
+    }
+
+    #[test]
+    fn test_encode_rows() {
+        let rows = vec![
+            json!({
+                "transaction_version": 1,
+                "write_set_change_index": 0,
+                "transaction_block_height": 10,
+                "name": "coin",
+                "address": "0x1",
+                "bytecode": [1, 2, 3],
+                "friends": ["0x1::aptos_coin"],
+                "exposed_functions": null,
+                "structs": {"Coin": {"abilities": ["store"]}},
+                "is_deleted": false,
+            }),
+            json!({
+                "transaction_version": 2,
+                "write_set_change_index": 1,
+                "transaction_block_height": 11,
+                "name": "coin",
+                "address": "0x1",
+                "is_deleted": true,
+            }),
+        ];
+        let (schema, read_back) = read_rows(encode_rows("move_modules", &rows).unwrap());
+        assert_eq!(&schema, SCHEMAS["move_modules"].as_ref());
+        assert_eq!(read_back.len(), 2);
+        assert_eq!(read_back[0].get_long(0).unwrap(), 1);
+        assert_eq!(read_back[0].get_string(3).unwrap(), "coin");
+        assert_eq!(read_back[0].get_bytes(5).unwrap().data(), &[1, 2, 3]);
+        assert_eq!(
+            read_back[0].get_string(8).unwrap(),
+            "{\"Coin\":{\"abilities\":[\"store\"]}}"
+        );
+        assert!(!read_back[0].get_bool(9).unwrap());
+        assert_eq!(read_back[1].get_long(0).unwrap(), 2);
+        assert!(read_back[1].get_bytes(5).is_err());
+        assert!(read_back[1].get_bool(9).unwrap());
+
+        // Files of the same table have the same schema, whatever their rows.
+        let rows = vec![json!({
+            "owner_address": "0x1",
+            "coin_type_hash": "0x2",
+            "coin_type": "0x1::aptos_coin::AptosCoin",
+            "amount": "100000000000000000000",
+            "last_transaction_version": 3,
+            "last_transaction_timestamp": "2022-10-04T19:28:03.123456",
+        })];
+        let (schema, read_back) = read_rows(encode_rows("current_coin_balances", &rows).unwrap());
+        assert_eq!(&schema, SCHEMAS["current_coin_balances"].as_ref());
+        assert_eq!(read_back[0].get_string(3).unwrap(), "100000000000000000000");
+        assert_eq!(
+            read_back[0].get_timestamp_micros(5).unwrap(),
+            1_664_911_683_123_456
+        );
+        let (schema, read_back) = read_rows(encode_rows("current_coin_balances", &[]).unwrap());
+        assert_eq!(&schema, SCHEMAS["current_coin_balances"].as_ref());
+        assert!(read_back.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_rows() {
+        let row = json!({"transaction_version": 1, "account_address": "0x1"});
+        assert!(encode_rows("account_transactions", &[row]).is_ok());
+        assert!(encode_rows("unknown_table", &[json!({})]).is_err());
+        assert!(encode_rows("account_transactions", &[json!([1, 2])]).is_err());
+        // Unknown column
+        let row = json!({"transaction_version": 1, "account_address": "0x1", "other": 1});
+        assert!(encode_rows("account_transactions", &[row]).is_err());
+        // Missing required column
+        let row = json!({"transaction_version": 1});
+        assert!(encode_rows("account_transactions", &[row]).is_err());
+        let row = json!({"transaction_version": 1, "account_address": null});
+        assert!(encode_rows("account_transactions", &[row]).is_err());
+        // Wrong types
+        let row = json!({"transaction_version": "1", "account_address": "0x1"});
+        assert!(encode_rows("account_transactions", &[row]).is_err());
+        let row = json!({"transaction_version": 1, "account_address": 1});
+        assert!(encode_rows("account_transactions", &[row]).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::Sink;
+use anyhow::{ensure, Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use std::{path::Path, sync::Mutex};
+
+const CHECKPOINTS_TABLE: &str = "sink_checkpoints";
+
+/// Writes the rows to a SQLite database, with one table per indexer table holding the rows as
+/// JSON along with their processor and batch, so that they can be queried with `json_extract`.
+/// The `current_*` tables hold a single row per processor and key instead, which is replaced by
+/// rows with a higher or equal `last_transaction_version`, same as in Postgres.
+///
+/// Processors still keep their progress in Postgres, but reading the processed rows back from an
+/// in-memory database (see `rows` and `current_rows`) doesn't need one.
+#[derive(Debug)]
+pub struct SqliteSink {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteSink {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open sqlite sink {:?}", path))?;
+        Self::new(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    fn new(conn: Connection) -> Result<Self> {
+        conn.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (processor TEXT PRIMARY KEY, version INTEGER NOT NULL)",
+            CHECKPOINTS_TABLE
+        ))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Returns the rows of `table` written by `processor`, in the order of the batches.
+    pub fn rows(&self, processor: &str, table: &str) -> Result<Vec<Value>> {
+        validate_table_name(table)?;
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT data FROM {} WHERE processor = ?1 ORDER BY start_version, rowid",
+            table
+        ))?;
+        let rows: Result<Vec<Value>> = stmt
+            .query_map(params![processor], |row| row.get::<_, String>(0))?
+            .map(|data| Ok(serde_json::from_str(&data?)?))
+            .collect();
+        rows
+    }
+
+    /// Returns the current rows of the `current_*` table `table` written by `processor`, ordered by
+    /// key.
+    pub fn current_rows(&self, processor: &str, table: &str) -> Result<Vec<Value>> {
+        validate_table_name(table)?;
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT data FROM {} WHERE processor = ?1 ORDER BY key",
+            table
+        ))?;
+        let rows: Result<Vec<Value>> = stmt
+            .query_map(params![processor], |row| row.get::<_, String>(0))?
+            .map(|data| Ok(serde_json::from_str(&data?)?))
+            .collect();
+        rows
+    }
+}
+
+fn validate_table_name(table: &str) -> Result<()> {
+    ensure!(
+        !table.is_empty()
+            && table != CHECKPOINTS_TABLE
+            && table
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'),
+        "Invalid table name '{}'",
+        table
+    );
+    Ok(())
+}
+
+impl Sink for SqliteSink {
+    fn write_rows(
+        &self,
+        processor: &str,
+        table: &str,
+        start_version: u64,
+        end_version: u64,
+        rows: &[Value],
+    ) -> Result<()> {
+        validate_table_name(table)?;
+        let mut conn = self.conn.lock().unwrap();
+        let txn = conn.transaction()?;
+        txn.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {0} (
+                processor TEXT NOT NULL,
+                start_version INTEGER NOT NULL,
+                end_version INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS {0}_batch_index ON {0} (processor, start_version);",
+            table
+        ))?;
+        txn.execute(
+            &format!(
+                "DELETE FROM {} WHERE processor = ?1 AND start_version = ?2 AND end_version = ?3",
+                table
+            ),
+            params![processor, start_version as i64, end_version as i64],
+        )?;
+        {
+            let mut stmt = txn.prepare(&format!(
+                "INSERT INTO {} (processor, start_version, end_version, data) VALUES (?1, ?2, ?3, ?4)",
+                table
+            ))?;
+            for row in rows {
+                stmt.execute(params![
+                    processor,
+                    start_version as i64,
+                    end_version as i64,
+                    row.to_string()
+                ])?;
+            }
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    fn upsert_current_rows(
+        &self,
+        processor: &str,
+        table: &str,
+        key_columns: &[&str],
+        _start_version: u64,
+        _end_version: u64,
+        rows: &[Value],
+    ) -> Result<()> {
+        validate_table_name(table)?;
+        let mut conn = self.conn.lock().unwrap();
+        let txn = conn.transaction()?;
+        txn.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                processor TEXT NOT NULL,
+                key TEXT NOT NULL,
+                last_transaction_version INTEGER NOT NULL,
+                data TEXT NOT NULL,
+                PRIMARY KEY (processor, key)
+            )",
+            table
+        ))?;
+        {
+            let mut stmt = txn.prepare(&format!(
+                "INSERT INTO {0} (processor, key, last_transaction_version, data)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT (processor, key) DO UPDATE SET
+                    last_transaction_version = excluded.last_transaction_version,
+                    data = excluded.data
+                WHERE {0}.last_transaction_version <= excluded.last_transaction_version",
+                table
+            ))?;
+            for row in rows {
+                let key = key_columns
+                    .iter()
+                    .map(|column| {
+                        row.get(column)
+                            .cloned()
+                            .with_context(|| format!("Missing key column {} in {}", column, table))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let last_transaction_version = row
+                    .get("last_transaction_version")
+                    .and_then(Value::as_i64)
+                    .with_context(|| format!("Missing last_transaction_version in {}", table))?;
+                stmt.execute(params![
+                    processor,
+                    Value::Array(key).to_string(),
+                    last_transaction_version,
+                    row.to_string()
+                ])?;
+            }
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    fn checkpoint(&self, processor: &str, version: u64) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            &format!(
+                "INSERT INTO {0} (processor, version) VALUES (?1, ?2)
+                ON CONFLICT (processor) DO UPDATE SET version = MAX({0}.version, excluded.version)",
+                CHECKPOINTS_TABLE
+            ),
+            params![processor, version as i64],
+        )?;
+        Ok(())
+    }
+
+    fn last_checkpoint(&self, processor: &str) -> Result<Option<u64>> {
+        let version: Option<i64> = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                &format!(
+                    "SELECT version FROM {} WHERE processor = ?1",
+                    CHECKPOINTS_TABLE
+                ),
+                params![processor],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(version.map(|version| version as u64))
+    }
+
+    /// The `current_*` tables are left as is: writing the batches again upserts the same rows.
+    fn rewind(&self, processor: &str, version: u64) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tables = conn
+            .prepare(
+                "SELECT m.name FROM sqlite_master AS m WHERE m.type = 'table' AND EXISTS (
+                    SELECT 1 FROM pragma_table_info(m.name) AS c WHERE c.name = 'start_version'
+                )",
+            )?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        let txn = conn.transaction()?;
+        for table in tables {
+            txn.execute(
+                &format!(
+                    "DELETE FROM {} WHERE processor = ?1 AND start_version >= ?2",
+                    table
+                ),
+                params![processor, version as i64],
+            )?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_write_checkpoint_and_rewind() {
+        let sink = SqliteSink::open_in_memory().unwrap();
+        let batch = |version: u64| vec![json!({ "version": version })];
+
+        sink.write_rows("coin_processor", "coin_activities", 1, 1, &batch(1))
+            .unwrap();
+        sink.write_rows("coin_processor", "coin_activities", 2, 2, &batch(2))
+            .unwrap();
+        // Rewriting a batch replaces it
+        sink.write_rows("coin_processor", "coin_activities", 2, 2, &batch(2))
+            .unwrap();
+        assert_eq!(
+            sink.rows("coin_processor", "coin_activities").unwrap(),
+            vec![batch(1), batch(2)].concat()
+        );
+
+        assert_eq!(sink.last_checkpoint("coin_processor").unwrap(), None);
+        sink.checkpoint("coin_processor", 1).unwrap();
+        sink.checkpoint("coin_processor", 0).unwrap();
+        assert_eq!(sink.last_checkpoint("coin_processor").unwrap(), Some(1));
+
+        sink.rewind("coin_processor", 2).unwrap();
+        assert_eq!(
+            sink.rows("coin_processor", "coin_activities").unwrap(),
+            batch(1)
+        );
+        assert!(sink
+            .write_rows("coin_processor", "bad; DROP TABLE", 3, 3, &batch(3))
+            .is_err());
+    }
+
+    #[test]
+    fn test_upsert_current_rows() {
+        let sink = SqliteSink::open_in_memory().unwrap();
+        let balance = |owner: &str, amount: u64, version: u64| {
+            json!({
+                "owner_address": owner,
+                "amount": amount,
+                "last_transaction_version": version,
+            })
+        };
+        let upsert = |start_version: u64, rows: &[Value]| {
+            sink.upsert_current_rows(
+                "coin_processor",
+                "current_coin_balances",
+                &["owner_address"],
+                start_version,
+                start_version,
+                rows,
+            )
+        };
+
+        upsert(1, &[balance("0x1", 10, 1), balance("0x2", 20, 1)]).unwrap();
+        upsert(2, &[balance("0x1", 5, 2)]).unwrap();
+        // Rows older than the current one are ignored
+        upsert(1, &[balance("0x1", 10, 1)]).unwrap();
+        assert_eq!(
+            sink.current_rows("coin_processor", "current_coin_balances")
+                .unwrap(),
+            vec![balance("0x1", 5, 2), balance("0x2", 20, 1)]
+        );
+
+        // Rewinding keeps the current rows, which are upserted again
+        sink.rewind("coin_processor", 2).unwrap();
+        assert_eq!(
+            sink.current_rows("coin_processor", "current_coin_balances")
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(upsert(3, &[json!({ "owner_address": "0x3" })]).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Admin endpoints of the inspection service. Unlike the other endpoints these change the state
+//! of the node, so they require the bearer token configured in the InspectionServiceConfig.
+
+use aptos_config::config::NodeConfig;
+use aptos_logger::{prelude::*, Filter, FilterKind, Logger};
+use hyper::{header::AUTHORIZATION, Body, Method, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+// All admin endpoints are served under this prefix.
+pub const ADMIN_PATH_PREFIX: &str = "/admin/";
+
+// The endpoints to read and replace the logger filters.
+const LOG_FILTER_PATH: &str = "/admin/log_filter";
+const LOCAL_LOG_FILTER_PATH: &str = "/admin/log_filter/local";
+const REMOTE_LOG_FILTER_PATH: &str = "/admin/log_filter/remote";
+
+// The message displayed when no admin token is configured.
+const ADMIN_DISABLED_MESSAGE: &str =
+    "The admin endpoints are disabled! Set an admin_auth_token in the InspectionServiceConfig.";
+
+// The message displayed when the bearer token is missing or wrong.
+const UNAUTHORIZED_MESSAGE: &str = "Missing or invalid bearer token!";
+
+// The message displayed when the node wasn't started with a logger that can be updated.
+const LOGGER_NOT_AVAILABLE_MESSAGE: &str = "The logger filters can't be changed on this node!";
+
+/// The state of one logger filter
+#[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct LogFilterState {
+    /// The filter directives, e.g. `info,aptos_mempool=debug`
+    pub directives: String,
+    /// True if the filter was replaced through the admin endpoint
+    pub overridden: bool,
+}
+
+/// The state of the local and remote (telemetry) logger filters
+#[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct LogFilters {
+    pub local: LogFilterState,
+    pub remote: LogFilterState,
+}
+
+/// A request to replace a logger filter
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogFilterUpdate {
+    /// The new filter directives, in the same syntax as `RUST_LOG`
+    pub directives: String,
+    /// If set, the previous filter is restored after this many seconds
+    pub revert_after_secs: Option<u64>,
+}
+
+pub struct AdminService {
+    auth_token: Option<String>,
+    logger: Option<Arc<Logger>>,
+}
+
+impl AdminService {
+    pub fn new(node_config: &NodeConfig, logger: Option<Arc<Logger>>) -> Self {
+        let auth_token = node_config
+            .inspection_service
+            .admin_auth_token
+            .as_ref()
+            .and_then(|token| match token.read_token() {
+                Ok(token) => Some(token.trim().to_string()),
+                Err(error) => {
+                    error!(
+                        "Failed to read the admin auth token, the admin endpoints are disabled: {}",
+                        error
+                    );
+                    None
+                },
+            })
+            .filter(|token| !token.is_empty());
+
+        Self { auth_token, logger }
+    }
+
+    pub async fn serve(&self, req: Request<Body>, remote_addr: SocketAddr) -> Response<Body> {
+        let auth_token = match &self.auth_token {
+            Some(auth_token) => auth_token,
+            None => return text_response(StatusCode::FORBIDDEN, ADMIN_DISABLED_MESSAGE),
+        };
+        if !is_authorized(&req, auth_token) {
+            warn!(
+                remote_addr = %remote_addr,
+                path = req.uri().path(),
+                "Rejected an unauthorized admin request"
+            );
+            return text_response(StatusCode::UNAUTHORIZED, UNAUTHORIZED_MESSAGE);
+        }
+        let logger = match &self.logger {
+            Some(logger) => logger,
+            None => {
+                return text_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    LOGGER_NOT_AVAILABLE_MESSAGE,
+                )
+            },
+        };
+
+        let kind = match req.uri().path() {
+            LOCAL_LOG_FILTER_PATH => Some(FilterKind::Local),
+            REMOTE_LOG_FILTER_PATH => Some(FilterKind::Telemetry),
+            _ => None,
+        };
+        match (req.method(), req.uri().path(), kind) {
+            (&Method::GET, LOG_FILTER_PATH, _) => json_response(&LogFilters {
+                local: filter_state(logger, FilterKind::Local),
+                remote: filter_state(logger, FilterKind::Telemetry),
+            }),
+            (&Method::GET, _, Some(kind)) => json_response(&filter_state(logger, kind)),
+            (&Method::PUT, _, Some(kind)) => {
+                update_log_filter(logger.clone(), kind, req, remote_addr).await
+            },
+            (&Method::DELETE, _, Some(kind)) => {
+                if logger.reset_filter(kind, None) {
+                    warn!(
+                        remote_addr = %remote_addr,
+                        filter = ?kind,
+                        directives = logger.filter_directives(kind),
+                        "Log filter reset through the admin endpoint"
+                    );
+                }
+                json_response(&filter_state(logger, kind))
+            },
+            _ => text_response(StatusCode::NOT_FOUND, ""),
+        }
+    }
+}
+
+/// Replaces a filter with the directives in the request body, and schedules the revert
+async fn update_log_filter(
+    logger: Arc<Logger>,
+    kind: FilterKind,
+    req: Request<Body>,
+    remote_addr: SocketAddr,
+) -> Response<Body> {
+    let body = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => body,
+        Err(error) => {
+            return text_response(
+                StatusCode::BAD_REQUEST,
+                &format!("Failed to read the request body: {}", error),
+            )
+        },
+    };
+    let update: LogFilterUpdate = match serde_json::from_slice(&body) {
+        Ok(update) => update,
+        Err(error) => {
+            return text_response(
+                StatusCode::BAD_REQUEST,
+                &format!("Invalid log filter update: {}", error),
+            )
+        },
+    };
+    let filter: Filter = match update.directives.parse() {
+        Ok(filter) => filter,
+        Err(_) => {
+            return text_response(
+                StatusCode::BAD_REQUEST,
+                &format!("Invalid filter directives: {}", update.directives),
+            )
+        },
+    };
+
+    // Audit before replacing the filter, as the new filter may hide the log
+    warn!(
+        remote_addr = %remote_addr,
+        filter = ?kind,
+        previous_directives = logger.filter_directives(kind),
+        directives = filter.to_string(),
+        revert_after_secs = update.revert_after_secs,
+        "Log filter overridden through the admin endpoint"
+    );
+    let id = logger.override_filter(kind, filter);
+
+    if let Some(revert_after_secs) = update.revert_after_secs {
+        let logger = logger.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(revert_after_secs)).await;
+            if logger.reset_filter(kind, Some(id)) {
+                warn!(
+                    filter = ?kind,
+                    directives = logger.filter_directives(kind),
+                    "Log filter override reverted after {} seconds",
+                    revert_after_secs
+                );
+            }
+        });
+    }
+
+    json_response(&filter_state(&logger, kind))
+}
+
+fn filter_state(logger: &Logger, kind: FilterKind) -> LogFilterState {
+    LogFilterState {
+        directives: logger.filter_directives(kind),
+        overridden: logger.is_filter_overridden(kind),
+    }
+}
+
+/// Returns true iff the request carries the bearer token. The comparison doesn't stop at the
+/// first mismatch, so the response time doesn't leak how much of the token was guessed.
+fn is_authorized(req: &Request<Body>, auth_token: &str) -> bool {
+    let provided = match req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        Some(provided) => provided.trim().as_bytes(),
+        None => return false,
+    };
+    let expected = auth_token.as_bytes();
+    provided.len() == expected.len()
+        && provided
+            .iter()
+            .zip(expected)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn text_response(status: StatusCode, message: &str) -> Response<Body> {
+    let mut resp = Response::new(Body::from(message.to_string()));
+    *resp.status_mut() = status;
+    resp
+}
+
+fn json_response<T: Serialize>(value: &T) -> Response<Body> {
+    Response::new(Body::from(serde_json::to_string(value).unwrap()))
+}
@@ -1,10 +1,17 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{gather_metrics, json_encoder::JsonEncoder, NUM_METRICS};
+use crate::{
+    admin::{AdminService, ADMIN_PATH_PREFIX},
+    gather_metrics,
+    json_encoder::JsonEncoder,
+    NUM_METRICS,
+};
 use aptos_build_info::build_information;
 use aptos_config::config::NodeConfig;
+use aptos_logger::Logger;
 use hyper::{
+    server::conn::AddrStream,
     service::{make_service_fn, service_fn},
     Body, Method, Request, Response, Server, StatusCode,
 };
@@ -16,6 +23,7 @@ use std::{
     collections::HashMap,
     convert::Infallible,
     net::{SocketAddr, ToSocketAddrs},
+    sync::Arc,
     thread,
 };
 
@@ -23,6 +31,19 @@ use std::{
 const DISABLED_ENDPOINT_MESSAGE: &str =
     "This endpoint is disabled! Enable it in the InspectionServiceConfig.";
 
+// The message displayed when consensus is not running on this node.
+const CONSENSUS_NOT_RUNNING_MESSAGE: &str = "Consensus is not running on this node!";
+
+// The query parameter used to request a DOT graph instead of JSON.
+const DOT_FORMAT_QUERY: &str = "format=dot";
+
+/// Returns true iff the request asks for the response to be rendered as a DOT graph
+fn requests_dot_format(req: &Request<Body>) -> bool {
+    req.uri().query().map_or(false, |query| {
+        query.split('&').any(|q| q == DOT_FORMAT_QUERY)
+    })
+}
+
 pub fn encode_metrics(encoder: impl Encoder) -> Vec<u8> {
     let metric_families = gather_metrics();
     let mut buffer = vec![];
@@ -81,10 +102,17 @@ pub fn get_all_metrics() -> HashMap<String, String> {
     get_metrics(all_metric_families)
 }
 
-async fn serve_requests(
+pub(crate) async fn serve_requests(
     req: Request<Body>,
     node_config: NodeConfig,
+    admin_service: Arc<AdminService>,
+    remote_addr: SocketAddr,
 ) -> Result<Response<Body>, hyper::Error> {
+    // Admin endpoints check the auth token before serving anything
+    if req.uri().path().starts_with(ADMIN_PATH_PREFIX) {
+        return Ok(admin_service.serve(req, remote_addr).await);
+    }
+
     let mut resp = Response::new(Body::empty());
     match (req.method(), req.uri().path()) {
         // Expose the node configuration
@@ -99,6 +127,28 @@ async fn serve_requests(
                 *resp.body_mut() = Body::from(DISABLED_ENDPOINT_MESSAGE);
             }
         },
+        // Expose the live consensus state (as JSON, or as a DOT graph if requested)
+        (&Method::GET, "/consensus_state") => {
+            if node_config.inspection_service.expose_consensus_state {
+                match aptos_consensus::introspection::get_consensus_state_snapshot() {
+                    Some(consensus_state) => {
+                        if requests_dot_format(&req) {
+                            *resp.body_mut() = Body::from(consensus_state.to_dot());
+                        } else {
+                            let encoded_state =
+                                serde_json::to_string_pretty(&consensus_state).unwrap();
+                            *resp.body_mut() = Body::from(encoded_state);
+                        }
+                    },
+                    None => {
+                        *resp.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+                        *resp.body_mut() = Body::from(CONSENSUS_NOT_RUNNING_MESSAGE);
+                    },
+                }
+            } else {
+                *resp.body_mut() = Body::from(DISABLED_ENDPOINT_MESSAGE);
+            }
+        },
         // Exposes JSON encoded metrics
         (&Method::GET, "/json_metrics") => {
             let encoder = JsonEncoder;
@@ -138,7 +188,9 @@ async fn serve_requests(
     Ok(resp)
 }
 
-pub fn start_inspection_service(node_config: NodeConfig) {
+/// Starts the inspection service. The logger is needed to change the log filters through the
+/// admin endpoints.
+pub fn start_inspection_service(node_config: NodeConfig, logger: Option<Arc<Logger>>) {
     // Fetch the service port and address
     let service_port = node_config.inspection_service.port;
     let service_address = node_config.inspection_service.address.clone();
@@ -156,12 +208,20 @@ pub fn start_inspection_service(node_config: NodeConfig) {
         .unwrap();
 
     // Spawn the server
+    let admin_service = Arc::new(AdminService::new(&node_config, logger));
     thread::spawn(move || {
-        let make_service = make_service_fn(move |_conn| {
+        let make_service = make_service_fn(move |conn: &AddrStream| {
             let node_config = node_config.clone();
+            let admin_service = admin_service.clone();
+            let remote_addr = conn.remote_addr();
             async move {
                 Ok::<_, Infallible>(service_fn(move |request| {
-                    serve_requests(request, node_config.clone())
+                    serve_requests(
+                        request,
+                        node_config.clone(),
+                        admin_service.clone(),
+                        remote_addr,
+                    )
                 }))
             }
         });
@@ -3,6 +3,7 @@
 
 #![forbid(unsafe_code)]
 
+pub mod admin;
 pub mod inspection_client;
 pub mod inspection_service;
 mod json_encoder;
@@ -0,0 +1,149 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    admin::{AdminService, LogFilterState, LogFilters},
+    inspection_service::serve_requests,
+};
+use aptos_config::config::{NodeConfig, Token};
+use aptos_logger::{FilterKind, Logger};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+
+const AUTH_TOKEN: &str = "admin-token";
+
+fn admin_service(auth_token: Option<&str>, logger: Option<Arc<Logger>>) -> AdminService {
+    let mut node_config = NodeConfig::default();
+    node_config.inspection_service.admin_auth_token =
+        auth_token.map(|token| Token::FromConfig(token.to_string()));
+    AdminService::new(&node_config, logger)
+}
+
+async fn send(
+    service: &AdminService,
+    method: Method,
+    path: &str,
+    auth_token: &str,
+    body: &str,
+) -> Response<Body> {
+    let request = Request::builder()
+        .method(method)
+        .uri(path)
+        .header("Authorization", format!("Bearer {}", auth_token))
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    service
+        .serve(request, SocketAddr::from((Ipv4Addr::LOCALHOST, 9101)))
+        .await
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: Response<Body>) -> T {
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn admin_requires_token() {
+    let service = admin_service(None, None);
+    let response = send(&service, Method::GET, "/admin/log_filter", AUTH_TOKEN, "").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let service = admin_service(Some(AUTH_TOKEN), None);
+    let response = send(
+        &service,
+        Method::GET,
+        "/admin/log_filter",
+        "wrong-token",
+        "",
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = send(&service, Method::GET, "/admin/log_filter", AUTH_TOKEN, "").await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn override_and_reset_log_filter() {
+    let logger = Logger::builder().is_async(false).build();
+    let configured = logger.filter_directives(FilterKind::Local);
+    let service = admin_service(Some(AUTH_TOKEN), Some(logger.clone()));
+
+    let response = send(
+        &service,
+        Method::PUT,
+        "/admin/log_filter/local",
+        AUTH_TOKEN,
+        r#"{"directives": "info,crate1=loud"}"#,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = send(
+        &service,
+        Method::PUT,
+        "/admin/log_filter/local",
+        AUTH_TOKEN,
+        r#"{"directives": "info,aptos_mempool=debug"}"#,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        body_json::<LogFilterState>(response).await,
+        LogFilterState {
+            directives: "info,aptos_mempool=debug".into(),
+            overridden: true,
+        }
+    );
+
+    let response = send(&service, Method::GET, "/admin/log_filter", AUTH_TOKEN, "").await;
+    let filters = body_json::<LogFilters>(response).await;
+    assert!(filters.local.overridden);
+    assert!(!filters.remote.overridden);
+
+    let response = send(
+        &service,
+        Method::DELETE,
+        "/admin/log_filter/local",
+        AUTH_TOKEN,
+        "",
+    )
+    .await;
+    assert_eq!(
+        body_json::<LogFilterState>(response).await,
+        LogFilterState {
+            directives: configured,
+            overridden: false,
+        }
+    );
+}
+
+#[tokio::test]
+async fn configuration_hides_token() {
+    let mut node_config = NodeConfig::default();
+    node_config.inspection_service.expose_configuration = true;
+    node_config.inspection_service.admin_auth_token =
+        Some(Token::FromConfig(AUTH_TOKEN.to_string()));
+    let service = Arc::new(AdminService::new(&node_config, None));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/configuration")
+        .body(Body::empty())
+        .unwrap();
+    let response = serve_requests(
+        request,
+        node_config,
+        service,
+        SocketAddr::from((Ipv4Addr::LOCALHOST, 9101)),
+    )
+    .await
+    .unwrap();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body = std::str::from_utf8(&body).unwrap();
+    assert!(body.contains("admin_auth_token"));
+    assert!(!body.contains(AUTH_TOKEN));
+}
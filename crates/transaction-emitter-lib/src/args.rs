@@ -1,6 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::{EntryPoints, TransactionType};
 use anyhow::{bail, format_err, Result};
 use aptos::common::types::EncodingType;
 use aptos_config::keys::ConfigKey;
@@ -84,6 +85,41 @@ pub enum TransactionTypeArg {
     CreateNewResource,
 }
 
+impl TransactionTypeArg {
+    pub fn materialize(&self, invalid_transaction_ratio: usize) -> TransactionType {
+        match self {
+            TransactionTypeArg::CoinTransfer => TransactionType::CoinTransfer {
+                invalid_transaction_ratio,
+                sender_use_account_pool: false,
+            },
+            TransactionTypeArg::AccountGeneration => TransactionType::default_account_generation(),
+            TransactionTypeArg::AccountGenerationLargePool => TransactionType::AccountGeneration {
+                add_created_accounts_to_pool: true,
+                max_account_working_set: 50_000_000,
+                creation_balance: 200_000_000,
+            },
+            TransactionTypeArg::NftMintAndTransfer => TransactionType::NftMintAndTransfer,
+            TransactionTypeArg::PublishPackage => TransactionType::PublishPackage {
+                use_account_pool: false,
+            },
+            TransactionTypeArg::CustomFunctionLargeModuleWorkingSet => {
+                TransactionType::CallCustomModules {
+                    entry_point: EntryPoints::Nop,
+                    num_modules: 1000,
+                    use_account_pool: false,
+                }
+            },
+            TransactionTypeArg::CreateNewResource => TransactionType::CallCustomModules {
+                entry_point: EntryPoints::BytesMakeOrChange {
+                    data_length: Some(32),
+                },
+                num_modules: 1,
+                use_account_pool: true,
+            },
+        }
+    }
+}
+
 impl Default for TransactionTypeArg {
     fn default() -> Self {
         TransactionTypeArg::CoinTransfer
@@ -7,7 +7,7 @@ mod args;
 mod cluster;
 pub mod emitter;
 mod instance;
-mod transaction_generator;
+pub mod transaction_generator;
 mod wrappers;
 
 // These are the top level things you should need to run the emitter.
@@ -20,4 +20,6 @@ pub use emitter::{
     EmitJob, EmitJobMode, EmitJobRequest, EmitModeParams, TransactionType, TxnEmitter,
 };
 pub use transaction_generator::EntryPoints;
-pub use wrappers::{emit_transactions, emit_transactions_with_cluster};
+pub use wrappers::{
+    create_transaction_mix_per_phase, emit_transactions, emit_transactions_with_cluster,
+};
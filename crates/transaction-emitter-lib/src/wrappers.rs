@@ -6,7 +6,7 @@ use crate::{
     cluster::Cluster,
     emitter::{stats::TxnStats, EmitJobMode, EmitJobRequest, TxnEmitter},
     instance::Instance,
-    EntryPoints, TransactionType, TransactionTypeArg,
+    TransactionType, TransactionTypeArg,
 };
 use anyhow::{Context, Result};
 use aptos_sdk::transaction_builder::TransactionFactory;
@@ -40,60 +40,79 @@ pub async fn emit_transactions_with_cluster(
         StdRng::from_entropy(),
     );
 
-    let arg_transaction_types = args
-        .transaction_type
+    let transaction_mix_per_phase = create_transaction_mix_per_phase(
+        &args.transaction_type,
+        &args.transaction_weights,
+        &args.transaction_phases,
+        args.invalid_tx,
+    );
+
+    let mut emit_job_request =
+        EmitJobRequest::new(cluster.all_instances().map(Instance::rest_client).collect())
+            .mode(emitter_mode)
+            .transaction_mix_per_phase(transaction_mix_per_phase)
+            .txn_expiration_time_secs(args.txn_expiration_time_secs)
+            .delay_after_minting(Duration::from_secs(args.delay_after_minting.unwrap_or(0)))
+            .gas_price(aptos_global_constants::GAS_UNIT_PRICE);
+    if reuse_accounts {
+        emit_job_request = emit_job_request.reuse_accounts();
+    }
+    if let Some(max_transactions_per_account) = args.max_transactions_per_account {
+        emit_job_request =
+            emit_job_request.max_transactions_per_account(max_transactions_per_account);
+    }
+    if let Some(expected_max_txns) = args.expected_max_txns {
+        emit_job_request = emit_job_request.expected_max_txns(expected_max_txns);
+    }
+    if let Some(expected_gas_per_txn) = args.expected_gas_per_txn {
+        emit_job_request = emit_job_request.expected_gas_per_txn(expected_gas_per_txn);
+    }
+    if !cluster.coin_source_is_root {
+        emit_job_request = emit_job_request.prompt_before_spending();
+    }
+    let stats = emitter
+        .emit_txn_for_with_stats(
+            &mut coin_source_account,
+            emit_job_request,
+            duration,
+            (args.duration / 5).clamp(1, 10),
+        )
+        .await?;
+    Ok(stats)
+}
+
+/// Groups the given transaction types into the mix of each phase, using the weights and phases
+/// given in the same order (every type has weight 1 and is in phase 0 if none are given).
+pub fn create_transaction_mix_per_phase(
+    transaction_types: &[TransactionTypeArg],
+    transaction_weights: &[usize],
+    transaction_phases: &[usize],
+    invalid_transaction_ratio: usize,
+) -> Vec<Vec<(TransactionType, usize)>> {
+    let arg_transaction_types = transaction_types
         .iter()
-        .map(|t| match t {
-            TransactionTypeArg::CoinTransfer => TransactionType::CoinTransfer {
-                invalid_transaction_ratio: args.invalid_tx,
-                sender_use_account_pool: false,
-            },
-            TransactionTypeArg::AccountGeneration => TransactionType::default_account_generation(),
-            TransactionTypeArg::AccountGenerationLargePool => TransactionType::AccountGeneration {
-                add_created_accounts_to_pool: true,
-                max_account_working_set: 50_000_000,
-                creation_balance: 200_000_000,
-            },
-            TransactionTypeArg::NftMintAndTransfer => TransactionType::NftMintAndTransfer,
-            TransactionTypeArg::PublishPackage => TransactionType::PublishPackage {
-                use_account_pool: false,
-            },
-            TransactionTypeArg::CustomFunctionLargeModuleWorkingSet => {
-                TransactionType::CallCustomModules {
-                    entry_point: EntryPoints::Nop,
-                    num_modules: 1000,
-                    use_account_pool: false,
-                }
-            },
-            TransactionTypeArg::CreateNewResource => TransactionType::CallCustomModules {
-                entry_point: EntryPoints::BytesMakeOrChange {
-                    data_length: Some(32),
-                },
-                num_modules: 1,
-                use_account_pool: true,
-            },
-        })
+        .map(|t| t.materialize(invalid_transaction_ratio))
         .collect::<Vec<_>>();
 
-    let arg_transaction_weights = if args.transaction_weights.is_empty() {
+    let arg_transaction_weights = if transaction_weights.is_empty() {
         vec![1; arg_transaction_types.len()]
     } else {
         assert_eq!(
-            args.transaction_weights.len(),
+            transaction_weights.len(),
             arg_transaction_types.len(),
             "Transaction types and weights need to be the same length"
         );
-        args.transaction_weights.clone()
+        transaction_weights.to_vec()
     };
-    let arg_transaction_phases = if args.transaction_phases.is_empty() {
+    let arg_transaction_phases = if transaction_phases.is_empty() {
         vec![0; arg_transaction_types.len()]
     } else {
         assert_eq!(
-            args.transaction_phases.len(),
+            transaction_phases.len(),
             arg_transaction_types.len(),
             "Transaction types and phases need to be the same length"
         );
-        args.transaction_phases.clone()
+        transaction_phases.to_vec()
     };
 
     let mut transaction_mix_per_phase: Vec<Vec<(TransactionType, usize)>> = Vec::new();
@@ -115,37 +134,5 @@ pub async fn emit_transactions_with_cluster(
             .unwrap()
             .push((transaction_type, weight));
     }
-
-    let mut emit_job_request =
-        EmitJobRequest::new(cluster.all_instances().map(Instance::rest_client).collect())
-            .mode(emitter_mode)
-            .transaction_mix_per_phase(transaction_mix_per_phase)
-            .txn_expiration_time_secs(args.txn_expiration_time_secs)
-            .delay_after_minting(Duration::from_secs(args.delay_after_minting.unwrap_or(0)))
-            .gas_price(aptos_global_constants::GAS_UNIT_PRICE);
-    if reuse_accounts {
-        emit_job_request = emit_job_request.reuse_accounts();
-    }
-    if let Some(max_transactions_per_account) = args.max_transactions_per_account {
-        emit_job_request =
-            emit_job_request.max_transactions_per_account(max_transactions_per_account);
-    }
-    if let Some(expected_max_txns) = args.expected_max_txns {
-        emit_job_request = emit_job_request.expected_max_txns(expected_max_txns);
-    }
-    if let Some(expected_gas_per_txn) = args.expected_gas_per_txn {
-        emit_job_request = emit_job_request.expected_gas_per_txn(expected_gas_per_txn);
-    }
-    if !cluster.coin_source_is_root {
-        emit_job_request = emit_job_request.prompt_before_spending();
-    }
-    let stats = emitter
-        .emit_txn_for_with_stats(
-            &mut coin_source_account,
-            emit_job_request,
-            duration,
-            (args.duration / 5).clamp(1, 10),
-        )
-        .await?;
-    Ok(stats)
+    transaction_mix_per_phase
 }
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aptos_sdk::{move_types::account_address::AccountAddress, types::LocalAccount};
-use rand::{rngs::StdRng, RngCore, SeedableRng};
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
 use std::{collections::VecDeque, sync::mpsc};
 
 type Seed = [u8; 32];
@@ -66,6 +66,9 @@ pub struct AccountCache {
 }
 
 impl AccountCache {
+    /// Fraction of the accounts that make up the hotspot, i.e. the accounts picked with
+    /// `hotspot_probability` when it's given.
+    const HOTSPOT_FRACTION: f64 = 0.01;
     const SEED: Seed = [1; 32];
 
     pub fn new(generator: AccountGenerator) -> Self {
@@ -96,14 +99,51 @@ impl AccountCache {
         &mut self.accounts[index]
     }
 
-    pub fn get_random_transfer(&mut self) -> (&mut LocalAccount, AccountAddress) {
-        let indices = rand::seq::index::sample(&mut self.rng, self.accounts.len(), 2);
-        let sender_idx = indices.index(0);
-        let receiver_idx = indices.index(1);
+    /// Same as `get_random`, but with probability `hotspot_probability` the account is picked
+    /// among the hotspot accounts only, so that transactions contend on them.
+    pub fn get_random_with_hotspot(
+        &mut self,
+        hotspot_probability: Option<f32>,
+    ) -> &mut LocalAccount {
+        let index = match hotspot_probability {
+            None => rand::seq::index::sample(&mut self.rng, self.accounts.len(), 1).index(0),
+            Some(probability) => self.random_index_with_hotspot(probability),
+        };
+
+        &mut self.accounts[index]
+    }
+
+    pub fn get_random_transfer(
+        &mut self,
+        hotspot_probability: Option<f32>,
+    ) -> (&mut LocalAccount, AccountAddress) {
+        let (sender_idx, receiver_idx) = match hotspot_probability {
+            None => {
+                let indices = rand::seq::index::sample(&mut self.rng, self.accounts.len(), 2);
+                (indices.index(0), indices.index(1))
+            },
+            Some(probability) => loop {
+                let sender_idx = self.random_index_with_hotspot(probability);
+                let receiver_idx = self.random_index_with_hotspot(probability);
+                if sender_idx != receiver_idx {
+                    break (sender_idx, receiver_idx);
+                }
+            },
+        };
 
         let receiver = self.accounts[receiver_idx].address();
         let sender = &mut self.accounts[sender_idx];
 
         (sender, receiver)
     }
+
+    fn random_index_with_hotspot(&mut self, hotspot_probability: f32) -> usize {
+        let num_hotspot_accounts = ((self.accounts.len() as f64 * Self::HOTSPOT_FRACTION) as usize)
+            .clamp(2, self.accounts.len());
+        if self.rng.gen_bool(hotspot_probability as f64) {
+            self.rng.gen_range(0, num_hotspot_accounts)
+        } else {
+            self.rng.gen_range(0, self.accounts.len())
+        }
+    }
 }
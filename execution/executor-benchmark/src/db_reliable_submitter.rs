@@ -0,0 +1,103 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::benchmark_transaction::BenchmarkTransaction;
+use anyhow::{bail, Result};
+use aptos_crypto::HashValue;
+use aptos_state_view::account_with_state_view::AsAccountWithStateView;
+use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReaderWriter};
+use aptos_transaction_emitter_lib::transaction_generator::TransactionExecutor;
+use aptos_types::{
+    account_address::AccountAddress,
+    account_view::AccountView,
+    transaction::{SignedTransaction, Transaction},
+};
+use async_trait::async_trait;
+use std::{
+    iter::once,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+const COMMIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Executes the transactions the transaction emitter's generators need to set themselves up
+/// (e.g. publishing modules, creating NFT collections) by sending them to the benchmark pipeline
+/// as a block, and waiting for them to be committed to the DB.
+pub struct DbReliableTransactionSubmitter {
+    pub db: DbReaderWriter,
+    pub block_sender: mpsc::SyncSender<Vec<BenchmarkTransaction>>,
+}
+
+impl DbReliableTransactionSubmitter {
+    async fn wait_for_commit(&self, txn: &SignedTransaction) -> Result<bool> {
+        let start = Instant::now();
+        while start.elapsed() < COMMIT_TIMEOUT {
+            if self.query_sequence_number(txn.sender()).await? > txn.sequence_number() {
+                return Ok(true);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        Ok(false)
+    }
+}
+
+#[async_trait]
+impl TransactionExecutor for DbReliableTransactionSubmitter {
+    async fn get_account_balance(&self, account_address: AccountAddress) -> Result<u64> {
+        let db_state_view = self.db.reader.latest_state_checkpoint_view()?;
+        let account_state_view = db_state_view.as_account_with_state_view(&account_address);
+        Ok(account_state_view
+            .get_coin_store_resource()?
+            .map_or(0, |coin_store| coin_store.coin()))
+    }
+
+    async fn query_sequence_number(&self, account_address: AccountAddress) -> Result<u64> {
+        let db_state_view = self.db.reader.latest_state_checkpoint_view()?;
+        let account_state_view = db_state_view.as_account_with_state_view(&account_address);
+        Ok(account_state_view
+            .get_account_resource()?
+            .map_or(0, |account_resource| account_resource.sequence_number()))
+    }
+
+    async fn execute_transactions(&self, txns: &[SignedTransaction]) -> Result<()> {
+        self.execute_transactions_with_counter(txns, &AtomicUsize::new(0))
+            .await
+    }
+
+    async fn execute_transactions_with_counter(
+        &self,
+        txns: &[SignedTransaction],
+        failure_counter: &AtomicUsize,
+    ) -> Result<()> {
+        self.block_sender.send(
+            txns.iter()
+                .map(|txn| Transaction::UserTransaction(txn.clone()).into())
+                .chain(once(
+                    Transaction::StateCheckpoint(HashValue::random()).into(),
+                ))
+                .collect(),
+        )?;
+
+        let mut num_failed = 0;
+        for txn in txns {
+            if !self.wait_for_commit(txn).await? {
+                num_failed += 1;
+            }
+        }
+        if num_failed > 0 {
+            failure_counter.fetch_add(num_failed, Ordering::Relaxed);
+            bail!(
+                "{} out of {} transactions were not committed within {:?}.",
+                num_failed,
+                txns.len(),
+                COMMIT_TIMEOUT,
+            );
+        }
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@
 mod account_generator;
 pub mod benchmark_transaction;
 pub mod db_generator;
+mod db_reliable_submitter;
 pub mod fake_executor;
 mod metrics;
 pub mod pipeline;
@@ -23,6 +24,7 @@ use aptos_jellyfish_merkle::metrics::{
     APTOS_JELLYFISH_INTERNAL_ENCODED_BYTES, APTOS_JELLYFISH_LEAF_ENCODED_BYTES,
 };
 use aptos_storage_interface::DbReaderWriter;
+use aptos_transaction_emitter_lib::TransactionType;
 use std::{fs, path::Path};
 
 pub fn init_db_and_executor<V>(
@@ -35,7 +37,7 @@ where
         AptosDB::open(
             &config.storage.dir(),
             false, /* readonly */
-            config.storage.storage_pruner_config,
+            config.storage.storage_pruner_config.clone(),
             RocksdbConfigs::default(),
             false,
             config.storage.buffered_state_target_items,
@@ -59,10 +61,15 @@ fn create_checkpoint(source_dir: impl AsRef<Path>, checkpoint_dir: impl AsRef<Pa
     AptosDB::create_checkpoint(source_dir, checkpoint_dir).expect("db checkpoint creation fails.");
 }
 
-/// Runs the benchmark with given parameters.
+/// Runs the benchmark with given parameters. Native P2P transfers are run unless a
+/// `transaction_mix_per_phase` is given, in which case each of its phases runs `num_blocks`
+/// blocks generated by the transaction emitter's generators.
 pub fn run_benchmark<V>(
     block_size: usize,
-    num_transfer_blocks: usize,
+    num_blocks: usize,
+    transaction_mix_per_phase: Option<Vec<Vec<(TransactionType, usize)>>>,
+    transactions_per_sender: usize,
+    hotspot_probability: Option<f32>,
     source_dir: impl AsRef<Path>,
     checkpoint_dir: impl AsRef<Path>,
     verify_sequence_numbers: bool,
@@ -88,7 +95,22 @@ pub fn run_benchmark<V>(
         source_dir,
         version,
     );
-    generator.run_transfer(block_size, num_transfer_blocks);
+    if let Some(transaction_mix_per_phase) = transaction_mix_per_phase {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create tokio runtime.");
+        runtime.block_on(generator.run_workload(
+            db.clone(),
+            block_size,
+            num_blocks,
+            &transaction_mix_per_phase,
+            transactions_per_sender,
+            hotspot_probability,
+        ));
+    } else {
+        generator.run_transfer(block_size, num_blocks, hotspot_probability);
+    }
     generator.drop_sender();
     pipeline.join();
 
@@ -189,6 +211,7 @@ fn add_accounts_impl<V>(
 mod tests {
     use aptos_config::config::NO_OP_STORAGE_PRUNER_CONFIG;
     use aptos_temppath::TempPath;
+    use aptos_transaction_emitter_lib::TransactionTypeArg;
     use aptos_vm::AptosVM;
 
     #[test]
@@ -206,9 +229,42 @@ mod tests {
             true,
         );
 
+        super::run_benchmark::<AptosVM>(
+            5,    /* block_size */
+            5,    /* num_blocks */
+            None, /* transaction_mix_per_phase */
+            1,    /* transactions_per_sender */
+            None, /* hotspot_probability */
+            storage_dir.as_ref(),
+            checkpoint_dir,
+            true,
+            NO_OP_STORAGE_PRUNER_CONFIG,
+        );
+    }
+
+    #[test]
+    fn test_benchmark_workload() {
+        let storage_dir = TempPath::new();
+        let checkpoint_dir = TempPath::new();
+
+        crate::db_generator::run::<AptosVM>(
+            25,            /* num_accounts */
+            1_000_000_000, /* init_account_balance */
+            5,             /* block_size */
+            storage_dir.as_ref(),
+            NO_OP_STORAGE_PRUNER_CONFIG, /* prune_window */
+            true,
+        );
+
         super::run_benchmark::<AptosVM>(
             5, /* block_size */
-            5, /* num_transfer_blocks */
+            2, /* num_blocks */
+            Some(vec![
+                vec![(TransactionTypeArg::CoinTransfer.materialize(0), 1)],
+                vec![(TransactionTypeArg::PublishPackage.materialize(0), 1)],
+            ]),
+            2,         /* transactions_per_sender */
+            Some(0.5), /* hotspot_probability */
             storage_dir.as_ref(),
             checkpoint_dir,
             true,
@@ -9,7 +9,9 @@ use aptos_executor_benchmark::{
     benchmark_transaction::BenchmarkTransaction, fake_executor::FakeExecutor,
 };
 use aptos_push_metrics::MetricsPusher;
+use aptos_transaction_emitter_lib::{create_transaction_mix_per_phase, TransactionTypeArg};
 use aptos_vm::AptosVM;
+use clap::ArgEnum;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -65,11 +67,52 @@ impl PrunerOpt {
                 prune_window: self.ledger_prune_window,
                 batch_size: self.ledger_pruning_batch_size,
                 user_pruning_window_offset: 0,
+                archive_dir: None,
+                archive_chunk_bytes: 0,
             },
         }
     }
 }
 
+#[derive(Debug, StructOpt)]
+struct WorkloadOpt {
+    /// Transaction types of the transaction emitter to run instead of native P2P transfers.
+    #[structopt(long, parse(try_from_str = parse_transaction_type))]
+    transaction_type: Vec<TransactionTypeArg>,
+
+    #[structopt(long)]
+    transaction_weights: Vec<usize>,
+
+    /// Phase of each transaction type, the phases run one after another with `blocks` blocks
+    /// each, and their TPS and speculative aborts are reported separately.
+    #[structopt(long)]
+    transaction_phases: Vec<usize>,
+
+    #[structopt(long, default_value = "1")]
+    transactions_per_sender: usize,
+
+    /// Probability that a sender (and receiver, for P2P transfers) is picked among the 1% of
+    /// hotspot accounts rather than all of them, to create contention.
+    #[structopt(long, parse(try_from_str = parse_hotspot_probability))]
+    hotspot_probability: Option<f32>,
+}
+
+fn parse_transaction_type(s: &str) -> Result<TransactionTypeArg, String> {
+    TransactionTypeArg::from_str(s, true /* ignore_case */)
+}
+
+fn parse_hotspot_probability(s: &str) -> Result<f32, String> {
+    let probability = s.parse::<f32>().map_err(|e| e.to_string())?;
+    if (0.0..=1.0).contains(&probability) {
+        Ok(probability)
+    } else {
+        Err(format!(
+            "Hotspot probability must be between 0 and 1, got {}",
+            probability
+        ))
+    }
+}
+
 #[derive(Debug, StructOpt)]
 struct Opt {
     #[structopt(long, default_value = "10000")]
@@ -126,7 +169,7 @@ enum Command {
         #[structopt(
             long,
             default_value = "1000",
-            about = "number of transfer blocks to run"
+            about = "number of blocks to run (per phase, if transaction types are given)"
         )]
         blocks: usize,
 
@@ -135,6 +178,9 @@ enum Command {
 
         #[structopt(long, parse(from_os_str))]
         checkpoint_dir: PathBuf,
+
+        #[structopt(flatten)]
+        workload_opt: WorkloadOpt,
     },
     AddAccounts {
         #[structopt(long, parse(from_os_str))]
@@ -174,10 +220,24 @@ where
             blocks,
             data_dir,
             checkpoint_dir,
+            workload_opt,
         } => {
+            let transaction_mix_per_phase = if workload_opt.transaction_type.is_empty() {
+                None
+            } else {
+                Some(create_transaction_mix_per_phase(
+                    &workload_opt.transaction_type,
+                    &workload_opt.transaction_weights,
+                    &workload_opt.transaction_phases,
+                    0, /* invalid_transaction_ratio */
+                ))
+            };
             aptos_executor_benchmark::run_benchmark::<E>(
                 opt.block_size,
                 blocks,
+                transaction_mix_per_phase,
+                workload_opt.transactions_per_sender,
+                workload_opt.hotspot_probability,
                 data_dir,
                 checkpoint_dir,
                 opt.verify_sequence_numbers,
@@ -4,11 +4,18 @@
 use crate::{
     account_generator::{AccountCache, AccountGenerator},
     benchmark_transaction::{AccountCreationInfo, BenchmarkTransaction, ExtraInfo, TransferInfo},
+    db_reliable_submitter::DbReliableTransactionSubmitter,
 };
+use aptos_block_executor::counters::SPECULATIVE_ABORT_COUNT;
 use aptos_crypto::{ed25519::Ed25519PrivateKey, HashValue};
 use aptos_sdk::{transaction_builder::TransactionFactory, types::LocalAccount};
 use aptos_state_view::account_with_state_view::AsAccountWithStateView;
 use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReader, DbReaderWriter};
+use aptos_transaction_emitter_lib::{
+    emitter::stats::DynamicStatsTracking,
+    transaction_generator::{create_txn_generator_creator, TransactionGenerator as _},
+    TransactionType,
+};
 use aptos_types::{
     account_address::AccountAddress,
     account_config::aptos_test_root_address,
@@ -27,10 +34,13 @@ use std::{
     iter::once,
     path::Path,
     sync::{mpsc, Arc},
+    time::{Duration, Instant},
 };
 
 const META_FILENAME: &str = "metadata.toml";
 const MAX_ACCOUNTS_INVOLVED_IN_P2P: usize = 1_000_000;
+const COMMIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+const COMMIT_STALL_TIMEOUT: Duration = Duration::from_secs(60);
 
 fn get_progress_bar(num_accounts: usize) -> ProgressBar {
     let bar = ProgressBar::new(num_accounts as u64);
@@ -51,6 +61,28 @@ fn get_sequence_number(address: AccountAddress, reader: Arc<dyn DbReader>) -> u6
     }
 }
 
+/// Waits until `version` is committed, or until the commit progress stalls, which happens if some
+/// of the transactions sent were discarded.
+async fn wait_for_version(reader: &Arc<dyn DbReader>, version: Version) {
+    let mut latest_version = reader.get_latest_version().unwrap();
+    let mut last_progress = Instant::now();
+    while latest_version < version {
+        if last_progress.elapsed() > COMMIT_STALL_TIMEOUT {
+            println!(
+                "Stopped waiting for version {} at version {}, were transactions discarded?",
+                version, latest_version,
+            );
+            return;
+        }
+        tokio::time::sleep(COMMIT_POLL_INTERVAL).await;
+        let new_latest_version = reader.get_latest_version().unwrap();
+        if new_latest_version > latest_version {
+            latest_version = new_latest_version;
+            last_progress = Instant::now();
+        }
+    }
+}
+
 macro_rules! now_fmt {
     () => {
         Local::now().format("%m-%d %H:%M:%S")
@@ -225,9 +257,107 @@ impl TransactionGenerator {
         );
     }
 
-    pub fn run_transfer(&mut self, block_size: usize, num_transfer_blocks: usize) {
+    pub fn run_transfer(
+        &mut self,
+        block_size: usize,
+        num_transfer_blocks: usize,
+        hotspot_probability: Option<f32>,
+    ) {
+        assert!(self.block_sender.is_some());
+        self.gen_transfer_transactions(block_size, num_transfer_blocks, hotspot_probability);
+    }
+
+    /// Runs the phases of `transaction_mix_per_phase` one after another, `num_blocks` blocks each,
+    /// with the transaction generators of the transaction emitter, and reports the TPS and the
+    /// number of speculative aborts of parallel execution of each phase once it's committed.
+    pub async fn run_workload(
+        &mut self,
+        db: DbReaderWriter,
+        block_size: usize,
+        num_blocks: usize,
+        transaction_mix_per_phase: &[Vec<(TransactionType, usize)>],
+        transactions_per_sender: usize,
+        hotspot_probability: Option<f32>,
+    ) {
         assert!(self.block_sender.is_some());
-        self.gen_transfer_transactions(block_size, num_transfer_blocks);
+        assert!(transactions_per_sender > 0);
+
+        // Setting up the generators (e.g. publishing the modules they call) commits
+        // transactions through the pipeline as well.
+        let submitter = DbReliableTransactionSubmitter {
+            db: db.clone(),
+            block_sender: self.block_sender.clone().unwrap(),
+        };
+        let phase = Arc::new(DynamicStatsTracking::new(transaction_mix_per_phase.len()));
+        let mut transaction_generator_creator = create_txn_generator_creator(
+            transaction_mix_per_phase,
+            1, /* num_workers */
+            self.accounts_cache
+                .as_mut()
+                .unwrap()
+                .accounts
+                .make_contiguous(),
+            &submitter,
+            &self.transaction_factory,
+            phase.clone(),
+        )
+        .await;
+        let mut transaction_generator = transaction_generator_creator
+            .create_transaction_generator()
+            .await;
+        self.version = db.reader.get_latest_version().unwrap();
+
+        let num_senders_per_block =
+            (block_size + transactions_per_sender - 1) / transactions_per_sender;
+        for (phase_idx, transaction_mix) in transaction_mix_per_phase.iter().enumerate() {
+            if phase_idx > 0 {
+                phase.start_next_phase();
+            }
+            let start_version = self.version;
+            let start_num_aborts = SPECULATIVE_ABORT_COUNT.get();
+            let start_time = Instant::now();
+
+            for _ in 0..num_blocks {
+                let mut transactions = Vec::with_capacity(block_size + 1);
+                for _ in 0..num_senders_per_block {
+                    let sender = self
+                        .accounts_cache
+                        .as_mut()
+                        .unwrap()
+                        .get_random_with_hotspot(hotspot_probability);
+                    transactions.extend(
+                        transaction_generator
+                            .generate_transactions(vec![sender], transactions_per_sender)
+                            .into_iter()
+                            .map(|txn| {
+                                BenchmarkTransaction::from(Transaction::UserTransaction(txn))
+                            }),
+                    );
+                }
+                transactions.push(Transaction::StateCheckpoint(HashValue::random()).into());
+                self.version += transactions.len() as Version;
+
+                if let Some(sender) = &self.block_sender {
+                    sender.send(transactions).unwrap();
+                }
+            }
+            wait_for_version(&db.reader, self.version).await;
+
+            let num_txns = self.version - start_version;
+            let elapsed = start_time.elapsed().as_secs_f64();
+            let num_aborts = SPECULATIVE_ABORT_COUNT.get() - start_num_aborts;
+            println!(
+                "[{}] Phase {} {:?}: {} txns in {:.3}s, TPS: {:.0}, speculative aborts: {} ({:.3} per txn).",
+                now_fmt!(),
+                phase_idx,
+                transaction_mix,
+                num_txns,
+                elapsed,
+                num_txns as f64 / elapsed,
+                num_aborts,
+                num_aborts as f64 / num_txns as f64,
+            );
+        }
     }
 
     pub fn create_seed_accounts(
@@ -344,13 +474,21 @@ impl TransactionGenerator {
     }
 
     /// Generates transactions for random pairs of accounts.
-    pub fn gen_transfer_transactions(&mut self, block_size: usize, num_blocks: usize) {
+    pub fn gen_transfer_transactions(
+        &mut self,
+        block_size: usize,
+        num_blocks: usize,
+        hotspot_probability: Option<f32>,
+    ) {
         for _ in 0..num_blocks {
             let transactions: Vec<_> = (0..block_size)
                 .into_iter()
                 .map(|_| {
-                    let (sender, receiver) =
-                        self.accounts_cache.as_mut().unwrap().get_random_transfer();
+                    let (sender, receiver) = self
+                        .accounts_cache
+                        .as_mut()
+                        .unwrap()
+                        .get_random_transfer(hotspot_probability);
                     let amount = 1;
                     let txn = sender.sign_with_transaction_builder(
                         self.transaction_factory.transfer(receiver, amount),
@@ -12,12 +12,15 @@ use aptos_storage_interface::{
     ExecutedTrees,
 };
 use aptos_types::{
-    account_config::CORE_CODE_ADDRESS,
+    access_path::AccessPath,
+    account_config::{AccountResource, CoinStoreResource, CORE_CODE_ADDRESS},
+    state_store::state_key::StateKey,
     transaction::{ExecutionStatus, Transaction, TransactionOutput, TransactionStatus},
 };
 use aptos_vm::{AptosVM, VMExecutor};
 use fail::fail_point;
-use std::time::Duration;
+use move_core_types::move_resource::MoveResource;
+use std::{collections::HashSet, time::Duration};
 
 pub struct ChunkOutput {
     /// Input transactions.
@@ -35,6 +38,9 @@ impl ChunkOutput {
         transactions: Vec<Transaction>,
         state_view: CachedStateView,
     ) -> Result<Self> {
+        // Read the state every user transaction touches in its prologue and epilogue in batches,
+        // instead of key by key during execution.
+        state_view.prime_cache(&Self::sender_state_keys(&transactions))?;
         let transaction_outputs = Self::execute_block::<V>(transactions.clone(), &state_view)?;
 
         // to print txn output for debugging, uncomment:
@@ -74,6 +80,28 @@ impl ChunkOutput {
         })
     }
 
+    /// Returns the state keys of the account and coin store resources of all user transaction
+    /// senders.
+    fn sender_state_keys(transactions: &[Transaction]) -> Vec<StateKey> {
+        transactions
+            .iter()
+            .filter_map(|txn| match txn {
+                Transaction::UserTransaction(signed_txn) => Some(signed_txn.sender()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .flat_map(|sender| {
+                [
+                    AccountResource::resource_path(),
+                    CoinStoreResource::resource_path(),
+                ]
+                .into_iter()
+                .map(move |path| StateKey::AccessPath(AccessPath::new(sender, path)))
+            })
+            .collect()
+    }
+
     pub fn apply_to_ledger(
         self,
         base_view: &ExecutedTrees,
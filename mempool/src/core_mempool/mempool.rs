@@ -16,7 +16,7 @@ use crate::{
 };
 use aptos_config::config::NodeConfig;
 use aptos_crypto::HashValue;
-use aptos_logger::prelude::*;
+use aptos_logger::{prelude::*, span_export, txn_span};
 use aptos_types::{
     account_address::AccountAddress,
     account_config::AccountSequenceInfo,
@@ -94,14 +94,30 @@ impl Mempool {
             .transactions
             .get_insertion_time_and_bucket(&account, sequence_number)
         {
+            let scope = if is_end_to_end {
+                E2E_LABEL
+            } else {
+                LOCAL_LABEL
+            };
             if let Ok(time_delta) = SystemTime::now().duration_since(insertion_time) {
-                let scope = if is_end_to_end {
-                    E2E_LABEL
-                } else {
-                    LOCAL_LABEL
-                };
                 counters::core_mempool_txn_commit_latency(stage, scope, bucket, time_delta);
             }
+            if span_export::is_enabled() {
+                if let Some(txn) = self.transactions.get(&account, sequence_number) {
+                    // Covers the time the transaction spent in mempool until this stage
+                    txn_span!(
+                        "mempool",
+                        txn_hash = %txn.committed_hash().to_hex(),
+                        otel.name = %format!("mempool.{}", stage),
+                        otel.start_time_unix_nano = span_export::unix_nanos(insertion_time),
+                        sender = %account,
+                        sequence_number = sequence_number,
+                        scope = scope,
+                        bucket = bucket
+                    )
+                    .in_scope(|| {});
+                }
+            }
         }
     }
 
@@ -19,7 +19,7 @@ use aptos_config::{
     network_id::PeerNetworkId,
 };
 use aptos_infallible::{Mutex, RwLock};
-use aptos_logger::prelude::*;
+use aptos_logger::{prelude::*, span_export, txn_span};
 use aptos_netcore::transport::ConnectionOrigin;
 use aptos_network::{
     application::{error::Error, interface::NetworkClientInterface},
@@ -445,11 +445,27 @@ impl<NetworkClient: NetworkClientInterface<MempoolSyncMsg>> MempoolNetworkInterf
             self.determine_broadcast_batch(peer, scheduled_backoff, smp)?;
 
         let num_txns = transactions.len();
+        let txn_spans: Vec<_> = if span_export::is_enabled() {
+            transactions
+                .iter()
+                .map(|txn| {
+                    txn_span!(
+                        "mempool.broadcast",
+                        txn_hash = %txn.clone().committed_hash().to_hex(),
+                        batch_id = ?batch_id,
+                        peer = %peer
+                    )
+                })
+                .collect()
+        } else {
+            vec![]
+        };
         let send_time = SystemTime::now();
         self.send_batch_to_peer(peer, batch_id.clone(), transactions)
             .await?;
         let num_pending_broadcasts =
             self.update_broadcast_state(peer, batch_id.clone(), send_time)?;
+        drop(txn_spans);
         notify_subscribers(SharedMempoolNotification::Broadcast, &smp.subscribers);
 
         // Log all the metrics
@@ -19,7 +19,7 @@ use aptos_config::network_id::PeerNetworkId;
 use aptos_consensus_types::common::{RejectedTransactionSummary, TransactionSummary};
 use aptos_crypto::HashValue;
 use aptos_infallible::{Mutex, RwLock};
-use aptos_logger::prelude::*;
+use aptos_logger::{prelude::*, span_export, txn_span};
 use aptos_metrics_core::HistogramTimer;
 use aptos_network::application::interface::NetworkClientInterface;
 use aptos_storage_interface::state_view::LatestDbStateCheckpointView;
@@ -111,6 +111,15 @@ pub(crate) async fn process_client_transaction_submission<NetworkClient, Transac
 {
     timer.stop_and_record();
     let _timer = counters::process_txn_submit_latency_timer_client();
+    let span = if span_export::is_enabled() {
+        txn_span!(
+            "mempool.submit",
+            txn_hash = %transaction.clone().committed_hash().to_hex(),
+            status = span_export::Empty
+        )
+    } else {
+        span_export::Span::none()
+    };
     let ineligible_for_broadcast =
         smp.network_interface.is_validator() && !smp.broadcast_within_validator_network();
     let timeline_state = if ineligible_for_broadcast {
@@ -122,6 +131,7 @@ pub(crate) async fn process_client_transaction_submission<NetworkClient, Transac
     log_txn_process_results(&statuses, None);
 
     if let Some(status) = statuses.first() {
+        span.record("status", &format!("{:?}", status.1 .0.code).as_str());
         if callback.send(Ok(status.1.clone())).is_err() {
             warn!(LogSchema::event_log(
                 LogEntry::JsonRpc,
@@ -75,6 +75,7 @@ async fn test_get_connected_peers() {
         ConnectionOrigin::Inbound,
         MessagingProtocolVersion::V1,
         ProtocolIdSet::empty(),
+        true,
         PeerRole::Unknown,
     );
     peers_and_metadata
@@ -13,6 +13,12 @@ pub const INBOUND_RPC_TIMEOUT_MS: u64 = 10_000;
 pub const MAX_CONCURRENT_OUTBOUND_RPCS: u32 = 100;
 /// Limit on concurrent Inbound RPC requests before backpressure is applied
 pub const MAX_CONCURRENT_INBOUND_RPCS: u32 = 100;
+/// Limit on the outbound messages pulled off the write queue (per peer) and held
+/// for scheduling, before the peer writer stops pulling (and applies backpressure)
+pub const MAX_QUEUED_OUTBOUND_MESSAGES: usize = 64;
+/// The number of normal priority frames sent for each bulk priority frame
+/// when both classes have pending messages
+pub const NORMAL_FRAMES_PER_BULK_FRAME: u64 = 4;
 
 // These are only used in tests
 // TODO: Fix this so the tests and the defaults in config are the same
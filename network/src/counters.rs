@@ -1,7 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::protocols::wire::handshake::v1::ProtocolId;
+use crate::protocols::wire::{handshake::v1::ProtocolId, messaging::v1::PriorityClass};
 use aptos_config::network_id::NetworkContext;
 use aptos_metrics_core::{
     register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
@@ -396,24 +396,72 @@ pub static PENDING_WIRE_MESSAGES: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
-/// Counter of messages pending in queue to be sent out on the multiplex channel
-pub static PENDING_MULTIPLEX_MESSAGE: Lazy<IntGauge> = Lazy::new(|| {
-    register_int_gauge!(
-        "aptos_network_pending_multiplex_messages",
-        "Number of pending multiplex messages"
+/// Counter of outbound messages queued by the peer writers, per priority class
+pub static PENDING_OUTBOUND_MESSAGES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_network_pending_outbound_messages",
+        "Number of outbound messages queued by the peer writers, per priority class",
+        &["role_type", "network_id", "peer_id", "priority_class"]
     )
     .unwrap()
 });
 
-/// Counter of stream messages pending in queue to be sent out on the multiplex channel
-pub static PENDING_MULTIPLEX_STREAM: Lazy<IntGauge> = Lazy::new(|| {
-    register_int_gauge!(
-        "aptos_network_pending_multiplex_stream",
-        "Number of pending multiplex stream messages"
+pub fn pending_outbound_messages(
+    network_context: &NetworkContext,
+    priority_class: PriorityClass,
+) -> IntGauge {
+    PENDING_OUTBOUND_MESSAGES.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        priority_class.as_str(),
+    ])
+}
+
+/// Counter of outbound bytes queued by the peer writers, per priority class
+pub static PENDING_OUTBOUND_BYTES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_network_pending_outbound_bytes",
+        "Number of outbound bytes queued by the peer writers, per priority class",
+        &["role_type", "network_id", "peer_id", "priority_class"]
     )
     .unwrap()
 });
 
+pub fn pending_outbound_bytes(
+    network_context: &NetworkContext,
+    priority_class: PriorityClass,
+) -> IntGauge {
+    PENDING_OUTBOUND_BYTES.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        priority_class.as_str(),
+    ])
+}
+
+/// Counter of frames (i.e., messages and stream fragments) written to the wire
+pub static SENT_OUTBOUND_FRAMES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_network_sent_outbound_frames",
+        "Number of frames written to the wire, per priority class",
+        &["role_type", "network_id", "peer_id", "priority_class"]
+    )
+    .unwrap()
+});
+
+pub fn sent_outbound_frames(
+    network_context: &NetworkContext,
+    priority_class: PriorityClass,
+) -> IntCounter {
+    SENT_OUTBOUND_FRAMES.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        priority_class.as_str(),
+    ])
+}
+
 /// Counter of pending requests in Direct Send
 pub static PENDING_DIRECT_SEND_REQUESTS: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!(
@@ -83,6 +83,7 @@ pub fn fuzz(data: &[u8]) {
         ConnectionOrigin::Inbound,
         MessagingProtocolVersion::V1,
         ProtocolIdSet::all_known(),
+        true,
         PeerRole::Unknown,
     );
     let connection = Connection { socket, metadata };
@@ -15,19 +15,21 @@
 //! [`PeerManager`]: crate::peer_manager::PeerManager
 
 use crate::{
+    constants::MAX_QUEUED_OUTBOUND_MESSAGES,
     counters::{
         self, network_application_inbound_traffic, network_application_outbound_traffic,
         RECEIVED_LABEL, SENT_LABEL,
     },
     logging::NetworkSchema,
+    peer::outbound_queues::OutboundQueues,
     peer_manager::{PeerManagerError, TransportNotification},
     protocols::{
         direct_send::Message,
         rpc::{InboundRpcRequest, InboundRpcs, OutboundRpcRequest, OutboundRpcs},
-        stream::{InboundStreamBuffer, OutboundStream, StreamMessage},
+        stream::{InboundStreamBuffer, StreamMessage},
         wire::messaging::v1::{
             DirectSendMsg, ErrorCode, MultiplexMessage, MultiplexMessageSink,
-            MultiplexMessageStream, NetworkMessage, Priority, ReadError, WriteError,
+            MultiplexMessageStream, NetworkMessage, ReadError, WriteError,
         },
     },
     transport::{self, Connection, ConnectionMetadata},
@@ -46,9 +48,8 @@ use futures::{
     channel::oneshot,
     io::{AsyncRead, AsyncWrite},
     stream::StreamExt,
-    SinkExt,
+    FutureExt, SinkExt,
 };
-use futures_util::stream::select;
 use serde::Serialize;
 use std::{fmt, panic, time::Duration};
 use tokio::runtime::Handle;
@@ -56,6 +57,8 @@ use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
 };
 
+mod outbound_queues;
+pub use outbound_queues::OutboundMessage;
 #[cfg(test)]
 mod test;
 
@@ -312,8 +315,12 @@ where
     // task:
     // 1. The first channel is used to send outbound NetworkMessages to the task
     // 2. The second channel is used to instruct the task to close the connection and terminate.
-    // If outbound messages are queued when the task receives a close instruction, it discards
-    // them and immediately closes the connection.
+    // Outbound messages are scheduled by protocol and priority class (see `OutboundQueues`), one
+    // frame at a time, so that large bulk messages don't delay latency sensitive ones. Messages
+    // are only pulled off the (bounded) first channel when the previous frame has been written,
+    // so the channel applies backpressure to the senders. If outbound messages are queued when
+    // the task receives a close instruction, it discards them and immediately closes the
+    // connection.
     fn start_writer_task(
         executor: &Handle,
         time_service: TimeService,
@@ -322,32 +329,63 @@ where
         mut writer: MultiplexMessageSink<impl AsyncWrite + Unpin + Send + 'static>,
         max_frame_size: usize,
         max_message_size: usize,
-    ) -> (aptos_channels::Sender<NetworkMessage>, oneshot::Sender<()>) {
+    ) -> (aptos_channels::Sender<OutboundMessage>, oneshot::Sender<()>) {
         let remote_peer_id = connection_metadata.remote_peer_id;
-        let (write_reqs_tx, mut write_reqs_rx): (aptos_channels::Sender<NetworkMessage>, _) =
+        let (write_reqs_tx, mut write_reqs_rx): (aptos_channels::Sender<OutboundMessage>, _) =
             aptos_channels::new(1024, &counters::PENDING_WIRE_MESSAGES);
-        let (close_tx, mut close_rx) = oneshot::channel();
+        let (close_tx, mut close_rx) = oneshot::channel::<()>();
 
-        let (mut msg_tx, msg_rx) = aptos_channels::new(1024, &counters::PENDING_MULTIPLEX_MESSAGE);
-        let (stream_msg_tx, stream_msg_rx) =
-            aptos_channels::new(1024, &counters::PENDING_MULTIPLEX_STREAM);
-
-        // this task ends when the multiplex task ends (by dropping the senders)
         let writer_task = async move {
-            let mut stream = select(msg_rx, stream_msg_rx);
             let log_context =
                 NetworkSchema::new(&network_context).connection_metadata(&connection_metadata);
-            while let Some(message) = stream.next().await {
-                if let Err(err) = writer.send(&message).await {
-                    warn!(
-                        log_context,
-                        error = %err,
-                        "{} Error in sending message to peer: {}",
-                        network_context,
-                        remote_peer_id.short_str(),
-                    );
+            let mut outbound_queues = OutboundQueues::new(
+                network_context,
+                max_frame_size,
+                max_message_size,
+                MAX_QUEUED_OUTBOUND_MESSAGES,
+                connection_metadata.interleaved_streams,
+            );
+            let mut write_reqs_closed = false;
+            loop {
+                // Pull the pending messages (until the queues are full), so that the next
+                // frame is chosen by priority. The remaining messages wait in the channel.
+                while !write_reqs_closed && !outbound_queues.is_full() {
+                    match write_reqs_rx.next().now_or_never() {
+                        Some(Some(message)) => outbound_queues.push(message),
+                        Some(None) => write_reqs_closed = true,
+                        None => break,
+                    }
+                }
+
+                // Stop if we've been asked to close the connection
+                if !matches!(close_rx.try_recv(), Ok(None)) {
+                    break;
+                }
+
+                // Write the next frame, or wait for new messages
+                if let Some(frame) = outbound_queues.pop() {
+                    if let Err(err) = writer.send(&frame).await {
+                        warn!(
+                            log_context,
+                            error = %err,
+                            "{} Error in sending message to peer: {}",
+                            network_context,
+                            remote_peer_id.short_str(),
+                        );
+                    }
+                } else if write_reqs_closed {
+                    break; // All messages have been written
+                } else {
+                    futures::select! {
+                        message = write_reqs_rx.next() => match message {
+                            Some(message) => outbound_queues.push(message),
+                            None => write_reqs_closed = true,
+                        },
+                        _ = close_rx => break,
+                    }
                 }
             }
+            outbound_queues.clear();
             info!(
                 log_context,
                 "{} Closing connection to peer: {}",
@@ -391,35 +429,7 @@ where
                 },
             }
         };
-        let multiplex_task = async move {
-            let mut outbound_stream =
-                OutboundStream::new(max_frame_size, max_message_size, stream_msg_tx);
-            loop {
-                futures::select! {
-                    message = write_reqs_rx.select_next_some() => {
-                        // either channel full would block the other one
-                        let result = if outbound_stream.should_stream(&message) {
-                            outbound_stream.stream_message(message).await
-                        } else {
-                            msg_tx.send(MultiplexMessage::Message(message)).await.map_err(|_| anyhow::anyhow!("Writer task ended"))
-                        };
-                        if let Err(err) = result {
-                            warn!(
-                                error = %err,
-                                "{} Error in sending message to peer: {}",
-                                network_context,
-                                remote_peer_id.short_str(),
-                            );
-                        }
-                    },
-                    _ = close_rx => {
-                        break;
-                    }
-                }
-            }
-        };
         executor.spawn(writer_task);
-        executor.spawn(multiplex_task);
         (write_reqs_tx, close_tx)
     }
 
@@ -482,7 +492,7 @@ where
     async fn handle_inbound_message(
         &mut self,
         message: Result<MultiplexMessage, ReadError>,
-        write_reqs_tx: &mut aptos_channels::Sender<NetworkMessage>,
+        write_reqs_tx: &mut aptos_channels::Sender<OutboundMessage>,
     ) -> Result<(), PeerManagerError> {
         trace!(
             NetworkSchema::new(&self.network_context)
@@ -502,7 +512,7 @@ where
                     let message_type = frame_prefix.as_ref().first().unwrap_or(&0);
                     let protocol_id = frame_prefix.as_ref().get(1).unwrap_or(&0);
                     let error_code = ErrorCode::parsing_error(*message_type, *protocol_id);
-                    let message = NetworkMessage::Error(error_code);
+                    let message = OutboundMessage::error(error_code);
 
                     write_reqs_tx.send(message).await?;
                     return Err(err.into());
@@ -563,7 +573,7 @@ where
     async fn handle_outbound_request(
         &mut self,
         request: PeerRequest,
-        write_reqs_tx: &mut aptos_channels::Sender<NetworkMessage>,
+        write_reqs_tx: &mut aptos_channels::Sender<OutboundMessage>,
     ) {
         trace!(
             "Peer {} PeerRequest::{:?}",
@@ -583,9 +593,10 @@ where
                 );
                 let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
                     protocol_id,
-                    priority: Priority::default(),
+                    priority: protocol_id.priority_class().as_priority(),
                     raw_msg: Vec::from(message.mdata.as_ref()),
                 });
+                let message = OutboundMessage::new(protocol_id, message);
 
                 match write_reqs_tx.send(message).await {
                     Ok(_) => {
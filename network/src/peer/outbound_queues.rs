@@ -0,0 +1,709 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`OutboundQueues`] schedules the outbound messages of a single connection.
+//!
+//! Every outbound message is queued according to its protocol, and every protocol
+//! belongs to a [`PriorityClass`]. Messages of the high priority class (e.g., consensus)
+//! are always written first, while the normal and bulk classes share the remaining
+//! bandwidth using weighted round robin. The protocols of a class take turns, so a busy
+//! protocol can't starve the other protocols in its class. Large messages are split into
+//! stream fragments, which are scheduled one frame at a time, so a bulk storage service
+//! response never delays consensus messages by more than a single frame.
+//!
+//! The queues only hold a small number of messages. The writer pulls messages from the
+//! (bounded) outbound channel only when the queues have room, and only after the previous
+//! frame has been written, so the channel continues to apply backpressure to the senders.
+//! Messages are never dropped by the queues.
+//!
+//! If the peer supports interleaved streams (as negotiated during the handshake), it
+//! reassembles a single stream per priority class at a time, so at most one stream of each
+//! class is in flight. A high priority stream is therefore written in full before the next
+//! frame of an active normal or bulk stream. Otherwise, the peer reassembles a single stream
+//! at a time, so a new stream only starts once the active one has been written in full
+//! (messages that don't need streaming are still written in between).
+
+use crate::{
+    constants::NORMAL_FRAMES_PER_BULK_FRAME,
+    counters,
+    protocols::{
+        stream::{OutboundStream, StreamMessage},
+        wire::messaging::v1::{ErrorCode, MultiplexMessage, NetworkMessage, PriorityClass},
+    },
+    ProtocolId,
+};
+use aptos_config::network_id::NetworkContext;
+use aptos_logger::prelude::*;
+use aptos_metrics_core::{IntCounter, IntGauge};
+use std::collections::{HashMap, VecDeque};
+
+/// A message to write to the wire, along with the protocol it belongs to. This is
+/// required because RPC responses don't identify their protocol on the wire.
+#[derive(Debug)]
+pub struct OutboundMessage {
+    protocol_id: Option<ProtocolId>, // None iff the message is an error message
+    message: NetworkMessage,
+}
+
+impl OutboundMessage {
+    pub fn new(protocol_id: ProtocolId, message: NetworkMessage) -> Self {
+        Self {
+            protocol_id: Some(protocol_id),
+            message,
+        }
+    }
+
+    /// Creates an error message (which doesn't belong to any protocol)
+    pub fn error(error_code: ErrorCode) -> Self {
+        Self {
+            protocol_id: None,
+            message: NetworkMessage::Error(error_code),
+        }
+    }
+}
+
+/// The queued messages of a single priority class (per protocol)
+#[derive(Default)]
+struct ClassQueue {
+    protocol_queues: HashMap<Option<ProtocolId>, VecDeque<NetworkMessage>>,
+    ready_protocols: VecDeque<Option<ProtocolId>>, // The protocols with queued messages (in turn order)
+    queued_bytes: usize,
+}
+
+/// The metrics of a single priority class (cached to avoid label lookups)
+struct ClassCounters {
+    pending_messages: IntGauge,
+    pending_bytes: IntGauge,
+    sent_frames: IntCounter,
+}
+
+impl ClassCounters {
+    fn new(network_context: &NetworkContext, priority_class: PriorityClass) -> Self {
+        Self {
+            pending_messages: counters::pending_outbound_messages(network_context, priority_class),
+            pending_bytes: counters::pending_outbound_bytes(network_context, priority_class),
+            sent_frames: counters::sent_outbound_frames(network_context, priority_class),
+        }
+    }
+}
+
+pub struct OutboundQueues {
+    network_context: NetworkContext,
+    class_queues: HashMap<PriorityClass, ClassQueue>,
+    class_counters: HashMap<PriorityClass, ClassCounters>,
+    active_streams: HashMap<PriorityClass, VecDeque<StreamMessage>>, // At most one stream per class
+    interleave_streams: bool, // Whether streams of different classes may be in flight together
+    outbound_stream: OutboundStream,
+    max_queued_messages: usize,
+    num_queued_messages: usize,
+    normal_frames_since_bulk: u64,
+}
+
+impl OutboundQueues {
+    pub fn new(
+        network_context: NetworkContext,
+        max_frame_size: usize,
+        max_message_size: usize,
+        max_queued_messages: usize,
+        interleave_streams: bool,
+    ) -> Self {
+        let class_queues = PriorityClass::all()
+            .iter()
+            .map(|priority_class| (*priority_class, ClassQueue::default()))
+            .collect();
+        let class_counters = PriorityClass::all()
+            .iter()
+            .map(|priority_class| {
+                (
+                    *priority_class,
+                    ClassCounters::new(&network_context, *priority_class),
+                )
+            })
+            .collect();
+        Self {
+            network_context,
+            class_queues,
+            class_counters,
+            active_streams: HashMap::new(),
+            interleave_streams,
+            outbound_stream: OutboundStream::new(max_frame_size, max_message_size),
+            max_queued_messages,
+            num_queued_messages: 0,
+            normal_frames_since_bulk: 0,
+        }
+    }
+
+    /// Returns true iff there are no messages left to write
+    pub fn is_empty(&self) -> bool {
+        self.active_streams.is_empty() && self.num_queued_messages == 0
+    }
+
+    /// Returns true iff no more messages should be queued (i.e., until
+    /// the queued messages have been written to the wire).
+    pub fn is_full(&self) -> bool {
+        self.num_queued_messages >= self.max_queued_messages
+    }
+
+    /// Queues the given message. Callers are expected to check `is_full` first,
+    /// but the message is always queued (messages are never dropped).
+    pub fn push(&mut self, outbound_message: OutboundMessage) {
+        let OutboundMessage {
+            protocol_id,
+            message,
+        } = outbound_message;
+        let priority_class = message.priority_class();
+        let message_bytes = message.data_len();
+
+        let class_queue = self.class_queue_mut(priority_class);
+        let protocol_queue = class_queue.protocol_queues.entry(protocol_id).or_default();
+        if protocol_queue.is_empty() {
+            class_queue.ready_protocols.push_back(protocol_id);
+        }
+        protocol_queue.push_back(message);
+        class_queue.queued_bytes += message_bytes;
+        self.num_queued_messages += 1;
+        self.update_pending_counters(priority_class, 1, message_bytes as i64);
+    }
+
+    /// Returns the next frame to write to the wire (if any)
+    pub fn pop(&mut self) -> Option<MultiplexMessage> {
+        // High priority messages always go first
+        if let Some(frame) = self.pop_class(PriorityClass::High) {
+            self.class_counters(PriorityClass::High).sent_frames.inc();
+            return Some(frame);
+        }
+
+        // Otherwise, share the bandwidth between the normal and bulk classes
+        let classes = if self.normal_frames_since_bulk >= NORMAL_FRAMES_PER_BULK_FRAME {
+            [PriorityClass::Bulk, PriorityClass::Normal]
+        } else {
+            [PriorityClass::Normal, PriorityClass::Bulk]
+        };
+        for priority_class in classes {
+            if let Some(frame) = self.pop_class(priority_class) {
+                if priority_class == PriorityClass::Normal {
+                    self.normal_frames_since_bulk += 1;
+                } else {
+                    self.normal_frames_since_bulk = 0;
+                }
+                self.class_counters(priority_class).sent_frames.inc();
+                return Some(frame);
+            }
+        }
+        None
+    }
+
+    /// Drops all queued messages (e.g., because the connection is closing)
+    pub fn clear(&mut self) {
+        self.active_streams.clear();
+        for priority_class in PriorityClass::all() {
+            let class_queue = self.class_queue_mut(*priority_class);
+            let num_messages: usize = class_queue
+                .protocol_queues
+                .values()
+                .map(VecDeque::len)
+                .sum();
+            let queued_bytes = class_queue.queued_bytes as i64;
+            *class_queue = ClassQueue::default();
+            self.update_pending_counters(*priority_class, -(num_messages as i64), -queued_bytes);
+        }
+        self.num_queued_messages = 0;
+    }
+
+    /// Returns the next frame of the given priority class (if any)
+    fn pop_class(&mut self, priority_class: PriorityClass) -> Option<MultiplexMessage> {
+        // Continue the active stream of this class (if any)
+        if let Some(stream_messages) = self.active_streams.get_mut(&priority_class) {
+            let stream_message = stream_messages.pop_front();
+            if stream_messages.is_empty() {
+                self.active_streams.remove(&priority_class);
+            }
+            return stream_message.map(MultiplexMessage::Stream);
+        }
+
+        // Otherwise, give the next protocol (with queued messages) its turn. Without
+        // interleaving, a new stream can't start while another stream is in flight.
+        let can_stream = self.interleave_streams || self.active_streams.is_empty();
+        while let Some(message) = self.pop_message(priority_class, can_stream) {
+            if !self.outbound_stream.should_stream(&message) {
+                return Some(MultiplexMessage::Message(message));
+            }
+            match self.outbound_stream.stream_message(message) {
+                Ok(stream_messages) => {
+                    let mut stream_messages = VecDeque::from(stream_messages);
+                    let header = stream_messages.pop_front();
+                    if !stream_messages.is_empty() {
+                        self.active_streams.insert(priority_class, stream_messages);
+                    }
+                    return header.map(MultiplexMessage::Stream);
+                },
+                Err(error) => {
+                    // The message can't be sent, so move on to the next one
+                    warn!(
+                        error = %error,
+                        "{} Error in streaming {} priority message",
+                        self.network_context,
+                        priority_class.as_str(),
+                    );
+                },
+            }
+        }
+        None
+    }
+
+    /// Removes the next message of the protocol whose turn it is (in the given
+    /// priority class). The protocol then goes to the back of the line. If streams
+    /// can't be started, protocols whose next message must be streamed keep their
+    /// turn until they can.
+    fn pop_message(
+        &mut self,
+        priority_class: PriorityClass,
+        can_stream: bool,
+    ) -> Option<NetworkMessage> {
+        let outbound_stream = &self.outbound_stream;
+        let class_queue = self
+            .class_queues
+            .get_mut(&priority_class)
+            .expect("All priority classes should have a queue!");
+        let turn = class_queue.ready_protocols.iter().position(|protocol_id| {
+            can_stream
+                || class_queue
+                    .protocol_queues
+                    .get(protocol_id)
+                    .and_then(VecDeque::front)
+                    .map_or(false, |message| !outbound_stream.should_stream(message))
+        })?;
+        let protocol_id = class_queue.ready_protocols.remove(turn)?;
+        let protocol_queue = class_queue.protocol_queues.get_mut(&protocol_id)?;
+        let message = protocol_queue.pop_front()?;
+        if !protocol_queue.is_empty() {
+            class_queue.ready_protocols.push_back(protocol_id);
+        }
+        let message_bytes = message.data_len();
+        class_queue.queued_bytes -= message_bytes;
+        self.num_queued_messages -= 1;
+        self.update_pending_counters(priority_class, -1, -(message_bytes as i64));
+        Some(message)
+    }
+
+    fn class_queue(&self, priority_class: PriorityClass) -> &ClassQueue {
+        self.class_queues
+            .get(&priority_class)
+            .expect("All priority classes should have a queue!")
+    }
+
+    fn class_queue_mut(&mut self, priority_class: PriorityClass) -> &mut ClassQueue {
+        self.class_queues
+            .get_mut(&priority_class)
+            .expect("All priority classes should have a queue!")
+    }
+
+    fn class_counters(&self, priority_class: PriorityClass) -> &ClassCounters {
+        self.class_counters
+            .get(&priority_class)
+            .expect("All priority classes should have counters!")
+    }
+
+    fn update_pending_counters(
+        &self,
+        priority_class: PriorityClass,
+        num_messages: i64,
+        num_bytes: i64,
+    ) {
+        let class_counters = self.class_counters(priority_class);
+        class_counters.pending_messages.add(num_messages);
+        class_counters.pending_bytes.add(num_bytes);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::protocols::wire::messaging::v1::{DirectSendMsg, RpcResponse};
+
+    const MAX_FRAME_SIZE: usize = 128;
+    const MAX_MESSAGE_SIZE: usize = 64 * 255;
+    const MAX_QUEUED_MESSAGES: usize = 100;
+
+    fn create_outbound_queues(
+        max_queued_messages: usize,
+        interleave_streams: bool,
+    ) -> OutboundQueues {
+        OutboundQueues::new(
+            NetworkContext::mock(),
+            MAX_FRAME_SIZE,
+            MAX_MESSAGE_SIZE,
+            max_queued_messages,
+            interleave_streams,
+        )
+    }
+
+    fn direct_send(protocol_id: ProtocolId, message_bytes: usize) -> OutboundMessage {
+        let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
+            protocol_id,
+            priority: protocol_id.priority_class().as_priority(),
+            raw_msg: vec![0; message_bytes],
+        });
+        OutboundMessage::new(protocol_id, message)
+    }
+
+    fn rpc_response(protocol_id: ProtocolId, message_bytes: usize) -> OutboundMessage {
+        let message = NetworkMessage::RpcResponse(RpcResponse {
+            request_id: message_bytes as u32,
+            priority: protocol_id.priority_class().as_priority(),
+            raw_response: vec![0; message_bytes],
+        });
+        OutboundMessage::new(protocol_id, message)
+    }
+
+    /// Reassembles inbound streams like the peers that don't support interleaved
+    /// streams, i.e., a new stream header discards the stream in flight (if any).
+    #[derive(Default)]
+    struct LegacyInboundStreamBuffer {
+        stream: Option<(u32, u8)>, // The request id and number of fragments left
+    }
+
+    impl LegacyInboundStreamBuffer {
+        /// Receives the given frame, and returns true iff it completes a message
+        fn receive(&mut self, frame: MultiplexMessage) -> anyhow::Result<bool> {
+            match frame {
+                MultiplexMessage::Message(_) => Ok(true),
+                MultiplexMessage::Stream(StreamMessage::Header(header)) => {
+                    match self
+                        .stream
+                        .replace((header.request_id, header.num_fragments))
+                    {
+                        Some((request_id, _)) => {
+                            anyhow::bail!("Discard existing stream {}", request_id)
+                        },
+                        None => Ok(false),
+                    }
+                },
+                MultiplexMessage::Stream(StreamMessage::Fragment(fragment)) => {
+                    let (request_id, fragments_left) = self
+                        .stream
+                        .as_mut()
+                        .ok_or_else(|| anyhow::anyhow!("No stream exist"))?;
+                    anyhow::ensure!(
+                        *request_id == fragment.request_id,
+                        "Stream fragment from a different request"
+                    );
+                    *fragments_left -= 1;
+                    if *fragments_left == 0 {
+                        self.stream = None;
+                        return Ok(true);
+                    }
+                    Ok(false)
+                },
+            }
+        }
+    }
+
+    /// Queues large and small messages of every priority class
+    fn push_large_and_small_messages(outbound_queues: &mut OutboundQueues) -> usize {
+        let messages = [
+            rpc_response(ProtocolId::StorageServiceRpc, 5 * MAX_FRAME_SIZE),
+            direct_send(ProtocolId::StateSyncDirectSend, 3 * MAX_FRAME_SIZE),
+            direct_send(ProtocolId::MempoolDirectSend, 3 * MAX_FRAME_SIZE),
+            direct_send(ProtocolId::MempoolDirectSend, 10),
+            direct_send(ProtocolId::ConsensusDirectSendBcs, 3 * MAX_FRAME_SIZE),
+            direct_send(ProtocolId::ConsensusDirectSendBcs, 10),
+        ];
+        let num_messages = messages.len();
+        for message in messages {
+            outbound_queues.push(message);
+        }
+        num_messages
+    }
+
+    /// Pops all frames and returns the priority class of each
+    fn pop_all_classes(outbound_queues: &mut OutboundQueues) -> Vec<PriorityClass> {
+        let mut priority_classes = vec![];
+        while let Some(frame) = outbound_queues.pop() {
+            let priority_class = match frame {
+                MultiplexMessage::Message(message) => message.priority_class(),
+                MultiplexMessage::Stream(StreamMessage::Header(header)) => {
+                    header.message.priority_class()
+                },
+                MultiplexMessage::Stream(StreamMessage::Fragment(_)) => {
+                    priority_classes.last().copied().unwrap()
+                },
+            };
+            priority_classes.push(priority_class);
+        }
+        assert!(outbound_queues.is_empty());
+        priority_classes
+    }
+
+    #[test]
+    fn test_high_priority_preempts_streams() {
+        let mut outbound_queues = create_outbound_queues(MAX_QUEUED_MESSAGES, true);
+
+        // Queue a large storage service response and start streaming it
+        outbound_queues.push(rpc_response(
+            ProtocolId::StorageServiceRpc,
+            10 * MAX_FRAME_SIZE,
+        ));
+        assert!(matches!(
+            outbound_queues.pop(),
+            Some(MultiplexMessage::Stream(StreamMessage::Header(_)))
+        ));
+
+        // Queue a consensus message and verify it's sent before the next fragment
+        outbound_queues.push(direct_send(ProtocolId::ConsensusDirectSendBcs, 10));
+        match outbound_queues.pop() {
+            Some(MultiplexMessage::Message(message)) => {
+                assert_eq!(message.priority_class(), PriorityClass::High)
+            },
+            frame => panic!("Expected a consensus message, but got: {:?}", frame),
+        }
+        assert!(matches!(
+            outbound_queues.pop(),
+            Some(MultiplexMessage::Stream(StreamMessage::Fragment(_)))
+        ));
+    }
+
+    #[test]
+    fn test_normal_and_bulk_round_robin() {
+        let mut outbound_queues = create_outbound_queues(MAX_QUEUED_MESSAGES, true);
+
+        // Queue several normal and bulk priority messages
+        for _ in 0..10 {
+            outbound_queues.push(direct_send(ProtocolId::MempoolDirectSend, 10));
+            outbound_queues.push(direct_send(ProtocolId::StateSyncDirectSend, 10));
+        }
+
+        // Verify the bulk messages are interleaved with the normal messages
+        let priority_classes = pop_all_classes(&mut outbound_queues);
+        let mut expected_classes = vec![];
+        for _ in 0..2 {
+            expected_classes.extend([PriorityClass::Normal; 4]);
+            expected_classes.push(PriorityClass::Bulk);
+        }
+        expected_classes.extend([PriorityClass::Normal; 2]);
+        expected_classes.extend([PriorityClass::Bulk; 8]);
+        assert_eq!(priority_classes, expected_classes);
+    }
+
+    #[test]
+    fn test_protocols_take_turns() {
+        let mut outbound_queues = create_outbound_queues(MAX_QUEUED_MESSAGES, true);
+
+        // Queue several storage service responses, followed by state sync messages
+        for _ in 0..3 {
+            outbound_queues.push(rpc_response(ProtocolId::StorageServiceRpc, 10));
+        }
+        for _ in 0..3 {
+            outbound_queues.push(direct_send(ProtocolId::StateSyncDirectSend, 10));
+        }
+
+        // Verify the protocols of the bulk class take turns
+        let mut is_rpc_response = vec![];
+        while let Some(frame) = outbound_queues.pop() {
+            match frame {
+                MultiplexMessage::Message(message) => {
+                    is_rpc_response.push(matches!(message, NetworkMessage::RpcResponse(_)))
+                },
+                frame => panic!("Expected a single frame message, but got: {:?}", frame),
+            }
+        }
+        assert_eq!(is_rpc_response, vec![true, false, true, false, true, false]);
+    }
+
+    #[test]
+    fn test_high_priority_preempts_bulk_streams() {
+        let mut outbound_queues = create_outbound_queues(MAX_QUEUED_MESSAGES, true);
+
+        // Queue a large storage service response and start streaming it
+        outbound_queues.push(rpc_response(
+            ProtocolId::StorageServiceRpc,
+            10 * MAX_FRAME_SIZE,
+        ));
+        let bulk_request_id = match outbound_queues.pop() {
+            Some(MultiplexMessage::Stream(StreamMessage::Header(header))) => header.request_id,
+            frame => panic!("Expected a stream header, but got: {:?}", frame),
+        };
+
+        // Queue a large consensus message and verify it's streamed in full first
+        outbound_queues.push(direct_send(
+            ProtocolId::ConsensusDirectSendBcs,
+            3 * MAX_FRAME_SIZE,
+        ));
+        let header = match outbound_queues.pop() {
+            Some(MultiplexMessage::Stream(StreamMessage::Header(header))) => header,
+            frame => panic!("Expected a stream header, but got: {:?}", frame),
+        };
+        assert_eq!(header.message.priority_class(), PriorityClass::High);
+        for _ in 0..header.num_fragments {
+            match outbound_queues.pop() {
+                Some(MultiplexMessage::Stream(StreamMessage::Fragment(fragment))) => {
+                    assert_eq!(fragment.request_id, header.request_id)
+                },
+                frame => panic!("Expected a consensus fragment, but got: {:?}", frame),
+            }
+        }
+
+        // Verify the storage service response is then resumed
+        while let Some(frame) = outbound_queues.pop() {
+            match frame {
+                MultiplexMessage::Stream(StreamMessage::Fragment(fragment)) => {
+                    assert_eq!(fragment.request_id, bulk_request_id)
+                },
+                frame => panic!("Expected a bulk fragment, but got: {:?}", frame),
+            }
+        }
+        assert!(outbound_queues.is_empty());
+    }
+
+    #[test]
+    fn test_one_active_stream_per_class() {
+        let mut outbound_queues = create_outbound_queues(MAX_QUEUED_MESSAGES, true);
+
+        // Queue two large bulk messages, followed by a large and small normal message
+        outbound_queues.push(direct_send(
+            ProtocolId::StateSyncDirectSend,
+            3 * MAX_FRAME_SIZE,
+        ));
+        outbound_queues.push(rpc_response(
+            ProtocolId::StorageServiceRpc,
+            3 * MAX_FRAME_SIZE,
+        ));
+        outbound_queues.push(direct_send(
+            ProtocolId::MempoolDirectSend,
+            3 * MAX_FRAME_SIZE,
+        ));
+        outbound_queues.push(direct_send(ProtocolId::MempoolDirectSend, 10));
+
+        // Verify streams of different classes interleave, but never within a class
+        let mut active_streams = HashMap::new(); // Priority class -> (request id, fragments left)
+        let mut num_streams = 0;
+        while let Some(frame) = outbound_queues.pop() {
+            match frame {
+                MultiplexMessage::Stream(StreamMessage::Header(header)) => {
+                    let priority_class = header.message.priority_class();
+                    let active_stream = (header.request_id, header.num_fragments);
+                    assert!(active_streams
+                        .insert(priority_class, active_stream)
+                        .is_none());
+                    num_streams += 1;
+                },
+                MultiplexMessage::Stream(StreamMessage::Fragment(fragment)) => {
+                    // Fragments must always belong to an active stream
+                    let priority_class = *active_streams
+                        .iter()
+                        .find(|(_, (request_id, _))| *request_id == fragment.request_id)
+                        .unwrap()
+                        .0;
+                    let (_, fragments_left) = active_streams.get_mut(&priority_class).unwrap();
+                    *fragments_left -= 1;
+                    if *fragments_left == 0 {
+                        active_streams.remove(&priority_class);
+                    }
+                },
+                MultiplexMessage::Message(_) => {},
+            }
+        }
+        assert_eq!(num_streams, 3);
+        assert!(active_streams.is_empty());
+        assert!(outbound_queues.is_empty());
+    }
+
+    #[test]
+    fn test_queue_limit() {
+        let mut outbound_queues = create_outbound_queues(3, true);
+
+        // Fill the queues and verify they're full
+        outbound_queues.push(direct_send(ProtocolId::StateSyncDirectSend, 60));
+        outbound_queues.push(rpc_response(ProtocolId::StorageServiceRpc, 40));
+        assert!(!outbound_queues.is_full());
+        outbound_queues.push(rpc_response(ProtocolId::ConsensusRpcBcs, 100));
+        assert!(outbound_queues.is_full());
+
+        // Verify messages are never dropped (even when the queues are full)
+        outbound_queues.push(OutboundMessage::error(ErrorCode::parsing_error(0, 0)));
+        let priority_classes = pop_all_classes(&mut outbound_queues);
+        assert_eq!(
+            priority_classes,
+            vec![
+                PriorityClass::High,
+                PriorityClass::High,
+                PriorityClass::Bulk,
+                PriorityClass::Bulk
+            ]
+        );
+
+        // Verify space is freed up once the messages are written
+        assert!(!outbound_queues.is_full());
+        outbound_queues.push(direct_send(ProtocolId::StateSyncDirectSend, 100));
+        outbound_queues.clear();
+        assert!(outbound_queues.is_empty());
+        assert!(!outbound_queues.is_full());
+    }
+
+    #[test]
+    fn test_one_active_stream_without_interleaving() {
+        let mut outbound_queues = create_outbound_queues(MAX_QUEUED_MESSAGES, false);
+
+        // Queue a large storage service response and start streaming it
+        outbound_queues.push(rpc_response(
+            ProtocolId::StorageServiceRpc,
+            3 * MAX_FRAME_SIZE,
+        ));
+        let bulk_header = match outbound_queues.pop() {
+            Some(MultiplexMessage::Stream(StreamMessage::Header(header))) => header,
+            frame => panic!("Expected a stream header, but got: {:?}", frame),
+        };
+
+        // Queue a large and a small consensus message, and verify the small one is sent
+        // first, as the large one must wait for the storage service response to complete.
+        outbound_queues.push(direct_send(
+            ProtocolId::ConsensusDirectSendBcs,
+            3 * MAX_FRAME_SIZE,
+        ));
+        outbound_queues.push(direct_send(ProtocolId::ConsensusRpcBcs, 10));
+        match outbound_queues.pop() {
+            Some(MultiplexMessage::Message(message)) => {
+                assert_eq!(message.priority_class(), PriorityClass::High)
+            },
+            frame => panic!("Expected a consensus message, but got: {:?}", frame),
+        }
+        for _ in 0..bulk_header.num_fragments {
+            match outbound_queues.pop() {
+                Some(MultiplexMessage::Stream(StreamMessage::Fragment(fragment))) => {
+                    assert_eq!(fragment.request_id, bulk_header.request_id)
+                },
+                frame => panic!("Expected a bulk fragment, but got: {:?}", frame),
+            }
+        }
+
+        // Verify the consensus message is then streamed
+        match outbound_queues.pop() {
+            Some(MultiplexMessage::Stream(StreamMessage::Header(header))) => {
+                assert_eq!(header.message.priority_class(), PriorityClass::High)
+            },
+            frame => panic!("Expected a stream header, but got: {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn test_legacy_receiver() {
+        // Verify a legacy receiver gets every message if streams don't interleave
+        let mut outbound_queues = create_outbound_queues(MAX_QUEUED_MESSAGES, false);
+        let num_messages = push_large_and_small_messages(&mut outbound_queues);
+        let mut inbound_stream = LegacyInboundStreamBuffer::default();
+        let mut num_received_messages = 0;
+        while let Some(frame) = outbound_queues.pop() {
+            if inbound_stream.receive(frame).unwrap() {
+                num_received_messages += 1;
+            }
+        }
+        assert_eq!(num_received_messages, num_messages);
+
+        // Verify a legacy receiver discards streams if they interleave
+        let mut outbound_queues = create_outbound_queues(MAX_QUEUED_MESSAGES, true);
+        push_large_and_small_messages(&mut outbound_queues);
+        let mut inbound_stream = LegacyInboundStreamBuffer::default();
+        let mut frames = std::iter::from_fn(|| outbound_queues.pop());
+        assert!(frames.any(|frame| inbound_stream.receive(frame).is_err()));
+    }
+}
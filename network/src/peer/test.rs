@@ -65,6 +65,7 @@ fn build_test_peer(
             origin,
             MessagingProtocolVersion::V1,
             ProtocolIdSet::empty(),
+            true,
             PeerRole::Unknown,
         ),
         socket: a,
@@ -611,7 +612,7 @@ fn peer_send_rpc() {
             };
 
             assert_eq!(received.protocol_id, PROTOCOL);
-            assert_eq!(received.priority, 0);
+            assert_eq!(received.priority, PROTOCOL.priority_class().as_priority());
             assert_eq!(received.raw_request, b"hello world");
 
             assert!(
@@ -680,7 +681,7 @@ fn peer_send_rpc_concurrent() {
             };
 
             assert_eq!(received.protocol_id, PROTOCOL);
-            assert_eq!(received.priority, 0);
+            assert_eq!(received.priority, PROTOCOL.priority_class().as_priority());
             assert_eq!(received.raw_request, b"hello world");
 
             assert!(
@@ -735,7 +736,7 @@ fn peer_send_rpc_cancel() {
         };
 
         assert_eq!(received.protocol_id, PROTOCOL);
-        assert_eq!(received.priority, 0);
+        assert_eq!(received.priority, PROTOCOL.priority_class().as_priority());
         assert_eq!(received.raw_request, b"hello world");
 
         // Request should still be live. Ok(_) means the sender is not dropped.
@@ -796,7 +797,7 @@ fn peer_send_rpc_timeout() {
         };
 
         assert_eq!(received.protocol_id, PROTOCOL);
-        assert_eq!(received.priority, 0);
+        assert_eq!(received.priority, PROTOCOL.priority_class().as_priority());
         assert_eq!(received.raw_request, b"hello world");
 
         // Request should still be live. Ok(_) means the sender is not dropped.
@@ -60,6 +60,7 @@ pub fn build_test_transport(
                     origin,
                     MessagingProtocolVersion::V1,
                     ProtocolIdSet::mock(),
+                    true,
                     PeerRole::Unknown,
                 ),
             })
@@ -245,6 +246,7 @@ fn create_connection<TSocket: transport::TSocket>(
             origin,
             MessagingProtocolVersion::V1,
             ProtocolIdSet::mock(),
+            true,
             PeerRole::Unknown,
         ),
     }
@@ -583,6 +585,7 @@ fn peer_manager_simultaneous_dial_disconnect_event() {
                 ConnectionOrigin::Inbound,
                 MessagingProtocolVersion::V1,
                 ProtocolIdSet::mock(),
+                true,
                 PeerRole::Unknown,
             ),
             DisconnectReason::ConnectionLost,
@@ -639,6 +642,7 @@ fn test_dial_disconnect() {
                 ConnectionOrigin::Outbound,
                 MessagingProtocolVersion::V1,
                 ProtocolIdSet::mock(),
+                true,
                 PeerRole::Unknown,
             ),
             DisconnectReason::Requested,
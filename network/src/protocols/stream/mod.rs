@@ -1,15 +1,13 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::protocols::wire::messaging::v1::{MultiplexMessage, NetworkMessage};
+use crate::protocols::wire::messaging::v1::{NetworkMessage, PriorityClass};
 use anyhow::{bail, ensure};
-use aptos_channels::Sender;
 use aptos_id_generator::{IdGenerator, U32IdGenerator};
-use futures_util::SinkExt;
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
@@ -58,24 +56,27 @@ impl Debug for StreamFragment {
     }
 }
 
+/// Reassembles inbound streams. Each priority class has at most one stream in flight,
+/// so a higher priority stream may interleave with a lower priority one.
 pub struct InboundStreamBuffer {
-    stream: Option<InboundStream>,
+    streams: HashMap<PriorityClass, InboundStream>,
     max_fragments: usize,
 }
 
 impl InboundStreamBuffer {
     pub fn new(max_fragments: usize) -> Self {
         Self {
-            stream: None,
+            streams: HashMap::new(),
             max_fragments,
         }
     }
 
     pub fn new_stream(&mut self, header: StreamHeader) -> anyhow::Result<()> {
-        if let Some(old) = self
-            .stream
-            .replace(InboundStream::new(header, self.max_fragments)?)
-        {
+        let priority_class = header.message.priority_class();
+        if let Some(old) = self.streams.insert(
+            priority_class,
+            InboundStream::new(header, self.max_fragments)?,
+        ) {
             bail!("Discard existing stream {}", old.request_id)
         } else {
             Ok(())
@@ -86,13 +87,19 @@ impl InboundStreamBuffer {
         &mut self,
         fragment: StreamFragment,
     ) -> anyhow::Result<Option<NetworkMessage>> {
-        let stream = self
-            .stream
-            .as_mut()
+        let priority_class = self
+            .streams
+            .iter()
+            .find(|(_, stream)| stream.request_id == fragment.request_id)
+            .map(|(priority_class, _)| *priority_class)
             .ok_or_else(|| anyhow::anyhow!("No stream exist"))?;
+        let stream = self
+            .streams
+            .get_mut(&priority_class)
+            .expect("Stream should exist");
         let stream_end = stream.append_fragment(fragment)?;
         if stream_end {
-            Ok(Some(self.stream.take().unwrap().message))
+            Ok(Some(self.streams.remove(&priority_class).unwrap().message))
         } else {
             Ok(None)
         }
@@ -151,15 +158,10 @@ pub struct OutboundStream {
     request_id_gen: U32IdGenerator,
     max_frame_size: usize,
     max_message_size: usize,
-    stream_tx: Sender<MultiplexMessage>,
 }
 
 impl OutboundStream {
-    pub fn new(
-        max_frame_size: usize,
-        max_message_size: usize,
-        stream_tx: Sender<MultiplexMessage>,
-    ) -> Self {
+    pub fn new(max_frame_size: usize, max_message_size: usize) -> Self {
         // some buffer for headers
         let max_frame_size = max_frame_size - 64;
         assert!(
@@ -172,7 +174,6 @@ impl OutboundStream {
             request_id_gen: U32IdGenerator::new(),
             max_frame_size,
             max_message_size,
-            stream_tx,
         }
     }
 
@@ -180,7 +181,13 @@ impl OutboundStream {
         message.data_len() > self.max_frame_size
     }
 
-    pub async fn stream_message(&mut self, mut message: NetworkMessage) -> anyhow::Result<()> {
+    /// Splits the given message into a stream header followed by its fragments. The
+    /// frames of a stream must be sent in order, and without interleaving other streams
+    /// of the same priority class.
+    pub fn stream_message(
+        &mut self,
+        mut message: NetworkMessage,
+    ) -> anyhow::Result<Vec<StreamMessage>> {
         ensure!(
             message.data_len() <= self.max_message_size,
             "Message length {} exceed size limit {}",
@@ -218,19 +225,14 @@ impl OutboundStream {
             num_fragments: chunks.len() as u8,
             message,
         });
-        self.stream_tx
-            .send(MultiplexMessage::Stream(header))
-            .await?;
+        let mut stream_messages = vec![header];
         for (index, chunk) in chunks.enumerate() {
-            let message = StreamMessage::Fragment(StreamFragment {
+            stream_messages.push(StreamMessage::Fragment(StreamFragment {
                 request_id,
                 fragment_id: index as u8 + 1,
                 raw_data: Vec::from(chunk),
-            });
-            self.stream_tx
-                .send(MultiplexMessage::Stream(message))
-                .await?;
+            }));
         }
-        Ok(())
+        Ok(stream_messages)
     }
 }
@@ -12,6 +12,7 @@
 //!
 //! [AptosNet Handshake v1 Specification]: https://github.com/aptos-labs/aptos-core/blob/main/specifications/network/handshake-v1.md
 
+use crate::protocols::wire::messaging::v1::PriorityClass;
 use anyhow::anyhow;
 use aptos_compression::metrics::CompressionClient;
 use aptos_config::{config::MAX_APPLICATION_MESSAGE_SIZE, network_id::NetworkId};
@@ -102,6 +103,26 @@ impl ProtocolId {
         ]
     }
 
+    /// The priority class used when sending messages for a given `ProtocolId`.
+    /// Consensus (and health checker) messages preempt everything else, while
+    /// large state sync messages are sent with the lowest priority.
+    pub fn priority_class(self) -> PriorityClass {
+        use ProtocolId::*;
+        match self {
+            ConsensusRpcBcs
+            | ConsensusDirectSendBcs
+            | ConsensusDirectSendJson
+            | ConsensusRpcJson
+            | ConsensusRpcCompressed
+            | ConsensusDirectSendCompressed
+            | HealthCheckerRpc => PriorityClass::High,
+            StateSyncDirectSend | StorageServiceRpc => PriorityClass::Bulk,
+            MempoolDirectSend | MempoolRpc | DiscoveryDirectSend | PeerMonitoringServiceRpc => {
+                PriorityClass::Normal
+            },
+        }
+    }
+
     /// How to encode messages for a given `ProtocolId`
     fn encoding(self) -> Encoding {
         match self {
@@ -193,12 +214,19 @@ impl fmt::Display for ProtocolId {
 // ProtocolIdSet
 //
 
+/// The bit of a [`ProtocolIdSet`] advertising that the node reassembles an inbound
+/// stream per priority class, i.e., that streams of different classes may be interleaved.
+/// Older nodes reassemble a single stream at a time, and ignore the bit since it doesn't
+/// map to any [`ProtocolId`].
+const INTERLEAVED_STREAMS_BIT: u8 = u8::MAX;
+
 /// A compact representation for a set of [`ProtocolId`]s. Internally, this is a
 /// bitvec which supports at most 256 bits.
 ///
 /// These sets are sent over-the-wire in the initial [`HandshakeMsg`] to other
 /// AptosNet peers in order to negotiate the set of common supported protocols for
-/// use on a new AptosNet connection.
+/// use on a new AptosNet connection. The sets may also carry feature bits
+/// (e.g., whether streams may be interleaved).
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 pub struct ProtocolIdSet(aptos_bitvec::BitVec);
@@ -248,6 +276,28 @@ impl ProtocolIdSet {
     pub fn insert(&mut self, protocol: ProtocolId) {
         self.0.set(protocol as u16)
     }
+
+    /// Returns if the interleaved streams feature bit is set.
+    pub fn supports_interleaved_streams(&self) -> bool {
+        self.0.is_set(INTERLEAVED_STREAMS_BIT as u16)
+    }
+
+    /// Returns the set with the interleaved streams feature bit set.
+    pub fn with_interleaved_streams(mut self) -> ProtocolIdSet {
+        self.0.set(INTERLEAVED_STREAMS_BIT as u16);
+        self
+    }
+
+    /// Returns the set without the interleaved streams feature bit, i.e., only the protocols.
+    pub fn without_interleaved_streams(&self) -> ProtocolIdSet {
+        ProtocolIdSet(
+            self.0
+                .iter_ones()
+                .filter(|idx| *idx != INTERLEAVED_STREAMS_BIT as usize)
+                .map(|idx| idx as u8)
+                .collect(),
+        )
+    }
 }
 
 impl FromIterator<ProtocolId> for ProtocolIdSet {
@@ -342,7 +392,8 @@ impl HandshakeMsg {
 
     /// This function:
     /// 1. verifies that both HandshakeMsg are compatible and
-    /// 2. finds out the intersection of protocols that is supported
+    /// 2. finds out the intersection of protocols that is supported (along with
+    ///    the common feature bits)
     pub fn perform_handshake(
         &self,
         other: &HandshakeMsg,
@@ -369,7 +420,7 @@ impl HandshakeMsg {
             if let Some(their_protocols) = other.supported_protocols.get(our_handshake_version) {
                 let common_protocols = our_protocols.intersect(their_protocols);
 
-                if !common_protocols.is_empty() {
+                if !common_protocols.without_interleaved_streams().is_empty() {
                     return Ok((*our_handshake_version, common_protocols));
                 }
             }
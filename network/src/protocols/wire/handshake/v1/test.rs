@@ -182,3 +182,35 @@ fn ignore_unknown_protocols() {
         ProtocolIdSet::empty(),
     );
 }
+
+#[test]
+fn interleaved_streams() {
+    let protocols = ProtocolIdSet::from_iter([ProtocolId::MempoolDirectSend]);
+    let new_hs = HandshakeMsg::from_supported(protocols.clone().with_interleaved_streams());
+    let old_hs = HandshakeMsg::from_supported(protocols.clone());
+
+    // Case 1: both peers support interleaved streams
+    let (_, common_protos) = new_hs.perform_handshake(&new_hs).unwrap();
+    assert!(common_protos.supports_interleaved_streams());
+    assert_eq!(common_protos.without_interleaved_streams(), protocols);
+    assert_eq!(
+        ProtocolIdSet::from_iter(common_protos.iter()),
+        protocols.clone()
+    );
+
+    // Case 2: only one of the peers supports interleaved streams
+    let (_, common_protos) = new_hs.perform_handshake(&old_hs).unwrap();
+    assert!(!common_protos.supports_interleaved_streams());
+    let (_, common_protos) = old_hs.perform_handshake(&new_hs).unwrap();
+    assert!(!common_protos.supports_interleaved_streams());
+    assert_eq!(common_protos, protocols);
+
+    // Case 3: the feature bit alone isn't a common protocol
+    let other_hs = HandshakeMsg::from_supported(
+        ProtocolIdSet::from_iter([ProtocolId::StateSyncDirectSend]).with_interleaved_streams(),
+    );
+    assert_eq!(
+        new_hs.perform_handshake(&other_hs).unwrap_err(),
+        HandshakeError::NoCommonProtocols,
+    );
+}
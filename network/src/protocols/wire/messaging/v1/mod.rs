@@ -62,6 +62,21 @@ impl NetworkMessage {
             NetworkMessage::DirectSendMsg(message) => message.raw_msg.len(),
         }
     }
+
+    /// The priority class of the message, as encoded in its priority field.
+    /// Error messages are always sent with the highest priority.
+    pub fn priority_class(&self) -> PriorityClass {
+        match self {
+            NetworkMessage::Error(_) => PriorityClass::High,
+            NetworkMessage::RpcRequest(request) => PriorityClass::from_priority(request.priority),
+            NetworkMessage::RpcResponse(response) => {
+                PriorityClass::from_priority(response.priority)
+            },
+            NetworkMessage::DirectSendMsg(message) => {
+                PriorityClass::from_priority(message.priority)
+            },
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
@@ -103,6 +118,52 @@ pub type RequestId = u32;
 /// Create alias Priority for u8.
 pub type Priority = u8;
 
+/// The priority classes used to schedule outbound messages on a connection. Each
+/// protocol maps to a single class (see [`ProtocolId::priority_class`]), and the
+/// class is encoded in the priority field of the messages sent for that protocol.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum PriorityClass {
+    /// Large, throughput sensitive messages (e.g., storage service responses)
+    Bulk,
+    /// Messages that are neither latency nor throughput sensitive (e.g., mempool)
+    Normal,
+    /// Small, latency sensitive messages (e.g., consensus votes)
+    High,
+}
+
+impl PriorityClass {
+    pub fn all() -> &'static [PriorityClass] {
+        &[
+            PriorityClass::High,
+            PriorityClass::Normal,
+            PriorityClass::Bulk,
+        ]
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PriorityClass::Bulk => "bulk",
+            PriorityClass::Normal => "normal",
+            PriorityClass::High => "high",
+        }
+    }
+
+    /// Returns the wire priority of the class
+    pub fn as_priority(self) -> Priority {
+        self as Priority
+    }
+
+    /// Returns the class of the given wire priority. Unknown
+    /// priorities (i.e., from newer peers) are treated as high.
+    pub fn from_priority(priority: Priority) -> Self {
+        match priority {
+            0 => PriorityClass::Bulk,
+            1 => PriorityClass::Normal,
+            _ => PriorityClass::High,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 pub struct RpcRequest {
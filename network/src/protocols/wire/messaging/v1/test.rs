@@ -9,7 +9,6 @@ use crate::{
 use aptos_memsocket::MemorySocket;
 use bcs::test_helpers::assert_canonical_encode_decode;
 use futures::{executor::block_on, future, sink::SinkExt, stream::StreamExt};
-use futures_util::stream::select;
 use proptest::{collection::vec, prelude::*};
 
 // Ensure serialization of ProtocolId enum takes 1 byte.
@@ -234,16 +233,20 @@ proptest! {
 
         let mut message_tx = MultiplexMessageSink::new(socket_tx, 128, None);
         let message_rx = MultiplexMessageStream::new(socket_rx, 128, None);
-        let (stream_tx, stream_rx) = aptos_channels::new_test(1024);
-        let (mut msg_tx, msg_rx) = aptos_channels::new_test(1024);
-        let mut outbound_stream = OutboundStream::new(128, 64 * 255, stream_tx);
+        let (mut msg_tx, mut msg_rx) = aptos_channels::new_test(1024);
+        let mut outbound_stream = OutboundStream::new(128, 64 * 255);
         let mut inbound_stream = InboundStreamBuffer::new(255);
 
         let messages_clone = messages.clone();
         let f_stream_all = async move {
             for message in messages_clone {
                 if outbound_stream.should_stream(&message) {
-                    outbound_stream.stream_message(message).await.unwrap();
+                    for stream_message in outbound_stream.stream_message(message).unwrap() {
+                        msg_tx
+                            .send(MultiplexMessage::Stream(stream_message))
+                            .await
+                            .unwrap();
+                    }
                 } else {
                     msg_tx.send(MultiplexMessage::Message(message)).await.unwrap();
                 }
@@ -251,8 +254,7 @@ proptest! {
         };
 
         let f_send_all = async {
-            let mut stream = select(msg_rx, stream_rx);
-            while let Some(message) = stream.next().await {
+            while let Some(message) = msg_rx.next().await {
                 message_tx.send(&message).await.unwrap();
             }
             message_tx.close().await.unwrap();
@@ -104,6 +104,7 @@ pub struct ConnectionMetadata {
     pub origin: ConnectionOrigin,
     pub messaging_protocol: MessagingProtocolVersion,
     pub application_protocols: ProtocolIdSet,
+    pub interleaved_streams: bool, // Whether streams of different priority classes may interleave
     pub role: PeerRole,
 }
 
@@ -115,6 +116,7 @@ impl ConnectionMetadata {
         origin: ConnectionOrigin,
         messaging_protocol: MessagingProtocolVersion,
         application_protocols: ProtocolIdSet,
+        interleaved_streams: bool,
         role: PeerRole,
     ) -> ConnectionMetadata {
         ConnectionMetadata {
@@ -124,6 +126,7 @@ impl ConnectionMetadata {
             origin,
             messaging_protocol,
             application_protocols,
+            interleaved_streams,
             role,
         }
     }
@@ -151,6 +154,7 @@ impl ConnectionMetadata {
             addr: NetworkAddress::mock(),
             messaging_protocol: MessagingProtocolVersion::V1,
             application_protocols: ProtocolIdSet::empty(),
+            interleaved_streams: true,
         }
     }
 }
@@ -314,6 +318,10 @@ async fn upgrade_inbound<T: TSocket>(
             )
         })?;
 
+    // the interleaved streams feature bit is only kept if both peers set it
+    let interleaved_streams = application_protocols.supports_interleaved_streams();
+    let application_protocols = application_protocols.without_interleaved_streams();
+
     // return successful connection
     Ok(Connection {
         socket,
@@ -324,6 +332,7 @@ async fn upgrade_inbound<T: TSocket>(
             origin,
             messaging_protocol,
             application_protocols,
+            interleaved_streams,
             peer_role,
         ),
     })
@@ -384,6 +393,10 @@ pub async fn upgrade_outbound<T: TSocket>(
             io::Error::new(io::ErrorKind::Other, e)
         })?;
 
+    // the interleaved streams feature bit is only kept if both peers set it
+    let interleaved_streams = application_protocols.supports_interleaved_streams();
+    let application_protocols = application_protocols.without_interleaved_streams();
+
     // return successful connection
     Ok(Connection {
         socket,
@@ -394,6 +407,7 @@ pub async fn upgrade_outbound<T: TSocket>(
             origin,
             messaging_protocol,
             application_protocols,
+            interleaved_streams,
             PeerRole::Unknown,
         ),
     })
@@ -440,7 +454,10 @@ where
     ) -> Self {
         // build supported protocols
         let mut supported_protocols = BTreeMap::new();
-        supported_protocols.insert(SUPPORTED_MESSAGING_PROTOCOL, application_protocols);
+        supported_protocols.insert(
+            SUPPORTED_MESSAGING_PROTOCOL,
+            application_protocols.with_interleaved_streams(),
+        );
 
         let identity_pubkey = identity_key.public_key();
 
@@ -257,6 +257,7 @@ fn test_transport_success<TTransport>(
             conn.metadata.application_protocols,
             supported_protocols_clone,
         );
+        assert!(conn.metadata.interleaved_streams);
 
         // test the socket works
         let msg = write_read_msg(&mut conn.socket, b"foobar").await;
@@ -283,6 +284,7 @@ fn test_transport_success<TTransport>(
             MessagingProtocolVersion::V1
         );
         assert_eq!(conn.metadata.application_protocols, supported_protocols);
+        assert!(conn.metadata.interleaved_streams);
 
         // test the socket works
         let msg = write_read_msg(&mut conn.socket, b"barbaz").await;
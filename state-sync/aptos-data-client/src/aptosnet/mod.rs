@@ -13,6 +13,10 @@ use crate::{
     AptosDataClient, Error, GlobalDataSummary, Response, ResponseCallback, ResponseContext,
     ResponseError, ResponseId, Result,
 };
+use aptos_compression::{
+    dictionary::{self, DictionaryId},
+    CompressionCodec,
+};
 use aptos_config::{
     config::{AptosDataClientConfig, BaseConfig, StorageServiceConfig},
     network_id::PeerNetworkId,
@@ -29,7 +33,10 @@ use aptos_storage_service_types::{
         StateValuesWithProofRequest, StorageServiceRequest, TransactionOutputsWithProofRequest,
         TransactionsOrOutputsWithProofRequest, TransactionsWithProofRequest,
     },
-    responses::{StorageServerSummary, StorageServiceResponse, TransactionOrOutputListWithProof},
+    responses::{
+        ServerProtocolVersion, StorageServerSummary, StorageServiceResponse,
+        TransactionOrOutputListWithProof,
+    },
     Epoch, StorageServiceMessage,
 };
 use aptos_time_service::{TimeService, TimeServiceTrait};
@@ -41,7 +48,7 @@ use aptos_types::{
 };
 use async_trait::async_trait;
 use futures::StreamExt;
-use rand::seq::SliceRandom;
+use rand::{seq::SliceRandom, Rng};
 use std::{convert::TryFrom, fmt, sync::Arc, time::Duration};
 use tokio::{runtime::Handle, task::JoinHandle};
 
@@ -62,6 +69,14 @@ const PEER_LOG_FREQ_SECS: u64 = 10;
 const POLLER_LOG_FREQ_SECS: u64 = 2;
 const REGULAR_PEER_SAMPLE_FREQ: u64 = 3;
 
+// The (inverse) probability of refreshing the compression codecs of a peer
+// that doesn't offer a dictionary yet (e.g., the dictionary is still being trained),
+// or the configured codec.
+const COMPRESSION_CODECS_REFRESH_FREQ: u32 = 100;
+
+// The Zstd level to request with dictionaries (unless Zstd is configured)
+const DEFAULT_DICTIONARY_COMPRESSION_LEVEL: i32 = 3;
+
 /// An [`AptosDataClient`] that fulfills requests from remote peers' Storage Service
 /// over AptosNet.
 ///
@@ -128,6 +143,143 @@ impl AptosNetDataClient {
         self.data_client_config.use_compression
     }
 
+    /// Returns the compression codec to request from the given peer for the
+    /// given request. State value requests use the peer's dictionary (if it's
+    /// offered and registered locally). Otherwise, this is the configured codec
+    /// if the peer supports it, or LZ4 (which is supported by all peers).
+    fn get_compression_codec(
+        &self,
+        peer: &PeerNetworkId,
+        request: &StorageServiceRequest,
+    ) -> CompressionCodec {
+        if matches!(
+            request.get_data_request(),
+            DataRequest::GetStateValuesWithProof(_)
+        ) {
+            if let Some(compression_codec) = self.get_dictionary_compression_codec(peer) {
+                return compression_codec;
+            }
+        }
+
+        let compression_codec = self.data_client_config.compression_codec;
+        if self
+            .peer_states
+            .read()
+            .supports_compression_codec(peer, &compression_codec)
+        {
+            compression_codec
+        } else {
+            CompressionCodec::Lz4
+        }
+    }
+
+    /// Returns the dictionary codec to request from the given peer (if
+    /// dictionaries are enabled and the peer's dictionary is registered).
+    fn get_dictionary_compression_codec(&self, peer: &PeerNetworkId) -> Option<CompressionCodec> {
+        let (max_level, dictionary_id) = self.get_offered_dictionary(peer)?;
+        if !dictionary::is_dictionary_registered(dictionary_id) {
+            return None;
+        }
+        let compression_level = match self.data_client_config.compression_codec {
+            CompressionCodec::Zstd(compression_level) => compression_level,
+            _ => DEFAULT_DICTIONARY_COMPRESSION_LEVEL,
+        };
+        Some(CompressionCodec::ZstdWithDictionary(
+            compression_level.min(max_level),
+            dictionary_id,
+        ))
+    }
+
+    /// Returns the max level and identifier of the dictionary offered by the
+    /// peer (if dictionaries are enabled). Only the first dictionary offered
+    /// is used, so a peer can't make us fetch (and register) many of them.
+    fn get_offered_dictionary(&self, peer: &PeerNetworkId) -> Option<(i32, DictionaryId)> {
+        if !self.data_client_config.use_compression_dictionaries {
+            return None;
+        }
+        self.peer_states
+            .read()
+            .get_compression_codecs(peer)
+            .into_iter()
+            .find_map(|compression_codec| match compression_codec {
+                CompressionCodec::ZstdWithDictionary(max_level, dictionary_id) => {
+                    Some((max_level, dictionary_id))
+                },
+                _ => None,
+            })
+    }
+
+    /// Returns true iff the compression codecs of the peer should be fetched,
+    /// i.e., to negotiate a codec other than LZ4 (or a dictionary).
+    fn requires_compression_codecs(&self, peer: &PeerNetworkId) -> bool {
+        let use_dictionaries = self.data_client_config.use_compression_dictionaries;
+        if !self.use_compression()
+            || (self.data_client_config.compression_codec == CompressionCodec::Lz4
+                && !use_dictionaries)
+        {
+            return false;
+        }
+
+        // Fetch the codecs if they're unknown. Otherwise, periodically refresh
+        // them if the peer might still offer a dictionary or the configured
+        // codec (e.g., it fell back to LZ4 before being upgraded).
+        let peer_states = self.peer_states.read();
+        if !peer_states.has_compression_codecs(peer) {
+            return true;
+        }
+        let compression_codecs = peer_states.get_compression_codecs(peer);
+        let missing_dictionary = use_dictionaries
+            && !compression_codecs
+                .iter()
+                .any(|codec| matches!(codec, CompressionCodec::ZstdWithDictionary(..)));
+        let missing_codec = !self
+            .data_client_config
+            .compression_codec
+            .is_supported_by(&compression_codecs);
+        (missing_dictionary || missing_codec)
+            && rand::thread_rng().gen_ratio(1, COMPRESSION_CODECS_REFRESH_FREQ)
+    }
+
+    /// Update a peer's compression codecs.
+    fn update_compression_codecs(
+        &self,
+        peer: PeerNetworkId,
+        compression_codecs: Vec<CompressionCodec>,
+    ) {
+        self.peer_states
+            .write()
+            .update_compression_codecs(peer, compression_codecs)
+    }
+
+    /// Returns the dictionary offered by the peer if it isn't yet registered
+    /// (e.g., it hasn't been fetched, or it was evicted from the registry).
+    fn get_missing_dictionary(&self, peer: &PeerNetworkId) -> Option<DictionaryId> {
+        self.get_offered_dictionary(peer)
+            .map(|(_, dictionary_id)| dictionary_id)
+            .filter(|dictionary_id| !dictionary::is_dictionary_registered(*dictionary_id))
+    }
+
+    /// Fetches the specified dictionary from the peer, verifies
+    /// it (against the identifier) and registers it.
+    async fn fetch_compression_dictionary(
+        &self,
+        peer: PeerNetworkId,
+        dictionary_id: DictionaryId,
+        request_timeout_ms: u64,
+    ) -> Result<()> {
+        dictionary::request_dictionary(dictionary_id);
+        let data_request = DataRequest::GetCompressionDictionary(dictionary_id);
+        let storage_request = StorageServiceRequest::new(data_request, self.use_compression());
+        let compression_dictionary: Vec<u8> = self
+            .send_request_to_peer_and_decode(peer, storage_request, request_timeout_ms)
+            .await?
+            .into_payload();
+
+        // Verify and register the dictionary
+        dictionary::register_requested_dictionary(dictionary_id, compression_dictionary)
+            .map_err(|error| Error::InvalidResponse(error.to_string()))
+    }
+
     /// Returns the max number of output reductions as defined by the config
     fn get_max_num_output_reductions(&self) -> u64 {
         self.data_client_config.max_num_output_reductions
@@ -320,6 +472,16 @@ impl AptosNetDataClient {
         T: TryFrom<StorageServiceResponse, Error = E>,
         E: Into<Error>,
     {
+        // Request the best compression codec supported by the peer
+        let request = if request.use_compression {
+            let compression_codec = self.get_compression_codec(&peer, &request);
+            StorageServiceRequest::new_with_compression_codec(
+                request.data_request,
+                compression_codec,
+            )
+        } else {
+            request
+        };
         let response = self
             .send_request_to_peer(peer, request.clone(), request_timeout_ms)
             .await?;
@@ -337,6 +499,12 @@ impl AptosNetDataClient {
                 "Requested uncompressed data, but the response was compressed! Response: {:?}",
                 storage_response.get_label()
             )));
+        } else if request.get_compression_codec() != storage_response.get_compression_codec() {
+            return Err(Error::InvalidResponse(format!(
+                "Requested a different compression codec! Requested: {:?}, response: {:?}",
+                request.get_compression_codec(),
+                storage_response.get_compression_codec()
+            )));
         }
 
         // try to convert the storage service enum into the exact variant we're expecting.
@@ -826,11 +994,61 @@ pub(crate) fn poll_peer(
 
     // Create the poller for the peer
     let poller = async move {
+        let request_timeout = data_client.data_client_config.response_timeout_ms;
+
+        // Fetch the compression codecs of the peer (if required)
+        if data_client.requires_compression_codecs(&peer) {
+            let data_request = DataRequest::GetServerProtocolVersion;
+            let storage_request =
+                StorageServiceRequest::new(data_request, data_client.use_compression());
+            let result: Result<ServerProtocolVersion> = data_client
+                .send_request_to_peer_and_decode(peer, storage_request, request_timeout)
+                .await
+                .map(Response::into_payload);
+            match result {
+                Ok(server_protocol_version) => data_client.update_compression_codecs(
+                    peer,
+                    server_protocol_version.supported_compression_codecs,
+                ),
+                Err(error) => {
+                    // Only fall back to LZ4 if the peer answered with a response we can't
+                    // decode (e.g., the peer doesn't support codec negotiation). Other errors
+                    // (e.g., timeouts) may be transient, so the codecs are left unknown and
+                    // fetched again on the next poll (using LZ4 meanwhile).
+                    if matches!(error, Error::InvalidResponse(_)) {
+                        data_client.update_compression_codecs(peer, vec![CompressionCodec::Lz4]);
+                    }
+                    warn!(
+                        (LogSchema::new(LogEntry::StorageSummaryResponse)
+                            .event(LogEvent::PeerPollingError)
+                            .message("Error encountered when fetching the peer compression codecs!")
+                            .error(&error)
+                            .peer(&peer))
+                    );
+                },
+            }
+        }
+
+        // Fetch the dictionary offered by the peer (if we don't have it)
+        if let Some(dictionary_id) = data_client.get_missing_dictionary(&peer) {
+            if let Err(error) = data_client
+                .fetch_compression_dictionary(peer, dictionary_id, request_timeout)
+                .await
+            {
+                warn!(
+                    (LogSchema::new(LogEntry::StorageSummaryResponse)
+                        .event(LogEvent::PeerPollingError)
+                        .message("Error encountered when fetching a peer compression dictionary!")
+                        .error(&error)
+                        .peer(&peer))
+                );
+            }
+        }
+
         // Construct the request for polling
         let data_request = DataRequest::GetStorageServerSummary;
         let storage_request =
             StorageServiceRequest::new(data_request, data_client.use_compression());
-        let request_timeout = data_client.data_client_config.response_timeout_ms;
 
         // Start the peer polling timer
         let timer = start_request_timer(
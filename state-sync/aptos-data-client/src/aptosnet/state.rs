@@ -5,6 +5,7 @@ use crate::{
     aptosnet::logging::{LogEntry, LogEvent, LogSchema},
     AdvertisedData, GlobalDataSummary, OptimalChunkSizes, ResponseError,
 };
+use aptos_compression::CompressionCodec;
 use aptos_config::{
     config::{BaseConfig, StorageServiceConfig},
     network_id::{NetworkId, PeerNetworkId},
@@ -60,6 +61,9 @@ struct PeerState {
     /// The latest observed advertised data for this peer, or `None` if we
     /// haven't polled them yet.
     storage_summary: Option<StorageServerSummary>,
+    /// The compression codecs supported by the peer's storage server, or
+    /// `None` if we haven't fetched them yet.
+    compression_codecs: Option<Vec<CompressionCodec>>,
     /// For now, a simplified port of the original state-sync v1 scoring system.
     score: f64,
 }
@@ -68,6 +72,7 @@ impl Default for PeerState {
     fn default() -> Self {
         Self {
             storage_summary: None,
+            compression_codecs: None,
             score: STARTING_SCORE,
         }
     }
@@ -144,6 +149,7 @@ impl PeerStates {
         // requests to new peers (who don't have a peer state yet).
         if request.data_request.is_storage_summary_request()
             || request.data_request.is_protocol_version_request()
+            || request.data_request.is_compression_dictionary_request()
         {
             return true;
         }
@@ -288,6 +294,52 @@ impl PeerStates {
         false
     }
 
+    /// Returns true iff the compression codecs supported by the peer are known
+    pub fn has_compression_codecs(&self, peer: &PeerNetworkId) -> bool {
+        self.peer_to_state
+            .get(peer)
+            .map(|peer_state| peer_state.compression_codecs.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Returns the compression codecs known to be supported by the peer.
+    /// All peers support LZ4 (in fast mode).
+    pub fn get_compression_codecs(&self, peer: &PeerNetworkId) -> Vec<CompressionCodec> {
+        self.peer_to_state
+            .get(peer)
+            .and_then(|peer_state| peer_state.compression_codecs.clone())
+            .unwrap_or_else(|| vec![CompressionCodec::Lz4])
+    }
+
+    /// Returns true iff the peer is known to support the given compression codec.
+    /// All peers support LZ4 (in fast mode).
+    pub fn supports_compression_codec(
+        &self,
+        peer: &PeerNetworkId,
+        compression_codec: &CompressionCodec,
+    ) -> bool {
+        if *compression_codec == CompressionCodec::Lz4 {
+            return true;
+        }
+        self.peer_to_state
+            .get(peer)
+            .and_then(|peer_state| peer_state.compression_codecs.as_ref())
+            .map(|compression_codecs| compression_codec.is_supported_by(compression_codecs))
+            .unwrap_or(false)
+    }
+
+    /// Updates the compression codecs supported by the given peer
+    pub fn update_compression_codecs(
+        &mut self,
+        peer: PeerNetworkId,
+        compression_codecs: Vec<CompressionCodec>,
+    ) {
+        self.peer_to_state
+            .entry(peer)
+            .or_default()
+            .compression_codecs = Some(compression_codecs);
+    }
+
     /// Updates the storage summary for the given peer
     pub fn update_summary(&mut self, peer: PeerNetworkId, summary: StorageServerSummary) {
         self.peer_to_state
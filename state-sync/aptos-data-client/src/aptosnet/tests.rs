@@ -4,6 +4,7 @@
 use super::{AptosDataClient, AptosNetDataClient, DataSummaryPoller, Error};
 use crate::aptosnet::{poll_peer, state::calculate_optimal_chunk_sizes};
 use aptos_channels::{aptos_channel, message_queues::QueueStyle};
+use aptos_compression::{dictionary, CompressionCodec};
 use aptos_config::{
     config::{AptosDataClientConfig, BaseConfig, RoleType, StorageServiceConfig},
     network_id::{NetworkId, PeerNetworkId},
@@ -27,8 +28,8 @@ use aptos_storage_service_types::{
         StorageServiceRequest, TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
     },
     responses::{
-        CompleteDataRange, DataResponse, DataSummary, ProtocolMetadata, StorageServerSummary,
-        StorageServiceResponse, OPTIMISTIC_FETCH_VERSION_DELTA,
+        CompleteDataRange, DataResponse, DataSummary, ProtocolMetadata, ServerProtocolVersion,
+        StorageServerSummary, StorageServiceResponse, OPTIMISTIC_FETCH_VERSION_DELTA,
     },
     StorageServiceError, StorageServiceMessage,
 };
@@ -37,6 +38,8 @@ use aptos_types::{
     aggregate_signature::AggregateSignature,
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::SparseMerkleRangeProof,
+    state_store::state_value::StateValueChunkWithProof,
     transaction::{TransactionListWithProof, Version},
     PeerId,
 };
@@ -1180,6 +1183,297 @@ async fn compression_mismatch_disabled() {
     assert_matches!(response, Error::InvalidResponse(_));
 }
 
+#[tokio::test]
+async fn compression_codec_negotiation() {
+    ::aptos_logger::Logger::init_for_testing();
+
+    // Enable compression using zstd
+    let compression_codec = CompressionCodec::Zstd(3);
+    let data_client_config = AptosDataClientConfig {
+        compression_codec,
+        use_compression: true,
+        ..Default::default()
+    };
+    let (mut mock_network, mock_time, client, poller) =
+        MockNetwork::new(None, Some(data_client_config), None);
+
+    tokio::spawn(poller.start_poller());
+
+    // Add a connected peer
+    let _ = mock_network.add_peer(true);
+
+    // Advance time so the poller sends a protocol version request
+    tokio::task::yield_now().await;
+    mock_time.advance_async(Duration::from_millis(1_000)).await;
+
+    // Receive the request (compressed using LZ4) and respond
+    let network_request = mock_network.next_request().await.unwrap();
+    assert_matches!(
+        network_request.storage_service_request.data_request,
+        DataRequest::GetServerProtocolVersion
+    );
+    assert_eq!(
+        network_request
+            .storage_service_request
+            .get_compression_codec(),
+        Some(CompressionCodec::Lz4)
+    );
+    let data_response = DataResponse::ServerProtocolVersion(ServerProtocolVersion {
+        protocol_version: 1,
+        supported_compression_codecs: vec![CompressionCodec::Lz4, CompressionCodec::Zstd(9)],
+    });
+    network_request
+        .response_sender
+        .send(Ok(StorageServiceResponse::new(data_response, true).unwrap()));
+
+    // Receive the data summary request (compressed using zstd) and respond
+    let network_request = mock_network.next_request().await.unwrap();
+    assert_matches!(
+        network_request.storage_service_request.data_request,
+        DataRequest::GetStorageServerSummary
+    );
+    assert_eq!(
+        network_request
+            .storage_service_request
+            .get_compression_codec(),
+        Some(compression_codec)
+    );
+    let data_response = DataResponse::StorageServerSummary(mock_storage_summary(200));
+    network_request
+        .response_sender
+        .send(Ok(StorageServiceResponse::new_with_codec(
+            data_response,
+            Some(compression_codec),
+        )
+        .unwrap()));
+
+    // Let the poller finish processing the response
+    tokio::task::yield_now().await;
+
+    // Handle the client's transactions request, but respond using LZ4
+    tokio::spawn(async move {
+        let network_request = mock_network.next_request().await.unwrap();
+        assert_eq!(
+            network_request
+                .storage_service_request
+                .get_compression_codec(),
+            Some(compression_codec)
+        );
+
+        let data_response =
+            DataResponse::TransactionsWithProof(TransactionListWithProof::new_empty());
+        let storage_response = StorageServiceResponse::new(data_response, true).unwrap();
+        network_request.response_sender.send(Ok(storage_response));
+    });
+
+    // The client should receive a response with a different codec and return an error
+    let request_timeout = client.data_client_config.response_timeout_ms;
+    let response = client
+        .get_transactions_with_proof(100, 50, 100, false, request_timeout)
+        .await
+        .unwrap_err();
+    assert_matches!(response, Error::InvalidResponse(_));
+}
+
+#[tokio::test]
+async fn compression_codec_negotiation_retry() {
+    ::aptos_logger::Logger::init_for_testing();
+
+    // Enable compression using zstd
+    let compression_codec = CompressionCodec::Zstd(3);
+    let data_client_config = AptosDataClientConfig {
+        compression_codec,
+        use_compression: true,
+        ..Default::default()
+    };
+    let (mut mock_network, mock_time, _, poller) =
+        MockNetwork::new(None, Some(data_client_config), None);
+
+    tokio::spawn(poller.start_poller());
+
+    // Add a connected peer
+    let _ = mock_network.add_peer(true);
+
+    // Advance time so the poller sends a protocol version request
+    tokio::task::yield_now().await;
+    mock_time.advance_async(Duration::from_millis(1_000)).await;
+
+    // Receive the request and fail it (transiently)
+    let network_request = mock_network.next_request().await.unwrap();
+    assert_matches!(
+        network_request.storage_service_request.data_request,
+        DataRequest::GetServerProtocolVersion
+    );
+    network_request
+        .response_sender
+        .send(Err(StorageServiceError::InternalError(
+            "An unexpected error occurred!".into(),
+        )));
+
+    // Receive the data summary request (compressed using LZ4) and respond
+    let network_request = mock_network.next_request().await.unwrap();
+    assert_matches!(
+        network_request.storage_service_request.data_request,
+        DataRequest::GetStorageServerSummary
+    );
+    assert_eq!(
+        network_request
+            .storage_service_request
+            .get_compression_codec(),
+        Some(CompressionCodec::Lz4)
+    );
+    let data_response = DataResponse::StorageServerSummary(mock_storage_summary(200));
+    network_request
+        .response_sender
+        .send(Ok(StorageServiceResponse::new(data_response, true).unwrap()));
+
+    // Advance time so the poller polls the peer again
+    tokio::task::yield_now().await;
+    mock_time.advance_async(Duration::from_millis(1_000)).await;
+
+    // The codecs are requested again, so respond with zstd support
+    let network_request = mock_network.next_request().await.unwrap();
+    assert_matches!(
+        network_request.storage_service_request.data_request,
+        DataRequest::GetServerProtocolVersion
+    );
+    let data_response = DataResponse::ServerProtocolVersion(ServerProtocolVersion {
+        protocol_version: 1,
+        supported_compression_codecs: vec![CompressionCodec::Lz4, CompressionCodec::Zstd(9)],
+    });
+    network_request
+        .response_sender
+        .send(Ok(StorageServiceResponse::new(data_response, true).unwrap()));
+
+    // The data summary request should now be compressed using zstd
+    let network_request = mock_network.next_request().await.unwrap();
+    assert_matches!(
+        network_request.storage_service_request.data_request,
+        DataRequest::GetStorageServerSummary
+    );
+    assert_eq!(
+        network_request
+            .storage_service_request
+            .get_compression_codec(),
+        Some(compression_codec)
+    );
+}
+
+#[tokio::test]
+async fn compression_dictionary_negotiation() {
+    ::aptos_logger::Logger::init_for_testing();
+
+    // Create a dictionary (that the peer will offer)
+    let samples: Vec<Vec<u8>> = (0..1000)
+        .map(|index| format!("0x1::coin::CoinStore/{}: {}", index, index * 13).into_bytes())
+        .collect();
+    let compression_dictionary = dictionary::train_dictionary(&samples, 4 * 1024).unwrap();
+    let dictionary_id = dictionary::get_dictionary_id(&compression_dictionary);
+
+    // Enable compression dictionaries
+    let data_client_config = AptosDataClientConfig {
+        use_compression: true,
+        use_compression_dictionaries: true,
+        ..Default::default()
+    };
+    let (mut mock_network, mock_time, client, poller) =
+        MockNetwork::new(None, Some(data_client_config), None);
+
+    tokio::spawn(poller.start_poller());
+
+    // Add a connected peer
+    let _ = mock_network.add_peer(true);
+
+    // Advance time so the poller sends a protocol version request
+    tokio::task::yield_now().await;
+    mock_time.advance_async(Duration::from_millis(1_000)).await;
+
+    // Receive the request and respond with a dictionary codec
+    let network_request = mock_network.next_request().await.unwrap();
+    assert_matches!(
+        network_request.storage_service_request.data_request,
+        DataRequest::GetServerProtocolVersion
+    );
+    let data_response = DataResponse::ServerProtocolVersion(ServerProtocolVersion {
+        protocol_version: 1,
+        supported_compression_codecs: vec![
+            CompressionCodec::Lz4,
+            CompressionCodec::ZstdWithDictionary(9, dictionary_id),
+        ],
+    });
+    network_request
+        .response_sender
+        .send(Ok(StorageServiceResponse::new(data_response, true).unwrap()));
+
+    // Receive the dictionary request and respond with the dictionary
+    let network_request = mock_network.next_request().await.unwrap();
+    assert_eq!(
+        network_request.storage_service_request.data_request,
+        DataRequest::GetCompressionDictionary(dictionary_id)
+    );
+    let data_response = DataResponse::CompressionDictionary(compression_dictionary);
+    network_request
+        .response_sender
+        .send(Ok(StorageServiceResponse::new(data_response, true).unwrap()));
+
+    // Receive the data summary request (compressed using LZ4) and respond
+    let network_request = mock_network.next_request().await.unwrap();
+    assert_matches!(
+        network_request.storage_service_request.data_request,
+        DataRequest::GetStorageServerSummary
+    );
+    assert_eq!(
+        network_request
+            .storage_service_request
+            .get_compression_codec(),
+        Some(CompressionCodec::Lz4)
+    );
+    let mut storage_summary = mock_storage_summary(200);
+    storage_summary.data_summary.states = Some(CompleteDataRange::new(0, 200).unwrap());
+    let data_response = DataResponse::StorageServerSummary(storage_summary);
+    network_request
+        .response_sender
+        .send(Ok(StorageServiceResponse::new(data_response, true).unwrap()));
+
+    // Let the poller finish processing the response
+    tokio::task::yield_now().await;
+
+    // Handle the client's state values request (which should use the dictionary)
+    let dictionary_codec = CompressionCodec::ZstdWithDictionary(3, dictionary_id);
+    let state_value_chunk_with_proof = StateValueChunkWithProof {
+        first_index: 0,
+        last_index: 0,
+        first_key: HashValue::random(),
+        last_key: HashValue::random(),
+        raw_values: vec![],
+        proof: SparseMerkleRangeProof::new(vec![]),
+        root_hash: HashValue::random(),
+    };
+    let expected_state_values = state_value_chunk_with_proof.clone();
+    tokio::spawn(async move {
+        let network_request = mock_network.next_request().await.unwrap();
+        assert_eq!(
+            network_request
+                .storage_service_request
+                .get_compression_codec(),
+            Some(dictionary_codec)
+        );
+
+        let data_response = DataResponse::StateValueChunkWithProof(state_value_chunk_with_proof);
+        let storage_response =
+            StorageServiceResponse::new_with_codec(data_response, Some(dictionary_codec)).unwrap();
+        network_request.response_sender.send(Ok(storage_response));
+    });
+
+    // The client should decompress the response using the dictionary
+    let request_timeout = client.data_client_config.response_timeout_ms;
+    let response = client
+        .get_state_values_with_proof(100, 0, 0, request_timeout)
+        .await
+        .unwrap();
+    assert_eq!(response.payload, expected_state_values);
+}
+
 #[tokio::test]
 async fn compression_mismatch_enabled() {
     ::aptos_logger::Logger::init_for_testing();
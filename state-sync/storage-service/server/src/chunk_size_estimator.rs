@@ -0,0 +1,66 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_infallible::Mutex;
+use std::{cmp::min, collections::HashMap};
+
+/// The weight given to the latest observation when updating the average item sizes
+const AVERAGE_ITEM_BYTES_WEIGHT: f64 = 0.25;
+
+/// The fraction of the target chunk bytes to fill when estimating chunk sizes.
+/// This leaves room for variance in item sizes (and avoids re-fetching data).
+const TARGET_CHUNK_BYTES_FRACTION: f64 = 0.9;
+
+/// The data types for which chunk sizes are estimated
+pub(crate) const EPOCH_ENDING_LEDGER_INFOS: &str = "epoch_ending_ledger_infos";
+pub(crate) const STATE_VALUES: &str = "state_values";
+pub(crate) const TRANSACTIONS: &str = "transactions";
+pub(crate) const TRANSACTION_OUTPUTS: &str = "transaction_outputs";
+
+/// Estimates the number of items to fetch for each chunk, such that the serialized
+/// chunk fits into the target number of bytes. This is done by tracking the average
+/// serialized size of the items served for each data type. Until the first chunk of
+/// a data type has been served, the max chunk size (as given by the caller) is used.
+#[derive(Debug)]
+pub(crate) struct ChunkSizeEstimator {
+    target_chunk_bytes: u64,
+    average_item_bytes: Mutex<HashMap<&'static str, f64>>,
+}
+
+impl ChunkSizeEstimator {
+    pub fn new(target_chunk_bytes: u64) -> Self {
+        Self {
+            target_chunk_bytes,
+            average_item_bytes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the number of items to fetch for a chunk of the given data
+    /// type. The result is always between 1 and `max_num_items` (inclusive).
+    pub fn estimate_num_items(&self, data_type: &'static str, max_num_items: u64) -> u64 {
+        let average_item_bytes = match self.average_item_bytes.lock().get(data_type) {
+            Some(average_item_bytes) => *average_item_bytes,
+            None => return max_num_items,
+        };
+        let target_bytes = self.target_chunk_bytes as f64 * TARGET_CHUNK_BYTES_FRACTION;
+        let num_items = (target_bytes / average_item_bytes.max(1.0)) as u64;
+        min(num_items, max_num_items).max(1)
+    }
+
+    /// Updates the average item size of the given data type using
+    /// the serialized size of a chunk with the given number of items.
+    pub fn update(&self, data_type: &'static str, num_items: u64, num_bytes: u64) {
+        if num_items == 0 {
+            return; // Nothing to learn from an empty chunk
+        }
+        let item_bytes = num_bytes as f64 / num_items as f64;
+        self.average_item_bytes
+            .lock()
+            .entry(data_type)
+            .and_modify(|average_item_bytes| {
+                *average_item_bytes = (AVERAGE_ITEM_BYTES_WEIGHT * item_bytes)
+                    + ((1.0 - AVERAGE_ITEM_BYTES_WEIGHT) * *average_item_bytes)
+            })
+            .or_insert(item_bytes);
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_compression::dictionary::{self, DictionaryId};
+use aptos_config::config::StorageServiceConfig;
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_types::state_store::state_value::StateValueChunkWithProof;
+use std::{mem, sync::Arc};
+use tokio::runtime::Handle;
+
+/// The number of state values to sample before training the dictionary
+const NUM_DICTIONARY_SAMPLES: usize = 10_000;
+
+/// The max size of a single sample (larger state values are truncated)
+const MAX_SAMPLE_BYTES: usize = 4 * 1024;
+
+/// The state of the state value dictionary
+#[derive(Debug)]
+enum DictionaryState {
+    Disabled,               // Dictionaries are disabled by the config
+    Sampling(Vec<Vec<u8>>), // State values are being sampled
+    Training,               // The dictionary is being trained
+    Trained(DictionaryId),  // The dictionary was trained and registered
+    Failed,                 // Training failed (the server continues without a dictionary)
+}
+
+/// A Zstd dictionary for state values. The dictionary is trained on the
+/// state values served by this node, and offered to clients (alongside
+/// the other compression codecs) once it has been trained.
+#[derive(Debug)]
+pub(crate) struct StateValueDictionary {
+    max_dictionary_bytes: usize,
+    runtime: Handle,
+    state: Arc<Mutex<DictionaryState>>,
+}
+
+impl StateValueDictionary {
+    pub fn new(config: &StorageServiceConfig, runtime: Handle) -> Self {
+        let state = if config.max_state_value_dictionary_bytes == 0
+            || config.max_zstd_compression_level == 0
+        {
+            DictionaryState::Disabled
+        } else {
+            DictionaryState::Sampling(vec![])
+        };
+        Self {
+            max_dictionary_bytes: config.max_state_value_dictionary_bytes as usize,
+            runtime,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Returns the identifier of the dictionary (if it has been trained)
+    pub fn get_dictionary_id(&self) -> Option<DictionaryId> {
+        match *self.state.lock() {
+            DictionaryState::Trained(dictionary_id) => Some(dictionary_id),
+            _ => None,
+        }
+    }
+
+    /// Samples the state values in the given chunk. Once enough state values
+    /// have been sampled, the dictionary is trained on a blocking thread in
+    /// the background (so that the request handlers aren't held up).
+    pub fn add_samples(&self, state_value_chunk_with_proof: &StateValueChunkWithProof) {
+        // Sample the state values
+        let samples = {
+            let mut state = self.state.lock();
+            let samples = match &mut *state {
+                DictionaryState::Sampling(samples) => samples,
+                _ => return, // We're no longer sampling
+            };
+            for (state_key, state_value) in &state_value_chunk_with_proof.raw_values {
+                match bcs::to_bytes(&(state_key, state_value)) {
+                    Ok(mut sample) => {
+                        sample.truncate(MAX_SAMPLE_BYTES);
+                        samples.push(sample);
+                    },
+                    Err(error) => {
+                        warn!("Failed to serialize a state value sample: {:?}", error);
+                    },
+                }
+            }
+            if samples.len() < NUM_DICTIONARY_SAMPLES {
+                return; // We need more samples
+            }
+            let samples = mem::take(samples);
+            *state = DictionaryState::Training;
+            samples
+        };
+
+        // Train and register the dictionary in the background
+        let max_dictionary_bytes = self.max_dictionary_bytes;
+        let state = self.state.clone();
+        self.runtime.spawn_blocking(move || {
+            let dictionary_state =
+                match dictionary::train_dictionary(&samples, max_dictionary_bytes)
+                    .and_then(dictionary::register_dictionary)
+                {
+                    Ok(dictionary_id) => {
+                        info!(
+                            "Trained the state value compression dictionary: {}",
+                            dictionary_id
+                        );
+                        DictionaryState::Trained(dictionary_id)
+                    },
+                    Err(error) => {
+                        warn!(
+                            "Failed to train the state value compression dictionary: {:?}",
+                            error
+                        );
+                        DictionaryState::Failed
+                    },
+                };
+            *state.lock() = dictionary_state;
+        });
+    }
+}
@@ -4,6 +4,11 @@
 #![forbid(unsafe_code)]
 
 use crate::{
+    chunk_size_estimator::{
+        ChunkSizeEstimator, EPOCH_ENDING_LEDGER_INFOS, STATE_VALUES, TRANSACTIONS,
+        TRANSACTION_OUTPUTS,
+    },
+    compression_dictionary::StateValueDictionary,
     logging::{LogEntry, LogSchema},
     metrics::{
         increment_counter, increment_network_frame_overflow, start_timer, LRU_CACHE_HIT,
@@ -12,6 +17,7 @@ use crate::{
     network::{ResponseSender, StorageServiceNetworkEvents},
 };
 use aptos_bounded_executor::BoundedExecutor;
+use aptos_compression::{dictionary::DictionaryId, CompressionCodec};
 use aptos_config::{config::StorageServiceConfig, network_id::PeerNetworkId};
 use aptos_infallible::{Mutex, RwLock};
 use aptos_logger::prelude::*;
@@ -48,6 +54,8 @@ use std::{
 use thiserror::Error;
 use tokio::runtime::Handle;
 
+mod chunk_size_estimator;
+mod compression_dictionary;
 mod logging;
 pub mod metrics;
 pub mod network;
@@ -56,7 +64,7 @@ pub mod network;
 mod tests;
 
 /// Storage server constants.
-const STORAGE_SERVER_VERSION: u64 = 1;
+const STORAGE_SERVER_VERSION: u64 = 2;
 const SUMMARY_LOG_FREQUENCY_SECS: u64 = 5;
 
 #[derive(Clone, Debug, Deserialize, Error, PartialEq, Eq, Serialize)]
@@ -145,7 +153,7 @@ impl DataSubscriptionRequest {
             })?;
 
         // Create the storage request
-        let data_request = match &self.request.data_request {
+        let data_request = match self.request.get_data_request() {
             DataRequest::GetNewTransactionOutputsWithProof(_) => {
                 DataRequest::GetTransactionOutputsWithProof(TransactionOutputsWithProofRequest {
                     proof_version: target_version,
@@ -174,14 +182,13 @@ impl DataSubscriptionRequest {
             },
             request => unreachable!("Unexpected subscription request: {:?}", request),
         };
-        let storage_request =
-            StorageServiceRequest::new(data_request, self.request.use_compression);
+        let storage_request = self.request.with_data_request(data_request);
         Ok(storage_request)
     }
 
     /// Returns the highest version known by the peer
     fn highest_known_version(&self) -> u64 {
-        match &self.request.data_request {
+        match self.request.get_data_request() {
             DataRequest::GetNewTransactionOutputsWithProof(request) => request.known_version,
             DataRequest::GetNewTransactionsWithProof(request) => request.known_version,
             DataRequest::GetNewTransactionsOrOutputsWithProof(request) => request.known_version,
@@ -191,7 +198,7 @@ impl DataSubscriptionRequest {
 
     /// Returns the highest epoch known by the peer
     fn highest_known_epoch(&self) -> u64 {
-        match &self.request.data_request {
+        match self.request.get_data_request() {
             DataRequest::GetNewTransactionOutputsWithProof(request) => request.known_epoch,
             DataRequest::GetNewTransactionsWithProof(request) => request.known_epoch,
             DataRequest::GetNewTransactionsOrOutputsWithProof(request) => request.known_epoch,
@@ -202,7 +209,7 @@ impl DataSubscriptionRequest {
     /// Returns the maximum chunk size for the request depending
     /// on the request type.
     fn max_chunk_size_for_request(&self, config: StorageServiceConfig) -> u64 {
-        match &self.request.data_request {
+        match self.request.get_data_request() {
             DataRequest::GetNewTransactionOutputsWithProof(_) => {
                 config.max_transaction_output_chunk_size
             },
@@ -244,6 +251,9 @@ pub struct StorageServiceServer<T> {
     // from the cached storage summary because these responses should
     // never change while the storage summary changes over time.
     lru_storage_cache: Arc<Mutex<LruCache<StorageServiceRequest, StorageServiceResponse>>>,
+
+    // The Zstd dictionary for state values (trained on the state values served)
+    state_value_dictionary: Arc<StateValueDictionary>,
 }
 
 impl<T: StorageReaderInterface> StorageServiceServer<T> {
@@ -254,6 +264,7 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         time_service: TimeService,
         network_requests: StorageServiceNetworkEvents,
     ) -> Self {
+        let state_value_dictionary = Arc::new(StateValueDictionary::new(&config, executor.clone()));
         let bounded_executor =
             BoundedExecutor::new(config.max_concurrent_requests as usize, executor);
         let cached_storage_server_summary = Arc::new(RwLock::new(StorageServerSummary::default()));
@@ -271,6 +282,7 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
             cached_storage_server_summary,
             data_subscriptions,
             lru_storage_cache,
+            state_value_dictionary,
         }
     }
 
@@ -316,6 +328,7 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         let config = self.config;
         let data_subscriptions = self.data_subscriptions.clone();
         let lru_storage_cache = self.lru_storage_cache.clone();
+        let state_value_dictionary = self.state_value_dictionary.clone();
         let storage = self.storage.clone();
         let time_service = self.time_service.clone();
 
@@ -336,9 +349,11 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
 
                     // Identify the peers with ready subscriptions
                     let peers_with_ready_subscriptions = match get_peers_with_ready_subscriptions(
+                        config,
                         cached_storage_server_summary.clone(),
                         data_subscriptions.clone(),
                         lru_storage_cache.clone(),
+                        state_value_dictionary.clone(),
                         storage.clone(),
                         time_service.clone(),
                     ) {
@@ -360,6 +375,7 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
                                 config,
                                 data_subscriptions.clone(),
                                 lru_storage_cache.clone(),
+                                state_value_dictionary.clone(),
                                 storage.clone(),
                                 time_service.clone(),
                                 data_subscription,
@@ -399,17 +415,21 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
             // All handler methods are currently CPU-bound and synchronous
             // I/O-bound, so we want to spawn on the blocking thread pool to
             // avoid starving other async tasks on the same runtime.
+            let config = self.config;
             let storage = self.storage.clone();
             let cached_storage_server_summary = self.cached_storage_server_summary.clone();
             let data_subscriptions = self.data_subscriptions.clone();
             let lru_storage_cache = self.lru_storage_cache.clone();
+            let state_value_dictionary = self.state_value_dictionary.clone();
             let time_service = self.time_service.clone();
             self.bounded_executor
                 .spawn_blocking(move || {
                     Handler::new(
+                        config,
                         cached_storage_server_summary,
                         data_subscriptions,
                         lru_storage_cache,
+                        state_value_dictionary,
                         storage,
                         time_service,
                     )
@@ -429,9 +449,11 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
 /// Returns the list of peers that made those subscriptions
 /// alongside the ledger info at the target version for the peer.
 fn get_peers_with_ready_subscriptions<T: StorageReaderInterface>(
+    config: StorageServiceConfig,
     cached_storage_server_summary: Arc<RwLock<StorageServerSummary>>,
     data_subscriptions: Arc<Mutex<HashMap<PeerNetworkId, DataSubscriptionRequest>>>,
     lru_storage_cache: Arc<Mutex<LruCache<StorageServiceRequest, StorageServiceResponse>>>,
+    state_value_dictionary: Arc<StateValueDictionary>,
     storage: T,
     time_service: TimeService,
 ) -> Result<Vec<(PeerNetworkId, LedgerInfoWithSignatures)>, Error> {
@@ -452,11 +474,13 @@ fn get_peers_with_ready_subscriptions<T: StorageReaderInterface>(
             let target_ledger_info = if highest_known_epoch < highest_synced_epoch {
                 // The peer needs to sync to their epoch ending ledger info
                 get_epoch_ending_ledger_info(
+                    config,
                     cached_storage_server_summary.clone(),
                     data_subscriptions.clone(),
                     highest_known_epoch,
                     lru_storage_cache.clone(),
                     data_subscription.protocol,
+                    state_value_dictionary.clone(),
                     storage.clone(),
                     time_service.clone(),
                 )?
@@ -471,11 +495,13 @@ fn get_peers_with_ready_subscriptions<T: StorageReaderInterface>(
 
 /// Gets the epoch ending ledger info at the given epoch
 fn get_epoch_ending_ledger_info<T: StorageReaderInterface>(
+    config: StorageServiceConfig,
     cached_storage_server_summary: Arc<RwLock<StorageServerSummary>>,
     data_subscriptions: Arc<Mutex<HashMap<PeerNetworkId, DataSubscriptionRequest>>>,
     epoch: u64,
     lru_storage_cache: Arc<Mutex<LruCache<StorageServiceRequest, StorageServiceResponse>>>,
     protocol: ProtocolId,
+    state_value_dictionary: Arc<StateValueDictionary>,
     storage: T,
     time_service: TimeService,
 ) -> Result<LedgerInfoWithSignatures, Error> {
@@ -491,9 +517,11 @@ fn get_epoch_ending_ledger_info<T: StorageReaderInterface>(
 
     // Process the request
     let handler = Handler::new(
+        config,
         cached_storage_server_summary,
         data_subscriptions,
         lru_storage_cache,
+        state_value_dictionary,
         storage,
         time_service,
     );
@@ -529,6 +557,7 @@ fn notify_peer_of_new_data<T: StorageReaderInterface>(
     config: StorageServiceConfig,
     data_subscriptions: Arc<Mutex<HashMap<PeerNetworkId, DataSubscriptionRequest>>>,
     lru_storage_cache: Arc<Mutex<LruCache<StorageServiceRequest, StorageServiceResponse>>>,
+    state_value_dictionary: Arc<StateValueDictionary>,
     storage: T,
     time_service: TimeService,
     subscription: DataSubscriptionRequest,
@@ -537,11 +566,12 @@ fn notify_peer_of_new_data<T: StorageReaderInterface>(
     match subscription.get_storage_request_for_missing_data(config, &target_ledger_info) {
         Ok(storage_request) => {
             // Handle the storage service request to fetch the missing data
-            let use_compression = storage_request.use_compression;
             let handler = Handler::new(
+                config,
                 cached_storage_server_summary,
                 data_subscriptions,
                 lru_storage_cache,
+                state_value_dictionary,
                 storage,
                 time_service,
             );
@@ -597,16 +627,17 @@ fn notify_peer_of_new_data<T: StorageReaderInterface>(
                     )))
                 },
             };
-            let storage_response =
-                match StorageServiceResponse::new(transformed_data_response, use_compression) {
-                    Ok(storage_response) => storage_response,
-                    Err(error) => {
-                        return Err(Error::UnexpectedErrorEncountered(format!(
-                            "Failed to create transformed response! Error: {:?}",
-                            error
-                        )));
-                    },
-                };
+            let storage_response = match handler
+                .create_storage_response(&storage_request, transformed_data_response)
+            {
+                Ok(storage_response) => storage_response,
+                Err(error) => {
+                    return Err(Error::UnexpectedErrorEncountered(format!(
+                        "Failed to create transformed response! Error: {:?}",
+                        error
+                    )));
+                },
+            };
 
             // If the storage response has overflown the network frame size
             // return an error. We don't need to retry with less data because
@@ -680,26 +711,32 @@ fn remove_expired_data_subscriptions(
 /// request. We usually clone/create a new handler for every request.
 #[derive(Clone)]
 pub struct Handler<T> {
+    config: StorageServiceConfig,
     cached_storage_server_summary: Arc<RwLock<StorageServerSummary>>,
     data_subscriptions: Arc<Mutex<HashMap<PeerNetworkId, DataSubscriptionRequest>>>,
     lru_storage_cache: Arc<Mutex<LruCache<StorageServiceRequest, StorageServiceResponse>>>,
+    state_value_dictionary: Arc<StateValueDictionary>,
     storage: T,
     time_service: TimeService,
 }
 
 impl<T: StorageReaderInterface> Handler<T> {
-    pub fn new(
+    pub(crate) fn new(
+        config: StorageServiceConfig,
         cached_storage_server_summary: Arc<RwLock<StorageServerSummary>>,
         data_subscriptions: Arc<Mutex<HashMap<PeerNetworkId, DataSubscriptionRequest>>>,
         lru_storage_cache: Arc<Mutex<LruCache<StorageServiceRequest, StorageServiceResponse>>>,
+        state_value_dictionary: Arc<StateValueDictionary>,
         storage: T,
         time_service: TimeService,
     ) -> Self {
         Self {
+            config,
             storage,
             cached_storage_server_summary,
             data_subscriptions,
             lru_storage_cache,
+            state_value_dictionary,
             time_service,
         }
     }
@@ -750,17 +787,18 @@ impl<T: StorageReaderInterface> Handler<T> {
         );
 
         // Process the request
-        let response = match &request.data_request {
+        let response = match request.get_data_request() {
             DataRequest::GetServerProtocolVersion => {
                 let data_response = self.get_server_protocol_version();
-                StorageServiceResponse::new(data_response, request.use_compression)
-                    .map_err(|error| error.into())
+                self.create_storage_response(&request, data_response)
             },
             DataRequest::GetStorageServerSummary => {
                 let data_response = self.get_storage_server_summary();
-                StorageServiceResponse::new(data_response, request.use_compression)
-                    .map_err(|error| error.into())
+                self.create_storage_response(&request, data_response)
             },
+            DataRequest::GetCompressionDictionary(dictionary_id) => self
+                .get_compression_dictionary(*dictionary_id)
+                .and_then(|data_response| self.create_storage_response(&request, data_response)),
             _ => self.process_cachable_request(protocol, &request),
         };
 
@@ -844,7 +882,7 @@ impl<T: StorageReaderInterface> Handler<T> {
         }
 
         // Fetch the data response from storage
-        let data_response = match &request.data_request {
+        let data_response = match request.get_data_request() {
             DataRequest::GetStateValuesWithProof(request) => {
                 self.get_state_value_chunk_with_proof(request)
             },
@@ -868,7 +906,7 @@ impl<T: StorageReaderInterface> Handler<T> {
                 request
             ))),
         }?;
-        let storage_response = StorageServiceResponse::new(data_response, request.use_compression)?;
+        let storage_response = self.create_storage_response(request, data_response)?;
 
         // Cache the response before returning
         let _ = self
@@ -879,6 +917,59 @@ impl<T: StorageReaderInterface> Handler<T> {
         Ok(storage_response)
     }
 
+    /// Creates a storage service response for the given request, compressing
+    /// the data response using the requested codec (if compression is required).
+    fn create_storage_response(
+        &self,
+        request: &StorageServiceRequest,
+        data_response: DataResponse,
+    ) -> Result<StorageServiceResponse, Error> {
+        let compression_codec = request.get_compression_codec();
+        if let Some(compression_codec) = compression_codec {
+            if !compression_codec.is_supported_by(&self.get_supported_compression_codecs()) {
+                return Err(Error::InvalidRequest(format!(
+                    "The requested compression codec is not supported: {:?}",
+                    compression_codec
+                )));
+            }
+        }
+        StorageServiceResponse::new_with_codec(data_response, compression_codec)
+            .map_err(|error| error.into())
+    }
+
+    /// Returns the compression codecs (and max levels) supported by the server.
+    /// This includes the state value dictionary, once it has been trained.
+    fn get_supported_compression_codecs(&self) -> Vec<CompressionCodec> {
+        let mut compression_codecs = self.config.supported_compression_codecs();
+        if let Some(dictionary_id) = self.state_value_dictionary.get_dictionary_id() {
+            compression_codecs.push(CompressionCodec::ZstdWithDictionary(
+                self.config.max_zstd_compression_level,
+                dictionary_id,
+            ));
+        }
+        compression_codecs
+    }
+
+    fn get_compression_dictionary(
+        &self,
+        dictionary_id: DictionaryId,
+    ) -> Result<DataResponse, Error> {
+        if self.state_value_dictionary.get_dictionary_id() != Some(dictionary_id) {
+            return Err(Error::InvalidRequest(format!(
+                "The requested compression dictionary is not offered: {}",
+                dictionary_id
+            )));
+        }
+        let dictionary =
+            aptos_compression::dictionary::get_dictionary(dictionary_id).ok_or_else(|| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "The compression dictionary is not registered: {}",
+                    dictionary_id
+                ))
+            })?;
+        Ok(DataResponse::CompressionDictionary(dictionary.to_vec()))
+    }
+
     fn get_state_value_chunk_with_proof(
         &self,
         request: &StateValuesWithProofRequest,
@@ -888,6 +979,8 @@ impl<T: StorageReaderInterface> Handler<T> {
             request.start_index,
             request.end_index,
         )?;
+        self.state_value_dictionary
+            .add_samples(&state_value_chunk_with_proof);
 
         Ok(DataResponse::StateValueChunkWithProof(
             state_value_chunk_with_proof,
@@ -914,6 +1007,7 @@ impl<T: StorageReaderInterface> Handler<T> {
     fn get_server_protocol_version(&self) -> DataResponse {
         let server_protocol_version = ServerProtocolVersion {
             protocol_version: STORAGE_SERVER_VERSION,
+            supported_compression_codecs: self.get_supported_compression_codecs(),
         };
         DataResponse::ServerProtocolVersion(server_protocol_version)
     }
@@ -1051,11 +1145,18 @@ pub trait StorageReaderInterface: Clone + Send + 'static {
 pub struct StorageReader {
     config: StorageServiceConfig,
     storage: Arc<dyn DbReader>,
+    chunk_size_estimator: Arc<ChunkSizeEstimator>,
 }
 
 impl StorageReader {
     pub fn new(config: StorageServiceConfig, storage: Arc<dyn DbReader>) -> Self {
-        Self { config, storage }
+        let chunk_size_estimator =
+            Arc::new(ChunkSizeEstimator::new(config.target_network_chunk_bytes));
+        Self {
+            config,
+            storage,
+            chunk_size_estimator,
+        }
     }
 
     /// Returns the state values range held in the database (lowest to highest).
@@ -1189,7 +1290,9 @@ impl StorageReaderInterface for StorageReader {
     ) -> Result<TransactionListWithProof, Error> {
         // Calculate the number of transactions to fetch
         let expected_num_transactions = inclusive_range_len(start_version, end_version)?;
-        let max_num_transactions = self.config.max_transaction_chunk_size;
+        let max_num_transactions = self
+            .chunk_size_estimator
+            .estimate_num_items(TRANSACTIONS, self.config.max_transaction_chunk_size);
         let mut num_transactions_to_fetch = min(expected_num_transactions, max_num_transactions);
 
         // Attempt to serve the request
@@ -1212,6 +1315,11 @@ impl StorageReaderInterface for StorageReader {
                 &transaction_list_with_proof,
                 self.config.max_network_chunk_bytes,
             )?;
+            self.chunk_size_estimator.update(
+                TRANSACTIONS,
+                transaction_list_with_proof.transactions.len() as u64,
+                num_bytes,
+            );
             if !overflow_frame {
                 return Ok(transaction_list_with_proof);
             } else {
@@ -1240,7 +1348,9 @@ impl StorageReaderInterface for StorageReader {
     ) -> Result<EpochChangeProof, Error> {
         // Calculate the number of ledger infos to fetch
         let expected_num_ledger_infos = inclusive_range_len(start_epoch, expected_end_epoch)?;
-        let max_num_ledger_infos = self.config.max_epoch_chunk_size;
+        let max_num_ledger_infos = self
+            .chunk_size_estimator
+            .estimate_num_items(EPOCH_ENDING_LEDGER_INFOS, self.config.max_epoch_chunk_size);
         let mut num_ledger_infos_to_fetch = min(expected_num_ledger_infos, max_num_ledger_infos);
 
         // Attempt to serve the request
@@ -1265,6 +1375,11 @@ impl StorageReaderInterface for StorageReader {
                 &epoch_change_proof,
                 self.config.max_network_chunk_bytes,
             )?;
+            self.chunk_size_estimator.update(
+                EPOCH_ENDING_LEDGER_INFOS,
+                epoch_change_proof.ledger_info_with_sigs.len() as u64,
+                num_bytes,
+            );
             if !overflow_frame {
                 return Ok(epoch_change_proof);
             } else {
@@ -1293,7 +1408,10 @@ impl StorageReaderInterface for StorageReader {
     ) -> Result<TransactionOutputListWithProof, Error> {
         // Calculate the number of transaction outputs to fetch
         let expected_num_outputs = inclusive_range_len(start_version, end_version)?;
-        let max_num_outputs = self.config.max_transaction_output_chunk_size;
+        let max_num_outputs = self.chunk_size_estimator.estimate_num_items(
+            TRANSACTION_OUTPUTS,
+            self.config.max_transaction_output_chunk_size,
+        );
         let mut num_outputs_to_fetch = min(expected_num_outputs, max_num_outputs);
 
         // Attempt to serve the request
@@ -1311,6 +1429,11 @@ impl StorageReaderInterface for StorageReader {
                 &output_list_with_proof,
                 self.config.max_network_chunk_bytes,
             )?;
+            self.chunk_size_estimator.update(
+                TRANSACTION_OUTPUTS,
+                output_list_with_proof.transactions_and_outputs.len() as u64,
+                num_bytes,
+            );
             if !overflow_frame {
                 return Ok(output_list_with_proof);
             } else {
@@ -1341,7 +1464,10 @@ impl StorageReaderInterface for StorageReader {
     ) -> Result<TransactionOrOutputListWithProof, Error> {
         // Calculate the number of transaction outputs to fetch
         let expected_num_outputs = inclusive_range_len(start_version, end_version)?;
-        let max_num_outputs = self.config.max_transaction_output_chunk_size;
+        let max_num_outputs = self.chunk_size_estimator.estimate_num_items(
+            TRANSACTION_OUTPUTS,
+            self.config.max_transaction_output_chunk_size,
+        );
         let mut num_outputs_to_fetch = min(expected_num_outputs, max_num_outputs);
 
         // Attempt to serve the outputs. Halve the data only as many
@@ -1357,6 +1483,11 @@ impl StorageReaderInterface for StorageReader {
                 &output_list_with_proof,
                 self.config.max_network_chunk_bytes,
             )?;
+            self.chunk_size_estimator.update(
+                TRANSACTION_OUTPUTS,
+                output_list_with_proof.transactions_and_outputs.len() as u64,
+                num_bytes,
+            );
 
             if !overflow_frame {
                 return Ok((None, Some(output_list_with_proof)));
@@ -1404,7 +1535,9 @@ impl StorageReaderInterface for StorageReader {
     ) -> Result<StateValueChunkWithProof, Error> {
         // Calculate the number of state values to fetch
         let expected_num_state_values = inclusive_range_len(start_index, end_index)?;
-        let max_num_state_values = self.config.max_state_chunk_size;
+        let max_num_state_values = self
+            .chunk_size_estimator
+            .estimate_num_items(STATE_VALUES, self.config.max_state_chunk_size);
         let mut num_state_values_to_fetch = min(expected_num_state_values, max_num_state_values);
 
         // Attempt to serve the request
@@ -1426,6 +1559,11 @@ impl StorageReaderInterface for StorageReader {
                 &state_value_chunk_with_proof,
                 self.config.max_network_chunk_bytes,
             )?;
+            self.chunk_size_estimator.update(
+                STATE_VALUES,
+                state_value_chunk_with_proof.raw_values.len() as u64,
+                num_bytes,
+            );
             if !overflow_frame {
                 return Ok(state_value_chunk_with_proof);
             } else {
@@ -1484,10 +1622,7 @@ fn log_storage_response(
         Ok(storage_response) => {
             // We expect peers to be polling our storage server summary frequently,
             // so only log this response periodically.
-            if matches!(
-                storage_request.data_request,
-                DataRequest::GetStorageServerSummary
-            ) {
+            if storage_request.data_request.is_storage_summary_request() {
                 sample!(
                     SampleRate::Duration(Duration::from_secs(SUMMARY_LOG_FREQUENCY_SECS)),
                     {
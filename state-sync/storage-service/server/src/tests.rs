@@ -3,10 +3,16 @@
 
 #![forbid(unsafe_code)]
 
-use crate::{metrics, network::StorageServiceNetworkEvents, StorageReader, StorageServiceServer};
+use crate::{
+    chunk_size_estimator::{ChunkSizeEstimator, STATE_VALUES, TRANSACTIONS},
+    metrics,
+    network::StorageServiceNetworkEvents,
+    StorageReader, StorageServiceServer,
+};
 use anyhow::{format_err, Result};
 use aptos_bitvec::BitVec;
 use aptos_channels::{aptos_channel, message_queues::QueueStyle};
+use aptos_compression::{dictionary, CompressionCodec};
 use aptos_config::{
     config::StorageServiceConfig,
     network_id::{NetworkId, PeerNetworkId},
@@ -73,11 +79,12 @@ use mockall::{
 };
 use rand::{rngs::OsRng, Rng};
 use std::{collections::HashMap, sync::Arc, time::Duration};
-use tokio::time::timeout;
+use tokio::time::{sleep, timeout};
 
 /// Various test constants for storage
+const MAX_DICTIONARY_TRAINING_CHECKS: u64 = 100;
 const MAX_RESPONSE_TIMEOUT_SECS: u64 = 60;
-const PROTOCOL_VERSION: u64 = 1;
+const PROTOCOL_VERSION: u64 = 2;
 
 #[tokio::test]
 async fn test_cachable_requests_compression() {
@@ -146,6 +153,275 @@ async fn test_cachable_requests_compression() {
     }
 }
 
+#[tokio::test]
+async fn test_cachable_requests_compression_codecs() {
+    // Create test data
+    let start_version = 0;
+    let end_version = 454;
+    let proof_version = end_version;
+    let include_events = false;
+    let compression_codecs = [
+        CompressionCodec::Lz4,
+        CompressionCodec::Lz4HighCompression(9),
+        CompressionCodec::Zstd(3),
+    ];
+
+    // Create the mock db reader
+    let mut db_reader = create_mock_db_reader();
+    let mut expectation_sequence = Sequence::new();
+    let mut transaction_lists_with_proof = vec![];
+    for _ in compression_codecs {
+        // Create and save test transaction lists
+        let transaction_list_with_proof = create_transaction_list_with_proof(
+            start_version,
+            end_version,
+            proof_version,
+            include_events,
+        );
+        transaction_lists_with_proof.push(transaction_list_with_proof.clone());
+
+        // Expect the data to be fetched from storage exactly once (per codec)
+        db_reader
+            .expect_get_transactions()
+            .times(1)
+            .with(
+                eq(start_version),
+                eq(end_version - start_version + 1),
+                eq(proof_version),
+                eq(include_events),
+            )
+            .return_once(move |_, _, _, _| Ok(transaction_list_with_proof))
+            .in_sequence(&mut expectation_sequence);
+    }
+
+    // Create the storage client and server
+    let (mut mock_client, service, _) = MockClient::new(Some(db_reader), None);
+    tokio::spawn(service.start());
+
+    // Fetch the data using each codec and verify the responses
+    for (i, compression_codec) in compression_codecs.iter().enumerate() {
+        for _ in 0..3 {
+            let data_request =
+                DataRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+                    proof_version,
+                    start_version,
+                    end_version,
+                    include_events,
+                });
+            let storage_request =
+                StorageServiceRequest::new_with_compression_codec(data_request, *compression_codec);
+            let response = mock_client.process_request(storage_request).await.unwrap();
+
+            // Verify the response is correct
+            assert_eq!(response.get_compression_codec(), Some(*compression_codec));
+            if *compression_codec == CompressionCodec::Lz4 {
+                assert_matches!(response, StorageServiceResponse::CompressedResponse(_, _));
+            } else {
+                assert_matches!(
+                    response,
+                    StorageServiceResponse::CodecCompressedResponse(_, _, _)
+                );
+            }
+            match response.get_data_response().unwrap() {
+                DataResponse::TransactionsWithProof(response) => {
+                    assert_eq!(response, transaction_lists_with_proof[i]);
+                },
+                _ => panic!("Expected transactions with proof but got: {:?}", response),
+            };
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_compression_codec_unsupported() {
+    // Create a storage config that doesn't support zstd
+    let storage_config = StorageServiceConfig {
+        max_zstd_compression_level: 0,
+        ..Default::default()
+    };
+
+    // Create the storage client and server
+    let (mut mock_client, service, _) = MockClient::new(None, Some(storage_config));
+    tokio::spawn(service.start());
+
+    // Verify the server only advertises the supported codecs
+    let response = get_compression_codecs(&mut mock_client).await;
+    assert_eq!(response, vec![
+        CompressionCodec::Lz4,
+        CompressionCodec::Lz4HighCompression(storage_config.max_lz4_high_compression_level)
+    ]);
+
+    // Process requests with unsupported codecs and verify they're rejected
+    for compression_codec in [
+        CompressionCodec::Zstd(1),
+        CompressionCodec::Lz4HighCompression(storage_config.max_lz4_high_compression_level + 1),
+    ] {
+        let storage_request = StorageServiceRequest::new_with_compression_codec(
+            DataRequest::GetStorageServerSummary,
+            compression_codec,
+        );
+        let response = mock_client
+            .process_request(storage_request)
+            .await
+            .unwrap_err();
+        assert_matches!(response, StorageServiceError::InvalidRequest(_));
+    }
+
+    // Verify unknown dictionaries can't be fetched
+    let storage_request =
+        StorageServiceRequest::new(DataRequest::GetCompressionDictionary(1), true);
+    let response = mock_client
+        .process_request(storage_request)
+        .await
+        .unwrap_err();
+    assert_matches!(response, StorageServiceError::InvalidRequest(_));
+}
+
+#[tokio::test]
+async fn test_compression_dictionary() {
+    // Create test data
+    let version = 101;
+    let chunk_size = 1000;
+    let num_chunks = 10;
+
+    // Create the mock db reader
+    let mut db_reader = create_mock_db_reader();
+    let mut state_value_chunks_with_proof = vec![];
+    for i in 0..num_chunks {
+        let start_index = i * chunk_size;
+        let raw_values = (start_index..start_index + chunk_size)
+            .map(|index| {
+                let state_key =
+                    StateKey::Raw(format!("0x1::account::Account/{}", index).into_bytes());
+                let state_value = StateValue::from(
+                    format!("balance: {}, sequence_number: {}", index * 7, index).into_bytes(),
+                );
+                (state_key, state_value)
+            })
+            .collect();
+        let state_value_chunk_with_proof = StateValueChunkWithProof {
+            first_index: start_index,
+            last_index: start_index + chunk_size - 1,
+            first_key: HashValue::random(),
+            last_key: HashValue::random(),
+            raw_values,
+            proof: SparseMerkleRangeProof::new(vec![]),
+            root_hash: HashValue::random(),
+        };
+        expect_get_state_values_with_proof(
+            &mut db_reader,
+            version,
+            start_index,
+            chunk_size,
+            state_value_chunk_with_proof.clone(),
+        );
+        state_value_chunks_with_proof.push(state_value_chunk_with_proof);
+    }
+
+    // Create the storage client and server
+    let (mut mock_client, service, _) = MockClient::new(Some(db_reader), None);
+    tokio::spawn(service.start());
+
+    // Verify the dictionary isn't offered before any state values are served
+    let storage_config = StorageServiceConfig::default();
+    let compression_codecs = get_compression_codecs(&mut mock_client).await;
+    assert_eq!(
+        compression_codecs,
+        storage_config.supported_compression_codecs()
+    );
+
+    // Serve enough state values to train the dictionary
+    for state_value_chunk_with_proof in &state_value_chunks_with_proof {
+        let response = get_state_values_with_proof(
+            &mut mock_client,
+            version,
+            state_value_chunk_with_proof.first_index,
+            state_value_chunk_with_proof.last_index,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            response.get_data_response().unwrap(),
+            DataResponse::StateValueChunkWithProof(state_value_chunk_with_proof.clone())
+        );
+    }
+
+    // Wait until the dictionary is trained (in the background) and offered
+    let mut compression_codecs = get_compression_codecs(&mut mock_client).await;
+    for _ in 0..MAX_DICTIONARY_TRAINING_CHECKS {
+        if compression_codecs.len() > storage_config.supported_compression_codecs().len() {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+        compression_codecs = get_compression_codecs(&mut mock_client).await;
+    }
+    let dictionary_codec = *compression_codecs.last().unwrap();
+    let dictionary_id = match dictionary_codec {
+        CompressionCodec::ZstdWithDictionary(level, dictionary_id) => {
+            assert_eq!(level, storage_config.max_zstd_compression_level);
+            dictionary_id
+        },
+        compression_codec => panic!(
+            "Expected a dictionary codec but got: {:?}",
+            compression_codec
+        ),
+    };
+
+    // Fetch the dictionary and verify its identifier
+    let storage_request =
+        StorageServiceRequest::new(DataRequest::GetCompressionDictionary(dictionary_id), true);
+    let response = mock_client.process_request(storage_request).await.unwrap();
+    let dictionary = Vec::<u8>::try_from(response).unwrap();
+    assert_eq!(dictionary::get_dictionary_id(&dictionary), dictionary_id);
+}
+
+#[test]
+fn test_chunk_size_estimator() {
+    // Create a chunk size estimator with a target of 10 KB
+    let chunk_size_estimator = ChunkSizeEstimator::new(10_000);
+
+    // Verify the max chunk size is used until data has been served
+    assert_eq!(
+        chunk_size_estimator.estimate_num_items(TRANSACTIONS, 2000),
+        2000
+    );
+
+    // Serve a chunk of 100 byte transactions and verify the estimate fits the target
+    chunk_size_estimator.update(TRANSACTIONS, 1000, 100_000);
+    assert_eq!(
+        chunk_size_estimator.estimate_num_items(TRANSACTIONS, 2000),
+        90
+    );
+    assert_eq!(
+        chunk_size_estimator.estimate_num_items(TRANSACTIONS, 50),
+        50
+    );
+    assert_eq!(
+        chunk_size_estimator.estimate_num_items(STATE_VALUES, 4000),
+        4000
+    );
+
+    // Serve chunks of larger transactions and verify the estimate shrinks
+    chunk_size_estimator.update(TRANSACTIONS, 10, 10_000);
+    let num_items = chunk_size_estimator.estimate_num_items(TRANSACTIONS, 2000);
+    assert!(num_items < 90);
+    for _ in 0..100 {
+        chunk_size_estimator.update(TRANSACTIONS, 10, 10_000);
+    }
+    assert_eq!(
+        chunk_size_estimator.estimate_num_items(TRANSACTIONS, 2000),
+        9
+    );
+
+    // Verify at least one item is always fetched
+    chunk_size_estimator.update(STATE_VALUES, 1, 1_000_000);
+    assert_eq!(
+        chunk_size_estimator.estimate_num_items(STATE_VALUES, 4000),
+        1
+    );
+}
+
 #[tokio::test]
 async fn test_cachable_requests_eviction() {
     // Create test data
@@ -291,6 +567,8 @@ async fn test_get_server_protocol_version() {
     // Verify the response is correct
     let expected_data_response = DataResponse::ServerProtocolVersion(ServerProtocolVersion {
         protocol_version: PROTOCOL_VERSION,
+        supported_compression_codecs: StorageServiceConfig::default()
+            .supported_compression_codecs(),
     });
     assert_matches!(response, StorageServiceResponse::CompressedResponse(_, _));
     assert_eq!(
@@ -2765,6 +3043,14 @@ async fn get_protocol_version(
         .unwrap()
 }
 
+/// Sends a protocol version request and returns the supported compression codecs
+async fn get_compression_codecs(mock_client: &mut MockClient) -> Vec<CompressionCodec> {
+    let response = get_protocol_version(mock_client, true).await;
+    ServerProtocolVersion::try_from(response)
+        .unwrap()
+        .supported_compression_codecs
+}
+
 /// Sends a storage summary request and processes the response
 async fn get_storage_server_summary(
     mock_client: &mut MockClient,
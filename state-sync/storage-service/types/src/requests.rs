@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::COMPRESSION_SUFFIX_LABEL;
+use aptos_compression::{dictionary::DictionaryId, CompressionCodec};
 use aptos_types::transaction::Version;
 use serde::{Deserialize, Serialize};
 
@@ -20,6 +21,49 @@ impl StorageServiceRequest {
         }
     }
 
+    /// Creates a new request for data compressed with the given codec. This
+    /// should only be a codec supported by the server (as returned by a
+    /// `GetServerProtocolVersion` request). LZ4 (in fast mode) is supported by
+    /// all servers, so it doesn't require a codec request.
+    pub fn new_with_compression_codec(
+        data_request: DataRequest,
+        compression_codec: CompressionCodec,
+    ) -> Self {
+        let data_request = match compression_codec {
+            CompressionCodec::Lz4 => data_request.get_inner_request().clone(),
+            compression_codec => DataRequest::GetDataWithCompressionCodec(CodecDataRequest {
+                compression_codec,
+                data_request: Box::new(data_request.get_inner_request().clone()),
+            }),
+        };
+        Self::new(data_request, true)
+    }
+
+    /// Creates a new request for the given data, compressed in the same way
+    /// as the data of this request.
+    pub fn with_data_request(&self, data_request: DataRequest) -> Self {
+        match self.get_compression_codec() {
+            Some(compression_codec) => {
+                Self::new_with_compression_codec(data_request, compression_codec)
+            },
+            None => Self::new(data_request, false),
+        }
+    }
+
+    /// Returns the data request, without the compression codec request (if any)
+    pub fn get_data_request(&self) -> &DataRequest {
+        self.data_request.get_inner_request()
+    }
+
+    /// Returns the codec to compress the response with (if any)
+    pub fn get_compression_codec(&self) -> Option<CompressionCodec> {
+        match &self.data_request {
+            DataRequest::GetDataWithCompressionCodec(request) => Some(request.compression_codec),
+            _ if self.use_compression => Some(CompressionCodec::Lz4),
+            _ => None,
+        }
+    }
+
     /// Returns a summary label for the request
     pub fn get_label(&self) -> String {
         let mut label = self.data_request.get_label().to_string();
@@ -44,6 +88,8 @@ pub enum DataRequest {
     GetTransactionsWithProof(TransactionsWithProofRequest), // Fetches a list of transactions with a proof
     GetNewTransactionsOrOutputsWithProof(NewTransactionsOrOutputsWithProofRequest), // Subscribes to new transactions or outputs with a proof
     GetTransactionsOrOutputsWithProof(TransactionsOrOutputsWithProofRequest), // Fetches a list of transactions or outputs with a proof
+    GetCompressionDictionary(DictionaryId), // Fetches a compression dictionary offered by the server
+    GetDataWithCompressionCodec(CodecDataRequest), // Fetches data compressed with a codec other than LZ4
 }
 
 impl DataRequest {
@@ -63,21 +109,37 @@ impl DataRequest {
                 "get_new_transactions_or_outputs_with_proof"
             },
             Self::GetTransactionsOrOutputsWithProof(_) => "get_transactions_or_outputs_with_proof",
+            Self::GetCompressionDictionary(_) => "get_compression_dictionary",
+            Self::GetDataWithCompressionCodec(request) => request.data_request.get_label(),
+        }
+    }
+
+    /// Returns the request for the data itself, i.e., without the
+    /// compression codec request (if any).
+    pub fn get_inner_request(&self) -> &DataRequest {
+        match self {
+            Self::GetDataWithCompressionCodec(request) => request.data_request.get_inner_request(),
+            request => request,
         }
     }
 
     pub fn is_storage_summary_request(&self) -> bool {
-        matches!(self, &Self::GetStorageServerSummary)
+        matches!(self.get_inner_request(), &Self::GetStorageServerSummary)
     }
 
     pub fn is_data_subscription_request(&self) -> bool {
-        matches!(self, &Self::GetNewTransactionOutputsWithProof(_))
-            || matches!(self, &Self::GetNewTransactionsWithProof(_))
-            || matches!(self, Self::GetNewTransactionsOrOutputsWithProof(_))
+        let request = self.get_inner_request();
+        matches!(request, &Self::GetNewTransactionOutputsWithProof(_))
+            || matches!(request, &Self::GetNewTransactionsWithProof(_))
+            || matches!(request, Self::GetNewTransactionsOrOutputsWithProof(_))
     }
 
     pub fn is_protocol_version_request(&self) -> bool {
-        matches!(self, &Self::GetServerProtocolVersion)
+        matches!(self.get_inner_request(), &Self::GetServerProtocolVersion)
+    }
+
+    pub fn is_compression_dictionary_request(&self) -> bool {
+        matches!(self.get_inner_request(), &Self::GetCompressionDictionary(_))
     }
 }
 
@@ -153,3 +215,11 @@ pub struct TransactionsOrOutputsWithProofRequest {
     pub include_events: bool, // Whether or not to include events (if transactions are returned)
     pub max_num_output_reductions: u64, // The max num of output reductions before transactions are returned
 }
+
+/// A storage service request for fetching data compressed with the given
+/// codec. The codec must be supported by the server.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct CodecDataRequest {
+    pub compression_codec: CompressionCodec, // The codec to compress the data with
+    pub data_request: Box<DataRequest>,      // The data to fetch from the storage service
+}
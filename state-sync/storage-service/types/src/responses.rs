@@ -3,16 +3,18 @@
 
 use crate::{
     requests::DataRequest::{
-        GetEpochEndingLedgerInfos, GetNewTransactionOutputsWithProof,
-        GetNewTransactionsOrOutputsWithProof, GetNewTransactionsWithProof,
-        GetNumberOfStatesAtVersion, GetServerProtocolVersion, GetStateValuesWithProof,
-        GetStorageServerSummary, GetTransactionOutputsWithProof, GetTransactionsOrOutputsWithProof,
-        GetTransactionsWithProof,
+        GetCompressionDictionary, GetDataWithCompressionCodec, GetEpochEndingLedgerInfos,
+        GetNewTransactionOutputsWithProof, GetNewTransactionsOrOutputsWithProof,
+        GetNewTransactionsWithProof, GetNumberOfStatesAtVersion, GetServerProtocolVersion,
+        GetStateValuesWithProof, GetStorageServerSummary, GetTransactionOutputsWithProof,
+        GetTransactionsOrOutputsWithProof, GetTransactionsWithProof,
     },
     responses::Error::DegenerateRangeError,
     Epoch, StorageServiceRequest, COMPRESSION_SUFFIX_LABEL,
 };
-use aptos_compression::{metrics::CompressionClient, CompressedData, CompressionError};
+use aptos_compression::{
+    metrics::CompressionClient, CompressedData, CompressionCodec, CompressionError,
+};
 use aptos_config::config::{StorageServiceConfig, MAX_APPLICATION_MESSAGE_SIZE};
 use aptos_types::{
     epoch_change::EpochChangeProof,
@@ -57,59 +59,105 @@ impl From<CompressionError> for Error {
 pub enum StorageServiceResponse {
     CompressedResponse(String, CompressedData), // Store the label and the data (e.g., for logging/metrics)
     RawResponse(DataResponse),
+    CodecCompressedResponse(String, CompressionCodec, CompressedData), // Store the label, the codec and the data
 }
 
 impl StorageServiceResponse {
     /// Creates a new response and performs compression if required
     pub fn new(data_response: DataResponse, perform_compression: bool) -> Result<Self, Error> {
-        if perform_compression {
-            let raw_data = bcs::to_bytes(&data_response)
-                .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
-            let compressed_data = aptos_compression::compress(
-                raw_data,
-                CompressionClient::StateSync,
-                MAX_APPLICATION_MESSAGE_SIZE,
-            )?;
-            let label = data_response.get_label().to_string() + COMPRESSION_SUFFIX_LABEL;
+        let compression_codec = if perform_compression {
+            Some(CompressionCodec::Lz4)
+        } else {
+            None
+        };
+        Self::new_with_codec(data_response, compression_codec)
+    }
+
+    /// Creates a new response and compresses it using the given codec (if any).
+    /// Responses compressed using LZ4 (in fast mode) use the original compressed
+    /// response format, so that they can be read by all clients.
+    pub fn new_with_codec(
+        data_response: DataResponse,
+        compression_codec: Option<CompressionCodec>,
+    ) -> Result<Self, Error> {
+        let compression_codec = match compression_codec {
+            Some(compression_codec) => compression_codec,
+            None => return Ok(StorageServiceResponse::RawResponse(data_response)),
+        };
+
+        // Compress the data
+        let raw_data = bcs::to_bytes(&data_response)
+            .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
+        let compressed_data = aptos_compression::compress_with_codec(
+            raw_data,
+            compression_codec,
+            CompressionClient::StateSync,
+            MAX_APPLICATION_MESSAGE_SIZE,
+        )?;
+        let label = data_response.get_label().to_string() + COMPRESSION_SUFFIX_LABEL;
+        if compression_codec == CompressionCodec::Lz4 {
             Ok(StorageServiceResponse::CompressedResponse(
                 label,
                 compressed_data,
             ))
         } else {
-            Ok(StorageServiceResponse::RawResponse(data_response))
+            Ok(StorageServiceResponse::CodecCompressedResponse(
+                label,
+                compression_codec,
+                compressed_data,
+            ))
         }
     }
 
     /// Returns the data response regardless of the inner format
     pub fn get_data_response(&self) -> Result<DataResponse, Error> {
-        match self {
+        let (compression_codec, compressed_data) = match self {
             StorageServiceResponse::CompressedResponse(_, compressed_data) => {
-                let raw_data = aptos_compression::decompress(
-                    compressed_data,
-                    CompressionClient::StateSync,
-                    MAX_APPLICATION_MESSAGE_SIZE,
-                )?;
-                let data_response = bcs::from_bytes::<DataResponse>(&raw_data)
-                    .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
-                Ok(data_response)
+                (CompressionCodec::Lz4, compressed_data)
             },
-            StorageServiceResponse::RawResponse(data_response) => Ok(data_response.clone()),
-        }
+            StorageServiceResponse::CodecCompressedResponse(
+                _,
+                compression_codec,
+                compressed_data,
+            ) => (*compression_codec, compressed_data),
+            StorageServiceResponse::RawResponse(data_response) => return Ok(data_response.clone()),
+        };
+        let raw_data = aptos_compression::decompress_with_codec(
+            compressed_data,
+            compression_codec,
+            CompressionClient::StateSync,
+            MAX_APPLICATION_MESSAGE_SIZE,
+        )?;
+        let data_response = bcs::from_bytes::<DataResponse>(&raw_data)
+            .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
+        Ok(data_response)
     }
 
     /// Returns a summary label for the response
     pub fn get_label(&self) -> String {
         match self {
-            StorageServiceResponse::CompressedResponse(label, _) => label.clone(),
+            StorageServiceResponse::CompressedResponse(label, _)
+            | StorageServiceResponse::CodecCompressedResponse(label, _, _) => label.clone(),
             StorageServiceResponse::RawResponse(data_response) => {
                 data_response.get_label().to_string()
             },
         }
     }
 
+    /// Returns the codec the data response is compressed with (if any)
+    pub fn get_compression_codec(&self) -> Option<CompressionCodec> {
+        match self {
+            StorageServiceResponse::CompressedResponse(_, _) => Some(CompressionCodec::Lz4),
+            StorageServiceResponse::CodecCompressedResponse(_, compression_codec, _) => {
+                Some(*compression_codec)
+            },
+            StorageServiceResponse::RawResponse(_) => None,
+        }
+    }
+
     /// Returns true iff the data response is compressed
     pub fn is_compressed(&self) -> bool {
-        matches!(self, Self::CompressedResponse(_, _))
+        self.get_compression_codec().is_some()
     }
 }
 
@@ -134,6 +182,7 @@ pub enum DataResponse {
     TransactionsWithProof(TransactionListWithProof),
     NewTransactionsOrOutputsWithProof((TransactionOrOutputListWithProof, LedgerInfoWithSignatures)),
     TransactionsOrOutputsWithProof(TransactionOrOutputListWithProof),
+    CompressionDictionary(Vec<u8>),
 }
 
 impl DataResponse {
@@ -151,6 +200,7 @@ impl DataResponse {
             Self::TransactionsWithProof(_) => "transactions_with_proof",
             Self::NewTransactionsOrOutputsWithProof(_) => "new_transactions_or_outputs_with_proof",
             Self::TransactionsOrOutputsWithProof(_) => "transactions_or_outputs_with_proof",
+            Self::CompressionDictionary(_) => "compression_dictionary",
         }
     }
 }
@@ -265,6 +315,21 @@ impl TryFrom<StorageServiceResponse> for ServerProtocolVersion {
     }
 }
 
+impl TryFrom<StorageServiceResponse> for Vec<u8> {
+    type Error = crate::responses::Error;
+
+    fn try_from(response: StorageServiceResponse) -> crate::Result<Self, Self::Error> {
+        let data_response = response.get_data_response()?;
+        match data_response {
+            DataResponse::CompressionDictionary(inner) => Ok(inner),
+            _ => Err(Error::UnexpectedResponseError(format!(
+                "expected compression_dictionary, found {}",
+                data_response.get_label()
+            ))),
+        }
+    }
+}
+
 impl TryFrom<StorageServiceResponse> for StorageServerSummary {
     type Error = crate::responses::Error;
 
@@ -347,6 +412,7 @@ impl TryFrom<StorageServiceResponse> for TransactionOrOutputListWithProof {
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ServerProtocolVersion {
     pub protocol_version: u64, // The storage server version run by this instance.
+    pub supported_compression_codecs: Vec<CompressionCodec>, // The codecs (and max levels) supported by the server
 }
 
 /// A storage server summary, containing a summary of the information held
@@ -377,13 +443,14 @@ pub struct ProtocolMetadata {
 impl ProtocolMetadata {
     /// Returns true iff the request can be serviced
     pub fn can_service(&self, request: &StorageServiceRequest) -> bool {
-        match &request.data_request {
+        match request.get_data_request() {
             GetNewTransactionsWithProof(_)
             | GetNewTransactionOutputsWithProof(_)
             | GetNewTransactionsOrOutputsWithProof(_)
             | GetNumberOfStatesAtVersion(_)
             | GetServerProtocolVersion
-            | GetStorageServerSummary => true,
+            | GetStorageServerSummary
+            | GetCompressionDictionary(_) => true,
             GetStateValuesWithProof(request) => CompleteDataRange::new(
                 request.start_index,
                 request.end_index,
@@ -430,6 +497,7 @@ impl ProtocolMetadata {
                         && self.max_transaction_output_chunk_size >= chunk_size
                 })
             }),
+            GetDataWithCompressionCodec(_) => false, // The codec request is removed above
         }
     }
 }
@@ -472,8 +540,10 @@ pub struct DataSummary {
 impl DataSummary {
     /// Returns true iff the request can be serviced
     pub fn can_service(&self, request: &StorageServiceRequest) -> bool {
-        match &request.data_request {
-            GetServerProtocolVersion | GetStorageServerSummary => true,
+        match request.get_data_request() {
+            GetServerProtocolVersion | GetStorageServerSummary | GetCompressionDictionary(_) => {
+                true
+            },
             GetEpochEndingLedgerInfos(request) => {
                 let desired_range =
                     match CompleteDataRange::new(request.start_epoch, request.expected_end_epoch) {
@@ -578,6 +648,7 @@ impl DataSummary {
 
                 can_serve_txns && can_serve_outputs && can_create_proof
             },
+            GetDataWithCompressionCodec(_) => false, // The codec request is removed above
         }
     }
 
@@ -7,9 +7,10 @@ use crate::{
         TransactionOutputsWithProofRequest, TransactionsOrOutputsWithProofRequest,
         TransactionsWithProofRequest,
     },
-    responses::{CompleteDataRange, DataSummary, ProtocolMetadata},
+    responses::{CompleteDataRange, DataSummary, ProtocolMetadata, ServerProtocolVersion},
     Epoch, StorageServiceRequest,
 };
+use aptos_compression::CompressionCodec;
 use aptos_crypto::hash::HashValue;
 use aptos_types::{
     aggregate_signature::AggregateSignature,
@@ -243,6 +244,89 @@ proptest! {
     }
 }
 
+#[test]
+fn test_compression_codec_requests() {
+    // Create a request for compressed data and verify the codec
+    let summary = DataSummary {
+        synced_ledger_info: Some(create_mock_ledger_info(250)),
+        transactions: Some(create_range(100, 200)),
+        ..Default::default()
+    };
+    let request = txns_request(225, 100, 200, true);
+    assert_eq!(request.get_compression_codec(), Some(CompressionCodec::Lz4));
+
+    // Request the same data compressed using Zstd and verify the request
+    let compression_codec = CompressionCodec::Zstd(3);
+    let codec_request = request.with_data_request(request.data_request.clone());
+    assert_eq!(codec_request, request);
+    let codec_request = StorageServiceRequest::new_with_compression_codec(
+        request.data_request.clone(),
+        compression_codec,
+    );
+    assert_eq!(
+        codec_request.get_compression_codec(),
+        Some(compression_codec)
+    );
+    assert_eq!(codec_request.get_data_request(), &request.data_request);
+    assert_eq!(codec_request.get_label(), request.get_label());
+    assert!(summary.can_service(&codec_request));
+    assert!(
+        !summary.can_service(&StorageServiceRequest::new_with_compression_codec(
+            txns_request(225, 100, 201, true).data_request,
+            compression_codec
+        ))
+    );
+
+    // Verify that new requests keep the codec
+    let new_request = codec_request.with_data_request(states_request(10, false).data_request);
+    assert_eq!(new_request.get_compression_codec(), Some(compression_codec));
+    assert_eq!(
+        new_request.get_data_request(),
+        &states_request(10, false).data_request
+    );
+
+    // Verify LZ4 requests don't wrap the data request (so all servers can read them)
+    let lz4_request = StorageServiceRequest::new_with_compression_codec(
+        codec_request.data_request,
+        CompressionCodec::Lz4,
+    );
+    assert_eq!(lz4_request, request);
+
+    // Verify uncompressed requests remain uncompressed
+    let request = txns_request(225, 100, 200, false);
+    assert_eq!(request.get_compression_codec(), None);
+    assert_eq!(
+        request.with_data_request(request.data_request.clone()),
+        request
+    );
+}
+
+#[test]
+fn test_wire_format_compatibility() {
+    // Verify the request is encoded as (data request, use compression), as
+    // expected by servers that don't support compression codecs.
+    let request = epochs_request(10, 20, true);
+    let mut expected_bytes = bcs::to_bytes(&request.data_request).unwrap();
+    expected_bytes.push(1);
+    assert_eq!(bcs::to_bytes(&request).unwrap(), expected_bytes);
+
+    // Verify the protocol version is encoded as the version number, followed
+    // by the supported compression codecs. The response of servers that don't
+    // support compression codecs fails to decode, so clients fall back to LZ4.
+    let supported_compression_codecs = vec![CompressionCodec::Lz4, CompressionCodec::Zstd(9)];
+    let server_protocol_version = ServerProtocolVersion {
+        protocol_version: 7,
+        supported_compression_codecs: supported_compression_codecs.clone(),
+    };
+    let mut expected_bytes = bcs::to_bytes(&7u64).unwrap();
+    expected_bytes.extend(bcs::to_bytes(&supported_compression_codecs).unwrap());
+    assert_eq!(
+        bcs::to_bytes(&server_protocol_version).unwrap(),
+        expected_bytes
+    );
+    assert!(bcs::from_bytes::<ServerProtocolVersion>(&bcs::to_bytes(&7u64).unwrap()).is_err());
+}
+
 fn create_mock_ledger_info(version: Version) -> LedgerInfoWithSignatures {
     LedgerInfoWithSignatures::new(
         LedgerInfo::new(
@@ -6,16 +6,22 @@ use crate::{
     pruner::{
         ledger_pruner_manager::LedgerPrunerManager, state_pruner_manager::StatePrunerManager,
     },
+    schema::{
+        db_metadata::{DbMetadataKey, DbMetadataSchema, DbMetadataValue},
+        state_value::StateValueSchema,
+    },
     test_helper,
     test_helper::{arb_blocks_to_commit, put_as_state_root, put_transaction_info},
+    utils::truncation_helper::{find_state_snapshot_before, set_truncation_target},
     AptosDB, PrunerManager, StaleNodeIndexSchema,
 };
 use aptos_config::config::{
-    EpochSnapshotPrunerConfig, LedgerPrunerConfig, PrunerConfig, RocksdbConfigs,
+    EpochSnapshotPrunerConfig, LedgerPrunerConfig, PrunerConfig, RocksdbConfig, RocksdbConfigs,
     StateMerklePrunerConfig, BUFFERED_STATE_TARGET_ITEMS,
-    DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+    DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD, NO_OP_STORAGE_PRUNER_CONFIG,
 };
 use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_db_indexer::Indexer;
 use aptos_storage_interface::{DbReader, DbWriter, ExecutedTrees, Order};
 use aptos_temppath::TempPath;
 use aptos_types::{
@@ -25,10 +31,13 @@ use aptos_types::{
         state_key::StateKey, state_storage_usage::StateStorageUsage, state_value::StateValue,
     },
     transaction::{ExecutionStatus, TransactionInfo, TransactionToCommit, Version},
+    write_set::WriteSet,
 };
 use proptest::prelude::*;
 use std::{collections::HashSet, sync::Arc};
-use test_helper::{test_save_blocks_impl, test_sync_transactions_impl};
+use test_helper::{
+    test_save_blocks_impl, test_save_blocks_in_memory_impl, test_sync_transactions_impl,
+};
 
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
@@ -38,6 +47,11 @@ proptest! {
         test_save_blocks_impl(input, threshold);
     }
 
+    #[test]
+    fn test_save_blocks_in_memory(input in arb_blocks_to_commit(), threshold in 10..20usize) {
+        test_save_blocks_in_memory_impl(input, threshold);
+    }
+
     #[test]
     fn test_sync_transactions(input in arb_blocks_to_commit(), threshold in 10..20usize) {
         test_sync_transactions_impl(input, threshold);
@@ -110,6 +124,8 @@ fn test_pruner_config() {
                 prune_window: 100,
                 batch_size: 1,
                 user_pruning_window_offset: 0,
+                archive_dir: None,
+                archive_chunk_bytes: 0,
             },
         );
         assert_eq!(ledger_pruner.is_pruner_enabled(), enable);
@@ -191,6 +207,8 @@ pub fn test_state_merkle_pruning_impl(
                 prune_window: 10,
                 batch_size: 1,
                 user_pruning_window_offset: 0,
+                archive_dir: None,
+                archive_chunk_bytes: 0,
             },
             state_merkle_pruner_config: StateMerklePrunerConfig {
                 enable: true,
@@ -296,3 +314,210 @@ proptest! {
         test_state_merkle_pruning_impl(input);
     }
 }
+
+pub fn test_truncate_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    let mut in_memory_state = db
+        .state_store
+        .buffered_state()
+        .lock()
+        .current_state()
+        .clone();
+    let mut next_ver: Version = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        test_helper::update_in_memory_state(&mut in_memory_state, txns_to_commit.as_slice());
+        db.save_transactions(
+            txns_to_commit,
+            next_ver,                /* first_version */
+            next_ver.checked_sub(1), /* base_state_version */
+            Some(ledger_info_with_sigs),
+            true, /* sync_commit */
+            in_memory_state.clone(),
+        )
+        .unwrap();
+        next_ver += txns_to_commit.len() as u64;
+    }
+
+    // Simulate a crash right after the truncation target is recorded, the truncation is carried
+    // out on the next open.
+    let (_, ledger_info) = &input[(input.len() - 1) / 2];
+    let target_version = ledger_info.ledger_info().version();
+    set_truncation_target(&db.ledger_db, target_version).unwrap();
+    drop(db);
+    let db = AptosDB::new_for_test(&tmp_dir);
+    assert_eq!(
+        db.get_latest_transaction_info_option()
+            .unwrap()
+            .map(|(version, _)| version),
+        Some(target_version)
+    );
+    assert_eq!(
+        db.get_latest_ledger_info().unwrap().ledger_info(),
+        ledger_info.ledger_info(),
+    );
+
+    // A target is rejected before anything is deleted if the write sets after the latest state
+    // snapshot before it have been pruned, since the state couldn't be replayed.
+    let (_, ledger_info) = &input[0];
+    let target_version = ledger_info.ledger_info().version();
+    let snapshot_version = find_state_snapshot_before(&db.state_merkle_db, target_version)
+        .unwrap()
+        .unwrap();
+    db.ledger_db
+        .put::<DbMetadataSchema>(
+            &DbMetadataKey::LedgerPrunerProgress,
+            &DbMetadataValue::Version(snapshot_version + 1),
+        )
+        .unwrap();
+    let latest_version = db.get_latest_version().unwrap();
+    drop(db);
+    AptosDB::truncate(&tmp_dir, RocksdbConfigs::default(), target_version).unwrap_err();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    assert_eq!(db.get_latest_version().unwrap(), latest_version);
+    db.ledger_db
+        .delete::<DbMetadataSchema>(&DbMetadataKey::LedgerPrunerProgress)
+        .unwrap();
+    drop(db);
+
+    // So is a target that isn't a state checkpoint, since the state at it couldn't be verified.
+    if let Some(idx) = input[0]
+        .0
+        .iter()
+        .position(|txn_to_commit| !txn_to_commit.is_state_checkpoint())
+    {
+        AptosDB::truncate(&tmp_dir, RocksdbConfigs::default(), idx as Version).unwrap_err();
+        let db = AptosDB::new_for_test(&tmp_dir);
+        assert_eq!(db.get_latest_version().unwrap(), latest_version);
+    }
+
+    let root_hash = AptosDB::truncate(&tmp_dir, RocksdbConfigs::default(), target_version).unwrap();
+    assert_eq!(
+        root_hash,
+        ledger_info.ledger_info().transaction_accumulator_hash()
+    );
+    let db = AptosDB::new_for_test(&tmp_dir);
+    assert_eq!(
+        db.get_latest_transaction_info_option()
+            .unwrap()
+            .map(|(version, _)| version),
+        Some(target_version)
+    );
+    assert!(db
+        .ledger_store
+        .get_transaction_info(target_version + 1)
+        .is_err());
+    test_helper::verify_committed_transactions(
+        &db,
+        &input[0].0,
+        0,
+        ledger_info,
+        true, /* is_latest */
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_truncate(input in arb_blocks_to_commit()) {
+        test_truncate_impl(input);
+    }
+}
+
+pub fn test_truncate_kv_db_and_indexer_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let rocksdb_configs = RocksdbConfigs {
+        use_kv_db: true,
+        ..Default::default()
+    };
+    let open_db = || {
+        AptosDB::open(
+            &tmp_dir,
+            false,
+            NO_OP_STORAGE_PRUNER_CONFIG,
+            rocksdb_configs,
+            false, /* indexer */
+            BUFFERED_STATE_TARGET_ITEMS,
+            DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+        )
+        .unwrap()
+    };
+    let db = Arc::new(open_db());
+    let mut in_memory_state = db
+        .state_store
+        .buffered_state()
+        .lock()
+        .current_state()
+        .clone();
+    let mut next_ver: Version = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        test_helper::update_in_memory_state(&mut in_memory_state, txns_to_commit.as_slice());
+        db.save_transactions(
+            txns_to_commit,
+            next_ver,                /* first_version */
+            next_ver.checked_sub(1), /* base_state_version */
+            Some(ledger_info_with_sigs),
+            true, /* sync_commit */
+            in_memory_state.clone(),
+        )
+        .unwrap();
+        next_ver += txns_to_commit.len() as u64;
+    }
+
+    // Mirror the state values into the K/V DB and index all the versions.
+    let kv_db = db._kv_db.as_ref().as_ref().unwrap();
+    let mut iter = db
+        .ledger_db
+        .iter::<StateValueSchema>(Default::default())
+        .unwrap();
+    iter.seek_to_first();
+    for item in iter {
+        let (key, value) = item.unwrap();
+        kv_db.put::<StateValueSchema>(&key, &value).unwrap();
+    }
+    let indexer = Indexer::open(&tmp_dir, RocksdbConfig::default()).unwrap();
+    let write_sets = vec![WriteSet::default(); next_ver as usize];
+    indexer
+        .index(
+            db.clone(),
+            0, /* first_version */
+            &write_sets.iter().collect::<Vec<_>>(),
+        )
+        .unwrap();
+    assert_eq!(indexer.next_version(), next_ver);
+    drop(indexer);
+    drop(db);
+
+    let (_, ledger_info) = &input[0];
+    let target_version = ledger_info.ledger_info().version();
+    AptosDB::truncate(&tmp_dir, rocksdb_configs, target_version).unwrap();
+
+    let db = open_db();
+    let kv_db = db._kv_db.as_ref().as_ref().unwrap();
+    let mut iter = kv_db.iter::<StateValueSchema>(Default::default()).unwrap();
+    iter.seek_to_first();
+    for item in iter {
+        let ((state_key, version), _) = item.unwrap();
+        assert!(
+            version <= target_version,
+            "{:?} at version {} wasn't truncated.",
+            state_key,
+            version,
+        );
+    }
+    drop(db);
+    let indexer = Indexer::open(&tmp_dir, RocksdbConfig::default()).unwrap();
+    assert_eq!(indexer.next_version(), target_version + 1);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_truncate_kv_db_and_indexer(input in arb_blocks_to_commit()) {
+        test_truncate_kv_db_and_indexer_impl(input);
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    db_debugger::common::DbDir,
+    db_options::{ledger_db_column_families, state_merkle_db_column_families},
+    LEDGER_DB_NAME, STATE_MERKLE_DB_NAME,
+};
+use anyhow::Result;
+use aptos_schemadb::{ColumnFamilyName, DB};
+use clap::Parser;
+
+const PROPERTIES: &[&str] = &[
+    "rocksdb.estimate-num-keys",
+    "rocksdb.estimate-live-data-size",
+    "rocksdb.total-sst-files-size",
+];
+
+#[derive(Parser)]
+#[clap(about = "Print size statistics of each column family.")]
+pub struct Cmd {
+    #[clap(flatten)]
+    db_dir: DbDir,
+}
+
+impl Cmd {
+    pub fn run(self) -> Result<()> {
+        print_stats(
+            LEDGER_DB_NAME,
+            &self.db_dir.open_ledger_db()?,
+            ledger_db_column_families(),
+        )?;
+        print_stats(
+            STATE_MERKLE_DB_NAME,
+            &self.db_dir.open_state_merkle_db()?,
+            state_merkle_db_column_families(),
+        )
+    }
+}
+
+fn print_stats(db_name: &str, db: &DB, cfs: Vec<ColumnFamilyName>) -> Result<()> {
+    println!("* {}", db_name);
+    println!(
+        "{:<40} {:>16} {:>20} {:>20}",
+        "column family", "num keys", "live data bytes", "sst file bytes"
+    );
+    let mut totals = [0u64; PROPERTIES.len()];
+    for cf_name in cfs {
+        let values = PROPERTIES
+            .iter()
+            .map(|property| db.get_property(cf_name, property))
+            .collect::<Result<Vec<_>>>()?;
+        println!(
+            "{:<40} {:>16} {:>20} {:>20}",
+            cf_name, values[0], values[1], values[2]
+        );
+        totals
+            .iter_mut()
+            .zip(values)
+            .for_each(|(total, value)| *total += value);
+    }
+    println!(
+        "{:<40} {:>16} {:>20} {:>20}\n",
+        "total", totals[0], totals[1], totals[2]
+    );
+    Ok(())
+}
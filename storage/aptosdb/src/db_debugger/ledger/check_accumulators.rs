@@ -0,0 +1,92 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{db_debugger::common::DbDir, event_store::EventStore, ledger_store::LedgerStore};
+use anyhow::{ensure, Result};
+use aptos_crypto::hash::{CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher};
+use aptos_types::{proof::accumulator::InMemoryAccumulator, transaction::Version};
+use clap::Parser;
+use std::sync::Arc;
+
+#[derive(Parser)]
+#[clap(
+    about = "Check the transaction accumulator and the per transaction event accumulators by \
+    recomputing them from the TransactionInfos and events."
+)]
+pub struct Cmd {
+    #[clap(flatten)]
+    db_dir: DbDir,
+
+    start_version: Version,
+
+    num_versions: usize,
+}
+
+impl Cmd {
+    pub fn run(self) -> Result<()> {
+        ensure!(self.num_versions > 0, "num_versions must be positive.");
+
+        let db = Arc::new(self.db_dir.open_ledger_db()?);
+        let ledger_store = LedgerStore::new(db.clone());
+        let event_store = EventStore::new(db);
+
+        println!("Checking event accumulators...");
+        let mut txn_info_hashes = Vec::with_capacity(self.num_versions);
+        let txn_info_iter =
+            ledger_store.get_transaction_info_iter(self.start_version, self.num_versions)?;
+        let events_iter =
+            event_store.get_events_by_version_iter(self.start_version, self.num_versions)?;
+        let mut version = self.start_version;
+        for (txn_info_res, events_res) in txn_info_iter.zip(events_iter) {
+            let txn_info = txn_info_res?;
+            let events = events_res?;
+            let event_hashes: Vec<_> = events.iter().map(CryptoHash::hash).collect();
+            let event_root_hash =
+                InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes)
+                    .root_hash();
+            ensure!(
+                event_root_hash == txn_info.event_root_hash(),
+                "Event root hash mismatch at version {}: computed {:?}, in TransactionInfo {:?}",
+                version,
+                event_root_hash,
+                txn_info.event_root_hash(),
+            );
+            txn_info_hashes.push(txn_info.hash());
+
+            if version % 10_000 == 0 {
+                println!("Good until version {}.", version);
+            }
+            version += 1;
+        }
+        ensure!(
+            txn_info_hashes.len() == self.num_versions,
+            "expecting {} txns, got {}",
+            self.num_versions,
+            txn_info_hashes.len(),
+        );
+
+        println!("Checking transaction accumulator...");
+        let frozen_subtree_hashes = ledger_store.get_frozen_subtree_hashes(self.start_version)?;
+        let accumulator = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            frozen_subtree_hashes,
+            self.start_version,
+        )?
+        .append(&txn_info_hashes);
+        let last_version = self.start_version + self.num_versions as u64 - 1;
+        let stored_root_hash = ledger_store.get_root_hash(last_version)?;
+        ensure!(
+            accumulator.root_hash() == stored_root_hash,
+            "Transaction accumulator root hash mismatch at version {}: computed {:?}, stored {:?}",
+            last_version,
+            accumulator.root_hash(),
+            stored_root_hash,
+        );
+        println!(
+            "Root hash at version {}: {:?}",
+            last_version, stored_root_hash
+        );
+
+        println!("Done.");
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    db_debugger::common::DbDir, event_store::EventStore, ledger_store::LedgerStore,
+    transaction_store::TransactionStore,
+};
+use anyhow::Result;
+use aptos_types::transaction::Version;
+use clap::Parser;
+use std::sync::Arc;
+
+#[derive(Parser)]
+#[clap(about = "Print a transaction, its TransactionInfo, events and write set.")]
+pub struct Cmd {
+    #[clap(flatten)]
+    db_dir: DbDir,
+
+    version: Version,
+}
+
+impl Cmd {
+    pub fn run(self) -> Result<()> {
+        let db = Arc::new(self.db_dir.open_ledger_db()?);
+        let transaction_store = TransactionStore::new(db.clone());
+        let ledger_store = LedgerStore::new(db.clone());
+        let event_store = EventStore::new(db);
+
+        println!("* Transaction at version {}:", self.version);
+        println!("{:#?}", transaction_store.get_transaction(self.version)?);
+
+        println!("\n* TransactionInfo:");
+        println!("{:#?}", ledger_store.get_transaction_info(self.version)?);
+
+        let events = event_store.get_events_by_version(self.version)?;
+        println!("\n* {} Events:", events.len());
+        for (idx, event) in events.iter().enumerate() {
+            println!("{} {:?}", idx, event);
+        }
+
+        let write_set = transaction_store.get_write_set(self.version)?;
+        println!("\n* {} Write set entries:", write_set.iter().count());
+        for (state_key, write_op) in write_set.iter() {
+            println!("{:?} => {:?}", state_key, write_op);
+        }
+
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+mod check_accumulators;
 mod check_range_proof;
 mod check_rxn_info_hashes;
+mod dump_transaction;
 
 use anyhow::Result;
 
@@ -11,6 +13,8 @@ use anyhow::Result;
 pub enum Cmd {
     CheckTransactionInfoHashes(check_rxn_info_hashes::Cmd),
     CheckRangeProof(check_range_proof::Cmd),
+    CheckAccumulators(check_accumulators::Cmd),
+    DumpTransaction(dump_transaction::Cmd),
 }
 
 impl Cmd {
@@ -18,6 +22,8 @@ impl Cmd {
         match self {
             Self::CheckTransactionInfoHashes(cmd) => cmd.run(),
             Self::CheckRangeProof(cmd) => cmd.run(),
+            Self::CheckAccumulators(cmd) => cmd.run(),
+            Self::DumpTransaction(cmd) => cmd.run(),
         }
     }
 }
@@ -1,10 +1,17 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+mod cf_stats;
 mod checkpoint;
 mod common;
 mod ledger;
+mod pruner_progress;
+mod state_kv;
 mod state_tree;
+mod truncate;
+
+#[cfg(test)]
+mod test;
 
 use anyhow::Result;
 use clap::Parser;
@@ -18,6 +25,15 @@ pub enum Cmd {
 
     #[clap(subcommand)]
     Ledger(ledger::Cmd),
+
+    #[clap(subcommand)]
+    StateKv(state_kv::Cmd),
+
+    CfStats(cf_stats::Cmd),
+
+    PrunerProgress(pruner_progress::Cmd),
+
+    Truncate(truncate::Cmd),
 }
 
 impl Cmd {
@@ -26,6 +42,10 @@ impl Cmd {
             Cmd::StateTree(cmd) => cmd.run(),
             Cmd::Checkpoint(cmd) => cmd.run(),
             Cmd::Ledger(cmd) => cmd.run(),
+            Cmd::StateKv(cmd) => cmd.run(),
+            Cmd::CfStats(cmd) => cmd.run(),
+            Cmd::PrunerProgress(cmd) => cmd.run(),
+            Cmd::Truncate(cmd) => cmd.run(),
         }
     }
 }
@@ -0,0 +1,58 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    db_debugger::common::DbDir,
+    schema::db_metadata::{DbMetadataKey, DbMetadataSchema},
+    utils::truncation_helper::get_ledger_commit_progress,
+};
+use anyhow::Result;
+use aptos_schemadb::DB;
+use clap::Parser;
+
+#[derive(Parser)]
+#[clap(about = "Print the progress of the pruners, i.e. the min readable versions.")]
+pub struct Cmd {
+    #[clap(flatten)]
+    db_dir: DbDir,
+}
+
+impl Cmd {
+    pub fn run(self) -> Result<()> {
+        let ledger_db = self.db_dir.open_ledger_db()?;
+        let state_merkle_db = self.db_dir.open_state_merkle_db()?;
+
+        println!(
+            "Latest version: {:?}",
+            get_ledger_commit_progress(&ledger_db)?
+        );
+        print_progress(&ledger_db, DbMetadataKey::LedgerPrunerProgress)?;
+        print_progress(&state_merkle_db, DbMetadataKey::StateMerklePrunerProgress)?;
+        print_progress(
+            &state_merkle_db,
+            DbMetadataKey::EpochEndingStateMerklePrunerProgress,
+        )?;
+
+        let mut iter = ledger_db.iter::<DbMetadataSchema>(Default::default())?;
+        iter.seek_to_first();
+        for res in iter {
+            if let (DbMetadataKey::StateSnapshotRestoreProgress(version), progress) = res? {
+                println!(
+                    "StateSnapshotRestoreProgress({}): {:?}",
+                    version,
+                    progress.expect_state_snapshot_progress()
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn print_progress(db: &DB, key: DbMetadataKey) -> Result<()> {
+    let progress = db
+        .get::<DbMetadataSchema>(&key)?
+        .map(|v| v.expect_version());
+    println!("{:?}: {:?}", key, progress);
+    Ok(())
+}
@@ -0,0 +1,61 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    db_debugger::common::{DbDir, PAGE_SIZE},
+    state_value::StateValueSchema,
+};
+use anyhow::Result;
+use aptos_types::{state_store::state_key::StateKey, transaction::Version};
+use clap::Parser;
+
+#[derive(Parser)]
+#[clap(about = "List the versions at which a state key was modified, newest first.")]
+pub struct Cmd {
+    #[clap(flatten)]
+    db_dir: DbDir,
+
+    #[clap(long, default_value = "18446744073709551615")]
+    next_version: Version,
+
+    /// Hex encoded `StateKey::encode()` bytes.
+    #[clap(long, parse(try_from_str=parse_state_key))]
+    state_key: StateKey,
+
+    #[clap(long, default_value_t = PAGE_SIZE)]
+    limit: usize,
+}
+
+impl Cmd {
+    pub fn run(self) -> Result<()> {
+        println!(
+            "* Versions strictly before {} that modified {:?}. \n",
+            self.next_version, self.state_key
+        );
+
+        if self.next_version > 0 {
+            let db = self.db_dir.open_ledger_db()?;
+            let mut iter = db.iter::<StateValueSchema>(Default::default())?;
+            // Versions are encoded inverted, so iterating forward goes from new to old.
+            iter.seek(&(self.state_key.clone(), self.next_version - 1))?;
+            for (n, res) in iter.take(self.limit).enumerate() {
+                let ((state_key, version), value) = res?;
+                if state_key != self.state_key {
+                    break;
+                }
+                match value {
+                    Some(value) => println!("{} {} {} bytes", n, version, value.bytes().len()),
+                    None => println!("{} {} deleted", n, version),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_state_key(src: &str) -> Result<StateKey> {
+    Ok(StateKey::decode(&hex::decode(
+        src.trim_start_matches("0x"),
+    )?)?)
+}
@@ -0,0 +1,20 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+mod list_versions;
+
+use anyhow::Result;
+
+#[derive(clap::Subcommand)]
+#[clap(about = "Examine the state values.")]
+pub enum Cmd {
+    ListVersions(list_versions::Cmd),
+}
+
+impl Cmd {
+    pub fn run(self) -> Result<()> {
+        match self {
+            Self::ListVersions(cmd) => cmd.run(),
+        }
+    }
+}
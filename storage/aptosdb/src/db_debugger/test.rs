@@ -0,0 +1,101 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    db_debugger::Cmd,
+    test_helper::{arb_blocks_to_commit, update_in_memory_state},
+    AptosDB,
+};
+use aptos_storage_interface::{DbReader, DbWriter};
+use aptos_temppath::TempPath;
+use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{TransactionToCommit, Version},
+};
+use clap::Parser;
+use proptest::prelude::*;
+use std::path::Path;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(5))]
+
+    #[test]
+    fn test_check_accumulators(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let path = tmp_dir.path();
+        let num_versions = commit_blocks(path, &input);
+
+        run(path, &["ledger", "check-accumulators", "0", &num_versions.to_string()]).unwrap();
+        run(path, &["ledger", "check-accumulators", "1", &(num_versions - 1).to_string()])
+            .unwrap();
+        // There are fewer transactions than requested.
+        run(path, &["ledger", "check-accumulators", "0", &(num_versions + 1).to_string()])
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_truncate(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let path = tmp_dir.path();
+        let num_versions = commit_blocks(path, &input);
+        let ledger_info = input[0].1.ledger_info();
+        let target_version = ledger_info.version().to_string();
+
+        run(path, &["truncate", "--target-version", &target_version, "--dry-run"]).unwrap();
+        prop_assert_eq!(latest_version(path), num_versions - 1);
+
+        run(path, &["truncate", "--target-version", &target_version]).unwrap();
+        prop_assert_eq!(latest_version(path), ledger_info.version());
+        run(path, &["ledger", "check-accumulators", "0", &(ledger_info.version() + 1).to_string()])
+            .unwrap();
+
+        // The target is newer than the latest version now.
+        run(path, &["truncate", "--target-version", &num_versions.to_string()]).unwrap_err();
+    }
+}
+
+/// Commits the blocks to a new DB at `path`, returning the number of transactions.
+fn commit_blocks(
+    path: &Path,
+    input: &[(Vec<TransactionToCommit>, LedgerInfoWithSignatures)],
+) -> Version {
+    let db = AptosDB::new_for_test(path);
+    let mut in_memory_state = db
+        .state_store
+        .buffered_state()
+        .lock()
+        .current_state()
+        .clone();
+    let mut next_ver: Version = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input {
+        update_in_memory_state(&mut in_memory_state, txns_to_commit.as_slice());
+        db.save_transactions(
+            txns_to_commit,
+            next_ver,                /* first_version */
+            next_ver.checked_sub(1), /* base_state_version */
+            Some(ledger_info_with_sigs),
+            true, /* sync_commit */
+            in_memory_state.clone(),
+        )
+        .unwrap();
+        next_ver += txns_to_commit.len() as u64;
+    }
+    next_ver
+}
+
+/// Runs the db-debugger with `args` against the DB at `path`.
+fn run(path: &Path, args: &[&str]) -> anyhow::Result<()> {
+    let db_dir = path.to_str().unwrap();
+    let mut cmd_args = vec!["db-debugger"];
+    cmd_args.extend_from_slice(args);
+    cmd_args.extend_from_slice(&["--db-dir", db_dir]);
+    Cmd::try_parse_from(cmd_args)?.run()
+}
+
+fn latest_version(path: &Path) -> Version {
+    AptosDB::new_for_test(path)
+        .get_latest_transaction_info_option()
+        .unwrap()
+        .unwrap()
+        .0
+}
@@ -0,0 +1,57 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    db_debugger::common::DbDir,
+    utils::truncation_helper::{check_truncation_target, find_state_snapshot_before},
+    AptosDB, KV_DB_NAME,
+};
+use anyhow::Result;
+use aptos_config::config::RocksdbConfigs;
+use aptos_types::transaction::Version;
+use clap::Parser;
+
+#[derive(Parser)]
+#[clap(about = "Roll the DB back to the target version, deleting everything committed after it.")]
+pub struct Cmd {
+    #[clap(flatten)]
+    db_dir: DbDir,
+
+    #[clap(long)]
+    target_version: Version,
+
+    /// Only run the safety checks, without deleting anything.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl Cmd {
+    pub fn run(self) -> Result<()> {
+        {
+            let ledger_db = self.db_dir.open_ledger_db()?;
+            let state_merkle_db = self.db_dir.open_state_merkle_db()?;
+            check_truncation_target(&ledger_db, &state_merkle_db, self.target_version)?;
+            println!(
+                "Truncating to version {}, the state will be replayed from the snapshot at version {:?}.",
+                self.target_version,
+                find_state_snapshot_before(&state_merkle_db, self.target_version)?,
+            );
+        }
+        if self.dry_run {
+            println!("Dry run, nothing deleted.");
+            return Ok(());
+        }
+
+        let rocksdb_configs = RocksdbConfigs {
+            use_kv_db: self.db_dir.as_ref().join(KV_DB_NAME).exists(),
+            ..Default::default()
+        };
+        let root_hash =
+            AptosDB::truncate(self.db_dir.as_ref(), rocksdb_configs, self.target_version)?;
+        println!(
+            "Done. Transaction accumulator root hash at version {}: {:?}",
+            self.target_version, root_hash
+        );
+        Ok(())
+    }
+}
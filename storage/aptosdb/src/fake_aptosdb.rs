@@ -15,7 +15,8 @@ use aptos_crypto::{
 };
 use aptos_infallible::Mutex;
 use aptos_storage_interface::{
-    state_delta::StateDelta, DbReader, DbWriter, ExecutedTrees, MAX_REQUEST_LIMIT,
+    state_delta::StateDelta, ArchivedTransaction, DbReader, DbWriter, ExecutedTrees,
+    MAX_REQUEST_LIMIT,
 };
 use aptos_types::{
     access_path::AccessPath,
@@ -735,6 +736,15 @@ impl DbReader for FakeAptosDB {
             .get_state_value_with_proof_by_version_ext(state_key, version)
     }
 
+    fn get_state_values_with_proof_by_version_ext(
+        &self,
+        state_keys: &[StateKey],
+        version: Version,
+    ) -> Result<Vec<(Option<StateValue>, SparseMerkleProofExt)>> {
+        self.inner
+            .get_state_values_with_proof_by_version_ext(state_keys, version)
+    }
+
     fn get_latest_executed_trees(&self) -> Result<ExecutedTrees> {
         // If the genesis is not executed yet, we need to get the executed trees from the inner AptosDB
         // This is because when we call save_transactions for the genesis block, we call [AptosDB::save_transactions]
@@ -779,6 +789,27 @@ impl DbReader for FakeAptosDB {
         Ok(HashValue::zero())
     }
 
+    fn get_archived_transaction(&self, version: Version) -> Result<Option<ArchivedTransaction>> {
+        self.inner.get_archived_transaction(version)
+    }
+
+    fn get_archived_transaction_by_hash(
+        &self,
+        hash: HashValue,
+    ) -> Result<Option<ArchivedTransaction>> {
+        self.inner.get_archived_transaction_by_hash(hash)
+    }
+
+    fn get_archived_events(
+        &self,
+        event_key: &EventKey,
+        start_seq_num: u64,
+        limit: u64,
+    ) -> Result<Vec<EventWithVersion>> {
+        self.inner
+            .get_archived_events(event_key, start_seq_num, limit)
+    }
+
     fn get_accumulator_consistency_proof(
         &self,
         client_known_version: Option<Version>,
@@ -64,23 +64,22 @@ use crate::{
     stale_node_index_cross_epoch::StaleNodeIndexCrossEpochSchema,
     state_store::StateStore,
     transaction_store::TransactionStore,
+    utils::truncation_helper,
 };
-use anyhow::{bail, ensure, Result};
-#[cfg(any(test, feature = "fuzzing"))]
-use aptos_config::config::DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD;
+use anyhow::{bail, ensure, format_err, Result};
 use aptos_config::config::{
     PrunerConfig, RocksdbConfig, RocksdbConfigs, BUFFERED_STATE_TARGET_ITEMS,
-    NO_OP_STORAGE_PRUNER_CONFIG,
+    DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD, NO_OP_STORAGE_PRUNER_CONFIG,
 };
-use aptos_crypto::hash::HashValue;
-use aptos_db_indexer::Indexer;
+use aptos_crypto::hash::{CryptoHash, HashValue, TransactionAccumulatorHasher};
+use aptos_db_indexer::{Indexer, INDEX_DB_NAME};
 use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
 use aptos_rocksdb_options::gen_rocksdb_options;
 use aptos_schemadb::{SchemaBatch, DB};
 use aptos_storage_interface::{
-    state_delta::StateDelta, state_view::DbStateView, DbReader, DbWriter, ExecutedTrees, Order,
-    StateSnapshotReceiver, MAX_REQUEST_LIMIT,
+    state_delta::StateDelta, state_view::DbStateView, ArchivedTransaction, DbReader, DbWriter,
+    Error as StorageError, ExecutedTrees, Order, StateSnapshotReceiver, MAX_REQUEST_LIMIT,
 };
 use aptos_types::{
     account_address::AccountAddress,
@@ -263,16 +262,14 @@ pub struct AptosDB {
 
 impl AptosDB {
     fn new_with_dbs(
-        ledger_rocksdb: DB,
-        state_merkle_rocksdb: DB,
+        arc_ledger_rocksdb: Arc<DB>,
+        arc_state_merkle_rocksdb: Arc<DB>,
         kv_rocksdb: Option<DB>,
         pruner_config: PrunerConfig,
         buffered_state_target_items: usize,
         max_nodes_per_lru_cache_shard: usize,
         hack_for_tests: bool,
     ) -> Self {
-        let arc_ledger_rocksdb = Arc::new(ledger_rocksdb);
-        let arc_state_merkle_rocksdb = Arc::new(state_merkle_rocksdb);
         let arc_kv_rocksdb = Arc::new(kv_rocksdb);
         let state_pruner = StatePrunerManager::new(
             Arc::clone(&arc_state_merkle_rocksdb),
@@ -334,24 +331,67 @@ impl AptosDB {
         let kv_db_path = db_root_path.as_ref().join(KV_DB_NAME);
         let instant = Instant::now();
 
-        let (ledger_db, state_merkle_db, kv_db) = if readonly {
+        let (ledger_db, state_merkle_db, kv_db) =
+            Self::open_dbs(db_root_path.clone(), &rocksdb_configs, readonly)?;
+        let ledger_db = Arc::new(ledger_db);
+        let state_merkle_db = Arc::new(state_merkle_db);
+        if !readonly {
+            // Finish a truncation left behind by a crash, before the state is loaded.
+            truncation_helper::resume_truncation(&ledger_db, &state_merkle_db, kv_db.as_ref())?;
+        }
+
+        let mut myself = Self::new_with_dbs(
+            ledger_db,
+            state_merkle_db,
+            kv_db,
+            pruner_config,
+            buffered_state_target_items,
+            max_num_nodes_per_lru_cache_shard,
+            readonly,
+        );
+
+        if !readonly && enable_indexer {
+            myself.open_indexer(db_root_path, rocksdb_configs.index_db_config)?;
+        }
+
+        if rocksdb_configs.use_kv_db {
+            info!(kv_db_path = kv_db_path, "Opened K/V DB.",);
+        }
+        info!(
+            ledger_db_path = ledger_db_path,
+            state_merkle_db_path = state_merkle_db_path,
+            time_ms = %instant.elapsed().as_millis(),
+            "Opened AptosDB (LedgerDB + StateMerkleDB).",
+        );
+        Ok(myself)
+    }
+
+    fn open_dbs<P: AsRef<Path>>(
+        db_root_path: P,
+        rocksdb_configs: &RocksdbConfigs,
+        readonly: bool,
+    ) -> Result<(DB, DB, Option<DB>)> {
+        let ledger_db_path = db_root_path.as_ref().join(LEDGER_DB_NAME);
+        let state_merkle_db_path = db_root_path.as_ref().join(STATE_MERKLE_DB_NAME);
+        let kv_db_path = db_root_path.as_ref().join(KV_DB_NAME);
+        Ok(if readonly {
             (
                 DB::open_cf_readonly(
                     &gen_rocksdb_options(&rocksdb_configs.ledger_db_config, true),
-                    ledger_db_path.clone(),
+                    ledger_db_path,
                     LEDGER_DB_NAME,
                     ledger_db_column_families(),
                 )?,
                 DB::open_cf_readonly(
                     &gen_rocksdb_options(&rocksdb_configs.state_merkle_db_config, true),
-                    state_merkle_db_path.clone(),
+                    state_merkle_db_path,
                     STATE_MERKLE_DB_NAME,
                     state_merkle_db_column_families(),
                 )?,
                 if rocksdb_configs.use_kv_db {
                     Some(DB::open_cf_readonly(
                         &gen_rocksdb_options(&rocksdb_configs.kv_db_config, true),
-                        kv_db_path.clone(),
+                        kv_db_path,
                         KV_DB_NAME,
                         kv_db_column_families(),
                     )?)
@@ -363,20 +403,20 @@ impl AptosDB {
             (
                 DB::open_cf(
                     &gen_rocksdb_options(&rocksdb_configs.ledger_db_config, false),
-                    ledger_db_path.clone(),
+                    ledger_db_path,
                     LEDGER_DB_NAME,
                     gen_ledger_cfds(&rocksdb_configs.ledger_db_config),
                 )?,
                 DB::open_cf(
                     &gen_rocksdb_options(&rocksdb_configs.state_merkle_db_config, false),
-                    state_merkle_db_path.clone(),
+                    state_merkle_db_path,
                     STATE_MERKLE_DB_NAME,
                     gen_state_merkle_cfds(&rocksdb_configs.state_merkle_db_config),
                 )?,
                 if rocksdb_configs.use_kv_db {
                     Some(DB::open_cf(
                         &gen_rocksdb_options(&rocksdb_configs.kv_db_config, false),
-                        kv_db_path.clone(),
+                        kv_db_path,
                         KV_DB_NAME,
                         gen_kv_cfds(&rocksdb_configs.kv_db_config),
                     )?)
@@ -384,32 +424,31 @@ impl AptosDB {
                     None
                 },
             )
-        };
+        })
+    }
 
-        let mut myself = Self::new_with_dbs(
-            ledger_db,
-            state_merkle_db,
-            kv_db,
-            pruner_config,
+    /// Opens an `AptosDB` that lives in memory only, without the pruner and the indexer. It
+    /// behaves the same as one opened on disk, for tests and benchmarks that don't need
+    /// persistence and don't want to pay for disk I/O.
+    pub fn open_in_memory(
+        buffered_state_target_items: usize,
+        max_num_nodes_per_lru_cache_shard: usize,
+    ) -> Self {
+        Self::new_with_dbs(
+            Arc::new(DB::open_in_memory(
+                LEDGER_DB_NAME,
+                ledger_db_column_families(),
+            )),
+            Arc::new(DB::open_in_memory(
+                STATE_MERKLE_DB_NAME,
+                state_merkle_db_column_families(),
+            )),
+            None,
+            NO_OP_STORAGE_PRUNER_CONFIG,
             buffered_state_target_items,
             max_num_nodes_per_lru_cache_shard,
-            readonly,
-        );
-
-        if !readonly && enable_indexer {
-            myself.open_indexer(db_root_path, rocksdb_configs.index_db_config)?;
-        }
-
-        if rocksdb_configs.use_kv_db {
-            info!(kv_db_path = kv_db_path, "Opened K/V DB.",);
-        }
-        info!(
-            ledger_db_path = ledger_db_path,
-            state_merkle_db_path = state_merkle_db_path,
-            time_ms = %instant.elapsed().as_millis(),
-            "Opened AptosDB (LedgerDB + StateMerkleDB).",
-        );
-        Ok(myself)
+            false,
+        )
     }
 
     fn open_indexer(
@@ -421,6 +460,8 @@ impl AptosDB {
         let ledger_next_version = self
             .get_latest_transaction_info_option()?
             .map_or(0, |(v, _)| v + 1);
+        // The ledger has been truncated since the indexer was last opened.
+        indexer.rewind(ledger_next_version)?;
         info!(
             indexer_next_version = indexer.next_version(),
             ledger_next_version = ledger_next_version,
@@ -474,20 +515,20 @@ impl AptosDB {
         rocksdb_configs.state_merkle_db_config.max_open_files = -1;
 
         Ok(Self::new_with_dbs(
-            DB::open_cf_as_secondary(
+            Arc::new(DB::open_cf_as_secondary(
                 &gen_rocksdb_options(&rocksdb_configs.ledger_db_config, false),
                 ledger_db_primary_path,
                 ledger_db_secondary_path,
                 "ledgerdb_sec",
                 ledger_db_column_families(),
-            )?,
-            DB::open_cf_as_secondary(
+            )?),
+            Arc::new(DB::open_cf_as_secondary(
                 &gen_rocksdb_options(&rocksdb_configs.state_merkle_db_config, false),
                 state_merkle_db_primary_path,
                 state_merkle_db_secondary_path,
                 "state_merkle_db_sec",
                 state_merkle_db_column_families(),
-            )?,
+            )?),
             if rocksdb_configs.use_kv_db {
                 Some(DB::open_cf_as_secondary(
                     &gen_rocksdb_options(&rocksdb_configs.kv_db_config, false),
@@ -506,6 +547,113 @@ impl AptosDB {
         ))
     }
 
+    /// Rolls the DB back to `target_version`, deleting everything committed after it, including
+    /// from the K/V DB and the indexer DB if they exist.
+    ///
+    /// The target is persisted before anything is deleted and an interrupted truncation is
+    /// finished the next time the DB is opened for write, so it's safe to crash at any point.
+    /// The target must be a state checkpoint (e.g. the end of a block). Returns the transaction
+    /// accumulator root hash at `target_version`, after checking it extends the latest remaining
+    /// `LedgerInfo` and that the replayed state matches the state root hash recorded for it.
+    pub fn truncate<P: AsRef<Path> + Clone>(
+        db_root_path: P,
+        rocksdb_configs: RocksdbConfigs,
+        target_version: Version,
+    ) -> Result<HashValue> {
+        {
+            let (ledger_db, state_merkle_db, _kv_db) =
+                Self::open_dbs(db_root_path.clone(), &rocksdb_configs, false)?;
+            truncation_helper::check_truncation_target(
+                &ledger_db,
+                &state_merkle_db,
+                target_version,
+            )?;
+            truncation_helper::set_truncation_target(&ledger_db, target_version)?;
+        }
+
+        // Opening the DB for write carries out the truncation, then rebuilds the buffered state
+        // by replaying the write sets after the latest remaining state snapshot. Opening the
+        // indexer, if there is one, rewinds it to the target version.
+        let enable_indexer = db_root_path.as_ref().join(INDEX_DB_NAME).exists();
+        let db = Self::open(
+            db_root_path,
+            false,
+            NO_OP_STORAGE_PRUNER_CONFIG, /* pruner */
+            rocksdb_configs,
+            enable_indexer,
+            BUFFERED_STATE_TARGET_ITEMS,
+            DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+        )?;
+        db.verify_truncation(target_version)
+    }
+
+    fn verify_truncation(&self, target_version: Version) -> Result<HashValue> {
+        let (latest_version, _) = self.ledger_store.get_latest_transaction_info()?;
+        ensure!(
+            latest_version == target_version,
+            "Truncation incomplete, latest version: {}, target version: {}",
+            latest_version,
+            target_version,
+        );
+
+        let root_hash = self.ledger_store.get_root_hash(target_version)?;
+        if let Some(ledger_info) = self.ledger_store.get_latest_ledger_info_option() {
+            let li_num_txns = ledger_info.ledger_info().version() + 1;
+            let accumulator = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+                self.ledger_store.get_frozen_subtree_hashes(li_num_txns)?,
+                li_num_txns,
+            )?;
+            ensure!(
+                accumulator.root_hash() == ledger_info.ledger_info().transaction_accumulator_hash(),
+                "Accumulator root hash at version {} doesn't match the LedgerInfo.",
+                li_num_txns - 1,
+            );
+            let txn_info_hashes = self
+                .ledger_store
+                .get_transaction_info_iter(
+                    li_num_txns,
+                    (target_version + 1 - li_num_txns) as usize,
+                )?
+                .map(|res| res.map(|txn_info| txn_info.hash()))
+                .collect::<Result<Vec<_>>>()?;
+            ensure!(
+                accumulator.append(&txn_info_hashes).root_hash() == root_hash,
+                "Accumulator root hash at version {} doesn't extend the latest LedgerInfo.",
+                target_version,
+            );
+        }
+
+        // The state replayed from the latest remaining snapshot up to the target (which is a
+        // checkpoint) must match the state root hash recorded for it.
+        let expected_state_root_hash = self
+            .ledger_store
+            .get_transaction_info(target_version)?
+            .state_checkpoint_hash()
+            .ok_or_else(|| format_err!("Version {} is not a checkpoint.", target_version))?;
+        let buffered_state = self.state_store.buffered_state().lock();
+        let state = buffered_state.current_state();
+        ensure!(
+            state.current_version == Some(target_version),
+            "State replayed up to version {:?}, target version: {}",
+            state.current_version,
+            target_version,
+        );
+        ensure!(
+            state.current.root_hash() == expected_state_root_hash,
+            "State root hash mismatch at version {}, expected: {:?}, got: {:?}",
+            target_version,
+            expected_state_root_hash,
+            state.current.root_hash(),
+        );
+
+        info!(
+            target_version = target_version,
+            root_hash = %root_hash,
+            "Truncated AptosDB."
+        );
+        Ok(root_hash)
+    }
+
     #[cfg(any(test, feature = "fuzzing"))]
     fn new_without_pruner<P: AsRef<Path> + Clone>(
         db_root_path: P,
@@ -764,6 +912,17 @@ impl AptosDB {
         // Convert requested range and order to a range in ascending order.
         let (first_seq, real_limit) = get_first_seq_num_and_limit(order, cursor, limit)?;
 
+        // Sequence numbers start from 0 and the indices of pruned events are deleted with them, so
+        // the events before the oldest one left are pruned.
+        if let Some(oldest_seq) = self.event_store.get_oldest_sequence_number(event_key)? {
+            if first_seq < oldest_seq {
+                let min_readable_version = self.ledger_pruner.get_min_readable_version();
+                return Err(self
+                    .ledger_pruned_error("Event", min_readable_version.saturating_sub(1))
+                    .into());
+            }
+        }
+
         // Query the index.
         let mut event_indices = self.event_store.lookup_events_by_key(
             event_key,
@@ -899,17 +1058,20 @@ impl AptosDB {
     }
 
     fn error_if_ledger_pruned(&self, data_type: &str, version: Version) -> Result<()> {
-        let min_readable_version = self.ledger_pruner.get_min_readable_version();
-        ensure!(
-            version >= min_readable_version,
-            "{} at version {} is pruned, min available version is {}.",
-            data_type,
-            version,
-            min_readable_version
-        );
+        if version < self.ledger_pruner.get_min_readable_version() {
+            return Err(self.ledger_pruned_error(data_type, version).into());
+        }
         Ok(())
     }
 
+    fn ledger_pruned_error(&self, data_type: &str, version: Version) -> StorageError {
+        StorageError::Pruned {
+            data_type: data_type.to_string(),
+            version,
+            min_readable_version: self.ledger_pruner.get_min_readable_version(),
+        }
+    }
+
     fn error_if_state_merkle_pruned(&self, data_type: &str, version: Version) -> Result<()> {
         let min_readable_version = self
             .state_store
@@ -1395,6 +1557,32 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_state_values_by_version(
+        &self,
+        state_store_keys: &[StateKey],
+        version: Version,
+    ) -> Result<Vec<Option<StateValue>>> {
+        gauged_api("get_state_values_by_version", || {
+            self.error_if_ledger_pruned("State", version)?;
+
+            self.state_store
+                .get_state_values_by_version(state_store_keys, version)
+        })
+    }
+
+    fn get_state_values_with_proof_by_version_ext(
+        &self,
+        state_store_keys: &[StateKey],
+        version: Version,
+    ) -> Result<Vec<(Option<StateValue>, SparseMerkleProofExt)>> {
+        gauged_api("get_state_values_with_proof_by_version_ext", || {
+            self.error_if_state_merkle_pruned("State merkle", version)?;
+
+            self.state_store
+                .get_state_values_with_proof_by_version_ext(state_store_keys, version)
+        })
+    }
+
     fn get_latest_epoch_state(&self) -> Result<EpochState> {
         gauged_api("get_latest_epoch_state", || {
             let latest_ledger_info = self.ledger_store.get_latest_ledger_info()?;
@@ -1546,6 +1734,42 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_archived_transaction(&self, version: Version) -> Result<Option<ArchivedTransaction>> {
+        gauged_api("get_archived_transaction", || {
+            match self.ledger_pruner.archive() {
+                Some(archive) => archive.get_transaction(version),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn get_archived_transaction_by_hash(
+        &self,
+        hash: HashValue,
+    ) -> Result<Option<ArchivedTransaction>> {
+        gauged_api("get_archived_transaction_by_hash", || {
+            match self.ledger_pruner.archive() {
+                Some(archive) => archive.get_transaction_by_hash(hash),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn get_archived_events(
+        &self,
+        event_key: &EventKey,
+        start_seq_num: u64,
+        limit: u64,
+    ) -> Result<Vec<EventWithVersion>> {
+        gauged_api("get_archived_events", || {
+            error_if_too_many_requested(limit, MAX_REQUEST_LIMIT)?;
+            match self.ledger_pruner.archive() {
+                Some(archive) => archive.get_events(event_key, start_seq_num, limit),
+                None => Ok(Vec::new()),
+            }
+        })
+    }
+
     fn get_accumulator_consistency_proof(
         &self,
         client_known_version: Option<Version>,
@@ -75,6 +75,8 @@ fn verify_event_store_pruner(events: Vec<Vec<ContractEvent>>) {
             prune_window: 0,
             batch_size: 1,
             user_pruning_window_offset: 0,
+            archive_dir: None,
+            archive_chunk_bytes: 0,
         },
     );
     // start pruning events batches of size 2 and verify transactions have been pruned from DB
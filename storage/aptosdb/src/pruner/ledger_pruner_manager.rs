@@ -4,8 +4,10 @@
 use crate::{
     metrics::{PRUNER_BATCH_SIZE, PRUNER_WINDOW},
     pruner::{
-        db_pruner::DBPruner, ledger_pruner_worker::LedgerPrunerWorker,
-        ledger_store::ledger_store_pruner::LedgerPruner, pruner_manager::PrunerManager,
+        db_pruner::DBPruner,
+        ledger_pruner_worker::LedgerPrunerWorker,
+        ledger_store::{ledger_archive::LedgerArchive, ledger_store_pruner::LedgerPruner},
+        pruner_manager::PrunerManager,
     },
     pruner_utils, StateStore,
 };
@@ -104,7 +106,12 @@ impl LedgerPrunerManager {
         state_store: Arc<StateStore>,
         ledger_pruner_config: LedgerPrunerConfig,
     ) -> Self {
-        let ledger_pruner = pruner_utils::create_ledger_pruner(ledger_rocksdb, state_store);
+        let ledger_pruner = pruner_utils::create_ledger_pruner(
+            ledger_rocksdb,
+            state_store,
+            ledger_pruner_config.archive_dir.as_deref(),
+            ledger_pruner_config.archive_chunk_bytes,
+        );
 
         if ledger_pruner_config.enable {
             PRUNER_WINDOW
@@ -118,7 +125,7 @@ impl LedgerPrunerManager {
 
         let ledger_pruner_worker = Arc::new(LedgerPrunerWorker::new(
             Arc::clone(&ledger_pruner),
-            ledger_pruner_config,
+            ledger_pruner_config.clone(),
         ));
 
         let ledger_pruner_worker_clone = Arc::clone(&ledger_pruner_worker);
@@ -149,6 +156,11 @@ impl LedgerPrunerManager {
         }
     }
 
+    /// Returns the archive pruned transactions are exported to, if configured.
+    pub(crate) fn archive(&self) -> Option<&Arc<LedgerArchive>> {
+        self.pruner.archive()
+    }
+
     #[cfg(test)]
     pub fn testonly_update_min_version(&self, version: Version) {
         self.pruner.testonly_update_min_version(version);
@@ -0,0 +1,1014 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines `LedgerArchive`, which the ledger pruner exports transactions to before
+//! pruning them, so that historical reads can still be served.
+//!
+//! Sealed chunks are laid out exactly like transaction backups in a `LocalFs` backup storage of
+//! the backup-cli, so they can be restored from or verified by the regular backup tooling:
+//!
+//!   <archive_dir>/transaction_<first>-<last>/<first>-.chunk
+//!   <archive_dir>/transaction_<first>-<last>/<first>-<last>.proof
+//!   <archive_dir>/transaction_<first>-<last>/transaction.manifest
+//!   <archive_dir>/metadata/transaction_<first>-<last>.meta
+//!
+//! On top of that, each chunk has a few files the backup tooling ignores:
+//!   * `<first>-.aux` has a fixed width record per transaction, with its hash, the accumulator root
+//!     hash, the block timestamp and the offset of the transaction in the chunk. The first two
+//!     can't be derived from the chunk alone once the ledger is pruned, and the offset makes
+//!     reading a transaction two seeks.
+//!   * `<first>-<last>.hashes` has the transaction hashes and versions, sorted by hash, and
+//!     `<first>-<last>.bloom` a bloom filter of them, to find transactions by hash.
+//!   * `<first>-<last>.events` has the event keys and sequence numbers of the events in the chunk,
+//!     with their versions and indices in the transaction, sorted by event key and sequence
+//!     number, to find events by key once their indices are pruned from the DB.
+//!
+//! The pruner works in small batches, which are appended to an open chunk in
+//! `<archive_dir>/.open_transaction_<first>` until it reaches the target size, at which point it's
+//! sealed and renamed to the above. The `progress` file in the open chunk records how far it's
+//! valid and the range proof so far, since the accumulator nodes needed for the proof of the whole
+//! chunk are pruned by the time it's sealed. Everything is fsynced before `archive()` returns, as
+//! the pruner deletes the transactions from the DB right after.
+
+use crate::{backup::backup_handler::BackupHandler, EventStore, LedgerStore};
+use anyhow::{ensure, format_err, Context, Result};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_infallible::RwLock;
+use aptos_logger::{info, warn};
+use aptos_storage_interface::ArchivedTransaction;
+use aptos_types::{
+    account_config::{new_block_event_key, NewBlockEvent},
+    contract_event::{ContractEvent, EventWithVersion},
+    event::EventKey,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::TransactionAccumulatorRangeProof,
+    transaction::{Transaction, TransactionInfo, Version},
+    write_set::WriteSet,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+const METADATA_DIR: &str = "metadata";
+const MANIFEST_NAME: &str = "transaction.manifest";
+const PROGRESS_NAME: &str = "progress";
+const BACKUP_PREFIX: &str = "transaction_";
+const OPEN_CHUNK_PREFIX: &str = ".open_transaction_";
+const TMP_PREFIX: &str = ".tmp_";
+
+/// Transaction hash, accumulator root hash, block timestamp and offset in the chunk.
+const AUX_RECORD_BYTES: usize = HashValue::LENGTH * 2 + 8 + 8;
+/// Transaction hash and version.
+const HASH_RECORD_BYTES: usize = HashValue::LENGTH + 8;
+/// Event key, sequence number, version and index in the transaction.
+const EVENT_RECORD_BYTES: usize = EventKey::LENGTH + 8 + 8 + 8;
+/// With 4 probes, this gives about 1% false positives.
+const BLOOM_BITS_PER_TRANSACTION: usize = 10;
+const BLOOM_NUM_PROBES: usize = 4;
+
+/// Same as `TransactionChunk` in the backup-cli transaction backup manifest.
+#[derive(Deserialize, Serialize)]
+struct TransactionChunk {
+    first_version: Version,
+    last_version: Version,
+    transactions: String,
+    proof: String,
+}
+
+/// Same as `TransactionBackup` in the backup-cli transaction backup manifest.
+#[derive(Deserialize, Serialize)]
+struct TransactionBackup {
+    first_version: Version,
+    last_version: Version,
+    chunks: Vec<TransactionChunk>,
+}
+
+/// Same as `TransactionBackupMeta` in the backup-cli metadata.
+#[derive(Clone, Deserialize, Serialize)]
+struct TransactionBackupMeta {
+    first_version: Version,
+    last_version: Version,
+    manifest: String,
+}
+
+/// The subset of the backup-cli `Metadata` that the archive writes.
+#[derive(Deserialize, Serialize)]
+enum Metadata {
+    TransactionBackup(TransactionBackupMeta),
+}
+
+type TransactionRecord = (Transaction, TransactionInfo, Vec<ContractEvent>, WriteSet);
+
+/// Event key and sequence number, to version and index in the transaction.
+type EventIndex = BTreeMap<(EventKey, u64), (Version, u64)>;
+
+struct AuxRecord {
+    transaction_hash: HashValue,
+    accumulator_root_hash: HashValue,
+    block_timestamp_usecs: u64,
+    offset: u64,
+}
+
+impl AuxRecord {
+    fn to_bytes(&self) -> [u8; AUX_RECORD_BYTES] {
+        let mut bytes = [0u8; AUX_RECORD_BYTES];
+        bytes[..32].copy_from_slice(self.transaction_hash.as_ref());
+        bytes[32..64].copy_from_slice(self.accumulator_root_hash.as_ref());
+        bytes[64..72].copy_from_slice(&self.block_timestamp_usecs.to_be_bytes());
+        bytes[72..].copy_from_slice(&self.offset.to_be_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        ensure!(
+            bytes.len() == AUX_RECORD_BYTES,
+            "Archive corrupted: bad aux record length {}.",
+            bytes.len()
+        );
+        Ok(Self {
+            transaction_hash: HashValue::from_slice(&bytes[..32])?,
+            accumulator_root_hash: HashValue::from_slice(&bytes[32..64])?,
+            block_timestamp_usecs: u64::from_be_bytes(bytes[64..72].try_into()?),
+            offset: u64::from_be_bytes(bytes[72..].try_into()?),
+        })
+    }
+}
+
+/// Persisted in the open chunk after every batch.
+#[derive(Deserialize, Serialize)]
+struct OpenChunkProgress {
+    first_version: Version,
+    /// The version after the last one in the chunk.
+    next_version: Version,
+    /// Length of the chunk file, anything after it is from an interrupted batch.
+    chunk_bytes: u64,
+    /// The range proof of the chunk so far, i.e. the left siblings of its first version and the
+    /// right siblings of its last version, with the ledger info it's relative to.
+    proof: (TransactionAccumulatorRangeProof, LedgerInfoWithSignatures),
+}
+
+struct OpenChunk {
+    dir: PathBuf,
+    progress: OpenChunkProgress,
+    /// Transaction hashes in the chunk, to find them by hash until the chunk is sealed.
+    hashes: HashMap<HashValue, Version>,
+    /// Events in the chunk, to find them by key until the chunk is sealed.
+    events: EventIndex,
+}
+
+struct SealedChunk {
+    dir: PathBuf,
+    last_version: Version,
+    bloom: BloomFilter,
+}
+
+/// A bloom filter of transaction hashes. Transaction hashes are uniformly distributed already, so
+/// the probes are taken from the hash itself.
+struct BloomFilter {
+    bits: Vec<u8>,
+}
+
+impl BloomFilter {
+    fn new<'a>(hashes: impl ExactSizeIterator<Item = &'a HashValue>) -> Self {
+        let num_bytes = (hashes.len() * BLOOM_BITS_PER_TRANSACTION + 7) / 8;
+        let mut bits = vec![0u8; num_bytes.max(1)];
+        let num_bits = bits.len() * 8;
+        for hash in hashes {
+            for bit in Self::probes(num_bits, hash) {
+                bits[bit / 8] |= 1 << (bit % 8);
+            }
+        }
+        Self { bits }
+    }
+
+    fn may_contain(&self, hash: &HashValue) -> bool {
+        Self::probes(self.bits.len() * 8, hash)
+            .all(|bit| self.bits[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    fn probes(num_bits: usize, hash: &HashValue) -> impl Iterator<Item = usize> + '_ {
+        (0..BLOOM_NUM_PROBES).map(move |i| {
+            let probe = u64::from_le_bytes(hash.as_ref()[i * 8..(i + 1) * 8].try_into().unwrap());
+            (probe % num_bits as u64) as usize
+        })
+    }
+}
+
+pub(crate) struct LedgerArchive {
+    dir: PathBuf,
+    /// An open chunk is sealed once it's at least this large.
+    target_chunk_bytes: u64,
+    /// Sealed chunks, keyed by their first version.
+    sealed: RwLock<BTreeMap<Version, Arc<SealedChunk>>>,
+    open: RwLock<Option<OpenChunk>>,
+    backup_handler: BackupHandler,
+    ledger_store: Arc<LedgerStore>,
+    event_store: Arc<EventStore>,
+}
+
+impl LedgerArchive {
+    pub fn open(
+        dir: impl AsRef<Path>,
+        target_chunk_bytes: u64,
+        backup_handler: BackupHandler,
+        ledger_store: Arc<LedgerStore>,
+        event_store: Arc<EventStore>,
+    ) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let metadata_dir = dir.join(METADATA_DIR);
+        fs::create_dir_all(&metadata_dir)
+            .with_context(|| format!("Failed to create {:?}", metadata_dir))?;
+
+        let mut sealed = BTreeMap::new();
+        for entry in fs::read_dir(&metadata_dir)? {
+            let path = entry?.path();
+            if path.extension().map_or(true, |ext| ext != "meta") {
+                continue;
+            }
+            for line in fs::read_to_string(&path)?.lines() {
+                let Metadata::TransactionBackup(meta) = serde_json::from_str(line)
+                    .with_context(|| format!("Failed to parse metadata in {:?}", path))?;
+                let chunk_dir = dir.join(Self::backup_name(meta.first_version, meta.last_version));
+                sealed.insert(
+                    meta.first_version,
+                    Arc::new(Self::load_sealed_chunk(
+                        chunk_dir,
+                        meta.first_version,
+                        meta.last_version,
+                    )?),
+                );
+            }
+        }
+
+        let mut open = None;
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            if name.starts_with(TMP_PREFIX) {
+                // Left over by an interrupted write.
+                Self::remove(&path)?;
+            } else if let Some(range) = name.strip_prefix(BACKUP_PREFIX) {
+                let first_version: Version = range
+                    .split('-')
+                    .next()
+                    .and_then(|first| first.parse().ok())
+                    .ok_or_else(|| format_err!("Unexpected archive entry {:?}", path))?;
+                // The chunk was sealed by renaming its directory, the rest might not have
+                // happened before the process stopped.
+                let stale_progress = path.join(PROGRESS_NAME);
+                if stale_progress.exists() {
+                    fs::remove_file(stale_progress)?;
+                }
+                if !sealed.contains_key(&first_version) {
+                    let manifest: TransactionBackup =
+                        serde_json::from_slice(&fs::read(path.join(MANIFEST_NAME))?)?;
+                    Self::write_metadata(&dir, manifest.first_version, manifest.last_version)?;
+                    sealed.insert(
+                        first_version,
+                        Arc::new(Self::load_sealed_chunk(
+                            path,
+                            manifest.first_version,
+                            manifest.last_version,
+                        )?),
+                    );
+                }
+            } else if name.starts_with(OPEN_CHUNK_PREFIX) {
+                ensure!(
+                    open.is_none(),
+                    "More than one open chunk in the archive: {:?}",
+                    path
+                );
+                open = Self::load_open_chunk(path)?;
+            }
+        }
+        info!(
+            dir = dir.as_path(),
+            num_sealed_chunks = sealed.len(),
+            open_chunk_next_version = ?open
+                .as_ref()
+                .map(|open: &OpenChunk| open.progress.next_version),
+            "Opened ledger archive."
+        );
+
+        Ok(Self {
+            dir,
+            target_chunk_bytes,
+            sealed: RwLock::new(sealed),
+            open: RwLock::new(open),
+            backup_handler,
+            ledger_store,
+            event_store,
+        })
+    }
+
+    /// Exports transactions in [`begin`, `end`) to the archive and makes sure they are on disk.
+    /// Versions that are already archived, which happens if the pruner was interrupted after
+    /// archiving a batch but before committing the pruning, are skipped.
+    pub fn archive(&self, begin: Version, end: Version) -> Result<()> {
+        let begin = self.first_unarchived_version(begin);
+        if begin >= end {
+            return Ok(());
+        }
+        let last = end - 1;
+
+        // A chunk covers consecutive versions, so a gap, which happens if the archive was disabled
+        // for a while, starts a new one.
+        let next_version = self
+            .open
+            .read()
+            .as_ref()
+            .map(|open| open.progress.next_version);
+        if next_version.map_or(false, |next_version| next_version != begin) {
+            self.seal()?;
+        }
+        if self.open.read().is_none() {
+            self.create_open_chunk(begin)?;
+        }
+
+        let (dir, first_version, chunk_bytes, left_siblings) = {
+            let open = self.open.read();
+            let open = open.as_ref().expect("Open chunk must exist.");
+            (
+                open.dir.clone(),
+                open.progress.first_version,
+                open.progress.chunk_bytes,
+                open.progress.proof.0.left_siblings().clone(),
+            )
+        };
+        let (batch, aux, events) = self.read_batch(begin, end, chunk_bytes)?;
+
+        // Readers only look at what the progress covers, so appending doesn't need the lock.
+        Self::append(
+            &dir.join(Self::chunk_name(first_version)),
+            chunk_bytes,
+            &batch,
+        )?;
+        Self::append(
+            &dir.join(Self::aux_name(first_version)),
+            (begin - first_version) * AUX_RECORD_BYTES as u64,
+            &aux.iter().flat_map(AuxRecord::to_bytes).collect::<Vec<_>>(),
+        )?;
+        let (last_proof, ledger_info) = self
+            .backup_handler
+            .get_transaction_range_proof(last, last)?;
+        let progress = OpenChunkProgress {
+            first_version,
+            next_version: end,
+            chunk_bytes: chunk_bytes + batch.len() as u64,
+            proof: (
+                TransactionAccumulatorRangeProof::new(
+                    left_siblings,
+                    last_proof.right_siblings().clone(),
+                ),
+                ledger_info,
+            ),
+        };
+        Self::write_atomically(&dir, PROGRESS_NAME, &bcs::to_bytes(&progress)?)?;
+
+        let mut guard = self.open.write();
+        let open = guard.as_mut().expect("Open chunk must exist.");
+        open.progress = progress;
+        open.hashes.extend(
+            aux.iter()
+                .zip(begin..end)
+                .map(|(aux, version)| (aux.transaction_hash, version)),
+        );
+        open.events.extend(events);
+        if open.progress.chunk_bytes >= self.target_chunk_bytes {
+            self.seal_locked(open)?;
+            *guard = None;
+        }
+        Ok(())
+    }
+
+    /// Reads a transaction from the archive, returns `None` if it's not archived.
+    pub fn get_transaction(&self, version: Version) -> Result<Option<ArchivedTransaction>> {
+        // The open chunk is checked first: sealing adds it to the sealed chunks before closing it,
+        // so a transaction can't be missed in between.
+        if let Some(open) = self.open.read().as_ref() {
+            if open.progress.first_version <= version && version < open.progress.next_version {
+                // Holding the lock so the open chunk isn't sealed, i.e. moved, in the meantime.
+                return Self::read_transaction(&open.dir, open.progress.first_version, version)
+                    .map(Some);
+            }
+        }
+
+        let sealed = match self.sealed.read().range(..=version).next_back() {
+            Some((first_version, chunk)) if chunk.last_version >= version => {
+                (*first_version, Arc::clone(chunk))
+            },
+            _ => return Ok(None),
+        };
+        let (first_version, chunk) = sealed;
+        Self::read_transaction(&chunk.dir, first_version, version).map(Some)
+    }
+
+    /// Finds a transaction in the archive by its hash, returns `None` if it's not archived.
+    pub fn get_transaction_by_hash(&self, hash: HashValue) -> Result<Option<ArchivedTransaction>> {
+        if let Some(open) = self.open.read().as_ref() {
+            if let Some(version) = open.hashes.get(&hash) {
+                return Self::read_transaction(&open.dir, open.progress.first_version, *version)
+                    .map(Some);
+            }
+        }
+
+        let candidates = self
+            .sealed
+            .read()
+            .iter()
+            .rev()
+            .filter(|(_, chunk)| chunk.bloom.may_contain(&hash))
+            .map(|(first_version, chunk)| (*first_version, Arc::clone(chunk)))
+            .collect::<Vec<_>>();
+        for (first_version, chunk) in candidates {
+            let path = chunk
+                .dir
+                .join(Self::hashes_name(first_version, chunk.last_version));
+            if let Some(version) = Self::search_hashes(&path, &hash)? {
+                return Self::read_transaction(&chunk.dir, first_version, version).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Finds the events on `event_key` from `start_seq_num` in the archive, at most `limit`.
+    /// Stops at the first sequence number that's not archived.
+    pub fn get_events(
+        &self,
+        event_key: &EventKey,
+        start_seq_num: u64,
+        limit: u64,
+    ) -> Result<Vec<EventWithVersion>> {
+        // Holding the lock of the open chunk so it's not sealed in the meantime, otherwise the
+        // events in it could be missed between reading the sealed chunks and the open one.
+        let open = self.open.read();
+        let sealed = self
+            .sealed
+            .read()
+            .iter()
+            .map(|(first_version, chunk)| (*first_version, Arc::clone(chunk)))
+            .collect::<Vec<_>>();
+
+        let mut events = Vec::new();
+        let mut next_seq_num = start_seq_num;
+        // The sequence numbers of an event key grow with the versions, so the chunks are read in
+        // order until there are enough events, or one of them is missing.
+        for (first_version, chunk) in sealed {
+            let path = chunk
+                .dir
+                .join(Self::events_name(first_version, chunk.last_version));
+            let remaining = limit - events.len() as u64;
+            for (seq_num, version, index) in
+                Self::search_events(&path, event_key, next_seq_num, remaining)?
+            {
+                if seq_num != next_seq_num {
+                    return Ok(events);
+                }
+                events.push(Self::read_event(&chunk.dir, first_version, version, index)?);
+                next_seq_num += 1;
+            }
+            if events.len() as u64 == limit {
+                return Ok(events);
+            }
+        }
+
+        if let Some(open) = open.as_ref() {
+            for ((key, seq_num), (version, index)) in
+                open.events.range((*event_key, next_seq_num)..)
+            {
+                if key != event_key || seq_num != &next_seq_num || events.len() as u64 == limit {
+                    break;
+                }
+                events.push(Self::read_event(
+                    &open.dir,
+                    open.progress.first_version,
+                    *version,
+                    *index,
+                )?);
+                next_seq_num += 1;
+            }
+        }
+        Ok(events)
+    }
+
+    fn first_unarchived_version(&self, mut version: Version) -> Version {
+        {
+            let sealed = self.sealed.read();
+            while let Some((_, chunk)) = sealed.range(..=version).next_back() {
+                if chunk.last_version < version {
+                    break;
+                }
+                version = chunk.last_version + 1;
+            }
+        }
+        match self.open.read().as_ref() {
+            Some(open)
+                if open.progress.first_version <= version
+                    && version < open.progress.next_version =>
+            {
+                open.progress.next_version
+            },
+            _ => version,
+        }
+    }
+
+    /// Reads transactions in [`begin`, `end`) from the DB, returns them serialized as chunk
+    /// records, to be appended to a chunk of `chunk_bytes`, along with their aux records and
+    /// events.
+    fn read_batch(
+        &self,
+        begin: Version,
+        end: Version,
+        chunk_bytes: u64,
+    ) -> Result<(Vec<u8>, Vec<AuxRecord>, EventIndex)> {
+        let num_transactions = (end - begin) as usize;
+        let mut batch = Vec::new();
+        let mut aux = Vec::with_capacity(num_transactions);
+        let mut events = EventIndex::new();
+        let mut block_timestamp = None;
+        for (version, record) in (begin..end).zip(
+            self.backup_handler
+                .get_transaction_iter(begin, num_transactions)?,
+        ) {
+            let record: TransactionRecord = record?;
+            // Same as `EventStore::get_block_metadata()`: the block a transaction belongs to is
+            // the latest one started at or before it.
+            if let Some(event) = record.2.iter().find(|e| *e.key() == new_block_event_key()) {
+                block_timestamp =
+                    Some(NewBlockEvent::try_from_bytes(event.event_data())?.proposed_time());
+            }
+            let timestamp = match block_timestamp {
+                Some(timestamp) => timestamp,
+                None => *block_timestamp.insert(self.block_timestamp_before_batch(version)?),
+            };
+
+            events.extend(Self::index_events(version, &record.2));
+            let record_bytes = bcs::to_bytes(&record)?;
+            aux.push(AuxRecord {
+                transaction_hash: record.0.hash(),
+                accumulator_root_hash: self.ledger_store.get_root_hash(version)?,
+                block_timestamp_usecs: timestamp,
+                offset: chunk_bytes + batch.len() as u64,
+            });
+            batch.extend((record_bytes.len() as u32).to_be_bytes());
+            batch.extend(record_bytes);
+        }
+        ensure!(
+            aux.len() == num_transactions,
+            "Expecting {} transactions to archive, got {}.",
+            num_transactions,
+            aux.len(),
+        );
+        Ok((batch, aux, events))
+    }
+
+    fn index_events(
+        version: Version,
+        events: &[ContractEvent],
+    ) -> impl Iterator<Item = ((EventKey, u64), (Version, u64))> + '_ {
+        events.iter().enumerate().map(move |(index, event)| {
+            (
+                (*event.key(), event.sequence_number()),
+                (version, index as u64),
+            )
+        })
+    }
+
+    /// Finds the block timestamp of the first transaction in a batch that doesn't start a block.
+    fn block_timestamp_before_batch(&self, version: Version) -> Result<u64> {
+        Ok(
+            match self
+                .event_store
+                .lookup_event_before_or_at_version(&new_block_event_key(), version)?
+            {
+                // The block started in a previous batch, whose events are pruned already if it's
+                // been archived.
+                Some(_) => match version
+                    .checked_sub(1)
+                    .map(|prev| self.get_transaction(prev))
+                    .transpose()?
+                    .flatten()
+                {
+                    Some(prev) => prev.block_timestamp_usecs,
+                    None => self
+                        .event_store
+                        .get_block_metadata(version)?
+                        .1
+                        .proposed_time(),
+                },
+                // No block started yet, i.e. the genesis transaction.
+                None => 0,
+            },
+        )
+    }
+
+    fn create_open_chunk(&self, first_version: Version) -> Result<()> {
+        let dir = self
+            .dir
+            .join(format!("{}{}", OPEN_CHUNK_PREFIX, first_version));
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        // The left siblings of the range proof only depend on the first version, and are pruned
+        // with it, so they are recorded right away.
+        let progress = OpenChunkProgress {
+            first_version,
+            next_version: first_version,
+            chunk_bytes: 0,
+            proof: self
+                .backup_handler
+                .get_transaction_range_proof(first_version, first_version)?,
+        };
+        File::create(dir.join(Self::chunk_name(first_version)))?;
+        File::create(dir.join(Self::aux_name(first_version)))?;
+        Self::write_atomically(&dir, PROGRESS_NAME, &bcs::to_bytes(&progress)?)?;
+        Self::sync_dir(&self.dir)?;
+
+        *self.open.write() = Some(OpenChunk {
+            dir,
+            progress,
+            hashes: HashMap::new(),
+            events: EventIndex::new(),
+        });
+        Ok(())
+    }
+
+    fn seal(&self) -> Result<()> {
+        let mut open = self.open.write();
+        if let Some(open_chunk) = open.as_ref() {
+            self.seal_locked(open_chunk)?;
+            *open = None;
+        }
+        Ok(())
+    }
+
+    /// Turns the open chunk into a backup and adds it to the sealed chunks. The caller holds the
+    /// write lock of the open chunk and clears it afterwards.
+    fn seal_locked(&self, open: &OpenChunk) -> Result<()> {
+        let first_version = open.progress.first_version;
+        if open.progress.next_version == first_version {
+            fs::remove_dir_all(&open.dir)?;
+            return Ok(());
+        }
+        let last_version = open.progress.next_version - 1;
+        let backup_name = Self::backup_name(first_version, last_version);
+        let chunk_name = Self::chunk_name(first_version);
+        let proof_name = format!("{}-{}.proof", first_version, last_version);
+
+        Self::write_atomically(
+            &open.dir,
+            &proof_name,
+            &bcs::to_bytes(&open.progress.proof)?,
+        )?;
+        let mut hashes = open.hashes.iter().collect::<Vec<_>>();
+        hashes.sort();
+        let hashes_bytes = hashes
+            .iter()
+            .flat_map(|(hash, version)| {
+                hash.to_vec()
+                    .into_iter()
+                    .chain(version.to_be_bytes())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let hashes_name = Self::hashes_name(first_version, last_version);
+        Self::write_atomically(&open.dir, &hashes_name, &hashes_bytes)?;
+        let bloom = BloomFilter::new(open.hashes.keys());
+        let events_bytes = open
+            .events
+            .iter()
+            .flat_map(|((key, seq_num), (version, index))| {
+                key.to_bytes()
+                    .into_iter()
+                    .chain(seq_num.to_be_bytes())
+                    .chain(version.to_be_bytes())
+                    .chain(index.to_be_bytes())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        Self::write_atomically(
+            &open.dir,
+            &Self::events_name(first_version, last_version),
+            &events_bytes,
+        )?;
+        Self::write_atomically(
+            &open.dir,
+            &Self::bloom_name(first_version, last_version),
+            &bloom.bits,
+        )?;
+        let manifest = TransactionBackup {
+            first_version,
+            last_version,
+            chunks: vec![TransactionChunk {
+                first_version,
+                last_version,
+                transactions: format!("{}/{}", backup_name, chunk_name),
+                proof: format!("{}/{}", backup_name, proof_name),
+            }],
+        };
+        Self::write_atomically(&open.dir, MANIFEST_NAME, &serde_json::to_vec(&manifest)?)?;
+
+        // Once renamed, the chunk is sealed, the rest is redone by `open()` if interrupted.
+        let backup_dir = self.dir.join(&backup_name);
+        if backup_dir.exists() {
+            fs::remove_dir_all(&backup_dir)?;
+        }
+        fs::rename(&open.dir, &backup_dir)?;
+        Self::sync_dir(&self.dir)?;
+        fs::remove_file(backup_dir.join(PROGRESS_NAME))?;
+        Self::write_metadata(&self.dir, first_version, last_version)?;
+
+        self.sealed.write().insert(
+            first_version,
+            Arc::new(SealedChunk {
+                dir: backup_dir,
+                last_version,
+                bloom,
+            }),
+        );
+        info!(
+            first_version = first_version,
+            last_version = last_version,
+            "Sealed ledger archive chunk."
+        );
+        Ok(())
+    }
+
+    fn load_sealed_chunk(
+        dir: PathBuf,
+        first_version: Version,
+        last_version: Version,
+    ) -> Result<SealedChunk> {
+        let bloom_path = dir.join(Self::bloom_name(first_version, last_version));
+        let bits =
+            fs::read(&bloom_path).with_context(|| format!("Failed to read {:?}", bloom_path))?;
+        ensure!(
+            !bits.is_empty(),
+            "Archive corrupted: {:?} empty.",
+            bloom_path
+        );
+        Ok(SealedChunk {
+            dir,
+            last_version,
+            bloom: BloomFilter { bits },
+        })
+    }
+
+    /// Loads the open chunk, dropping anything written after its recorded progress.
+    fn load_open_chunk(dir: PathBuf) -> Result<Option<OpenChunk>> {
+        let progress: OpenChunkProgress = match fs::read(dir.join(PROGRESS_NAME)) {
+            Ok(bytes) => bcs::from_bytes(&bytes)?,
+            Err(err) => {
+                // Interrupted while creating the open chunk.
+                warn!(dir = dir.as_path(), error = ?err, "Removing incomplete open chunk.");
+                fs::remove_dir_all(&dir)?;
+                return Ok(None);
+            },
+        };
+        let first_version = progress.first_version;
+        let num_transactions = progress.next_version - first_version;
+
+        let chunk = OpenOptions::new()
+            .write(true)
+            .open(dir.join(Self::chunk_name(first_version)))?;
+        chunk.set_len(progress.chunk_bytes)?;
+        chunk.sync_all()?;
+        let aux_path = dir.join(Self::aux_name(first_version));
+        let aux = OpenOptions::new().write(true).open(&aux_path)?;
+        aux.set_len(num_transactions * AUX_RECORD_BYTES as u64)?;
+        aux.sync_all()?;
+
+        let hashes = fs::read(&aux_path)?
+            .chunks(AUX_RECORD_BYTES)
+            .zip(first_version..)
+            .map(|(bytes, version)| {
+                AuxRecord::from_bytes(bytes).map(|aux| (aux.transaction_hash, version))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+        let mut events = EventIndex::new();
+        for version in first_version..progress.next_version {
+            let transaction = Self::read_transaction(&dir, first_version, version)?;
+            events.extend(Self::index_events(version, &transaction.events));
+        }
+        Ok(Some(OpenChunk {
+            dir,
+            progress,
+            hashes,
+            events,
+        }))
+    }
+
+    fn read_transaction(
+        dir: &Path,
+        first_version: Version,
+        version: Version,
+    ) -> Result<ArchivedTransaction> {
+        let mut aux_bytes = [0u8; AUX_RECORD_BYTES];
+        let mut aux_file = File::open(dir.join(Self::aux_name(first_version)))?;
+        aux_file.seek(SeekFrom::Start(
+            (version - first_version) * AUX_RECORD_BYTES as u64,
+        ))?;
+        aux_file
+            .read_exact(&mut aux_bytes)
+            .with_context(|| format!("Archive corrupted: {} not in aux file.", version))?;
+        let aux = AuxRecord::from_bytes(&aux_bytes)?;
+
+        let mut chunk_file = File::open(dir.join(Self::chunk_name(first_version)))?;
+        chunk_file.seek(SeekFrom::Start(aux.offset))?;
+        let mut len_bytes = [0u8; 4];
+        chunk_file.read_exact(&mut len_bytes)?;
+        let mut record_bytes = vec![0u8; u32::from_be_bytes(len_bytes) as usize];
+        chunk_file
+            .read_exact(&mut record_bytes)
+            .with_context(|| format!("Archive corrupted: {} not in chunk.", version))?;
+        let (transaction, info, events, write_set): TransactionRecord =
+            bcs::from_bytes(&record_bytes)?;
+
+        Ok(ArchivedTransaction {
+            version,
+            transaction,
+            info,
+            events,
+            write_set,
+            accumulator_root_hash: aux.accumulator_root_hash,
+            block_timestamp_usecs: aux.block_timestamp_usecs,
+        })
+    }
+
+    /// Binary searches a sorted hashes file.
+    fn search_hashes(path: &Path, hash: &HashValue) -> Result<Option<Version>> {
+        let mut file = File::open(path)?;
+        let num_records = file.metadata()?.len() / HASH_RECORD_BYTES as u64;
+        let mut record = [0u8; HASH_RECORD_BYTES];
+        let (mut low, mut high) = (0, num_records);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            file.seek(SeekFrom::Start(mid * HASH_RECORD_BYTES as u64))?;
+            file.read_exact(&mut record)?;
+            match record[..HashValue::LENGTH].cmp(hash.as_ref()) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => {
+                    return Ok(Some(u64::from_be_bytes(
+                        record[HashValue::LENGTH..].try_into()?,
+                    )))
+                },
+            }
+        }
+        Ok(None)
+    }
+
+    /// Binary searches a sorted events file for the events on `event_key` from `start_seq_num`,
+    /// returns at most `limit` of their sequence numbers, versions and indices.
+    fn search_events(
+        path: &Path,
+        event_key: &EventKey,
+        start_seq_num: u64,
+        limit: u64,
+    ) -> Result<Vec<(u64, Version, u64)>> {
+        let mut file = File::open(path)?;
+        let num_records = file.metadata()?.len() / EVENT_RECORD_BYTES as u64;
+        let mut record = [0u8; EVENT_RECORD_BYTES];
+        let target = (*event_key, start_seq_num);
+        let (mut low, mut high) = (0, num_records);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            file.seek(SeekFrom::Start(mid * EVENT_RECORD_BYTES as u64))?;
+            file.read_exact(&mut record)?;
+            let (key, seq_num, _, _) = Self::parse_event_record(&record)?;
+            if (key, seq_num) < target {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        let mut events = Vec::new();
+        file.seek(SeekFrom::Start(low * EVENT_RECORD_BYTES as u64))?;
+        for _ in low..num_records.min(low + limit) {
+            file.read_exact(&mut record)?;
+            let (key, seq_num, version, index) = Self::parse_event_record(&record)?;
+            if &key != event_key {
+                break;
+            }
+            events.push((seq_num, version, index));
+        }
+        Ok(events)
+    }
+
+    fn parse_event_record(record: &[u8]) -> Result<(EventKey, u64, Version, u64)> {
+        let (key, rest) = record.split_at(EventKey::LENGTH);
+        let key = EventKey::from_bytes(key)
+            .map_err(|_| format_err!("Archive corrupted: bad event key in events file."))?;
+        Ok((
+            key,
+            u64::from_be_bytes(rest[..8].try_into()?),
+            u64::from_be_bytes(rest[8..16].try_into()?),
+            u64::from_be_bytes(rest[16..].try_into()?),
+        ))
+    }
+
+    fn read_event(
+        dir: &Path,
+        first_version: Version,
+        version: Version,
+        index: u64,
+    ) -> Result<EventWithVersion> {
+        let mut transaction = Self::read_transaction(dir, first_version, version)?;
+        ensure!(
+            (index as usize) < transaction.events.len(),
+            "Archive corrupted: no event {} at version {}.",
+            index,
+            version
+        );
+        Ok(EventWithVersion::new(
+            version,
+            transaction.events.swap_remove(index as usize),
+        ))
+    }
+
+    fn write_metadata(dir: &Path, first_version: Version, last_version: Version) -> Result<()> {
+        let backup_name = Self::backup_name(first_version, last_version);
+        let meta = TransactionBackupMeta {
+            first_version,
+            last_version,
+            manifest: format!("{}/{}", backup_name, MANIFEST_NAME),
+        };
+        let mut line = serde_json::to_string(&Metadata::TransactionBackup(meta))?;
+        line.push('\n');
+        Self::write_atomically(
+            &dir.join(METADATA_DIR),
+            &format!("{}.meta", backup_name),
+            line.as_bytes(),
+        )
+    }
+
+    /// Appends to the first `len` bytes of a file, dropping whatever a failed attempt left after
+    /// them, and makes sure it's on disk.
+    fn append(path: &Path, len: u64, bytes: &[u8]) -> Result<()> {
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        file.set_len(len)?;
+        file.seek(SeekFrom::Start(len))?;
+        file.write_all(bytes)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Replaces a file in `dir` with `bytes`, so it's either the old or the new content on disk
+    /// afterwards.
+    fn write_atomically(dir: &Path, name: &str, bytes: &[u8]) -> Result<()> {
+        let tmp_path = dir.join(format!("{}{}", TMP_PREFIX, name));
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, dir.join(name))?;
+        Self::sync_dir(dir)
+    }
+
+    fn sync_dir(dir: &Path) -> Result<()> {
+        File::open(dir)?.sync_all()?;
+        Ok(())
+    }
+
+    fn remove(path: &Path) -> Result<()> {
+        if path.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn backup_name(first_version: Version, last_version: Version) -> String {
+        format!("{}{}-{}", BACKUP_PREFIX, first_version, last_version)
+    }
+
+    fn chunk_name(first_version: Version) -> String {
+        format!("{}-.chunk", first_version)
+    }
+
+    fn aux_name(first_version: Version) -> String {
+        format!("{}-.aux", first_version)
+    }
+
+    fn hashes_name(first_version: Version, last_version: Version) -> String {
+        format!("{}-{}.hashes", first_version, last_version)
+    }
+
+    fn bloom_name(first_version: Version, last_version: Version) -> String {
+        format!("{}-{}.bloom", first_version, last_version)
+    }
+
+    fn events_name(first_version: Version, last_version: Version) -> String {
+        format!("{}-{}.events", first_version, last_version)
+    }
+}
@@ -8,6 +8,7 @@ use crate::{
         db_pruner::DBPruner,
         db_sub_pruner::DBSubPruner,
         event_store::event_store_pruner::EventStorePruner,
+        ledger_store::ledger_archive::LedgerArchive,
         state_store::state_value_pruner::StateValuePruner,
         transaction_store::{
             transaction_store_pruner::TransactionStorePruner, write_set_pruner::WriteSetPruner,
@@ -37,6 +38,8 @@ pub(crate) struct LedgerPruner {
     state_value_pruner: Arc<dyn DBSubPruner + Send + Sync>,
     event_store_pruner: Arc<dyn DBSubPruner + Send + Sync>,
     write_set_pruner: Arc<dyn DBSubPruner + Send + Sync>,
+    /// If set, everything is exported here before being pruned.
+    archive: Option<Arc<LedgerArchive>>,
 }
 
 impl DBPruner for LedgerPruner {
@@ -49,9 +52,16 @@ impl DBPruner for LedgerPruner {
             return Ok(self.min_readable_version());
         }
 
+        // Current target version might be less than the target version to ensure we don't prune
+        // more than max_version in one go.
+        let current_target_version = self.get_current_batch_target(max_versions as Version);
+        if let Some(archive) = &self.archive {
+            archive.archive(self.min_readable_version(), current_target_version)?;
+        }
+
         // Collect the schema batch writes
         let mut db_batch = SchemaBatch::new();
-        let current_target_version = self.prune_inner(max_versions, &mut db_batch)?;
+        self.prune_inner(current_target_version, &mut db_batch)?;
         db_batch.put::<DbMetadataSchema>(
             &DbMetadataKey::LedgerPrunerProgress,
             &DbMetadataValue::Version(current_target_version),
@@ -130,6 +140,7 @@ impl LedgerPruner {
         transaction_store: Arc<TransactionStore>,
         event_store: Arc<EventStore>,
         state_store: Arc<StateStore>,
+        archive: Option<Arc<LedgerArchive>>,
     ) -> Self {
         let pruner = LedgerPruner {
             db,
@@ -141,6 +152,7 @@ impl LedgerPruner {
             state_value_pruner: Arc::new(StateValuePruner::new(state_store)),
             event_store_pruner: Arc::new(EventStorePruner::new(event_store)),
             write_set_pruner: Arc::new(WriteSetPruner::new(transaction_store)),
+            archive,
         };
         pruner.initialize();
         pruner
@@ -153,26 +165,24 @@ impl LedgerPruner {
         db_batch: &mut SchemaBatch,
     ) -> anyhow::Result<()> {
         let target_version = 1; // The genesis version is 0. Delete [0,1) (exclusive)
-        let max_version = 1; // We should only be pruning a single version
 
-        let ledger_pruner = pruner_utils::create_ledger_pruner(ledger_db, state_store);
-        ledger_pruner.set_target_version(target_version);
-        ledger_pruner.prune_inner(max_version, db_batch)?;
+        let ledger_pruner = pruner_utils::create_ledger_pruner(ledger_db, state_store, None, 0);
+        ledger_pruner.prune_inner(target_version, db_batch)?;
 
         Ok(())
     }
 
+    pub(crate) fn archive(&self) -> Option<&Arc<LedgerArchive>> {
+        self.archive.as_ref()
+    }
+
     fn prune_inner(
         &self,
-        max_versions: usize,
+        current_target_version: Version,
         db_batch: &mut SchemaBatch,
-    ) -> anyhow::Result<Version> {
+    ) -> anyhow::Result<()> {
         let min_readable_version = self.min_readable_version();
 
-        // Current target version might be less than the target version to ensure we don't prune
-        // more than max_version in one go.
-        let current_target_version = self.get_current_batch_target(max_versions as Version);
-
         self.transaction_store_pruner.prune(
             db_batch,
             min_readable_version,
@@ -185,6 +195,6 @@ impl LedgerPruner {
         self.event_store_pruner
             .prune(db_batch, min_readable_version, current_target_version)?;
 
-        Ok(current_target_version)
+        Ok(())
     }
 }
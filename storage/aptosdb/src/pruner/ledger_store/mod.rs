@@ -1,4 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+pub(crate) mod ledger_archive;
 pub(crate) mod ledger_store_pruner;
+#[cfg(test)]
+mod test;
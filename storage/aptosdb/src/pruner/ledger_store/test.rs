@@ -0,0 +1,212 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    test_helper::{arb_blocks_to_commit, update_in_memory_state},
+    AptosDB, LedgerPrunerManager, PrunerManager,
+};
+use anyhow::Result;
+use aptos_config::config::LedgerPrunerConfig;
+use aptos_crypto::hash::CryptoHash;
+use aptos_storage_interface::{DbReader, DbWriter, Error as StorageError, Order};
+use aptos_temppath::TempPath;
+use aptos_types::{
+    contract_event::EventWithVersion,
+    event::EventKey,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::TransactionAccumulatorRangeProof,
+    transaction::{TransactionToCommit, Version},
+};
+use proptest::prelude::*;
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Arc,
+};
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_ledger_archive(input in arb_blocks_to_commit()) {
+        verify_ledger_archive(input);
+    }
+}
+
+fn ledger_pruner_with_archive(db: &AptosDB, archive_dir: &Path) -> LedgerPrunerManager {
+    LedgerPrunerManager::new(
+        Arc::clone(&db.ledger_db),
+        Arc::clone(&db.state_store),
+        LedgerPrunerConfig {
+            enable: true,
+            prune_window: 0,
+            batch_size: 3,
+            user_pruning_window_offset: 0,
+            archive_dir: Some(archive_dir.to_path_buf()),
+            // Small enough to seal a few chunks, and likely leave one open.
+            archive_chunk_bytes: 4096,
+        },
+    )
+}
+
+fn verify_ledger_archive(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let archive_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    let mut in_memory_state = db
+        .state_store
+        .buffered_state()
+        .lock()
+        .current_state()
+        .clone();
+    let mut cur_ver: Version = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        update_in_memory_state(&mut in_memory_state, txns_to_commit.as_slice());
+        db.save_transactions(
+            txns_to_commit,
+            cur_ver,
+            cur_ver.checked_sub(1),
+            Some(ledger_info_with_sigs),
+            true, /* sync_commit */
+            in_memory_state.clone(),
+        )
+        .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+
+    let expected = db
+        .get_backup_handler()
+        .get_transaction_iter(0, cur_ver as usize)
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    let expected_root_hashes = (0..cur_ver)
+        .map(|version| db.get_accumulator_root_hash(version))
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+
+    let pruner = ledger_pruner_with_archive(&db, archive_dir.path());
+    pruner
+        .wake_and_wait_pruner(cur_ver - 1 /* latest_version */)
+        .unwrap();
+    let min_readable_version = pruner.get_min_readable_version();
+
+    // Sealed chunks span batches, and their range proofs are valid even though the accumulator
+    // was pruned while they were open.
+    let mut num_sealed_chunks = 0;
+    for entry in fs::read_dir(archive_dir.path()).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap().to_string();
+        let range = match name.strip_prefix("transaction_") {
+            Some(range) => range,
+            None => continue,
+        };
+        num_sealed_chunks += 1;
+        let (first, last) = range.split_once('-').unwrap();
+        let (first, last): (Version, Version) = (first.parse().unwrap(), last.parse().unwrap());
+        let (proof, ledger_info): (TransactionAccumulatorRangeProof, LedgerInfoWithSignatures) =
+            bcs::from_bytes(&fs::read(path.join(format!("{}-{}.proof", first, last))).unwrap())
+                .unwrap();
+        let info_hashes = expected[first as usize..=last as usize]
+            .iter()
+            .map(|(_, info, ..)| info.hash())
+            .collect::<Vec<_>>();
+        proof
+            .verify(
+                ledger_info.ledger_info().transaction_accumulator_hash(),
+                Some(first),
+                &info_hashes,
+            )
+            .unwrap();
+    }
+    assert!(num_sealed_chunks <= (min_readable_version + 2) / 3);
+
+    // Reopen the archive to make sure it's found on disk as well, with garbage from an
+    // interrupted batch in the open chunk, if any.
+    drop(pruner);
+    for entry in fs::read_dir(archive_dir.path()).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with(".open_")
+        {
+            for file in fs::read_dir(&path).unwrap() {
+                let file = file.unwrap().path();
+                if file
+                    .extension()
+                    .map_or(false, |ext| ext == "chunk" || ext == "aux")
+                {
+                    OpenOptions::new()
+                        .append(true)
+                        .open(file)
+                        .unwrap()
+                        .write_all(&[0xFF; 100])
+                        .unwrap();
+                }
+            }
+        }
+    }
+    let pruner = ledger_pruner_with_archive(&db, archive_dir.path());
+    let archive = pruner.archive().unwrap();
+    for version in 0..cur_ver {
+        let archived = archive.get_transaction(version).unwrap();
+        let (transaction, ..) = &expected[version as usize];
+        let archived_by_hash = archive.get_transaction_by_hash(transaction.hash()).unwrap();
+        if version >= min_readable_version {
+            assert!(archived.is_none());
+            continue;
+        }
+        assert!(db.transaction_store.get_transaction(version).is_err());
+        // Identical transactions, e.g. state checkpoints, are found at any of their versions.
+        assert_eq!(
+            &archived_by_hash.unwrap().transaction,
+            &archived.as_ref().unwrap().transaction
+        );
+
+        let archived = archived.unwrap();
+        let (transaction, info, events, write_set) = &expected[version as usize];
+        assert_eq!(archived.version, version);
+        assert_eq!(&archived.transaction, transaction);
+        assert_eq!(&archived.info, info);
+        assert_eq!(&archived.events, events);
+        assert_eq!(&archived.write_set, write_set);
+        assert_eq!(
+            archived.accumulator_root_hash,
+            expected_root_hashes[version as usize]
+        );
+    }
+
+    // Events on the pruned versions are found by key in the archive, in sequence number order,
+    // while the DB reports them pruned.
+    let mut expected_events: BTreeMap<EventKey, Vec<EventWithVersion>> = BTreeMap::new();
+    for (version, (_, _, events, _)) in expected[..min_readable_version as usize].iter().enumerate()
+    {
+        for event in events {
+            expected_events
+                .entry(*event.key())
+                .or_default()
+                .push(EventWithVersion::new(version as Version, event.clone()));
+        }
+    }
+    for (key, events) in expected_events {
+        let limit = events.len() as u64;
+        assert_eq!(archive.get_events(&key, 0, limit).unwrap(), events);
+        assert_eq!(
+            archive.get_events(&key, limit - 1, limit).unwrap(),
+            events[events.len() - 1..]
+        );
+        // The DB can only tell they are pruned if later events on the key are left.
+        match db.get_events(&key, 0, Order::Ascending, limit, cur_ver - 1) {
+            Ok(events) => assert!(events.is_empty()),
+            Err(err) => assert!(matches!(
+                err.downcast_ref::<StorageError>(),
+                Some(StorageError::Pruned { .. })
+            )),
+        }
+    }
+}
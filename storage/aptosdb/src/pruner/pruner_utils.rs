@@ -4,15 +4,16 @@
 //! This module provides common utilities for the DB pruner.
 
 use crate::{
+    backup::backup_handler::BackupHandler,
     pruner::{
-        ledger_store::ledger_store_pruner::LedgerPruner,
+        ledger_store::{ledger_archive::LedgerArchive, ledger_store_pruner::LedgerPruner},
         state_store::{generics::StaleNodeIndexSchemaTrait, StateMerklePruner},
     },
-    EventStore, StateStore, TransactionStore,
+    EventStore, LedgerStore, StateStore, TransactionStore,
 };
 use aptos_jellyfish_merkle::StaleNodeIndex;
 use aptos_schemadb::{schema::KeyCodec, DB};
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 /// A utility function to instantiate the state pruner
 pub fn create_state_pruner<S: StaleNodeIndexSchemaTrait>(
@@ -24,15 +25,41 @@ where
     Arc::new(StateMerklePruner::<S>::new(Arc::clone(&state_merkle_db)))
 }
 
-/// A utility function to instantiate the ledger pruner
+/// A utility function to instantiate the ledger pruner, which archives what it prunes to
+/// `archive_dir` if specified, in chunks of `archive_chunk_bytes`.
 pub(crate) fn create_ledger_pruner(
     ledger_db: Arc<DB>,
     state_store: Arc<StateStore>,
+    archive_dir: Option<&Path>,
+    archive_chunk_bytes: u64,
 ) -> Arc<LedgerPruner> {
+    let transaction_store = Arc::new(TransactionStore::new(Arc::clone(&ledger_db)));
+    let event_store = Arc::new(EventStore::new(Arc::clone(&ledger_db)));
+    let archive = archive_dir.map(|dir| {
+        let ledger_store = Arc::new(LedgerStore::new(Arc::clone(&ledger_db)));
+        let backup_handler = BackupHandler::new(
+            Arc::clone(&ledger_store),
+            Arc::clone(&transaction_store),
+            Arc::clone(&state_store),
+            Arc::clone(&event_store),
+        );
+        Arc::new(
+            LedgerArchive::open(
+                dir,
+                archive_chunk_bytes,
+                backup_handler,
+                ledger_store,
+                Arc::clone(&event_store),
+            )
+            .expect("Opening ledger archive should succeed."),
+        )
+    });
+
     Arc::new(LedgerPruner::new(
-        Arc::clone(&ledger_db),
-        Arc::new(TransactionStore::new(Arc::clone(&ledger_db))),
-        Arc::new(EventStore::new(Arc::clone(&ledger_db))),
+        ledger_db,
+        transaction_store,
+        event_store,
         state_store,
+        archive,
     ))
 }
@@ -385,6 +385,8 @@ fn verify_state_value_pruner(inputs: Vec<Vec<(StateKey, Option<StateValue>)>>) {
             prune_window: 0,
             batch_size: 1,
             user_pruning_window_offset: 0,
+            archive_dir: None,
+            archive_chunk_bytes: 0,
         },
     );
     for batch in inputs {
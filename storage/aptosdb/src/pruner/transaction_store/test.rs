@@ -54,6 +54,8 @@ fn verify_write_set_pruner(write_sets: Vec<WriteSet>) {
             prune_window: 0,
             batch_size: 1,
             user_pruning_window_offset: 0,
+            archive_dir: None,
+            archive_chunk_bytes: 0,
         },
     );
 
@@ -115,6 +117,8 @@ fn verify_txn_store_pruner(
                 prune_window: 0,
                 batch_size: 1,
                 user_pruning_window_offset: 0,
+                archive_dir: None,
+                archive_chunk_bytes: 0,
             },
         );
         pruner
@@ -48,6 +48,7 @@ pub enum DbMetadataKey {
     StateMerklePrunerProgress,
     EpochEndingStateMerklePrunerProgress,
     StateSnapshotRestoreProgress(Version),
+    TruncationTarget,
 }
 
 define_schema!(
@@ -26,7 +26,7 @@ use aptos_executor_types::in_memory_state_calculator::InMemoryStateCalculator;
 use aptos_infallible::Mutex;
 use aptos_jellyfish_merkle::iterator::JellyfishMerkleIterator;
 use aptos_logger::info;
-use aptos_schemadb::{ReadOptions, SchemaBatch, DB};
+use aptos_schemadb::{iterator::SchemaIterator, ReadOptions, SchemaBatch, DB};
 use aptos_state_view::StateViewId;
 use aptos_storage_interface::{
     cached_state_view::CachedStateView, state_delta::StateDelta,
@@ -61,9 +61,10 @@ mod state_store_test;
 type StateValueBatch = crate::state_restore::StateValueBatch<StateKey, Option<StateValue>>;
 
 // We assume TARGET_SNAPSHOT_INTERVAL_IN_VERSION > block size.
-const MAX_WRITE_SETS_AFTER_SNAPSHOT: LeafCount = buffered_state::TARGET_SNAPSHOT_INTERVAL_IN_VERSION
-    * (buffered_state::ASYNC_COMMIT_CHANNEL_BUFFER_SIZE + 2 + 1/*  Rendezvous channel */)
-    * 2;
+pub(crate) const MAX_WRITE_SETS_AFTER_SNAPSHOT: LeafCount =
+    buffered_state::TARGET_SNAPSHOT_INTERVAL_IN_VERSION
+        * (buffered_state::ASYNC_COMMIT_CHANNEL_BUFFER_SIZE + 2 + 1/*  Rendezvous channel */)
+        * 2;
 
 static IO_POOL: Lazy<rayon::ThreadPool> = Lazy::new(|| {
     rayon::ThreadPoolBuilder::new()
@@ -126,6 +127,22 @@ impl DbReader for StateDb {
             .map(|(_, value)| value))
     }
 
+    /// Gets the latest state values of the given keys up to the given version, reusing a single DB
+    /// iterator for all of them.
+    fn get_state_values_by_version(
+        &self,
+        state_keys: &[StateKey],
+        version: Version,
+    ) -> Result<Vec<Option<StateValue>>> {
+        let mut iter = self.state_value_iter()?;
+        state_keys
+            .iter()
+            .map(|state_key| {
+                Ok(Self::seek_state_value(&mut iter, state_key, version)?.map(|(_, value)| value))
+            })
+            .collect()
+    }
+
     /// Returns the proof of the given state key and version.
     fn get_state_proof_by_version_ext(
         &self,
@@ -156,6 +173,49 @@ impl DbReader for StateDb {
         ))
     }
 
+    /// Get the state values with proofs given the state keys and version. The proofs are read key
+    /// by key from the state merkle tree, after which the values they point to are read in one
+    /// batch.
+    fn get_state_values_with_proof_by_version_ext(
+        &self,
+        state_keys: &[StateKey],
+        version: Version,
+    ) -> Result<Vec<(Option<StateValue>, SparseMerkleProofExt)>> {
+        let leaves_and_proofs = state_keys
+            .iter()
+            .map(|state_key| self.state_merkle_db.get_with_proof_ext(state_key, version))
+            .collect::<Result<Vec<_>>>()?;
+        // Leaves record the exact versions the values are written at, so point lookups suffice.
+        let value_keys = leaves_and_proofs
+            .iter()
+            .filter_map(|(leaf_data, _)| leaf_data.as_ref().map(|(_, key)| key.clone()))
+            .collect::<Vec<_>>();
+        let mut values = value_keys
+            .iter()
+            .zip(self.ledger_db.multi_get::<StateValueSchema>(&value_keys)?);
+
+        leaves_and_proofs
+            .into_iter()
+            .map(|(leaf_data, proof)| {
+                let value = match leaf_data {
+                    Some(_) => {
+                        let ((key, version), value) =
+                            values.next().expect("One value is read for each leaf.");
+                        Some(value.flatten().ok_or_else(|| {
+                            format_err!(
+                                "State Value is missing for key {:?} by version {}",
+                                key,
+                                version
+                            )
+                        })?)
+                    },
+                    None => None,
+                };
+                Ok((value, proof))
+            })
+            .collect()
+    }
+
     fn get_state_storage_usage(&self, version: Option<Version>) -> Result<StateStorageUsage> {
         version.map_or(Ok(StateStorageUsage::zero()), |version| {
             Ok(self
@@ -175,14 +235,27 @@ impl StateDb {
         state_key: &StateKey,
         version: Version,
     ) -> Result<Option<(Version, StateValue)>> {
+        Self::seek_state_value(&mut self.state_value_iter()?, state_key, version)
+    }
+
+    fn state_value_iter(&self) -> Result<SchemaIterator<StateValueSchema>> {
         let mut read_opts = ReadOptions::default();
         // We want `None` if the state_key changes in iteration.
         read_opts.set_prefix_same_as_start(true);
-        let mut iter = self.ledger_db.iter::<StateValueSchema>(read_opts)?;
+        self.ledger_db.iter::<StateValueSchema>(read_opts)
+    }
+
+    fn seek_state_value(
+        iter: &mut SchemaIterator<StateValueSchema>,
+        state_key: &StateKey,
+        version: Version,
+    ) -> Result<Option<(Version, StateValue)>> {
         iter.seek(&(state_key.clone(), version))?;
+        // Also check the key explicitly, in case the DB backend doesn't honor the prefix.
         Ok(iter
             .next()
             .transpose()?
+            .filter(|((key, _), _)| key == state_key)
             .and_then(|((_, version), value_opt)| value_opt.map(|value| (version, value))))
     }
 
@@ -223,6 +296,15 @@ impl DbReader for StateStore {
         self.deref().get_state_value_by_version(state_key, version)
     }
 
+    fn get_state_values_by_version(
+        &self,
+        state_keys: &[StateKey],
+        version: Version,
+    ) -> Result<Vec<Option<StateValue>>> {
+        self.deref()
+            .get_state_values_by_version(state_keys, version)
+    }
+
     /// Returns the proof of the given state key and version.
     fn get_state_proof_by_version_ext(
         &self,
@@ -242,6 +324,15 @@ impl DbReader for StateStore {
         self.deref()
             .get_state_value_with_proof_by_version_ext(state_key, version)
     }
+
+    fn get_state_values_with_proof_by_version_ext(
+        &self,
+        state_keys: &[StateKey],
+        version: Version,
+    ) -> Result<Vec<(Option<StateValue>, SparseMerkleProofExt)>> {
+        self.deref()
+            .get_state_values_with_proof_by_version_ext(state_keys, version)
+    }
 }
 
 impl StateDb {
@@ -136,6 +136,55 @@ fn test_state_store_reader_writer() {
     verify_value_and_proof(store, key3, Some(&value3), 1, root);
 }
 
+#[test]
+fn test_batch_state_reads() {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    let store = &db.state_store;
+    let key1 = StateKey::Raw(String::from("test_key1").into_bytes());
+    let key2 = StateKey::Raw(String::from("test_key2").into_bytes());
+    let key3 = StateKey::Raw(String::from("test_key3").into_bytes());
+    let value1 = StateValue::from(String::from("test_val1").into_bytes());
+    let value1_update = StateValue::from(String::from("test_val1_update").into_bytes());
+    let value2 = StateValue::from(String::from("test_val2").into_bytes());
+
+    let root0 = put_value_set(store, vec![(key1.clone(), value1.clone())], 0, None);
+    let root1 = put_value_set(
+        store,
+        vec![
+            (key1.clone(), value1_update.clone()),
+            (key2.clone(), value2.clone()),
+        ],
+        1,
+        Some(0),
+    );
+
+    let keys = vec![key3, key1, key2];
+    for (version, root, expected_values) in [
+        (0, root0, vec![None, Some(&value1), None]),
+        (1, root1, vec![None, Some(&value1_update), Some(&value2)]),
+    ] {
+        let values = store.get_state_values_by_version(&keys, version).unwrap();
+        assert_eq!(
+            values.iter().map(Option::as_ref).collect::<Vec<_>>(),
+            expected_values
+        );
+
+        let values_and_proofs = store
+            .get_state_values_with_proof_by_version_ext(&keys, version)
+            .unwrap();
+        assert_eq!(values_and_proofs.len(), keys.len());
+        for ((key, (value, proof)), expected_value) in keys
+            .iter()
+            .zip(values_and_proofs)
+            .zip(expected_values.iter())
+        {
+            assert_eq!(value.as_ref(), *expected_value);
+            proof.verify(root, key.hash(), value.as_ref()).unwrap();
+        }
+    }
+}
+
 fn traverse_values(
     store: &StateStore,
     prefix: &StateKeyPrefix,
@@ -306,7 +306,26 @@ pub fn test_save_blocks_impl(
     let tmp_dir = TempPath::new();
     let db =
         AptosDB::new_for_test_with_buffered_state_target_items(&tmp_dir, snapshot_size_threshold);
+    save_blocks_and_verify(&db, input, snapshot_size_threshold);
+}
 
+/// Same as `test_save_blocks_impl`, but on an `AptosDB` that lives in memory.
+pub fn test_save_blocks_in_memory_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+    snapshot_size_threshold: usize,
+) {
+    let db = AptosDB::open_in_memory(
+        snapshot_size_threshold,
+        DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+    );
+    save_blocks_and_verify(&db, input, snapshot_size_threshold);
+}
+
+fn save_blocks_and_verify(
+    db: &AptosDB,
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+    snapshot_size_threshold: usize,
+) {
     let mut in_memory_state = db
         .state_store
         .buffered_state()
@@ -345,7 +364,7 @@ pub fn test_save_blocks_impl(
             *ledger_info_with_sigs
         );
         verify_committed_transactions(
-            &db,
+            db,
             txns_to_commit,
             cur_ver,
             ledger_info_with_sigs,
@@ -365,7 +384,7 @@ pub fn test_save_blocks_impl(
     let latest_ledger_info = input.last().unwrap().1.clone();
     // Verify an old batch with the latest LedgerInfo.
     verify_committed_transactions(
-        &db,
+        db,
         &first_batch,
         0,
         &latest_ledger_info,
@@ -373,19 +392,19 @@ pub fn test_save_blocks_impl(
     );
     // Verify an old batch with an old LedgerInfo.
     verify_committed_transactions(
-        &db,
+        db,
         &first_batch,
         0,
         &first_batch_ledger_info,
         true, /* is_latest */
     );
     let (_, ledger_infos_with_sigs): (Vec<_>, Vec<_>) = input.iter().cloned().unzip();
-    verify_epochs(&db, &ledger_infos_with_sigs);
+    verify_epochs(db, &ledger_infos_with_sigs);
 
     // sync the commits and verify the states
     db.state_store.buffered_state().lock().sync_commit();
     verify_snapshots(
-        &db,
+        db,
         0, /* first_version */
         snapshot_versions,
         input
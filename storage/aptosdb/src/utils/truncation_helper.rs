@@ -0,0 +1,354 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers to truncate the DB to an earlier version, i.e. to delete everything committed after
+//! a target version so that the node can resume (or re-sync) from there.
+//!
+//! The ledger DB is truncated top down in batches of `TRUNCATION_BATCH_SIZE` versions, each
+//! batch committed atomically, so the DB ends up at a smaller but internally consistent version
+//! after every batch. The target version is recorded in the ledger DB before anything is deleted
+//! and cleared once done, so that a truncation interrupted by a crash is resumed on the next open.
+//! The state values in the K/V DB, if enabled, are deleted batch by batch before the ledger DB
+//! batch that deletes the write sets they are found with. The JMT nodes and stale node indices
+//! after the target version are deleted in batches of `TRUNCATION_BATCH_SIZE` keys as well.
+
+use crate::{
+    event_store::EventStore,
+    jellyfish_merkle_node::JellyfishMerkleNodeSchema,
+    schema::{
+        db_metadata::{DbMetadataKey, DbMetadataSchema, DbMetadataValue},
+        epoch_by_version::EpochByVersionSchema,
+        ledger_info::LedgerInfoSchema,
+        stale_state_value_index::StaleStateValueIndexSchema,
+        state_value::StateValueSchema,
+        transaction::TransactionSchema,
+        transaction_accumulator::TransactionAccumulatorSchema,
+        transaction_info::TransactionInfoSchema,
+        version_data::VersionDataSchema,
+        write_set::WriteSetSchema,
+    },
+    stale_node_index::StaleNodeIndexSchema,
+    stale_node_index_cross_epoch::StaleNodeIndexCrossEpochSchema,
+    state_store::MAX_WRITE_SETS_AFTER_SNAPSHOT,
+    transaction_store::TransactionStore,
+};
+use anyhow::{ensure, format_err, Result};
+use aptos_jellyfish_merkle::node_type::NodeKey;
+use aptos_logger::info;
+use aptos_schemadb::{
+    schema::{Schema, SeekKeyCodec},
+    SchemaBatch, DB,
+};
+use aptos_types::{proof::position::Position, transaction::Version, write_set::WriteSet};
+use std::sync::Arc;
+
+const TRUNCATION_BATCH_SIZE: usize = 10_000;
+
+/// Returns the latest version that has a `TransactionInfo` in the ledger DB.
+pub(crate) fn get_ledger_commit_progress(ledger_db: &DB) -> Result<Option<Version>> {
+    let mut iter = ledger_db.rev_iter::<TransactionInfoSchema>(Default::default())?;
+    iter.seek_to_last();
+    Ok(iter.next().transpose()?.map(|(version, _)| version))
+}
+
+/// Returns the version of the latest state snapshot (JMT root) at or before `version`.
+pub(crate) fn find_state_snapshot_before(
+    state_merkle_db: &DB,
+    version: Version,
+) -> Result<Option<Version>> {
+    let mut iter = state_merkle_db.rev_iter::<JellyfishMerkleNodeSchema>(Default::default())?;
+    iter.seek_for_prev(&NodeKey::new_empty_path(version))?;
+    Ok(iter.next().transpose()?.map(|(key, _node)| key.version()))
+}
+
+/// Checks that the DB can be truncated to `target_version` without losing data we can't
+/// rebuild: the target must not be newer than the latest version, not older than what the
+/// pruners have already deleted, and there must be a state snapshot to replay from, whose
+/// following write sets haven't been pruned. The target must also be a state checkpoint, so
+/// the replayed state can be verified against the state root hash recorded for it.
+pub(crate) fn check_truncation_target(
+    ledger_db: &DB,
+    state_merkle_db: &DB,
+    target_version: Version,
+) -> Result<()> {
+    let latest_version = get_ledger_commit_progress(ledger_db)?
+        .ok_or_else(|| format_err!("The ledger DB is empty."))?;
+    ensure!(
+        target_version <= latest_version,
+        "Target version {} is newer than the latest version {}.",
+        target_version,
+        latest_version,
+    );
+
+    let ledger_pruner_progress = ledger_db
+        .get::<DbMetadataSchema>(&DbMetadataKey::LedgerPrunerProgress)?
+        .map_or(0, |v| v.expect_version());
+    ensure!(
+        target_version >= ledger_pruner_progress,
+        "Target version {} has been pruned, the ledger pruner progress is {}.",
+        target_version,
+        ledger_pruner_progress,
+    );
+
+    let target_txn_info = ledger_db
+        .get::<TransactionInfoSchema>(&target_version)?
+        .ok_or_else(|| format_err!("No transaction info at target version {}.", target_version))?;
+    ensure!(
+        target_txn_info.is_state_checkpoint(),
+        "Target version {} is not a state checkpoint (e.g. the end of a block), so the state \
+         after truncation couldn't be verified.",
+        target_version,
+    );
+
+    let snapshot_version = find_state_snapshot_before(state_merkle_db, target_version)?
+        .ok_or_else(|| {
+            format_err!(
+                "No state snapshot found at or before version {}.",
+                target_version
+            )
+        })?;
+    let state_merkle_pruner_progress = state_merkle_db
+        .get::<DbMetadataSchema>(&DbMetadataKey::StateMerklePrunerProgress)?
+        .map_or(0, |v| v.expect_version());
+    ensure!(
+        snapshot_version >= state_merkle_pruner_progress,
+        "The latest state snapshot before target version {} is at version {}, which has been \
+         pruned, the state merkle pruner progress is {}.",
+        target_version,
+        snapshot_version,
+        state_merkle_pruner_progress,
+    );
+    ensure!(
+        snapshot_version >= ledger_pruner_progress,
+        "The latest state snapshot before target version {} is at version {}, but the write \
+         sets before version {} have been pruned, so the state can't be replayed from it.",
+        target_version,
+        snapshot_version,
+        ledger_pruner_progress,
+    );
+    ensure!(
+        target_version - snapshot_version <= MAX_WRITE_SETS_AFTER_SNAPSHOT,
+        "Target version {} is too far from the latest state snapshot before it at version {}.",
+        target_version,
+        snapshot_version,
+    );
+
+    Ok(())
+}
+
+/// Records `target_version` as the version to truncate to, which `resume_truncation` acts on.
+pub(crate) fn set_truncation_target(ledger_db: &DB, target_version: Version) -> Result<()> {
+    ledger_db.put::<DbMetadataSchema>(
+        &DbMetadataKey::TruncationTarget,
+        &DbMetadataValue::Version(target_version),
+    )
+}
+
+/// Carries out the truncation recorded by `set_truncation_target`, if any, and clears it.
+pub(crate) fn resume_truncation(
+    ledger_db: &Arc<DB>,
+    state_merkle_db: &DB,
+    kv_db: Option<&DB>,
+) -> Result<()> {
+    let target_version =
+        match ledger_db.get::<DbMetadataSchema>(&DbMetadataKey::TruncationTarget)? {
+            Some(value) => value.expect_version(),
+            None => return Ok(()),
+        };
+    info!(target_version = target_version, "Truncating AptosDB.");
+
+    truncate_state_merkle_db(state_merkle_db, target_version)?;
+    truncate_ledger_db(Arc::clone(ledger_db), kv_db, target_version)?;
+
+    let batch = SchemaBatch::new();
+    batch.delete::<DbMetadataSchema>(&DbMetadataKey::TruncationTarget)?;
+    ledger_db.write_schemas(batch)
+}
+
+/// Deletes everything in the ledger DB and the K/V DB committed after `target_version`.
+pub(crate) fn truncate_ledger_db(
+    ledger_db: Arc<DB>,
+    kv_db: Option<&DB>,
+    target_version: Version,
+) -> Result<()> {
+    let latest_version = match get_ledger_commit_progress(&ledger_db)? {
+        Some(version) if version > target_version => version,
+        _ => return Ok(()),
+    };
+
+    let batch = SchemaBatch::new();
+    delete_ledger_infos_after(&ledger_db, target_version, &batch)?;
+    ledger_db.write_schemas(batch)?;
+
+    let transaction_store = TransactionStore::new(Arc::clone(&ledger_db));
+    let event_store = EventStore::new(Arc::clone(&ledger_db));
+    let mut end = latest_version + 1;
+    while end > target_version + 1 {
+        let start = std::cmp::max(
+            target_version + 1,
+            end.saturating_sub(TRUNCATION_BATCH_SIZE as Version),
+        );
+        let write_sets = transaction_store.get_write_sets(start, end)?;
+        if let Some(kv_db) = kv_db {
+            let batch = SchemaBatch::new();
+            delete_state_values(kv_db, &write_sets, start, &batch)?;
+            kv_db.write_schemas(batch)?;
+        }
+        let batch = SchemaBatch::new();
+        delete_ledger_data(
+            &ledger_db,
+            &transaction_store,
+            &event_store,
+            &write_sets,
+            start,
+            end,
+            &batch,
+        )?;
+        ledger_db.write_schemas(batch)?;
+        info!(
+            start_version = start,
+            end_version = end,
+            "Truncated ledger DB batch."
+        );
+        end = start;
+    }
+
+    Ok(())
+}
+
+/// Deletes the JMT nodes created after `target_version` and the stale node indices recorded
+/// after it.
+pub(crate) fn truncate_state_merkle_db(
+    state_merkle_db: &DB,
+    target_version: Version,
+) -> Result<()> {
+    // Nodes are deleted in key order, so the root of a version goes before the rest of its
+    // nodes and an interrupted truncation never leaves a partial snapshot that looks complete.
+    delete_keys_from::<JellyfishMerkleNodeSchema, _>(
+        state_merkle_db,
+        &NodeKey::new_empty_path(target_version + 1),
+    )?;
+    delete_keys_from::<StaleNodeIndexSchema, _>(state_merkle_db, &(target_version + 1))?;
+    delete_keys_from::<StaleNodeIndexCrossEpochSchema, _>(state_merkle_db, &(target_version + 1))
+}
+
+/// Deletes every key of `S` at or after `seek_key`, committing a batch every
+/// `TRUNCATION_BATCH_SIZE` keys.
+fn delete_keys_from<S, K>(db: &DB, seek_key: &K) -> Result<()>
+where
+    S: Schema,
+    K: SeekKeyCodec<S>,
+{
+    let mut iter = db.iter::<S>(Default::default())?;
+    iter.seek(seek_key)?;
+    let mut batch = SchemaBatch::new();
+    let mut batch_size = 0;
+    for item in iter {
+        let (key, _) = item?;
+        batch.delete::<S>(&key)?;
+        batch_size += 1;
+        if batch_size == TRUNCATION_BATCH_SIZE {
+            db.write_schemas(std::mem::replace(&mut batch, SchemaBatch::new()))?;
+            batch_size = 0;
+        }
+    }
+    db.write_schemas(batch)
+}
+
+/// Deletes the ledger infos (and the epoch ending markers) for versions after `target_version`.
+fn delete_ledger_infos_after(
+    ledger_db: &DB,
+    target_version: Version,
+    batch: &SchemaBatch,
+) -> Result<()> {
+    let mut iter = ledger_db.rev_iter::<LedgerInfoSchema>(Default::default())?;
+    iter.seek_to_last();
+    for item in iter {
+        let (epoch, ledger_info) = item?;
+        if ledger_info.ledger_info().version() <= target_version {
+            break;
+        }
+        batch.delete::<LedgerInfoSchema>(&epoch)?;
+    }
+
+    let mut iter = ledger_db.iter::<EpochByVersionSchema>(Default::default())?;
+    iter.seek(&(target_version + 1))?;
+    for item in iter {
+        let (version, _epoch) = item?;
+        batch.delete::<EpochByVersionSchema>(&version)?;
+    }
+
+    Ok(())
+}
+
+/// Deletes all ledger data for versions in [start, end), assuming nothing exists at or after
+/// `end` anymore.
+fn delete_ledger_data(
+    ledger_db: &DB,
+    transaction_store: &TransactionStore,
+    event_store: &EventStore,
+    write_sets: &[WriteSet],
+    start: Version,
+    end: Version,
+    batch: &SchemaBatch,
+) -> Result<()> {
+    let transactions = transaction_store
+        .get_transaction_iter(start, (end - start) as usize)?
+        .collect::<Result<Vec<_>>>()?;
+    transaction_store.prune_transaction_by_hash(&transactions, batch)?;
+    transaction_store.prune_transaction_by_account(&transactions, batch)?;
+    transaction_store.prune_transaction_schema(start, end, batch)?;
+    transaction_store.prune_transaction_info_schema(start, end, batch)?;
+
+    // Every accumulator node at or after the first leaf being removed in postorder covers some
+    // leaf being removed.
+    let mut iter = ledger_db.iter::<TransactionAccumulatorSchema>(Default::default())?;
+    iter.seek(&Position::from_leaf_index(start))?;
+    for item in iter {
+        let (position, _) = item?;
+        batch.delete::<TransactionAccumulatorSchema>(&position)?;
+    }
+
+    event_store.prune_events(start, end, batch)?;
+
+    delete_state_values(ledger_db, write_sets, start, batch)?;
+    for version in start..end {
+        batch.delete::<WriteSetSchema>(&version)?;
+        batch.delete::<VersionDataSchema>(&version)?;
+    }
+
+    // Sanity check that nothing beyond the batch was left behind by an earlier batch.
+    let mut iter = ledger_db.iter::<TransactionSchema>(Default::default())?;
+    iter.seek(&end)?;
+    ensure!(
+        iter.next().transpose()?.is_none(),
+        "Found transactions at or after version {} while truncating.",
+        end,
+    );
+
+    Ok(())
+}
+
+/// Deletes from `db` the state values in `write_sets`, which start at version `start`, and the
+/// stale state value indices recorded at or after `start`.
+fn delete_state_values(
+    db: &DB,
+    write_sets: &[WriteSet],
+    start: Version,
+    batch: &SchemaBatch,
+) -> Result<()> {
+    for (version, write_set) in (start..).zip(write_sets) {
+        for (state_key, _) in write_set.iter() {
+            batch.delete::<StateValueSchema>(&(state_key.clone(), version))?;
+        }
+    }
+
+    let mut iter = db.iter::<StaleStateValueIndexSchema>(Default::default())?;
+    iter.seek(&start)?;
+    for item in iter {
+        let (index, _) = item?;
+        batch.delete::<StaleStateValueIndexSchema>(&index)?;
+    }
+
+    Ok(())
+}
@@ -185,7 +185,7 @@ impl EpochEndingBackupController {
         };
         let (manifest_handle, mut manifest_file) = self
             .storage
-            .create_for_write(backup_handle, Self::manifest_name())
+            .create_manifest_for_write(backup_handle, Self::manifest_name())
             .await?;
         manifest_file
             .write_all(&serde_json::to_vec(&manifest)?)
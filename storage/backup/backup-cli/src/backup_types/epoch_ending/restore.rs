@@ -78,8 +78,7 @@ impl EpochEndingRestoreController {
     }
 
     async fn preheat_impl(&self) -> Result<EpochEndingRestorePreheatData> {
-        let manifest: EpochEndingBackup =
-            self.storage.load_json_file(&self.manifest_handle).await?;
+        let manifest: EpochEndingBackup = self.storage.load_manifest(&self.manifest_handle).await?;
         manifest.verify()?;
 
         let mut next_epoch = manifest.first_epoch;
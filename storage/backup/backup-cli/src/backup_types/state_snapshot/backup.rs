@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    backup_types::state_snapshot::manifest::{StateSnapshotBackup, StateSnapshotChunk},
+    backup_types::state_snapshot::manifest::{
+        StateSnapshotBackup, StateSnapshotBlobs, StateSnapshotChunk,
+    },
     metadata::Metadata,
     storage::{BackupHandleRef, BackupStorage, FileHandle, ShellSafeName},
     utils::{
@@ -22,7 +24,7 @@ use aptos_types::{
 use bytes::Bytes;
 use clap::Parser;
 use once_cell::sync::Lazy;
-use std::{convert::TryInto, str::FromStr, sync::Arc};
+use std::{collections::HashMap, convert::TryInto, str::FromStr, sync::Arc};
 use tokio::{io::AsyncWriteExt, time::Instant};
 
 #[derive(Parser)]
@@ -32,11 +34,26 @@ pub struct StateSnapshotBackupOpt {
         help = "Epoch at the end of which a state snapshot is to be taken."
     )]
     pub epoch: u64,
+
+    #[clap(
+        long,
+        help = "Manifest of a previous state snapshot backup. Parts of chunks that haven't changed \
+        since that snapshot are not uploaded again, instead the new manifest refers to the \
+        existing files."
+    )]
+    pub base_manifest: Option<FileHandle>,
 }
 
+/// The average number of records in a chunk cut at a key boundary, see `is_chunk_boundary()`.
+const AVERAGE_RECORDS_PER_CHUNK_BOUNDARY: u64 = 100_000;
+/// The average number of records in a part of a chunk, see `is_part_boundary()`.
+const AVERAGE_RECORDS_PER_PART_BOUNDARY: u64 = 1_000;
+
 pub struct StateSnapshotBackupController {
     epoch: u64,
     version: Option<Version>, // initialize before using
+    base_manifest: Option<FileHandle>,
+    base_blobs: HashMap<HashValue, FileHandle>, // by blobs hash, initialize before using
     max_chunk_size: usize,
     client: Arc<BackupServiceClient>,
     storage: Arc<dyn BackupStorage>,
@@ -52,6 +69,8 @@ impl StateSnapshotBackupController {
         Self {
             epoch: opt.epoch,
             version: None,
+            base_manifest: opt.base_manifest,
+            base_blobs: HashMap::new(),
             max_chunk_size: global_opt.max_chunk_size,
             client,
             storage,
@@ -70,6 +89,17 @@ impl StateSnapshotBackupController {
 
     async fn run_impl(mut self) -> Result<FileHandle> {
         self.version = Some(self.get_version_for_epoch_ending(self.epoch).await?);
+        if let Some(base_manifest) = &self.base_manifest {
+            let base: StateSnapshotBackup = self.storage.load_manifest(base_manifest).await?;
+            for chunk in base.chunks {
+                if let Some(blobs_hash) = chunk.blobs_hash {
+                    self.base_blobs.insert(blobs_hash, chunk.blobs);
+                }
+                for part in chunk.more_blobs {
+                    self.base_blobs.insert(part.blobs_hash, part.blobs);
+                }
+            }
+        }
         let backup_handle = self
             .storage
             .create_backup_with_random_suffix(&self.backup_name())
@@ -78,33 +108,41 @@ impl StateSnapshotBackupController {
         let mut chunks = vec![];
 
         let mut state_snapshot_file = self.client.get_state_snapshot(self.version()).await?;
-        let mut prev_record_bytes = state_snapshot_file
+        let first_record_bytes = state_snapshot_file
             .read_record_bytes()
             .await?
             .ok_or_else(|| anyhow!("State is empty."))?;
-        let mut chunk_bytes = (prev_record_bytes.len() as u32).to_be_bytes().to_vec();
-        chunk_bytes.extend(&prev_record_bytes);
-        let mut chunk_first_key = Self::parse_key(&prev_record_bytes)?;
+        let mut chunk_bytes = (first_record_bytes.len() as u32).to_be_bytes().to_vec();
+        chunk_bytes.extend(&first_record_bytes);
+        let mut chunk_first_key = Self::parse_key(&first_record_bytes)?;
+        let mut prev_key = chunk_first_key;
         let mut current_idx: usize = 0;
         let mut chunk_first_idx: usize = 0;
+        // The first index and the offset in `chunk_bytes` of each part of the chunk.
+        let mut part_starts = vec![(chunk_first_idx, 0)];
 
         let start = Instant::now();
         while let Some(record_bytes) = state_snapshot_file.read_record_bytes().await? {
-            if should_cut_chunk(&chunk_bytes, &record_bytes, self.max_chunk_size) {
+            let key = Self::parse_key(&record_bytes)?;
+            if should_cut_chunk(&chunk_bytes, &record_bytes, self.max_chunk_size)
+                || Self::is_chunk_boundary(&prev_key)
+            {
                 let chunk = self
                     .write_chunk(
                         &backup_handle,
                         &chunk_bytes,
+                        &part_starts,
                         chunk_first_idx,
                         current_idx,
                         chunk_first_key,
-                        Self::parse_key(&prev_record_bytes)?,
+                        prev_key,
                     )
                     .await?;
                 chunks.push(chunk);
                 chunk_bytes = vec![];
                 chunk_first_idx = current_idx + 1;
-                chunk_first_key = Self::parse_key(&record_bytes)?;
+                chunk_first_key = key;
+                part_starts = vec![(chunk_first_idx, 0)];
 
                 info!(
                     last_idx = current_idx,
@@ -112,12 +150,14 @@ impl StateSnapshotBackupController {
                         ((current_idx + 1) as f64 / start.elapsed().as_secs_f64()) as u64,
                     "Chunk written."
                 );
+            } else if Self::is_part_boundary(&prev_key) {
+                part_starts.push((current_idx + 1, chunk_bytes.len()));
             }
 
             current_idx += 1;
             chunk_bytes.extend((record_bytes.len() as u32).to_be_bytes());
             chunk_bytes.extend(&record_bytes);
-            prev_record_bytes = record_bytes;
+            prev_key = key;
         }
 
         assert!(!chunk_bytes.is_empty());
@@ -125,10 +165,11 @@ impl StateSnapshotBackupController {
             .write_chunk(
                 &backup_handle,
                 &chunk_bytes,
+                &part_starts,
                 chunk_first_idx,
                 current_idx,
                 chunk_first_key,
-                Self::parse_key(&prev_record_bytes)?,
+                prev_key,
             )
             .await?;
         chunks.push(chunk);
@@ -168,6 +209,21 @@ impl StateSnapshotBackupController {
             .unwrap()
     }
 
+    /// Besides the max chunk size, chunks are cut after keys whose hash falls in a fixed range, so
+    /// that chunk boundaries are mostly stable across snapshots.
+    fn is_chunk_boundary(key: &HashValue) -> bool {
+        let prefix = u64::from_be_bytes(key[..8].try_into().unwrap());
+        prefix < u64::MAX / AVERAGE_RECORDS_PER_CHUNK_BOUNDARY
+    }
+
+    /// Same as `is_chunk_boundary()`, but for the finer boundaries the records of a chunk are
+    /// split at, so parts that didn't change can be reused by incremental snapshots. Every chunk
+    /// boundary is a part boundary as well.
+    fn is_part_boundary(key: &HashValue) -> bool {
+        let prefix = u64::from_be_bytes(key[..8].try_into().unwrap());
+        prefix < u64::MAX / AVERAGE_RECORDS_PER_PART_BOUNDARY
+    }
+
     fn parse_key(record: &Bytes) -> Result<HashValue> {
         let (key, _): (StateKey, StateValue) = bcs::from_bytes(record)?;
         Ok(key.hash())
@@ -188,21 +244,62 @@ impl StateSnapshotBackupController {
         Ok(ledger_info.ledger_info().version())
     }
 
+    /// Writes the blobs of the records starting at index `first_idx`, unless the base snapshot
+    /// has the same blobs already.
+    async fn write_blobs(
+        &self,
+        backup_handle: &BackupHandleRef,
+        blobs_bytes: &[u8],
+        first_idx: usize,
+    ) -> Result<StateSnapshotBlobs> {
+        let blobs_hash = HashValue::sha3_256_of(blobs_bytes);
+        let blobs = match self.base_blobs.get(&blobs_hash) {
+            Some(blobs) => {
+                debug!(
+                    first_idx = first_idx,
+                    "Records unchanged since the base snapshot, reusing {}.", blobs,
+                );
+                blobs.clone()
+            },
+            None => {
+                let (blobs, mut blobs_file) = self
+                    .storage
+                    .create_for_write(backup_handle, &Self::chunk_name(first_idx))
+                    .await?;
+                blobs_file.write_all(blobs_bytes).await?;
+                blobs_file.shutdown().await?;
+                blobs
+            },
+        };
+        Ok(StateSnapshotBlobs { blobs, blobs_hash })
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn write_chunk(
         &self,
         backup_handle: &BackupHandleRef,
         chunk_bytes: &[u8],
+        part_starts: &[(usize, usize)],
         first_idx: usize,
         last_idx: usize,
         first_key: HashValue,
         last_key: HashValue,
     ) -> Result<StateSnapshotChunk> {
-        let (chunk_handle, mut chunk_file) = self
-            .storage
-            .create_for_write(backup_handle, &Self::chunk_name(first_idx))
-            .await?;
-        chunk_file.write_all(chunk_bytes).await?;
-        chunk_file.shutdown().await?;
+        let part_ends = part_starts
+            .iter()
+            .skip(1)
+            .map(|(_idx, offset)| *offset)
+            .chain(std::iter::once(chunk_bytes.len()));
+        let mut parts = vec![];
+        for ((part_first_idx, start), end) in part_starts.iter().zip(part_ends) {
+            parts.push(
+                self.write_blobs(backup_handle, &chunk_bytes[*start..end], *part_first_idx)
+                    .await?,
+            );
+        }
+        let mut parts = parts.into_iter();
+        let first_part = parts.next().expect("Chunks have at least one part.");
+
         let (proof_handle, mut proof_file) = self
             .storage
             .create_for_write(backup_handle, &Self::chunk_proof_name(first_idx, last_idx))
@@ -222,7 +319,9 @@ impl StateSnapshotBackupController {
             last_idx,
             first_key,
             last_key,
-            blobs: chunk_handle,
+            blobs: first_part.blobs,
+            blobs_hash: Some(first_part.blobs_hash),
+            more_blobs: parts.collect(),
             proof: proof_handle,
         })
     }
@@ -253,7 +352,7 @@ impl StateSnapshotBackupController {
 
         let (manifest_handle, mut manifest_file) = self
             .storage
-            .create_for_write(backup_handle, Self::manifest_name())
+            .create_manifest_for_write(backup_handle, Self::manifest_name())
             .await?;
         manifest_file
             .write_all(&serde_json::to_vec(&manifest)?)
@@ -19,13 +19,34 @@ pub struct StateSnapshotChunk {
     /// key of the last account in this chunk.
     pub last_key: HashValue,
     /// Repeated `len(record) + record` where `record` is BCS serialized tuple
-    /// `(key, state_value)`
+    /// `(key, state_value)`. Only holds the first part of the records if `more_blobs` isn't
+    /// empty. Incremental snapshots refer to the blobs of a previous snapshot if the records
+    /// didn't change, so this doesn't necessarily belong to the same backup.
     pub blobs: FileHandle,
+    /// SHA3-256 of the content of `blobs`, verified on restore and used to identify unchanged
+    /// records across snapshots. Missing in manifests written before incremental snapshots were
+    /// supported.
+    #[serde(default)]
+    pub blobs_hash: Option<HashValue>,
+    /// The rest of the records in this chunk, in order. Records are split into parts at finer key
+    /// boundaries than chunks, so that incremental snapshots can reuse the parts that didn't
+    /// change even if the rest of the chunk did.
+    #[serde(default)]
+    pub more_blobs: Vec<StateSnapshotBlobs>,
     /// BCS serialized `SparseMerkleRangeProof` that proves this chunk adds up to the root hash
     /// indicated in the backup (`StateSnapshotBackup::root_hash`).
     pub proof: FileHandle,
 }
 
+/// A part of the records of a `StateSnapshotChunk`, in a file of its own.
+#[derive(Deserialize, Serialize)]
+pub struct StateSnapshotBlobs {
+    /// Repeated `len(record) + record`, same as `StateSnapshotChunk::blobs`.
+    pub blobs: FileHandle,
+    /// SHA3-256 of the content of `blobs`.
+    pub blobs_hash: HashValue,
+}
+
 /// State snapshot backup manifest, representing a complete state view at specified version.
 #[derive(Deserialize, Serialize)]
 pub struct StateSnapshotBackup {
@@ -3,7 +3,8 @@
 
 use crate::{
     backup_types::{
-        epoch_ending::restore::EpochHistory, state_snapshot::manifest::StateSnapshotBackup,
+        epoch_ending::restore::EpochHistory,
+        state_snapshot::manifest::{StateSnapshotBackup, StateSnapshotChunk},
     },
     metrics::{
         restore::{
@@ -22,6 +23,7 @@ use crate::{
     },
 };
 use anyhow::{anyhow, ensure, Result};
+use aptos_crypto::HashValue;
 use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
 use aptos_storage_interface::StateSnapshotReceiver;
@@ -111,7 +113,7 @@ impl StateSnapshotRestoreController {
         }
 
         let manifest: StateSnapshotBackup =
-            self.storage.load_json_file(&self.manifest_handle).await?;
+            self.storage.load_manifest(&self.manifest_handle).await?;
         let (txn_info_with_proof, li): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
             self.storage.load_bcs_file(&manifest.proof).await?;
         txn_info_with_proof.verify(li.ledger_info(), manifest.version)?;
@@ -177,7 +179,7 @@ impl StateSnapshotRestoreController {
             let storage = storage.clone();
             async move {
                 tokio::spawn(async move {
-                    let blobs = Self::read_state_value(&storage, chunk.blobs.clone()).await?;
+                    let blobs = Self::read_state_value(&storage, &chunk).await?;
                     let proof = storage.load_bcs_file(&chunk.proof).await?;
                     Result::<_>::Ok((chunk_idx, chunk, blobs, proof))
                 })
@@ -234,18 +236,35 @@ impl StateSnapshotRestoreController {
         }
     }
 
+    /// Reads the records of all parts of the chunk, verifying their hashes if known.
     async fn read_state_value(
         storage: &Arc<dyn BackupStorage>,
-        file_handle: FileHandle,
+        chunk: &StateSnapshotChunk,
     ) -> Result<Vec<(StateKey, StateValue)>> {
-        let mut file = storage.open_for_read(&file_handle).await?;
-
-        let mut chunk = vec![];
+        let parts = std::iter::once((&chunk.blobs, chunk.blobs_hash)).chain(
+            chunk
+                .more_blobs
+                .iter()
+                .map(|part| (&part.blobs, Some(part.blobs_hash))),
+        );
 
-        while let Some(record_bytes) = file.read_record_bytes().await? {
-            chunk.push(bcs::from_bytes(&record_bytes)?);
+        let mut records = vec![];
+        for (file_handle, blobs_hash) in parts {
+            let bytes = storage.read_all(file_handle).await?;
+            if let Some(blobs_hash) = blobs_hash {
+                ensure!(
+                    HashValue::sha3_256_of(&bytes) == blobs_hash,
+                    "Hash of {} doesn't match the manifest, expected {}.",
+                    file_handle,
+                    blobs_hash,
+                );
+            }
+            let mut file = bytes.as_slice();
+            while let Some(record_bytes) = file.read_record_bytes().await? {
+                records.push(bcs::from_bytes(&record_bytes)?);
+            }
         }
 
-        Ok(chunk)
+        Ok(records)
     }
 }
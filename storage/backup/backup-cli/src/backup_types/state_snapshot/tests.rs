@@ -4,11 +4,13 @@
 use crate::{
     backup_types::state_snapshot::{
         backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+        manifest::StateSnapshotBackup,
         restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
     },
-    storage::{local_fs::LocalFs, BackupStorage},
+    storage::{local_fs::LocalFs, BackupStorage, FileHandle},
     utils::{
         backup_service_client::BackupServiceClient,
+        storage_ext::BackupStorageExt,
         test_utils::{start_local_backup_service, tmp_db_with_random_content},
         ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, ReplayConcurrencyLevelOpt,
         RocksdbOpt, TrustedWaypointOpt,
@@ -57,10 +59,31 @@ fn end_to_end() {
         "http://localhost:{}",
         port
     )));
+    let base_manifest_handle = rt
+        .block_on(
+            StateSnapshotBackupController::new(
+                StateSnapshotBackupOpt {
+                    epoch,
+                    base_manifest: None,
+                },
+                GlobalBackupOpt {
+                    max_chunk_size: 500,
+                },
+                Arc::clone(&client),
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+
+    // Take the same snapshot again incrementally, which should reuse all chunks.
     let manifest_handle = rt
         .block_on(
             StateSnapshotBackupController::new(
-                StateSnapshotBackupOpt { epoch },
+                StateSnapshotBackupOpt {
+                    epoch,
+                    base_manifest: Some(base_manifest_handle.clone()),
+                },
                 GlobalBackupOpt {
                     max_chunk_size: 500,
                 },
@@ -70,6 +93,12 @@ fn end_to_end() {
             .run(),
         )
         .unwrap();
+    let base_manifest: StateSnapshotBackup = rt
+        .block_on(store.load_manifest(&base_manifest_handle))
+        .unwrap();
+    let manifest: StateSnapshotBackup = rt.block_on(store.load_manifest(&manifest_handle)).unwrap();
+    assert_ne!(manifest_handle, base_manifest_handle);
+    assert_eq!(all_blobs(&manifest), all_blobs(&base_manifest));
 
     rt.block_on(
         StateSnapshotRestoreController::new(
@@ -89,7 +118,7 @@ fn end_to_end() {
             }
             .try_into()
             .unwrap(),
-            store,
+            Arc::clone(&store),
             None, /* epoch_history */
         )
         .run(),
@@ -105,5 +134,45 @@ fn end_to_end() {
         (version, state_root_hash)
     );
 
+    // Restoring fails if the content of a blobs file doesn't match the manifest.
+    std::fs::write(backup_dir.path().join(all_blobs(&manifest)[0]), b"").unwrap();
+    let other_tgt_db_dir = TempPath::new();
+    other_tgt_db_dir.create_as_dir().unwrap();
+    rt.block_on(
+        StateSnapshotRestoreController::new(
+            StateSnapshotRestoreOpt {
+                manifest_handle: base_manifest_handle,
+                version,
+                validate_modules: false,
+            },
+            GlobalRestoreOpt {
+                dry_run: false,
+                db_dir: Some(other_tgt_db_dir.path().to_path_buf()),
+                target_version: None, // max
+                trusted_waypoints: TrustedWaypointOpt::default(),
+                rocksdb_opt: RocksdbOpt::default(),
+                concurrent_downloads: ConcurrentDownloadsOpt::default(),
+                replay_concurrency_level: ReplayConcurrencyLevelOpt::default(),
+            }
+            .try_into()
+            .unwrap(),
+            store,
+            None, /* epoch_history */
+        )
+        .run(),
+    )
+    .unwrap_err();
+
     rt.shutdown_timeout(Duration::from_secs(1));
 }
+
+/// All blobs files of the snapshot, in order.
+fn all_blobs(manifest: &StateSnapshotBackup) -> Vec<&FileHandle> {
+    manifest
+        .chunks
+        .iter()
+        .flat_map(|chunk| {
+            std::iter::once(&chunk.blobs).chain(chunk.more_blobs.iter().map(|part| &part.blobs))
+        })
+        .collect()
+}
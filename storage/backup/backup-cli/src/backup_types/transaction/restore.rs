@@ -258,7 +258,7 @@ impl TransactionRestoreBatchController {
         let manifest_stream = manifest_handle_stream
             .map(move |hdl| {
                 let storage = storage.clone();
-                async move { storage.load_json_file(&hdl).await.err_notes(&hdl) }
+                async move { storage.load_manifest(&hdl).await.err_notes(&hdl) }
             })
             .buffered_x(con * 3, con)
             .and_then(|m: TransactionBackup| future::ready(m.verify().map(|_| m)));
@@ -8,7 +8,10 @@ use aptos_backup_cli::{
         state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
         transaction::backup::{TransactionBackupController, TransactionBackupOpt},
     },
-    coordinators::backup::{BackupCoordinator, BackupCoordinatorOpt},
+    coordinators::{
+        backup::{BackupCoordinator, BackupCoordinatorOpt},
+        gc::{GcCoordinator, GcCoordinatorOpt},
+    },
     metadata::{cache, cache::MetadataCacheOpt},
     storage::StorageOpt,
     utils::{
@@ -114,6 +117,11 @@ enum CoordinatorCommand {
     a Aptos Node."
     )]
     Run(CoordinatorRunOpt),
+    #[clap(
+        about = "Garbage collect the backup storage, dropping state snapshots not selected by the \
+        retention policy and compacting the metadata files."
+    )]
+    Gc(CoordinatorGcOpt),
 }
 
 #[derive(Parser)]
@@ -131,6 +139,15 @@ struct CoordinatorRunOpt {
     storage: StorageOpt,
 }
 
+#[derive(Parser)]
+struct CoordinatorGcOpt {
+    #[clap(flatten)]
+    coordinator: GcCoordinatorOpt,
+
+    #[clap(subcommand)]
+    storage: StorageOpt,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     main_impl().await.map_err(|e| {
@@ -215,6 +232,11 @@ async fn main_impl() -> Result<()> {
                 .run()
                 .await?;
             },
+            CoordinatorCommand::Gc(opt) => {
+                GcCoordinator::new(opt.coordinator, opt.storage.init_storage().await?)?
+                    .run()
+                    .await?;
+            },
         },
     }
     Ok(())
@@ -12,7 +12,7 @@ use crate::{
     metrics::backup::{
         EPOCH_ENDING_EPOCH, HEARTBEAT_TS, STATE_SNAPSHOT_EPOCH, TRANSACTION_VERSION,
     },
-    storage::BackupStorage,
+    storage::{BackupStorage, FileHandle},
     utils::{
         backup_service_client::BackupServiceClient, unix_timestamp_sec, ConcurrentDownloadsOpt,
         GlobalBackupOpt,
@@ -20,6 +20,7 @@ use crate::{
 };
 use anyhow::{anyhow, ensure, Result};
 use aptos_db::backup::backup_handler::DbState;
+use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
 use aptos_types::transaction::Version;
 use clap::Parser;
@@ -85,6 +86,8 @@ pub struct BackupCoordinator {
     state_snapshot_interval_epochs: usize,
     transaction_batch_size: usize,
     concurrent_downloads: usize,
+    // The manifest of the latest state snapshot, against which the next one is taken incrementally.
+    last_state_snapshot_manifest: Mutex<Option<FileHandle>>,
 }
 
 impl BackupCoordinator {
@@ -103,18 +106,22 @@ impl BackupCoordinator {
             state_snapshot_interval_epochs: opt.state_snapshot_interval_epochs,
             transaction_batch_size: opt.transaction_batch_size,
             concurrent_downloads: opt.concurrent_downloads.get(),
+            last_state_snapshot_manifest: Mutex::new(None),
         }
     }
 
     pub async fn run(&self) -> Result<()> {
         // Connect to both the local node and the backup storage.
-        let backup_state = metadata::cache::sync_and_load(
+        let metadata_view = metadata::cache::sync_and_load(
             &self.metadata_cache_opt,
             Arc::clone(&self.storage),
             self.concurrent_downloads,
         )
-        .await?
-        .get_storage_state()?;
+        .await?;
+        let backup_state = metadata_view.get_storage_state()?;
+        *self.last_state_snapshot_manifest.lock() = metadata_view
+            .select_state_snapshot(Version::MAX)?
+            .map(|snapshot| snapshot.manifest);
 
         // On new DbState retrieved:
         // `watch_db_state` informs `backup_epoch_endings` via channel 1,
@@ -249,14 +256,19 @@ impl BackupCoordinator {
             return Ok(last_snapshot_epoch_in_backup);
         }
 
-        StateSnapshotBackupController::new(
-            StateSnapshotBackupOpt { epoch },
+        let base_manifest = self.last_state_snapshot_manifest.lock().clone();
+        let manifest = StateSnapshotBackupController::new(
+            StateSnapshotBackupOpt {
+                epoch,
+                base_manifest,
+            },
             self.global_opt.clone(),
             Arc::clone(&self.client),
             Arc::clone(&self.storage),
         )
         .run()
         .await?;
+        *self.last_state_snapshot_manifest.lock() = Some(manifest);
 
         Ok(Some(epoch))
     }
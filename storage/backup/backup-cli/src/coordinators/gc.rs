@@ -0,0 +1,520 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::state_snapshot::manifest::StateSnapshotBackup,
+    metadata,
+    metadata::{cache::MetadataCacheOpt, view::MetadataView, Metadata, StateSnapshotBackupMeta},
+    metrics::gc::{
+        GC_COORDINATOR_FAIL_TS, GC_COORDINATOR_START_TS, GC_COORDINATOR_SUCC_TS, GC_DELETED_FILES,
+        GC_DROPPED_STATE_SNAPSHOTS, GC_RETAINED_STATE_SNAPSHOTS,
+    },
+    storage::{BackupStorage, FileHandle, ShellSafeName},
+    utils::{
+        error_notes::ErrorNotes, storage_ext::BackupStorageExt, stream::StreamX,
+        unix_timestamp_sec, ConcurrentDownloadsOpt,
+    },
+};
+use anyhow::{anyhow, ensure, Context, Result};
+use aptos_logger::prelude::*;
+use aptos_types::{ledger_info::LedgerInfoWithSignatures, proof::TransactionInfoWithProof};
+use clap::Parser;
+use futures::{stream, TryStreamExt};
+use itertools::Itertools;
+use std::{collections::HashSet, convert::TryInto, sync::Arc};
+
+const USECS_PER_DAY: u64 = 24 * 3600 * 1_000_000;
+
+#[derive(Parser)]
+pub struct GcCoordinatorOpt {
+    #[clap(flatten)]
+    pub metadata_cache_opt: MetadataCacheOpt,
+    #[clap(
+        long,
+        help = "Keep the earliest state snapshot in every this many epochs, i.e. with the backup \
+        coordinator running with the same --state-snapshot-interval-epochs, set this to a multiple \
+        of it to thin out older snapshots."
+    )]
+    pub keep_state_snapshot_every_n_epochs: Option<u64>,
+    #[clap(
+        long,
+        help = "Keep all state snapshots taken within this many days before the latest one, \
+        according to the timestamps on the ledger infos proving them."
+    )]
+    pub keep_state_snapshots_for_days: Option<u64>,
+    #[clap(
+        long,
+        help = "Only log what would be deleted, without deleting anything or compacting metadata."
+    )]
+    pub dry_run: bool,
+    #[clap(
+        long,
+        help = "Confirms no backup is being taken while GC runs, i.e. the backup coordinator and \
+        any one-off backups are stopped. An incremental state snapshot being taken concurrently \
+        can refer to files of the state snapshots GC drops, so GC refuses to delete anything \
+        without this."
+    )]
+    pub backups_stopped: bool,
+    #[clap(flatten)]
+    pub concurrent_downloads: ConcurrentDownloadsOpt,
+}
+
+impl GcCoordinatorOpt {
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            self.keep_state_snapshot_every_n_epochs.is_some()
+                || self.keep_state_snapshots_for_days.is_some(),
+            "No retention policy specified, refusing to drop all but the latest state snapshot."
+        );
+        ensure!(
+            self.keep_state_snapshot_every_n_epochs != Some(0),
+            "--keep-state-snapshot-every-n-epochs must be greater than 0."
+        );
+        ensure!(
+            self.backups_stopped || self.dry_run,
+            "GC must not run alongside backups, stop them and pass --backups-stopped."
+        );
+        Ok(())
+    }
+}
+
+/// Garbage collects the backup storage:
+///   1. State snapshots not selected by the retention policy are dropped. The latest one, and the
+/// latest one that can be restored to, are always retained. Epoch ending and transaction backups
+/// are never dropped since restoring always requires them from genesis.
+///   2. Before deleting anything, it's verified that every retained state snapshot that can be
+/// restored to with the existing backups can still be restored to with the retained ones.
+///   3. All metadata files are compacted into a single one, which no longer has the dropped state
+/// snapshots.
+///   4. Files belonging to the dropped state snapshots are deleted, unless an incremental
+/// snapshot that is retained still refers to them.
+/// If interrupted between 3 and 4, some files are left behind without being referenced by any
+/// metadata, but the backup storage stays usable.
+///
+/// GC must not run alongside backups: the files to delete are determined once, so an incremental
+/// state snapshot taken concurrently could refer to them. This is enforced by requiring
+/// `--backups-stopped`, and by refusing to delete anything if metadata files were saved while GC
+/// was running.
+pub struct GcCoordinator {
+    storage: Arc<dyn BackupStorage>,
+    metadata_cache_opt: MetadataCacheOpt,
+    retention_policy: RetentionPolicy,
+    dry_run: bool,
+    concurrent_downloads: usize,
+}
+
+impl GcCoordinator {
+    pub fn new(opt: GcCoordinatorOpt, storage: Arc<dyn BackupStorage>) -> Result<Self> {
+        opt.validate()?;
+        Ok(Self {
+            storage,
+            metadata_cache_opt: opt.metadata_cache_opt,
+            retention_policy: RetentionPolicy {
+                every_n_epochs: opt.keep_state_snapshot_every_n_epochs,
+                keep_usecs: opt
+                    .keep_state_snapshots_for_days
+                    .map(|days| days.saturating_mul(USECS_PER_DAY)),
+            },
+            dry_run: opt.dry_run,
+            concurrent_downloads: opt.concurrent_downloads.get(),
+        })
+    }
+
+    pub async fn run(self) -> Result<()> {
+        info!("Backup GC coordinator started.");
+        GC_COORDINATOR_START_TS.set(unix_timestamp_sec());
+
+        let ret = self.run_impl().await;
+
+        if let Err(e) = &ret {
+            error!(
+                error = ?e,
+                "Backup GC coordinator failed."
+            );
+            GC_COORDINATOR_FAIL_TS.set(unix_timestamp_sec());
+        } else {
+            info!("Backup GC coordinator exiting with success.");
+            GC_COORDINATOR_SUCC_TS.set(unix_timestamp_sec());
+        }
+
+        ret
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        // Listed before loading the metadata, so metadata files saved after this (by a backup
+        // coordinator running concurrently) won't be deleted without being compacted.
+        let metadata_files = self.storage.list_metadata_files().await?;
+        let metadata_view = metadata::cache::sync_and_load(
+            &self.metadata_cache_opt,
+            Arc::clone(&self.storage),
+            self.concurrent_downloads,
+        )
+        .await?;
+
+        let snapshots = self
+            .load_state_snapshots(metadata_view.all_state_snapshots())
+            .await?;
+        let restorable = snapshots
+            .iter()
+            .map(|s| check_restorable(&metadata_view, &s.meta).is_ok())
+            .collect::<Vec<_>>();
+        let mut keep = self.retention_policy.select(
+            &snapshots
+                .iter()
+                .map(|s| (s.meta.epoch, s.timestamp_usecs))
+                .collect::<Vec<_>>(),
+        );
+        if let Some(idx) = restorable.iter().rposition(|r| *r) {
+            keep[idx] = true;
+        }
+
+        let mut retained = Vec::new();
+        let mut dropped = Vec::new();
+        for ((snapshot, keep), restorable) in snapshots.into_iter().zip(keep).zip(restorable) {
+            if keep {
+                retained.push((snapshot, restorable));
+            } else {
+                dropped.push(snapshot);
+            }
+        }
+
+        let dropped_manifests = dropped
+            .iter()
+            .map(|s| &s.meta.manifest)
+            .collect::<HashSet<_>>();
+        let retained_metadata = metadata_view
+            .to_metadata()
+            .into_iter()
+            .filter(|m| match m {
+                Metadata::StateSnapshotBackup(s) => !dropped_manifests.contains(&s.manifest),
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+        let retained_view = MetadataView::from(retained_metadata.clone());
+        for (snapshot, restorable) in &retained {
+            if *restorable {
+                check_restorable(&retained_view, &snapshot.meta).with_context(|| {
+                    format!(
+                        "State snapshot at version {} can no longer be restored to after GC.",
+                        snapshot.meta.version,
+                    )
+                })?;
+            }
+        }
+
+        let referenced_files = retained
+            .iter()
+            .flat_map(|(s, _)| s.files.iter())
+            .collect::<HashSet<_>>();
+        let files_to_delete = dropped
+            .iter()
+            .flat_map(|s| s.files.iter())
+            .filter(|f| !referenced_files.contains(f))
+            .unique()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        GC_RETAINED_STATE_SNAPSHOTS.set(retained.len() as i64);
+        GC_DROPPED_STATE_SNAPSHOTS.set(dropped.len() as i64);
+        info!(
+            retained_state_snapshots = retained.len(),
+            dropped_state_snapshots = dropped.len(),
+            files_to_delete = files_to_delete.len(),
+            metadata_files = metadata_files.len(),
+            "Retention policy applied, retained state snapshots verified."
+        );
+        for snapshot in &dropped {
+            info!(
+                epoch = snapshot.meta.epoch,
+                version = snapshot.meta.version,
+                manifest = snapshot.meta.manifest.as_str(),
+                "Dropping state snapshot."
+            );
+        }
+        if self.dry_run {
+            info!("Dry run, nothing is deleted.");
+            return Ok(());
+        }
+
+        // A backup finished meanwhile, so one might still be running and refer to the files.
+        self.ensure_no_new_metadata_files(&metadata_files).await?;
+
+        // Compact the metadata before deleting, so the dropped snapshots are never visible
+        // without their files.
+        if !dropped.is_empty() || metadata_files.len() > 1 {
+            self.compact_metadata(&retained_metadata, &metadata_files)
+                .await?;
+        }
+        self.delete_files(&files_to_delete).await?;
+
+        Ok(())
+    }
+
+    async fn load_state_snapshots(
+        &self,
+        metas: Vec<StateSnapshotBackupMeta>,
+    ) -> Result<Vec<StateSnapshotInfo>> {
+        let storage = &self.storage;
+        let futs = metas.into_iter().map(|meta| async move {
+            let manifest: StateSnapshotBackup = storage
+                .load_manifest(&meta.manifest)
+                .await
+                .err_notes(&meta.manifest)?;
+            let (_txn_info_with_proof, li): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
+                storage
+                    .load_bcs_file(&manifest.proof)
+                    .await
+                    .err_notes(&manifest.proof)?;
+
+            let files = std::iter::once(meta.manifest.clone())
+                .chain(std::iter::once(manifest.proof))
+                .chain(manifest.chunks.into_iter().flat_map(|chunk| {
+                    std::iter::once(chunk.blobs)
+                        .chain(chunk.more_blobs.into_iter().map(|part| part.blobs))
+                        .chain(std::iter::once(chunk.proof))
+                }))
+                .collect();
+            Result::<_>::Ok(StateSnapshotInfo {
+                timestamp_usecs: li.ledger_info().timestamp_usecs(),
+                meta,
+                files,
+            })
+        });
+        let con = self.concurrent_downloads;
+        stream::iter(futs)
+            .buffered_x(con * 2, con)
+            .try_collect()
+            .await
+    }
+
+    async fn ensure_no_new_metadata_files(&self, metadata_files: &[FileHandle]) -> Result<()> {
+        let known = metadata_files.iter().collect::<HashSet<_>>();
+        let new_files = self
+            .storage
+            .list_metadata_files()
+            .await?
+            .into_iter()
+            .filter(|file_handle| !known.contains(file_handle))
+            .collect::<Vec<_>>();
+        ensure!(
+            new_files.is_empty(),
+            "Metadata files were saved while GC was running, is a backup running concurrently? \
+            Refusing to delete anything. New metadata files: {:?}",
+            new_files,
+        );
+        Ok(())
+    }
+
+    async fn compact_metadata(
+        &self,
+        metadata: &[Metadata],
+        metadata_files: &[FileHandle],
+    ) -> Result<()> {
+        let name: ShellSafeName = format!("compacted_{}.meta", unix_timestamp_sec()).try_into()?;
+        let lines = metadata
+            .iter()
+            .map(Metadata::to_text_line)
+            .collect::<Result<Vec<_>>>()?;
+        self.storage.save_metadata_lines(&name, &lines).await?;
+        info!(
+            name = name.as_ref(),
+            entries = lines.len(),
+            "Compacted metadata saved."
+        );
+
+        self.delete_files(metadata_files).await?;
+        info!(
+            deleted = metadata_files.len(),
+            "Compacted metadata files deleted."
+        );
+        Ok(())
+    }
+
+    async fn delete_files(&self, file_handles: &[FileHandle]) -> Result<()> {
+        let storage = &self.storage;
+        let futs = file_handles.iter().map(|file_handle| async move {
+            storage
+                .delete_file(file_handle)
+                .await
+                .err_notes(file_handle)?;
+            GC_DELETED_FILES.inc();
+            Result::<_>::Ok(())
+        });
+        let con = self.concurrent_downloads;
+        stream::iter(futs)
+            .buffered_x(con * 2, con)
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(())
+    }
+}
+
+struct StateSnapshotInfo {
+    meta: StateSnapshotBackupMeta,
+    /// Timestamp of the ledger info proving the snapshot.
+    timestamp_usecs: u64,
+    /// All files making up the snapshot, including blobs reused from previous snapshots.
+    files: Vec<FileHandle>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct RetentionPolicy {
+    every_n_epochs: Option<u64>,
+    keep_usecs: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// Given the `(epoch, timestamp_usecs)` of all state snapshots ordered by version, returns
+    /// whether to retain each of them.
+    fn select(&self, snapshots: &[(u64, u64)]) -> Vec<bool> {
+        let latest_timestamp_usecs = snapshots.last().map_or(0, |(_epoch, ts)| *ts);
+        let mut last_bucket = None;
+
+        snapshots
+            .iter()
+            .enumerate()
+            .map(|(idx, (epoch, timestamp_usecs))| {
+                let mut keep = idx + 1 == snapshots.len();
+                if let Some(n) = self.every_n_epochs {
+                    let bucket = epoch / n;
+                    if last_bucket != Some(bucket) {
+                        last_bucket = Some(bucket);
+                        keep = true;
+                    }
+                }
+                if let Some(keep_usecs) = self.keep_usecs {
+                    keep |= timestamp_usecs.saturating_add(keep_usecs) >= latest_timestamp_usecs;
+                }
+                keep
+            })
+            .collect()
+    }
+}
+
+/// Checks the metadata has what the restore coordinator needs to restore to the state snapshot.
+fn check_restorable(view: &MetadataView, snapshot: &StateSnapshotBackupMeta) -> Result<()> {
+    let version = snapshot.version;
+    view.expect_state_snapshot(version)?;
+
+    let next_epoch = view
+        .select_epoch_ending_backups(version)?
+        .last()
+        .map_or(0, |backup| backup.last_epoch + 1);
+    ensure!(
+        next_epoch >= snapshot.epoch,
+        "Epoch ending backups end before epoch {}, needed by the state snapshot at version {}.",
+        snapshot.epoch,
+        version,
+    );
+
+    let transaction_backup = view
+        .select_transaction_backups(version, version)?
+        .pop()
+        .ok_or_else(|| {
+            anyhow!(
+                "No transaction backup at state snapshot version {}.",
+                version
+            )
+        })?;
+    ensure!(
+        transaction_backup.first_version <= version && version <= transaction_backup.last_version,
+        "No transaction backup at state snapshot version {}.",
+        version,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        coordinators::gc::{check_restorable, GcCoordinatorOpt, RetentionPolicy, USECS_PER_DAY},
+        metadata::{view::MetadataView, Metadata, StateSnapshotBackupMeta},
+    };
+    use clap::Parser;
+
+    #[test]
+    fn test_requires_backups_stopped() {
+        let validate = |args: &[&str]| {
+            GcCoordinatorOpt::try_parse_from(
+                ["gc", "--keep-state-snapshots-for-days", "7"]
+                    .iter()
+                    .chain(args),
+            )
+            .unwrap()
+            .validate()
+        };
+
+        validate(&[]).unwrap_err();
+        validate(&["--backups-stopped"]).unwrap();
+        // Nothing is deleted in a dry run.
+        validate(&["--dry-run"]).unwrap();
+    }
+
+    #[test]
+    fn test_retention_policy() {
+        let snapshots = (0..10)
+            .map(|epoch| (epoch, epoch * USECS_PER_DAY))
+            .collect::<Vec<_>>();
+        let select = |every_n_epochs, keep_days: Option<u64>| {
+            RetentionPolicy {
+                every_n_epochs,
+                keep_usecs: keep_days.map(|days| days * USECS_PER_DAY),
+            }
+            .select(&snapshots)
+            .into_iter()
+            .zip(&snapshots)
+            .filter_map(|(keep, (epoch, _ts))| keep.then_some(*epoch))
+            .collect::<Vec<_>>()
+        };
+
+        // The latest is always kept.
+        assert_eq!(select(None, None), vec![9]);
+        assert_eq!(select(Some(4), None), vec![0, 4, 8, 9]);
+        assert_eq!(select(None, Some(2)), vec![7, 8, 9]);
+        assert_eq!(select(Some(5), Some(1)), vec![0, 5, 8, 9]);
+
+        // The earliest snapshot in each bucket is kept, even if not at a multiple of N.
+        let snapshots = [(1, 0), (2, 0), (5, 0), (7, 0), (8, 0)];
+        assert_eq!(
+            RetentionPolicy {
+                every_n_epochs: Some(3),
+                keep_usecs: None,
+            }
+            .select(&snapshots),
+            vec![true, false, true, true, true],
+        );
+    }
+
+    #[test]
+    fn test_check_restorable() {
+        let snapshot = |epoch, version| StateSnapshotBackupMeta {
+            epoch,
+            version,
+            manifest: format!("snapshot_{}", version),
+        };
+        let view = MetadataView::from(vec![
+            Metadata::new_epoch_ending_backup(0, 1, 0, 100, "epoch_ending_0".to_string()),
+            Metadata::new_epoch_ending_backup(2, 2, 200, 200, "epoch_ending_2".to_string()),
+            Metadata::new_transaction_backup(0, 149, "transaction_0".to_string()),
+            Metadata::new_transaction_backup(150, 299, "transaction_150".to_string()),
+            Metadata::StateSnapshotBackup(snapshot(2, 150)),
+            Metadata::StateSnapshotBackup(snapshot(3, 250)),
+            Metadata::StateSnapshotBackup(snapshot(3, 350)),
+            Metadata::StateSnapshotBackup(snapshot(2, 150)),
+        ]);
+
+        check_restorable(&view, &snapshot(2, 150)).unwrap();
+        check_restorable(&view, &snapshot(3, 250)).unwrap();
+        // Transactions not backed up yet.
+        check_restorable(&view, &snapshot(3, 350)).unwrap_err();
+        // Epoch ending backups missing.
+        let view = MetadataView::from(vec![
+            Metadata::new_epoch_ending_backup(0, 1, 0, 100, "epoch_ending_0".to_string()),
+            Metadata::new_transaction_backup(0, 299, "transaction_0".to_string()),
+            Metadata::StateSnapshotBackup(snapshot(3, 250)),
+        ]);
+        check_restorable(&view, &snapshot(3, 250)).unwrap_err();
+        // Not in the metadata.
+        check_restorable(&view, &snapshot(2, 150)).unwrap_err();
+    }
+}
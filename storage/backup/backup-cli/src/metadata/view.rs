@@ -13,10 +13,41 @@ pub struct MetadataView {
     epoch_ending_backups: Vec<EpochEndingBackupMeta>,
     state_snapshot_backups: Vec<StateSnapshotBackupMeta>,
     transaction_backups: Vec<TransactionBackupMeta>,
-    _identity: Option<IdentityMeta>,
+    identity: Option<IdentityMeta>,
 }
 
 impl MetadataView {
+    /// All state snapshot backups, ordered by version.
+    pub fn all_state_snapshots(&self) -> Vec<StateSnapshotBackupMeta> {
+        self.state_snapshot_backups
+            .iter()
+            .sorted()
+            .cloned()
+            .collect()
+    }
+
+    /// All metadata entries in the view, used to compact metadata files.
+    pub(crate) fn to_metadata(&self) -> Vec<Metadata> {
+        self.epoch_ending_backups
+            .iter()
+            .cloned()
+            .map(Metadata::EpochEndingBackup)
+            .chain(
+                self.state_snapshot_backups
+                    .iter()
+                    .cloned()
+                    .map(Metadata::StateSnapshotBackup),
+            )
+            .chain(
+                self.transaction_backups
+                    .iter()
+                    .cloned()
+                    .map(Metadata::TransactionBackup),
+            )
+            .chain(self.identity.iter().cloned().map(Metadata::Identity))
+            .collect()
+    }
+
     pub fn get_storage_state(&self) -> Result<BackupStorageState> {
         let latest_epoch_ending_epoch =
             self.epoch_ending_backups.iter().map(|e| e.last_epoch).max();
@@ -144,11 +175,20 @@ impl From<Vec<Metadata>> for MetadataView {
             }
         }
 
+        // The same entry can appear in multiple metadata files, if the process compacting them was
+        // interrupted before removing the original ones.
+        epoch_ending_backups.sort();
+        epoch_ending_backups.dedup();
+        state_snapshot_backups.sort();
+        state_snapshot_backups.dedup();
+        transaction_backups.sort();
+        transaction_backups.dedup();
+
         Self {
             epoch_ending_backups,
             state_snapshot_backups,
             transaction_backups,
-            _identity: identity,
+            identity,
         }
     }
 }
@@ -0,0 +1,53 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_push_metrics::{register_int_gauge, IntGauge};
+use once_cell::sync::Lazy;
+
+pub static GC_RETAINED_STATE_SNAPSHOTS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_db_backup_gc_retained_state_snapshots",
+        "Number of state snapshots retained by the last garbage collection."
+    )
+    .unwrap()
+});
+
+pub static GC_DROPPED_STATE_SNAPSHOTS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_db_backup_gc_dropped_state_snapshots",
+        "Number of state snapshots dropped by the last garbage collection."
+    )
+    .unwrap()
+});
+
+pub static GC_DELETED_FILES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_db_backup_gc_deleted_files",
+        "Number of files deleted by the current garbage collection."
+    )
+    .unwrap()
+});
+
+pub static GC_COORDINATOR_START_TS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_db_backup_gc_coordinator_start_timestamp_s",
+        "Timestamp when the garbage collection coordinator starts."
+    )
+    .unwrap()
+});
+
+pub static GC_COORDINATOR_SUCC_TS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_db_backup_gc_coordinator_succeed_timestamp_s",
+        "Timestamp when the garbage collection coordinator succeeds."
+    )
+    .unwrap()
+});
+
+pub static GC_COORDINATOR_FAIL_TS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_db_backup_gc_coordinator_fail_timestamp_s",
+        "Timestamp when the garbage collection coordinator fails."
+    )
+    .unwrap()
+});
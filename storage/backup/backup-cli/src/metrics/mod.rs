@@ -5,6 +5,7 @@ use aptos_push_metrics::{exponential_buckets, register_histogram_vec, HistogramV
 use once_cell::sync::Lazy;
 
 pub mod backup;
+pub mod gc;
 pub mod metadata;
 pub mod restore;
 pub mod verify;
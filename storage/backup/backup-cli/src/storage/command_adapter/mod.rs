@@ -13,12 +13,13 @@ use crate::{
             command::Command,
             config::{CommandAdapterConfig, EnvVar},
         },
+        envelope::EnvelopeOpt,
         BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
         TextLine,
     },
     utils::error_notes::ErrorNotes,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use clap::Parser;
 use std::path::PathBuf;
@@ -31,6 +32,9 @@ pub struct CommandAdapterOpt {
         help = "Config file for the command adapter backup store."
     )]
     config: PathBuf,
+
+    #[clap(flatten)]
+    pub envelope: EnvelopeOpt,
 }
 
 /// A BackupStorage that delegates required APIs to configured command lines.
@@ -109,17 +113,24 @@ impl BackupStorage for CommandAdapter {
     }
 
     async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()> {
+        self.save_metadata_lines(name, std::slice::from_ref(content))
+            .await
+    }
+
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()> {
         let mut child = self
             .cmd(&self.config.commands.save_metadata_line, vec![
                 EnvVar::file_name(name.to_string()),
             ])
             .spawn()?;
 
-        child
-            .stdin()
-            .write_all(content.as_ref().as_bytes())
-            .await
-            .err_notes(name)?;
+        for line in lines {
+            child
+                .stdin()
+                .write_all(line.as_ref().as_bytes())
+                .await
+                .err_notes(name)?;
+        }
         child.join().await?;
         Ok(())
     }
@@ -137,4 +148,18 @@ impl BackupStorage for CommandAdapter {
             .err_notes((file!(), line!(), &buf))?;
         Ok(buf.lines().map(str::to_string).collect())
     }
+
+    async fn delete_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        let cmd = self
+            .config
+            .commands
+            .delete_file
+            .as_ref()
+            .ok_or_else(|| anyhow!("delete_file command not configured."))?;
+        self.cmd(cmd, vec![EnvVar::file_handle(file_handle.to_string())])
+            .spawn()?
+            .join()
+            .await
+            .err_notes(file_handle)
+    }
 }
@@ -0,0 +1,589 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    storage::{
+        BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
+        TextLine,
+    },
+    utils::error_notes::ErrorNotes,
+};
+use anyhow::{anyhow, bail, ensure, Result};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    HashValue, PrivateKey, Signature, SigningKey, ValidCryptoMaterialStringExt,
+};
+use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use async_trait::async_trait;
+use clap::Parser;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{self, Cursor, Read},
+    mem::size_of,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+
+#[derive(Clone, Default, Parser)]
+pub struct EnvelopeOpt {
+    #[clap(
+        long,
+        parse(from_os_str),
+        help = "File holding the hex encoded 32 byte key with which backup files are encrypted \
+        (and decrypted on restore). Each file is encrypted with a random data key, which is in \
+        turn encrypted with this key and stored along with the file."
+    )]
+    pub encryption_key_file: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "Compress backup files with zstd at the given level (1-22), before encryption."
+    )]
+    pub zstd_compression_level: Option<i32>,
+
+    #[clap(
+        long,
+        parse(from_os_str),
+        help = "File holding the hex encoded Ed25519 private key with which backup manifests are \
+        signed."
+    )]
+    pub manifest_signing_key_file: Option<PathBuf>,
+
+    #[clap(
+        long = "trusted-manifest-signer",
+        help = "Hex encoded Ed25519 public key trusted to sign backup manifests. Can be repeated. \
+        If set, every manifest read from the storage must carry a valid signature from one of \
+        these keys."
+    )]
+    pub trusted_manifest_signers: Vec<String>,
+}
+
+impl EnvelopeOpt {
+    pub fn is_enabled(&self) -> bool {
+        self.encryption_key_file.is_some()
+            || self.zstd_compression_level.is_some()
+            || self.manifest_signing_key_file.is_some()
+            || !self.trusted_manifest_signers.is_empty()
+    }
+}
+
+/// Identifies files written by the `EnvelopeStorage`. Files without it are passed through as is
+/// unless an encryption key is configured, so backups taken before the envelope was configured
+/// can still be restored.
+const MAGIC: &[u8; 8] = b"APTBKENV";
+
+const ENCRYPTION_KEY_LEN: usize = 32;
+
+/// Backup files are bounded by the max chunk size (128 MiB by default), so anything decompressing
+/// to more than this is rejected rather than exhausting memory.
+const MAX_DECOMPRESSED_LEN: u64 = 1 << 30;
+
+#[derive(Debug, Deserialize, Serialize)]
+enum Compression {
+    Zstd,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Encryption {
+    /// The random data key of this file, encrypted (and authenticated) with the configured key.
+    wrapped_data_key: Vec<u8>,
+    key_nonce: [u8; NONCE_LEN],
+    data_nonce: [u8; NONCE_LEN],
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ManifestSignature {
+    public_key: Ed25519PublicKey,
+    signature: Ed25519Signature,
+}
+
+/// What a manifest signature signs: the handle the manifest was written to, and the hash of the
+/// plain text manifest. Signing the handle prevents a signed manifest from being moved in place
+/// of another one.
+#[derive(CryptoHasher, BCSCryptoHash, Deserialize, Serialize)]
+struct SignedManifest {
+    file_handle: FileHandle,
+    manifest_hash: HashValue,
+}
+
+impl SignedManifest {
+    fn new(file_handle: &FileHandleRef, plain_text: &[u8]) -> Self {
+        Self {
+            file_handle: file_handle.to_string(),
+            manifest_hash: HashValue::sha3_256_of(plain_text),
+        }
+    }
+}
+
+/// Stored in front of the payload of each file, describing how to recover the plain text.
+#[derive(Debug, Deserialize, Serialize)]
+struct EnvelopeHeader {
+    compression: Option<Compression>,
+    encryption: Option<Encryption>,
+    signature: Option<ManifestSignature>,
+}
+
+/// Encrypts, compresses and signs the files written to the underlying storage, and reverses that
+/// on read. Files are sealed in memory, which is fine since backup files are bounded by the max
+/// chunk size.
+///
+/// File layout: `MAGIC | header length (u32, big endian) | BCS(EnvelopeHeader) | payload`, where
+/// the payload is the plain text, optionally compressed and then encrypted with AES-256-GCM. The
+/// file handle is authenticated along with the payload of encrypted files, and signed along with
+/// the hash of signed manifests, so neither can be moved to another handle. Files that are
+/// neither encrypted nor signed don't authenticate their handle.
+///
+/// Metadata lines are passed through as is, since they only hold backup ranges and file handles,
+/// and the metadata cache relies on them being plain text lines.
+pub struct EnvelopeStorage {
+    inner: Arc<dyn BackupStorage>,
+    envelope: Arc<Envelope>,
+}
+
+impl EnvelopeStorage {
+    pub fn new(
+        inner: Arc<dyn BackupStorage>,
+        encryption_key: Option<[u8; ENCRYPTION_KEY_LEN]>,
+        zstd_compression_level: Option<i32>,
+        manifest_signing_key: Option<Ed25519PrivateKey>,
+        trusted_manifest_signers: Vec<Ed25519PublicKey>,
+    ) -> Result<Self> {
+        if let Some(level) = zstd_compression_level {
+            ensure!(
+                (1..=22).contains(&level),
+                "Invalid zstd compression level: {}",
+                level
+            );
+        }
+        Ok(Self {
+            inner,
+            envelope: Arc::new(Envelope {
+                encryption_key: encryption_key.map(|key| aes_key(&key)).transpose()?,
+                zstd_compression_level,
+                manifest_signing_key,
+                trusted_manifest_signers,
+            }),
+        })
+    }
+
+    /// Wraps the storage according to the options, or returns it as is if nothing is configured.
+    pub async fn wrap_with_opt(
+        inner: Arc<dyn BackupStorage>,
+        opt: EnvelopeOpt,
+    ) -> Result<Arc<dyn BackupStorage>> {
+        if !opt.is_enabled() {
+            return Ok(inner);
+        }
+
+        let encryption_key = match &opt.encryption_key_file {
+            Some(path) => {
+                let key = hex::decode(read_key_file(path).await?)?;
+                ensure!(
+                    key.len() == ENCRYPTION_KEY_LEN,
+                    "Encryption key must be {} bytes, got {}.",
+                    ENCRYPTION_KEY_LEN,
+                    key.len()
+                );
+                let mut encryption_key = [0u8; ENCRYPTION_KEY_LEN];
+                encryption_key.copy_from_slice(&key);
+                Some(encryption_key)
+            },
+            None => None,
+        };
+        let manifest_signing_key = match &opt.manifest_signing_key_file {
+            Some(path) => Some(Ed25519PrivateKey::from_encoded_string(
+                &read_key_file(path).await?,
+            )?),
+            None => None,
+        };
+        let trusted_manifest_signers = opt
+            .trusted_manifest_signers
+            .iter()
+            .map(|key| Ed25519PublicKey::from_encoded_string(key))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Arc::new(Self::new(
+            inner,
+            encryption_key,
+            opt.zstd_compression_level,
+            manifest_signing_key,
+            trusted_manifest_signers,
+        )?))
+    }
+
+    async fn open_impl(
+        &self,
+        file_handle: &FileHandleRef,
+        is_manifest: bool,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        let mut sealed = Vec::new();
+        self.inner
+            .open_for_read(file_handle)
+            .await?
+            .read_to_end(&mut sealed)
+            .await
+            .err_notes(file_handle)?;
+        let plain_text = self
+            .envelope
+            .open(sealed, file_handle, is_manifest)
+            .err_notes(file_handle)?;
+        Ok(Box::new(Cursor::new(plain_text)))
+    }
+
+    async fn create_impl(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+        is_manifest: bool,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        let (file_handle, inner) = self.inner.create_for_write(backup_handle, name).await?;
+        Ok((
+            file_handle.clone(),
+            Box::new(EnvelopeWriter {
+                envelope: Arc::clone(&self.envelope),
+                file_handle,
+                is_manifest,
+                buffer: Vec::new(),
+                sealed: None,
+                inner,
+            }),
+        ))
+    }
+}
+
+/// The configured keys and compression, shared by the storage and its writers.
+struct Envelope {
+    encryption_key: Option<LessSafeKey>,
+    zstd_compression_level: Option<i32>,
+    manifest_signing_key: Option<Ed25519PrivateKey>,
+    trusted_manifest_signers: Vec<Ed25519PublicKey>,
+}
+
+impl Envelope {
+    fn seal(
+        &self,
+        plain_text: Vec<u8>,
+        file_handle: &FileHandleRef,
+        is_manifest: bool,
+    ) -> Result<Vec<u8>> {
+        let signature = match (&self.manifest_signing_key, is_manifest) {
+            (Some(signing_key), true) => Some(ManifestSignature {
+                public_key: signing_key.public_key(),
+                signature: signing_key.sign(&SignedManifest::new(file_handle, &plain_text))?,
+            }),
+            _ => None,
+        };
+
+        let mut payload = plain_text;
+        let compression = match self.zstd_compression_level {
+            Some(level) => {
+                payload = zstd::stream::encode_all(payload.as_slice(), level)?;
+                Some(Compression::Zstd)
+            },
+            None => None,
+        };
+        let encryption = match &self.encryption_key {
+            Some(encryption_key) => Some(encrypt(encryption_key, file_handle, &mut payload)?),
+            None => None,
+        };
+
+        let header = bcs::to_bytes(&EnvelopeHeader {
+            compression,
+            encryption,
+            signature,
+        })?;
+        let mut sealed =
+            Vec::with_capacity(MAGIC.len() + size_of::<u32>() + header.len() + payload.len());
+        sealed.extend(MAGIC);
+        sealed.extend((header.len() as u32).to_be_bytes());
+        sealed.extend(header);
+        sealed.extend(payload);
+        Ok(sealed)
+    }
+
+    fn open(
+        &self,
+        sealed: Vec<u8>,
+        file_handle: &FileHandleRef,
+        is_manifest: bool,
+    ) -> Result<Vec<u8>> {
+        if !sealed.starts_with(MAGIC) {
+            ensure!(
+                self.encryption_key.is_none(),
+                "File is not encrypted, but an encryption key is configured."
+            );
+            ensure!(
+                !(is_manifest && self.requires_manifest_signature()),
+                "Manifest is not signed, but trusted manifest signers are configured."
+            );
+            return Ok(sealed);
+        }
+
+        let rest = &sealed[MAGIC.len()..];
+        ensure!(rest.len() >= size_of::<u32>(), "Truncated envelope header.");
+        let (header_len, rest) = rest.split_at(size_of::<u32>());
+        let header_len = u32::from_be_bytes(header_len.try_into()?) as usize;
+        ensure!(rest.len() >= header_len, "Truncated envelope header.");
+        let (header, payload) = rest.split_at(header_len);
+        let header: EnvelopeHeader = bcs::from_bytes(header)?;
+
+        let mut payload = payload.to_vec();
+        match (&self.encryption_key, &header.encryption) {
+            (Some(encryption_key), Some(encryption)) => {
+                payload = decrypt(encryption_key, encryption, file_handle, payload)?;
+            },
+            (Some(_), None) => bail!("File is not encrypted, but an encryption key is configured."),
+            (None, Some(_)) => bail!("File is encrypted, but no encryption key is configured."),
+            (None, None) => (),
+        }
+        if let Some(Compression::Zstd) = header.compression {
+            payload = decompress(&payload)?;
+        }
+
+        if is_manifest && self.requires_manifest_signature() {
+            let signature = header
+                .signature
+                .as_ref()
+                .ok_or_else(|| anyhow!("Manifest is not signed."))?;
+            ensure!(
+                self.trusted_manifest_signers
+                    .contains(&signature.public_key),
+                "Manifest is signed by an untrusted key: {}",
+                signature.public_key,
+            );
+            signature
+                .signature
+                .verify(
+                    &SignedManifest::new(file_handle, &payload),
+                    &signature.public_key,
+                )
+                .map_err(|_| {
+                    anyhow!("Invalid manifest signature, was it moved or tampered with?")
+                })?;
+        }
+        Ok(payload)
+    }
+
+    fn requires_manifest_signature(&self) -> bool {
+        !self.trusted_manifest_signers.is_empty()
+    }
+}
+
+#[async_trait]
+impl BackupStorage for EnvelopeStorage {
+    async fn create_backup(&self, name: &ShellSafeName) -> Result<BackupHandle> {
+        self.inner.create_backup(name).await
+    }
+
+    async fn create_for_write(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        self.create_impl(backup_handle, name, false).await
+    }
+
+    async fn create_manifest_for_write(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        self.create_impl(backup_handle, name, true).await
+    }
+
+    async fn open_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        self.open_impl(file_handle, false).await
+    }
+
+    async fn open_manifest_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        self.open_impl(file_handle, true).await
+    }
+
+    async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()> {
+        self.inner.save_metadata_line(name, content).await
+    }
+
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()> {
+        self.inner.save_metadata_lines(name, lines).await
+    }
+
+    async fn list_metadata_files(&self) -> Result<Vec<FileHandle>> {
+        self.inner.list_metadata_files().await
+    }
+
+    async fn delete_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        self.inner.delete_file(file_handle).await
+    }
+}
+
+/// Buffers the content of a file, and seals and writes it to the underlying storage on shutdown.
+struct EnvelopeWriter {
+    envelope: Arc<Envelope>,
+    file_handle: FileHandle,
+    is_manifest: bool,
+    buffer: Vec<u8>,
+    /// The sealed file, and how much of it has been written. Set once shutdown starts.
+    sealed: Option<(Vec<u8>, usize)>,
+    inner: Box<dyn AsyncWrite + Send + Unpin>,
+}
+
+impl AsyncWrite for EnvelopeWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.sealed.is_some() {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Write after shutdown.",
+            )));
+        }
+        this.buffer.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Nothing is written until the file is sealed on shutdown.
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.sealed.is_none() {
+            let plain_text = std::mem::take(&mut this.buffer);
+            let sealed = this
+                .envelope
+                .seal(plain_text, &this.file_handle, this.is_manifest)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            this.sealed = Some((sealed, 0));
+        }
+
+        let (sealed, written) = this.sealed.as_mut().expect("Set above.");
+        while *written < sealed.len() {
+            let n = futures::ready!(Pin::new(&mut this.inner).poll_write(cx, &sealed[*written..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            *written += n;
+        }
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+async fn read_key_file(path: &Path) -> Result<String> {
+    Ok(tokio::fs::read_to_string(path)
+        .await
+        .err_notes(path)?
+        .trim()
+        .to_string())
+}
+
+fn aes_key(key: &[u8]) -> Result<LessSafeKey> {
+    Ok(LessSafeKey::new(
+        UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow!("Invalid AES-256 key."))?,
+    ))
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| anyhow!("Failed to generate random bytes."))?;
+    Ok(bytes)
+}
+
+/// The associated data authenticated along with the payload of the file.
+fn aad(file_handle: &FileHandleRef) -> Vec<u8> {
+    [MAGIC.as_slice(), file_handle.as_bytes()].concat()
+}
+
+fn decompress(payload: &[u8]) -> Result<Vec<u8>> {
+    let mut plain_text = Vec::new();
+    zstd::stream::read::Decoder::new(payload)?
+        .take(MAX_DECOMPRESSED_LEN + 1)
+        .read_to_end(&mut plain_text)?;
+    ensure!(
+        plain_text.len() as u64 <= MAX_DECOMPRESSED_LEN,
+        "File decompresses to more than {} bytes.",
+        MAX_DECOMPRESSED_LEN,
+    );
+    Ok(plain_text)
+}
+
+/// Encrypts the payload in place with a new random data key, and returns the wrapped data key.
+fn encrypt(
+    encryption_key: &LessSafeKey,
+    file_handle: &FileHandleRef,
+    payload: &mut Vec<u8>,
+) -> Result<Encryption> {
+    let data_key = random_bytes::<ENCRYPTION_KEY_LEN>()?;
+    let key_nonce = random_bytes::<NONCE_LEN>()?;
+    let data_nonce = random_bytes::<NONCE_LEN>()?;
+
+    aes_key(&data_key)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(data_nonce),
+            Aad::from(aad(file_handle)),
+            payload,
+        )
+        .map_err(|_| anyhow!("Failed to encrypt file."))?;
+    let mut wrapped_data_key = data_key.to_vec();
+    encryption_key
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(key_nonce),
+            Aad::empty(),
+            &mut wrapped_data_key,
+        )
+        .map_err(|_| anyhow!("Failed to encrypt data key."))?;
+
+    Ok(Encryption {
+        wrapped_data_key,
+        key_nonce,
+        data_nonce,
+    })
+}
+
+fn decrypt(
+    encryption_key: &LessSafeKey,
+    encryption: &Encryption,
+    file_handle: &FileHandleRef,
+    mut payload: Vec<u8>,
+) -> Result<Vec<u8>> {
+    let mut data_key = encryption.wrapped_data_key.clone();
+    let data_key = encryption_key
+        .open_in_place(
+            Nonce::assume_unique_for_key(encryption.key_nonce),
+            Aad::empty(),
+            &mut data_key,
+        )
+        .map_err(|_| anyhow!("Failed to decrypt data key, is the encryption key correct?"))?;
+
+    let plain_text_len = aes_key(data_key)?
+        .open_in_place(
+            Nonce::assume_unique_for_key(encryption.data_nonce),
+            Aad::from(aad(file_handle)),
+            &mut payload,
+        )
+        .map_err(|_| anyhow!("Failed to decrypt file, it's corrupted or tampered with."))?
+        .len();
+    payload.truncate(plain_text_len);
+    Ok(payload)
+}
@@ -0,0 +1,223 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::storage::{
+    local_fs::LocalFs,
+    test_util::{
+        arb_backups, arb_metadata_files, test_save_and_list_metadata_files_impl,
+        test_write_and_read_impl,
+    },
+};
+use aptos_crypto::Uniform;
+use aptos_temppath::TempPath;
+use proptest::prelude::*;
+use std::str::FromStr;
+use tokio::{io::AsyncWriteExt, runtime::Runtime};
+
+fn local_fs(tmpdir: &TempPath) -> Arc<dyn BackupStorage> {
+    Arc::new(LocalFs::new(tmpdir.path().to_path_buf()))
+}
+
+fn envelope_storage(
+    inner: Arc<dyn BackupStorage>,
+    encryption_key: Option<[u8; ENCRYPTION_KEY_LEN]>,
+    manifest_signing_key: Option<Ed25519PrivateKey>,
+    trusted_manifest_signers: Vec<Ed25519PublicKey>,
+) -> EnvelopeStorage {
+    EnvelopeStorage::new(
+        inner,
+        encryption_key,
+        Some(3), /* zstd_compression_level */
+        manifest_signing_key,
+        trusted_manifest_signers,
+    )
+    .unwrap()
+}
+
+async fn write_manifest(
+    storage: &dyn BackupStorage,
+    backup_name: &str,
+    content: &[u8],
+) -> FileHandle {
+    let backup_handle = storage
+        .create_backup(&ShellSafeName::from_str(backup_name).unwrap())
+        .await
+        .unwrap();
+    let (file_handle, mut file) = storage
+        .create_manifest_for_write(
+            &backup_handle,
+            &ShellSafeName::from_str("test.manifest").unwrap(),
+        )
+        .await
+        .unwrap();
+    file.write_all(content).await.unwrap();
+    file.shutdown().await.unwrap();
+    file_handle
+}
+
+async fn read_manifest(
+    storage: &dyn BackupStorage,
+    file_handle: &FileHandleRef,
+) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    storage
+        .open_manifest_for_read(file_handle)
+        .await?
+        .read_to_end(&mut content)
+        .await?;
+    Ok(content)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_write_and_read(
+        backups in arb_backups()
+    ) {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let store = envelope_storage(
+            local_fs(&tmpdir),
+            Some(random_bytes().unwrap()),
+            None, /* manifest_signing_key */
+            vec![], /* trusted_manifest_signers */
+        );
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_write_and_read_impl(Box::new(store), backups));
+    }
+
+    #[test]
+    fn test_save_list_metadata_files(
+        input in arb_metadata_files(),
+    ) {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let store = envelope_storage(
+            local_fs(&tmpdir),
+            Some(random_bytes().unwrap()),
+            None, /* manifest_signing_key */
+            vec![], /* trusted_manifest_signers */
+        );
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_save_and_list_metadata_files_impl(Box::new(store), input));
+    }
+}
+
+#[tokio::test]
+async fn test_encryption() {
+    let tmpdir = TempPath::new();
+    tmpdir.create_as_dir().unwrap();
+    let encryption_key = random_bytes().unwrap();
+    let store = envelope_storage(local_fs(&tmpdir), Some(encryption_key), None, vec![]);
+    let content = b"the quick brown fox jumps over the lazy dog";
+    let file_handle = write_manifest(&store, "encrypted", content).await;
+
+    // The stored file doesn't contain the plain text.
+    let stored = tokio::fs::read(tmpdir.path().join(&file_handle))
+        .await
+        .unwrap();
+    assert!(stored.starts_with(MAGIC));
+    assert!(!stored
+        .windows(content.len())
+        .any(|window| window == &content[..]));
+    assert_eq!(read_manifest(&store, &file_handle).await.unwrap(), content);
+
+    // A wrong key fails to decrypt.
+    let wrong_key_store = envelope_storage(
+        local_fs(&tmpdir),
+        Some(random_bytes().unwrap()),
+        None,
+        vec![],
+    );
+    read_manifest(&wrong_key_store, &file_handle)
+        .await
+        .unwrap_err();
+
+    // So does a missing key.
+    let no_key_store = envelope_storage(local_fs(&tmpdir), None, None, vec![]);
+    read_manifest(&no_key_store, &file_handle)
+        .await
+        .unwrap_err();
+
+    // Files not written through the envelope are rejected.
+    let plain = write_manifest(local_fs(&tmpdir).as_ref(), "plain", content).await;
+    read_manifest(&store, &plain).await.unwrap_err();
+
+    // So are encrypted files moved in place of another file.
+    let other = write_manifest(&store, "other", b"other content").await;
+    tokio::fs::copy(tmpdir.path().join(&other), tmpdir.path().join(&file_handle))
+        .await
+        .unwrap();
+    read_manifest(&store, &file_handle).await.unwrap_err();
+
+    // Tampering with the file is detected.
+    let mut tampered = stored.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    tokio::fs::write(tmpdir.path().join(&file_handle), tampered)
+        .await
+        .unwrap();
+    read_manifest(&store, &file_handle).await.unwrap_err();
+}
+
+#[tokio::test]
+async fn test_manifest_signatures() {
+    let tmpdir = TempPath::new();
+    tmpdir.create_as_dir().unwrap();
+    let signing_key = Ed25519PrivateKey::generate_for_testing();
+    let other_key = Ed25519PrivateKey::generate(&mut rand::rngs::OsRng);
+    let content = b"{}";
+
+    // Unsigned manifests, including ones written without the envelope, are rejected once trusted
+    // signers are configured.
+    let unsigned_store = envelope_storage(local_fs(&tmpdir), None, None, vec![]);
+    let unsigned = write_manifest(&unsigned_store, "unsigned", content).await;
+    let plain = write_manifest(local_fs(&tmpdir).as_ref(), "plain", content).await;
+    let verifying_store = envelope_storage(
+        local_fs(&tmpdir),
+        None,
+        None,
+        vec![signing_key.public_key()],
+    );
+    read_manifest(&verifying_store, &unsigned)
+        .await
+        .unwrap_err();
+    read_manifest(&verifying_store, &plain).await.unwrap_err();
+    // But can be read if no signature is required.
+    assert_eq!(
+        read_manifest(&unsigned_store, &plain).await.unwrap(),
+        content
+    );
+
+    // Manifests signed by a trusted key are accepted.
+    let signing_store = envelope_storage(local_fs(&tmpdir), None, Some(signing_key), vec![]);
+    let signed = write_manifest(&signing_store, "signed", content).await;
+    assert_eq!(
+        read_manifest(&verifying_store, &signed).await.unwrap(),
+        content
+    );
+
+    // Manifests signed by other keys are rejected.
+    let other_store = envelope_storage(local_fs(&tmpdir), None, Some(other_key), vec![]);
+    let other_signed = write_manifest(&other_store, "other_signed", content).await;
+    read_manifest(&verifying_store, &other_signed)
+        .await
+        .unwrap_err();
+
+    // So are signed manifests moved in place of another manifest.
+    let moved = write_manifest(&signing_store, "moved", b"{\"moved\": true}").await;
+    tokio::fs::copy(tmpdir.path().join(&moved), tmpdir.path().join(&signed))
+        .await
+        .unwrap();
+    read_manifest(&verifying_store, &signed).await.unwrap_err();
+    assert!(read_manifest(&verifying_store, &moved).await.is_ok());
+
+    // Non-manifest files don't require signatures.
+    let mut file = verifying_store.open_for_read(&unsigned).await.unwrap();
+    let mut read_back = Vec::new();
+    file.read_to_end(&mut read_back).await.unwrap();
+    assert_eq!(read_back, content);
+}
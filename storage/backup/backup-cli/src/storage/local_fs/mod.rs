@@ -6,7 +6,7 @@ mod tests;
 
 use super::{BackupHandle, BackupHandleRef, FileHandle, FileHandleRef};
 use crate::{
-    storage::{BackupStorage, ShellSafeName, TextLine},
+    storage::{envelope::EnvelopeOpt, BackupStorage, ShellSafeName, TextLine},
     utils::{error_notes::ErrorNotes, path_exists, PathToString},
 };
 use anyhow::Result;
@@ -14,7 +14,7 @@ use async_trait::async_trait;
 use clap::Parser;
 use std::path::{Path, PathBuf};
 use tokio::{
-    fs::{create_dir_all, read_dir, OpenOptions},
+    fs::{create_dir_all, read_dir, remove_file, OpenOptions},
     io::{AsyncRead, AsyncWrite, AsyncWriteExt},
 };
 
@@ -26,6 +26,9 @@ pub struct LocalFsOpt {
         help = "Target local dir to hold backups."
     )]
     pub dir: PathBuf,
+
+    #[clap(flatten)]
+    pub envelope: EnvelopeOpt,
 }
 
 /// A storage backend that stores everything in a local directory.
@@ -91,6 +94,11 @@ impl BackupStorage for LocalFs {
     }
 
     async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()> {
+        self.save_metadata_lines(name, std::slice::from_ref(content))
+            .await
+    }
+
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()> {
         let dir = self.metadata_dir();
         create_dir_all(&dir).await.err_notes(name)?; // in case not yet created
 
@@ -101,9 +109,11 @@ impl BackupStorage for LocalFs {
             .open(&path)
             .await
             .err_notes(&path)?;
-        file.write_all(content.as_ref().as_bytes())
-            .await
-            .err_notes(&path)?;
+        for line in lines {
+            file.write_all(line.as_ref().as_bytes())
+                .await
+                .err_notes(&path)?;
+        }
         file.shutdown().await.err_notes(&path)?;
 
         Ok(())
@@ -122,4 +132,10 @@ impl BackupStorage for LocalFs {
         }
         Ok(res)
     }
+
+    async fn delete_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        let path = self.dir.join(file_handle);
+        remove_file(&path).await.err_notes(&path)?;
+        Ok(())
+    }
 }
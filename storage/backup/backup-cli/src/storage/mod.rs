@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod command_adapter;
+pub mod envelope;
 pub mod local_fs;
 
 #[cfg(test)]
@@ -11,6 +12,7 @@ mod tests;
 
 use crate::storage::{
     command_adapter::{CommandAdapter, CommandAdapterOpt},
+    envelope::EnvelopeStorage,
     local_fs::{LocalFs, LocalFsOpt},
 };
 use anyhow::{ensure, Result};
@@ -146,11 +148,28 @@ pub trait BackupStorage: Send + Sync {
         backup_handle: &BackupHandleRef,
         name: &ShellSafeName,
     ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)>;
+    /// Same as `create_for_write`, but for the manifest of a backup. Storages that sign manifests
+    /// do so when the file is shut down.
+    async fn create_manifest_for_write(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        self.create_for_write(backup_handle, name).await
+    }
     /// Open file for reading.
     async fn open_for_read(
         &self,
         file_handle: &FileHandleRef,
     ) -> Result<Box<dyn AsyncRead + Send + Unpin>>;
+    /// Same as `open_for_read`, but for a manifest written by `create_manifest_for_write()`.
+    /// Storages that sign manifests verify the signature before returning the content.
+    async fn open_manifest_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        self.open_for_read(file_handle).await
+    }
     /// Asks to save a metadata entry. A metadata entry is one line of text.
     /// The backup system doesn't expect a metadata entry to exclusively map to a single file
     /// handle, or the same file handle when accessed later, so there's no need to return one. This
@@ -160,6 +179,9 @@ pub trait BackupStorage: Send + Sync {
     /// is straightforward and acceptable.
     /// See `list_metadata_files`.
     async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()>;
+    /// Same as `save_metadata_line`, but saves multiple metadata entries into a single file, which
+    /// is used to compact metadata files.
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()>;
     /// The backup system always asks for all metadata files and cache and build index on top of
     /// the content of them. This means:
     ///   1. The storage is free to reorganise the metadata files, like combining multiple ones to
@@ -167,6 +189,10 @@ pub trait BackupStorage: Send + Sync {
     ///   2. But the cache does expect the content stays the same for a file handle, so when
     /// reorganising metadata files, give them new unique names.
     async fn list_metadata_files(&self) -> Result<Vec<FileHandle>>;
+    /// Deletes a file, identified by either a file handle returned by `create_for_write()` or a
+    /// metadata file handle returned by `list_metadata_files()`. Only used to garbage collect
+    /// backups, so storages are free not to support it.
+    async fn delete_file(&self, file_handle: &FileHandleRef) -> Result<()>;
 }
 
 #[derive(Parser)]
@@ -175,8 +201,9 @@ pub enum StorageOpt {
     LocalFs(LocalFsOpt),
     #[clap(
         about = "Select the CommandAdapter backup storage type, which reads shell commands with which \
-    it communicates with either a local file system or a remote cloud storage. Compression or other \
-    fitlers can be added as part of the commands. See a sample config here: \
+    it communicates with either a local file system or a remote cloud storage. Other fitlers can be \
+    added as part of the commands, but prefer the builtin compression and encryption options. See a \
+    sample config here: \
     https://github.com/aptos-labs/aptos-core/tree/main/storage/backup/backup-cli/src/storage/command_adapter/sample_configs/"
     )]
     CommandAdapter(CommandAdapterOpt),
@@ -184,9 +211,19 @@ pub enum StorageOpt {
 
 impl StorageOpt {
     pub async fn init_storage(self) -> Result<Arc<dyn BackupStorage>> {
-        Ok(match self {
-            StorageOpt::LocalFs(opt) => Arc::new(LocalFs::new_with_opt(opt)),
-            StorageOpt::CommandAdapter(opt) => Arc::new(CommandAdapter::new_with_opt(opt).await?),
-        })
+        let (storage, envelope_opt): (Arc<dyn BackupStorage>, _) = match self {
+            StorageOpt::LocalFs(opt) => {
+                let envelope_opt = opt.envelope.clone();
+                (Arc::new(LocalFs::new_with_opt(opt)), envelope_opt)
+            },
+            StorageOpt::CommandAdapter(opt) => {
+                let envelope_opt = opt.envelope.clone();
+                (
+                    Arc::new(CommandAdapter::new_with_opt(opt).await?),
+                    envelope_opt,
+                )
+            },
+        };
+        EnvelopeStorage::wrap_with_opt(storage, envelope_opt).await
     }
 }
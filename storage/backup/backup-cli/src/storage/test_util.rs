@@ -97,6 +97,46 @@ pub async fn test_save_and_list_metadata_files_impl(
     assert_eq!(read_back, expected)
 }
 
+pub async fn test_compact_metadata_files_impl(
+    store: Box<dyn BackupStorage>,
+    input: Vec<(ShellSafeName, TextLine)>,
+) {
+    for (name, content) in &input {
+        store.save_metadata_line(name, content).await.unwrap();
+    }
+    let old_file_handles = store.list_metadata_files().await.unwrap();
+
+    let lines = input
+        .iter()
+        .map(|(_name, content)| TextLine::new(content.as_ref().trim_end()).unwrap())
+        .collect::<Vec<_>>();
+    store
+        .save_metadata_lines(&"compacted.meta".parse().unwrap(), &lines)
+        .await
+        .unwrap();
+    for file_handle in &old_file_handles {
+        store.delete_file(file_handle).await.unwrap();
+        store.open_for_read(file_handle).await.unwrap_err();
+    }
+
+    let file_handles = store.list_metadata_files().await.unwrap();
+    assert_eq!(file_handles.len(), 1);
+    let mut buf = String::new();
+    store
+        .open_for_read(&file_handles[0])
+        .await
+        .unwrap()
+        .read_to_string(&mut buf)
+        .await
+        .unwrap();
+    let read_back = buf
+        .lines()
+        .map(TextLine::new)
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(read_back, lines);
+}
+
 pub fn arb_metadata_files() -> impl Strategy<Value = Vec<(ShellSafeName, TextLine)>> {
     hash_map(any::<ShellSafeName>(), any::<TextLine>(), 0..10)
         .prop_map(HashMap::into_iter)
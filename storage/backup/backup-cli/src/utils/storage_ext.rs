@@ -14,6 +14,8 @@ pub trait BackupStorageExt {
     async fn read_all(&self, file_handle: &FileHandleRef) -> Result<Vec<u8>>;
     async fn load_json_file<T: DeserializeOwned>(&self, file_handle: &FileHandleRef) -> Result<T>;
     async fn load_bcs_file<T: DeserializeOwned>(&self, file_handle: &FileHandleRef) -> Result<T>;
+    /// Loads a JSON manifest written by `BackupStorage::create_manifest_for_write()`.
+    async fn load_manifest<T: DeserializeOwned>(&self, file_handle: &FileHandleRef) -> Result<T>;
     /// Adds a random suffix ".XXXX" to the backup name, so a retry won't pass a same backup name to
     /// the storage.
     async fn create_backup_with_random_suffix(&self, name: &str) -> Result<BackupHandle>;
@@ -36,6 +38,13 @@ impl BackupStorageExt for Arc<dyn BackupStorage> {
         Ok(serde_json::from_slice(&self.read_all(file_handle).await?)?)
     }
 
+    async fn load_manifest<T: DeserializeOwned>(&self, file_handle: &FileHandleRef) -> Result<T> {
+        let mut file = self.open_manifest_for_read(file_handle).await?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
     async fn create_backup_with_random_suffix(&self, name: &str) -> Result<BackupHandle> {
         self.create_backup(&format!("{}.{:04x}", name, random::<u16>()).try_into()?)
             .await
@@ -5,8 +5,8 @@ mod db;
 mod metadata;
 mod schema;
 
+pub use crate::db::INDEX_DB_NAME;
 use crate::{
-    db::INDEX_DB_NAME,
     metadata::{MetadataKey, MetadataValue},
     schema::{
         column_families, indexer_metadata::IndexerMetadataSchema, table_info::TableInfoSchema,
@@ -129,6 +129,27 @@ impl Indexer {
         Ok(())
     }
 
+    /// Moves the indexer back to `next_version` after the ledger has been truncated, so the
+    /// transactions from there on are indexed again. The table infos found in the truncated
+    /// transactions are kept, they are keyed by table handle and can't be reached anymore.
+    pub fn rewind(&self, next_version: Version) -> Result<()> {
+        if next_version >= self.next_version() {
+            return Ok(());
+        }
+        let batch = SchemaBatch::new();
+        match next_version.checked_sub(1) {
+            Some(version) => batch.put::<IndexerMetadataSchema>(
+                &MetadataKey::LatestVersion,
+                &MetadataValue::Version(version),
+            )?,
+            None => batch.delete::<IndexerMetadataSchema>(&MetadataKey::LatestVersion)?,
+        }
+        self.db.write_schemas(batch)?;
+        self.next_version.store(next_version, Ordering::Relaxed);
+
+        Ok(())
+    }
+
     pub fn next_version(&self) -> Version {
         self.next_version.load(Ordering::Relaxed)
     }
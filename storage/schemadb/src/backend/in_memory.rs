@@ -0,0 +1,218 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backend::{Backend, RawIterator},
+    ColumnFamilyName, ReadOptions, WriteOp,
+};
+use anyhow::{ensure, format_err, Result};
+use aptos_infallible::RwLock;
+use im::OrdMap;
+use std::{collections::HashMap, ops::Bound, path::Path};
+
+type ColumnFamily = OrdMap<Vec<u8>, Vec<u8>>;
+
+/// [`Backend`] that keeps each column family in a persistent ordered map.
+///
+/// Cloning a column family is O(1) and writes only copy the nodes on the path to the keys they
+/// touch, so reads and iterators work on a cheap snapshot taken at creation, same as RocksDB
+/// iterators do, without making concurrent writes copy the whole column family. Since keys are
+/// always iterated in total order, prefix related `ReadOptions` like `set_prefix_same_as_start`
+/// have no effect.
+#[derive(Debug)]
+pub struct InMemoryBackend {
+    column_families: RwLock<HashMap<ColumnFamilyName, ColumnFamily>>,
+}
+
+impl InMemoryBackend {
+    pub fn new(column_families: Vec<ColumnFamilyName>) -> Self {
+        Self {
+            column_families: RwLock::new(
+                column_families
+                    .into_iter()
+                    .map(|cf_name| (cf_name, ColumnFamily::new()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn get_cf(&self, cf_name: &str) -> Result<ColumnFamily> {
+        self.column_families
+            .read()
+            .get(cf_name)
+            .cloned()
+            .ok_or_else(|| cf_not_found(cf_name))
+    }
+}
+
+impl Backend for InMemoryBackend {
+    fn get(&self, cf_name: ColumnFamilyName, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.get_cf(cf_name)?.get(key).cloned())
+    }
+
+    fn multi_get(
+        &self,
+        cf_name: ColumnFamilyName,
+        keys: &[Vec<u8>],
+    ) -> Result<Vec<Option<Vec<u8>>>> {
+        let cf = self.get_cf(cf_name)?;
+        Ok(keys.iter().map(|key| cf.get(key).cloned()).collect())
+    }
+
+    fn raw_iter(
+        &self,
+        cf_name: ColumnFamilyName,
+        _opts: ReadOptions,
+    ) -> Result<Box<dyn RawIterator + '_>> {
+        Ok(Box::new(InMemoryIterator {
+            cf: self.get_cf(cf_name)?,
+            current: None,
+        }))
+    }
+
+    fn write(&self, rows: &HashMap<ColumnFamilyName, Vec<WriteOp>>) -> Result<usize> {
+        let mut column_families = self.column_families.write();
+        for cf_name in rows.keys() {
+            ensure!(column_families.contains_key(cf_name), cf_not_found(cf_name));
+        }
+
+        let mut size = 0;
+        for (cf_name, rows) in rows.iter() {
+            let cf = column_families
+                .get_mut(cf_name)
+                .expect("Checked to exist above.");
+            for write_op in rows {
+                match write_op {
+                    WriteOp::Value { key, value } => {
+                        size += key.len() + value.len();
+                        cf.insert(key.clone(), value.clone());
+                    },
+                    WriteOp::Deletion { key } => {
+                        size += key.len();
+                        cf.remove(key);
+                    },
+                }
+            }
+        }
+        Ok(size)
+    }
+
+    fn flush_cf(&self, cf_name: &str) -> Result<()> {
+        self.get_cf(cf_name).map(|_| ())
+    }
+
+    fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64> {
+        let cf = self.get_cf(cf_name)?;
+        Ok(match property_name {
+            "rocksdb.estimate-num-keys" => cf.len() as u64,
+            "rocksdb.estimate-live-data-size" | "rocksdb.total-sst-files-size" => cf
+                .iter()
+                .map(|(key, value)| (key.len() + value.len()) as u64)
+                .sum(),
+            // Nothing like memtables, compactions or snapshots exists in memory.
+            _ => 0,
+        })
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        ensure!(!path.exists(), "Checkpoint path {:?} already exists.", path);
+
+        // Snapshot all column families first, so the checkpoint is consistent.
+        let column_families = self.column_families.read().clone();
+        let mut db_opts = rocksdb::Options::default();
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+        let db = rocksdb::DB::open_cf(&db_opts, path, column_families.keys())?;
+
+        let mut db_batch = rocksdb::WriteBatch::default();
+        for (cf_name, cf) in column_families.iter() {
+            let cf_handle = db.cf_handle(cf_name).ok_or_else(|| cf_not_found(cf_name))?;
+            for (key, value) in cf.iter() {
+                db_batch.put_cf(cf_handle, key, value);
+            }
+        }
+        db.write(db_batch)?;
+        db.flush()?;
+        Ok(())
+    }
+}
+
+fn cf_not_found(cf_name: &str) -> anyhow::Error {
+    format_err!(
+        "DB::cf_handle not found for column family name: {}",
+        cf_name
+    )
+}
+
+/// Iterator over a snapshot of a column family, which remembers the current entry and navigates
+/// from there with range queries.
+struct InMemoryIterator {
+    cf: ColumnFamily,
+    current: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+fn to_owned_entry(entry: Option<(&Vec<u8>, &Vec<u8>)>) -> Option<(Vec<u8>, Vec<u8>)> {
+    entry.map(|(key, value)| (key.clone(), value.clone()))
+}
+
+impl RawIterator for InMemoryIterator {
+    fn seek_to_first(&mut self) {
+        self.current = to_owned_entry(self.cf.iter().next());
+    }
+
+    fn seek_to_last(&mut self) {
+        self.current = to_owned_entry(self.cf.iter().next_back());
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        self.current = to_owned_entry(
+            self.cf
+                .range::<[u8], _>((Bound::Included(key), Bound::Unbounded))
+                .next(),
+        );
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) {
+        self.current = to_owned_entry(
+            self.cf
+                .range::<[u8], _>((Bound::Unbounded, Bound::Included(key)))
+                .next_back(),
+        );
+    }
+
+    fn valid(&self) -> bool {
+        self.current.is_some()
+    }
+
+    fn status(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn key(&self) -> Option<&[u8]> {
+        self.current.as_ref().map(|(key, _)| key.as_slice())
+    }
+
+    fn value(&self) -> Option<&[u8]> {
+        self.current.as_ref().map(|(_, value)| value.as_slice())
+    }
+
+    fn next(&mut self) {
+        if let Some((key, _)) = self.current.take() {
+            self.current = to_owned_entry(
+                self.cf
+                    .range::<[u8], _>((Bound::Excluded(key.as_slice()), Bound::Unbounded))
+                    .next(),
+            );
+        }
+    }
+
+    fn prev(&mut self) {
+        if let Some((key, _)) = self.current.take() {
+            self.current = to_owned_entry(
+                self.cf
+                    .range::<[u8], _>((Bound::Unbounded, Bound::Excluded(key.as_slice())))
+                    .next_back(),
+            );
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines the [`Backend`] trait, which abstracts the key-value store underneath a
+//! [`DB`](crate::DB), and its implementations:
+//!
+//!   * [`RocksDbBackend`], used in production, and
+//!   * [`InMemoryBackend`], which keeps everything in memory and is meant for tests and
+//! benchmarks that don't want to pay for disk I/O.
+
+mod in_memory;
+mod rocks_db;
+
+use crate::{ColumnFamilyName, ReadOptions, WriteOp};
+use anyhow::Result;
+pub use in_memory::InMemoryBackend;
+pub use rocks_db::RocksDbBackend;
+use std::{collections::HashMap, fmt::Debug, path::Path};
+
+/// Raw key-value store that holds the column families of a [`DB`](crate::DB). Keys and values
+/// are already encoded by the schemas, and keys are ordered bytewise in each column family.
+pub trait Backend: Debug + Send + Sync {
+    /// Reads a single value by its raw key.
+    fn get(&self, cf_name: ColumnFamilyName, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// Reads multiple values by their raw keys in one batch, results are in the same order as
+    /// `keys`.
+    fn multi_get(
+        &self,
+        cf_name: ColumnFamilyName,
+        keys: &[Vec<u8>],
+    ) -> Result<Vec<Option<Vec<u8>>>>;
+
+    /// Returns an iterator over a column family, which is not positioned until seeked.
+    fn raw_iter(
+        &self,
+        cf_name: ColumnFamilyName,
+        opts: ReadOptions,
+    ) -> Result<Box<dyn RawIterator + '_>>;
+
+    /// Applies the write operations to the column families atomically and durably, and returns
+    /// the size of the written data in bytes.
+    fn write(&self, rows: &HashMap<ColumnFamilyName, Vec<WriteOp>>) -> Result<usize>;
+
+    /// Flushes buffered writes of a column family, if the backend buffers any.
+    fn flush_cf(&self, cf_name: &str) -> Result<()>;
+
+    /// Gets a RocksDB integer property of a column family, like "rocksdb.estimate-num-keys".
+    fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64>;
+
+    /// Creates a RocksDB checkpoint of the whole DB at `path`, which must not exist yet.
+    fn create_checkpoint(&self, path: &Path) -> Result<()>;
+}
+
+/// Mirrors `rocksdb::DBRawIterator`, which [`SchemaIterator`](crate::iterator::SchemaIterator)
+/// is built upon.
+pub trait RawIterator {
+    /// Seeks to the first key.
+    fn seek_to_first(&mut self);
+
+    /// Seeks to the last key.
+    fn seek_to_last(&mut self);
+
+    /// Seeks to the first key that is equal to or greater than `key`.
+    fn seek(&mut self, key: &[u8]);
+
+    /// Seeks to the last key that is less than or equal to `key`.
+    fn seek_for_prev(&mut self, key: &[u8]);
+
+    /// Returns true if the iterator is positioned at a key.
+    fn valid(&self) -> bool;
+
+    /// Returns the error the iterator ran into, if any.
+    fn status(&self) -> Result<()>;
+
+    /// Returns the current key, `None` if not `valid()`.
+    fn key(&self) -> Option<&[u8]>;
+
+    /// Returns the current value, `None` if not `valid()`.
+    fn value(&self) -> Option<&[u8]>;
+
+    /// Moves to the next key.
+    fn next(&mut self);
+
+    /// Moves to the previous key.
+    fn prev(&mut self);
+}
@@ -0,0 +1,147 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backend::{Backend, RawIterator},
+    ColumnFamilyName, ReadOptions, WriteOp,
+};
+use anyhow::{format_err, Result};
+use std::{collections::HashMap, path::Path};
+
+/// [`Backend`] on top of a RocksDB instance.
+#[derive(Debug)]
+pub struct RocksDbBackend {
+    inner: rocksdb::DB,
+}
+
+impl RocksDbBackend {
+    pub fn new(inner: rocksdb::DB) -> Self {
+        Self { inner }
+    }
+
+    fn get_cf_handle(&self, cf_name: &str) -> Result<&rocksdb::ColumnFamily> {
+        self.inner.cf_handle(cf_name).ok_or_else(|| {
+            format_err!(
+                "DB::cf_handle not found for column family name: {}",
+                cf_name
+            )
+        })
+    }
+}
+
+impl Backend for RocksDbBackend {
+    fn get(&self, cf_name: ColumnFamilyName, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.inner.get_cf(self.get_cf_handle(cf_name)?, key)?)
+    }
+
+    fn multi_get(
+        &self,
+        cf_name: ColumnFamilyName,
+        keys: &[Vec<u8>],
+    ) -> Result<Vec<Option<Vec<u8>>>> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        self.inner
+            .multi_get_cf(keys.iter().map(|key| (cf_handle, key)))
+            .into_iter()
+            .map(|res| res.map_err(Into::into))
+            .collect()
+    }
+
+    fn raw_iter(
+        &self,
+        cf_name: ColumnFamilyName,
+        opts: ReadOptions,
+    ) -> Result<Box<dyn RawIterator + '_>> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        Ok(Box::new(self.inner.raw_iterator_cf_opt(cf_handle, opts)))
+    }
+
+    fn write(&self, rows: &HashMap<ColumnFamilyName, Vec<WriteOp>>) -> Result<usize> {
+        let mut db_batch = rocksdb::WriteBatch::default();
+        for (cf_name, rows) in rows.iter() {
+            let cf_handle = self.get_cf_handle(cf_name)?;
+            for write_op in rows {
+                match write_op {
+                    WriteOp::Value { key, value } => db_batch.put_cf(cf_handle, key, value),
+                    WriteOp::Deletion { key } => db_batch.delete_cf(cf_handle, key),
+                }
+            }
+        }
+        let serialized_size = db_batch.size_in_bytes();
+
+        self.inner.write_opt(db_batch, &default_write_options())?;
+        Ok(serialized_size)
+    }
+
+    fn flush_cf(&self, cf_name: &str) -> Result<()> {
+        Ok(self.inner.flush_cf(self.get_cf_handle(cf_name)?)?)
+    }
+
+    fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64> {
+        self.inner
+            .property_int_value_cf(self.get_cf_handle(cf_name)?, property_name)?
+            .ok_or_else(|| {
+                format_err!(
+                    "Unable to get property \"{}\" of  column family \"{}\".",
+                    property_name,
+                    cf_name,
+                )
+            })
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        rocksdb::checkpoint::Checkpoint::new(&self.inner)?.create_checkpoint(path)?;
+        Ok(())
+    }
+}
+
+impl<'a> RawIterator for rocksdb::DBRawIterator<'a> {
+    fn seek_to_first(&mut self) {
+        rocksdb::DBRawIterator::seek_to_first(self)
+    }
+
+    fn seek_to_last(&mut self) {
+        rocksdb::DBRawIterator::seek_to_last(self)
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        rocksdb::DBRawIterator::seek(self, key)
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) {
+        rocksdb::DBRawIterator::seek_for_prev(self, key)
+    }
+
+    fn valid(&self) -> bool {
+        rocksdb::DBRawIterator::valid(self)
+    }
+
+    fn status(&self) -> Result<()> {
+        Ok(rocksdb::DBRawIterator::status(self)?)
+    }
+
+    fn key(&self) -> Option<&[u8]> {
+        rocksdb::DBRawIterator::key(self)
+    }
+
+    fn value(&self) -> Option<&[u8]> {
+        rocksdb::DBRawIterator::value(self)
+    }
+
+    fn next(&mut self) {
+        rocksdb::DBRawIterator::next(self)
+    }
+
+    fn prev(&mut self) {
+        rocksdb::DBRawIterator::prev(self)
+    }
+}
+
+/// For now we always use synchronous writes. This makes sure that once the operation returns
+/// `Ok(())` the data is persisted even if the machine crashes. In the future we might consider
+/// selectively turning this off for some non-critical writes to improve performance.
+fn default_write_options() -> rocksdb::WriteOptions {
+    let mut opts = rocksdb::WriteOptions::default();
+    opts.set_sync(true);
+    opts
+}
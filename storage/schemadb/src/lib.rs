@@ -12,22 +12,28 @@
 //! families.  To use this library to store a kind of key-value pairs, the user needs to use the
 //! [`define_schema!`] macro to define the schema name, the types of key and value, and name of the
 //! column family.
+//!
+//! RocksDB can be swapped out for a pure in-memory store with [`DB::open_in_memory`], see
+//! [`backend`].
 
 mod metrics;
 #[macro_use]
 pub mod schema;
+pub mod backend;
 pub mod iterator;
 
 use crate::{
+    backend::{Backend, InMemoryBackend, RocksDbBackend},
     metrics::{
         APTOS_SCHEMADB_BATCH_COMMIT_BYTES, APTOS_SCHEMADB_BATCH_COMMIT_LATENCY_SECONDS,
         APTOS_SCHEMADB_BATCH_PUT_LATENCY_SECONDS, APTOS_SCHEMADB_DELETES, APTOS_SCHEMADB_GET_BYTES,
         APTOS_SCHEMADB_GET_LATENCY_SECONDS, APTOS_SCHEMADB_ITER_BYTES,
-        APTOS_SCHEMADB_ITER_LATENCY_SECONDS, APTOS_SCHEMADB_PUT_BYTES,
+        APTOS_SCHEMADB_ITER_LATENCY_SECONDS, APTOS_SCHEMADB_MULTI_GET_KEYS,
+        APTOS_SCHEMADB_MULTI_GET_LATENCY_SECONDS, APTOS_SCHEMADB_PUT_BYTES,
     },
     schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec},
 };
-use anyhow::{format_err, Result};
+use anyhow::Result;
 use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
 use iterator::{ScanDirection, SchemaIterator};
@@ -40,8 +46,9 @@ use std::{collections::HashMap, iter::Iterator, path::Path};
 
 pub type ColumnFamilyName = &'static str;
 
+/// A single write operation in a [`SchemaBatch`], with the key and value already encoded.
 #[derive(Debug)]
-enum WriteOp {
+pub enum WriteOp {
     Value { key: Vec<u8>, value: Vec<u8> },
     Deletion { key: Vec<u8> },
 }
@@ -96,12 +103,12 @@ impl SchemaBatch {
     }
 }
 
-/// This DB is a schematized RocksDB wrapper where all data passed in and out are typed according to
-/// [`Schema`]s.
+/// This DB is a schematized wrapper of a key-value [`Backend`], RocksDB unless opened in memory,
+/// where all data passed in and out are typed according to [`Schema`]s.
 #[derive(Debug)]
 pub struct DB {
     name: &'static str, // for logging
-    inner: rocksdb::DB,
+    inner: Box<dyn Backend>,
 }
 
 impl DB {
@@ -163,9 +170,22 @@ impl DB {
         Ok(Self::log_construct(name, inner))
     }
 
+    /// Opens a DB that lives in memory only, with the same semantics as a RocksDB backed one,
+    /// except that prefix related `ReadOptions` are ignored and iteration is always in total order.
+    pub fn open_in_memory(name: &'static str, column_families: Vec<ColumnFamilyName>) -> DB {
+        info!(db_name = name, "Opened in-memory DB.");
+        DB {
+            name,
+            inner: Box::new(InMemoryBackend::new(column_families)),
+        }
+    }
+
     fn log_construct(name: &'static str, inner: rocksdb::DB) -> DB {
         info!(rocksdb_name = name, "Opened RocksDB.");
-        DB { name, inner }
+        DB {
+            name,
+            inner: Box::new(RocksDbBackend::new(inner)),
+        }
     }
 
     /// Reads single record by key.
@@ -175,9 +195,8 @@ impl DB {
             .start_timer();
 
         let k = <S::Key as KeyCodec<S>>::encode_key(schema_key)?;
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
 
-        let result = self.inner.get_cf(cf_handle, k)?;
+        let result = self.inner.get(S::COLUMN_FAMILY_NAME, &k)?;
         APTOS_SCHEMADB_GET_BYTES
             .with_label_values(&[S::COLUMN_FAMILY_NAME])
             .observe(result.as_ref().map_or(0.0, |v| v.len() as f64));
@@ -187,6 +206,35 @@ impl DB {
             .transpose()
     }
 
+    /// Reads multiple records by keys in one batch, which is cheaper than calling [`DB::get`] for
+    /// each of them. Results are in the same order as `schema_keys`.
+    pub fn multi_get<S: Schema>(&self, schema_keys: &[S::Key]) -> Result<Vec<Option<S::Value>>> {
+        let _timer = APTOS_SCHEMADB_MULTI_GET_LATENCY_SECONDS
+            .with_label_values(&[S::COLUMN_FAMILY_NAME])
+            .start_timer();
+        APTOS_SCHEMADB_MULTI_GET_KEYS
+            .with_label_values(&[S::COLUMN_FAMILY_NAME])
+            .observe(schema_keys.len() as f64);
+
+        let keys = schema_keys
+            .iter()
+            .map(<S::Key as KeyCodec<S>>::encode_key)
+            .collect::<Result<Vec<_>>>()?;
+
+        self.inner
+            .multi_get(S::COLUMN_FAMILY_NAME, &keys)?
+            .into_iter()
+            .map(|result| {
+                APTOS_SCHEMADB_GET_BYTES
+                    .with_label_values(&[S::COLUMN_FAMILY_NAME])
+                    .observe(result.as_ref().map_or(0.0, |v| v.len() as f64));
+                result
+                    .map(|raw_value| <S::Value as ValueCodec<S>>::decode_value(&raw_value))
+                    .transpose()
+            })
+            .collect()
+    }
+
     /// Writes single record.
     pub fn put<S: Schema>(&self, key: &S::Key, value: &S::Value) -> Result<()> {
         // Not necessary to use a batch, but we'd like a central place to bump counters.
@@ -201,9 +249,8 @@ impl DB {
         opts: ReadOptions,
         direction: ScanDirection,
     ) -> Result<SchemaIterator<S>> {
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
         Ok(SchemaIterator::new(
-            self.inner.raw_iterator_cf_opt(cf_handle, opts),
+            self.inner.raw_iter(S::COLUMN_FAMILY_NAME, opts)?,
             direction,
         ))
     }
@@ -225,19 +272,7 @@ impl DB {
             .start_timer();
         let rows_locked = batch.rows.lock();
 
-        let mut db_batch = rocksdb::WriteBatch::default();
-        for (cf_name, rows) in rows_locked.iter() {
-            let cf_handle = self.get_cf_handle(cf_name)?;
-            for write_op in rows {
-                match write_op {
-                    WriteOp::Value { key, value } => db_batch.put_cf(cf_handle, key, value),
-                    WriteOp::Deletion { key } => db_batch.delete_cf(cf_handle, key),
-                }
-            }
-        }
-        let serialized_size = db_batch.size_in_bytes();
-
-        self.inner.write_opt(db_batch, &default_write_options())?;
+        let serialized_size = self.inner.write(&rows_locked)?;
 
         // Bump counters only after DB write succeeds.
         for (cf_name, rows) in rows_locked.iter() {
@@ -261,45 +296,18 @@ impl DB {
         Ok(())
     }
 
-    fn get_cf_handle(&self, cf_name: &str) -> Result<&rocksdb::ColumnFamily> {
-        self.inner.cf_handle(cf_name).ok_or_else(|| {
-            format_err!(
-                "DB::cf_handle not found for column family name: {}",
-                cf_name
-            )
-        })
-    }
-
     /// Flushes memtable data. This is only used for testing `get_approximate_sizes_cf` in unit
     /// tests.
     pub fn flush_cf(&self, cf_name: &str) -> Result<()> {
-        Ok(self.inner.flush_cf(self.get_cf_handle(cf_name)?)?)
+        self.inner.flush_cf(cf_name)
     }
 
     pub fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64> {
-        self.inner
-            .property_int_value_cf(self.get_cf_handle(cf_name)?, property_name)?
-            .ok_or_else(|| {
-                format_err!(
-                    "Unable to get property \"{}\" of  column family \"{}\".",
-                    property_name,
-                    cf_name,
-                )
-            })
+        self.inner.get_property(cf_name, property_name)
     }
 
     /// Creates new physical DB checkpoint in directory specified by `path`.
     pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        rocksdb::checkpoint::Checkpoint::new(&self.inner)?.create_checkpoint(path)?;
-        Ok(())
+        self.inner.create_checkpoint(path.as_ref())
     }
 }
-
-/// For now we always use synchronous writes. This makes sure that once the operation returns
-/// `Ok(())` the data is persisted even if the machine crashes. In the future we might consider
-/// selectively turning this off for some non-critical writes to improve performance.
-fn default_write_options() -> rocksdb::WriteOptions {
-    let mut opts = rocksdb::WriteOptions::default();
-    opts.set_sync(true);
-    opts
-}
@@ -57,6 +57,32 @@ pub static APTOS_SCHEMADB_GET_BYTES: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static APTOS_SCHEMADB_MULTI_GET_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        // metric name
+        "aptos_schemadb_multi_get_latency_seconds",
+        // metric description
+        "Aptos schemadb multi_get latency in seconds",
+        // metric labels (dimensions)
+        &["cf_name"],
+        exponential_buckets(/*start=*/ 1e-6, /*factor=*/ 2.0, /*count=*/ 22).unwrap(),
+    )
+    .unwrap()
+});
+
+pub static APTOS_SCHEMADB_MULTI_GET_KEYS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        // metric name
+        "aptos_schemadb_multi_get_keys",
+        // metric description
+        "Aptos schemadb number of keys per multi_get call",
+        // metric labels (dimensions)
+        &["cf_name"],
+        exponential_buckets(/*start=*/ 1.0, /*factor=*/ 2.0, /*count=*/ 16).unwrap(),
+    )
+    .unwrap()
+});
+
 pub static APTOS_SCHEMADB_BATCH_COMMIT_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         // metric name
@@ -108,7 +108,7 @@ fn open_db_as_secondary(dir: &aptos_temppath::TempPath, dir_sec: &aptos_temppath
 }
 
 struct TestDB {
-    _tmpdir: aptos_temppath::TempPath,
+    _tmpdir: Option<aptos_temppath::TempPath>,
     db: DB,
 }
 
@@ -118,10 +118,22 @@ impl TestDB {
         let db = open_db(&tmpdir);
 
         TestDB {
-            _tmpdir: tmpdir,
+            _tmpdir: Some(tmpdir),
             db,
         }
     }
+
+    fn new_in_memory() -> Self {
+        TestDB {
+            _tmpdir: None,
+            db: DB::open_in_memory("test", get_column_families()),
+        }
+    }
+
+    /// Returns an empty DB for each backend.
+    fn all() -> Vec<Self> {
+        vec![Self::new(), Self::new_in_memory()]
+    }
 }
 
 impl std::ops::Deref for TestDB {
@@ -134,42 +146,63 @@ impl std::ops::Deref for TestDB {
 
 #[test]
 fn test_schema_put_get() {
-    let db = TestDB::new();
+    for db in TestDB::all() {
+        db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+        db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
+        db.put::<TestSchema1>(&TestField(2), &TestField(2)).unwrap();
+        db.put::<TestSchema2>(&TestField(2), &TestField(3)).unwrap();
+        db.put::<TestSchema2>(&TestField(3), &TestField(4)).unwrap();
+        db.put::<TestSchema2>(&TestField(4), &TestField(5)).unwrap();
 
-    db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
-    db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
-    db.put::<TestSchema1>(&TestField(2), &TestField(2)).unwrap();
-    db.put::<TestSchema2>(&TestField(2), &TestField(3)).unwrap();
-    db.put::<TestSchema2>(&TestField(3), &TestField(4)).unwrap();
-    db.put::<TestSchema2>(&TestField(4), &TestField(5)).unwrap();
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(0)).unwrap(),
+            Some(TestField(0)),
+        );
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(1)).unwrap(),
+            Some(TestField(1)),
+        );
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(2)).unwrap(),
+            Some(TestField(2)),
+        );
+        assert_eq!(db.get::<TestSchema1>(&TestField(3)).unwrap(), None);
 
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(0)).unwrap(),
-        Some(TestField(0)),
-    );
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(1)).unwrap(),
-        Some(TestField(1)),
-    );
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(2)).unwrap(),
-        Some(TestField(2)),
-    );
-    assert_eq!(db.get::<TestSchema1>(&TestField(3)).unwrap(), None);
+        assert_eq!(db.get::<TestSchema2>(&TestField(1)).unwrap(), None);
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(2)).unwrap(),
+            Some(TestField(3)),
+        );
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(3)).unwrap(),
+            Some(TestField(4)),
+        );
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(4)).unwrap(),
+            Some(TestField(5)),
+        );
+    }
+}
 
-    assert_eq!(db.get::<TestSchema2>(&TestField(1)).unwrap(), None);
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(2)).unwrap(),
-        Some(TestField(3)),
-    );
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(3)).unwrap(),
-        Some(TestField(4)),
-    );
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(4)).unwrap(),
-        Some(TestField(5)),
-    );
+#[test]
+fn test_schema_multi_get() {
+    for db in TestDB::all() {
+        db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+        db.put::<TestSchema1>(&TestField(2), &TestField(2)).unwrap();
+        db.put::<TestSchema2>(&TestField(1), &TestField(3)).unwrap();
+
+        assert_eq!(
+            db.multi_get::<TestSchema1>(&[TestField(2), TestField(1), TestField(0)])
+                .unwrap(),
+            vec![Some(TestField(2)), None, Some(TestField(0))],
+        );
+        assert_eq!(
+            db.multi_get::<TestSchema2>(&[TestField(1), TestField(1)])
+                .unwrap(),
+            vec![Some(TestField(3)), Some(TestField(3))],
+        );
+        assert!(db.multi_get::<TestSchema2>(&[]).unwrap().is_empty());
+    }
 }
 
 fn collect_values<S: Schema>(db: &TestDB) -> Vec<(S::Key, S::Value)> {
@@ -190,85 +223,85 @@ fn gen_expected_values(values: &[(u32, u32)]) -> Vec<(TestField, TestField)> {
 
 #[test]
 fn test_single_schema_batch() {
-    let db = TestDB::new();
-
-    let db_batch = SchemaBatch::new();
-    db_batch
-        .put::<TestSchema1>(&TestField(0), &TestField(0))
-        .unwrap();
-    db_batch
-        .put::<TestSchema1>(&TestField(1), &TestField(1))
-        .unwrap();
-    db_batch
-        .put::<TestSchema1>(&TestField(2), &TestField(2))
-        .unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(3), &TestField(3))
-        .unwrap();
-    db_batch.delete::<TestSchema2>(&TestField(4)).unwrap();
-    db_batch.delete::<TestSchema2>(&TestField(3)).unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(4), &TestField(4))
-        .unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(5), &TestField(5))
-        .unwrap();
-
-    db.write_schemas(db_batch).unwrap();
+    for db in TestDB::all() {
+        let db_batch = SchemaBatch::new();
+        db_batch
+            .put::<TestSchema1>(&TestField(0), &TestField(0))
+            .unwrap();
+        db_batch
+            .put::<TestSchema1>(&TestField(1), &TestField(1))
+            .unwrap();
+        db_batch
+            .put::<TestSchema1>(&TestField(2), &TestField(2))
+            .unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(3), &TestField(3))
+            .unwrap();
+        db_batch.delete::<TestSchema2>(&TestField(4)).unwrap();
+        db_batch.delete::<TestSchema2>(&TestField(3)).unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(4), &TestField(4))
+            .unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(5), &TestField(5))
+            .unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1), (2, 2)]),
-    );
-    assert_eq!(
-        collect_values::<TestSchema2>(&db),
-        gen_expected_values(&[(4, 4), (5, 5)]),
-    );
+        db.write_schemas(db_batch).unwrap();
+
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1), (2, 2)]),
+        );
+        assert_eq!(
+            collect_values::<TestSchema2>(&db),
+            gen_expected_values(&[(4, 4), (5, 5)]),
+        );
+    }
 }
 
 #[test]
 fn test_two_schema_batches() {
-    let db = TestDB::new();
-
-    let db_batch1 = SchemaBatch::new();
-    db_batch1
-        .put::<TestSchema1>(&TestField(0), &TestField(0))
-        .unwrap();
-    db_batch1
-        .put::<TestSchema1>(&TestField(1), &TestField(1))
-        .unwrap();
-    db_batch1
-        .put::<TestSchema1>(&TestField(2), &TestField(2))
-        .unwrap();
-    db_batch1.delete::<TestSchema1>(&TestField(2)).unwrap();
-    db.write_schemas(db_batch1).unwrap();
+    for db in TestDB::all() {
+        let db_batch1 = SchemaBatch::new();
+        db_batch1
+            .put::<TestSchema1>(&TestField(0), &TestField(0))
+            .unwrap();
+        db_batch1
+            .put::<TestSchema1>(&TestField(1), &TestField(1))
+            .unwrap();
+        db_batch1
+            .put::<TestSchema1>(&TestField(2), &TestField(2))
+            .unwrap();
+        db_batch1.delete::<TestSchema1>(&TestField(2)).unwrap();
+        db.write_schemas(db_batch1).unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1)]),
-    );
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1)]),
+        );
 
-    let db_batch2 = SchemaBatch::new();
-    db_batch2.delete::<TestSchema2>(&TestField(3)).unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(3), &TestField(3))
-        .unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(4), &TestField(4))
-        .unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(5), &TestField(5))
-        .unwrap();
-    db.write_schemas(db_batch2).unwrap();
+        let db_batch2 = SchemaBatch::new();
+        db_batch2.delete::<TestSchema2>(&TestField(3)).unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(3), &TestField(3))
+            .unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(4), &TestField(4))
+            .unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(5), &TestField(5))
+            .unwrap();
+        db.write_schemas(db_batch2).unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1)]),
-    );
-    assert_eq!(
-        collect_values::<TestSchema2>(&db),
-        gen_expected_values(&[(3, 3), (4, 4), (5, 5)]),
-    );
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1)]),
+        );
+        assert_eq!(
+            collect_values::<TestSchema2>(&db),
+            gen_expected_values(&[(3, 3), (4, 4), (5, 5)]),
+        );
+    }
 }
 
 #[test]
@@ -325,37 +358,37 @@ fn test_open_as_secondary() {
 
 #[test]
 fn test_report_size() {
-    let db = TestDB::new();
-
-    for i in 0..1000 {
-        let db_batch = SchemaBatch::new();
-        db_batch
-            .put::<TestSchema1>(&TestField(i), &TestField(i))
-            .unwrap();
-        db_batch
-            .put::<TestSchema2>(&TestField(i), &TestField(i))
-            .unwrap();
-        db.write_schemas(db_batch).unwrap();
-    }
+    for db in TestDB::all() {
+        for i in 0..1000 {
+            let db_batch = SchemaBatch::new();
+            db_batch
+                .put::<TestSchema1>(&TestField(i), &TestField(i))
+                .unwrap();
+            db_batch
+                .put::<TestSchema2>(&TestField(i), &TestField(i))
+                .unwrap();
+            db.write_schemas(db_batch).unwrap();
+        }
 
-    db.flush_cf("TestCF1").unwrap();
-    db.flush_cf("TestCF2").unwrap();
+        db.flush_cf("TestCF1").unwrap();
+        db.flush_cf("TestCF2").unwrap();
 
-    assert!(
-        db.get_property("TestCF1", "rocksdb.estimate-live-data-size")
-            .unwrap()
-            > 0
-    );
-    assert!(
-        db.get_property("TestCF2", "rocksdb.estimate-live-data-size")
-            .unwrap()
-            > 0
-    );
-    assert_eq!(
-        db.get_property("default", "rocksdb.estimate-live-data-size")
-            .unwrap(),
-        0
-    );
+        assert!(
+            db.get_property("TestCF1", "rocksdb.estimate-live-data-size")
+                .unwrap()
+                > 0
+        );
+        assert!(
+            db.get_property("TestCF2", "rocksdb.estimate-live-data-size")
+                .unwrap()
+                > 0
+        );
+        assert_eq!(
+            db.get_property("default", "rocksdb.estimate-live-data-size")
+                .unwrap(),
+            0
+        );
+    }
 }
 
 #[test]
@@ -387,3 +420,24 @@ fn test_checkpoint() {
         assert_eq!(db.get::<TestSchema1>(&TestField(1)).unwrap(), None);
     }
 }
+
+#[test]
+fn test_checkpoint_in_memory() {
+    let checkpoint = aptos_temppath::TempPath::new();
+    let db = TestDB::new_in_memory();
+    db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+    db.put::<TestSchema2>(&TestField(1), &TestField(1)).unwrap();
+    db.create_checkpoint(&checkpoint).unwrap();
+    db.put::<TestSchema1>(&TestField(2), &TestField(2)).unwrap();
+
+    let cp = open_db(&checkpoint);
+    assert_eq!(
+        cp.get::<TestSchema1>(&TestField(0)).unwrap(),
+        Some(TestField(0)),
+    );
+    assert_eq!(
+        cp.get::<TestSchema2>(&TestField(1)).unwrap(),
+        Some(TestField(1)),
+    );
+    assert_eq!(cp.get::<TestSchema1>(&TestField(2)).unwrap(), None);
+}
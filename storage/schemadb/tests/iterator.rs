@@ -6,7 +6,7 @@ use aptos_schemadb::{
     define_schema,
     iterator::SchemaIterator,
     schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec},
-    DB,
+    ColumnFamilyName, DB,
 };
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use rocksdb::DEFAULT_COLUMN_FAMILY_NAME;
@@ -72,20 +72,35 @@ fn collect_values(iter: SchemaIterator<TestSchema>) -> Vec<u32> {
     iter.map(|row| (row.unwrap().1).0).collect()
 }
 
+fn get_column_families() -> Vec<ColumnFamilyName> {
+    vec![DEFAULT_COLUMN_FAMILY_NAME, TestSchema::COLUMN_FAMILY_NAME]
+}
+
 struct TestDB {
-    _tmpdir: aptos_temppath::TempPath,
+    _tmpdir: Option<aptos_temppath::TempPath>,
     db: DB,
 }
 
 impl TestDB {
     fn new() -> Self {
         let tmpdir = aptos_temppath::TempPath::new();
-        let column_families = vec![DEFAULT_COLUMN_FAMILY_NAME, TestSchema::COLUMN_FAMILY_NAME];
         let mut db_opts = rocksdb::Options::default();
         db_opts.create_if_missing(true);
         db_opts.create_missing_column_families(true);
-        let db = DB::open(tmpdir.path(), "test", column_families, &db_opts).unwrap();
+        let db = DB::open(tmpdir.path(), "test", get_column_families(), &db_opts).unwrap();
+        Self::populate(Some(tmpdir), db)
+    }
+
+    fn new_in_memory() -> Self {
+        Self::populate(None, DB::open_in_memory("test", get_column_families()))
+    }
 
+    /// Returns a DB for each backend, populated with the same data.
+    fn all() -> Vec<Self> {
+        vec![Self::new(), Self::new_in_memory()]
+    }
+
+    fn populate(tmpdir: Option<aptos_temppath::TempPath>, db: DB) -> Self {
         db.put::<TestSchema>(&TestKey(1, 0, 0), &TestValue(100))
             .unwrap();
         db.put::<TestSchema>(&TestKey(1, 0, 2), &TestValue(102))
@@ -134,134 +149,134 @@ impl std::ops::Deref for TestDB {
 
 #[test]
 fn test_seek_to_first() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek_to_first();
-    assert_eq!(collect_values(iter), [
-        100, 102, 104, 110, 112, 114, 200, 202
-    ]);
-
-    let mut iter = db.rev_iter();
-    iter.seek_to_first();
-    assert_eq!(collect_values(iter), [100]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_to_first();
+        assert_eq!(collect_values(iter), [
+            100, 102, 104, 110, 112, 114, 200, 202
+        ]);
+
+        let mut iter = db.rev_iter();
+        iter.seek_to_first();
+        assert_eq!(collect_values(iter), [100]);
+    }
 }
 
 #[test]
 fn test_seek_to_last() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek_to_last();
-    assert_eq!(collect_values(iter), [202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek_to_last();
-    assert_eq!(collect_values(iter), [
-        202, 200, 114, 112, 110, 104, 102, 100
-    ]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_to_last();
+        assert_eq!(collect_values(iter), [202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek_to_last();
+        assert_eq!(collect_values(iter), [
+            202, 200, 114, 112, 110, 104, 102, 100
+        ]);
+    }
 }
 
 #[test]
 fn test_seek_by_existing_key() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_by_nonexistent_key() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [112, 114, 200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [112, 110, 104, 102, 100]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [112, 114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [112, 110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_existing_key() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_nonexistent_key() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_by_1prefix() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [200, 114, 112, 110, 104, 102, 100]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [200, 114, 112, 110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_1prefix() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [114, 200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [114, 112, 110, 104, 102, 100]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [114, 112, 110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_by_2prefix() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [200, 114, 112, 110, 104, 102, 100]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [200, 114, 112, 110, 104, 102, 100]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_2prefix() {
-    let db = TestDB::new();
-
-    let mut iter = db.iter();
-    iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [114, 200, 202]);
-
-    let mut iter = db.rev_iter();
-    iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [114, 112, 110, 104, 102, 100]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [114, 200, 202]);
+
+        let mut iter = db.rev_iter();
+        iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [114, 112, 110, 104, 102, 100]);
+    }
 }
@@ -144,6 +144,33 @@ impl ProofFetcher for AsyncProofFetcher {
         Ok((value, None))
     }
 
+    fn fetch_state_values_and_proofs(
+        &self,
+        state_keys: &[StateKey],
+        version: Version,
+        root_hash: Option<HashValue>,
+    ) -> Result<Vec<(Option<StateValue>, Option<SparseMerkleProofExt>)>> {
+        let _timer = TIMER
+            .with_label_values(&["async_proof_fetcher_batch_fetch"])
+            .start_timer();
+        let values = self
+            .reader
+            .get_state_values_by_version(state_keys, version)?;
+        Ok(state_keys
+            .iter()
+            .zip(values)
+            .map(|(state_key, value)| {
+                self.schedule_proof_read(
+                    state_key.clone(),
+                    version,
+                    root_hash,
+                    value.as_ref().map(|v| v.hash()),
+                );
+                (value, None)
+            })
+            .collect())
+    }
+
     fn get_proof_cache(&self) -> HashMap<HashValue, SparseMerkleProofExt> {
         self.wait()
     }
@@ -177,4 +204,30 @@ mod tests {
         assert_eq!(proofs.len(), 10);
         assert_eq_unordered!(proofs.into_keys().collect::<Vec<_>>(), expected_key_hashes);
     }
+
+    #[test]
+    fn test_batch_fetch() {
+        let fetcher = AsyncProofFetcher::new(Arc::new(MockDbReaderWriter));
+        let state_keys = (0..10)
+            .map(|i| StateKey::Raw(format!("test_key_{}", i).into_bytes()))
+            .collect::<Vec<_>>();
+        let results = fetcher
+            .fetch_state_values_and_proofs(&state_keys, 0, None)
+            .expect("Should not fail.");
+        assert_eq!(results.len(), 10);
+        for (state_key, (value, proof)) in state_keys.iter().zip(results) {
+            let expected_value = StateValue::from(match state_key {
+                StateKey::Raw(key) => key.clone(),
+                _ => unreachable!(),
+            });
+            assert_eq!(value, Some(expected_value));
+            assert!(proof.is_none());
+        }
+
+        let proofs = fetcher.get_proof_cache();
+        assert_eq_unordered!(
+            proofs.into_keys().collect::<Vec<_>>(),
+            state_keys.iter().map(|key| key.hash()).collect::<Vec<_>>()
+        );
+    }
 }
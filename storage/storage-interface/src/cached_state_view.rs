@@ -17,6 +17,7 @@ use aptos_types::{
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
+use rayon::prelude::*;
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
@@ -30,6 +31,9 @@ static IO_POOL: Lazy<rayon::ThreadPool> = Lazy::new(|| {
         .unwrap()
 });
 
+/// Number of keys read from the DB in one batch when priming the cache.
+const PRIME_CACHE_BATCH_SIZE: usize = 64;
+
 /// `CachedStateView` is like a snapshot of the global state comprised of state view at two
 /// levels, persistent storage and memory.
 pub struct CachedStateView {
@@ -117,20 +121,68 @@ impl CachedStateView {
         &self,
         write_sets: T,
     ) -> Result<()> {
-        IO_POOL.scope(|s| {
-            write_sets
-                .into_iter()
-                .flat_map(|write_set| write_set.iter())
-                .map(|(key, _)| key)
-                .collect::<HashSet<_>>()
-                .into_iter()
-                .for_each(|key| {
-                    s.spawn(move |_| {
-                        self.get_state_value(key).expect("Must succeed.");
+        let state_keys = write_sets
+            .into_iter()
+            .flat_map(|write_set| write_set.iter())
+            .map(|(key, _)| key)
+            .collect::<HashSet<_>>();
+        self.prime_cache(state_keys)
+    }
+
+    /// Loads the state values of the given keys into the cache. Keys not found in the speculative
+    /// state are read from the DB in batches.
+    pub fn prime_cache<'a, T: IntoIterator<Item = &'a StateKey>>(
+        &self,
+        state_keys: T,
+    ) -> Result<()> {
+        let _timer = TIMER.with_label_values(&["prime_cache"]).start_timer();
+        let mut keys_to_read = Vec::new();
+        for state_key in state_keys {
+            if self.state_cache.contains_key(state_key) {
+                continue;
+            }
+            match self.speculative_state.get(state_key.hash()) {
+                StateStoreStatus::ExistsInScratchPad(value) => {
+                    self.state_cache
+                        .entry(state_key.clone())
+                        .or_insert(Some(value));
+                },
+                StateStoreStatus::DoesNotExist => {
+                    self.state_cache.entry(state_key.clone()).or_insert(None);
+                },
+                StateStoreStatus::ExistsInDB | StateStoreStatus::Unknown => {
+                    keys_to_read.push(state_key.clone())
+                },
+            }
+        }
+
+        match self.snapshot {
+            Some((version, root_hash)) => IO_POOL.install(|| {
+                keys_to_read
+                    .par_chunks(PRIME_CACHE_BATCH_SIZE)
+                    .try_for_each(|state_keys| -> Result<()> {
+                        let values_and_proofs = self.proof_fetcher.fetch_state_values_and_proofs(
+                            state_keys,
+                            version,
+                            Some(root_hash),
+                        )?;
+                        for (state_key, (value, proof)) in state_keys.iter().zip(values_and_proofs)
+                        {
+                            if let Some(proof) = proof {
+                                Self::verify_proof(state_key, root_hash, value.as_ref(), &proof)?;
+                            }
+                            self.state_cache.entry(state_key.clone()).or_insert(value);
+                        }
+                        Ok(())
                     })
-                });
-        });
-        Ok(())
+            }),
+            None => {
+                for state_key in keys_to_read {
+                    self.state_cache.entry(state_key).or_insert(None);
+                }
+                Ok(())
+            },
+        }
     }
 
     pub fn into_state_cache(self) -> StateCache {
@@ -159,16 +211,7 @@ impl CachedStateView {
                         )?;
                         // TODO: proof verification can be opted out, for performance
                         if let Some(proof) = proof {
-                            proof
-                                .verify(root_hash, key_hash, value.as_ref())
-                                .map_err(|err| {
-                                    format_err!(
-                                    "Proof is invalid for key {:?} with state root hash {:?}: {}",
-                                    state_key,
-                                    root_hash,
-                                    err
-                                )
-                                })?;
+                            Self::verify_proof(state_key, root_hash, value.as_ref(), &proof)?;
                         }
                         value
                     },
@@ -179,6 +222,24 @@ impl CachedStateView {
 
         Ok(state_value_option)
     }
+
+    fn verify_proof(
+        state_key: &StateKey,
+        root_hash: HashValue,
+        value: Option<&StateValue>,
+        proof: &SparseMerkleProofExt,
+    ) -> Result<()> {
+        proof
+            .verify(root_hash, state_key.hash(), value)
+            .map_err(|err| {
+                format_err!(
+                    "Proof is invalid for key {:?} with state root hash {:?}: {}",
+                    state_key,
+                    root_hash,
+                    err
+                )
+            })
+    }
 }
 
 pub struct StateCache {
@@ -224,6 +285,35 @@ pub struct CachedDbStateView {
     state_cache: RwLock<HashMap<StateKey, Option<Vec<u8>>>>,
 }
 
+impl CachedDbStateView {
+    /// Reads the state values of the given keys from the DB in one batch, and caches them.
+    pub fn prime_cache(&self, state_keys: &[StateKey]) -> Result<()> {
+        let state_keys = {
+            let cache = self.state_cache.read();
+            state_keys
+                .iter()
+                .filter(|state_key| !cache.contains_key(state_key))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let values = match self.db_state_view.version {
+            Some(version) => self
+                .db_state_view
+                .db
+                .get_state_values_by_version(&state_keys, version)?,
+            None => vec![None; state_keys.len()],
+        };
+
+        let mut cache = self.state_cache.write();
+        for (state_key, value) in state_keys.into_iter().zip(values) {
+            cache
+                .entry(state_key)
+                .or_insert_with(|| value.map(StateValue::into_bytes));
+        }
+        Ok(())
+    }
+}
+
 impl From<DbStateView> for CachedDbStateView {
     fn from(db_state_view: DbStateView) -> Self {
         Self {
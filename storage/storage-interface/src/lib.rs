@@ -69,6 +69,15 @@ pub enum Error {
 
     #[error("Serialization error: {0}")]
     SerializationError(String),
+
+    #[error(
+        "{data_type} at version {version} is pruned, min available version is {min_readable_version}."
+    )]
+    Pruned {
+        data_type: String,
+        version: Version,
+        min_readable_version: Version,
+    },
 }
 
 impl From<anyhow::Error> for Error {
@@ -93,6 +102,20 @@ impl From<aptos_secure_net::Error> for Error {
     }
 }
 
+/// A transaction pruned from the ledger DB, served from the ledger archive instead.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ArchivedTransaction {
+    pub version: Version,
+    pub transaction: Transaction,
+    pub info: TransactionInfo,
+    pub events: Vec<ContractEvent>,
+    pub write_set: WriteSet,
+    /// The transaction accumulator root hash at this version.
+    pub accumulator_root_hash: HashValue,
+    /// The timestamp of the block the transaction belongs to.
+    pub block_timestamp_usecs: u64,
+}
+
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub enum Order {
     Ascending,
@@ -427,6 +450,32 @@ pub trait DbReader: Send + Sync {
             .map(|(value, proof_ext)| (value, proof_ext.into()))
     }
 
+    /// Same as `get_state_value_by_version`, but reads multiple state keys in one batch. Results
+    /// are in the same order as `state_keys`.
+    fn get_state_values_by_version(
+        &self,
+        state_keys: &[StateKey],
+        version: Version,
+    ) -> Result<Vec<Option<StateValue>>> {
+        state_keys
+            .iter()
+            .map(|state_key| self.get_state_value_by_version(state_key, version))
+            .collect()
+    }
+
+    /// Same as `get_state_value_with_proof_by_version_ext`, but reads multiple state keys in one
+    /// batch. Results are in the same order as `state_keys`.
+    fn get_state_values_with_proof_by_version_ext(
+        &self,
+        state_keys: &[StateKey],
+        version: Version,
+    ) -> Result<Vec<(Option<StateValue>, SparseMerkleProofExt)>> {
+        state_keys
+            .iter()
+            .map(|state_key| self.get_state_value_with_proof_by_version_ext(state_key, version))
+            .collect()
+    }
+
     /// Gets the latest ExecutedTrees no matter if db has been bootstrapped.
     /// Used by the Db-bootstrapper.
     fn get_latest_executed_trees(&self) -> Result<ExecutedTrees> {
@@ -451,6 +500,32 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Returns a transaction that has been pruned from the DB, if the ledger pruner archived it
+    /// before pruning. Returns `None` if there's no archive or the archive doesn't have it.
+    fn get_archived_transaction(&self, version: Version) -> Result<Option<ArchivedTransaction>> {
+        Ok(None)
+    }
+
+    /// Same as `get_archived_transaction()`, but finds the transaction by its hash.
+    fn get_archived_transaction_by_hash(
+        &self,
+        hash: HashValue,
+    ) -> Result<Option<ArchivedTransaction>> {
+        Ok(None)
+    }
+
+    /// Returns the events on `event_key` with sequence numbers from `start_seq_num` that have
+    /// been pruned from the DB, as far as the ledger pruner archived them, at most `limit`.
+    /// Returns an empty list if there's no archive or the archive doesn't have the first one.
+    fn get_archived_events(
+        &self,
+        event_key: &EventKey,
+        start_seq_num: u64,
+        limit: u64,
+    ) -> Result<Vec<EventWithVersion>> {
+        Ok(Vec::new())
+    }
+
     /// Gets an [`AccumulatorConsistencyProof`] starting from `client_known_version`
     /// (or pre-genesis if `None`) until `ledger_version`.
     ///
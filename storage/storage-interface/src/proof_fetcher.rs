@@ -20,6 +20,20 @@ pub trait ProofFetcher: Sync + Send {
         root_hash: Option<HashValue>,
     ) -> anyhow::Result<(Option<StateValue>, Option<SparseMerkleProofExt>)>;
 
+    /// Same as `fetch_state_value_and_proof`, but for multiple state keys, which implementations
+    /// can read from the DB in one batch. Results are in the same order as `state_keys`.
+    fn fetch_state_values_and_proofs(
+        &self,
+        state_keys: &[StateKey],
+        version: Version,
+        root_hash: Option<HashValue>,
+    ) -> anyhow::Result<Vec<(Option<StateValue>, Option<SparseMerkleProofExt>)>> {
+        state_keys
+            .iter()
+            .map(|state_key| self.fetch_state_value_and_proof(state_key, version, root_hash))
+            .collect()
+    }
+
     /// API to return all the proofs fetched by the proof fetcher so far.
     fn get_proof_cache(&self) -> HashMap<HashValue, SparseMerkleProofExt>;
 }
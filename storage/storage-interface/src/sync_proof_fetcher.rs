@@ -26,21 +26,17 @@ impl SyncProofFetcher {
             state_proof_cache: RwLock::new(HashMap::new()),
         }
     }
-}
 
-impl ProofFetcher for SyncProofFetcher {
-    fn fetch_state_value_and_proof(
+    fn verify_and_cache(
         &self,
         state_key: &StateKey,
-        version: Version,
+        state_value: Option<&StateValue>,
+        proof: &SparseMerkleProofExt,
         root_hash: Option<HashValue>,
-    ) -> anyhow::Result<(Option<StateValue>, Option<SparseMerkleProofExt>)> {
-        let (state_value, proof) = self
-            .reader
-            .get_state_value_with_proof_by_version_ext(state_key, version)?;
+    ) -> anyhow::Result<()> {
         if let Some(root_hash) = root_hash {
             proof
-                .verify(root_hash, state_key.hash(), state_value.as_ref())
+                .verify(root_hash, state_key.hash(), state_value)
                 .map_err(|err| {
                     format_err!(
                         "Proof is invalid for key {:?} with state root hash {:?}: {}.",
@@ -55,10 +51,44 @@ impl ProofFetcher for SyncProofFetcher {
         self.state_proof_cache
             .write()
             .insert(state_key.hash(), proof.clone());
+        Ok(())
+    }
+}
+
+impl ProofFetcher for SyncProofFetcher {
+    fn fetch_state_value_and_proof(
+        &self,
+        state_key: &StateKey,
+        version: Version,
+        root_hash: Option<HashValue>,
+    ) -> anyhow::Result<(Option<StateValue>, Option<SparseMerkleProofExt>)> {
+        let (state_value, proof) = self
+            .reader
+            .get_state_value_with_proof_by_version_ext(state_key, version)?;
+        self.verify_and_cache(state_key, state_value.as_ref(), &proof, root_hash)?;
 
         Ok((state_value, Some(proof)))
     }
 
+    fn fetch_state_values_and_proofs(
+        &self,
+        state_keys: &[StateKey],
+        version: Version,
+        root_hash: Option<HashValue>,
+    ) -> anyhow::Result<Vec<(Option<StateValue>, Option<SparseMerkleProofExt>)>> {
+        let values_and_proofs = self
+            .reader
+            .get_state_values_with_proof_by_version_ext(state_keys, version)?;
+        state_keys
+            .iter()
+            .zip(values_and_proofs)
+            .map(|(state_key, (state_value, proof))| {
+                self.verify_and_cache(state_key, state_value.as_ref(), &proof, root_hash)?;
+                Ok((state_value, Some(proof)))
+            })
+            .collect()
+    }
+
     fn get_proof_cache(&self) -> HashMap<HashValue, SparseMerkleProofExt> {
         self.state_proof_cache
             .read()
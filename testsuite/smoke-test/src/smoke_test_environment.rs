@@ -201,6 +201,8 @@ pub fn launch_faucet(
         chain_id,
         maximum_amount: None,
         do_not_delegate: true,
+        num_minters: 1,
+        abuse_protection_config: None,
     };
     tokio::spawn(faucet.run())
 }
@@ -673,6 +673,14 @@ impl TransactionWithProof {
             sequence_number,
         );
 
+        self.verify(ledger_info)
+    }
+
+    /// Verifies that the transaction (and its events, if present) exists in the ledger
+    /// represented by `ledger_info` at `self.version`.
+    ///
+    /// Unlike `verify_user_txn`, this works for all kinds of transactions.
+    pub fn verify(&self, ledger_info: &LedgerInfo) -> Result<()> {
         let txn_hash = self.transaction.hash();
         ensure!(
             txn_hash == self.proof.transaction_info().transaction_hash(),
@@ -694,7 +702,7 @@ impl TransactionWithProof {
             );
         }
 
-        self.proof.verify(ledger_info, version)
+        self.proof.verify(ledger_info, self.version)
     }
 }
 